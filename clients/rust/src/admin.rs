@@ -0,0 +1,123 @@
+// The /admin API: teenytiny's runtime configuration, which isn't part of
+// OpenAI's API. Most of it needs the server's own key; the request log
+// answers any key with that key's requests, and a key of a tenant can look at
+// its tenant's keys.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::error::Result;
+use crate::Client;
+
+pub struct Admin<'a> {
+    pub(crate) client: &'a Client,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ApiKey {
+    pub key: String,
+    /// The models the key may use, or None for every model
+    pub models: Option<Vec<String>>,
+    /// The tenant the key belongs to, if any
+    #[serde(default)]
+    pub tenant: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FaultSettings {
+    /// The chance, from 0 to 1, that a request to the flaky model fails
+    pub failure_rate: f64,
+    /// Which faults a failure picks from: "500", "502", "503", "reset", "malformed", "error_event"
+    pub kinds: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct CapturedRequest {
+    pub id: String,
+    pub timestamp: String,
+    pub api_key: String,
+    pub method: String,
+    pub path: String,
+    pub model: Option<String>,
+    pub status: u16,
+    pub duration_ms: u64,
+    pub stream: bool,
+    pub cancelled: bool,
+    pub request_body: Value,
+    pub response_body: Value,
+}
+
+#[derive(Deserialize)]
+struct List<T> {
+    data: Vec<T>,
+}
+
+#[derive(Deserialize)]
+struct RateLimit {
+    requests_per_minute: u32,
+}
+
+impl Admin<'_> {
+    /// The registered models, aliases, and variants
+    pub async fn models(&self) -> Result<Value> {
+        self.client.get("/admin/models").await
+    }
+
+    /// Mints a key, limited to the given models if any
+    pub async fn create_key(&self, models: Option<&[&str]>) -> Result<ApiKey> {
+        let body = match models {
+            Some(models) => json!({ "models": models }),
+            None => json!({}),
+        };
+        self.client.send_json(reqwest::Method::POST, "/admin/keys", &body).await
+    }
+
+    /// Mints a key in a tenant, which takes the server's key
+    pub async fn create_tenant_key(&self, tenant: &str, models: Option<&[&str]>) -> Result<ApiKey> {
+        let mut body = json!({ "tenant": tenant });
+        if let Some(models) = models {
+            body["models"] = json!(models);
+        }
+        self.client.send_json(reqwest::Method::POST, "/admin/keys", &body).await
+    }
+
+    pub async fn revoke_key(&self, key: &str) -> Result<()> {
+        let _: Value = self.client.send(reqwest::Method::DELETE, &format!("/admin/keys/{}", key)).await?;
+        Ok(())
+    }
+
+    pub async fn rate_limit(&self) -> Result<u32> {
+        let limit: RateLimit = self.client.get("/admin/rate-limit").await?;
+        Ok(limit.requests_per_minute)
+    }
+
+    pub async fn set_rate_limit(&self, requests_per_minute: u32) -> Result<u32> {
+        let body = json!({ "requests_per_minute": requests_per_minute });
+        let limit: RateLimit = self.client.send_json(reqwest::Method::PUT, "/admin/rate-limit", &body).await?;
+        Ok(limit.requests_per_minute)
+    }
+
+    pub async fn faults(&self) -> Result<FaultSettings> {
+        self.client.get("/admin/faults").await
+    }
+
+    pub async fn set_faults(&self, settings: &FaultSettings) -> Result<FaultSettings> {
+        self.client.send_json(reqwest::Method::PUT, "/admin/faults", settings).await
+    }
+
+    /// Resets one key's rate limit windows, or every counter when no key is given
+    pub async fn reset_usage(&self, key: Option<&str>) -> Result<()> {
+        let body = match key {
+            Some(key) => json!({ "key": key }),
+            None => json!({}),
+        };
+        let _: Value = self.client.send_json(reqwest::Method::POST, "/admin/usage/reset", &body).await?;
+        Ok(())
+    }
+
+    /// The most recent requests, newest first
+    pub async fn requests(&self, limit: u32) -> Result<Vec<CapturedRequest>> {
+        let list: List<CapturedRequest> = self.client.get(&format!("/admin/requests?limit={}", limit)).await?;
+        Ok(list.data)
+    }
+}
@@ -0,0 +1,101 @@
+// Directives shape a reply from the request side: "!delay:500" in a user
+// message waits half a second before answering, "!error:503" fails the
+// request, and so on. The server strips them before the model sees the text.
+
+use std::fmt;
+use std::time::Duration;
+
+use crate::chat::FinishReason;
+
+/// A fault the server can inject into a response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// Suppress the flaky model's random faults for this request
+    None,
+    Status500,
+    Status502,
+    Status503,
+    /// Drop the connection partway through a stream
+    Reset,
+    /// Send a chunk that isn't valid JSON
+    Malformed,
+    /// End a stream partway through with an error event
+    ErrorEvent,
+}
+
+impl Fault {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Fault::None => "none",
+            Fault::Status500 => "500",
+            Fault::Status502 => "502",
+            Fault::Status503 => "503",
+            Fault::Reset => "reset",
+            Fault::Malformed => "malformed",
+            Fault::ErrorEvent => "error_event",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Directive {
+    /// Wait before the first content chunk
+    Delay(Duration),
+    /// Deliver the content in exactly this many chunks
+    Chunks(u32),
+    /// End with this finish reason; tool_calls isn't one the server accepts
+    Finish(FinishReason),
+    /// Fail with this HTTP status instead of answering
+    Error(u16),
+    /// Report this many completion tokens in usage
+    Tokens(u32),
+    Fault(Fault),
+}
+
+impl fmt::Display for Directive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Directive::Delay(delay) => write!(f, "!delay:{}", delay.as_millis()),
+            Directive::Chunks(chunks) => write!(f, "!chunks:{}", chunks),
+            Directive::Finish(reason) => {
+                let reason = match reason {
+                    FinishReason::Stop => "stop",
+                    FinishReason::Length => "length",
+                    FinishReason::ContentFilter => "content_filter",
+                    FinishReason::ToolCalls => "tool_calls",
+                };
+                write!(f, "!finish:{}", reason)
+            }
+            Directive::Error(status) => write!(f, "!error:{}", status),
+            Directive::Tokens(tokens) => write!(f, "!tokens:{}", tokens),
+            Directive::Fault(fault) => write!(f, "!fault:{}", fault.as_str()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::{ChatCompletionRequest, Message};
+
+    #[test]
+    fn directives_are_written_as_the_server_reads_them() {
+        assert_eq!(Directive::Delay(Duration::from_millis(1500)).to_string(), "!delay:1500");
+        assert_eq!(Directive::Finish(FinishReason::ContentFilter).to_string(), "!finish:content_filter");
+        assert_eq!(Directive::Fault(Fault::Status503).to_string(), "!fault:503");
+    }
+
+    #[test]
+    fn directives_join_the_last_user_message() {
+        let request = ChatCompletionRequest::builder("echo")
+            .message(Message::user("First"))
+            .message(Message::assistant("Reply"))
+            .message(Message::user("Second"))
+            .directive(Directive::Chunks(3))
+            .directive(Directive::Tokens(7))
+            .build();
+
+        assert_eq!(request.messages[0].content.as_deref(), Some("First"));
+        assert_eq!(request.messages[2].content.as_deref(), Some("Second !chunks:3 !tokens:7"));
+    }
+}
@@ -0,0 +1,118 @@
+// Errors, as the server reports them in its OpenAI-style envelope:
+// {"error": {"message", "type", "param", "code"}}
+
+use serde::Deserialize;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The server answered with an error status and envelope
+    #[error("{status}: {error}")]
+    Api { status: u16, error: ApiError },
+    /// A stream was cut short by an error event
+    #[error("stream failed: {0}")]
+    Stream(ApiError),
+    /// The request couldn't be sent, or the response couldn't be read
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    /// The response wasn't the JSON expected
+    #[error("unexpected response: {0}")]
+    Decode(#[from] serde_json::Error),
+}
+
+impl Error {
+    /// The server's error, whether sent with a status or inside a stream
+    pub fn api_error(&self) -> Option<&ApiError> {
+        match self {
+            Error::Api { error, .. } | Error::Stream(error) => Some(error),
+            _ => None,
+        }
+    }
+
+    /// The HTTP status of an error response
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Error::Api { status, .. } => Some(*status),
+            Error::Http(error) => error.status().map(|status| status.as_u16()),
+            _ => None,
+        }
+    }
+
+    /// The error's code, such as "model_not_found" or "rate_limit_exceeded"
+    pub fn code(&self) -> Option<&str> {
+        self.api_error().and_then(|error| error.code.as_deref())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ApiError {
+    pub message: String,
+    #[serde(rename = "type")]
+    pub kind: ErrorKind,
+    pub param: Option<String>,
+    pub code: Option<String>,
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.message, self.kind.as_str())
+    }
+}
+
+/// The error types the server sends, as OpenAI names them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    InvalidRequestError,
+    AuthenticationError,
+    PermissionError,
+    NotFoundError,
+    RateLimitError,
+    InsufficientQuota,
+    ApiError,
+    OverloadedError,
+    /// A type this client doesn't know yet
+    #[serde(other)]
+    Other,
+}
+
+impl ErrorKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorKind::InvalidRequestError => "invalid_request_error",
+            ErrorKind::AuthenticationError => "authentication_error",
+            ErrorKind::PermissionError => "permission_error",
+            ErrorKind::NotFoundError => "not_found_error",
+            ErrorKind::RateLimitError => "rate_limit_error",
+            ErrorKind::InsufficientQuota => "insufficient_quota",
+            ErrorKind::ApiError => "api_error",
+            ErrorKind::OverloadedError => "overloaded_error",
+            ErrorKind::Other => "other",
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub(crate) struct Envelope {
+    pub(crate) error: ApiError,
+}
+
+// Turns an error response into Error::Api, keeping the status when the body
+// isn't an envelope (say, from a proxy in between)
+pub(crate) async fn check(response: reqwest::Response) -> Result<reqwest::Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await?;
+    let error = serde_json::from_str::<Envelope>(&body)
+        .map(|envelope| envelope.error)
+        .unwrap_or_else(|_| ApiError {
+            message: body,
+            kind: ErrorKind::Other,
+            param: None,
+            code: None,
+        });
+    Err(Error::Api { status: status.as_u16(), error })
+}
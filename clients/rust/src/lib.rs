@@ -0,0 +1,135 @@
+//! A client for TeenyTiny AI. It speaks the OpenAI chat completions API, and
+//! also knows teenytiny's directives and admin API.
+//!
+//! ```no_run
+//! use teenytiny_client::{ChatCompletionRequest, Client, Directive, Message};
+//!
+//! # async fn run() -> teenytiny_client::Result<()> {
+//! let client = Client::builder().base_url("http://localhost:8080").api_key("testkey").build();
+//! let request = ChatCompletionRequest::builder("echo")
+//!     .message(Message::user("Hello"))
+//!     .directive(Directive::Chunks(2))
+//!     .build();
+//! let completion = client.chat_completion(&request).await?;
+//! println!("{}", completion.content().unwrap_or_default());
+//! # Ok(())
+//! # }
+//! ```
+
+mod admin;
+mod chat;
+mod directives;
+mod error;
+mod stream;
+mod webhooks;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+pub use admin::{Admin, ApiKey, CapturedRequest, FaultSettings};
+pub use chat::{
+    ChatCompletion, ChatCompletionChunk, ChatCompletionRequest, ChatCompletionRequestBuilder, Choice, ChunkChoice,
+    Delta, FinishReason, FunctionCall, FunctionCallDelta, FunctionDefinition, Message, Model, Role, Tool, ToolCall,
+    ToolCallDelta, Usage,
+};
+pub use directives::{Directive, Fault};
+pub use error::{ApiError, Error, ErrorKind, Result};
+pub use stream::{ChatStream, Event, EventParser};
+pub use webhooks::{WebhookError, WebhookVerifier, DEFAULT_WEBHOOK_TOLERANCE, WEBHOOK_SIGNATURE_HEADER};
+
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: String,
+    api_key: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ClientBuilder {
+    http: Option<reqwest::Client>,
+    base_url: String,
+    api_key: String,
+}
+
+impl ClientBuilder {
+    /// The server's address, without the /v1 suffix
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = api_key.into();
+        self
+    }
+
+    /// Sends requests with this client, say to trust a self-signed certificate
+    pub fn http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = Some(http);
+        self
+    }
+
+    pub fn build(self) -> Client {
+        Client { http: self.http.unwrap_or_default(), base_url: self.base_url, api_key: self.api_key }
+    }
+}
+
+#[derive(Deserialize)]
+struct ModelList {
+    data: Vec<Model>,
+}
+
+impl Client {
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder { http: None, base_url: "http://localhost:8080".to_string(), api_key: String::new() }
+    }
+
+    pub async fn chat_completion(&self, request: &ChatCompletionRequest) -> Result<ChatCompletion> {
+        let request = ChatCompletionRequest { stream: None, ..request.clone() };
+        self.send_json(reqwest::Method::POST, "/v1/chat/completions", &request).await
+    }
+
+    /// Streams a completion as chunks. The stream ends after the last chunk,
+    /// or with Error::Stream if the server fails partway through.
+    pub async fn chat_completion_stream(&self, request: &ChatCompletionRequest) -> Result<ChatStream> {
+        let request = ChatCompletionRequest { stream: Some(true), ..request.clone() };
+        let response = self.request(reqwest::Method::POST, "/v1/chat/completions").json(&request).send().await?;
+        Ok(stream::chat_stream(error::check(response).await?))
+    }
+
+    /// The models this key may use
+    pub async fn models(&self) -> Result<Vec<Model>> {
+        let list: ModelList = self.get("/v1/models").await?;
+        Ok(list.data)
+    }
+
+    pub fn admin(&self) -> Admin<'_> {
+        Admin { client: self }
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        self.http.request(method, format!("{}{}", self.base_url, path)).bearer_auth(&self.api_key)
+    }
+
+    pub(crate) async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        self.send(reqwest::Method::GET, path).await
+    }
+
+    pub(crate) async fn send<T: DeserializeOwned>(&self, method: reqwest::Method, path: &str) -> Result<T> {
+        decode(self.request(method, path).send().await?).await
+    }
+
+    pub(crate) async fn send_json<T: DeserializeOwned>(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: &impl Serialize,
+    ) -> Result<T> {
+        decode(self.request(method, path).json(body).send().await?).await
+    }
+}
+
+async fn decode<T: DeserializeOwned>(response: reqwest::Response) -> Result<T> {
+    let body = error::check(response).await?.bytes().await?;
+    Ok(serde_json::from_slice(&body)?)
+}
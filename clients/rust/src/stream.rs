@@ -0,0 +1,133 @@
+// Server-sent events, read from the response body as it arrives. Each
+// "data:" event of a chat completion stream is a chunk, until "[DONE]"; an
+// event carrying an error envelope ends the stream with Error::Stream.
+
+use std::pin::Pin;
+
+use futures::stream::{self, Stream};
+
+use crate::chat::ChatCompletionChunk;
+use crate::error::{Envelope, Error, Result};
+
+pub type ChatStream = Pin<Box<dyn Stream<Item = Result<ChatCompletionChunk>> + Send>>;
+
+/// One event, with its name when the server gave one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub event: Option<String>,
+    pub data: String,
+}
+
+/// Splits bytes into events. Bytes are kept until a blank line ends the
+/// event, so text split across reads, even mid-character, comes out whole.
+#[derive(Debug, Default)]
+pub struct EventParser {
+    buffer: Vec<u8>,
+}
+
+impl EventParser {
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<Event> {
+        self.buffer.extend(bytes.iter().filter(|&&byte| byte != b'\r'));
+
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.windows(2).position(|pair| pair == b"\n\n") {
+            let block: Vec<u8> = self.buffer.drain(..end + 2).collect();
+            if let Some(event) = parse_event(&String::from_utf8_lossy(&block)) {
+                events.push(event);
+            }
+        }
+        events
+    }
+}
+
+fn parse_event(block: &str) -> Option<Event> {
+    let mut event = None;
+    let mut data: Vec<&str> = Vec::new();
+    for line in block.lines() {
+        if let Some(value) = line.strip_prefix("data:") {
+            data.push(value.strip_prefix(' ').unwrap_or(value));
+        } else if let Some(value) = line.strip_prefix("event:") {
+            event = Some(value.trim().to_string());
+        }
+    }
+    (!data.is_empty()).then(|| Event { event, data: data.join("\n") })
+}
+
+struct State {
+    response: reqwest::Response,
+    parser: EventParser,
+    pending: std::collections::VecDeque<Event>,
+    done: bool,
+}
+
+/// Reads a chat completion stream from a successful response
+pub(crate) fn chat_stream(response: reqwest::Response) -> ChatStream {
+    let state = State { response, parser: EventParser::default(), pending: Default::default(), done: false };
+
+    Box::pin(stream::unfold(state, |mut state| async move {
+        loop {
+            if state.done {
+                return None;
+            }
+            if let Some(event) = state.pending.pop_front() {
+                if event.data == "[DONE]" {
+                    return None;
+                }
+                let item = match serde_json::from_str::<Envelope>(&event.data) {
+                    Ok(envelope) => {
+                        state.done = true;
+                        Err(Error::Stream(envelope.error))
+                    }
+                    Err(_) => serde_json::from_str(&event.data).map_err(Error::from),
+                };
+                return Some((item, state));
+            }
+            match state.response.chunk().await {
+                Ok(Some(bytes)) => {
+                    let events = state.parser.push(&bytes);
+                    state.pending.extend(events);
+                }
+                Ok(None) => return None,
+                Err(error) => {
+                    state.done = true;
+                    return Some((Err(Error::Http(error)), state));
+                }
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_split_across_reads_come_out_whole() {
+        let mut parser = EventParser::default();
+
+        assert_eq!(parser.push(b"data: {\"a\":"), vec![]);
+        assert_eq!(
+            parser.push(b" 1}\n\nevent: done\r\ndata: [DONE]\r\n\r\n"),
+            vec![
+                Event { event: None, data: "{\"a\": 1}".to_string() },
+                Event { event: Some("done".to_string()), data: "[DONE]".to_string() },
+            ]
+        );
+    }
+
+    #[test]
+    fn characters_split_across_reads_are_kept() {
+        let mut parser = EventParser::default();
+        let text = "data: héllo\n\n".as_bytes();
+
+        assert_eq!(parser.push(&text[..8]), vec![]);
+        assert_eq!(parser.push(&text[8..])[0].data, "héllo");
+    }
+
+    #[test]
+    fn comments_and_blank_events_are_skipped() {
+        let mut parser = EventParser::default();
+
+        assert_eq!(parser.push(b": keep-alive\n\n\n\ndata: x\n\n").len(), 1);
+    }
+}
@@ -0,0 +1,188 @@
+// Webhook signatures: the server signs each delivery's timestamp and body with
+// the webhook's secret, sending
+//
+//   x-teenytiny-signature: t=1700000000,v1=<hex HMAC-SHA256 of "1700000000.<body>">
+//
+// A receiver checks the signature to know the event came from the server, and
+// the timestamp to refuse a captured delivery sent again later.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// The header a delivery's signature comes in
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "x-teenytiny-signature";
+
+/// How far a delivery's timestamp may be from the receiver's clock by default
+pub const DEFAULT_WEBHOOK_TOLERANCE: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum WebhookError {
+    /// The header isn't t=<timestamp>,v1=<hex signature>
+    #[error("malformed signature header")]
+    Malformed,
+    /// No signature matches the body under the secret, so it was changed or
+    /// signed with another secret
+    #[error("signature doesn't match")]
+    Mismatch,
+    /// The timestamp is further from now than the tolerance, as for a replay
+    #[error("timestamp is {seconds}s away, more than the tolerance allows")]
+    Stale { seconds: u64 },
+}
+
+/// Checks deliveries signed with a webhook's secret
+///
+/// ```
+/// use teenytiny_client::WebhookVerifier;
+///
+/// let verifier = WebhookVerifier::new("whsec");
+/// let header = verifier.sign(1_700_000_000, br#"{"id":"evt_1"}"#);
+/// assert!(verifier.verify(&header, br#"{"id":"evt_1"}"#).is_err()); // long since stale
+/// ```
+#[derive(Debug, Clone)]
+pub struct WebhookVerifier {
+    secret: Vec<u8>,
+    tolerance: Duration,
+}
+
+impl WebhookVerifier {
+    pub fn new(secret: impl AsRef<[u8]>) -> Self {
+        WebhookVerifier { secret: secret.as_ref().to_vec(), tolerance: DEFAULT_WEBHOOK_TOLERANCE }
+    }
+
+    /// Accepts timestamps this far either side of the receiver's clock
+    pub fn tolerance(mut self, tolerance: Duration) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Checks a delivery's signature header against its raw body, as of now
+    pub fn verify(&self, header: &str, body: &[u8]) -> Result<(), WebhookError> {
+        self.verify_at(header, body, SystemTime::now())
+    }
+
+    /// Checks a delivery as of the given time. The signature is checked
+    /// first, so a tampered timestamp is a mismatch rather than stale.
+    pub fn verify_at(&self, header: &str, body: &[u8], now: SystemTime) -> Result<(), WebhookError> {
+        let mut timestamp = None;
+        let mut signatures = Vec::new();
+        for part in header.split(',') {
+            match part.trim().split_once('=') {
+                Some(("t", value)) => timestamp = Some(value.parse::<u64>().map_err(|_| WebhookError::Malformed)?),
+                Some(("v1", value)) => signatures.push(decode_hex(value).ok_or(WebhookError::Malformed)?),
+                // Schemes this client doesn't know
+                Some(_) => {}
+                None => return Err(WebhookError::Malformed),
+            }
+        }
+        let timestamp = timestamp.ok_or(WebhookError::Malformed)?;
+        if signatures.is_empty() {
+            return Err(WebhookError::Malformed);
+        }
+
+        // Any of several signatures may match, as while a secret is rotated
+        if !signatures.iter().any(|signature| self.mac(timestamp, body).verify_slice(signature).is_ok()) {
+            return Err(WebhookError::Mismatch);
+        }
+
+        let now = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let seconds = now.abs_diff(timestamp);
+        if seconds > self.tolerance.as_secs() {
+            return Err(WebhookError::Stale { seconds });
+        }
+        Ok(())
+    }
+
+    /// The header the server would send for a body at a timestamp, for
+    /// testing a receiver
+    pub fn sign(&self, timestamp: u64, body: &[u8]) -> String {
+        let digest: String =
+            self.mac(timestamp, body).finalize().into_bytes().iter().map(|byte| format!("{:02x}", byte)).collect();
+        format!("t={},v1={}", timestamp, digest)
+    }
+
+    fn mac(&self, timestamp: u64, body: &[u8]) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.secret).expect("HMAC takes keys of any length");
+        mac.update(format!("{}.", timestamp).as_bytes());
+        mac.update(body);
+        mac
+    }
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len()).step_by(2).map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BODY: &[u8] = br#"{"id":"evt_1"}"#;
+    const SENT: u64 = 1_700_000_000;
+
+    fn at(seconds: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn signatures_match_the_servers() {
+        // As the service's own tests sign the same body
+        assert_eq!(
+            WebhookVerifier::new("whsec").sign(SENT, BODY),
+            "t=1700000000,v1=e7e846cdb96220c3674ade89e534304fc91f6f15064facee1e7a7096f5f57f62"
+        );
+    }
+
+    #[test]
+    fn fresh_deliveries_are_accepted() {
+        let verifier = WebhookVerifier::new("whsec");
+        let header = verifier.sign(SENT, BODY);
+        assert_eq!(verifier.verify_at(&header, BODY, at(SENT)), Ok(()));
+        assert_eq!(verifier.verify_at(&header, BODY, at(SENT + 300)), Ok(()));
+        // A receiver's clock may be a little behind
+        assert_eq!(verifier.verify_at(&header, BODY, at(SENT - 30)), Ok(()));
+    }
+
+    #[test]
+    fn tampered_deliveries_are_rejected() {
+        let verifier = WebhookVerifier::new("whsec");
+        let header = verifier.sign(SENT, BODY);
+
+        assert_eq!(verifier.verify_at(&header, br#"{"id":"evt_2"}"#, at(SENT)), Err(WebhookError::Mismatch));
+        let retimed = header.replace("t=1700000000", "t=1700000100");
+        assert_eq!(verifier.verify_at(&retimed, BODY, at(SENT + 100)), Err(WebhookError::Mismatch));
+        let other = WebhookVerifier::new("another secret");
+        assert_eq!(other.verify_at(&header, BODY, at(SENT)), Err(WebhookError::Mismatch));
+    }
+
+    #[test]
+    fn replays_are_rejected_once_stale() {
+        let verifier = WebhookVerifier::new("whsec");
+        let header = verifier.sign(SENT, BODY);
+
+        assert_eq!(verifier.verify_at(&header, BODY, at(SENT + 301)), Err(WebhookError::Stale { seconds: 301 }));
+        assert_eq!(verifier.verify_at(&header, BODY, at(SENT - 301)), Err(WebhookError::Stale { seconds: 301 }));
+        let strict = verifier.tolerance(Duration::from_secs(5));
+        assert_eq!(strict.verify_at(&header, BODY, at(SENT + 6)), Err(WebhookError::Stale { seconds: 6 }));
+    }
+
+    #[test]
+    fn any_matching_signature_is_accepted() {
+        let old = WebhookVerifier::new("old secret").sign(SENT, BODY);
+        let new = WebhookVerifier::new("whsec").sign(SENT, BODY);
+        let header = format!("{},{},v0=ignored", new, old.split_once(',').unwrap().1);
+        assert_eq!(WebhookVerifier::new("old secret").verify_at(&header, BODY, at(SENT)), Ok(()));
+    }
+
+    #[test]
+    fn malformed_headers_are_rejected() {
+        let verifier = WebhookVerifier::new("whsec");
+        for header in ["", "sha256=abcd", "t=soon,v1=abcd", "t=1700000000", "t=1700000000,v1=xyz", "t=1700000000,v1=abc"] {
+            assert_eq!(verifier.verify_at(header, BODY, at(SENT)), Err(WebhookError::Malformed), "{:?}", header);
+        }
+    }
+}
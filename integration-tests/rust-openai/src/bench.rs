@@ -0,0 +1,307 @@
+// Load generator: fires chat completions at a fixed rate and reports latency
+// percentiles, time to first byte for streams, error rate and throughput.
+//
+// Requests go through reqwest rather than async-openai, whose client retries
+// rate limited requests and would hide them from the error rate.
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use teenytiny_rust_openai_integration::config::config;
+use teenytiny_rust_openai_integration::{api_key, base_url, http_client};
+use tokio::time::{interval, MissedTickBehavior};
+
+pub const USAGE: &str = "\
+Usage: integration_test bench [options]
+
+Options:
+  --rps <n>            Requests started per second (default 10)
+  --duration <secs>    How long to keep starting requests (default 10)
+  --stream-ratio <f>   Fraction of requests that stream, from 0 to 1 (default 0.5)
+  --model <name>       Model to call (default echo)
+  --prompt <text>      User message to send (default \"Hello from the bench\")
+  --json <file>        Also write the report as JSON, - for stdout (default --bench-report)
+
+The server and key come from TEENYTINY_URL and TEENYTINY_API_KEY.";
+
+#[derive(Debug, PartialEq)]
+pub struct Options {
+    pub rps: f64,
+    pub duration: Duration,
+    pub stream_ratio: f64,
+    pub model: String,
+    pub prompt: String,
+    pub json: Option<String>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            rps: 10.0,
+            duration: Duration::from_secs(10),
+            stream_ratio: 0.5,
+            model: "echo".to_string(),
+            prompt: "Hello from the bench".to_string(),
+            json: None,
+        }
+    }
+}
+
+pub fn parse_options(args: &[String]) -> Result<Options> {
+    let mut options = Options::default();
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let value = args.next().with_context(|| format!("Missing value for {}", flag))?;
+        match flag.as_str() {
+            "--rps" => options.rps = parse_number(flag, value, 0.0, 10_000.0)?,
+            "--duration" => options.duration = Duration::from_secs_f64(parse_number(flag, value, 0.0, 86_400.0)?),
+            "--stream-ratio" => options.stream_ratio = parse_number(flag, value, 0.0, 1.0)?,
+            "--model" => options.model = value.clone(),
+            "--prompt" => options.prompt = value.clone(),
+            "--json" => options.json = Some(value.clone()),
+            _ => bail!("Unknown option {}", flag),
+        }
+    }
+    if options.rps == 0.0 || options.duration.is_zero() {
+        bail!("--rps and --duration must be greater than 0");
+    }
+    Ok(options)
+}
+
+fn parse_number(flag: &str, value: &str, min: f64, max: f64) -> Result<f64> {
+    match value.parse::<f64>() {
+        Ok(n) if (min..=max).contains(&n) => Ok(n),
+        _ => bail!("Invalid {} '{}': expected a number from {} to {}", flag, value, min, max),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Kind {
+    Blocking,
+    Streaming,
+}
+
+pub struct Sample {
+    pub kind: Kind,
+    pub latency: Duration,
+    // Until the first streamed event arrived
+    pub ttfb: Option<Duration>,
+    // HTTP status, or "connection" / "stream" for failures without one
+    pub error: Option<String>,
+}
+
+// Whether the i-th request streams, spreading streams evenly through the run
+pub fn streams(i: u64, ratio: f64) -> bool {
+    ((i + 1) as f64 * ratio).floor() > (i as f64 * ratio).floor()
+}
+
+pub async fn send(client: reqwest::Client, model: &str, prompt: &str, kind: Kind) -> Sample {
+    let body = json!({
+        "model": model,
+        "messages": [{"role": "user", "content": prompt}],
+        "stream": kind == Kind::Streaming,
+    });
+    let start = Instant::now();
+    let sample = |ttfb, error| Sample { kind, latency: start.elapsed(), ttfb, error };
+
+    let response = client
+        .post(format!("{}/v1/chat/completions", base_url()))
+        .bearer_auth(api_key())
+        .json(&body)
+        .send()
+        .await;
+    let mut response = match response {
+        Ok(response) if response.status().is_success() => response,
+        Ok(response) => return sample(None, Some(response.status().as_u16().to_string())),
+        Err(_) => return sample(None, Some("connection".to_string())),
+    };
+
+    if kind == Kind::Blocking {
+        let error = response.bytes().await.err().map(|_| "connection".to_string());
+        return sample(None, error);
+    }
+
+    // A stream only succeeded if it ran through to [DONE] without an error event
+    let mut ttfb = None;
+    let mut received = String::new();
+    loop {
+        match response.chunk().await {
+            Ok(Some(bytes)) => {
+                ttfb.get_or_insert_with(|| start.elapsed());
+                received.push_str(&String::from_utf8_lossy(&bytes));
+            }
+            Ok(None) => break,
+            Err(_) => return sample(ttfb, Some("stream".to_string())),
+        }
+    }
+    let complete = received.contains("data: [DONE]") && !received.contains("\"error\"");
+    sample(ttfb, (!complete).then(|| "stream".to_string()))
+}
+
+// Nearest-rank percentile of sorted durations, in milliseconds
+pub fn percentile(sorted: &[Duration], p: f64) -> Option<f64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil().max(1.0) as usize;
+    Some(sorted[rank - 1].as_secs_f64() * 1000.0)
+}
+
+fn percentiles(mut durations: Vec<Duration>) -> Value {
+    durations.sort();
+    json!({
+        "p50": percentile(&durations, 50.0),
+        "p95": percentile(&durations, 95.0),
+        "p99": percentile(&durations, 99.0),
+    })
+}
+
+fn summarize(samples: &[&Sample], elapsed: Duration) -> Value {
+    let ok: Vec<_> = samples.iter().filter(|s| s.error.is_none()).collect();
+    let mut errors = BTreeMap::<&str, u64>::new();
+    for error in samples.iter().filter_map(|s| s.error.as_deref()) {
+        *errors.entry(error).or_default() += 1;
+    }
+    let error_rate = if samples.is_empty() { 0.0 } else { (samples.len() - ok.len()) as f64 / samples.len() as f64 };
+
+    json!({
+        "requests": samples.len(),
+        "errors": errors,
+        "error_rate": error_rate,
+        "throughput_rps": ok.len() as f64 / elapsed.as_secs_f64(),
+        "latency_ms": percentiles(ok.iter().map(|s| s.latency).collect()),
+        "ttfb_ms": percentiles(ok.iter().filter_map(|s| s.ttfb).collect()),
+    })
+}
+
+fn report(options: &Options, samples: &[Sample], elapsed: Duration) -> Value {
+    let of_kind = |kind| samples.iter().filter(|s| s.kind == kind).collect::<Vec<_>>();
+    json!({
+        "target": base_url(),
+        "model": options.model,
+        "rps": options.rps,
+        "duration_s": options.duration.as_secs_f64(),
+        "stream_ratio": options.stream_ratio,
+        "elapsed_s": elapsed.as_secs_f64(),
+        "results": {
+            "blocking": summarize(&of_kind(Kind::Blocking), elapsed),
+            "streaming": summarize(&of_kind(Kind::Streaming), elapsed),
+            "all": summarize(&samples.iter().collect::<Vec<_>>(), elapsed),
+        },
+    })
+}
+
+fn print_table(report: &Value) {
+    let ms = |value: &Value| value.as_f64().map_or("-".to_string(), |ms| format!("{:.1}", ms));
+    println!(
+        "{:<10} {:>8} {:>8} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9}",
+        "", "requests", "errors", "req/s", "p50 ms", "p95 ms", "p99 ms", "ttfb p50", "ttfb p95", "ttfb p99"
+    );
+    for name in ["blocking", "streaming", "all"] {
+        let row = &report["results"][name];
+        println!(
+            "{:<10} {:>8} {:>7.1}% {:>9.1} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9}",
+            name,
+            row["requests"].as_u64().unwrap_or(0),
+            row["error_rate"].as_f64().unwrap_or(0.0) * 100.0,
+            row["throughput_rps"].as_f64().unwrap_or(0.0),
+            ms(&row["latency_ms"]["p50"]),
+            ms(&row["latency_ms"]["p95"]),
+            ms(&row["latency_ms"]["p99"]),
+            ms(&row["ttfb_ms"]["p50"]),
+            ms(&row["ttfb_ms"]["p95"]),
+            ms(&row["ttfb_ms"]["p99"]),
+        );
+    }
+    let errors = &report["results"]["all"]["errors"];
+    if errors.as_object().is_some_and(|errors| !errors.is_empty()) {
+        println!("\nErrors by status: {}", errors);
+    }
+}
+
+pub async fn run(args: &[String]) -> Result<()> {
+    let options = parse_options(args)?;
+    let total = (options.rps * options.duration.as_secs_f64()).round().max(1.0) as u64;
+    println!(
+        "Sending {} requests to {} at {} rps ({}% streaming)...\n",
+        total,
+        base_url(),
+        options.rps,
+        options.stream_ratio * 100.0
+    );
+
+    // Open loop: requests start on schedule whether or not earlier ones have
+    // finished, so a slow server shows up as latency rather than a lower rate
+    let client = http_client();
+    let options = std::sync::Arc::new(options);
+    let mut ticks = interval(Duration::from_secs_f64(1.0 / options.rps));
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Burst);
+    let start = Instant::now();
+    let mut tasks = Vec::new();
+    for i in 0..total {
+        ticks.tick().await;
+        let kind = if streams(i, options.stream_ratio) { Kind::Streaming } else { Kind::Blocking };
+        let (client, options) = (client.clone(), options.clone());
+        tasks.push(tokio::spawn(async move { send(client, &options.model, &options.prompt, kind).await }));
+    }
+
+    let mut samples = Vec::new();
+    for task in tasks {
+        samples.push(task.await?);
+    }
+    let report = report(&options, &samples, start.elapsed());
+
+    print_table(&report);
+    let json = options.json.clone().or_else(|| config().bench_report.as_ref().map(|path| path.display().to_string()));
+    match json.as_deref() {
+        Some("-") => println!("\n{}", serde_json::to_string_pretty(&report)?),
+        Some(path) => std::fs::write(path, serde_json::to_string_pretty(&report)?)
+            .with_context(|| format!("Could not write {}", path))?,
+        None => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let options = parse_options(&args("--rps 50 --duration 2.5 --stream-ratio 0.25 --model slow:10 --json -")).unwrap();
+        assert_eq!(options.rps, 50.0);
+        assert_eq!(options.duration, Duration::from_millis(2500));
+        assert_eq!(options.stream_ratio, 0.25);
+        assert_eq!(options.model, "slow:10");
+        assert_eq!(options.json.as_deref(), Some("-"));
+
+        assert_eq!(parse_options(&[]).unwrap(), Options::default());
+        assert!(parse_options(&args("--rps")).is_err());
+        assert!(parse_options(&args("--rps 0")).is_err());
+        assert!(parse_options(&args("--stream-ratio 1.5")).is_err());
+        assert!(parse_options(&args("--concurrency 4")).is_err());
+    }
+
+    #[test]
+    fn test_stream_mix_is_spread_evenly() {
+        let mix: String = (0..8).map(|i| if streams(i, 0.25) { 'S' } else { 'B' }).collect();
+        assert_eq!(mix, "BBBSBBBS");
+        assert!((0..10).all(|i| !streams(i, 0.0)));
+        assert!((0..10).all(|i| streams(i, 1.0)));
+    }
+
+    #[test]
+    fn test_percentile_uses_nearest_rank() {
+        let durations: Vec<_> = (1..=100).map(Duration::from_millis).collect();
+        assert_eq!(percentile(&durations, 50.0), Some(50.0));
+        assert_eq!(percentile(&durations, 99.0), Some(99.0));
+        assert_eq!(percentile(&durations[..1], 95.0), Some(1.0));
+        assert_eq!(percentile(&[], 50.0), None);
+    }
+}
@@ -0,0 +1,293 @@
+// Which OpenAI capability each test verifies, so a run can be summed up as
+// "tools: 9/12 passing" and a server can say which conformance tier it
+// reaches. Tests take their suite's capability unless listed in OVERRIDES;
+// suites that test teenytiny itself (its directives, mock models, admin API)
+// count towards no tier.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Capability {
+    CoreChat,
+    Errors,
+    Models,
+    Streaming,
+    Tools,
+    StructuredOutput,
+    Usage,
+    Vision,
+    Audio,
+    Images,
+    Moderations,
+    Files,
+    Batches,
+    Assistants,
+    Responses,
+    Realtime,
+    RateLimits,
+    Http,
+    // Other providers' APIs served alongside OpenAI's
+    Dialects,
+    Teenytiny,
+}
+
+impl Capability {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Capability::CoreChat => "core-chat",
+            Capability::Errors => "errors",
+            Capability::Models => "models",
+            Capability::Streaming => "streaming",
+            Capability::Tools => "tools",
+            Capability::StructuredOutput => "structured-output",
+            Capability::Usage => "usage",
+            Capability::Vision => "vision",
+            Capability::Audio => "audio",
+            Capability::Images => "images",
+            Capability::Moderations => "moderations",
+            Capability::Files => "files",
+            Capability::Batches => "batches",
+            Capability::Assistants => "assistants",
+            Capability::Responses => "responses",
+            Capability::Realtime => "realtime",
+            Capability::RateLimits => "rate-limits",
+            Capability::Http => "http",
+            Capability::Dialects => "dialects",
+            Capability::Teenytiny => "teenytiny",
+        }
+    }
+}
+
+use Capability::*;
+
+// Every suite in src/tests, with the capability its tests verify, from the
+// list in lib.rs that also declares their modules
+macro_rules! suite_table {
+    ($($suite:ident: $capability:ident),* $(,)?) => {
+        pub const SUITES: &[(&str, Capability)] = &[$((stringify!($suite), $capability)),*];
+    };
+}
+suites!(suite_table);
+
+// Tests that verify something other than their suite's capability
+pub const OVERRIDES: &[(&str, Capability)] = &[
+    ("golden::test_chat_completion_echo_streaming", Streaming),
+    ("golden::test_chat_completion_slow_streaming", Streaming),
+    ("golden::test_chat_completion_echo_directives", Teenytiny),
+    ("golden::test_chat_completion_flaky_without_fault", Teenytiny),
+    ("golden::test_chat_completion_tooluse", Tools),
+    ("golden::test_chat_completion_tooluse_streaming", Tools),
+    ("golden::test_chat_completion_json_schema", StructuredOutput),
+    ("golden::test_chat_completion_errors", Errors),
+    ("golden::test_invalid_api_key", Errors),
+    ("golden::test_models_list", Models),
+    ("golden::test_moderation", Moderations),
+    ("golden::test_image_generation", Images),
+    ("golden::test_transcription", Audio),
+    ("golden::test_session_say", Teenytiny),
+    ("options::test_streaming_with_parameters", Streaming),
+];
+
+/// Conformance tiers, each needing every capability of the tiers below it too
+pub const TIERS: &[(&str, &[Capability])] = &[
+    ("core", &[CoreChat, Errors, Models]),
+    ("standard", &[Streaming, Tools, StructuredOutput, Usage]),
+    ("extended", &[Vision, Audio, Images, Moderations]),
+    ("full", &[Files, Batches, Assistants, Responses, Realtime]),
+];
+
+/// The capability a test verifies, from its path within the tests module
+pub fn capability_of(test: &str) -> Option<Capability> {
+    let test = test.strip_prefix("tests::").unwrap_or(test);
+    if let Some((_, capability)) = OVERRIDES.iter().find(|(name, _)| *name == test) {
+        return Some(*capability);
+    }
+    let suite = test.split("::").next()?;
+    SUITES.iter().find(|(name, _)| *name == suite).map(|(_, capability)| *capability)
+}
+
+// The areas of a server's /version api_surface that serve a capability; any one will do
+fn areas(capability: Capability) -> &'static [&'static str] {
+    match capability {
+        CoreChat | Errors | Streaming | Tools | StructuredOutput | Usage | Vision | RateLimits | Http => &["chat.completions"],
+        Models => &["models"],
+        Audio => &["audio.transcriptions", "audio.speech"],
+        Images => &["images.generations"],
+        Moderations => &["moderations"],
+        Files => &["files"],
+        Batches => &["batches"],
+        Assistants => &["assistants"],
+        Responses => &["responses"],
+        Realtime => &["realtime"],
+        Dialects => &["ollama", "gemini", "azure"],
+        // Only teenytiny has an admin API
+        Teenytiny => &["admin"],
+    }
+}
+
+/// Which capabilities the server under test has, so tests of the rest skip
+/// rather than fail. Without anything to go on, every capability is assumed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Support {
+    areas: Option<Vec<String>>,
+}
+
+impl Support {
+    /// From a teenytiny server's /version, which lists the areas it serves
+    pub fn from_version(version: &Value) -> Option<Support> {
+        let areas = version["api_surface"].as_array()?.iter().filter_map(Value::as_str).map(String::from).collect();
+        Some(Support { areas: Some(areas) })
+    }
+
+    /// From /v1/models, for servers without /version such as OpenAI itself:
+    /// the OpenAI API, with audio, images, moderations and realtime only when
+    /// there are models for them
+    pub fn from_models(models: &Value) -> Option<Support> {
+        let ids: Vec<&str> = models["data"].as_array()?.iter().filter_map(|model| model["id"].as_str()).collect();
+        let any = |words: &[&str]| ids.iter().any(|id| words.iter().any(|word| id.contains(word)));
+        let mut areas = vec!["models", "chat.completions", "files", "batches", "assistants", "responses"];
+        for (words, area) in [
+            (&["whisper", "transcribe"][..], "audio.transcriptions"),
+            (&["tts"], "audio.speech"),
+            (&["dall-e", "image"], "images.generations"),
+            (&["moderation"], "moderations"),
+            (&["realtime"], "realtime"),
+        ] {
+            if any(words) {
+                areas.push(area);
+            }
+        }
+        Some(Support { areas: Some(areas.into_iter().map(String::from).collect()) })
+    }
+
+    pub fn supports(&self, capability: Capability) -> bool {
+        match &self.areas {
+            Some(served) => areas(capability).iter().any(|area| served.iter().any(|served| served == area)),
+            None => true,
+        }
+    }
+
+    /// The first of these capabilities the server doesn't have
+    pub fn missing(&self, required: impl IntoIterator<Item = Capability>) -> Option<Capability> {
+        required.into_iter().find(|capability| !self.supports(*capability))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Tally {
+    pub passed: usize,
+    pub failed: usize,
+    pub ignored: usize,
+}
+
+impl Tally {
+    /// Every test that ran passed, and at least one ran
+    pub fn met(&self) -> bool {
+        self.failed == 0 && self.passed > 0
+    }
+}
+
+/// Passes and failures per capability. Tests the registry doesn't know are
+/// returned separately, so a new suite without a capability gets noticed.
+pub fn tally<'a>(
+    results: impl IntoIterator<Item = (&'a str, Option<bool>)>,
+) -> (BTreeMap<Capability, Tally>, Vec<&'a str>) {
+    let mut tallies: BTreeMap<Capability, Tally> = BTreeMap::new();
+    let mut unknown = Vec::new();
+    for (test, passed) in results {
+        let Some(capability) = capability_of(test) else {
+            unknown.push(test);
+            continue;
+        };
+        let tally = tallies.entry(capability).or_default();
+        match passed {
+            Some(true) => tally.passed += 1,
+            Some(false) => tally.failed += 1,
+            None => tally.ignored += 1,
+        }
+    }
+    (tallies, unknown)
+}
+
+/// The highest tier whose capabilities, and those of every tier below, all pass
+pub fn tier(tallies: &BTreeMap<Capability, Tally>) -> Option<&'static str> {
+    let mut reached = None;
+    for (name, capabilities) in TIERS {
+        if !capabilities.iter().all(|capability| tallies.get(capability).is_some_and(Tally::met)) {
+            break;
+        }
+        reached = Some(*name);
+    }
+    reached
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_suite_file_is_listed() {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests");
+        let files: Vec<String> = std::fs::read_dir(dir).unwrap()
+            .filter_map(|entry| entry.unwrap().file_name().to_str()?.strip_suffix(".rs").map(String::from))
+            .collect();
+        assert!(files.len() > 40, "Expected to find the suites in src/tests");
+
+        for file in &files {
+            assert!(SUITES.iter().any(|(name, _)| name == file), "src/tests/{}.rs isn't in the suites list in lib.rs", file);
+        }
+    }
+
+    #[test]
+    fn test_overrides_win_over_the_suite() {
+        assert_eq!(capability_of("tests::golden::test_chat_completion_echo"), Some(CoreChat));
+        assert_eq!(capability_of("golden::test_chat_completion_tooluse_streaming"), Some(Tools));
+        assert_eq!(capability_of("tooluse::test_blocking_tool_calls"), Some(Tools));
+        assert_eq!(capability_of("not_a_suite::test_anything"), None);
+    }
+
+    #[test]
+    fn test_support_comes_from_the_api_surface_or_the_models() {
+        let teenytiny = Support::from_version(&serde_json::json!({"api_surface": ["models", "chat.completions", "admin"]})).unwrap();
+        assert!(teenytiny.supports(Tools));
+        assert!(teenytiny.supports(Teenytiny));
+        assert_eq!(teenytiny.missing([CoreChat, Realtime, Images]), Some(Realtime));
+
+        let models = serde_json::json!({"data": [{"id": "gpt-4o"}, {"id": "whisper-1"}]});
+        let openai = Support::from_models(&models).unwrap();
+        assert!(openai.supports(Audio) && openai.supports(Assistants));
+        assert_eq!(openai.missing([Images, Teenytiny]), Some(Images));
+        assert!(!openai.supports(Dialects));
+
+        assert!(Support::from_version(&serde_json::json!({"error": "not found"})).is_none());
+        assert_eq!(Support::default().missing([Realtime, Teenytiny]), None);
+    }
+
+    #[test]
+    fn test_tiers_need_every_capability_below() {
+        let results = [
+            ("basic::a", Some(true)),
+            ("error_shapes::a", Some(true)),
+            ("models::a", Some(true)),
+            ("streaming::a", Some(true)),
+            ("tooluse::a", Some(true)),
+            ("tooluse::b", Some(false)),
+            ("json_model::a", Some(true)),
+            ("tokenizer::a", None),
+            ("new_suite::a", Some(true)),
+        ];
+
+        let (tallies, unknown) = tally(results);
+        assert_eq!(tallies[&Tools], Tally { passed: 1, failed: 1, ignored: 0 });
+        assert_eq!(unknown, ["new_suite::a"]);
+        assert_eq!(tier(&tallies), Some("core"));
+
+        let (tallies, _) = tally(results.into_iter().filter(|(test, _)| *test != "tooluse::b"));
+        // Usage's only test was ignored, so standard still isn't reached
+        assert_eq!(tier(&tallies), Some("core"));
+    }
+}
@@ -0,0 +1,614 @@
+// Harness settings, gathered in one place. Each source overrides the ones
+// before it:
+//
+//   1. defaults, for a local server started with `npm run dev`
+//   2. a TOML profile named by --profile or TEENYTINY_PROFILE
+//   3. a .env file in the working directory
+//   4. the process environment
+//   5. command line flags, for the integration_test binary
+//
+// `cargo test` can't take flags, so tests see the first four.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+pub const USAGE: &str = "\
+Settings (before the command, or in the environment):
+  --url <url>            Server under test, without /v1 (TEENYTINY_URL, default http://localhost:8080)
+  --api-key <key>        The server's API key (TEENYTINY_API_KEY, default testkey)
+  --ca-cert <file>       PEM bundle to trust for https:// (TEENYTINY_CA_CERT)
+  --timeout <secs>       Per-request timeout, none by default (TEENYTINY_TIMEOUT)
+  --test-timeout <secs>  Fail a test that takes longer, none by default (TEENYTINY_TEST_TIMEOUT)
+  --ready-timeout <secs> How long to wait for the server to answer before testing (TEENYTINY_READY_TIMEOUT, default 30)
+  --skip-tags <a,b>      Skip the tests with any of these tags (TEENYTINY_SKIP_TAGS)
+  --concurrency <n>      Simultaneous streams in the concurrency tests (TEENYTINY_CONCURRENCY, default 120)
+  --junit-report <file>  Where ./test writes JUnit XML (TEENYTINY_JUNIT_REPORT, default ../reports/rust-openai.xml)
+  --bench-report <file>  Where bench writes its JSON report unless given --json (TEENYTINY_BENCH_REPORT)
+  --long                 Also run the long tests, which take minutes (TEENYTINY_LONG=1)
+  --spawn                Start a server from this checkout on a free port instead of using --url (TEENYTINY_SPAWN=1)
+  --verbose-http         Write each test's requests and responses, secrets redacted, to files (TEENYTINY_VERBOSE_HTTP=1)
+  --trace-dir <dir>      Where --verbose-http writes them (TEENYTINY_TRACE_DIR, default ../reports/http-traces)
+  --stream-timings       Time each streamed response and report percentiles per suite (TEENYTINY_STREAM_TIMINGS=1)
+  --ttfb-max-ms <ms>     Fail a test whose streams take longer to start, timing them (TEENYTINY_TTFB_MAX_MS)
+  --chunk-gap-max-ms <ms> Fail a test whose streams wait longer between chunks, timing them (TEENYTINY_CHUNK_GAP_MAX_MS)
+  --config-file <file>   The server's --config file, for the reload tests to change (TEENYTINY_CONFIG)
+  --fixtures-dir <dir>   The directory the server's --fixtures watches, for the hot reload test (TEENYTINY_FIXTURES_DIR)
+  --api-keys <a,b:m|m>   The server's provisioned keys, for the key scoping tests (TEENYTINY_API_KEYS)
+  --revoked-keys <a,b>   Keys the server was told to refuse (TEENYTINY_REVOKED_KEYS)
+  --profile <file>       TOML file of these settings, with underscores for dashes (TEENYTINY_PROFILE)
+  --print-config         Print the settings in effect and exit
+  --no-color             Plain output, as when NO_COLOR is set or output isn't a terminal
+  --quiet                Only show failing tests and the summary while the suite runs
+
+A .env file in the working directory is read too; the environment wins over it.";
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HarnessConfig {
+    pub url: String,
+    pub api_key: String,
+    pub ca_cert: Option<PathBuf>,
+    #[serde(serialize_with = "seconds")]
+    pub timeout: Option<Duration>,
+    #[serde(serialize_with = "seconds")]
+    pub test_timeout: Option<Duration>,
+    #[serde(serialize_with = "duration_seconds")]
+    pub ready_timeout: Duration,
+    pub skip_tags: Vec<String>,
+    pub concurrency: usize,
+    pub junit_report: PathBuf,
+    pub bench_report: Option<PathBuf>,
+    pub long: bool,
+    pub spawn: bool,
+    pub verbose_http: bool,
+    pub trace_dir: PathBuf,
+    pub stream_timings: bool,
+    #[serde(rename = "ttfb_max_ms", serialize_with = "millis")]
+    pub ttfb_max: Option<Duration>,
+    #[serde(rename = "chunk_gap_max_ms", serialize_with = "millis")]
+    pub chunk_gap_max: Option<Duration>,
+    pub config_file: Option<PathBuf>,
+    pub fixtures_dir: Option<PathBuf>,
+    /// As the server reads them, each key with an optional ":model|model" allowlist
+    pub api_keys: Vec<String>,
+    pub revoked_keys: Vec<String>,
+}
+
+impl Default for HarnessConfig {
+    fn default() -> Self {
+        HarnessConfig {
+            url: "http://localhost:8080".to_string(),
+            api_key: "testkey".to_string(),
+            ca_cert: None,
+            timeout: None,
+            test_timeout: None,
+            ready_timeout: Duration::from_secs(30),
+            skip_tags: Vec::new(),
+            concurrency: 120,
+            junit_report: PathBuf::from("../reports/rust-openai.xml"),
+            bench_report: None,
+            long: false,
+            spawn: false,
+            verbose_http: false,
+            trace_dir: PathBuf::from("../reports/http-traces"),
+            stream_timings: false,
+            ttfb_max: None,
+            chunk_gap_max: None,
+            config_file: None,
+            fixtures_dir: None,
+            api_keys: Vec::new(),
+            revoked_keys: Vec::new(),
+        }
+    }
+}
+
+fn seconds<S: serde::Serializer>(timeout: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error> {
+    match timeout {
+        Some(timeout) => serializer.serialize_some(&timeout.as_secs_f64()),
+        None => serializer.serialize_none(),
+    }
+}
+
+fn millis<S: serde::Serializer>(limit: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error> {
+    match limit {
+        Some(limit) => serializer.serialize_some(&(limit.as_secs_f64() * 1000.0)),
+        None => serializer.serialize_none(),
+    }
+}
+
+fn duration_seconds<S: serde::Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64())
+}
+
+// A profile file, or the settings from one other source. Every field is
+// optional so a source only overrides what it names.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Layer {
+    url: Option<String>,
+    api_key: Option<String>,
+    ca_cert: Option<String>,
+    timeout: Option<f64>,
+    test_timeout: Option<f64>,
+    ready_timeout: Option<f64>,
+    skip_tags: Option<Vec<String>>,
+    concurrency: Option<usize>,
+    junit_report: Option<String>,
+    bench_report: Option<String>,
+    long: Option<bool>,
+    spawn: Option<bool>,
+    verbose_http: Option<bool>,
+    trace_dir: Option<String>,
+    stream_timings: Option<bool>,
+    ttfb_max_ms: Option<f64>,
+    chunk_gap_max_ms: Option<f64>,
+    config_file: Option<String>,
+    fixtures_dir: Option<String>,
+    api_keys: Option<Vec<String>>,
+    revoked_keys: Option<Vec<String>>,
+    #[serde(skip)]
+    profile: Option<String>,
+}
+
+const ENV_VARS: [(&str, &str); 22] = [
+    ("TEENYTINY_URL", "url"),
+    ("TEENYTINY_API_KEY", "api_key"),
+    ("TEENYTINY_CA_CERT", "ca_cert"),
+    ("TEENYTINY_TIMEOUT", "timeout"),
+    ("TEENYTINY_TEST_TIMEOUT", "test_timeout"),
+    ("TEENYTINY_READY_TIMEOUT", "ready_timeout"),
+    ("TEENYTINY_SKIP_TAGS", "skip_tags"),
+    ("TEENYTINY_CONCURRENCY", "concurrency"),
+    ("TEENYTINY_JUNIT_REPORT", "junit_report"),
+    ("TEENYTINY_BENCH_REPORT", "bench_report"),
+    ("TEENYTINY_LONG", "long"),
+    ("TEENYTINY_SPAWN", "spawn"),
+    ("TEENYTINY_VERBOSE_HTTP", "verbose_http"),
+    ("TEENYTINY_TRACE_DIR", "trace_dir"),
+    ("TEENYTINY_STREAM_TIMINGS", "stream_timings"),
+    ("TEENYTINY_TTFB_MAX_MS", "ttfb_max_ms"),
+    ("TEENYTINY_CHUNK_GAP_MAX_MS", "chunk_gap_max_ms"),
+    ("TEENYTINY_CONFIG", "config_file"),
+    ("TEENYTINY_FIXTURES_DIR", "fixtures_dir"),
+    ("TEENYTINY_API_KEYS", "api_keys"),
+    ("TEENYTINY_REVOKED_KEYS", "revoked_keys"),
+    ("TEENYTINY_PROFILE", "profile"),
+];
+
+impl Layer {
+    // Sets a setting from its string form, as found in the environment or a flag.
+    // Empty values are ignored, so `TEENYTINY_CA_CERT=` leaves the setting alone.
+    fn set(&mut self, name: &str, source: &str, value: &str) -> Result<()> {
+        let value = value.trim();
+        if value.is_empty() {
+            return Ok(());
+        }
+        let text = Some(value.to_string());
+        match name {
+            "url" => self.url = text,
+            "api_key" => self.api_key = text,
+            "ca_cert" => self.ca_cert = text,
+            "junit_report" => self.junit_report = text,
+            "bench_report" => self.bench_report = text,
+            "trace_dir" => self.trace_dir = text,
+            "config_file" => self.config_file = text,
+            "fixtures_dir" => self.fixtures_dir = text,
+            "profile" => self.profile = text,
+            "timeout" | "test_timeout" | "ready_timeout" => {
+                let Ok(seconds) = value.parse() else {
+                    bail!("Invalid {} '{}': expected seconds", source, value);
+                };
+                match name {
+                    "timeout" => self.timeout = Some(seconds),
+                    "test_timeout" => self.test_timeout = Some(seconds),
+                    _ => self.ready_timeout = Some(seconds),
+                }
+            }
+            "ttfb_max_ms" | "chunk_gap_max_ms" => {
+                let Ok(millis) = value.parse() else {
+                    bail!("Invalid {} '{}': expected milliseconds", source, value);
+                };
+                match name {
+                    "ttfb_max_ms" => self.ttfb_max_ms = Some(millis),
+                    _ => self.chunk_gap_max_ms = Some(millis),
+                }
+            }
+            "skip_tags" | "api_keys" | "revoked_keys" => {
+                let list = Some(value.split(',').map(str::trim).filter(|item| !item.is_empty()).map(String::from).collect());
+                match name {
+                    "skip_tags" => self.skip_tags = list,
+                    "api_keys" => self.api_keys = list,
+                    _ => self.revoked_keys = list,
+                }
+            }
+            "concurrency" => match value.parse() {
+                Ok(n) => self.concurrency = Some(n),
+                Err(_) => bail!("Invalid {} '{}': expected a whole number", source, value),
+            },
+            "long" | "spawn" | "verbose_http" | "stream_timings" => {
+                let flag = match value {
+                    "1" | "true" | "yes" => true,
+                    "0" | "false" | "no" => false,
+                    _ => bail!("Invalid {} '{}': expected 1 or 0", source, value),
+                };
+                match name {
+                    "long" => self.long = Some(flag),
+                    "spawn" => self.spawn = Some(flag),
+                    "verbose_http" => self.verbose_http = Some(flag),
+                    _ => self.stream_timings = Some(flag),
+                }
+            }
+            _ => bail!("Unknown setting {}", source),
+        }
+        Ok(())
+    }
+
+    fn from_vars(vars: &HashMap<String, String>) -> Result<Layer> {
+        let mut layer = Layer::default();
+        for (var, name) in ENV_VARS {
+            if let Some(value) = vars.get(var) {
+                layer.set(name, var, value)?;
+            }
+        }
+        Ok(layer)
+    }
+
+    fn apply(self, config: &mut HarnessConfig) -> Result<()> {
+        if let Some(url) = self.url {
+            config.url = url;
+        }
+        if let Some(api_key) = self.api_key {
+            config.api_key = api_key;
+        }
+        if let Some(ca_cert) = self.ca_cert {
+            config.ca_cert = Some(PathBuf::from(ca_cert));
+        }
+        if let Some(timeout) = self.timeout {
+            config.timeout = Some(duration("timeout", timeout)?);
+        }
+        if let Some(test_timeout) = self.test_timeout {
+            config.test_timeout = Some(duration("test_timeout", test_timeout)?);
+        }
+        if let Some(ready_timeout) = self.ready_timeout {
+            config.ready_timeout = duration("ready_timeout", ready_timeout)?;
+        }
+        if let Some(skip_tags) = self.skip_tags {
+            config.skip_tags = skip_tags;
+        }
+        if let Some(concurrency) = self.concurrency {
+            config.concurrency = concurrency;
+        }
+        if let Some(junit_report) = self.junit_report {
+            config.junit_report = PathBuf::from(junit_report);
+        }
+        if let Some(bench_report) = self.bench_report {
+            config.bench_report = Some(PathBuf::from(bench_report));
+        }
+        if let Some(long) = self.long {
+            config.long = long;
+        }
+        if let Some(spawn) = self.spawn {
+            config.spawn = spawn;
+        }
+        if let Some(verbose_http) = self.verbose_http {
+            config.verbose_http = verbose_http;
+        }
+        if let Some(trace_dir) = self.trace_dir {
+            config.trace_dir = PathBuf::from(trace_dir);
+        }
+        if let Some(stream_timings) = self.stream_timings {
+            config.stream_timings = stream_timings;
+        }
+        if let Some(ttfb_max_ms) = self.ttfb_max_ms {
+            config.ttfb_max = Some(duration("ttfb_max_ms", ttfb_max_ms / 1000.0)?);
+        }
+        if let Some(chunk_gap_max_ms) = self.chunk_gap_max_ms {
+            config.chunk_gap_max = Some(duration("chunk_gap_max_ms", chunk_gap_max_ms / 1000.0)?);
+        }
+        if let Some(config_file) = self.config_file {
+            config.config_file = Some(PathBuf::from(config_file));
+        }
+        if let Some(fixtures_dir) = self.fixtures_dir {
+            config.fixtures_dir = Some(PathBuf::from(fixtures_dir));
+        }
+        if let Some(api_keys) = self.api_keys {
+            config.api_keys = api_keys;
+        }
+        if let Some(revoked_keys) = self.revoked_keys {
+            config.revoked_keys = revoked_keys;
+        }
+        Ok(())
+    }
+}
+
+// A duration from a number of seconds, where negatives count as 0 and inf is refused
+fn duration(name: &str, seconds: f64) -> Result<Duration> {
+    match Duration::try_from_secs_f64(seconds.max(0.0)) {
+        Ok(duration) => Ok(duration),
+        Err(_) => bail!("Invalid {}: expected a finite number", name),
+    }
+}
+
+// Reads KEY=VALUE lines, skipping blanks and # comments. Values may be quoted.
+fn parse_dotenv(text: &str) -> HashMap<String, String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.strip_prefix("export ").unwrap_or(line).split_once('='))
+        .map(|(key, value)| {
+            let value = value.trim();
+            let unquoted = value
+                .strip_prefix('"').and_then(|v| v.strip_suffix('"'))
+                .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+                .unwrap_or(value);
+            (key.trim().to_string(), unquoted.to_string())
+        })
+        .collect()
+}
+
+fn read_profile(path: &Path) -> Result<Layer> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("Can't read profile {}", path.display()))?;
+    toml::from_str(&text).with_context(|| format!("Invalid profile {}", path.display()))
+}
+
+/// Command line flags for the settings, and what was left after them
+#[derive(Default)]
+pub struct Flags {
+    layer: Layer,
+    pub print_config: bool,
+    pub no_color: bool,
+    pub quiet: bool,
+    pub rest: Vec<String>,
+}
+
+/// Takes settings flags from the front of the arguments, stopping at the first that isn't one
+pub fn parse_flags(args: &[String]) -> Result<Flags> {
+    let mut flags = Flags::default();
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let name = match flag.as_str() {
+            "--print-config" => {
+                flags.print_config = true;
+                continue;
+            }
+            "--no-color" => {
+                flags.no_color = true;
+                continue;
+            }
+            "--quiet" => {
+                flags.quiet = true;
+                continue;
+            }
+            "--long" => {
+                flags.layer.long = Some(true);
+                continue;
+            }
+            "--spawn" => {
+                flags.layer.spawn = Some(true);
+                continue;
+            }
+            "--verbose-http" => {
+                flags.layer.verbose_http = Some(true);
+                continue;
+            }
+            "--stream-timings" => {
+                flags.layer.stream_timings = Some(true);
+                continue;
+            }
+            "--url" | "--api-key" | "--ca-cert" | "--timeout" | "--test-timeout" | "--ready-timeout" | "--skip-tags"
+            | "--concurrency" | "--junit-report" | "--bench-report" | "--trace-dir" | "--ttfb-max-ms" | "--chunk-gap-max-ms"
+            | "--config-file" | "--fixtures-dir" | "--api-keys" | "--revoked-keys" | "--profile" => flag[2..].replace('-', "_"),
+            _ => {
+                flags.rest = std::iter::once(flag).chain(args).cloned().collect();
+                break;
+            }
+        };
+        let value = args.next().with_context(|| format!("Missing value for {}", flag))?;
+        flags.layer.set(&name, flag, value)?;
+    }
+    Ok(flags)
+}
+
+impl HarnessConfig {
+    /// Loads the settings from every source, as the harness binary does
+    pub fn load(flags: &Flags) -> Result<HarnessConfig> {
+        let dotenv = match std::fs::read_to_string(".env") {
+            Ok(text) => parse_dotenv(&text),
+            Err(_) => HashMap::new(),
+        };
+        let env: HashMap<String, String> = std::env::vars().collect();
+        Self::from_sources(Layer::from_vars(&dotenv)?, Layer::from_vars(&env)?, flags.layer.clone())
+    }
+
+    fn from_sources(dotenv: Layer, env: Layer, flags: Layer) -> Result<HarnessConfig> {
+        let profile = flags.profile.clone().or_else(|| env.profile.clone()).or_else(|| dotenv.profile.clone());
+
+        let mut config = HarnessConfig::default();
+        if let Some(profile) = profile {
+            read_profile(Path::new(&profile))?.apply(&mut config)?;
+        }
+        dotenv.apply(&mut config)?;
+        env.apply(&mut config)?;
+        flags.apply(&mut config)?;
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&mut self) -> Result<()> {
+        self.url = self.url.trim_end_matches('/').to_string();
+        if !self.url.starts_with("http://") && !self.url.starts_with("https://") {
+            bail!("Invalid url '{}': expected http:// or https://", self.url);
+        }
+        if self.url.ends_with("/v1") {
+            bail!("Invalid url '{}': leave off the /v1 suffix", self.url);
+        }
+        if self.api_key.is_empty() {
+            bail!("The api key can't be empty");
+        }
+        if let Some(ca_cert) = &self.ca_cert {
+            if !ca_cert.is_file() {
+                bail!("Invalid ca_cert '{}': no such file", ca_cert.display());
+            }
+        }
+        if self.timeout.is_some_and(|timeout| timeout.is_zero()) {
+            bail!("Invalid timeout: expected more than 0 seconds");
+        }
+        if self.test_timeout.is_some_and(|timeout| timeout.is_zero()) {
+            bail!("Invalid test_timeout: expected more than 0 seconds");
+        }
+        if self.ttfb_max.is_some_and(|limit| limit.is_zero()) {
+            bail!("Invalid ttfb_max_ms: expected more than 0 milliseconds");
+        }
+        if self.chunk_gap_max.is_some_and(|limit| limit.is_zero()) {
+            bail!("Invalid chunk_gap_max_ms: expected more than 0 milliseconds");
+        }
+        if !(1..=10_000).contains(&self.concurrency) {
+            bail!("Invalid concurrency {}: expected 1 to 10000", self.concurrency);
+        }
+        Ok(())
+    }
+
+    /// Whether streamed responses are timed, as asked for or to check against a limit
+    pub fn times_streams(&self) -> bool {
+        self.stream_timings || self.ttfb_max.is_some() || self.chunk_gap_max.is_some()
+    }
+
+    /// The settings as a TOML profile, for --print-config
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("Settings are always valid TOML")
+    }
+}
+
+static CONFIG: OnceLock<HarnessConfig> = OnceLock::new();
+
+/// The settings in effect, loaded from everything but flags on first use
+pub fn config() -> &'static HarnessConfig {
+    CONFIG.get_or_init(|| {
+        HarnessConfig::load(&Flags::default()).unwrap_or_else(|e| panic!("Invalid harness settings: {:#}", e))
+    })
+}
+
+/// Puts settings in effect before anything reads them, as the binary does with its flags
+pub fn init(config: HarnessConfig) {
+    if CONFIG.set(config).is_err() {
+        panic!("Harness settings were read before they were set");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    fn vars(pairs: &[(&str, &str)]) -> Layer {
+        let vars = pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        Layer::from_vars(&vars).unwrap()
+    }
+
+    #[test]
+    fn test_later_sources_win() {
+        let dotenv = vars(&[("TEENYTINY_URL", "http://dotenv:1"), ("TEENYTINY_CONCURRENCY", "4")]);
+        let env = vars(&[("TEENYTINY_URL", "http://env:2/"), ("TEENYTINY_TIMEOUT", "2.5")]);
+        let flags = parse_flags(&args("--concurrency 8 --long --spawn --verbose-http --ttfb-max-ms 800 --skip-tags slow,,network bench --rps 5")).unwrap();
+        assert_eq!(flags.rest, args("bench --rps 5"));
+
+        let config = HarnessConfig::from_sources(dotenv, env, flags.layer).unwrap();
+        assert_eq!(config.url, "http://env:2");
+        assert_eq!(config.timeout, Some(Duration::from_millis(2500)));
+        assert_eq!(config.concurrency, 8);
+        assert!(config.long);
+        assert!(config.spawn);
+        assert!(config.verbose_http);
+        assert_eq!(config.ttfb_max, Some(Duration::from_millis(800)));
+        assert!(config.times_streams());
+        assert_eq!(config.skip_tags, ["slow", "network"]);
+        assert_eq!(config.api_key, "testkey");
+    }
+
+    #[test]
+    fn test_server_settings_the_tests_need() {
+        let env = vars(&[
+            ("TEENYTINY_CONFIG", "teenytiny.toml"),
+            ("TEENYTINY_API_KEYS", "tenant-a, tenant-b:echo|eliza"),
+            ("TEENYTINY_REVOKED_KEYS", ""),
+        ]);
+        let flags = parse_flags(&args("--fixtures-dir ../../service/fixtures")).unwrap();
+
+        let config = HarnessConfig::from_sources(Layer::default(), env, flags.layer).unwrap();
+        assert_eq!(config.config_file, Some(PathBuf::from("teenytiny.toml")));
+        assert_eq!(config.fixtures_dir, Some(PathBuf::from("../../service/fixtures")));
+        assert_eq!(config.api_keys, ["tenant-a", "tenant-b:echo|eliza"]);
+        assert!(config.revoked_keys.is_empty());
+    }
+
+    #[test]
+    fn test_profile_sits_under_the_environment() {
+        let path = std::env::temp_dir().join(format!("harness-profile-{}.toml", std::process::id()));
+        std::fs::write(&path, "url = \"https://staging.example\"\napi_key = \"staging\"\nconcurrency = 16\n").unwrap();
+
+        let env = vars(&[("TEENYTINY_PROFILE", path.to_str().unwrap()), ("TEENYTINY_API_KEY", "override")]);
+        let config = HarnessConfig::from_sources(Layer::default(), env, Layer::default()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(config.url, "https://staging.example");
+        assert_eq!(config.api_key, "override");
+        assert_eq!(config.concurrency, 16);
+    }
+
+    #[test]
+    fn test_invalid_settings_are_rejected() {
+        for (var, value) in [
+            ("TEENYTINY_URL", "localhost:8080"),
+            ("TEENYTINY_URL", "http://localhost:8080/v1"),
+            ("TEENYTINY_CA_CERT", "/no/such/cert.pem"),
+            ("TEENYTINY_TIMEOUT", "0"),
+            ("TEENYTINY_TEST_TIMEOUT", "0"),
+            ("TEENYTINY_TTFB_MAX_MS", "0"),
+            ("TEENYTINY_CONCURRENCY", "0"),
+            ("TEENYTINY_TIMEOUT", "inf"),
+            ("TEENYTINY_READY_TIMEOUT", "inf"),
+            ("TEENYTINY_CHUNK_GAP_MAX_MS", "inf"),
+        ] {
+            let env = vars(&[(var, value)]);
+            assert!(HarnessConfig::from_sources(Layer::default(), env, Layer::default()).is_err(), "{}={}", var, value);
+        }
+        let vars = [("TEENYTINY_LONG".to_string(), "maybe".to_string())].into();
+        assert!(Layer::from_vars(&vars).is_err());
+        assert!(parse_flags(&args("--timeout soon")).is_err());
+        assert!(parse_flags(&args("--chunk-gap-max-ms soon")).is_err());
+        assert!(parse_flags(&args("--url")).is_err());
+    }
+
+    #[test]
+    fn test_dotenv_lines() {
+        let vars = parse_dotenv("# local server\nTEENYTINY_URL=http://localhost:9000\nexport TEENYTINY_API_KEY=\"quoted key\"\n\n");
+        assert_eq!(vars["TEENYTINY_URL"], "http://localhost:9000");
+        assert_eq!(vars["TEENYTINY_API_KEY"], "quoted key");
+        assert_eq!(vars.len(), 2);
+    }
+
+    #[test]
+    fn test_printed_config_reads_back_as_a_profile() {
+        let config = HarnessConfig {
+            timeout: Some(Duration::from_secs(30)),
+            test_timeout: Some(Duration::from_secs(120)),
+            ready_timeout: Duration::from_secs(5),
+            ttfb_max: Some(Duration::from_millis(250)),
+            skip_tags: vec!["slow".to_string()],
+            ..HarnessConfig::default()
+        };
+        let layer: Layer = toml::from_str(&config.to_toml()).unwrap();
+
+        let mut read_back = HarnessConfig::default();
+        layer.apply(&mut read_back).unwrap();
+        assert_eq!(read_back, config);
+    }
+}
@@ -0,0 +1,158 @@
+// Conformance report: runs the suite against the configured server and sums
+// up the results per OpenAI capability, with the highest tier the server
+// reaches, so an implementation can advertise what it supports.
+
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use teenytiny_rust_openai_integration::capabilities::{self, Capability, Tally, TIERS};
+use teenytiny_rust_openai_integration::base_url;
+
+use crate::matrix::{run_target, Outcome, Target};
+
+pub const USAGE: &str = "\
+Usage: integration_test conformance [options]
+
+Runs the suite against the configured server and reports passes per capability.
+
+Options:
+  --include-teenytiny    Also report the suites for teenytiny's own features
+  --json <file>          Also write the report as JSON, - for stdout";
+
+#[derive(Debug, PartialEq, Default)]
+pub struct Options {
+    pub include_teenytiny: bool,
+    pub json: Option<String>,
+}
+
+pub fn parse_options(args: &[String]) -> Result<Options> {
+    let mut options = Options::default();
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--include-teenytiny" => options.include_teenytiny = true,
+            "--json" => options.json = Some(args.next().with_context(|| format!("Missing value for {}", flag))?.clone()),
+            _ => bail!("Unknown option {}", flag),
+        }
+    }
+    Ok(options)
+}
+
+fn passed(outcome: Outcome) -> Option<Option<bool>> {
+    match outcome {
+        Outcome::Passed => Some(Some(true)),
+        Outcome::Failed => Some(Some(false)),
+        // Not run, either way
+        Outcome::Ignored | Outcome::Skipped => Some(None),
+        Outcome::Missing => None,
+    }
+}
+
+/// The per-capability report for one run's results
+pub fn report(results: &BTreeMap<String, Outcome>) -> Value {
+    let (tallies, unknown) = capabilities::tally(
+        results.iter().filter_map(|(test, outcome)| Some((test.as_str(), passed(*outcome)?))),
+    );
+    let by_capability: BTreeMap<&str, &Tally> =
+        tallies.iter().map(|(capability, tally)| (capability.as_str(), tally)).collect();
+    let tiers: Vec<Value> = TIERS.iter()
+        .map(|(name, capabilities)| {
+            let missing: Vec<&str> = capabilities.iter()
+                .filter(|capability| !tallies.get(capability).is_some_and(Tally::met))
+                .map(|capability| capability.as_str())
+                .collect();
+            json!({"tier": name, "met": missing.is_empty(), "missing": missing})
+        })
+        .collect();
+
+    json!({
+        "tier": capabilities::tier(&tallies),
+        "tiers": tiers,
+        "capabilities": by_capability,
+        "unclassified": unknown,
+    })
+}
+
+fn print_report(report: &Value, include_teenytiny: bool) {
+    println!("{:<18} {:>9} {:>8}", "capability", "passing", "ignored");
+    for (capability, tally) in report["capabilities"].as_object().into_iter().flatten() {
+        if capability == Capability::Teenytiny.as_str() && !include_teenytiny {
+            continue;
+        }
+        let (passed, failed) = (tally["passed"].as_u64().unwrap_or(0), tally["failed"].as_u64().unwrap_or(0));
+        println!(
+            "{:<18} {:>9} {:>8}{}",
+            capability,
+            format!("{}/{}", passed, passed + failed),
+            tally["ignored"],
+            if failed > 0 { "  FAILING" } else { "" }
+        );
+    }
+
+    println!();
+    for tier in report["tiers"].as_array().into_iter().flatten() {
+        let missing: Vec<&str> = tier["missing"].as_array().into_iter().flatten().filter_map(Value::as_str).collect();
+        match missing.is_empty() {
+            true => println!("{:<10} met", tier["tier"].as_str().unwrap_or_default()),
+            false => println!("{:<10} needs {}", tier["tier"].as_str().unwrap_or_default(), missing.join(", ")),
+        }
+    }
+    match report["tier"].as_str() {
+        Some(tier) => println!("\nConformance tier: {}", tier),
+        None => println!("\nConformance tier: none"),
+    }
+
+    let unclassified = report["unclassified"].as_array().map(Vec::len).unwrap_or(0);
+    if unclassified > 0 {
+        println!("{} tests have no capability; add their suite to the suites list in src/lib.rs", unclassified);
+    }
+}
+
+pub async fn run(args: &[String]) -> Result<()> {
+    let options = parse_options(args)?;
+    let target = Target { name: "server".to_string(), url: base_url(), key: None };
+
+    println!("Running the suite against {}...\n", target.url);
+    let run = run_target(&target, None).await?;
+    let report = report(&run.results);
+
+    print_report(&report, options.include_teenytiny);
+    match options.json.as_deref() {
+        Some("-") => println!("\n{}", serde_json::to_string_pretty(&report)?),
+        Some(path) => std::fs::write(path, serde_json::to_string_pretty(&report)?)
+            .with_context(|| format!("Could not write {}", path))?,
+        None => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matrix::parse_results;
+
+    #[test]
+    fn test_report_counts_per_capability() {
+        let results = parse_results("\
+test tests::basic::test_basic_completion ... ok
+test tests::error_shapes::test_400_invalid_json ... ok
+test tests::models::test_unknown_model_is_404 ... ok
+test tests::tooluse::test_blocking_tool_calls ... ok
+test tests::tooluse::test_named_tool_choice ... FAILED
+test tests::proxy::test_upstream ... ignored");
+
+        let report = report(&results);
+        assert_eq!(report["capabilities"]["tools"], json!({"passed": 1, "failed": 1, "ignored": 0}));
+        assert_eq!(report["capabilities"]["teenytiny"]["ignored"], 1);
+        assert_eq!(report["tier"], "core");
+        assert_eq!(report["tiers"][1]["missing"], json!(["streaming", "tools", "structured-output", "usage"]));
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let args: Vec<String> = ["--include-teenytiny", "--json", "-"].map(String::from).to_vec();
+        assert_eq!(parse_options(&args).unwrap(), Options { include_teenytiny: true, json: Some("-".to_string()) });
+        assert!(parse_options(&["--json".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,426 @@
+// Generated test data: conversation histories, unicode text and tool schemas,
+// so suites don't hand-write message arrays. Everything is drawn from a seeded
+// generator, so the same seed always gives the same data and a failure can be
+// reproduced. (Canned responses for the server's fixture model are a different
+// thing; see tests/fixture_model.rs.)
+
+use std::ops::RangeInclusive;
+
+use async_openai::types::{
+    ChatCompletionRequestAssistantMessageArgs, ChatCompletionRequestMessage,
+    ChatCompletionRequestSystemMessageArgs, ChatCompletionRequestUserMessageArgs, ChatCompletionTool,
+    ChatCompletionToolArgs, FunctionObjectArgs,
+};
+use serde_json::{json, Value};
+
+const DEFAULT_SEED: u64 = 0x7ee9_7119;
+
+// SplitMix64: small, fast and good enough to pick words
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    pub fn range(&mut self, range: RangeInclusive<usize>) -> usize {
+        let span = (range.end() - range.start() + 1) as u64;
+        range.start() + (self.next_u64() % span) as usize
+    }
+
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.range(0..=items.len() - 1)]
+    }
+}
+
+/// Scripts a conversation's words are drawn from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    English,
+    French,
+    German,
+    Russian,
+    Greek,
+    Arabic,
+    Hindi,
+    Japanese,
+    Chinese,
+    Emoji,
+    /// Each message in a language of its own
+    Mixed,
+}
+
+impl Language {
+    pub const ALL: [Language; 10] = [
+        Language::English,
+        Language::French,
+        Language::German,
+        Language::Russian,
+        Language::Greek,
+        Language::Arabic,
+        Language::Hindi,
+        Language::Japanese,
+        Language::Chinese,
+        Language::Emoji,
+    ];
+
+    fn words(self) -> &'static [&'static str] {
+        match self {
+            Language::English | Language::Mixed => &[
+                "the", "weather", "today", "is", "quite", "pleasant", "could", "you", "help", "me", "with", "my",
+                "report", "please", "thanks", "a", "lot", "server", "request", "answer",
+            ],
+            Language::French => &[
+                "le", "temps", "est", "très", "agréable", "aujourd'hui", "pourriez-vous", "m'aider", "avec", "mon",
+                "rapport", "s'il", "vous", "plaît", "merci", "beaucoup", "été", "où", "ça", "déjà",
+            ],
+            Language::German => &[
+                "das", "Wetter", "ist", "heute", "ziemlich", "angenehm", "könnten", "Sie", "mir", "bei", "meinem",
+                "Bericht", "helfen", "bitte", "danke", "schön", "Straße", "Größe", "Übung", "fußläufig",
+            ],
+            Language::Russian => &[
+                "погода", "сегодня", "довольно", "приятная", "не", "могли", "бы", "вы", "помочь", "мне", "с",
+                "отчётом", "пожалуйста", "спасибо", "большое", "ещё", "ёлка", "щука", "объявление", "съезд",
+            ],
+            Language::Greek => &[
+                "ο", "καιρός", "είναι", "σήμερα", "αρκετά", "ευχάριστος", "μπορείτε", "να", "με", "βοηθήσετε",
+                "παρακαλώ", "ευχαριστώ", "πολύ", "ψυχή", "ώρα", "ΐ", "Ωμέγα",
+            ],
+            Language::Arabic => &[
+                "الطقس", "اليوم", "لطيف", "جدا", "هل", "يمكنك", "مساعدتي", "في", "تقريري", "من", "فضلك", "شكرا",
+                "جزيلا", "مرحبا", "السلام", "عليكم",
+            ],
+            Language::Hindi => &[
+                "आज", "मौसम", "काफी", "सुहावना", "है", "क्या", "आप", "मेरी", "रिपोर्ट", "में", "मदद", "कर", "सकते",
+                "हैं", "कृपया", "धन्यवाद",
+            ],
+            Language::Japanese => &[
+                "今日", "の", "天気", "は", "とても", "いい", "です", "レポート", "を", "手伝って", "ください",
+                "ありがとう", "ございます", "カタカナ", "ｶﾀｶﾅ", "〜",
+            ],
+            Language::Chinese => &[
+                "今天", "天气", "很", "好", "你", "能", "帮", "我", "写", "报告", "吗", "谢谢", "请", "服务器", "𠀋",
+                "龘",
+            ],
+            Language::Emoji => &[
+                "🙂", "👍", "🎉", "🚀", "❤️", "👩‍💻", "👨‍👩‍👧‍👦", "🏳️‍🌈", "👋🏽", "🇯🇵", "🧪", "☕", "1️⃣", "🐛",
+            ],
+        }
+    }
+
+    // Chinese and Japanese are written without spaces between words
+    fn separator(self) -> &'static str {
+        match self {
+            Language::Japanese | Language::Chinese => "",
+            _ => " ",
+        }
+    }
+}
+
+/// Builds a conversation: an optional system prompt, then user and assistant
+/// messages taking turns, ending with a user message
+///
+/// ```ignore
+/// let conversation = Conversation::new().turns(4).language(Language::Japanese);
+/// let request = CreateChatCompletionRequestArgs::default()
+///     .messages(conversation.messages())
+///     ...;
+/// assert_eq!(reply, conversation.last_user_message());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Conversation {
+    system: Option<String>,
+    turns: usize,
+    words: RangeInclusive<usize>,
+    language: Language,
+    seed: u64,
+}
+
+impl Default for Conversation {
+    fn default() -> Self {
+        Conversation { system: None, turns: 1, words: 3..=12, language: Language::English, seed: DEFAULT_SEED }
+    }
+}
+
+/// One generated message, before it's turned into a request message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Turn {
+    pub role: &'static str,
+    pub content: String,
+}
+
+impl Conversation {
+    pub fn new() -> Conversation {
+        Conversation::default()
+    }
+
+    pub fn system(mut self, prompt: &str) -> Self {
+        self.system = Some(prompt.to_string());
+        self
+    }
+
+    /// User messages, each but the last followed by an assistant reply
+    pub fn turns(mut self, turns: usize) -> Self {
+        assert!(turns > 0, "A conversation needs at least one turn");
+        self.turns = turns;
+        self
+    }
+
+    /// Words per message
+    pub fn words(mut self, words: RangeInclusive<usize>) -> Self {
+        self.words = words;
+        self
+    }
+
+    pub fn language(mut self, language: Language) -> Self {
+        self.language = language;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn turns_list(&self) -> Vec<Turn> {
+        let mut rng = Rng::new(self.seed);
+        let mut turns: Vec<Turn> = self
+            .system
+            .iter()
+            .map(|prompt| Turn { role: "system", content: prompt.clone() })
+            .collect();
+        for i in 0..self.turns {
+            turns.push(Turn { role: "user", content: self.sentence(&mut rng) });
+            if i + 1 < self.turns {
+                turns.push(Turn { role: "assistant", content: self.sentence(&mut rng) });
+            }
+        }
+        turns
+    }
+
+    pub fn messages(&self) -> Vec<ChatCompletionRequestMessage> {
+        self.turns_list().into_iter().map(|turn| message(turn.role, &turn.content)).collect()
+    }
+
+    /// The messages as raw JSON, for requests async-openai can't build
+    pub fn json(&self) -> Value {
+        Value::Array(
+            self.turns_list().into_iter().map(|turn| json!({"role": turn.role, "content": turn.content})).collect(),
+        )
+    }
+
+    /// What the echo model replies with
+    pub fn last_user_message(&self) -> String {
+        self.turns_list().pop().expect("Conversations end with a user message").content
+    }
+
+    fn sentence(&self, rng: &mut Rng) -> String {
+        let language = match self.language {
+            Language::Mixed => *rng.pick(&Language::ALL),
+            language => language,
+        };
+        let count = rng.range(self.words.clone());
+        (0..count).map(|_| *rng.pick(language.words())).collect::<Vec<_>>().join(language.separator())
+    }
+}
+
+pub fn system_message(content: &str) -> ChatCompletionRequestMessage {
+    message("system", content)
+}
+
+pub fn user_message(content: &str) -> ChatCompletionRequestMessage {
+    message("user", content)
+}
+
+pub fn assistant_message(content: &str) -> ChatCompletionRequestMessage {
+    message("assistant", content)
+}
+
+fn message(role: &str, content: &str) -> ChatCompletionRequestMessage {
+    match role {
+        "system" => ChatCompletionRequestSystemMessageArgs::default().content(content).build().unwrap().into(),
+        "user" => ChatCompletionRequestUserMessageArgs::default().content(content).build().unwrap().into(),
+        "assistant" => ChatCompletionRequestAssistantMessageArgs::default().content(content).build().unwrap().into(),
+        _ => unreachable!("Unknown role {}", role),
+    }
+}
+
+// Text that is hard to get right: combining marks, right-to-left runs, astral
+// characters, emoji sequences, zero-width and unusual whitespace
+const TRICKY: &[&str] = &[
+    "e\u{301}", "n\u{303}", "\u{5d0}\u{5d1}\u{5d2}", "\u{202e}", "\u{200b}", "\u{200d}", "\u{feff}", "\u{a0}",
+    "\u{2028}", "\u{3000}", "\t", "\n", "\r\n", "\"", "\\", "</script>", "\u{fffd}", "𝔘𝔫𝔦𝔠𝔬𝔡𝔢", "𝟘𝟙𝟚",
+    "\u{1f1fa}\u{1f1f8}", "🧑🏾‍🚀", "Z̤͔ͧ̑̓ä͖̭̈̇lͮ̒ͫǧ̗͚̚o̙̔ͮ̇͐̇",
+];
+
+/// At least `chars` characters of text mixing every language with awkward
+/// characters, e.g. to check content survives JSON, SSE framing and chunking
+/// byte for byte
+pub fn unicode_corpus(chars: usize, seed: u64) -> String {
+    let mut rng = Rng::new(seed);
+    let mut corpus = String::new();
+    let mut count = 0;
+    while count < chars {
+        let piece = if rng.range(0..=3) == 0 {
+            *rng.pick(TRICKY)
+        } else {
+            let language = *rng.pick(&Language::ALL);
+            *rng.pick(language.words())
+        };
+        corpus.push_str(piece);
+        corpus.push(' ');
+        count += piece.chars().count() + 1;
+    }
+    corpus
+}
+
+/// A realistic function tool by catalog index, from a flat schema up to nested
+/// objects, arrays and enums; indices past the catalog repeat it with new names
+pub fn tool(index: usize) -> ChatCompletionTool {
+    let (name, description, parameters) = tool_definition(index);
+    ChatCompletionToolArgs::default()
+        .function(FunctionObjectArgs::default().name(name).description(description).parameters(parameters).build().unwrap())
+        .build()
+        .unwrap()
+}
+
+/// The first `count` tools of the catalog
+pub fn tools(count: usize) -> Vec<ChatCompletionTool> {
+    (0..count).map(tool).collect()
+}
+
+/// A tool as the raw JSON of a request's tools array
+pub fn tool_json(index: usize) -> Value {
+    let (name, description, parameters) = tool_definition(index);
+    json!({"type": "function", "function": {"name": name, "description": description, "parameters": parameters}})
+}
+
+fn tool_definition(index: usize) -> (String, &'static str, Value) {
+    let catalog: [(&str, &str, Value); 4] = [
+        (
+            "get_weather",
+            "Get the current weather in a city",
+            json!({
+                "type": "object",
+                "properties": {
+                    "city": {"type": "string", "description": "City name, e.g. Paris"},
+                    "unit": {"type": "string", "enum": ["celsius", "fahrenheit"]},
+                },
+                "required": ["city"],
+            }),
+        ),
+        (
+            "search_products",
+            "Search the catalog",
+            json!({
+                "type": "object",
+                "properties": {
+                    "query": {"type": "string"},
+                    "max_results": {"type": "integer", "minimum": 1, "maximum": 50},
+                    "in_stock": {"type": "boolean"},
+                    "tags": {"type": "array", "items": {"type": "string"}},
+                },
+                "required": ["query"],
+            }),
+        ),
+        (
+            "create_event",
+            "Add an event to a calendar",
+            json!({
+                "type": "object",
+                "properties": {
+                    "title": {"type": "string"},
+                    "start": {"type": "string", "format": "date-time"},
+                    "attendees": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "email": {"type": "string"},
+                                "optional": {"type": "boolean"},
+                            },
+                            "required": ["email"],
+                        },
+                    },
+                    "location": {
+                        "type": "object",
+                        "properties": {
+                            "name": {"type": "string"},
+                            "lat": {"type": "number"},
+                            "lng": {"type": "number"},
+                        },
+                    },
+                },
+                "required": ["title", "start"],
+            }),
+        ),
+        ("get_time", "Get the current time", json!({"type": "object", "properties": {}})),
+    ];
+    let (name, description, parameters) = catalog[index % catalog.len()].clone();
+    let name = match index / catalog.len() {
+        0 => name.to_string(),
+        n => format!("{}_{}", name, n + 1),
+    };
+    (name, description, parameters)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversations_take_turns_and_end_with_the_user() {
+        let conversation = Conversation::new().system("Be brief").turns(3);
+        let roles: Vec<_> = conversation.turns_list().iter().map(|turn| turn.role).collect();
+
+        assert_eq!(roles, ["system", "user", "assistant", "user", "assistant", "user"]);
+        assert_eq!(conversation.messages().len(), 6);
+        assert_eq!(conversation.json()[5]["content"], conversation.last_user_message());
+    }
+
+    #[test]
+    fn test_same_seed_same_conversation() {
+        let a = Conversation::new().turns(5).language(Language::Mixed).seed(42);
+
+        assert_eq!(a.turns_list(), a.clone().turns_list());
+        assert_ne!(a.turns_list(), a.clone().seed(43).turns_list());
+    }
+
+    #[test]
+    fn test_words_per_message() {
+        let conversation = Conversation::new().turns(10).words(2..=4).seed(7);
+
+        for turn in conversation.turns_list() {
+            let words = turn.content.split(' ').count();
+            assert!((2..=4).contains(&words), "{} words in {:?}", words, turn.content);
+        }
+    }
+
+    #[test]
+    fn test_unicode_corpus() {
+        let corpus = unicode_corpus(5000, 1);
+
+        assert!(corpus.chars().count() >= 5000);
+        assert!(corpus.chars().any(|c| c.len_utf8() == 4), "No astral characters");
+        assert!(corpus.chars().any(|c| ('\u{300}'..='\u{36f}').contains(&c)), "No combining marks");
+        assert_eq!(corpus, unicode_corpus(5000, 1));
+    }
+
+    #[test]
+    fn test_tool_names_are_unique() {
+        let names: Vec<_> = (0..10).map(|i| tool_json(i)["function"]["name"].as_str().unwrap().to_string()).collect();
+        let unique: std::collections::BTreeSet<_> = names.iter().collect();
+
+        assert_eq!(unique.len(), names.len(), "{:?}", names);
+        assert_eq!(tools(3).len(), 3);
+    }
+}
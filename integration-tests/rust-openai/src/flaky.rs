@@ -0,0 +1,204 @@
+// Flaky test detection: runs the suite, or the tests a filter picks, several
+// times against the configured server and reports each test's pass rate and
+// the spread of its times. A test that passes some runs and fails others is
+// flaky; so is one that never passes, since it wasn't passing either.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use teenytiny_rust_openai_integration::base_url;
+
+use crate::bench::percentile;
+use crate::matrix::{run_target, Outcome, Run, Target};
+
+pub const USAGE: &str = "\
+Usage: integration_test flaky [options]
+
+Runs the suite several times against the configured server and reports each test's pass rate.
+
+Options:
+  --repeat <n>           How many times to run each test (default 10)
+  --filter <text>        Only run tests whose names contain this
+  --json <file>          Also write every test's pass rate and times as JSON, - for stdout";
+
+#[derive(Debug, PartialEq)]
+pub struct Options {
+    pub repeat: usize,
+    pub filter: Option<String>,
+    pub json: Option<String>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options { repeat: 10, filter: None, json: None }
+    }
+}
+
+pub fn parse_options(args: &[String]) -> Result<Options> {
+    let mut options = Options::default();
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let value = args.next().with_context(|| format!("Missing value for {}", flag))?;
+        match flag.as_str() {
+            "--repeat" => {
+                options.repeat = match value.parse() {
+                    Ok(n) if n > 0 => n,
+                    _ => bail!("Invalid --repeat '{}': expected a whole number above 0", value),
+                }
+            }
+            "--filter" => options.filter = Some(value.clone()),
+            "--json" => options.json = Some(value.clone()),
+            _ => bail!("Unknown option {}", flag),
+        }
+    }
+    Ok(options)
+}
+
+fn seconds(sorted: &[Duration], p: f64) -> Option<f64> {
+    percentile(sorted, p).map(|ms| ms / 1000.0)
+}
+
+/// Each test's pass rate and times over the runs, and the tests that didn't always pass
+pub fn report(runs: &[Run]) -> Value {
+    let mut names: Vec<&String> = runs.iter().flat_map(|run| run.results.keys()).collect();
+    names.sort();
+    names.dedup();
+
+    let mut flaky = Vec::new();
+    let tests: BTreeMap<&str, Value> = names.into_iter()
+        .map(|name| {
+            let outcomes: Vec<Outcome> = runs.iter().filter_map(|run| run.results.get(name).copied()).collect();
+            let count = |outcome| outcomes.iter().filter(|o| **o == outcome).count();
+            let (passed, failed) = (count(Outcome::Passed), count(Outcome::Failed));
+            // Ignored and skipped runs say nothing about whether a test passes
+            let pass_rate = (passed + failed > 0).then(|| passed as f64 / (passed + failed) as f64);
+            if failed > 0 {
+                flaky.push(name.as_str());
+            }
+
+            let mut durations: Vec<Duration> = runs.iter()
+                .filter(|run| matches!(run.results.get(name), Some(Outcome::Passed | Outcome::Failed)))
+                .filter_map(|run| run.durations.get(name).copied())
+                .collect();
+            durations.sort();
+            let test = json!({
+                "runs": outcomes.len(),
+                "passed": passed,
+                "failed": failed,
+                "ignored": count(Outcome::Ignored),
+                "skipped": count(Outcome::Skipped),
+                "pass_rate": pass_rate,
+                "seconds": {
+                    "min": seconds(&durations, 0.0),
+                    "p50": seconds(&durations, 50.0),
+                    "p95": seconds(&durations, 95.0),
+                    "max": seconds(&durations, 100.0),
+                },
+            });
+            (name.as_str(), test)
+        })
+        .collect();
+
+    json!({
+        "target": base_url(),
+        "repeat": runs.len(),
+        "tests": tests,
+        "flaky": flaky,
+    })
+}
+
+fn print_report(report: &Value) {
+    let flaky: Vec<&str> = report["flaky"].as_array().into_iter().flatten().filter_map(Value::as_str).collect();
+    let total = report["tests"].as_object().map_or(0, |tests| tests.len());
+    if flaky.is_empty() {
+        println!("All {} tests passed every time they ran in {} runs.", total, report["repeat"]);
+        return;
+    }
+
+    let secs = |value: &Value| value.as_f64().map_or("-".to_string(), |secs| format!("{:.2}", secs));
+    let width = flaky.iter().map(|name| name.len()).max().unwrap_or(0).max(4);
+    println!(
+        "{:<width$} {:>9} {:>7} {:>7} {:>7} {:>7}",
+        "test", "pass rate", "min s", "p50 s", "p95 s", "max s", width = width
+    );
+    for name in &flaky {
+        let test = &report["tests"][name];
+        println!(
+            "{:<width$} {:>9} {:>7} {:>7} {:>7} {:>7}",
+            name,
+            format!("{}/{}", test["passed"], test["passed"].as_u64().unwrap_or(0) + test["failed"].as_u64().unwrap_or(0)),
+            secs(&test["seconds"]["min"]),
+            secs(&test["seconds"]["p50"]),
+            secs(&test["seconds"]["p95"]),
+            secs(&test["seconds"]["max"]),
+            width = width
+        );
+    }
+    println!("\n{} of {} tests didn't pass every run.", flaky.len(), total);
+}
+
+pub async fn run(args: &[String]) -> Result<()> {
+    let options = parse_options(args)?;
+    let target = Target { name: "server".to_string(), url: base_url(), key: None };
+
+    // One run after another, as tests that change server-wide settings can't overlap
+    let mut runs = Vec::new();
+    for i in 1..=options.repeat {
+        println!("Run {} of {} against {}...", i, options.repeat, target.url);
+        runs.push(run_target(&target, options.filter.as_deref()).await?);
+        println!();
+    }
+    let report = report(&runs);
+
+    print_report(&report);
+    match options.json.as_deref() {
+        Some("-") => println!("\n{}", serde_json::to_string_pretty(&report)?),
+        Some(path) => std::fs::write(path, serde_json::to_string_pretty(&report)?)
+            .with_context(|| format!("Could not write {}", path))?,
+        None => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matrix::parse_results;
+
+    fn run(output: &str, millis: &[(&str, u64)]) -> Run {
+        Run {
+            results: parse_results(output),
+            durations: millis.iter().map(|(name, ms)| (name.to_string(), Duration::from_millis(*ms))).collect(),
+            streams: Vec::new(),
+            elapsed: Duration::from_secs(1),
+        }
+    }
+
+    #[test]
+    fn test_report_flags_tests_that_did_not_always_pass() {
+        let runs = [
+            run("test a ... ok\ntest b ... ok\ntest c ... ignored", &[("a", 100), ("b", 200)]),
+            run("test a ... ok\ntest b ... FAILED\ntest c ... ignored", &[("a", 300), ("b", 5000)]),
+            run("test a ... ok\ntest b ... ok\ntest c ... ignored", &[("a", 200), ("b", 250)]),
+        ];
+
+        let report = report(&runs);
+        assert_eq!(report["flaky"], json!(["b"]));
+        assert_eq!(report["tests"]["a"]["pass_rate"], 1.0);
+        assert_eq!(report["tests"]["a"]["seconds"], json!({"min": 0.1, "p50": 0.2, "p95": 0.3, "max": 0.3}));
+        assert_eq!(report["tests"]["b"]["pass_rate"].as_f64().unwrap(), 2.0 / 3.0);
+        assert_eq!(report["tests"]["c"]["pass_rate"], Value::Null);
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let args: Vec<String> = ["--repeat", "25", "--filter", "streaming"].map(String::from).to_vec();
+        let options = parse_options(&args).unwrap();
+        assert_eq!((options.repeat, options.filter.as_deref()), (25, Some("streaming")));
+
+        assert_eq!(parse_options(&[]).unwrap(), Options::default());
+        assert!(parse_options(&["--repeat".to_string(), "0".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,192 @@
+// What each test in src/tests gets from teenytiny_test!: a client for the
+// server under test, skipping by tag or by what the server supports, the run's
+// per-test timeout, its time on stderr (shown with --nocapture or when it
+// fails), with --verbose-http a directory of its HTTP traces, and with stream
+// timing its streams timed and held to the run's latency limits.
+//
+//   teenytiny_test!(async fn test_completion(client) {
+//       let response = client.chat().create(request).await.unwrap();
+//       ...
+//   });
+//
+//   // Only runs with --long, and not with TEENYTINY_SKIP_TAGS=slow
+//   teenytiny_test!(tags(long, slow) async fn test_long_stream() {
+//       ...
+//   });
+//
+//   // Skips unless the server serves its suite's capability and Tools too
+//   teenytiny_test!(requires(Tools) async fn test_function_call() {
+//       ...
+//   });
+//
+// Tests are named by their module path as libtest prints it, such as
+// tests::basic::test_completion, which is also the name the integration_test
+// binary filters on and reports when it runs the suite with `cargo test`.
+
+use std::fs::OpenOptions;
+use std::future::Future;
+use std::io::Write;
+use std::time::Instant;
+
+use async_openai::{config::OpenAIConfig, Client};
+use tokio::sync::OnceCell;
+
+use crate::capabilities::{self, Capability, Support};
+use crate::config::{config, HarnessConfig};
+use crate::{api_key, probe, server_url, setup_client, stream_timing, trace};
+
+/// Defines a `#[tokio::test]` that runs its body through [`run`]. Name a
+/// parameter to be given a client for the server under test.
+#[cfg(test)]
+macro_rules! teenytiny_test {
+    (
+        $(#[$attr:meta])*
+        $(tags($($tag:ident),+ $(,)?))?
+        $(requires($($capability:ident),+ $(,)?))?
+        async fn $name:ident($($client:ident)?) $body:block
+    ) => {
+        $(#[$attr])*
+        #[tokio::test]
+        async fn $name() {
+            $crate::harness::run(
+                concat!(module_path!(), "::", stringify!($name)),
+                &[$($(stringify!($tag)),+)?],
+                &[$($($crate::capabilities::Capability::$capability),+)?],
+                |_client| async move {
+                    $(let $client = _client;)?
+                    $body
+                },
+            )
+            .await;
+        }
+    };
+}
+
+/// Runs one test unless its tags or the server's capabilities skip it,
+/// failing it if it outlasts the test_timeout setting
+pub async fn run<F, Fut>(path: &str, tags: &[&str], requires: &[Capability], test: F)
+where
+    F: FnOnce(Client<OpenAIConfig>) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    // Drop the crate name, leaving the path libtest prints
+    let name = path.split_once("::").map_or(path, |(_, name)| name);
+    if let Some(reason) = skip_reason(tags, config()) {
+        return skip(name, &reason);
+    }
+
+    if let Err(e) = server_ready().await {
+        panic!("{}", e);
+    }
+    // Its suite's capability, and any the test needs besides
+    let required = capabilities::capability_of(name).into_iter().chain(requires.iter().copied());
+    if let Some(capability) = server_support().await.missing(required) {
+        return skip(name, &format!("the server doesn't support {}", capability.as_str()));
+    }
+
+    if config().verbose_http {
+        trace::reset_test(&config().trace_dir, name);
+    }
+
+    let _timer = Timer { name, start: Instant::now() };
+    let test = trace::with_test(name, async { test(setup_client()).await });
+    match config().test_timeout {
+        Some(limit) => {
+            if tokio::time::timeout(limit, test).await.is_err() {
+                panic!("{} timed out after {:?} (TEENYTINY_TEST_TIMEOUT)", name, limit);
+            }
+        }
+        None => test.await,
+    }
+
+    let streams = report_streams(name);
+    if let Some(error) = stream_timing::check(&streams, config().ttfb_max, config().chunk_gap_max) {
+        panic!("{}: {}", name, error);
+    }
+}
+
+// Whether the server answered, checked by the first test to run so the rest
+// fail at once when it didn't
+async fn server_ready() -> &'static Result<(), String> {
+    static READY: OnceCell<Result<(), String>> = OnceCell::const_new();
+    READY
+        .get_or_init(|| async {
+            probe::wait_for_server(&server_url(), config().ready_timeout).await.map_err(|e| format!("{:#}", e))
+        })
+        .await
+}
+
+// What the server serves, asked by the first test to run
+async fn server_support() -> &'static Support {
+    static SUPPORT: OnceCell<Support> = OnceCell::const_new();
+    SUPPORT.get_or_init(|| async { probe::server_support(&server_url(), &api_key()).await }).await
+}
+
+/// Where the integration_test binary asks for each test's time, which tests
+/// skipped and how their streams were timed, to show them as the suite runs
+pub const RESULTS_VAR: &str = "TEENYTINY_RESULTS_FILE";
+
+/// Reports a test as skipped, for a test that finds it can't run once it has
+/// started. libtest reports a skipped test as passing, so the binary learns
+/// it skipped from here.
+pub fn skip(name: &str, reason: &str) {
+    eprintln!("Skipping: {}", reason);
+    report(&format!("{}\tskipped\t{}\n", name, reason));
+}
+
+fn report(line: &str) {
+    if let Ok(path) = std::env::var(RESULTS_VAR) {
+        let _ = OpenOptions::new().create(true).append(true).open(path).and_then(|mut file| file.write_all(line.as_bytes()));
+    }
+}
+
+// Reports the streams a test made, so those of a test that failed count too
+fn report_streams(name: &str) -> Vec<stream_timing::StreamTiming> {
+    let streams = stream_timing::take(name);
+    for timing in &streams {
+        report(&stream_timing::line(name, timing));
+    }
+    streams
+}
+
+// Reports a test's time, and any streams not yet reported, when it ends,
+// whether it returned or panicked
+struct Timer<'a> {
+    name: &'a str,
+    start: Instant,
+}
+
+impl Drop for Timer<'_> {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed();
+        eprintln!("{} took {:.2?}", self.name, elapsed);
+        report(&format!("{}\t{}\n", self.name, elapsed.as_secs_f64()));
+        report_streams(self.name);
+    }
+}
+
+/// Why a test with these tags doesn't run under these settings, if it doesn't
+pub fn skip_reason(tags: &[&str], config: &HarnessConfig) -> Option<String> {
+    if tags.contains(&"long") && !config.long {
+        return Some("long tests run with --long or TEENYTINY_LONG=1".to_string());
+    }
+    tags.iter()
+        .find(|tag| config.skip_tags.iter().any(|skipped| skipped == *tag))
+        .map(|tag| format!("tests tagged {} are skipped by TEENYTINY_SKIP_TAGS", tag))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tags_skip_tests() {
+        let config = HarnessConfig { skip_tags: vec!["slow".to_string()], ..HarnessConfig::default() };
+
+        assert_eq!(skip_reason(&[], &config), None);
+        assert_eq!(skip_reason(&["network"], &config), None);
+        assert!(skip_reason(&["network", "slow"], &config).unwrap().contains("slow"));
+        assert!(skip_reason(&["long"], &config).unwrap().contains("--long"));
+        assert_eq!(skip_reason(&["long"], &HarnessConfig { long: true, ..config }), None);
+    }
+}
@@ -1,46 +1,337 @@
 use async_openai::{config::OpenAIConfig, Client};
-use std::env;
+
+// Every suite in src/tests with the capability its tests verify (see
+// capabilities.rs). The one list declares the test modules and fills
+// capabilities::SUITES, so a new suite is added here and nowhere else.
+macro_rules! suites {
+    ($callback:ident) => {
+        $callback! {
+            basic: CoreChat,
+            options: CoreChat,
+            message_roles: CoreChat,
+            service_tier: CoreChat,
+            golden: CoreChat,
+            streaming: Streaming,
+            cancellation: Streaming,
+            concurrency: Streaming,
+            long_streams: Streaming,
+            auth_errors: Errors,
+            organization: Errors,
+            error_shapes: Errors,
+            validation: Errors,
+            raw_http: Errors,
+            middleware: Errors,
+            large_payloads: Errors,
+            models: Models,
+            tooluse: Tools,
+            legacy_functions: Tools,
+            json_model: StructuredOutput,
+            tokenizer: Usage,
+            usage: Usage,
+            prompt_caching: Usage,
+            multimodal: Vision,
+            audio: Audio,
+            images: Images,
+            moderations: Moderations,
+            files: Files,
+            batches: Batches,
+            assistants: Assistants,
+            responses: Responses,
+            realtime: Realtime,
+            rate_limits: RateLimits,
+            cors: Http,
+            compression: Http,
+            http2: Http,
+            tls: Http,
+            idempotency: Http,
+            ollama: Dialects,
+            gemini: Dialects,
+            azure: Dialects,
+            sessions: Teenytiny,
+            key_scoping: Teenytiny,
+            echo_directives: Teenytiny,
+            echo_properties: Teenytiny,
+            lorem: Teenytiny,
+            slow: Teenytiny,
+            flaky: Teenytiny,
+            filtered: Teenytiny,
+            reasoning: Teenytiny,
+            fixture_model: Teenytiny,
+            script_model: Teenytiny,
+            proxy: Teenytiny,
+            recording: Teenytiny,
+            admin: Teenytiny,
+            health: Teenytiny,
+            request_log: Teenytiny,
+            usage_metering: Teenytiny,
+            quotas: Teenytiny,
+            config_reload: Teenytiny,
+            latency: Teenytiny,
+            model_defaults: Teenytiny,
+            chunking: Teenytiny,
+            deterministic: Teenytiny,
+            debug_parse: Teenytiny,
+            backpressure: Teenytiny,
+            drain: Teenytiny,
+            memory: Teenytiny,
+            template: Teenytiny,
+            router: Teenytiny,
+            markov: Teenytiny,
+            scenarios: Teenytiny,
+            capture: Teenytiny,
+            webhooks: Teenytiny,
+            persistence: Teenytiny,
+            tenants: Teenytiny,
+            teenytiny_client: Teenytiny,
+        }
+    };
+}
+
+pub mod capabilities;
+pub mod config;
+pub mod fixtures;
+#[macro_use]
+pub mod harness;
+pub mod middleware;
+pub mod probe;
+pub mod raw;
+pub mod server;
+pub mod stream_timing;
+pub mod trace;
+
+use std::sync::OnceLock;
+
+use config::config;
+use server::TestServer;
+use trace::TraceProxy;
+
+// The server started for this run when the spawn setting is on, by whichever
+// test asks first. It lives as long as the test process.
+fn spawned() -> Option<&'static TestServer> {
+    static SERVER: OnceLock<Option<TestServer>> = OnceLock::new();
+    SERVER
+        .get_or_init(|| {
+            config().spawn.then(|| TestServer::start().unwrap_or_else(|e| panic!("Can't spawn the server: {:#}", e)))
+        })
+        .as_ref()
+}
+
+// Whether the suite's requests go through the trace proxy: to trace them with
+// --verbose-http, or to time streams
+fn proxied() -> bool {
+    config().verbose_http || config().times_streams()
+}
+
+// The proxy that traces this run's HTTP or times its streams, started by
+// whichever test asks first
+fn traced() -> Option<&'static TraceProxy> {
+    static PROXY: OnceLock<Option<TraceProxy>> = OnceLock::new();
+    PROXY
+        .get_or_init(|| {
+            proxied().then(|| {
+                let root = config().verbose_http.then_some(config().trace_dir.as_path());
+                TraceProxy::start(&server_url(), root, config().times_streams(), vec![api_key()])
+                    .unwrap_or_else(|e| panic!("Can't start the trace proxy: {:#}", e))
+            })
+        })
+        .as_ref()
+}
+
+// Base URL of the server under test, without the /v1 suffix
+pub fn base_url() -> String {
+    match traced() {
+        Some(proxy) => proxy.url().to_string(),
+        None => server_url(),
+    }
+}
+
+// The server's own URL, even with --verbose-http, for tests of the connection
+// itself (TLS, HTTP/2, WebSockets) that a proxy would get in the way of
+pub fn server_url() -> String {
+    match spawned() {
+        Some(server) => server.url().to_string(),
+        None => config().url.clone(),
+    }
+}
+
+// API key used to authenticate against the server under test
+pub fn api_key() -> String {
+    match spawned() {
+        Some(server) => server.api_key().to_string(),
+        None => config().api_key.clone(),
+    }
+}
+
+// PEM bundle to trust for an https:// server, such as one with a self-signed certificate
+pub fn ca_cert() -> Option<String> {
+    config().ca_cert.as_ref().map(|path| path.display().to_string())
+}
+
+// HTTP client builder that trusts the configured CA bundle and applies the timeout, for tests that need their own settings
+pub fn http_client_builder() -> reqwest::ClientBuilder {
+    let mut builder = reqwest::Client::builder();
+    if let Some(path) = ca_cert() {
+        let pem = std::fs::read(&path).unwrap_or_else(|e| panic!("Can't read TEENYTINY_CA_CERT {}: {}", path, e));
+        for certificate in reqwest::Certificate::from_pem_bundle(&pem).expect("TEENYTINY_CA_CERT is not a PEM bundle") {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+    if let Some(timeout) = config().timeout {
+        builder = builder.timeout(timeout);
+    }
+    // Tells the trace proxy which test is asking
+    if let Some(test) = trace::current_test().filter(|_| proxied()) {
+        if let Ok(value) = reqwest::header::HeaderValue::from_str(&test) {
+            builder = builder.default_headers([(reqwest::header::HeaderName::from_static(trace::TEST_HEADER), value)].into_iter().collect());
+        }
+    }
+    builder
+}
+
+// HTTP client for raw requests to the server under test
+pub fn http_client() -> reqwest::Client {
+    http_client_builder().build().unwrap()
+}
 
 // Helper function to setup client - used by tests
 pub fn setup_client() -> Client<OpenAIConfig> {
-    let base_url = env::var("TEENYTINY_URL").unwrap_or_else(|_| "http://localhost:8080".to_string());
-    let api_key = env::var("TEENYTINY_API_KEY").unwrap_or_else(|_| "testkey".to_string());
-
     let config = OpenAIConfig::new()
-        .with_api_key(api_key)
-        .with_api_base(format!("{}/v1", base_url));
+        .with_api_key(api_key())
+        .with_api_base(format!("{}/v1", base_url()));
 
-    Client::with_config(config)
+    Client::with_config(config).with_http_client(http_client())
 }
 
 // Test modules - these will be discovered by cargo test
 #[cfg(test)]
 mod tests {
-    use async_openai::types::{
-        ChatCompletionRequestMessage, ChatCompletionRequestSystemMessageArgs,
-        ChatCompletionRequestUserMessageArgs,
-    };
+    // Message builders, shared with the fixtures module
+    pub use crate::fixtures::{system_message, user_message};
+
+    // Helper function to POST a raw JSON body to any path, for requests async-openai can't build
+    pub async fn post_json(path: &str, body: serde_json::Value) -> (reqwest::StatusCode, serde_json::Value) {
+        let response = crate::raw::post(path, body.to_string()).await;
+        (response.status, response.json())
+    }
 
-    // Helper function to create user message
-    pub fn user_message(content: &str) -> ChatCompletionRequestMessage {
-        ChatCompletionRequestUserMessageArgs::default()
-            .content(content)
-            .build()
+    // Helper function to mint a fresh API key, for tests that need their own rate limit or usage budget
+    pub async fn new_api_key() -> String {
+        let response: serde_json::Value = crate::http_client()
+            .post(format!("{}/site/new-key", crate::base_url()))
+            .send()
+            .await
             .unwrap()
-            .into()
+            .json()
+            .await
+            .unwrap();
+
+        response["key"].as_str().expect("No key in response").to_string()
+    }
+
+    // Helper function to build an async-openai client that authenticates with a key of the test's own
+    pub fn client_for(key: &str) -> async_openai::Client<async_openai::config::OpenAIConfig> {
+        async_openai::Client::with_config(
+            async_openai::config::OpenAIConfig::new()
+                .with_api_key(key)
+                .with_api_base(format!("{}/v1", crate::base_url())),
+        )
+        .with_http_client(crate::http_client())
+    }
+
+    // Helper function to call the admin API with the configured key, at a path under /admin
+    pub async fn admin(
+        method: reqwest::Method,
+        path: &str,
+        body: Option<serde_json::Value>,
+    ) -> (reqwest::StatusCode, serde_json::Value) {
+        admin_as(&crate::api_key(), method, path, body).await
+    }
+
+    // Helper function to call the admin API with a given key, at a path under /admin
+    pub async fn admin_as(
+        key: &str,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<serde_json::Value>,
+    ) -> (reqwest::StatusCode, serde_json::Value) {
+        let mut request = crate::http_client()
+            .request(method, format!("{}/admin{}", crate::base_url(), path))
+            .bearer_auth(key);
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+        let response = crate::raw::send(request).await;
+        (response.status, response.json())
+    }
+
+    // Helper function to start a server of the test's own from this checkout, with
+    // extra flags, for tests that change server-wide settings or need flags the
+    // shared server may not have. When it can't be started the test is reported
+    // as skipped, with the reason, and None is returned.
+    pub async fn own_server(args: &[&str]) -> Option<crate::server::TestServer> {
+        own_server_with_env(args, &[]).await
+    }
+
+    // As own_server, also setting environment variables for the server
+    pub async fn own_server_with_env(args: &[&str], env: &[(&str, &str)]) -> Option<crate::server::TestServer> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        let env: Vec<(String, String)> = env.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect();
+        let started = tokio::task::spawn_blocking(move || {
+            crate::server::TestServer::start_with_env(
+                &args.iter().map(String::as_str).collect::<Vec<_>>(),
+                &env.iter().map(|(name, value)| (name.as_str(), value.as_str())).collect::<Vec<_>>(),
+            )
+        })
+        .await
+        .unwrap();
+        match started {
+            Ok(server) => Some(server),
+            Err(error) => {
+                skip(&format!("can't start a server of its own: {:#}", error));
+                None
+            }
+        }
+    }
+
+    // Reports the running test as skipped, for a test that finds the server
+    // under test can't exercise what it checks
+    pub fn skip(reason: &str) {
+        crate::harness::skip(&crate::trace::current_test().unwrap_or_default(), reason);
     }
 
-    // Helper function to create system message
-    pub fn system_message(content: &str) -> ChatCompletionRequestMessage {
-        ChatCompletionRequestSystemMessageArgs::default()
-            .content(content)
-            .build()
+    // Helper function to fetch the most recent request the server logged for a key
+    pub async fn last_captured_request(key: &str) -> serde_json::Value {
+        let response: serde_json::Value = crate::http_client()
+            .get(format!("{}/admin/requests?limit=1", crate::base_url()))
+            .bearer_auth(key)
+            .send()
+            .await
             .unwrap()
-            .into()
+            .json()
+            .await
+            .unwrap();
+
+        response["data"][0].clone()
+    }
+
+    // Helper function to assert a request parameter reached the server as expected
+    pub async fn assert_forwarded(key: &str, param: &str, expected: serde_json::Value) {
+        let captured = last_captured_request(key).await;
+        assert_eq!(
+            captured["request_body"][param], expected,
+            "Unexpected {} in captured request: {}", param, captured
+        );
     }
 
-    mod basic;
-    mod streaming;
-    mod auth_errors;
-    mod options;
+    // Helper function to POST a raw JSON body to chat completions
+    pub async fn post_chat_completion(body: serde_json::Value) -> (reqwest::StatusCode, serde_json::Value) {
+        post_json("/v1/chat/completions", body).await
+    }
+
+    macro_rules! suite_modules {
+        ($($suite:ident: $capability:ident),* $(,)?) => {
+            $(mod $suite;)*
+        };
+    }
+    suites!(suite_modules);
 }
\ No newline at end of file
@@ -0,0 +1,102 @@
+// Lists the suite's tests as the other commands find them: from `cargo test`
+// itself, so the binary never keeps a list of its own that could drift from
+// the tests that exist. Also checks every test belongs to a listed suite and
+// every listed suite has tests.
+
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Context, Result};
+use teenytiny_rust_openai_integration::capabilities::{self, SUITES};
+
+use crate::matrix::cargo_test;
+
+pub const USAGE: &str = "\
+Usage: integration_test list [--filter <text>]
+
+Lists the tests cargo test runs, with the capability each verifies.";
+
+// Reads "tests::basic::test_completion: test" lines from `cargo test -- --list`,
+// keeping the suites' tests and leaving out the harness's own unit tests
+pub fn parse_list(output: &str) -> Vec<String> {
+    output.lines()
+        .filter_map(|line| line.strip_suffix(": test")?.strip_prefix("tests::"))
+        .map(String::from)
+        .collect()
+}
+
+/// Tests outside any listed suite, and listed suites without tests
+pub fn mismatches(tests: &[String]) -> (Vec<&str>, Vec<&'static str>) {
+    let unlisted = tests.iter()
+        .map(String::as_str)
+        .filter(|test| capabilities::capability_of(test).is_none())
+        .collect();
+    let empty = SUITES.iter()
+        .map(|(suite, _)| *suite)
+        .filter(|suite| !tests.iter().any(|test| test.split("::").next() == Some(*suite)))
+        .collect();
+    (unlisted, empty)
+}
+
+/// The suite's tests that `cargo test` runs with this filter
+pub async fn list_tests(filter: Option<&str>) -> Result<Vec<String>> {
+    let output = cargo_test(filter).arg("--list").output().await.context("Could not run cargo test")?;
+    if !output.status.success() {
+        bail!("cargo test --list failed:\n{}", String::from_utf8_lossy(&output.stderr));
+    }
+    Ok(parse_list(&String::from_utf8_lossy(&output.stdout)))
+}
+
+pub async fn run(args: &[String]) -> Result<()> {
+    let filter = match args {
+        [] => None,
+        [flag, text] if flag == "--filter" => Some(text.as_str()),
+        _ => bail!("Unknown options {}", args.join(" ")),
+    };
+    let tests = list_tests(filter).await?;
+
+    let mut per_capability: BTreeMap<&str, usize> = BTreeMap::new();
+    let width = tests.iter().map(String::len).max().unwrap_or(0);
+    for test in &tests {
+        let capability = capabilities::capability_of(test).map_or("-", |capability| capability.as_str());
+        *per_capability.entry(capability).or_default() += 1;
+        println!("{:<width$}  {}", test, capability, width = width);
+    }
+    println!("\n{} tests: {}", tests.len(),
+        per_capability.iter().map(|(capability, n)| format!("{} {}", n, capability)).collect::<Vec<_>>().join(", "));
+
+    let (unlisted, empty) = mismatches(&tests);
+    if filter.is_none() && !empty.is_empty() {
+        bail!("Suites with no tests: {}", empty.join(", "));
+    }
+    if !unlisted.is_empty() {
+        bail!("Tests outside the suites list in src/lib.rs: {}", unlisted.join(", "));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_list() {
+        let output = "\
+tests::basic::test_basic_completion: test
+tests::streaming::test_basic_streaming_completion: test
+config::tests::test_dotenv_lines: test
+
+3 tests, 0 benchmarks";
+
+        assert_eq!(parse_list(output), ["basic::test_basic_completion", "streaming::test_basic_streaming_completion"]);
+    }
+
+    #[test]
+    fn test_mismatches() {
+        let mut tests: Vec<String> = SUITES.iter().map(|(suite, _)| format!("{}::test_a", suite)).collect();
+        assert_eq!(mismatches(&tests), (vec![], vec![]));
+
+        tests.retain(|test| !test.starts_with("basic::"));
+        tests.push("not_a_suite::test_a".to_string());
+        assert_eq!(mismatches(&tests), (vec!["not_a_suite::test_a"], vec!["basic"]));
+    }
+}
@@ -1,18 +1,78 @@
-use async_openai::{config::OpenAIConfig, Client};
 use std::env;
+use std::process::exit;
 
-// Helper function to setup client - used by tests
-pub fn setup_client() -> Client<OpenAIConfig> {
-    let base_url = env::var("TEENYTINY_URL").unwrap_or_else(|_| "http://localhost:8080".to_string());
-    let api_key = env::var("TEENYTINY_API_KEY").unwrap_or_else(|_| "testkey".to_string());
+use teenytiny_rust_openai_integration::config::{self, HarnessConfig};
 
-    let config = OpenAIConfig::new()
-        .with_api_key(api_key)
-        .with_api_base(format!("{}/v1", base_url));
+mod bench;
+mod conformance;
+mod flaky;
+mod list;
+mod matrix;
+mod progress;
+mod soak;
 
-    Client::with_config(config)
-}
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let loaded = config::parse_flags(&args).and_then(|flags| Ok((HarnessConfig::load(&flags)?, flags)));
+    let (settings, flags) = match loaded {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("Error: {:#}\n\n{}", e, config::USAGE);
+            exit(2);
+        }
+    };
+    if flags.print_config {
+        print!("{}", settings.to_toml());
+        return;
+    }
+    config::init(settings);
+    progress::init(progress::Style::detect(flags.no_color, flags.quiet));
 
-fn main() {
-    println!("This is now a test-only crate. Run with 'cargo test'.");
-}
\ No newline at end of file
+    let args = flags.rest;
+    let result = match args.first().map(String::as_str) {
+        Some("bench") => bench::run(&args[1..]).await,
+        Some("--soak") => soak::run(&args[1..]).await,
+        Some("matrix") => matrix::run(&args[1..]).await,
+        Some("conformance") => conformance::run(&args[1..]).await,
+        Some("flaky") => flaky::run(&args[1..]).await,
+        Some("list") => list::run(&args[1..]).await,
+        Some("help" | "--help" | "-h") => {
+            println!(
+                "{}\n\n{}\n\n{}\n\n{}\n\n{}\n\n{}\n\n{}",
+                bench::USAGE,
+                soak::USAGE,
+                matrix::USAGE,
+                conformance::USAGE,
+                flaky::USAGE,
+                list::USAGE,
+                config::USAGE
+            );
+            Ok(())
+        }
+        Some(command) => {
+            eprintln!(
+                "Unknown command '{}'\n\n{}\n\n{}\n\n{}\n\n{}\n\n{}\n\n{}\n\n{}",
+                command,
+                bench::USAGE,
+                soak::USAGE,
+                matrix::USAGE,
+                conformance::USAGE,
+                flaky::USAGE,
+                list::USAGE,
+                config::USAGE
+            );
+            exit(2);
+        }
+        None => {
+            println!("Run the tests with 'cargo test', load test a server with 'cargo run --release -- bench',");
+            println!("soak it with 'cargo run --release -- --soak 30m', or compare servers with");
+            println!("'cargo run -- matrix --target a=<url> --target b=<url>'. See 'help' for the settings.");
+            Ok(())
+        }
+    };
+    if let Err(e) = result {
+        eprintln!("Error: {:#}", e);
+        exit(1);
+    }
+}
@@ -0,0 +1,413 @@
+// Compatibility matrix: runs the whole suite once per target server and
+// reports each test's outcome side by side, so two teenytiny versions, or
+// teenytiny and a real provider, can be compared test by test.
+//
+// Each run is a separate `cargo test` with TEENYTINY_URL and TEENYTINY_API_KEY
+// pointing at its target, one target at a time, since some tests change
+// server-wide settings and expect to be the only ones doing so.
+
+use std::collections::BTreeMap;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use teenytiny_rust_openai_integration::api_key;
+use teenytiny_rust_openai_integration::config::config;
+use teenytiny_rust_openai_integration::harness::RESULTS_VAR;
+use teenytiny_rust_openai_integration::probe::wait_for_server;
+use teenytiny_rust_openai_integration::stream_timing::StreamTiming;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::process::Command;
+
+use crate::list;
+use crate::progress::{self, Progress};
+
+pub const USAGE: &str = "\
+Usage: integration_test matrix --target <name>=<url> [--target <name>=<url> ...] [options]
+
+Options:
+  --target <name>=<url>  A server to run the suite against; repeat for each
+  --key <name>=<key>     API key for one target (default the harness key)
+  --filter <text>        Only run tests whose names contain this
+  --json <file>          Also write the matrix as JSON, - for stdout";
+
+#[derive(Debug, PartialEq)]
+pub struct Target {
+    pub name: String,
+    pub url: String,
+    pub key: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Default)]
+pub struct Options {
+    pub targets: Vec<Target>,
+    pub filter: Option<String>,
+    pub json: Option<String>,
+}
+
+fn split_pair<'a>(flag: &str, value: &'a str) -> Result<(&'a str, &'a str)> {
+    match value.split_once('=') {
+        Some((name, rest)) if !name.is_empty() && !rest.is_empty() => Ok((name, rest)),
+        _ => bail!("Invalid {} '{}': expected <name>=<value>", flag, value),
+    }
+}
+
+pub fn parse_options(args: &[String]) -> Result<Options> {
+    let mut options = Options::default();
+    let mut keys = Vec::new();
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let value = args.next().with_context(|| format!("Missing value for {}", flag))?;
+        match flag.as_str() {
+            "--target" => {
+                let (name, url) = split_pair(flag, value)?;
+                if !url.starts_with("http://") && !url.starts_with("https://") {
+                    bail!("Invalid --target '{}': expected an http:// or https:// URL", value);
+                }
+                if options.targets.iter().any(|target| target.name == name) {
+                    bail!("Target '{}' is given twice", name);
+                }
+                let url = url.trim_end_matches('/').to_string();
+                options.targets.push(Target { name: name.to_string(), url, key: None });
+            }
+            "--key" => {
+                let (name, key) = split_pair(flag, value)?;
+                keys.push((name.to_string(), key.to_string()));
+            }
+            "--filter" => options.filter = Some(value.clone()),
+            "--json" => options.json = Some(value.clone()),
+            _ => bail!("Unknown option {}", flag),
+        }
+    }
+    for (name, key) in keys {
+        match options.targets.iter_mut().find(|target| target.name == name) {
+            Some(target) => target.key = Some(key),
+            None => bail!("--key names '{}', which isn't a target", name),
+        }
+    }
+    if options.targets.is_empty() {
+        bail!("Give at least one --target");
+    }
+    Ok(options)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Passed,
+    Failed,
+    Ignored,
+    // The harness skipped it, say because the server lacks its capability
+    Skipped,
+    // The test didn't run against this target, say because the build failed
+    Missing,
+}
+
+impl Outcome {
+    fn as_str(self) -> &'static str {
+        match self {
+            Outcome::Passed => "ok",
+            Outcome::Failed => "FAILED",
+            Outcome::Ignored => "ignored",
+            Outcome::Skipped => "skipped",
+            Outcome::Missing => "-",
+        }
+    }
+}
+
+// Reads a "test tests::basic::test_completion ... ok" line from libtest's output
+pub fn parse_result(line: &str) -> Option<(String, Outcome)> {
+    let (name, result) = line.strip_prefix("test ")?.split_once(" ... ")?;
+    let outcome = match result.trim() {
+        "ok" => Outcome::Passed,
+        "FAILED" => Outcome::Failed,
+        result if result.starts_with("ignored") => Outcome::Ignored,
+        _ => return None,
+    };
+    let name = name.strip_prefix("tests::").unwrap_or(name);
+    Some((name.to_string(), outcome))
+}
+
+#[cfg(test)]
+pub fn parse_results(output: &str) -> BTreeMap<String, Outcome> {
+    output.lines().filter_map(parse_result).collect()
+}
+
+pub struct Run {
+    pub results: BTreeMap<String, Outcome>,
+    // Each test's time, for the tests that ran
+    pub durations: BTreeMap<String, Duration>,
+    // Each stream the tests made, with stream timing on
+    pub streams: Vec<(String, StreamTiming)>,
+    pub elapsed: Duration,
+}
+
+// `cargo test` over the suite, as every command that runs or lists the tests
+// runs it; arguments added after this go to libtest
+pub fn cargo_test(filter: Option<&str>) -> Command {
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let mut command = Command::new(cargo);
+    command
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(["test", "--lib", "--"])
+        .args(filter);
+    command
+}
+
+pub async fn run_target(target: &Target, filter: Option<&str>) -> Result<Run> {
+    // Before building the suite, which takes longer than finding the server is down
+    wait_for_server(&target.url, config().ready_timeout).await?;
+
+    let mut command = cargo_test(filter);
+    command
+        .env("TEENYTINY_URL", &target.url)
+        .env("TEENYTINY_API_KEY", target.key.clone().unwrap_or_else(api_key))
+        .env("TEENYTINY_CONCURRENCY", config().concurrency.to_string())
+        // A server spawned by this run is the target; the suite mustn't start its own
+        .env("TEENYTINY_SPAWN", "0");
+    // Settings given as flags to this run carry through to the suite
+    if let Some(ca_cert) = &config().ca_cert {
+        command.env("TEENYTINY_CA_CERT", ca_cert);
+    }
+    if let Some(timeout) = config().timeout {
+        command.env("TEENYTINY_TIMEOUT", timeout.as_secs_f64().to_string());
+    }
+    if config().long {
+        command.env("TEENYTINY_LONG", "1");
+    }
+    if let Some(test_timeout) = config().test_timeout {
+        command.env("TEENYTINY_TEST_TIMEOUT", test_timeout.as_secs_f64().to_string());
+    }
+    // Each target's traces in a directory of its own
+    if config().verbose_http {
+        command.env("TEENYTINY_VERBOSE_HTTP", "1").env("TEENYTINY_TRACE_DIR", config().trace_dir.join(&target.name));
+    }
+    if !config().skip_tags.is_empty() {
+        command.env("TEENYTINY_SKIP_TAGS", config().skip_tags.join(","));
+    }
+    if config().times_streams() {
+        command.env("TEENYTINY_STREAM_TIMINGS", "1");
+    }
+    if let Some(ttfb_max) = config().ttfb_max {
+        command.env("TEENYTINY_TTFB_MAX_MS", (ttfb_max.as_secs_f64() * 1000.0).to_string());
+    }
+    if let Some(chunk_gap_max) = config().chunk_gap_max {
+        command.env("TEENYTINY_CHUNK_GAP_MAX_MS", (chunk_gap_max.as_secs_f64() * 1000.0).to_string());
+    }
+
+    // Results are drawn as libtest reports them, with the times, skips and
+    // stream timings the suite writes to a file alongside
+    let tests = list::list_tests(filter).await?;
+    let results_file = std::env::temp_dir().join(format!("teenytiny-results-{}-{}.tsv", std::process::id(), target.name));
+    let _ = std::fs::remove_file(&results_file);
+    command.env(RESULTS_VAR, &results_file).stdout(Stdio::piped()).stderr(Stdio::piped());
+    let mut progress = Progress::new(&tests, &results_file, progress::style());
+
+    let start = Instant::now();
+    let mut child = command.spawn().context("Could not run cargo test")?;
+    let mut stderr = child.stderr.take().expect("stderr is piped");
+    let errors = tokio::spawn(async move {
+        let mut text = String::new();
+        let _ = stderr.read_to_string(&mut text).await;
+        text
+    });
+    let mut results = BTreeMap::new();
+    let mut lines = BufReader::new(child.stdout.take().expect("stdout is piped")).lines();
+    while let Some(line) = lines.next_line().await? {
+        if let Some((name, outcome)) = parse_result(&line) {
+            let outcome = match outcome {
+                Outcome::Passed if progress.skipped(&name) => Outcome::Skipped,
+                outcome => outcome,
+            };
+            progress.record(&name, outcome);
+            results.insert(name, outcome);
+        }
+    }
+    let status = child.wait().await?;
+    if results.is_empty() && !status.success() {
+        let _ = std::fs::remove_file(&results_file);
+        bail!("cargo test failed before running any tests:\n{}", errors.await.unwrap_or_default());
+    }
+    progress.finish();
+    let durations = progress.durations();
+    let streams = progress.streams();
+    let _ = std::fs::remove_file(&results_file);
+    Ok(Run { results, durations, streams, elapsed: start.elapsed() })
+}
+
+// Every test seen on any target, with its outcome on each
+pub fn matrix(targets: &[Target], runs: &[Run]) -> Value {
+    let mut tests: BTreeMap<&str, Vec<Outcome>> = BTreeMap::new();
+    for (i, run) in runs.iter().enumerate() {
+        for (name, outcome) in &run.results {
+            tests.entry(name).or_insert_with(|| vec![Outcome::Missing; runs.len()])[i] = *outcome;
+        }
+    }
+
+    let summary: Vec<Value> = targets.iter().zip(runs)
+        .map(|(target, run)| {
+            let count = |outcome| run.results.values().filter(|o| **o == outcome).count();
+            json!({
+                "name": target.name,
+                "url": target.url,
+                "passed": count(Outcome::Passed),
+                "failed": count(Outcome::Failed),
+                "ignored": count(Outcome::Ignored),
+                "skipped": count(Outcome::Skipped),
+                "seconds": run.elapsed.as_secs_f64(),
+                "stream_latency": progress::stream_latency(&run.streams),
+            })
+        })
+        .collect();
+    let rows: BTreeMap<&str, Value> = tests.iter()
+        .map(|(name, outcomes)| {
+            let by_target: BTreeMap<&str, &str> = targets.iter().zip(outcomes)
+                .map(|(target, outcome)| (target.name.as_str(), outcome.as_str()))
+                .collect();
+            (*name, json!(by_target))
+        })
+        .collect();
+
+    json!({"targets": summary, "tests": rows})
+}
+
+// Tests whose outcome differs between targets, which is what a comparison is for
+fn differing(report: &Value) -> Vec<(&String, &Value)> {
+    report["tests"].as_object().into_iter().flatten()
+        .filter(|(_, outcomes)| {
+            let mut values = outcomes.as_object().into_iter().flatten().map(|(_, v)| v);
+            let first = values.next();
+            values.any(|v| Some(v) != first)
+        })
+        .collect()
+}
+
+fn print_matrix(targets: &[Target], report: &Value) {
+    println!("{:<12} {:>7} {:>7} {:>8} {:>8} {:>9}  url", "target", "passed", "failed", "ignored", "skipped", "seconds");
+    for row in report["targets"].as_array().into_iter().flatten() {
+        println!(
+            "{:<12} {:>7} {:>7} {:>8} {:>8} {:>9.1}  {}",
+            row["name"].as_str().unwrap_or_default(),
+            row["passed"],
+            row["failed"],
+            row["ignored"],
+            row["skipped"],
+            row["seconds"].as_f64().unwrap_or_default(),
+            row["url"].as_str().unwrap_or_default(),
+        );
+    }
+
+    if targets.len() < 2 {
+        return;
+    }
+    let differing = differing(report);
+    if differing.is_empty() {
+        println!("\nEvery test had the same outcome on every target.");
+        return;
+    }
+    println!("\n{} tests differ between targets:\n", differing.len());
+    let width = differing.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    let names: Vec<String> = targets.iter().map(|target| format!("{:<10}", target.name)).collect();
+    println!("{:<width$}  {}", "test", names.join(" "), width = width);
+    for (name, outcomes) in differing {
+        let cells: Vec<String> = targets.iter()
+            .map(|target| format!("{:<10}", outcomes[&target.name].as_str().unwrap_or("-")))
+            .collect();
+        println!("{:<width$}  {}", name, cells.join(" "), width = width);
+    }
+}
+
+pub async fn run(args: &[String]) -> Result<()> {
+    let options = parse_options(args)?;
+
+    let mut runs = Vec::new();
+    for target in &options.targets {
+        println!("Running the suite against {} ({})...", target.name, target.url);
+        runs.push(run_target(target, options.filter.as_deref()).await
+            .with_context(|| format!("Target {}", target.name))?);
+    }
+    println!();
+
+    let report = matrix(&options.targets, &runs);
+    print_matrix(&options.targets, &report);
+    match options.json.as_deref() {
+        Some("-") => println!("\n{}", serde_json::to_string_pretty(&report)?),
+        Some(path) => std::fs::write(path, serde_json::to_string_pretty(&report)?)
+            .with_context(|| format!("Could not write {}", path))?,
+        None => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    fn target(name: &str) -> Target {
+        Target { name: name.to_string(), url: format!("http://{}", name), key: None }
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let options = parse_options(&args(
+            "--target local=http://localhost:8080/ --target openai=https://api.openai.com --key openai=sk-1 --filter basic",
+        )).unwrap();
+        assert_eq!(options.targets, vec![
+            Target { name: "local".to_string(), url: "http://localhost:8080".to_string(), key: None },
+            Target { name: "openai".to_string(), url: "https://api.openai.com".to_string(), key: Some("sk-1".to_string()) },
+        ]);
+        assert_eq!(options.filter.as_deref(), Some("basic"));
+
+        assert!(parse_options(&[]).is_err());
+        assert!(parse_options(&args("--target local")).is_err());
+        assert!(parse_options(&args("--target local=localhost:8080")).is_err());
+        assert!(parse_options(&args("--target a=http://a --target a=http://b")).is_err());
+        assert!(parse_options(&args("--target a=http://a --key b=key")).is_err());
+    }
+
+    #[test]
+    fn test_parse_results() {
+        let output = "\
+running 3 tests
+test tests::basic::test_completion ... ok
+test tests::proxy::test_upstream ... ignored, needs TEENYTINY_UPSTREAM
+test tests::flaky::test_reset ... FAILED
+test result: FAILED. 1 passed; 1 failed; 1 ignored";
+
+        let results = parse_results(output);
+        assert_eq!(results.len(), 3);
+        assert_eq!(results["basic::test_completion"], Outcome::Passed);
+        assert_eq!(results["proxy::test_upstream"], Outcome::Ignored);
+        assert_eq!(results["flaky::test_reset"], Outcome::Failed);
+    }
+
+    #[test]
+    fn test_matrix_marks_missing_tests_and_differences() {
+        let targets = [target("old"), target("new")];
+        let runs = [
+            Run {
+                results: parse_results("test a ... ok\ntest b ... ok"),
+                durations: BTreeMap::new(),
+                streams: Vec::new(),
+                elapsed: Duration::from_secs(1),
+            },
+            Run {
+                results: parse_results("test a ... ok\ntest b ... FAILED\ntest c ... ok"),
+                durations: BTreeMap::new(),
+                streams: Vec::new(),
+                elapsed: Duration::from_secs(2),
+            },
+        ];
+
+        let report = matrix(&targets, &runs);
+        assert_eq!(report["targets"][1]["failed"], 1);
+        assert_eq!(report["tests"]["c"], json!({"old": "-", "new": "ok"}));
+
+        let names: Vec<&String> = differing(&report).into_iter().map(|(name, _)| name).collect();
+        assert_eq!(names, ["b", "c"]);
+    }
+}
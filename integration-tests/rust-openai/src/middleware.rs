@@ -0,0 +1,174 @@
+// Request/response middleware for raw HTTP tests. A MiddlewareClient sends
+// reqwest requests through a stack of layers, each of which may rewrite the
+// outgoing request (drop a header, corrupt the body) and then sees the
+// response along with how long it took. Negative tests describe what they
+// break as a layer rather than hand-building each broken request.
+//
+// async-openai owns its requests, so tests through it can't use these layers.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use reqwest::header::{HeaderName, HeaderValue};
+use reqwest::{Method, Request, RequestBuilder, Response, StatusCode};
+
+/// What a layer sees once a response arrives
+#[derive(Debug, Clone)]
+pub struct Exchange {
+    pub method: Method,
+    pub path: String,
+    pub status: StatusCode,
+    /// Time until the response headers arrived, so excludes reading a streamed body
+    pub elapsed: Duration,
+}
+
+pub trait Middleware: Send + Sync {
+    fn on_request(&self, _request: &mut Request) {}
+    fn on_response(&self, _exchange: &Exchange) {}
+}
+
+/// Sends requests to the server under test through a stack of middleware,
+/// outermost first: requests pass through the layers in the order they were
+/// added, and responses come back through them in reverse.
+#[derive(Clone)]
+pub struct MiddlewareClient {
+    http: reqwest::Client,
+    key: Option<String>,
+    layers: Vec<Arc<dyn Middleware>>,
+}
+
+impl Default for MiddlewareClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MiddlewareClient {
+    pub fn new() -> Self {
+        MiddlewareClient { http: crate::http_client(), key: Some(crate::api_key()), layers: Vec::new() }
+    }
+
+    /// Authenticates as this key instead of TEENYTINY_API_KEY
+    pub fn with_key(mut self, key: &str) -> Self {
+        self.key = Some(key.to_string());
+        self
+    }
+
+    pub fn with(mut self, layer: impl Middleware + 'static) -> Self {
+        self.layers.push(Arc::new(layer));
+        self
+    }
+
+    /// Adds a layer the caller keeps a handle to, such as a Timings to read afterwards
+    pub fn with_shared(mut self, layer: Arc<dyn Middleware>) -> Self {
+        self.layers.push(layer);
+        self
+    }
+
+    /// A request to a path on the server under test, authenticated with the client's key
+    pub fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let request = self.http.request(method, format!("{}{}", crate::base_url(), path));
+        match &self.key {
+            Some(key) => request.bearer_auth(key),
+            None => request,
+        }
+    }
+
+    pub fn get(&self, path: &str) -> RequestBuilder {
+        self.request(Method::GET, path)
+    }
+
+    pub fn post(&self, path: &str) -> RequestBuilder {
+        self.request(Method::POST, path)
+    }
+
+    pub async fn send(&self, request: RequestBuilder) -> reqwest::Result<Response> {
+        let mut request = request.build()?;
+        for layer in &self.layers {
+            layer.on_request(&mut request);
+        }
+
+        let method = request.method().clone();
+        let path = request.url().path().to_string();
+        let start = Instant::now();
+        let response = self.http.execute(request).await?;
+
+        let exchange = Exchange { method, path, status: response.status(), elapsed: start.elapsed() };
+        for layer in self.layers.iter().rev() {
+            layer.on_response(&exchange);
+        }
+        Ok(response)
+    }
+}
+
+/// Prints each request and its outcome, for following a failing test
+pub struct Logger;
+
+impl Middleware for Logger {
+    fn on_request(&self, request: &mut Request) {
+        println!("--> {} {}", request.method(), request.url().path());
+    }
+
+    fn on_response(&self, exchange: &Exchange) {
+        println!("<-- {} {} {} in {:?}", exchange.status.as_u16(), exchange.method, exchange.path, exchange.elapsed);
+    }
+}
+
+/// Removes a header, including ones reqwest added such as Authorization or Content-Type
+pub struct DropHeader(pub &'static str);
+
+impl Middleware for DropHeader {
+    fn on_request(&self, request: &mut Request) {
+        request.headers_mut().remove(self.0);
+    }
+}
+
+/// Sets a header, replacing any value it already had
+pub struct SetHeader(pub &'static str, pub &'static str);
+
+impl Middleware for SetHeader {
+    fn on_request(&self, request: &mut Request) {
+        request.headers_mut().insert(HeaderName::from_static(self.0), HeaderValue::from_static(self.1));
+    }
+}
+
+/// Ways to break a request body
+pub enum CorruptBody {
+    /// Keep only the first n bytes, as if the client was cut off
+    Truncate(usize),
+    /// Send these bytes instead
+    Replace(&'static [u8]),
+}
+
+impl Middleware for CorruptBody {
+    fn on_request(&self, request: &mut Request) {
+        let body = request.body().and_then(|body| body.as_bytes()).unwrap_or_default().to_vec();
+        let corrupted = match self {
+            CorruptBody::Truncate(n) => body[..body.len().min(*n)].to_vec(),
+            CorruptBody::Replace(bytes) => bytes.to_vec(),
+        };
+        *request.body_mut() = Some(corrupted.into());
+    }
+}
+
+/// Records every exchange, for asserting on status codes and latency afterwards
+#[derive(Default)]
+pub struct Timings {
+    exchanges: Mutex<Vec<Exchange>>,
+}
+
+impl Timings {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    pub fn exchanges(&self) -> Vec<Exchange> {
+        self.exchanges.lock().unwrap().clone()
+    }
+}
+
+impl Middleware for Timings {
+    fn on_response(&self, exchange: &Exchange) {
+        self.exchanges.lock().unwrap().push(exchange.clone());
+    }
+}
@@ -0,0 +1,72 @@
+// Checks the server under test answers before any test runs against it, so a
+// server that's down or still starting fails the run once, naming its URL,
+// instead of every test failing with "connection refused". Polls /healthz with
+// backoff for up to the ready_timeout setting. Then asks what the server
+// serves, so tests of capabilities it lacks skip instead of failing.
+
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Result};
+use serde_json::Value;
+
+use crate::capabilities::Support;
+use crate::http_client_builder;
+
+const FIRST_DELAY: Duration = Duration::from_millis(100);
+const MAX_DELAY: Duration = Duration::from_secs(2);
+const ATTEMPT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Waits until the server at `url` answers `/healthz`, trying once more after `limit`
+pub async fn wait_for_server(url: &str, limit: Duration) -> Result<()> {
+    let client = http_client_builder().build()?;
+    let deadline = Instant::now() + limit;
+    let mut delay = FIRST_DELAY;
+    loop {
+        let error = match client.get(format!("{}/healthz", url)).timeout(ATTEMPT_TIMEOUT).send().await {
+            // Anything but a server error means it's up; real providers answer 404 here
+            Ok(response) if !response.status().is_server_error() => return Ok(()),
+            Ok(response) => format!("/healthz answered {}", response.status()),
+            Err(e) => format!("{:#}", anyhow::Error::from(e)),
+        };
+        let now = Instant::now();
+        if now >= deadline {
+            bail!("Server unreachable at {} after {:?}: {} (TEENYTINY_READY_TIMEOUT)", url, limit, error);
+        }
+        tokio::time::sleep(delay.min(deadline - now)).await;
+        delay = (delay * 2).min(MAX_DELAY);
+    }
+}
+
+/// The capabilities of the server at `url`, from /version, or from /v1/models
+/// for servers without it; every capability if it says neither
+pub async fn server_support(url: &str, api_key: &str) -> Support {
+    let Ok(client) = http_client_builder().build() else {
+        return Support::default();
+    };
+    if let Some(support) = get_json(&client, url, api_key, "/version").await.as_ref().and_then(Support::from_version) {
+        return support;
+    }
+    get_json(&client, url, api_key, "/v1/models").await.as_ref().and_then(Support::from_models).unwrap_or_default()
+}
+
+async fn get_json(client: &reqwest::Client, url: &str, api_key: &str, path: &str) -> Option<Value> {
+    let response = client.get(format!("{}{}", url, path)).bearer_auth(api_key).timeout(ATTEMPT_TIMEOUT).send().await.ok()?;
+    response.error_for_status().ok()?.json().await.ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_unreachable_server_is_named() {
+        // A port nothing listens on once the listener is dropped
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let url = format!("http://127.0.0.1:{}", port);
+
+        let start = Instant::now();
+        let error = wait_for_server(&url, Duration::from_millis(300)).await.unwrap_err().to_string();
+        assert!(error.starts_with(&format!("Server unreachable at {}", url)), "{}", error);
+        assert!(start.elapsed() < Duration::from_secs(3));
+    }
+}
@@ -0,0 +1,461 @@
+// Live output while the binary runs the suite: a status line counting tests
+// as they finish, each suite drawn as a tree of its tests once its last test
+// is done, and a summary at the end. Only the terminal output changes; the
+// --json reports are written from the results as before.
+//
+//   basic  core-chat  9 passed  0.84s
+//   ├─ ✓ test_basic_completion             0.05s
+//   ├─ ✗ test_multi_message_conversation   0.31s
+//   └─ - test_empty_message_handling       ignored
+//
+// With stream timing on, the summary is followed by each suite's streaming
+// latency percentiles, in milliseconds:
+//
+//   suite      streams  ttfb p50  ttfb p95  ttfb p99  gap p95  gap p99  total p95
+//   streaming       14         3         9        12        4        6        118
+//
+// Colors are on when stdout is a terminal, unless NO_COLOR is set or --no-color
+// given. --quiet leaves out the status line and every suite that passed.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::{IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+use teenytiny_rust_openai_integration::capabilities;
+use teenytiny_rust_openai_integration::stream_timing::{self, StreamTiming};
+
+use crate::bench::percentile;
+use crate::matrix::Outcome;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Style {
+    pub color: bool,
+    pub quiet: bool,
+}
+
+impl Style {
+    pub fn detect(no_color: bool, quiet: bool) -> Style {
+        let color = !no_color && std::env::var_os("NO_COLOR").is_none() && std::io::stdout().is_terminal();
+        Style { color, quiet }
+    }
+
+    fn paint(&self, code: &str, text: &str) -> String {
+        match self.color {
+            true => format!("\x1b[{}m{}\x1b[0m", code, text),
+            false => text.to_string(),
+        }
+    }
+}
+
+static STYLE: OnceLock<Style> = OnceLock::new();
+
+/// Sets how runs are shown, from the binary's flags
+pub fn init(style: Style) {
+    let _ = STYLE.set(style);
+}
+
+pub fn style() -> Style {
+    STYLE.get().copied().unwrap_or_default()
+}
+
+fn mark(outcome: Outcome, style: &Style) -> String {
+    match outcome {
+        Outcome::Passed => style.paint("32", "✓"),
+        Outcome::Failed => style.paint("31", "✗"),
+        Outcome::Ignored | Outcome::Skipped | Outcome::Missing => style.paint("33", "-"),
+    }
+}
+
+fn seconds(duration: Duration) -> String {
+    format!("{:.2}s", duration.as_secs_f64())
+}
+
+#[derive(Debug, Clone)]
+pub struct Finished {
+    pub name: String,
+    pub outcome: Outcome,
+    pub duration: Option<Duration>,
+}
+
+/// One suite as a tree of its tests; with quiet, only its failures
+pub fn render_suite(suite: &str, tests: &[Finished], style: &Style) -> String {
+    let count = |outcome| tests.iter().filter(|test| test.outcome == outcome).count();
+    let total: Duration = tests.iter().filter_map(|test| test.duration).sum();
+    let capability = capabilities::capability_of(&format!("{}::", suite)).map_or("", |capability| capability.as_str());
+    let failed = count(Outcome::Failed);
+    let mut counts = vec![format!("{} passed", count(Outcome::Passed))];
+    if failed > 0 {
+        counts.push(style.paint("31", &format!("{} failed", failed)));
+    }
+    if count(Outcome::Ignored) > 0 {
+        counts.push(format!("{} ignored", count(Outcome::Ignored)));
+    }
+    if count(Outcome::Skipped) > 0 {
+        counts.push(format!("{} skipped", count(Outcome::Skipped)));
+    }
+
+    let mut text = format!(
+        "{}  {}  {}  {}\n",
+        style.paint("1", suite),
+        style.paint("2", capability),
+        counts.join(", "),
+        style.paint("2", &seconds(total))
+    );
+    let shown: Vec<&Finished> = tests.iter().filter(|test| !style.quiet || test.outcome == Outcome::Failed).collect();
+    let width = shown.iter().map(|test| short_name(&test.name).chars().count()).max().unwrap_or(0);
+    for (i, test) in shown.iter().enumerate() {
+        let branch = if i + 1 == shown.len() { "└─" } else { "├─" };
+        let time = match (test.outcome, test.duration) {
+            (Outcome::Ignored, _) => "ignored".to_string(),
+            (Outcome::Skipped, _) => "skipped".to_string(),
+            (_, Some(duration)) => seconds(duration),
+            (_, None) => String::new(),
+        };
+        text.push_str(&format!(
+            "{} {} {:<width$}  {}\n",
+            branch,
+            mark(test.outcome, style),
+            short_name(&test.name),
+            style.paint("2", &time),
+            width = width
+        ));
+    }
+    text
+}
+
+// A test's name within its suite
+fn short_name(test: &str) -> &str {
+    test.split_once("::").map_or(test, |(_, name)| name)
+}
+
+fn suite_of(test: &str) -> &str {
+    test.split("::").next().unwrap_or(test)
+}
+
+/// The footer once every test has finished
+pub fn render_summary(counts: &BTreeMap<&str, usize>, elapsed: Duration, style: &Style) -> String {
+    let get = |name| counts.get(name).copied().unwrap_or(0);
+    let total = get("passed") + get("failed") + get("ignored") + get("skipped");
+    let failed = match get("failed") {
+        0 => "0 failed".to_string(),
+        n => style.paint("31", &format!("{} failed", n)),
+    };
+    let skipped = match get("skipped") {
+        0 => String::new(),
+        n => format!(", {} skipped", n),
+    };
+    format!(
+        "{} tests: {}, {}, {} ignored{} in {}",
+        total,
+        style.paint("32", &format!("{} passed", get("passed"))),
+        failed,
+        get("ignored"),
+        skipped,
+        seconds(elapsed)
+    )
+}
+
+/// Each suite's streaming latency percentiles in milliseconds, from every stream its tests made
+pub fn stream_latency(streams: &[(String, StreamTiming)]) -> BTreeMap<String, Value> {
+    let mut suites: BTreeMap<&str, Vec<&StreamTiming>> = BTreeMap::new();
+    for (test, timing) in streams {
+        suites.entry(suite_of(test)).or_default().push(timing);
+    }
+    suites.into_iter()
+        .map(|(suite, timings)| {
+            let percentiles = |field: fn(&StreamTiming) -> Duration| {
+                let mut sorted: Vec<Duration> = timings.iter().map(|timing| field(timing)).collect();
+                sorted.sort();
+                json!({
+                    "p50": percentile(&sorted, 50.0),
+                    "p95": percentile(&sorted, 95.0),
+                    "p99": percentile(&sorted, 99.0),
+                })
+            };
+            let latency = json!({
+                "streams": timings.len(),
+                "ttfb_ms": percentiles(|timing| timing.ttfb),
+                "max_gap_ms": percentiles(|timing| timing.max_gap),
+                "total_ms": percentiles(|timing| timing.total),
+            });
+            (suite.to_string(), latency)
+        })
+        .collect()
+}
+
+/// The table of each suite's streaming latency that follows the summary
+pub fn render_stream_latency(latency: &BTreeMap<String, Value>, style: &Style) -> String {
+    let width = latency.keys().map(|suite| suite.chars().count()).max().unwrap_or(0).max(5);
+    let mut text = style.paint("2", &format!(
+        "{:<width$}  {:>7}  {:>8}  {:>8}  {:>8}  {:>7}  {:>7}  {:>9}",
+        "suite", "streams", "ttfb p50", "ttfb p95", "ttfb p99", "gap p95", "gap p99", "total p95", width = width
+    ));
+    text.push('\n');
+    let ms = |value: &Value| value.as_f64().map_or("-".to_string(), |ms| format!("{:.0}", ms));
+    for (suite, suite_latency) in latency {
+        text.push_str(&format!(
+            "{:<width$}  {:>7}  {:>8}  {:>8}  {:>8}  {:>7}  {:>7}  {:>9}\n",
+            suite,
+            suite_latency["streams"].to_string(),
+            ms(&suite_latency["ttfb_ms"]["p50"]),
+            ms(&suite_latency["ttfb_ms"]["p95"]),
+            ms(&suite_latency["ttfb_ms"]["p99"]),
+            ms(&suite_latency["max_gap_ms"]["p95"]),
+            ms(&suite_latency["max_gap_ms"]["p99"]),
+            ms(&suite_latency["total_ms"]["p95"]),
+            width = width
+        ));
+    }
+    text
+}
+
+/// Draws a run as its results come in
+pub struct Progress {
+    style: Style,
+    // Tests still to finish in each suite, from the list of what will run
+    pending: HashMap<String, usize>,
+    finished: BTreeMap<String, Vec<Finished>>,
+    counts: BTreeMap<&'static str, usize>,
+    total: usize,
+    results: Results,
+    start: Instant,
+}
+
+impl Progress {
+    /// Starts a run of these tests, reading their times and skips from the results file
+    pub fn new(tests: &[String], results: &Path, style: Style) -> Progress {
+        let mut pending = HashMap::new();
+        for test in tests {
+            *pending.entry(suite_of(test).to_string()).or_default() += 1;
+        }
+        Progress {
+            style,
+            pending,
+            finished: BTreeMap::new(),
+            counts: BTreeMap::new(),
+            total: tests.len(),
+            results: Results::new(results),
+            start: Instant::now(),
+        }
+    }
+
+    pub fn record(&mut self, name: &str, outcome: Outcome) {
+        let duration = self.results.duration(name);
+        *self.counts.entry(match outcome {
+            Outcome::Passed => "passed",
+            Outcome::Failed => "failed",
+            Outcome::Skipped => "skipped",
+            _ => "ignored",
+        }).or_default() += 1;
+
+        let suite = suite_of(name).to_string();
+        self.finished.entry(suite.clone()).or_default().push(Finished { name: name.to_string(), outcome, duration });
+        if let Some(left) = self.pending.get_mut(&suite) {
+            *left = left.saturating_sub(1);
+            if *left == 0 {
+                self.pending.remove(&suite);
+                self.print_suite(&suite);
+            }
+        }
+        self.status_line();
+    }
+
+    /// Draws what hasn't been drawn, such as suites cut short by a failed build, and the summary
+    pub fn finish(&mut self) {
+        let suites: Vec<String> = self.finished.keys().cloned().collect();
+        for suite in suites {
+            self.print_suite(&suite);
+        }
+        self.clear_status_line();
+        println!("{}", render_summary(&self.counts, self.start.elapsed(), &self.style));
+
+        let latency = stream_latency(&self.streams());
+        if !latency.is_empty() {
+            print!("\n{}", render_stream_latency(&latency, &self.style));
+        }
+    }
+
+    /// Whether the suite says it skipped a test libtest reported as passing
+    pub fn skipped(&mut self, name: &str) -> bool {
+        self.results.read_new_lines();
+        self.results.skipped.contains(name)
+    }
+
+    /// Every stream the suite has timed so far, with the test that made it
+    pub fn streams(&mut self) -> Vec<(String, StreamTiming)> {
+        self.results.read_new_lines();
+        self.results.streams.clone()
+    }
+
+    /// Every test's time the suite has written so far
+    pub fn durations(&mut self) -> BTreeMap<String, Duration> {
+        self.results.read_new_lines();
+        self.results.durations.iter().map(|(test, duration)| (test.clone(), *duration)).collect()
+    }
+
+    fn print_suite(&mut self, suite: &str) {
+        let Some(mut tests) = self.finished.remove(suite) else { return };
+        self.clear_status_line();
+        if self.style.quiet && tests.iter().all(|test| test.outcome != Outcome::Failed) {
+            return;
+        }
+        tests.sort_by(|a, b| a.name.cmp(&b.name));
+        print!("{}", render_suite(suite, &tests, &self.style));
+    }
+
+    // Rewritten in place, so only on a terminal
+    fn status_line(&self) {
+        if !self.style.color || self.style.quiet {
+            return;
+        }
+        let done: usize = self.counts.values().sum();
+        let failed = self.counts.get("failed").copied().unwrap_or(0);
+        print!("\r\x1b[2K{}/{} tests, {} failed, {}", done, self.total, failed, seconds(self.start.elapsed()));
+        let _ = std::io::stdout().flush();
+    }
+
+    fn clear_status_line(&self) {
+        if self.style.color && !self.style.quiet {
+            print!("\r\x1b[2K");
+        }
+    }
+}
+
+/// "test<TAB>seconds" lines the suite appends as each test ends,
+/// "test<TAB>skipped<TAB>reason" for those it skips (see harness.rs), and a
+/// "test<TAB>stream<TAB>..." line for each stream it timed (see stream_timing.rs)
+struct Results {
+    path: PathBuf,
+    read: usize,
+    durations: HashMap<String, Duration>,
+    skipped: HashSet<String>,
+    streams: Vec<(String, StreamTiming)>,
+}
+
+impl Results {
+    fn new(path: &Path) -> Results {
+        Results {
+            path: path.to_path_buf(),
+            read: 0,
+            durations: HashMap::new(),
+            skipped: HashSet::new(),
+            streams: Vec::new(),
+        }
+    }
+
+    fn duration(&mut self, test: &str) -> Option<Duration> {
+        if !self.durations.contains_key(test) {
+            self.read_new_lines();
+        }
+        self.durations.get(test).copied()
+    }
+
+    fn read_new_lines(&mut self) {
+        let mut text = String::new();
+        if File::open(&self.path).and_then(|mut file| file.read_to_string(&mut text)).is_err() {
+            return;
+        }
+        // Only whole lines, in case one is being written
+        let end = text.rfind('\n').map_or(0, |i| i + 1);
+        if end <= self.read {
+            return;
+        }
+        for line in text[self.read..end].lines() {
+            let Some((name, rest)) = line.split_once('\t') else { continue };
+            let name = name.strip_prefix("tests::").unwrap_or(name).to_string();
+            if rest.starts_with("skipped") {
+                self.skipped.insert(name);
+            } else if let Some(timing) = stream_timing::parse(rest) {
+                self.streams.push((name, timing));
+            } else if let Ok(secs) = rest.parse::<f64>() {
+                self.durations.insert(name, Duration::from_secs_f64(secs));
+            }
+        }
+        self.read = end;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finished(name: &str, outcome: Outcome, millis: u64) -> Finished {
+        Finished { name: name.to_string(), outcome, duration: Some(Duration::from_millis(millis)) }
+    }
+
+    #[test]
+    fn test_render_suite() {
+        let tests = [
+            finished("basic::test_a", Outcome::Passed, 50),
+            finished("basic::test_longer_name", Outcome::Failed, 1250),
+            finished("basic::test_c", Outcome::Ignored, 0),
+        ];
+
+        assert_eq!(render_suite("basic", &tests, &Style::default()), "\
+basic  core-chat  1 passed, 1 failed, 1 ignored  1.30s
+├─ ✓ test_a            0.05s
+├─ ✗ test_longer_name  1.25s
+└─ - test_c            ignored
+");
+        let quiet = Style { quiet: true, ..Style::default() };
+        assert!(render_suite("basic", &tests, &quiet).ends_with("└─ ✗ test_longer_name  1.25s\n"));
+        assert!(render_suite("basic", &tests, &Style { color: true, quiet: false }).contains("\x1b[31m✗\x1b[0m"));
+    }
+
+    #[test]
+    fn test_render_summary() {
+        let counts = BTreeMap::from([("passed", 420), ("failed", 3), ("ignored", 3)]);
+        assert_eq!(
+            render_summary(&counts, Duration::from_millis(42_100), &Style::default()),
+            "426 tests: 420 passed, 3 failed, 3 ignored in 42.10s"
+        );
+    }
+
+    #[test]
+    fn test_results_are_read_as_they_are_written() {
+        let path = std::env::temp_dir().join(format!("results-{}.tsv", std::process::id()));
+        std::fs::write(&path, "tests::basic::test_a\t0.5\ntests::basic::test_b\t1").unwrap();
+        let mut results = Results::new(&path);
+
+        assert_eq!(results.duration("basic::test_a"), Some(Duration::from_millis(500)));
+        // The second line isn't finished yet
+        assert_eq!(results.duration("basic::test_b"), None);
+        std::fs::write(&path, "tests::basic::test_a\t0.5\ntests::basic::test_b\t1.25\ntests::realtime::test_c\tskipped\tno realtime\n").unwrap();
+        assert_eq!(results.duration("basic::test_b"), Some(Duration::from_millis(1250)));
+        assert!(results.skipped.contains("realtime::test_c"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_stream_latency_per_suite() {
+        let timing = |ttfb: u64, total: u64| StreamTiming {
+            ttfb: Duration::from_millis(ttfb),
+            max_gap: Duration::from_millis(5),
+            total: Duration::from_millis(total),
+        };
+        let streams: Vec<(String, StreamTiming)> = [
+            ("streaming::test_a", timing(10, 100)),
+            ("streaming::test_a", timing(30, 300)),
+            ("streaming::test_b", timing(20, 200)),
+            ("slow::test_c", timing(500, 900)),
+        ]
+        .into_iter()
+        .map(|(test, timing)| (test.to_string(), timing))
+        .collect();
+
+        let latency = stream_latency(&streams);
+        assert_eq!(latency["streaming"]["streams"], 3);
+        assert_eq!(latency["streaming"]["ttfb_ms"], json!({"p50": 20.0, "p95": 30.0, "p99": 30.0}));
+        assert_eq!(latency["slow"]["total_ms"]["p50"], 900.0);
+
+        assert_eq!(render_stream_latency(&latency, &Style::default()), "\
+suite      streams  ttfb p50  ttfb p95  ttfb p99  gap p95  gap p99  total p95
+slow             1       500       500       500        5        5        900
+streaming        3        20        30        30        5        5        300
+");
+    }
+}
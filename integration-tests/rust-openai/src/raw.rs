@@ -0,0 +1,108 @@
+// Raw HTTP access to the server under test, for requests async-openai can't
+// build: arbitrary bytes, wrong content types, truncated or oversized bodies.
+// Responses come back whole, with status, headers and body, and the
+// assertions below check error responses against OpenAI's envelope.
+
+use reqwest::header::HeaderMap;
+use reqwest::{Method, StatusCode};
+use serde::Deserialize;
+use serde_json::Value;
+
+pub struct RawResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+}
+
+impl RawResponse {
+    /// The body as JSON, panicking with the raw text when it isn't
+    pub fn json(&self) -> Value {
+        serde_json::from_slice(&self.body)
+            .unwrap_or_else(|e| panic!("Response body is not JSON ({}): {}", e, self.text()))
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).and_then(|value| value.to_str().ok())
+    }
+}
+
+/// A request to build on, authenticated with TEENYTINY_API_KEY
+pub fn request(method: Method, path: &str) -> reqwest::RequestBuilder {
+    crate::http_client()
+        .request(method, format!("{}{}", crate::base_url(), path))
+        .bearer_auth(crate::api_key())
+}
+
+/// Sends a request and reads the whole response
+pub async fn send(request: reqwest::RequestBuilder) -> RawResponse {
+    let response = request.send().await.expect("Request failed");
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = response.bytes().await.expect("Failed to read response body").to_vec();
+    RawResponse { status, headers, body }
+}
+
+pub async fn get(path: &str) -> RawResponse {
+    send(request(Method::GET, path)).await
+}
+
+/// POSTs bytes as they are, labelled as JSON whether or not they are
+pub async fn post(path: &str, body: impl Into<Vec<u8>>) -> RawResponse {
+    post_as(path, Some("application/json"), body).await
+}
+
+/// POSTs bytes with the given Content-Type, or none at all
+pub async fn post_as(path: &str, content_type: Option<&str>, body: impl Into<Vec<u8>>) -> RawResponse {
+    let mut request = request(Method::POST, path).body(body.into());
+    if let Some(content_type) = content_type {
+        request = request.header("Content-Type", content_type);
+    }
+    send(request).await
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorEnvelope {
+    error: ErrorBody,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ErrorBody {
+    pub message: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub param: Option<String>,
+    pub code: Option<String>,
+}
+
+/// Checks the body is an OpenAI error envelope, with param and code present even when null
+pub fn assert_error_envelope(body: &Value) -> ErrorBody {
+    let error = body["error"].as_object()
+        .unwrap_or_else(|| panic!("Expected an error object, got: {}", body));
+
+    for key in ["message", "type", "param", "code"] {
+        assert!(error.contains_key(key), "Error envelope missing '{}': {}", key, body);
+    }
+
+    let envelope: ErrorEnvelope = serde_json::from_value(body.clone())
+        .unwrap_or_else(|e| panic!("Error envelope didn't deserialize ({}): {}", e, body));
+
+    assert!(!envelope.error.message.is_empty(), "Error message should not be empty");
+    envelope.error
+}
+
+/// Checks the response failed with this status and error type, returning the error for further checks
+pub fn assert_error(response: &RawResponse, status: StatusCode, kind: &str) -> ErrorBody {
+    assert_eq!(response.status, status, "Unexpected status with body: {}", response.text());
+    assert_eq!(
+        response.header("content-type").map(|value| value.starts_with("application/json")),
+        Some(true),
+        "Error responses should be JSON"
+    );
+    let error = assert_error_envelope(&response.json());
+    assert_eq!(error.kind, kind, "Unexpected error type: {}", response.text());
+    error
+}
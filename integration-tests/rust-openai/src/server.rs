@@ -0,0 +1,170 @@
+// Starts the TeenyTiny server from this checkout for the tests, so `cargo test`
+// needs no server started by hand. The server is Node.js, so it runs as a
+// child process on a free port, and exits when the process that started it
+// does (it's told to exit once its stdin closes).
+
+use std::io::Read;
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStderr, ChildStdin, Command, ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+
+const API_KEY: &str = "testkey";
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+// How much of the end of the server's stderr is kept, to explain a failed start
+const STDERR_TAIL_BYTES: usize = 4096;
+
+/// A server running from ../../service, stopped when dropped
+pub struct TestServer {
+    child: Child,
+    // Held open until the server should exit
+    _stdin: ChildStdin,
+    // The end of what the server wrote to stderr
+    stderr: Arc<Mutex<Vec<u8>>>,
+    url: String,
+}
+
+impl TestServer {
+    /// Starts a server on a free port and waits until it accepts connections
+    pub fn start() -> Result<TestServer> {
+        Self::start_with(&[])
+    }
+
+    /// As start, passing extra flags to the server, such as `--config <file>`
+    pub fn start_with(args: &[&str]) -> Result<TestServer> {
+        Self::start_with_env(args, &[])
+    }
+
+    /// As start_with, also setting environment variables such as
+    /// `TEENYTINY_SESSION_TTL_MS` for the server
+    pub fn start_with_env(args: &[&str], env: &[(&str, &str)]) -> Result<TestServer> {
+        let service = service_dir();
+        let port = free_port()?;
+        let mut child = server_command(&service)?
+            .args(["--port", &port.to_string(), "--api-key", API_KEY, "--exit-with-stdin"])
+            .args(args)
+            .envs(env.iter().copied())
+            .current_dir(&service)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Can't start the server in {}", service.display()))?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stderr = tail(child.stderr.take().expect("stderr is piped"));
+        let mut server = TestServer { child, _stdin: stdin, stderr, url: format!("http://localhost:{}", port) };
+        server.wait_until_listening(port)?;
+        Ok(server)
+    }
+
+    /// Base URL, without the /v1 suffix
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn api_key(&self) -> &str {
+        API_KEY
+    }
+
+    /// Sends the server SIGTERM, which drains it before it exits
+    pub fn terminate(&self) -> Result<()> {
+        let status = Command::new("kill")
+            .args(["-TERM", &self.child.id().to_string()])
+            .status()
+            .context("Can't run kill")?;
+        if !status.success() {
+            bail!("kill -TERM {} failed with {}", self.child.id(), status);
+        }
+        Ok(())
+    }
+
+    /// Waits for the server to exit, returning how, or None if it's still
+    /// running after the timeout
+    pub fn wait_for_exit(&mut self, timeout: Duration) -> Result<Option<ExitStatus>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(status) = self.child.try_wait()? {
+                return Ok(Some(status));
+            }
+            if Instant::now() > deadline {
+                return Ok(None);
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+    }
+
+    fn wait_until_listening(&mut self, port: u16) -> Result<()> {
+        let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+        let deadline = Instant::now() + STARTUP_TIMEOUT;
+        loop {
+            if let Some(status) = self.child.try_wait()? {
+                // Let the reader catch up with the server's last words
+                std::thread::sleep(Duration::from_millis(100));
+                let stderr = self.stderr.lock().unwrap();
+                bail!("The server exited with {} before listening: {}", status, String::from_utf8_lossy(&stderr).trim());
+            }
+            if TcpStream::connect_timeout(&address, Duration::from_millis(100)).is_ok() {
+                return Ok(());
+            }
+            if Instant::now() > deadline {
+                bail!("The server wasn't listening on port {} after {:?}", port, STARTUP_TIMEOUT);
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        }
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn service_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../../service")
+}
+
+// Runs the TypeScript source with tsx when npm install has been run, so the
+// server is never stale, otherwise the last `npm run build`
+fn server_command(service: &Path) -> Result<Command> {
+    let tsx = service.join("node_modules/.bin/tsx");
+    if tsx.is_file() {
+        let mut command = Command::new(tsx);
+        command.arg("src/server.ts");
+        return Ok(command);
+    }
+    if service.join("dist/server.js").is_file() {
+        let mut command = Command::new("node");
+        command.arg("dist/server.js");
+        return Ok(command);
+    }
+    bail!("Run `npm install` in {} to start the server from the harness", service.display())
+}
+
+// Keeps reading the server's stderr, so it never blocks on a full pipe, and
+// holds on to the end of it
+fn tail(mut stderr: ChildStderr) -> Arc<Mutex<Vec<u8>>> {
+    let kept = Arc::new(Mutex::new(Vec::new()));
+    let writer = Arc::clone(&kept);
+    std::thread::spawn(move || {
+        let mut buffer = [0; 1024];
+        while let Ok(read @ 1..) = stderr.read(&mut buffer) {
+            let mut kept = writer.lock().unwrap();
+            kept.extend_from_slice(&buffer[..read]);
+            let excess = kept.len().saturating_sub(STDERR_TAIL_BYTES);
+            kept.drain(..excess);
+        }
+    });
+    kept
+}
+
+// Asks the OS for a port nobody is listening on. Another process could take it
+// before the server binds it, but that is rare enough for tests.
+fn free_port() -> Result<u16> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).context("Can't find a free port")?;
+    Ok(listener.local_addr()?.port())
+}
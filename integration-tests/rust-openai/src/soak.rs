@@ -0,0 +1,243 @@
+// Soak test: runs mixed traffic for a long time while sampling the server's
+// /metrics, and fails if memory, open connections or active streams only ever
+// grow, the usual sign of a leak.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use teenytiny_rust_openai_integration::{api_key, base_url, http_client};
+use tokio::time::{interval, MissedTickBehavior};
+
+use crate::bench::{send, streams, Kind};
+
+pub const USAGE: &str = "\
+Usage: integration_test --soak <duration> [options]
+
+Runs mixed traffic for the duration (e.g. 90s, 30m or 2h), sampling /metrics as
+it goes, and fails if RSS, open connections or active streams grow monotonically.
+
+Options:
+  --rps <n>              Requests started per second (default 5)
+  --sample-every <dur>   Time between /metrics samples (default 30s)";
+
+// Series that must not only ever grow. RSS and connections are only reported by
+// the Node.js server.
+const SERIES: [&str; 3] = ["rss_bytes", "open_connections", "active_streams"];
+
+// Fewer samples than this can't tell a leak from warm-up
+const MIN_SAMPLES: usize = 5;
+
+// Traffic rotates through these, and every eighth request abandons a stream
+// after its first chunk, so cancellation paths are soaked too
+const MODELS: [&str; 3] = ["echo", "eliza", "slow:20"];
+const PROMPT: &str = "How long can you keep this up?";
+
+#[derive(Debug, PartialEq)]
+pub struct Options {
+    pub duration: Duration,
+    pub rps: f64,
+    pub sample_every: Duration,
+}
+
+// Parses "90s", "30m", "2h", or a bare number of seconds
+pub fn parse_duration(value: &str) -> Result<Duration> {
+    let (number, unit) = match value.find(|c: char| c.is_ascii_alphabetic()) {
+        Some(at) => value.split_at(at),
+        None => (value, "s"),
+    };
+    let scale = match unit {
+        "s" => 1.0,
+        "m" => 60.0,
+        "h" => 3600.0,
+        _ => bail!("Invalid duration '{}': expected e.g. 90s, 30m or 2h", value),
+    };
+    match number.parse::<f64>() {
+        Ok(n) if n > 0.0 && n.is_finite() => Ok(Duration::from_secs_f64(n * scale)),
+        _ => bail!("Invalid duration '{}': expected e.g. 90s, 30m or 2h", value),
+    }
+}
+
+// The first argument is the duration given to --soak
+pub fn parse_options(args: &[String]) -> Result<Options> {
+    let (duration, rest) = args.split_first().context("Missing duration for --soak")?;
+    let mut options = Options {
+        duration: parse_duration(duration)?,
+        rps: 5.0,
+        sample_every: Duration::from_secs(30),
+    };
+    let mut args = rest.iter();
+    while let Some(flag) = args.next() {
+        let value = args.next().with_context(|| format!("Missing value for {}", flag))?;
+        match flag.as_str() {
+            "--rps" => match value.parse::<f64>() {
+                Ok(rps) if rps > 0.0 && rps <= 10_000.0 => options.rps = rps,
+                _ => bail!("Invalid --rps '{}': expected a number from 0 to 10000", value),
+            },
+            "--sample-every" => options.sample_every = parse_duration(value)?,
+            _ => bail!("Unknown option {}", flag),
+        }
+    }
+    Ok(options)
+}
+
+// True when a series never went down across enough samples and ended higher
+pub fn grows_monotonically(values: &[f64]) -> bool {
+    values.len() >= MIN_SAMPLES
+        && values.windows(2).all(|pair| pair[0] <= pair[1])
+        && values.last() > values.first()
+}
+
+async fn abandon_stream(client: reqwest::Client, model: &str) -> bool {
+    let response = client
+        .post(format!("{}/v1/chat/completions", base_url()))
+        .bearer_auth(api_key())
+        .json(&json!({"model": model, "messages": [{"role": "user", "content": PROMPT}], "stream": true}))
+        .send()
+        .await;
+    match response {
+        Ok(mut response) if response.status().is_success() => response.chunk().await.is_ok(),
+        _ => false,
+    }
+}
+
+async fn sample_metrics(client: &reqwest::Client) -> Result<Value> {
+    let response = client.get(format!("{}/metrics", base_url())).send().await?;
+    Ok(response.error_for_status()?.json().await?)
+}
+
+fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    format!("{}m{:02}s", secs / 60, secs % 60)
+}
+
+pub async fn run(args: &[String]) -> Result<()> {
+    let options = parse_options(args)?;
+    println!(
+        "Soaking {} for {} at {} rps, sampling /metrics every {}...\n",
+        base_url(),
+        format_elapsed(options.duration),
+        options.rps,
+        format_elapsed(options.sample_every)
+    );
+
+    let client = http_client();
+    let sent = Arc::new(AtomicU64::new(0));
+    let failed = Arc::new(AtomicU64::new(0));
+
+    let traffic = {
+        let (client, sent, failed) = (client.clone(), sent.clone(), failed.clone());
+        let mut ticks = interval(Duration::from_secs_f64(1.0 / options.rps));
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        tokio::spawn(async move {
+            for i in 0u64.. {
+                ticks.tick().await;
+                let (client, failed) = (client.clone(), failed.clone());
+                let model = MODELS[i as usize % MODELS.len()];
+                sent.fetch_add(1, Ordering::Relaxed);
+                tokio::spawn(async move {
+                    let ok = if i % 8 == 7 {
+                        abandon_stream(client, model).await
+                    } else {
+                        let kind = if streams(i, 0.5) { Kind::Streaming } else { Kind::Blocking };
+                        send(client, model, PROMPT, kind).await.error.is_none()
+                    };
+                    if !ok {
+                        failed.fetch_add(1, Ordering::Relaxed);
+                    }
+                });
+            }
+        })
+    };
+
+    let start = Instant::now();
+    let mut samples = Vec::new();
+    let mut ticks = interval(options.sample_every);
+    while start.elapsed() < options.duration {
+        ticks.tick().await;
+        let metrics = sample_metrics(&client).await.context("Could not read /metrics")?;
+        println!(
+            "[{}] rss {} MB, {} connections, {} active streams, {} requests ({} failed)",
+            format_elapsed(start.elapsed()),
+            metrics["rss_bytes"].as_f64().map_or("-".to_string(), |rss| format!("{:.1}", rss / 1e6)),
+            metrics["open_connections"].as_u64().map_or("-".to_string(), |n| n.to_string()),
+            metrics["active_streams"],
+            sent.load(Ordering::Relaxed),
+            failed.load(Ordering::Relaxed),
+        );
+        samples.push(metrics);
+    }
+    traffic.abort();
+
+    // Once traffic stops, every stream should wind down
+    let drain_deadline = Instant::now() + Duration::from_secs(10);
+    let mut active = u64::MAX;
+    while active > 0 && Instant::now() < drain_deadline {
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        active = sample_metrics(&client).await?["active_streams"].as_u64().unwrap_or(0);
+    }
+
+    // The first sample is warm-up: connection pools and JIT still filling
+    let mut problems = Vec::new();
+    println!();
+    for series in SERIES {
+        let values: Vec<f64> = samples.iter().skip(1).filter_map(|s| s[series].as_f64()).collect();
+        if values.is_empty() {
+            println!("{:<18} not reported by this server", series);
+        } else if values.len() < MIN_SAMPLES {
+            println!("{:<18} only {} samples, too few to judge", series, values.len());
+        } else if grows_monotonically(&values) {
+            println!("{:<18} LEAK? grew from {} to {} without ever dropping", series, values[0], values[values.len() - 1]);
+            problems.push(series);
+        } else {
+            println!("{:<18} ok", series);
+        }
+    }
+    if active > 0 {
+        println!("{:<18} LEAK? {} streams still active after traffic stopped", "active_streams", active);
+        problems.push("active_streams");
+    }
+
+    if !problems.is_empty() {
+        bail!("Possible leak in {}", problems.join(", "));
+    }
+    println!("\nNo leaks detected");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("30m").unwrap(), Duration::from_secs(1800));
+        assert_eq!(parse_duration("90s").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("1.5h").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse_duration("45").unwrap(), Duration::from_secs(45));
+        for invalid in ["", "m", "0m", "-5s", "3d", "soon"] {
+            assert!(parse_duration(invalid).is_err(), "{} should be rejected", invalid);
+        }
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let args: Vec<String> = ["10m", "--rps", "20", "--sample-every", "15s"].map(String::from).to_vec();
+        assert_eq!(
+            parse_options(&args).unwrap(),
+            Options { duration: Duration::from_secs(600), rps: 20.0, sample_every: Duration::from_secs(15) }
+        );
+        assert!(parse_options(&[]).is_err());
+        assert!(parse_options(&["10m".to_string(), "--rps".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_only_steady_growth_counts_as_a_leak() {
+        assert!(grows_monotonically(&[1.0, 2.0, 2.0, 3.0, 5.0]));
+        assert!(!grows_monotonically(&[1.0, 2.0, 1.5, 3.0, 5.0]));
+        assert!(!grows_monotonically(&[4.0, 4.0, 4.0, 4.0, 4.0]));
+        assert!(!grows_monotonically(&[1.0, 2.0, 3.0]));
+    }
+}
@@ -0,0 +1,149 @@
+// Streaming latency. With stream timing on, the suite's requests go through
+// the proxy in trace.rs, which times every streamed response as it passes:
+// how long from sending the request to the first chunk of the body, the
+// longest wait between chunks, and the whole stream. Each test's streams are
+// reported to the integration_test binary, which shows percentiles per suite,
+// and a test whose streams are slower than --ttfb-max-ms or --chunk-gap-max-ms
+// allow fails.
+//
+// Streams are told apart by their content type. Those made from a task a test
+// spawned don't carry its name, so aren't counted.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StreamTiming {
+    /// From sending the request to the first chunk of the body
+    pub ttfb: Duration,
+    /// The longest wait between two chunks
+    pub max_gap: Duration,
+    /// From sending the request to the last chunk
+    pub total: Duration,
+}
+
+// Every stream timed so far that its test hasn't taken, by when it started
+static STREAMS: Mutex<BTreeMap<u64, (String, StreamTiming)>> = Mutex::new(BTreeMap::new());
+static NEXT: AtomicU64 = AtomicU64::new(0);
+
+/// Whether a response with this content type is a stream to time
+pub fn is_stream(content_type: &str) -> bool {
+    content_type.starts_with("text/event-stream") || content_type.starts_with("application/x-ndjson")
+}
+
+/// Times one streamed response for a test
+pub struct Clock {
+    id: u64,
+    test: String,
+    sent: Instant,
+    last: Option<Instant>,
+    timing: StreamTiming,
+}
+
+impl Clock {
+    pub fn start(test: &str, sent: Instant) -> Clock {
+        let timing = StreamTiming { ttfb: Duration::ZERO, max_gap: Duration::ZERO, total: Duration::ZERO };
+        Clock { id: NEXT.fetch_add(1, Ordering::Relaxed), test: test.to_string(), sent, last: None, timing }
+    }
+
+    /// Notes a chunk arriving. The timing is updated before the chunk is passed
+    /// on, so a test that stops reading once it has what it wants still sees it.
+    pub fn chunk(&mut self) {
+        let now = Instant::now();
+        match self.last {
+            None => self.timing.ttfb = now - self.sent,
+            Some(last) => self.timing.max_gap = self.timing.max_gap.max(now - last),
+        }
+        self.last = Some(now);
+        self.timing.total = now - self.sent;
+        STREAMS.lock().unwrap().insert(self.id, (self.test.clone(), self.timing));
+    }
+}
+
+/// The streams a test made, taken so they're reported once
+pub fn take(test: &str) -> Vec<StreamTiming> {
+    let mut streams = STREAMS.lock().unwrap();
+    let ids: Vec<u64> = streams.iter().filter(|(_, (name, _))| name == test).map(|(id, _)| *id).collect();
+    ids.iter().filter_map(|id| streams.remove(id)).map(|(_, timing)| timing).collect()
+}
+
+/// Why streams with these timings are too slow, if any is
+pub fn check(timings: &[StreamTiming], ttfb_max: Option<Duration>, gap_max: Option<Duration>) -> Option<String> {
+    timings.iter().find_map(|timing| match (ttfb_max, gap_max) {
+        (Some(max), _) if timing.ttfb > max => Some(format!(
+            "a stream's first byte took {}ms, over --ttfb-max-ms {} (TEENYTINY_TTFB_MAX_MS)",
+            timing.ttfb.as_millis(),
+            max.as_millis()
+        )),
+        (_, Some(max)) if timing.max_gap > max => Some(format!(
+            "a stream waited {}ms between chunks, over --chunk-gap-max-ms {} (TEENYTINY_CHUNK_GAP_MAX_MS)",
+            timing.max_gap.as_millis(),
+            max.as_millis()
+        )),
+        _ => None,
+    })
+}
+
+/// A stream as the harness writes it to the results file:
+/// "test<TAB>stream<TAB>ttfb<TAB>max gap<TAB>total", in seconds
+pub fn line(test: &str, timing: &StreamTiming) -> String {
+    format!(
+        "{}\tstream\t{}\t{}\t{}\n",
+        test,
+        timing.ttfb.as_secs_f64(),
+        timing.max_gap.as_secs_f64(),
+        timing.total.as_secs_f64()
+    )
+}
+
+/// Reads a stream back from what follows the test's name in its line
+pub fn parse(rest: &str) -> Option<StreamTiming> {
+    let mut fields = rest.strip_prefix("stream\t")?.split('\t')
+        .map(|field| field.parse::<f64>().ok().map(Duration::from_secs_f64));
+    let (ttfb, max_gap, total) = (fields.next()??, fields.next()??, fields.next()??);
+    Some(StreamTiming { ttfb, max_gap, total })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timing(ttfb: u64, max_gap: u64) -> StreamTiming {
+        StreamTiming { ttfb: Duration::from_millis(ttfb), max_gap: Duration::from_millis(max_gap), total: Duration::from_millis(1500) }
+    }
+
+    #[test]
+    fn test_slow_streams_fail_the_thresholds() {
+        let timings = [timing(120, 40), timing(650, 30)];
+        let ms = |n| Some(Duration::from_millis(n));
+
+        assert_eq!(check(&timings, None, None), None);
+        assert_eq!(check(&timings, ms(1000), ms(50)), None);
+        assert!(check(&timings, ms(500), None).unwrap().contains("650ms, over --ttfb-max-ms 500"));
+        assert!(check(&timings, None, ms(35)).unwrap().contains("40ms between chunks"));
+    }
+
+    #[test]
+    fn test_streams_are_taken_once() {
+        let mut clock = Clock::start("tests::streaming::test_taken", Instant::now());
+        clock.chunk();
+        clock.chunk();
+
+        let taken = take("tests::streaming::test_taken");
+        assert_eq!(taken.len(), 1);
+        assert!(taken[0].total >= taken[0].ttfb);
+        assert!(take("tests::streaming::test_taken").is_empty());
+    }
+
+    #[test]
+    fn test_results_lines_read_back() {
+        let written = line("tests::streaming::test_a", &timing(250, 125));
+        assert_eq!(written, "tests::streaming::test_a\tstream\t0.25\t0.125\t1.5\n");
+        let (_, rest) = written.trim_end().split_once('\t').unwrap();
+        assert_eq!(parse(rest), Some(timing(250, 125)));
+        assert_eq!(parse("0.5"), None);
+        assert_eq!(parse("stream\t0.5"), None);
+    }
+}
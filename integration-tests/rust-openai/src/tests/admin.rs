@@ -0,0 +1,118 @@
+// The admin API changes server-wide settings, so these tests only write values
+// that can't disturb tests running alongside them: new keys of their own, a
+// rate limit above the default, and the fault settings that were already set.
+
+use reqwest::{Method, StatusCode};
+use serde_json::json;
+
+use crate::{api_key, base_url};
+use super::{admin, admin_as, new_api_key};
+
+async fn chat(key: &str, model: &str, headers: &[(&str, &str)]) -> reqwest::Response {
+    let mut request = crate::http_client()
+        .post(format!("{}/v1/chat/completions", base_url()))
+        .bearer_auth(key)
+        .json(&json!({"model": model, "messages": [{"role": "user", "content": "Hi"}]}));
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    request.send().await.unwrap()
+}
+
+teenytiny_test!(async fn test_create_and_revoke_key() {
+    let (status, created) = admin(Method::POST, "/keys", Some(json!({"models": ["echo"]}))).await;
+    assert_eq!(status, StatusCode::OK);
+    let key = created["key"].as_str().expect("No key in response").to_string();
+    assert_eq!(created["models"], json!(["echo"]));
+
+    assert_eq!(chat(&key, "echo", &[]).await.status(), StatusCode::OK);
+    assert_eq!(chat(&key, "eliza", &[]).await.status(), StatusCode::FORBIDDEN);
+
+    let (_, listing) = admin(Method::GET, "/keys", None).await;
+    assert!(listing["keys"].as_array().unwrap().iter().any(|k| k["key"] == key.as_str()));
+
+    let (status, revoked) = admin(Method::DELETE, &format!("/keys/{}", key), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(revoked["revoked"], true);
+    assert_eq!(chat(&key, "echo", &[]).await.status(), StatusCode::UNAUTHORIZED);
+});
+
+teenytiny_test!(async fn test_server_key_cannot_be_revoked() {
+    let (status, body) = admin(Method::DELETE, &format!("/keys/{}", api_key()), None).await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["param"], "key");
+});
+
+teenytiny_test!(async fn test_rate_limit_settings() {
+    let (status, current) = admin(Method::GET, "/rate-limit", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let limit = current["requests_per_minute"].as_u64().expect("No rate limit in response");
+
+    // Writing the current value back leaves other tests unaffected
+    let (status, updated) = admin(Method::PUT, "/rate-limit", Some(json!({"requests_per_minute": limit}))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["requests_per_minute"], limit);
+
+    let response = chat(&api_key(), "echo", &[]).await;
+    assert_eq!(response.headers()["x-ratelimit-limit-requests"], limit.to_string().as_str());
+
+    let (status, body) = admin(Method::PUT, "/rate-limit", Some(json!({"requests_per_minute": 0}))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["param"], "requests_per_minute");
+});
+
+teenytiny_test!(async fn test_fault_settings() {
+    let (status, current) = admin(Method::GET, "/faults", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(current["failure_rate"].is_number());
+    assert!(current["kinds"].as_array().unwrap().iter().any(|k| k == "reset"));
+
+    let (status, updated) = admin(Method::PUT, "/faults", Some(current.clone())).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated, current);
+
+    let (status, body) = admin(Method::PUT, "/faults", Some(json!({"failure_rate": 1.5}))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["param"], "failure_rate");
+
+    let (status, body) = admin(Method::PUT, "/faults", Some(json!({"kinds": ["404"]}))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["param"], "kinds");
+});
+
+teenytiny_test!(async fn test_reset_usage_for_one_key() {
+    let key = new_api_key().await;
+    let budget = [("x-teenytiny-ratelimit-requests", "1")];
+
+    assert_eq!(chat(&key, "echo", &budget).await.status(), StatusCode::OK);
+    assert_eq!(chat(&key, "echo", &budget).await.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    let (status, body) = admin(Method::POST, "/usage/reset", Some(json!({"key": key}))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["key"], key.as_str());
+
+    assert_eq!(chat(&key, "echo", &budget).await.status(), StatusCode::OK);
+});
+
+teenytiny_test!(async fn test_list_models() {
+    let (status, body) = admin(Method::GET, "/models", None).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["models"].as_array().unwrap().iter().any(|m| m == "echo"));
+    assert_eq!(body["aliases"]["gpt-4o-mini"], "echo");
+    assert!(body["variants"].as_array().unwrap().iter().any(|v| v == "slow"));
+});
+
+teenytiny_test!(async fn test_admin_requires_server_key() {
+    let key = new_api_key().await;
+
+    for (method, path) in [(Method::GET, "/models"), (Method::POST, "/keys"), (Method::POST, "/usage/reset")] {
+        let (status, body) = admin_as(&key, method, path, None).await;
+        assert_eq!(status, StatusCode::FORBIDDEN, "{} accepted a non-admin key", path);
+        assert_eq!(body["error"]["code"], "admin_required");
+    }
+
+    let response = crate::http_client().get(format!("{}/admin/models", base_url())).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+});
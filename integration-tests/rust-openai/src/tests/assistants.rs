@@ -0,0 +1,253 @@
+// The Assistants API: assistants, threads and messages kept per key, and runs
+// that answer a thread in the background with the mock models. Tests poll runs
+// as a client would; the tooluse model drives the tool call round trip.
+
+use std::time::{Duration, Instant};
+
+use async_openai::{
+    config::OpenAIConfig,
+    error::OpenAIError,
+    types::{
+        AssistantObject, AssistantTools, AssistantToolsFunction, CreateAssistantRequestArgs,
+        CreateMessageRequestArgs, CreateRunRequestArgs, CreateThreadAndRunRequestArgs,
+        CreateThreadRequestArgs, FunctionObjectArgs, MessageContent, MessageObject, MessageRole,
+        ModifyAssistantRequestArgs, RunObject, RunStatus, StepDetails, SubmitToolOutputsRunRequest,
+        ToolsOutputs,
+    },
+    Client,
+};
+use serde_json::json;
+
+use super::{client_for, new_api_key};
+
+fn weather_tool() -> AssistantTools {
+    AssistantTools::Function(AssistantToolsFunction {
+        function: FunctionObjectArgs::default()
+            .name("get_weather")
+            .description("Current weather for a city")
+            .parameters(json!({
+                "type": "object",
+                "properties": { "city": { "type": "string" } },
+                "required": ["city"],
+            }))
+            .build()
+            .unwrap(),
+    })
+}
+
+async fn create_assistant(client: &Client<OpenAIConfig>, model: &str, tools: Vec<AssistantTools>) -> AssistantObject {
+    let request = CreateAssistantRequestArgs::default()
+        .model(model)
+        .name("Test assistant")
+        .instructions("Answer briefly")
+        .tools(tools)
+        .build()
+        .unwrap();
+    client.assistants().create(request).await.unwrap()
+}
+
+async fn thread_saying(client: &Client<OpenAIConfig>, content: &str) -> String {
+    let message = CreateMessageRequestArgs::default()
+        .role(MessageRole::User)
+        .content(content)
+        .build()
+        .unwrap();
+    let request = CreateThreadRequestArgs::default().messages(vec![message]).build().unwrap();
+    client.threads().create(request).await.unwrap().id
+}
+
+async fn start_run(client: &Client<OpenAIConfig>, thread_id: &str, assistant_id: &str) -> RunObject {
+    let request = CreateRunRequestArgs::default().assistant_id(assistant_id).build().unwrap();
+    client.threads().runs(thread_id).create(request).await.unwrap()
+}
+
+// Polls a run until it stops moving, as clients do
+async fn settle(client: &Client<OpenAIConfig>, run: &RunObject) -> RunObject {
+    let deadline = Instant::now() + Duration::from_secs(30);
+    loop {
+        let current = client.threads().runs(&run.thread_id).retrieve(&run.id).await.unwrap();
+        if !matches!(current.status, RunStatus::Queued | RunStatus::InProgress | RunStatus::Cancelling) {
+            return current;
+        }
+        assert!(Instant::now() < deadline, "Run {} still {:?} after 30s", run.id, current.status);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+}
+
+fn text_of(message: &MessageObject) -> &str {
+    match &message.content[0] {
+        MessageContent::Text(text) => &text.text.value,
+        other => panic!("Expected text content, got {:?}", other),
+    }
+}
+
+teenytiny_test!(async fn test_run_answers_the_thread(client) {
+    let assistant = create_assistant(&client, "echo", vec![]).await;
+    assert_eq!(assistant.model, "echo");
+    assert_eq!(assistant.instructions.as_deref(), Some("Answer briefly"));
+    let thread_id = thread_saying(&client, "Hello assistant").await;
+
+    let run = start_run(&client, &thread_id, &assistant.id).await;
+    assert_eq!(run.status, RunStatus::Queued);
+    assert_eq!(run.assistant_id.as_deref(), Some(assistant.id.as_str()));
+
+    let done = settle(&client, &run).await;
+    assert_eq!(done.status, RunStatus::Completed);
+    assert!(done.completed_at.is_some());
+    assert!(done.usage.unwrap().total_tokens > 0);
+
+    let messages = client.threads().messages(&thread_id).list(&[("order", "asc")]).await.unwrap();
+    assert_eq!(messages.data.len(), 2);
+    assert_eq!(messages.data[0].role, MessageRole::User);
+    assert_eq!(messages.data[1].role, MessageRole::Assistant);
+    assert_eq!(text_of(&messages.data[1]), "Hello assistant");
+    assert_eq!(messages.data[1].run_id.as_deref(), Some(done.id.as_str()));
+});
+
+teenytiny_test!(requires(Tools) async fn test_tool_call_round_trip(client) {
+    let assistant = create_assistant(&client, "tooluse", vec![weather_tool()]).await;
+    let thread_id = thread_saying(&client, "What's the weather in Paris?").await;
+
+    let waiting = settle(&client, &start_run(&client, &thread_id, &assistant.id).await).await;
+    assert_eq!(waiting.status, RunStatus::RequiresAction);
+    let calls = waiting.required_action.unwrap().submit_tool_outputs.tool_calls;
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].r#type, "function");
+    assert_eq!(calls[0].function.name, "get_weather");
+    let arguments: serde_json::Value = serde_json::from_str(&calls[0].function.arguments).unwrap();
+    assert!(arguments["city"].is_string(), "Unexpected arguments: {}", arguments);
+
+    let resumed = client
+        .threads()
+        .runs(&thread_id)
+        .submit_tool_outputs(
+            &waiting.id,
+            SubmitToolOutputsRunRequest {
+                tool_outputs: vec![ToolsOutputs {
+                    tool_call_id: Some(calls[0].id.clone()),
+                    output: Some("Sunny, 21C".to_string()),
+                }],
+                stream: None,
+            },
+        )
+        .await
+        .unwrap();
+    let done = settle(&client, &resumed).await;
+    assert_eq!(done.status, RunStatus::Completed);
+
+    let latest = client.threads().messages(&thread_id).list(&[("limit", "1")]).await.unwrap();
+    assert_eq!(text_of(&latest.data[0]), "Sunny, 21C");
+
+    let steps = client.threads().runs(&thread_id).steps(&done.id).list(&[("order", "asc")]).await.unwrap();
+    assert_eq!(steps.data.len(), 2);
+    match &steps.data[0].step_details {
+        StepDetails::ToolCalls(details) => assert_eq!(details.tool_calls.len(), 1),
+        other => panic!("Expected a tool_calls step, got {:?}", other),
+    }
+    match &steps.data[1].step_details {
+        StepDetails::MessageCreation(details) => assert_eq!(details.message_creation.message_id, latest.data[0].id),
+        other => panic!("Expected a message_creation step, got {:?}", other),
+    }
+
+    let step = client.threads().runs(&thread_id).steps(&done.id).retrieve(&steps.data[1].id).await.unwrap();
+    assert_eq!(step.status, RunStatus::Completed);
+});
+
+teenytiny_test!(async fn test_missing_tool_outputs_are_rejected(client) {
+    let assistant = create_assistant(&client, "tooluse", vec![weather_tool()]).await;
+    let thread_id = thread_saying(&client, "Weather?").await;
+    let waiting = settle(&client, &start_run(&client, &thread_id, &assistant.id).await).await;
+
+    let err = client
+        .threads()
+        .runs(&thread_id)
+        .submit_tool_outputs(&waiting.id, SubmitToolOutputsRunRequest { tool_outputs: vec![], stream: None })
+        .await
+        .unwrap_err();
+    match err {
+        OpenAIError::ApiError(err) => assert_eq!(err.param.as_deref(), Some("tool_outputs")),
+        other => panic!("Expected an API error, got {:?}", other),
+    }
+
+    let cancelled = client.threads().runs(&thread_id).cancel(&waiting.id).await.unwrap();
+    assert_eq!(cancelled.status, RunStatus::Cancelled);
+});
+
+teenytiny_test!(async fn test_cancel_a_slow_run(client) {
+    let assistant = create_assistant(&client, "slow", vec![]).await;
+    let thread_id = thread_saying(&client, &"word ".repeat(50)).await;
+    let run = start_run(&client, &thread_id, &assistant.id).await;
+
+    let cancelling = client.threads().runs(&thread_id).cancel(&run.id).await.unwrap();
+    assert!(matches!(cancelling.status, RunStatus::Cancelling | RunStatus::Cancelled), "{:?}", cancelling.status);
+
+    let done = settle(&client, &run).await;
+    assert_eq!(done.status, RunStatus::Cancelled);
+    assert!(done.cancelled_at.is_some());
+});
+
+teenytiny_test!(async fn test_create_thread_and_run(client) {
+    let assistant = create_assistant(&client, "echo", vec![]).await;
+    let message = CreateMessageRequestArgs::default()
+        .role(MessageRole::User)
+        .content("All in one call")
+        .build()
+        .unwrap();
+    let request = CreateThreadAndRunRequestArgs::default()
+        .assistant_id(&assistant.id)
+        .thread(CreateThreadRequestArgs::default().messages(vec![message]).build().unwrap())
+        .build()
+        .unwrap();
+
+    let run = client.threads().create_and_run(request).await.unwrap();
+    let done = settle(&client, &run).await;
+    assert_eq!(done.status, RunStatus::Completed);
+
+    let replies = client
+        .threads()
+        .messages(&run.thread_id)
+        .list(&[("run_id", run.id.as_str())])
+        .await
+        .unwrap();
+    assert_eq!(replies.data.len(), 1);
+    assert_eq!(text_of(&replies.data[0]), "All in one call");
+});
+
+teenytiny_test!(async fn test_update_list_and_delete_assistants() {
+    // A fresh key sees only its own assistants
+    let client = client_for(&new_api_key().await);
+    let first = create_assistant(&client, "echo", vec![]).await;
+    let second = create_assistant(&client, "eliza", vec![]).await;
+
+    let listed = client.assistants().list(&[("limit", "10")]).await.unwrap();
+    let ids: Vec<&str> = listed.data.iter().map(|assistant| assistant.id.as_str()).collect();
+    assert_eq!(ids, vec![second.id.as_str(), first.id.as_str()]);
+
+    let request = ModifyAssistantRequestArgs::default().name("Renamed").build().unwrap();
+    let updated = client.assistants().update(&first.id, request).await.unwrap();
+    assert_eq!(updated.name.as_deref(), Some("Renamed"));
+    assert_eq!(updated.model, "echo");
+
+    let deleted = client.assistants().delete(&first.id).await.unwrap();
+    assert!(deleted.deleted);
+    assert!(client.assistants().retrieve(&first.id).await.is_err());
+});
+
+teenytiny_test!(async fn test_unknown_model_is_rejected(client) {
+    let request = CreateAssistantRequestArgs::default().model("no-such-model").build().unwrap();
+
+    match client.assistants().create(request).await {
+        Err(OpenAIError::ApiError(err)) => assert_eq!(err.code.as_deref(), Some("model_not_found")),
+        other => panic!("Expected an API error, got {:?}", other),
+    }
+});
+
+teenytiny_test!(async fn test_one_active_run_per_thread(client) {
+    let assistant = create_assistant(&client, "tooluse", vec![weather_tool()]).await;
+    let thread_id = thread_saying(&client, "Weather?").await;
+    settle(&client, &start_run(&client, &thread_id, &assistant.id).await).await;
+
+    let request = CreateRunRequestArgs::default().assistant_id(&assistant.id).build().unwrap();
+    let err = client.threads().runs(&thread_id).create(request).await.unwrap_err();
+    assert!(matches!(err, OpenAIError::ApiError(_)), "Unexpected error: {:?}", err);
+});
@@ -0,0 +1,177 @@
+use async_openai::types::{
+    AudioInput, AudioResponseFormat, CreateSpeechRequestArgs, CreateTranscriptionRequestArgs,
+    SpeechModel, SpeechResponseFormat, Voice,
+};
+use reqwest::multipart::{Form, Part};
+use reqwest::StatusCode;
+
+use crate::{api_key, base_url};
+
+const SAMPLE_RATE: u32 = 16000;
+
+// Builds a mono 16-bit PCM WAV file of silence, small enough to upload quickly
+fn silent_wav(duration_ms: u32) -> Vec<u8> {
+    let data_len = SAMPLE_RATE * 2 * duration_ms / 1000;
+
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes()); // fmt chunk size
+    wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&1u16.to_le_bytes()); // mono
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes()); // byte rate
+    wav.extend_from_slice(&2u16.to_le_bytes()); // block align
+    wav.extend_from_slice(&16u16.to_le_bytes()); // bits per sample
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    wav.resize(44 + data_len as usize, 0);
+    wav
+}
+
+// Uploads the WAV fixture with raw multipart, for formats async-openai doesn't parse
+async fn transcribe_raw(response_format: &str) -> (StatusCode, String) {
+    let file = Part::bytes(silent_wav(1500))
+        .file_name("hello.wav")
+        .mime_str("audio/wav")
+        .unwrap();
+
+    let form = Form::new()
+        .part("file", file)
+        .text("model", "whisper-1")
+        .text("response_format", response_format.to_string());
+
+    let response = crate::http_client()
+        .post(format!("{}/v1/audio/transcriptions", base_url()))
+        .bearer_auth(api_key())
+        .multipart(form)
+        .send()
+        .await
+        .unwrap();
+
+    let status = response.status();
+    (status, response.text().await.unwrap())
+}
+
+teenytiny_test!(async fn test_transcription_json(client) {
+    let request = CreateTranscriptionRequestArgs::default()
+        .file(AudioInput::from_vec_u8("hello.wav".to_string(), silent_wav(1500)))
+        .model("whisper-1")
+        .build().unwrap();
+
+    let response = client.audio().transcribe(request).await.unwrap();
+
+    assert!(!response.text.is_empty(), "Transcript should not be empty");
+});
+
+teenytiny_test!(async fn test_transcription_verbose_json(client) {
+    let request = CreateTranscriptionRequestArgs::default()
+        .file(AudioInput::from_vec_u8("hello.wav".to_string(), silent_wav(1500)))
+        .model("whisper-1")
+        .response_format(AudioResponseFormat::VerboseJson)
+        .build().unwrap();
+
+    let response = client.audio().transcribe_verbose_json(request).await.unwrap();
+
+    assert!(!response.text.is_empty(), "Transcript should not be empty");
+    assert!((response.duration - 1.5).abs() < 0.01, "Expected duration 1.5s, got {}", response.duration);
+
+    let segments = response.segments.expect("verbose_json should include segments");
+    assert!(!segments.is_empty(), "Expected at least one segment");
+    assert_eq!(segments[0].text, response.text);
+});
+
+teenytiny_test!(async fn test_transcription_text() {
+    let (status, body) = transcribe_raw("text").await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert!(!body.trim().is_empty(), "Transcript should not be empty");
+    assert!(!body.trim_start().starts_with('{'), "text format should not be JSON, got: {}", body);
+});
+
+teenytiny_test!(async fn test_transcription_srt() {
+    let (status, body) = transcribe_raw("srt").await;
+
+    assert_eq!(status, StatusCode::OK);
+
+    let lines: Vec<&str> = body.lines().collect();
+    assert_eq!(lines[0], "1", "SRT should start with a cue number, got: {}", body);
+    assert_eq!(lines[1], "00:00:00,000 --> 00:00:01,500");
+    assert!(!lines[2].is_empty(), "SRT cue should contain the transcript");
+});
+
+teenytiny_test!(async fn test_transcription_invalid_response_format() {
+    let (status, body) = transcribe_raw("docx").await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body.contains("response_format"), "Expected error naming response_format, got: {}", body);
+});
+
+teenytiny_test!(async fn test_transcription_missing_file() {
+    let form = Form::new().text("model", "whisper-1");
+
+    let response = crate::http_client()
+        .post(format!("{}/v1/audio/transcriptions", base_url()))
+        .bearer_auth(api_key())
+        .multipart(form)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["error"]["param"], "file");
+});
+
+teenytiny_test!(async fn test_speech_default_format_is_mp3(client) {
+    let request = CreateSpeechRequestArgs::default()
+        .model(SpeechModel::Tts1)
+        .input("Hello World")
+        .voice(Voice::Alloy)
+        .build().unwrap();
+
+    let response = client.audio().speech(request).await.unwrap();
+
+    assert!(!response.bytes.is_empty(), "Expected audio bytes");
+    assert_eq!(&response.bytes[..2], &[0xff, 0xfb], "Expected an MPEG audio frame header");
+});
+
+teenytiny_test!(async fn test_speech_wav_format(client) {
+    let request = CreateSpeechRequestArgs::default()
+        .model(SpeechModel::Tts1)
+        .input("Hello World")
+        .voice(Voice::Alloy)
+        .response_format(SpeechResponseFormat::Wav)
+        .build().unwrap();
+
+    let response = client.audio().speech(request).await.unwrap();
+
+    assert_eq!(&response.bytes[..4], b"RIFF");
+    assert_eq!(&response.bytes[8..12], b"WAVE");
+});
+
+teenytiny_test!(async fn test_speech_content_types() {
+    for (format, content_type) in [("mp3", "audio/mpeg"), ("wav", "audio/wav"), ("pcm", "audio/pcm")] {
+        let response = crate::http_client()
+            .post(format!("{}/v1/audio/speech", base_url()))
+            .bearer_auth(api_key())
+            .json(&serde_json::json!({
+                "model": "tts-1",
+                "input": "Content type test",
+                "voice": "alloy",
+                "response_format": format,
+            }))
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()["content-type"], content_type,
+            "Wrong content-type for {}", format
+        );
+        assert!(!response.bytes().await.unwrap().is_empty(), "Expected audio bytes for {}", format);
+    }
+});
@@ -1,21 +1,18 @@
 use async_openai::{config::OpenAIConfig, types::CreateChatCompletionRequestArgs, Client};
 use futures::StreamExt;
-use std::env;
 
+use crate::{api_key, base_url};
 use super::user_message;
 
 fn setup_client_with_key(api_key: &str) -> Client<OpenAIConfig> {
-    let base_url = env::var("TEENYTINY_URL").unwrap_or_else(|_| "http://localhost:8080".to_string());
-    
     let config = OpenAIConfig::new()
         .with_api_key(api_key)
-        .with_api_base(format!("{}/v1", base_url));
+        .with_api_base(format!("{}/v1", base_url()));
 
-    Client::with_config(config)
+    Client::with_config(config).with_http_client(crate::http_client())
 }
 
-#[tokio::test]
-async fn test_missing_api_key() {
+teenytiny_test!(async fn test_missing_api_key() {
     let client = setup_client_with_key("");  // Empty API key
 
     let request = CreateChatCompletionRequestArgs::default()
@@ -32,10 +29,9 @@ async fn test_missing_api_key() {
         error_msg.contains("401") || error_msg.contains("Unauthorized") || error_msg.contains("authentication"),
         "Expected 401/authentication error, got: {}", error_msg
     );
-}
+});
 
-#[tokio::test]
-async fn test_invalid_api_key() {
+teenytiny_test!(async fn test_invalid_api_key() {
     let client = setup_client_with_key("invalid-key-12345");
 
     let request = CreateChatCompletionRequestArgs::default()
@@ -52,11 +48,10 @@ async fn test_invalid_api_key() {
         error_msg.contains("401") || error_msg.contains("Unauthorized") || error_msg.contains("authentication"),
         "Expected 401/authentication error, got: {}", error_msg
     );
-}
+});
 
-#[tokio::test]
-async fn test_empty_messages_array() {
-    let client = setup_client_with_key("testkey");
+teenytiny_test!(async fn test_empty_messages_array() {
+    let client = setup_client_with_key(&api_key());
 
     let request = CreateChatCompletionRequestArgs::default()
         .model("echo")
@@ -72,10 +67,9 @@ async fn test_empty_messages_array() {
         error_msg.contains("400") || error_msg.contains("Bad Request") || error_msg.contains("messages"),
         "Expected 400/validation error, got: {}", error_msg
     );
-}
+});
 
-#[tokio::test]
-async fn test_streaming_with_invalid_api_key() {
+teenytiny_test!(async fn test_streaming_with_invalid_api_key() {
     let client = setup_client_with_key("invalid-streaming-key");
 
     let request = CreateChatCompletionRequestArgs::default()
@@ -103,4 +97,74 @@ async fn test_streaming_with_invalid_api_key() {
             // This is also acceptable - error during stream creation
         },
     }
-}
\ No newline at end of file
+});
+
+// Authorization header forms. teenytiny accepts "Bearer <key>" with the scheme
+// in any case and surrounding whitespace ignored. A bare key, the Azure-style
+// api-key header, and a key in the query string are all rejected with 401.
+
+async fn models_status(request: reqwest::RequestBuilder) -> reqwest::StatusCode {
+    request.send().await.unwrap().status()
+}
+
+fn models_request() -> reqwest::RequestBuilder {
+    crate::http_client().get(format!("{}/v1/models", base_url()))
+}
+
+teenytiny_test!(async fn test_bearer_with_trailing_whitespace_accepted() {
+    let status = models_status(
+        models_request().header("Authorization", format!("Bearer {}   ", api_key()))
+    ).await;
+
+    assert_eq!(status, reqwest::StatusCode::OK);
+});
+
+teenytiny_test!(async fn test_lowercase_bearer_accepted() {
+    let status = models_status(
+        models_request().header("Authorization", format!("bearer {}", api_key()))
+    ).await;
+
+    assert_eq!(status, reqwest::StatusCode::OK);
+});
+
+teenytiny_test!(async fn test_missing_scheme_rejected() {
+    let status = models_status(
+        models_request().header("Authorization", api_key())
+    ).await;
+
+    assert_eq!(status, reqwest::StatusCode::UNAUTHORIZED);
+});
+
+teenytiny_test!(async fn test_other_scheme_rejected() {
+    let status = models_status(
+        models_request().basic_auth(api_key(), None::<&str>)
+    ).await;
+
+    assert_eq!(status, reqwest::StatusCode::UNAUTHORIZED);
+});
+
+teenytiny_test!(async fn test_azure_api_key_header_rejected() {
+    let status = models_status(
+        models_request().header("api-key", api_key())
+    ).await;
+
+    assert_eq!(status, reqwest::StatusCode::UNAUTHORIZED);
+});
+
+teenytiny_test!(async fn test_query_parameter_key_rejected() {
+    let status = models_status(
+        models_request().query(&[("api_key", api_key())])
+    ).await;
+
+    assert_eq!(status, reqwest::StatusCode::UNAUTHORIZED);
+});
+
+teenytiny_test!(async fn test_rejected_header_forms_use_error_envelope() {
+    let response = models_request()
+        .header("Authorization", api_key())
+        .send().await.unwrap();
+
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["error"]["type"], "authentication_error");
+    assert_eq!(body["error"]["code"], "invalid_api_key");
+});
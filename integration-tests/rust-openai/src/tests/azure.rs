@@ -0,0 +1,113 @@
+// Azure OpenAI's URL shape: the deployment is named in the path, the API
+// version in the query and the key in an api-key header, all of which
+// async-openai's AzureConfig takes care of. A deployment without a mapping on
+// the server is served by the model of the same name.
+
+use async_openai::{config::AzureConfig, error::OpenAIError, types::CreateChatCompletionRequestArgs, Client};
+use futures::StreamExt;
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+
+use crate::{api_key, base_url, http_client};
+use super::user_message;
+
+const API_VERSION: &str = "2024-10-21";
+
+fn azure_client(deployment: &str, key: &str) -> Client<AzureConfig> {
+    let config = AzureConfig::new()
+        .with_api_base(base_url())
+        .with_api_version(API_VERSION)
+        .with_deployment_id(deployment)
+        .with_api_key(key);
+
+    Client::with_config(config).with_http_client(http_client())
+}
+
+teenytiny_test!(async fn test_chat_completion_through_a_deployment() {
+    let client = azure_client("echo", &api_key());
+
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("ignored-by-azure")
+        .messages([user_message("Hello from Azure")])
+        .build()
+        .unwrap();
+
+    let response = client.chat().create(request).await.unwrap();
+    assert_eq!(response.model, "echo");
+    assert_eq!(response.choices[0].message.content.as_deref(), Some("Hello from Azure"));
+    assert!(response.usage.unwrap().completion_tokens > 0);
+});
+
+teenytiny_test!(async fn test_streaming_through_a_deployment() {
+    let client = azure_client("echo", &api_key());
+
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("ignored-by-azure")
+        .messages([user_message("Streamed from Azure")])
+        .stream(true)
+        .build()
+        .unwrap();
+
+    let mut stream = client.chat().create_stream(request).await.unwrap();
+    let mut content = String::new();
+    while let Some(result) = stream.next().await {
+        let chunk = result.unwrap();
+        assert_eq!(chunk.model, "echo");
+        if let Some(text) = chunk.choices.first().and_then(|choice| choice.delta.content.as_ref()) {
+            content.push_str(text);
+        }
+    }
+    assert_eq!(content, "Streamed from Azure");
+});
+
+teenytiny_test!(async fn test_deployment_picks_the_model() {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("echo")
+        .messages([user_message("I feel tired")])
+        .build()
+        .unwrap();
+
+    let response = azure_client("eliza", &api_key()).chat().create(request).await.unwrap();
+    assert_eq!(response.model, "eliza");
+    assert_ne!(response.choices[0].message.content.as_deref(), Some("I feel tired"));
+});
+
+teenytiny_test!(async fn test_invalid_api_key() {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("echo")
+        .messages([user_message("Hi")])
+        .build()
+        .unwrap();
+
+    match azure_client("echo", "invalid-key-12345").chat().create(request).await {
+        Err(OpenAIError::ApiError(error)) => assert_eq!(error.r#type.as_deref(), Some("authentication_error")),
+        other => panic!("Expected an authentication error, got {:?}", other),
+    }
+});
+
+teenytiny_test!(async fn test_unknown_deployment() {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("echo")
+        .messages([user_message("Hi")])
+        .build()
+        .unwrap();
+
+    match azure_client("no-such-deployment", &api_key()).chat().create(request).await {
+        Err(OpenAIError::ApiError(error)) => assert!(error.message.contains("no-such-deployment"), "{}", error.message),
+        other => panic!("Expected a model not found error, got {:?}", other),
+    }
+});
+
+teenytiny_test!(async fn test_missing_api_version_is_rejected() {
+    let response = http_client()
+        .post(format!("{}/openai/deployments/echo/chat/completions", base_url()))
+        .header("api-key", api_key())
+        .json(&json!({"messages": [{"role": "user", "content": "Hi"}]}))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body: Value = response.json().await.unwrap();
+    assert!(body["error"]["message"].as_str().unwrap().contains("api-version"), "{}", body);
+});
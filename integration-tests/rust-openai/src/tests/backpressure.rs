@@ -0,0 +1,127 @@
+// Opens more streams at once than the server lets through and checks the one
+// over the limit is turned away at once with a 503 and Retry-After, rather
+// than left hanging until the client gives up. Each test mints its own key and
+// sets a limit of two through the x-teenytiny-max-streams header, so it doesn't
+// depend on how many streams the server itself allows, nor hold back the
+// streams of other tests.
+
+use std::time::{Duration, Instant};
+
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde_json::{json, Value};
+
+use crate::base_url;
+use super::new_api_key;
+
+const LIMIT_HEADER: &str = "x-teenytiny-max-streams";
+const LIMIT: usize = 2;
+
+// A slow:100 stream of these words takes two seconds, long enough to still be
+// open while the next request is made
+const TEXT: &str = "one two three four five six seven eight nine ten \
+    eleven twelve thirteen fourteen fifteen sixteen seventeen eighteen nineteen twenty";
+
+fn stream_request(api_key: &str, limit: Option<usize>) -> RequestBuilder {
+    let mut request = crate::http_client()
+        .post(format!("{}/v1/chat/completions", base_url()))
+        .bearer_auth(api_key)
+        .json(&json!({
+            "model": "slow:100",
+            "messages": [{"role": "user", "content": TEXT}],
+            "stream": true,
+        }));
+    if let Some(limit) = limit {
+        request = request.header(LIMIT_HEADER, limit.to_string());
+    }
+    request
+}
+
+// Sends a request, failing rather than hanging if the server sits on it
+async fn send(request: RequestBuilder) -> Response {
+    tokio::time::timeout(Duration::from_secs(5), request.send())
+        .await
+        .expect("The server didn't answer within 5 seconds")
+        .unwrap()
+}
+
+// Opens streams up to the limit and leaves them open
+async fn fill(api_key: &str) -> Vec<Response> {
+    let mut open = Vec::new();
+    for _ in 0..LIMIT {
+        let response = send(stream_request(api_key, Some(LIMIT))).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        open.push(response);
+    }
+    open
+}
+
+async fn metrics() -> Value {
+    reqwest::get(format!("{}/metrics", base_url())).await.unwrap().json().await.unwrap()
+}
+
+async fn assert_overloaded(response: Response) {
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let retry_after: u64 = response.headers().get("retry-after")
+        .expect("Missing retry-after header")
+        .to_str().unwrap()
+        .parse().expect("Retry-After should be a number of seconds");
+    assert!((1..=60).contains(&retry_after), "Unexpected Retry-After: {}", retry_after);
+
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["error"]["type"], "overloaded_error", "Unexpected error: {}", body);
+}
+
+teenytiny_test!(async fn test_stream_over_the_limit_gets_503_with_retry_after() {
+    let api_key = new_api_key().await;
+    let before = metrics().await;
+    let _open = fill(&api_key).await;
+
+    let started = Instant::now();
+    assert_overloaded(send(stream_request(&api_key, Some(LIMIT))).await).await;
+    // Turned away at once, not after waiting for a place to free up
+    assert!(started.elapsed() < Duration::from_secs(1), "The 503 took {:?}", started.elapsed());
+
+    let after = metrics().await;
+    assert!(after.get("max_streams").is_some(), "No max_streams in /metrics: {}", after);
+    let rejected = |metrics: &Value| metrics["rejected_streams"].as_u64().expect("No rejected_streams in /metrics");
+    assert!(rejected(&after) > rejected(&before), "The 503 wasn't counted in rejected_streams");
+});
+
+teenytiny_test!(async fn test_closed_streams_give_back_their_place() {
+    let api_key = new_api_key().await;
+    let mut open = fill(&api_key).await;
+    assert_overloaded(send(stream_request(&api_key, Some(LIMIT))).await).await;
+
+    // The server only notices the client left at its next chunk, so retry as
+    // a client honouring Retry-After would, if more eagerly
+    drop(open.pop());
+    let deadline = Instant::now() + Duration::from_secs(3);
+    loop {
+        let response = send(stream_request(&api_key, Some(LIMIT))).await;
+        if response.status() == StatusCode::OK {
+            break;
+        }
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(Instant::now() < deadline, "The closed stream's place was never given back");
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+});
+
+teenytiny_test!(async fn test_limit_only_holds_back_its_own_streams() {
+    let api_key = new_api_key().await;
+    let _open = fill(&api_key).await;
+
+    // The same key without the header, or another key with it, isn't held back
+    let response = send(stream_request(&api_key, None)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = send(stream_request(&new_api_key().await, Some(LIMIT))).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Nor are requests that don't stream
+    let response = send(crate::http_client()
+        .post(format!("{}/v1/chat/completions", base_url()))
+        .bearer_auth(&api_key)
+        .header(LIMIT_HEADER, LIMIT.to_string())
+        .json(&json!({"model": "echo", "messages": [{"role": "user", "content": "Not streamed"}]}))).await;
+    assert_eq!(response.status(), StatusCode::OK);
+});
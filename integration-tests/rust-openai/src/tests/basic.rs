@@ -1,12 +1,9 @@
-use async_openai::types::{ChatCompletionRequestMessage, ChatCompletionRequestAssistantMessageArgs, Role, CreateChatCompletionRequestArgs, FinishReason};
+use async_openai::types::{Role, CreateChatCompletionRequestArgs, FinishReason};
 
-use crate::setup_client;
+use crate::fixtures::{unicode_corpus, Conversation, Language};
 use super::{user_message, system_message};
 
-#[tokio::test]
-async fn test_basic_completion() {
-    let client = setup_client();
-
+teenytiny_test!(async fn test_basic_completion(client) {
     let request = CreateChatCompletionRequestArgs::default()
         .model("echo")
         .messages([user_message("Hello World")])
@@ -26,25 +23,14 @@ async fn test_basic_completion() {
     
     let total_tokens = response.usage.as_ref().map(|u| u.total_tokens).unwrap_or(0);
     assert!(total_tokens > 0, "Expected total_tokens > 0");
-}
-
-#[tokio::test]
-async fn test_multi_message_conversation() {
-    let client = setup_client();
+});
 
-    let assistant_message: ChatCompletionRequestMessage = ChatCompletionRequestAssistantMessageArgs::default()
-        .content("First response")
-        .build().unwrap()
-        .into();
+teenytiny_test!(async fn test_multi_message_conversation(client) {
+    let conversation = Conversation::new().system("You are a helpful assistant.").turns(5);
 
     let request = CreateChatCompletionRequestArgs::default()
         .model("echo")
-        .messages([
-            system_message("You are a helpful assistant."),
-            user_message("First message"),
-            assistant_message,
-            user_message("Last message"),
-        ])
+        .messages(conversation.messages())
         .build().unwrap();
 
     let response = client.chat().create(request).await.unwrap();
@@ -52,19 +38,33 @@ async fn test_multi_message_conversation() {
     let content = response.choices[0].message.content.as_ref()
         .expect("No content in response");
 
-    assert_eq!(content, "Last message");
-}
+    assert_eq!(content, &conversation.last_user_message());
+});
+
+teenytiny_test!(async fn test_conversations_in_every_language(client) {
+    for language in Language::ALL {
+        let conversation = Conversation::new().turns(3).language(language);
+
+        let request = CreateChatCompletionRequestArgs::default()
+            .model("echo")
+            .messages(conversation.messages())
+            .build().unwrap();
+
+        let response = client.chat().create(request).await.unwrap();
 
-#[tokio::test]
-async fn test_system_prompt_with_user_message() {
-    let client = setup_client();
+        let content = response.choices[0].message.content.as_ref()
+            .expect("No content in response");
+
+        assert_eq!(content, &conversation.last_user_message(), "Echoed {:?} differently", language);
+    }
+});
+
+teenytiny_test!(async fn test_system_prompt_with_user_message(client) {
+    let conversation = Conversation::new().system("You are a helpful assistant.");
 
     let request = CreateChatCompletionRequestArgs::default()
         .model("echo")
-        .messages([
-            system_message("You are a helpful assistant."),
-            user_message("Test message"),
-        ])
+        .messages(conversation.messages())
         .build().unwrap();
 
     let response = client.chat().create(request).await.unwrap();
@@ -73,13 +73,10 @@ async fn test_system_prompt_with_user_message() {
         .expect("No content in response");
 
     // Echo model should return the user message, ignoring system prompt
-    assert_eq!(content, "Test message");
-}
-
-#[tokio::test]
-async fn test_system_only_returns_default() {
-    let client = setup_client();
+    assert_eq!(content, &conversation.last_user_message());
+});
 
+teenytiny_test!(async fn test_system_only_returns_default(client) {
     let request = CreateChatCompletionRequestArgs::default()
         .model("echo")
         .messages([system_message("You are a helpful assistant.")])
@@ -92,12 +89,9 @@ async fn test_system_only_returns_default() {
 
     // Should get the default echo model greeting
     assert!(content.contains("Echo model"), "Expected content to contain 'Echo model', got '{}'", content);
-}
-
-#[tokio::test]
-async fn test_response_structure() {
-    let client = setup_client();
+});
 
+teenytiny_test!(async fn test_response_structure(client) {
     let request = CreateChatCompletionRequestArgs::default()
         .model("echo")
         .messages([user_message("Structure test")])
@@ -131,12 +125,9 @@ async fn test_response_structure() {
     } else {
         panic!("Usage should be present");
     }
-}
-
-#[tokio::test]
-async fn test_empty_message_handling() {
-    let client = setup_client();
+});
 
+teenytiny_test!(async fn test_empty_message_handling(client) {
     let request = CreateChatCompletionRequestArgs::default()
         .model("echo")
         .messages([user_message("")])
@@ -147,16 +138,14 @@ async fn test_empty_message_handling() {
     // Empty input should be handled gracefully
     let _content = response.choices[0].message.content.as_ref()
         .expect("Should have content even for empty input");
-}
+});
 
-#[tokio::test]
-async fn test_special_characters_and_unicode() {
-    let client = setup_client();
-    let test_message = "Hello! 🌟 Special chars: @#$%^&*()_+-={}[]|\\:;\"'<>?,./ 中文";
+teenytiny_test!(async fn test_multiline_content(client) {
+    let multiline_message = "Line 1\nLine 2\nLine 3 with more content\nFinal line";
 
     let request = CreateChatCompletionRequestArgs::default()
         .model("echo")
-        .messages([user_message(test_message)])
+        .messages([user_message(multiline_message)])
         .build().unwrap();
 
     let response = client.chat().create(request).await.unwrap();
@@ -164,17 +153,15 @@ async fn test_special_characters_and_unicode() {
     let content = response.choices[0].message.content.as_ref()
         .expect("No content in response");
 
-    assert_eq!(content, test_message);
-}
+    assert_eq!(content, multiline_message);
+});
 
-#[tokio::test]
-async fn test_multiline_content() {
-    let client = setup_client();
-    let multiline_message = "Line 1\nLine 2\nLine 3 with more content\nFinal line";
+teenytiny_test!(async fn test_unicode_corpus_round_trip(client) {
+    let corpus = unicode_corpus(20_000, 1);
 
     let request = CreateChatCompletionRequestArgs::default()
         .model("echo")
-        .messages([user_message(multiline_message)])
+        .messages([user_message(&corpus)])
         .build().unwrap();
 
     let response = client.chat().create(request).await.unwrap();
@@ -182,5 +169,5 @@ async fn test_multiline_content() {
     let content = response.choices[0].message.content.as_ref()
         .expect("No content in response");
 
-    assert_eq!(content, multiline_message);
-}
\ No newline at end of file
+    assert_eq!(content, &corpus);
+});
\ No newline at end of file
@@ -0,0 +1,242 @@
+// The Batch API: a JSONL file of requests is uploaded through /v1/files, run
+// as a batch whose progress is simulated a step at a time, and its results
+// read back from the output and error files. Tests poll as a client would.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use async_openai::{
+    config::OpenAIConfig,
+    error::OpenAIError,
+    types::{
+        Batch, BatchCompletionWindow, BatchEndpoint, BatchRequest, BatchRequestOutput, BatchStatus,
+        CreateFileRequest, FileInput, FilePurpose, OpenAIFilePurpose,
+    },
+    Client,
+};
+use serde_json::{json, Value};
+
+use super::{client_for, new_api_key};
+
+// One line of a batch input file, asking the given model for a chat completion
+fn request_line(custom_id: &str, model: &str, content: &str) -> String {
+    json!({
+        "custom_id": custom_id,
+        "method": "POST",
+        "url": "/v1/chat/completions",
+        "body": { "model": model, "messages": [{ "role": "user", "content": content }] },
+    })
+    .to_string()
+}
+
+async fn upload(client: &Client<OpenAIConfig>, lines: &[String], purpose: FilePurpose) -> String {
+    let request = CreateFileRequest {
+        file: FileInput::from_vec_u8("input.jsonl".to_string(), lines.join("\n").into_bytes()),
+        purpose,
+    };
+    client.files().create(request).await.unwrap().id
+}
+
+async fn create_batch(client: &Client<OpenAIConfig>, input_file_id: &str) -> Result<Batch, OpenAIError> {
+    client
+        .batches()
+        .create(BatchRequest {
+            input_file_id: input_file_id.to_string(),
+            endpoint: BatchEndpoint::V1ChatCompletions,
+            completion_window: BatchCompletionWindow::W24H,
+            metadata: None,
+        })
+        .await
+}
+
+// Polls until the batch stops changing, returning every status seen on the way
+async fn wait_for(client: &Client<OpenAIConfig>, id: &str) -> (Batch, Vec<BatchStatus>) {
+    let deadline = Instant::now() + Duration::from_secs(30);
+    let mut seen = Vec::new();
+    loop {
+        let batch = client.batches().retrieve(id).await.unwrap();
+        if seen.last() != Some(&batch.status) {
+            seen.push(batch.status.clone());
+        }
+        match batch.status {
+            BatchStatus::Completed | BatchStatus::Failed | BatchStatus::Cancelled | BatchStatus::Expired => {
+                return (batch, seen)
+            }
+            _ => {}
+        }
+        assert!(Instant::now() < deadline, "Batch {} still {:?} after 30s", id, batch.status);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
+
+async fn results(client: &Client<OpenAIConfig>, file_id: &str) -> Vec<BatchRequestOutput> {
+    let content = client.files().content(file_id).await.unwrap();
+    String::from_utf8(content.to_vec())
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+teenytiny_test!(async fn test_batch_runs_to_completion(client) {
+    let lines = [request_line("first", "echo", "Hello batch"), request_line("second", "echo", "Goodbye batch")];
+    let input_file_id = upload(&client, &lines, FilePurpose::Batch).await;
+
+    let mut metadata = HashMap::new();
+    metadata.insert("run".to_string(), json!("nightly"));
+    let batch = client
+        .batches()
+        .create(BatchRequest {
+            input_file_id: input_file_id.clone(),
+            endpoint: BatchEndpoint::V1ChatCompletions,
+            completion_window: BatchCompletionWindow::W24H,
+            metadata: Some(metadata),
+        })
+        .await
+        .unwrap();
+    assert_eq!(batch.status, BatchStatus::Validating);
+    assert_eq!(batch.input_file_id, input_file_id);
+    assert_eq!(batch.metadata.unwrap()["run"], "nightly");
+
+    let (done, seen) = wait_for(&client, &batch.id).await;
+    assert_eq!(seen.last(), Some(&BatchStatus::Completed));
+    assert!(seen.contains(&BatchStatus::InProgress), "Never saw in_progress: {:?}", seen);
+    let counts = done.request_counts.unwrap();
+    assert_eq!((counts.total, counts.completed, counts.failed), (2, 2, 0));
+    assert!(done.completed_at.unwrap() >= done.created_at);
+    assert!(done.error_file_id.is_none());
+
+    let output_file_id = done.output_file_id.unwrap();
+    let file = client.files().retrieve(&output_file_id).await.unwrap();
+    assert_eq!(file.purpose, OpenAIFilePurpose::BatchOutput);
+
+    let outputs = results(&client, &output_file_id).await;
+    let replies: Vec<(String, Value)> = outputs
+        .into_iter()
+        .map(|output| {
+            let response = output.response.unwrap();
+            assert_eq!(response.status_code, 200);
+            assert!(!response.request_id.is_empty());
+            (output.custom_id, response.body["choices"][0]["message"]["content"].clone())
+        })
+        .collect();
+    assert_eq!(
+        replies,
+        vec![
+            ("first".to_string(), json!("Hello batch")),
+            ("second".to_string(), json!("Goodbye batch")),
+        ]
+    );
+});
+
+teenytiny_test!(async fn test_failed_requests_go_to_the_error_file(client) {
+    let lines = [request_line("good", "echo", "Fine"), request_line("bad", "no-such-model", "Not fine")];
+    let batch = create_batch(&client, &upload(&client, &lines, FilePurpose::Batch).await).await.unwrap();
+
+    let (done, _) = wait_for(&client, &batch.id).await;
+    assert_eq!(done.status, BatchStatus::Completed);
+    let counts = done.request_counts.unwrap();
+    assert_eq!((counts.completed, counts.failed), (1, 1));
+
+    let errors = results(&client, &done.error_file_id.unwrap()).await;
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].custom_id, "bad");
+    let response = errors[0].response.as_ref().unwrap();
+    assert_eq!(response.status_code, 404);
+    assert_eq!(response.body["error"]["code"], "model_not_found");
+});
+
+teenytiny_test!(async fn test_invalid_input_fails_validation(client) {
+    let lines = [
+        request_line("same", "echo", "One"),
+        request_line("same", "echo", "Two"),
+        "not json".to_string(),
+    ];
+    let batch = create_batch(&client, &upload(&client, &lines, FilePurpose::Batch).await).await.unwrap();
+
+    let (done, _) = wait_for(&client, &batch.id).await;
+    assert_eq!(done.status, BatchStatus::Failed);
+    assert!(done.failed_at.is_some());
+    assert!(done.output_file_id.is_none());
+    let errors: Vec<(String, Option<u32>)> = done
+        .errors
+        .unwrap()
+        .data
+        .into_iter()
+        .map(|error| (error.code, error.line))
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            ("duplicate_custom_id".to_string(), Some(2)),
+            ("invalid_json_line".to_string(), Some(3)),
+        ]
+    );
+});
+
+teenytiny_test!(async fn test_cancel_batch(client) {
+    let lines: Vec<String> = (0..20).map(|i| request_line(&format!("request-{}", i), "echo", "Hi")).collect();
+    let batch = create_batch(&client, &upload(&client, &lines, FilePurpose::Batch).await).await.unwrap();
+
+    let cancelling = client.batches().cancel(&batch.id).await.unwrap();
+    assert_eq!(cancelling.status, BatchStatus::Cancelling);
+    assert!(cancelling.cancelling_at.is_some());
+
+    let (done, _) = wait_for(&client, &batch.id).await;
+    assert_eq!(done.status, BatchStatus::Cancelled);
+    assert!(done.cancelled_at.is_some());
+    assert!(done.request_counts.unwrap().completed < 20);
+
+    let err = client.batches().cancel(&batch.id).await.unwrap_err();
+    assert!(matches!(err, OpenAIError::ApiError(_)), "Unexpected error: {:?}", err);
+});
+
+teenytiny_test!(async fn test_list_batches() {
+    // A fresh key sees only its own batches
+    let client = client_for(&new_api_key().await);
+    let input_file_id = upload(&client, &[request_line("only", "echo", "Hi")], FilePurpose::Batch).await;
+    let older = create_batch(&client, &input_file_id).await.unwrap();
+    let newer = create_batch(&client, &input_file_id).await.unwrap();
+
+    let first = client.batches().list(&[("limit", 1)]).await.unwrap();
+    assert_eq!(first.data.len(), 1);
+    assert_eq!(first.data[0].id, newer.id);
+    assert!(first.has_more);
+
+    let rest = client
+        .batches()
+        .list(&[("limit", "10"), ("after", first.last_id.unwrap().as_str())])
+        .await
+        .unwrap();
+    assert_eq!(rest.data.iter().map(|batch| batch.id.as_str()).collect::<Vec<_>>(), vec![older.id.as_str()]);
+    assert!(!rest.has_more);
+
+    let files = client.files().list(&[("purpose", "batch")]).await.unwrap();
+    assert_eq!(files.data.len(), 1);
+    assert_eq!(files.data[0].id, input_file_id);
+});
+
+teenytiny_test!(async fn test_input_file_needs_batch_purpose(client) {
+    let input_file_id = upload(&client, &[request_line("only", "echo", "Hi")], FilePurpose::Assistants).await;
+
+    match create_batch(&client, &input_file_id).await {
+        Err(OpenAIError::ApiError(err)) => assert_eq!(err.param.as_deref(), Some("input_file_id")),
+        other => panic!("Expected an API error, got {:?}", other),
+    }
+});
+
+teenytiny_test!(async fn test_unknown_batch_is_not_found(client) {
+    let err = client.batches().retrieve("batch_does_not_exist").await.unwrap_err();
+    match err {
+        OpenAIError::ApiError(err) => assert!(err.message.contains("batch_does_not_exist"), "{}", err.message),
+        other => panic!("Expected an API error, got {:?}", other),
+    }
+});
+
+teenytiny_test!(async fn test_deleted_file_is_gone(client) {
+    let file_id = upload(&client, &[request_line("only", "echo", "Hi")], FilePurpose::Batch).await;
+
+    let deleted = client.files().delete(&file_id).await.unwrap();
+    assert!(deleted.deleted);
+    assert!(client.files().retrieve(&file_id).await.is_err());
+});
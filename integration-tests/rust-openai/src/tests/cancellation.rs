@@ -0,0 +1,157 @@
+// Walks away from requests part way through and checks, through the request log
+// and /metrics, that the server noticed and stopped generating. Each test uses
+// its own key, so the last request logged for it is the one it abandoned.
+
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+
+use crate::base_url;
+use super::{last_captured_request, new_api_key};
+
+// Thirty words from a slow:100 model take three seconds to generate
+const TEXT: &str = "one two three four five six seven eight nine ten \
+    eleven twelve thirteen fourteen fifteen sixteen seventeen eighteen nineteen twenty \
+    twenty-one twenty-two twenty-three twenty-four twenty-five twenty-six twenty-seven twenty-eight twenty-nine thirty";
+
+fn completion_body(stream: bool) -> Value {
+    json!({
+        "model": "slow:100",
+        "messages": [{"role": "user", "content": TEXT}],
+        "stream": stream,
+    })
+}
+
+// Polls the request log until the abandoned request shows up; it is only
+// logged once the server has finished with it
+async fn wait_for_logged_request(key: &str) -> Value {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    loop {
+        let captured = last_captured_request(key).await;
+        if !captured.is_null() {
+            return captured;
+        }
+        assert!(Instant::now() < deadline, "Abandoned request was never logged");
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
+
+async fn metrics() -> Value {
+    reqwest::get(format!("{}/metrics", base_url()))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap()
+}
+
+async fn active_stream_ids() -> Vec<Value> {
+    metrics().await["active_stream_ids"].as_array().expect("No active_stream_ids in /metrics").clone()
+}
+
+async fn cancelled_generations() -> u64 {
+    metrics().await["cancelled_generations"].as_u64().expect("No cancelled_generations in /metrics")
+}
+
+teenytiny_test!(async fn test_dropped_stream_stops_generating() {
+    let key = new_api_key().await;
+    let mut response = crate::http_client()
+        .post(format!("{}/v1/chat/completions", base_url()))
+        .bearer_auth(&key)
+        .json(&completion_body(true))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    // Read a few words, then hang up
+    let started = Instant::now();
+    let mut received = String::new();
+    while received.matches("data: ").count() < 3 {
+        let bytes = response.chunk().await.unwrap().expect("Stream ended early");
+        received.push_str(&String::from_utf8_lossy(&bytes));
+    }
+    drop(response);
+
+    let captured = wait_for_logged_request(&key).await;
+    assert_eq!(captured["stream"], true);
+    assert_eq!(captured["cancelled"], true, "Disconnect not detected: {}", captured);
+    assert!(
+        started.elapsed() < Duration::from_secs(2),
+        "Stream kept generating for {:?} after the client left",
+        started.elapsed()
+    );
+
+    let id = &captured["id"];
+    assert!(
+        !active_stream_ids().await.contains(id),
+        "Stream {} still active after the client left",
+        id
+    );
+});
+
+teenytiny_test!(async fn test_dropped_stream_counts_as_a_cancelled_generation() {
+    let before = cancelled_generations().await;
+    let mut response = crate::http_client()
+        .post(format!("{}/v1/chat/completions", base_url()))
+        .bearer_auth(new_api_key().await)
+        .json(&completion_body(true))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    response.chunk().await.unwrap().expect("Stream ended early");
+    drop(response);
+
+    // Other tests may cancel generations too, so the count only has to rise
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while cancelled_generations().await <= before {
+        assert!(Instant::now() < deadline, "cancelled_generations never rose above {}", before);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+});
+
+teenytiny_test!(async fn test_timed_out_request_stops_generating() {
+    let key = new_api_key().await;
+    let result = crate::http_client_builder()
+        .timeout(Duration::from_millis(300))
+        .build()
+        .unwrap()
+        .post(format!("{}/v1/chat/completions", base_url()))
+        .bearer_auth(&key)
+        .json(&completion_body(false))
+        .send()
+        .await;
+    assert!(result.is_err_and(|e| e.is_timeout()), "Expected the request to time out");
+
+    let captured = wait_for_logged_request(&key).await;
+    assert_eq!(captured["stream"], false);
+    assert_eq!(captured["cancelled"], true, "Disconnect not detected: {}", captured);
+
+    // Generating all thirty words would take three seconds
+    let duration = captured["duration_ms"].as_u64().unwrap();
+    assert!(duration < 1500, "Server kept generating for {}ms after the client left", duration);
+});
+
+teenytiny_test!(async fn test_completed_requests_are_not_cancelled() {
+    let key = new_api_key().await;
+    for stream in [false, true] {
+        let response = crate::http_client()
+            .post(format!("{}/v1/chat/completions", base_url()))
+            .bearer_auth(&key)
+            .json(&json!({
+                "model": "slow:10",
+                "messages": [{"role": "user", "content": "Patience pays"}],
+                "stream": stream,
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        response.bytes().await.unwrap();
+
+        let captured = wait_for_logged_request(&key).await;
+        assert_eq!(captured["stream"], stream);
+        assert_eq!(captured["cancelled"], false, "Completed request marked cancelled: {}", captured);
+    }
+});
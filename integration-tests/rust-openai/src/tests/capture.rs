@@ -0,0 +1,87 @@
+// PUT /admin/capture limits the request log to keeping the bodies of chosen
+// keys, and redacts what it keeps before storing it. Limiting the server the
+// rest of the suite runs against would take the bodies other tests check, so
+// these tests start a server of their own, and are skipped when it can't be
+// started.
+
+use reqwest::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use super::own_server;
+use crate::server::TestServer;
+
+const CARD: &str = "4111-1111-1111-1111";
+const EMAIL: &str = "ada@example.com";
+
+async fn send(server: &TestServer, key: &str, method: Method, path: &str, body: Option<Value>) -> (StatusCode, Value) {
+    let mut request = crate::http_client().request(method, format!("{}{}", server.url(), path)).bearer_auth(key);
+    if let Some(body) = body {
+        request = request.json(&body);
+    }
+    let response = request.send().await.unwrap();
+    (response.status(), response.json().await.unwrap())
+}
+
+async fn chat(server: &TestServer, key: &str) {
+    let (status, body) = send(server, key, Method::POST, "/v1/chat/completions", Some(json!({
+        "model": "echo",
+        "messages": [{"role": "user", "content": format!("Bill {} and mail {}", CARD, EMAIL)}]
+    }))).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    // Only what the log keeps is redacted
+    assert!(body["choices"][0]["message"]["content"].as_str().unwrap().contains(CARD));
+}
+
+async fn last_request(server: &TestServer, key: &str) -> Value {
+    let path = format!("/admin/requests?limit=1&key={}", key);
+    let (status, body) = send(server, server.api_key(), Method::GET, &path, None).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    body["data"][0].clone()
+}
+
+teenytiny_test!(async fn test_capture_keeps_only_chosen_keys_bodies_redacted() {
+    let Some(server) = own_server(&[]).await else { return };
+    let (_, created) = send(&server, server.api_key(), Method::POST, "/admin/keys", None).await;
+    let other = created["key"].as_str().unwrap().to_string();
+
+    let settings = json!({"keys": [server.api_key()], "redact": [r"\d{4}-\d{4}-\d{4}-\d{4}", r"[\w.]+@[\w.]+"]});
+    let (status, body) = send(&server, server.api_key(), Method::PUT, "/admin/capture", Some(settings.clone())).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body, settings);
+
+    chat(&server, server.api_key()).await;
+    chat(&server, &other).await;
+
+    let kept = last_request(&server, server.api_key()).await;
+    let redacted = "Bill [REDACTED] and mail [REDACTED]";
+    assert_eq!(kept["request_body"]["messages"][0]["content"], redacted);
+    assert_eq!(kept["response_body"]["choices"][0]["message"]["content"], redacted);
+    assert!(!kept.to_string().contains(CARD) && !kept.to_string().contains(EMAIL), "Unredacted: {}", kept);
+
+    let withheld = last_request(&server, &other).await;
+    assert_eq!((&withheld["model"], &withheld["status"]), (&json!("echo"), &json!(200)));
+    assert_eq!((&withheld["request_body"], &withheld["response_body"]), (&Value::Null, &Value::Null));
+
+    // Lifting the limit keeps every key's bodies again
+    let (status, _) = send(&server, server.api_key(), Method::PUT, "/admin/capture", Some(json!({"keys": null}))).await;
+    assert_eq!(status, StatusCode::OK);
+    chat(&server, &other).await;
+    let kept = last_request(&server, &other).await;
+    assert_eq!(kept["request_body"]["messages"][0]["content"], "Bill [REDACTED] and mail [REDACTED]");
+});
+
+teenytiny_test!(async fn test_capture_settings_are_validated_and_admin_only() {
+    let Some(server) = own_server(&[]).await else { return };
+    let (_, created) = send(&server, server.api_key(), Method::POST, "/admin/keys", None).await;
+    let other = created["key"].as_str().unwrap().to_string();
+
+    for settings in [json!({"keys": "everyone"}), json!({"redact": ["("]}), json!({"redact": "\\d+"})] {
+        let (status, body) = send(&server, server.api_key(), Method::PUT, "/admin/capture", Some(settings)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+        assert_eq!(body["error"]["type"], "invalid_request_error");
+    }
+
+    let (status, body) = send(&server, &other, Method::GET, "/admin/capture", None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{}", body);
+    assert_eq!(body["error"]["code"], "admin_required");
+});
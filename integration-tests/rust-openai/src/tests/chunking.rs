@@ -0,0 +1,89 @@
+// The x-teenytiny-chunking header re-cuts a streamed completion: one token,
+// one word or a fixed number of bytes per chunk, or the whole message at once.
+// The echo text mixes characters of one to four bytes, so a chunker that cut
+// through a character would show up as a lone surrogate the JSON parser rejects.
+
+use reqwest::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use crate::raw::{self, assert_error};
+
+const TEXT: &str = "Héllo wörld, 日本語 🎉 naïve!";
+
+async fn chat(chunking: &str, stream: bool) -> raw::RawResponse {
+    let request = raw::request(Method::POST, "/v1/chat/completions")
+        .header("x-teenytiny-chunking", chunking)
+        .json(&json!({"model": "echo", "stream": stream, "messages": [{"role": "user", "content": TEXT}]}));
+    raw::send(request).await
+}
+
+// The content of each chunk, in order
+async fn deltas(chunking: &str) -> Vec<String> {
+    let response = chat(chunking, true).await;
+    assert_eq!(response.status, StatusCode::OK, "{}: {}", chunking, response.text());
+
+    let deltas: Vec<String> = response.text().lines()
+        .filter_map(|line| line.strip_prefix("data: "))
+        .filter(|data| *data != "[DONE]")
+        .map(|data| serde_json::from_str::<Value>(data).unwrap_or_else(|e| panic!("{}: bad chunk {} ({})", chunking, data, e)))
+        .filter_map(|chunk| chunk["choices"][0]["delta"]["content"].as_str().map(String::from))
+        .collect();
+    assert_eq!(deltas.concat(), TEXT, "{}: chunks don't join back into the text", chunking);
+    deltas
+}
+
+teenytiny_test!(async fn test_message_chunking_sends_one_chunk() {
+    assert_eq!(deltas("message").await, [TEXT]);
+});
+
+teenytiny_test!(async fn test_word_chunking() {
+    assert_eq!(deltas("word").await, ["Héllo", " wörld,", " 日本語", " 🎉", " naïve!"]);
+});
+
+teenytiny_test!(async fn test_token_chunking() {
+    let deltas = deltas("token").await;
+
+    let tokenizer = raw::get("/version").await.json()["tokenizer"].clone();
+    if tokenizer == "whitespace" {
+        assert_eq!(deltas, ["Héllo", " wörld", ",", " 日本語", " 🎉", " naïve", "!"]);
+    } else {
+        // BPE cuts words into pieces, but never inside a character
+        assert!(deltas.len() >= 7, "Expected at least a token per word and symbol, got {:?}", deltas);
+    }
+});
+
+teenytiny_test!(async fn test_byte_chunking_never_splits_a_character() {
+    for size in [1, 2, 3, 4, 5, 16] {
+        let deltas = deltas(&format!("bytes:{}", size)).await;
+
+        for (i, delta) in deltas.iter().enumerate() {
+            let longest_char = delta.chars().map(char::len_utf8).max().unwrap_or(0);
+            assert!(
+                delta.len() <= size.max(longest_char),
+                "bytes:{}: chunk {:?} is {} bytes", size, delta, delta.len()
+            );
+            // Chunks are filled as far as the next character allows
+            if let Some(next) = deltas.get(i + 1) {
+                let next_char = next.chars().next().unwrap().len_utf8();
+                assert!(delta.len() + next_char > size, "bytes:{}: chunk {:?} could have taken more", size, delta);
+            }
+        }
+    }
+    assert_eq!(deltas("bytes:1").await.len(), TEXT.chars().count());
+});
+
+teenytiny_test!(async fn test_chunking_leaves_blocking_completions_alone() {
+    let response = chat("bytes:1", false).await;
+
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    assert_eq!(response.json()["choices"][0]["message"]["content"], TEXT);
+});
+
+teenytiny_test!(async fn test_invalid_chunking_header() {
+    for value in ["sentence", "bytes", "bytes:0", "bytes:-4", "word:2"] {
+        let response = chat(value, true).await;
+
+        let error = assert_error(&response, StatusCode::BAD_REQUEST, "invalid_request_error");
+        assert_eq!(error.param.as_deref(), Some("x-teenytiny-chunking"), "{}", value);
+    }
+});
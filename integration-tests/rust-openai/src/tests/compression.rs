@@ -0,0 +1,172 @@
+// Compressed requests and responses. The Node.js server compresses JSON itself;
+// on Cloudflare Workers the edge does, so response tests accept either encoding
+// the client asked for. Event streams must never be compressed, since a
+// compressor holds back output and tokens would stop arriving one by one.
+
+use std::io::{Read, Write};
+use std::time::{Duration, Instant};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde_json::{json, Value};
+
+use crate::{api_key, base_url};
+
+fn chat_request(body: &Value) -> RequestBuilder {
+    crate::http_client()
+        .post(format!("{}/v1/chat/completions", base_url()))
+        .bearer_auth(api_key())
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+}
+
+fn chat_body(content: &str, stream: bool) -> Value {
+    json!({"model": "echo", "stream": stream, "messages": [{"role": "user", "content": content}]})
+}
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn header<'a>(response: &'a Response, name: &str) -> Option<&'a str> {
+    response.headers().get(name).map(|value| value.to_str().unwrap())
+}
+
+// The body decoded according to its Content-Encoding
+async fn decoded_json(response: Response) -> Value {
+    let encoding = header(&response, "content-encoding").map(str::to_string);
+    let bytes = response.bytes().await.unwrap();
+    let mut text = String::new();
+    match encoding.as_deref() {
+        None | Some("identity") => text = String::from_utf8(bytes.to_vec()).unwrap(),
+        Some("gzip") => {
+            GzDecoder::new(&bytes[..]).read_to_string(&mut text).unwrap();
+        }
+        Some("br") => {
+            brotli_decompressor::Decompressor::new(&bytes[..], 4096).read_to_string(&mut text).unwrap();
+        }
+        Some(other) => panic!("Unexpected Content-Encoding {}", other),
+    }
+    serde_json::from_str(&text).unwrap()
+}
+
+fn long_content() -> String {
+    "A reply long enough to be worth compressing. ".repeat(50).trim().to_string()
+}
+
+teenytiny_test!(async fn test_gzip_response_round_trips() {
+    let content = long_content();
+    let response = chat_request(&chat_body(&content, false))
+        .header("Accept-Encoding", "gzip")
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(
+        matches!(header(&response, "content-encoding"), None | Some("gzip")),
+        "Only gzip was accepted, got {:?}", header(&response, "content-encoding")
+    );
+    let body = decoded_json(response).await;
+    assert_eq!(body["choices"][0]["message"]["content"], content);
+});
+
+teenytiny_test!(async fn test_brotli_response_round_trips() {
+    let content = long_content();
+    let response = chat_request(&chat_body(&content, false))
+        .header("Accept-Encoding", "gzip, br")
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = decoded_json(response).await;
+    assert_eq!(body["choices"][0]["message"]["content"], content);
+});
+
+teenytiny_test!(async fn test_uncompressed_unless_asked() {
+    let response = chat_request(&chat_body(&long_content(), false))
+        .header("Accept-Encoding", "identity")
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(header(&response, "content-encoding"), None);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["choices"][0]["message"]["content"], long_content());
+});
+
+teenytiny_test!(async fn test_gzip_request_body() {
+    let body = chat_body("Compressed hello", false);
+    let response = chat_request(&body)
+        .header("Content-Encoding", "gzip")
+        .body(gzip(body.to_string().as_bytes()))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["choices"][0]["message"]["content"], "Compressed hello");
+});
+
+teenytiny_test!(async fn test_gzip_request_and_response() {
+    let content = long_content();
+    let body = chat_body(&content, false);
+    let response = chat_request(&body)
+        .header("Content-Encoding", "gzip")
+        .header("Accept-Encoding", "gzip")
+        .body(gzip(body.to_string().as_bytes()))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = decoded_json(response).await;
+    assert_eq!(body["choices"][0]["message"]["content"], content);
+});
+
+teenytiny_test!(async fn test_unsupported_request_encoding_is_rejected() {
+    let response = chat_request(&chat_body("Hi", false))
+        .header("Content-Encoding", "zstd")
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["error"]["code"], "unsupported_content_encoding");
+});
+
+teenytiny_test!(async fn test_streams_are_not_compressed() {
+    let mut response = chat_request(&json!({
+        "model": "slow:200",
+        "stream": true,
+        "messages": [{"role": "user", "content": "one two three four five"}]
+    }))
+    .header("Accept-Encoding", "gzip, br")
+    .send()
+    .await
+    .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(header(&response, "content-type").unwrap_or_default().starts_with("text/event-stream"));
+    assert_eq!(header(&response, "content-encoding"), None, "Event streams must not be compressed");
+
+    // Words are 200ms apart; had a compressor buffered them, they'd arrive together at the end
+    let start = Instant::now();
+    let mut arrivals = Vec::new();
+    let mut text = String::new();
+    while let Some(chunk) = response.chunk().await.unwrap() {
+        let chunk = String::from_utf8_lossy(&chunk).to_string();
+        if chunk.contains("\"content\"") {
+            arrivals.push(start.elapsed());
+        }
+        text.push_str(&chunk);
+    }
+    assert!(text.contains("data: [DONE]"));
+    let spread = arrivals.last().unwrap().saturating_sub(arrivals[0]);
+    assert!(spread >= Duration::from_millis(400), "Content arrived all at once ({:?} apart)", spread);
+});
@@ -0,0 +1,128 @@
+// Holds many streams open at once, the way a busy client library would. Each
+// stream carries its own text through the slow model, so chunks of different
+// streams interleave and any cross-talk or loss shows up as a wrong reassembly.
+
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use async_openai::types::CreateChatCompletionRequestArgs;
+use futures::StreamExt;
+
+use crate::config::config;
+use crate::setup_client;
+use super::user_message;
+
+const WORDS: &str = "alpha bravo charlie delta echo foxtrot golf hotel";
+
+// Small xorshift generator, so the cancelled subset differs between runs but
+// can be reproduced from the printed seed
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+enum Outcome {
+    Completed { expected: String, received: String },
+    Cancelled,
+}
+
+// Streams one message, dropping the stream after `cancel_after` chunks if set
+async fn run_stream(index: usize, cancel_after: Option<usize>) -> Outcome {
+    let expected = format!("stream {} {}", index, WORDS);
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("slow:20")
+        .messages([user_message(&expected)])
+        .stream(true)
+        .build().unwrap();
+
+    let mut stream = setup_client().chat().create_stream(request).await.unwrap();
+    let mut received = String::new();
+    let mut chunks = 0;
+    while let Some(result) = stream.next().await {
+        let response = result.unwrap_or_else(|e| panic!("Stream {} failed: {}", index, e));
+        if let Some(content) = response.choices.first().and_then(|c| c.delta.content.as_ref()) {
+            received.push_str(content);
+            chunks += 1;
+        }
+        if cancel_after == Some(chunks) {
+            return Outcome::Cancelled;
+        }
+        // Give other streams a turn between chunks
+        tokio::task::yield_now().await;
+    }
+    Outcome::Completed { expected, received }
+}
+
+async fn quick_completion() -> Duration {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("echo")
+        .messages([user_message("Still there?")])
+        .build().unwrap();
+
+    let start = Instant::now();
+    let response = setup_client().chat().create(request).await.unwrap();
+    assert_eq!(response.choices[0].message.content.as_deref(), Some("Still there?"));
+    start.elapsed()
+}
+
+teenytiny_test!(async fn test_many_concurrent_streams_with_cancellations() {
+    let seed = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos() as u64 | 1;
+    eprintln!("Concurrency test seed: {}", seed);
+    let mut rng = Rng(seed);
+    let streams = config().concurrency;
+
+    let tasks: Vec<_> = (0..streams)
+        .map(|index| {
+            // Cancel about a quarter of the streams, each after one to four chunks
+            let roll = (rng.next() % 16) as usize;
+            let cancel_after = (roll < 4).then_some(roll + 1);
+            tokio::spawn(run_stream(index, cancel_after))
+        })
+        .collect();
+
+    // The server should keep answering ordinary requests while the streams run
+    let latency = quick_completion().await;
+    assert!(latency < Duration::from_secs(2), "Completion took {:?} under load", latency);
+
+    let mut completed = 0;
+    let mut cancelled = 0;
+    for task in tasks {
+        match task.await.unwrap() {
+            Outcome::Completed { expected, received } => {
+                assert_eq!(received, expected, "Stream content was not reassembled correctly");
+                completed += 1;
+            }
+            Outcome::Cancelled => cancelled += 1,
+        }
+    }
+    eprintln!("{} streams completed, {} cancelled", completed, cancelled);
+    assert_eq!(completed + cancelled, streams);
+    assert!(completed > 0, "Every stream was cancelled, nothing was checked");
+
+    // And after the cancelled streams have been torn down
+    let latency = quick_completion().await;
+    assert!(latency < Duration::from_secs(2), "Completion took {:?} after load", latency);
+    let models = setup_client().models().list().await.unwrap();
+    assert!(!models.data.is_empty());
+});
+
+teenytiny_test!(async fn test_concurrent_blocking_and_streaming_requests() {
+    let streams: Vec<_> = (0..50).map(|index| tokio::spawn(run_stream(index, None))).collect();
+    let blocking: Vec<_> = (0..50).map(|_| tokio::spawn(quick_completion())).collect();
+
+    for task in blocking {
+        let latency = task.await.unwrap();
+        assert!(latency < Duration::from_secs(5), "Completion took {:?} alongside streams", latency);
+    }
+    for task in streams {
+        let Outcome::Completed { expected, received } = task.await.unwrap() else {
+            panic!("No stream was meant to be cancelled");
+        };
+        assert_eq!(received, expected);
+    }
+});
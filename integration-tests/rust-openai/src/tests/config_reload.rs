@@ -0,0 +1,66 @@
+// POST /admin/reload re-reads the server's --config file. Changing the file
+// needs to know where it is, so those tests only run when TEENYTINY_CONFIG
+// names the file the server was started with.
+
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+
+use crate::config::config;
+use crate::{api_key, base_url};
+use super::{new_api_key, skip};
+
+async fn reload(key: &str) -> (StatusCode, Value) {
+    let response = crate::http_client()
+        .post(format!("{}/admin/reload", base_url()))
+        .bearer_auth(key)
+        .send()
+        .await
+        .unwrap();
+    (response.status(), response.json().await.unwrap())
+}
+
+async fn chat(key: &str, model: &str) -> StatusCode {
+    crate::http_client()
+        .post(format!("{}/v1/chat/completions", base_url()))
+        .bearer_auth(key)
+        .json(&json!({"model": model, "messages": [{"role": "user", "content": "Hi"}]}))
+        .send()
+        .await
+        .unwrap()
+        .status()
+}
+
+teenytiny_test!(async fn test_reload_requires_the_server_key() {
+    let key = new_api_key().await;
+
+    let (status, body) = reload(&key).await;
+
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["error"]["code"], "admin_required");
+});
+
+teenytiny_test!(async fn test_reload_applies_keys_added_to_the_file() {
+    let Some(file) = &config().config_file else {
+        skip("TEENYTINY_CONFIG is not set");
+        return;
+    };
+    let original = std::fs::read_to_string(file).unwrap();
+    let key = format!("tt-config-reload-{}", std::process::id());
+    assert_eq!(chat(&key, "echo").await, StatusCode::UNAUTHORIZED);
+
+    // An array of tables can be appended to whatever the file already has
+    std::fs::write(file, format!("{}\n[[keys]]\nkey = \"{}\"\nmodels = [\"echo\"]\n", original, key)).unwrap();
+    let (status, body) = reload(&api_key()).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let echo = chat(&key, "echo").await;
+    let eliza = chat(&key, "eliza").await;
+
+    // Put the file back before asserting, so a failure doesn't leave it changed
+    std::fs::write(file, original).unwrap();
+    let (status, body) = reload(&api_key()).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    assert_eq!(echo, StatusCode::OK);
+    assert_eq!(eliza, StatusCode::FORBIDDEN);
+    assert_eq!(chat(&key, "echo").await, StatusCode::UNAUTHORIZED, "Keys removed from the file should stop working");
+});
@@ -0,0 +1,95 @@
+// What a browser-based playground sees: preflights, Origin headers on real
+// requests, and CORS headers on streams and errors too. The server allows
+// any origin unless started with TEENYTINY_CORS_ORIGINS, which should then
+// include PLAYGROUND for these tests.
+
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use serde_json::json;
+
+use crate::{api_key, base_url};
+
+const PLAYGROUND: &str = "https://playground.example.com";
+
+fn header<'a>(response: &'a Response, name: &str) -> &'a str {
+    response.headers().get(name).map_or("", |value| value.to_str().unwrap())
+}
+
+fn assert_allows_playground(response: &Response) {
+    let origin = header(response, "access-control-allow-origin");
+    assert!(
+        origin == "*" || origin == PLAYGROUND,
+        "Expected {} to be allowed, got Access-Control-Allow-Origin: {:?}", PLAYGROUND, origin
+    );
+}
+
+fn chat_request(stream: bool) -> RequestBuilder {
+    crate::http_client()
+        .post(format!("{}/v1/chat/completions", base_url()))
+        .header("Origin", PLAYGROUND)
+        .bearer_auth(api_key())
+        .json(&json!({"model": "echo", "stream": stream, "messages": [{"role": "user", "content": "Hi"}]}))
+}
+
+teenytiny_test!(async fn test_preflight_allows_authorization_header() {
+    let response = crate::http_client()
+        .request(Method::OPTIONS, format!("{}/v1/chat/completions", base_url()))
+        .header("Origin", PLAYGROUND)
+        .header("Access-Control-Request-Method", "POST")
+        .header("Access-Control-Request-Headers", "authorization, content-type")
+        .send()
+        .await
+        .unwrap();
+
+    assert!(response.status().is_success(), "Preflight failed with {}", response.status());
+    assert_allows_playground(&response);
+    assert!(header(&response, "access-control-allow-methods").contains("POST"));
+
+    let headers = header(&response, "access-control-allow-headers").to_lowercase();
+    for needed in ["authorization", "content-type"] {
+        assert!(headers.contains(needed), "{} not in Access-Control-Allow-Headers: {}", needed, headers);
+    }
+});
+
+teenytiny_test!(async fn test_preflight_needs_no_api_key() {
+    // Browsers never send credentials on a preflight
+    let response = crate::http_client()
+        .request(Method::OPTIONS, format!("{}/v1/models", base_url()))
+        .header("Origin", PLAYGROUND)
+        .header("Access-Control-Request-Method", "GET")
+        .send()
+        .await
+        .unwrap();
+
+    assert_ne!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_allows_playground(&response);
+});
+
+teenytiny_test!(async fn test_completion_carries_cors_headers() {
+    let response = chat_request(false).send().await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_allows_playground(&response);
+});
+
+teenytiny_test!(async fn test_streamed_completion_carries_cors_headers() {
+    let response = chat_request(true).send().await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(header(&response, "content-type").starts_with("text/event-stream"));
+    assert_allows_playground(&response);
+    assert!(response.text().await.unwrap().contains("data: [DONE]"));
+});
+
+teenytiny_test!(async fn test_errors_carry_cors_headers() {
+    // Without them a browser hides the error body from the page
+    let response = crate::http_client()
+        .get(format!("{}/v1/models", base_url()))
+        .header("Origin", PLAYGROUND)
+        .bearer_auth("not-a-real-key")
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_allows_playground(&response);
+});
@@ -0,0 +1,93 @@
+// POST /debug/parse answers with a chat completion request as the model would
+// get it, so these tests can check optional parameters reach the server as
+// sent. The echo model answers the same whatever they are, so a test that only
+// reads its reply can't tell a parameter that arrived from one that was dropped.
+
+use async_openai::types::{CreateChatCompletionRequest, CreateChatCompletionRequestArgs};
+use reqwest::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use crate::raw::{self, assert_error, RawResponse};
+use super::user_message;
+
+async fn parse(body: &Value) -> RawResponse {
+    raw::send(raw::request(Method::POST, "/debug/parse").json(body)).await
+}
+
+// The server's reading of a request built with the client, as the client sends it
+async fn parsed(request: &CreateChatCompletionRequest) -> Value {
+    let response = parse(&serde_json::to_value(request).unwrap()).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    response.json()
+}
+
+teenytiny_test!(async fn test_sampling_parameters_reach_the_server() {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("echo")
+        .messages([user_message("Penalty params test")])
+        .frequency_penalty(0.5)
+        .presence_penalty(0.25)
+        .temperature(0.75)
+        .top_p(0.5)
+        .max_tokens(100u16)
+        .seed(42)
+        .stop(["END"])
+        .user("test-user-123")
+        .build().unwrap();
+
+    let parsed = parsed(&request).await;
+    let server = &parsed["request"];
+    for (param, expected) in [
+        ("frequency_penalty", json!(0.5)),
+        ("presence_penalty", json!(0.25)),
+        ("temperature", json!(0.75)),
+        ("top_p", json!(0.5)),
+        ("max_tokens", json!(100)),
+        ("seed", json!(42)),
+        ("stop", json!(["END"])),
+        ("user", json!("test-user-123")),
+    ] {
+        assert_eq!(server[param], expected, "{} in {}", param, server);
+    }
+    assert_eq!(server["messages"], json!([{"role": "user", "content": "Penalty params test"}]));
+    assert_eq!(parsed["applied_defaults"], json!([]));
+});
+
+teenytiny_test!(async fn test_requests_are_normalized() {
+    let parsed = parse(&json!({
+        "model": "echo",
+        "messages": [
+            {"role": "developer", "content": "Be brief"},
+            {"role": "user", "content": "Weather?"},
+            {"role": "assistant", "content": null, "function_call": {"name": "get_weather", "arguments": "{}"}},
+            {"role": "function", "name": "get_weather", "content": "Sunny"}
+        ],
+        "functions": [{"name": "get_weather", "parameters": {"type": "object", "properties": {}}}]
+    })).await.json();
+
+    let messages = &parsed["request"]["messages"];
+    assert_eq!(messages[0]["role"], "system");
+    let call = &messages[2]["tool_calls"][0];
+    assert_eq!(call["function"]["name"], "get_weather");
+    assert_eq!(messages[3]["role"], "tool");
+    assert_eq!(messages[3]["tool_call_id"], call["id"]);
+    assert_eq!(parsed["request"]["tools"][0]["function"]["name"], "get_weather");
+    assert!(parsed["request"].get("functions").is_none());
+    assert_eq!(parsed["legacy_functions"], true);
+});
+
+teenytiny_test!(async fn test_invalid_requests_are_rejected_as_by_chat_completions() {
+    let body = json!({"model": "echo", "frequency_penalty": 5, "messages": [{"role": "user", "content": "Hi"}]});
+    let error = assert_error(&parse(&body).await, StatusCode::BAD_REQUEST, "invalid_request_error");
+    assert_eq!(error.param.as_deref(), Some("frequency_penalty"));
+
+    let body = json!({"model": "no-such-model", "messages": [{"role": "user", "content": "Hi"}]});
+    assert_error(&parse(&body).await, StatusCode::NOT_FOUND, "invalid_request_error");
+});
+
+teenytiny_test!(async fn test_requests_without_a_key_are_rejected() {
+    let body = json!({"model": "echo", "messages": [{"role": "user", "content": "Hi"}]});
+    let request = crate::http_client().post(format!("{}/debug/parse", crate::base_url())).json(&body);
+
+    assert_error(&raw::send(request).await, StatusCode::UNAUTHORIZED, "authentication_error");
+});
@@ -0,0 +1,68 @@
+// The x-teenytiny-deterministic header derives a completion's id, created
+// timestamp and system_fingerprint from a seed, so that with the echo model the
+// same request and seed give the same bytes every time, blocking or streamed.
+
+use reqwest::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use crate::raw::{self, assert_error};
+
+async fn chat(seed: Option<&str>, stream: bool) -> raw::RawResponse {
+    let mut request = raw::request(Method::POST, "/v1/chat/completions")
+        .json(&json!({"model": "echo", "stream": stream, "messages": [{"role": "user", "content": "Same every time"}]}));
+    if let Some(seed) = seed {
+        request = request.header("x-teenytiny-deterministic", seed);
+    }
+    let response = raw::send(request).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    response
+}
+
+fn chunks(response: &raw::RawResponse) -> Vec<Value> {
+    response.text().lines()
+        .filter_map(|line| line.strip_prefix("data: "))
+        .filter(|data| *data != "[DONE]")
+        .map(|data| serde_json::from_str(data).unwrap())
+        .collect()
+}
+
+teenytiny_test!(async fn test_same_seed_gives_identical_bytes() {
+    for stream in [false, true] {
+        let first = chat(Some("golden-1"), stream).await;
+        let second = chat(Some("golden-1"), stream).await;
+        assert_eq!(first.text(), second.text(), "stream: {}", stream);
+    }
+});
+
+teenytiny_test!(async fn test_seed_sets_id_timestamp_and_fingerprint() {
+    let response = chat(Some("golden-1"), false).await.json();
+    let id = response["id"].as_str().unwrap();
+    assert!(id.starts_with("chatcmpl-"), "{}", id);
+    assert!(response["created"].as_u64().is_some());
+    assert!(response["system_fingerprint"].as_str().unwrap().starts_with("fp_"));
+
+    // Streamed with the same seed, every chunk carries the same stamp
+    for chunk in chunks(&chat(Some("golden-1"), true).await) {
+        for field in ["id", "created", "system_fingerprint"] {
+            assert_eq!(chunk[field], response[field], "{} in {}", field, chunk);
+        }
+    }
+
+    let other = chat(Some("golden-2"), false).await.json();
+    assert_ne!(other["id"], response["id"]);
+    assert_ne!(other["system_fingerprint"], response["system_fingerprint"]);
+});
+
+teenytiny_test!(async fn test_without_a_seed_ids_differ() {
+    let first = chat(None, false).await.json();
+    let second = chat(None, false).await.json();
+    assert_ne!(first["id"], second["id"]);
+});
+
+teenytiny_test!(async fn test_empty_seed_is_rejected() {
+    let request = raw::request(Method::POST, "/v1/chat/completions")
+        .header("x-teenytiny-deterministic", " ")
+        .json(&json!({"model": "echo", "messages": [{"role": "user", "content": "Hi"}]}));
+    let error = assert_error(&raw::send(request).await, StatusCode::BAD_REQUEST, "invalid_request_error");
+    assert_eq!(error.param.as_deref(), Some("x-teenytiny-deterministic"));
+});
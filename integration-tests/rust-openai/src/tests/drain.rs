@@ -0,0 +1,122 @@
+// A draining server stops taking new requests but lets those in flight finish.
+// Draining the server the rest of the suite runs against would fail every
+// test after it, so these tests start a server of their own from this
+// checkout, and are skipped when it can't be started.
+
+use std::time::Duration;
+
+use reqwest::{Response, StatusCode};
+use serde_json::{json, Value};
+
+use super::own_server;
+use crate::server::TestServer;
+
+// A slow:100 stream of these words takes a second, long enough to drain the
+// server while it's open
+const TEXT: &str = "one two three four five six seven eight nine ten";
+
+async fn chat(server: &TestServer, stream: bool) -> Response {
+    crate::http_client()
+        .post(format!("{}/v1/chat/completions", server.url()))
+        .bearer_auth(server.api_key())
+        .json(&json!({
+            "model": "slow:100",
+            "messages": [{"role": "user", "content": TEXT}],
+            "stream": stream,
+        }))
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn get(server: &TestServer, path: &str) -> (StatusCode, Value) {
+    let response = crate::http_client()
+        .get(format!("{}{}", server.url(), path))
+        .bearer_auth(server.api_key())
+        .send()
+        .await
+        .unwrap();
+    (response.status(), response.json().await.unwrap())
+}
+
+// Reads the first chunk, so the stream is under way before the drain starts
+async fn start_stream(server: &TestServer) -> (Response, String) {
+    let mut response = chat(server, true).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let first = response.chunk().await.unwrap().expect("Stream ended before its first chunk");
+    (response, String::from_utf8_lossy(&first).into_owned())
+}
+
+// Reads the rest of a stream, failing if the server cuts it off
+async fn finish_stream(mut response: Response, mut received: String) -> String {
+    while let Some(bytes) = response.chunk().await.expect("The stream was cut off") {
+        received.push_str(&String::from_utf8_lossy(&bytes));
+    }
+    received
+}
+
+async fn assert_turned_away(server: &TestServer) {
+    let response = chat(server, false).await;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert!(response.headers().contains_key("retry-after"), "No Retry-After on the 503");
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["error"]["type"], "overloaded_error", "Unexpected error: {}", body);
+}
+
+teenytiny_test!(async fn test_drain_lets_streams_finish_and_turns_new_requests_away() {
+    let Some(server) = own_server(&[]).await else { return };
+    let (stream, first) = start_stream(&server).await;
+
+    let response = crate::http_client()
+        .post(format!("{}/admin/drain", server.url()))
+        .bearer_auth(server.api_key())
+        .json(&json!({"grace_ms": 10000}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let status: Value = response.json().await.unwrap();
+    assert_eq!(status["draining"], true);
+    assert_eq!(status["in_flight"], 1, "Unexpected drain status: {}", status);
+
+    assert_turned_away(&server).await;
+    // Load balancers see it's draining; it's still alive
+    let (ready, body) = get(&server, "/readyz").await;
+    assert_eq!((ready, &body["status"]), (StatusCode::SERVICE_UNAVAILABLE, &json!("draining")));
+    assert_eq!(get(&server, "/healthz").await.0, StatusCode::OK);
+
+    let received = finish_stream(stream, first).await;
+    assert!(received.contains("ten"), "The stream didn't finish its reply: {}", received);
+    assert!(received.contains("data: [DONE]"), "The stream ended without [DONE]: {}", received);
+    assert_eq!(get(&server, "/admin/drain").await.1["in_flight"], 0);
+});
+
+teenytiny_test!(async fn test_sigterm_drains_before_exiting() {
+    let Some(mut server) = own_server(&[]).await else { return };
+    let (stream, first) = start_stream(&server).await;
+
+    server.terminate().unwrap();
+    // The signal is handled asynchronously, so wait until the server says
+    // it's draining
+    let mut ready = StatusCode::OK;
+    for _ in 0..50 {
+        ready = get(&server, "/readyz").await.0;
+        if ready == StatusCode::SERVICE_UNAVAILABLE {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(ready, StatusCode::SERVICE_UNAVAILABLE, "The server never started draining");
+    assert_turned_away(&server).await;
+
+    let received = finish_stream(stream, first).await;
+    assert!(received.contains("data: [DONE]"), "The stream ended without [DONE]: {}", received);
+
+    // With nothing left in flight, the server exits of its own accord
+    let exited = tokio::task::spawn_blocking(move || server.wait_for_exit(Duration::from_secs(5)))
+        .await
+        .unwrap()
+        .unwrap();
+    let status = exited.expect("The server was still running 5 seconds after its last stream");
+    assert!(status.success(), "The server exited with {}", status);
+});
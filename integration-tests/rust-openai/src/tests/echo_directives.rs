@@ -0,0 +1,207 @@
+// The echo model reads !name:value directives from the user message to shape
+// its response; each test scripts one edge condition this way.
+
+use std::time::{Duration, Instant};
+
+use async_openai::types::{CreateChatCompletionRequestArgs, CreateChatCompletionStreamResponse, FinishReason};
+use futures::StreamExt;
+use reqwest::StatusCode;
+use serde_json::json;
+
+use crate::setup_client;
+use super::{post_chat_completion, user_message};
+
+// Streams the message through echo, returning every chunk received
+async fn stream_chunks(message: &str) -> Vec<CreateChatCompletionStreamResponse> {
+    let client = setup_client();
+
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("echo")
+        .messages([user_message(message)])
+        .stream(true)
+        .build().unwrap();
+
+    let mut stream = client.chat().create_stream(request).await.unwrap();
+
+    let mut chunks = Vec::new();
+    while let Some(result) = stream.next().await {
+        chunks.push(result.unwrap());
+    }
+    chunks
+}
+
+fn content_deltas(chunks: &[CreateChatCompletionStreamResponse]) -> Vec<String> {
+    chunks.iter()
+        .filter_map(|chunk| chunk.choices.first()?.delta.content.clone())
+        .collect()
+}
+
+teenytiny_test!(async fn test_directives_are_stripped_from_output(client) {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("echo")
+        .messages([user_message("Hello !tokens:5 World")])
+        .build().unwrap();
+
+    let response = client.chat().create(request).await.unwrap();
+
+    let content = response.choices[0].message.content.as_ref()
+        .expect("No content in response");
+    assert_eq!(content, "Hello World");
+});
+
+teenytiny_test!(async fn test_delay_directive(client) {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("echo")
+        .messages([user_message("Slow hello !delay:500")])
+        .build().unwrap();
+
+    let start = Instant::now();
+    let response = client.chat().create(request).await.unwrap();
+
+    assert!(start.elapsed() >= Duration::from_millis(500), "Response came back after {:?}", start.elapsed());
+    assert_eq!(response.choices[0].message.content.as_deref(), Some("Slow hello"));
+});
+
+teenytiny_test!(async fn test_delay_directive_delays_first_content_chunk(client) {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("echo")
+        .messages([user_message("!delay:500 Streaming hello")])
+        .stream(true)
+        .build().unwrap();
+
+    let start = Instant::now();
+    let mut stream = client.chat().create_stream(request).await.unwrap();
+
+    while let Some(result) = stream.next().await {
+        let chunk = result.unwrap();
+        if chunk.choices.first().and_then(|c| c.delta.content.as_ref()).is_some() {
+            break;
+        }
+    }
+
+    assert!(start.elapsed() >= Duration::from_millis(500), "First content after {:?}", start.elapsed());
+});
+
+teenytiny_test!(async fn test_chunks_directive() {
+    let chunks = stream_chunks("The quick brown fox jumps !chunks:7").await;
+
+    let deltas = content_deltas(&chunks);
+    assert_eq!(deltas.len(), 7, "Expected 7 content chunks, got: {:?}", deltas);
+    assert_eq!(deltas.join(""), "The quick brown fox jumps");
+});
+
+teenytiny_test!(async fn test_chunks_directive_single_chunk() {
+    let chunks = stream_chunks("One piece !chunks:1").await;
+
+    assert_eq!(content_deltas(&chunks), ["One piece"]);
+});
+
+teenytiny_test!(async fn test_finish_directive(client) {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("echo")
+        .messages([user_message("Cut off !finish:length")])
+        .build().unwrap();
+
+    let response = client.chat().create(request).await.unwrap();
+
+    assert_eq!(response.choices[0].finish_reason, Some(FinishReason::Length));
+});
+
+teenytiny_test!(async fn test_finish_directive_when_streaming() {
+    let chunks = stream_chunks("Filtered !finish:content_filter").await;
+
+    let finish_reason = chunks.iter()
+        .find_map(|chunk| chunk.choices.first()?.finish_reason);
+    assert_eq!(finish_reason, Some(FinishReason::ContentFilter));
+});
+
+teenytiny_test!(async fn test_tokens_directive(client) {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("echo")
+        .messages([user_message("Counted !tokens:123")])
+        .build().unwrap();
+
+    let response = client.chat().create(request).await.unwrap();
+
+    let usage = response.usage.expect("No usage in response");
+    assert_eq!(usage.completion_tokens, 123);
+    assert_eq!(usage.total_tokens, usage.prompt_tokens + 123);
+});
+
+teenytiny_test!(async fn test_tokens_directive_when_streaming() {
+    let chunks = stream_chunks("Counted !tokens:77").await;
+
+    let usage = chunks.iter()
+        .find_map(|chunk| chunk.usage.clone())
+        .expect("No usage in stream");
+    assert_eq!(usage.completion_tokens, 77);
+});
+
+teenytiny_test!(async fn test_error_directive() {
+    for (status, error_type) in [(500, "api_error"), (503, "overloaded_error"), (429, "rate_limit_error")] {
+        let (actual, body) = post_chat_completion(json!({
+            "model": "echo",
+            "messages": [{"role": "user", "content": format!("!error:{}", status)}],
+        })).await;
+
+        assert_eq!(actual.as_u16(), status);
+        assert_eq!(body["error"]["type"], error_type);
+    }
+});
+
+teenytiny_test!(async fn test_error_directive_when_streaming() {
+    let (status, body) = post_chat_completion(json!({
+        "model": "echo",
+        "stream": true,
+        "messages": [{"role": "user", "content": "!error:502"}],
+    })).await;
+
+    // The error is raised before the stream starts, so it arrives as a plain HTTP error
+    assert_eq!(status, StatusCode::BAD_GATEWAY);
+    assert_eq!(body["error"]["type"], "api_error");
+});
+
+teenytiny_test!(async fn test_error_directive_through_client(client) {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("echo")
+        .messages([user_message("!error:500")])
+        .build().unwrap();
+
+    let result = client.chat().create(request).await;
+
+    assert!(result.is_err(), "Expected the simulated error to surface");
+});
+
+teenytiny_test!(async fn test_combined_directives() {
+    let chunks = stream_chunks("!chunks:3 !finish:length !tokens:9 Hello there").await;
+
+    assert_eq!(content_deltas(&chunks).len(), 3);
+    assert_eq!(content_deltas(&chunks).join(""), "Hello there");
+
+    let finish_reason = chunks.iter().find_map(|chunk| chunk.choices.first()?.finish_reason);
+    assert_eq!(finish_reason, Some(FinishReason::Length));
+
+    let usage = chunks.iter().find_map(|chunk| chunk.usage.clone()).expect("No usage in stream");
+    assert_eq!(usage.completion_tokens, 9);
+});
+
+teenytiny_test!(async fn test_invalid_directive_value() {
+    let (status, body) = post_chat_completion(json!({
+        "model": "echo",
+        "messages": [{"role": "user", "content": "!chunks:lots"}],
+    })).await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["param"], "messages");
+});
+
+teenytiny_test!(async fn test_directives_ignored_by_other_models(client) {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("eliza")
+        .messages([user_message("!error:500")])
+        .build().unwrap();
+
+    let result = client.chat().create(request).await;
+
+    assert!(result.is_ok(), "Only echo should interpret directives");
+});
@@ -0,0 +1,159 @@
+// Property tests: the echo model must hand back the last user message exactly,
+// whatever its characters, the conversation before it or the sampling
+// parameters, in both blocking and streaming mode. Set PROPTEST_CASES to run
+// more or fewer than the default 1000 cases.
+
+use async_openai::{
+    config::OpenAIConfig,
+    types::{ChatCompletionRequestAssistantMessageArgs, ChatCompletionRequestMessage, CreateChatCompletionRequestArgs},
+    Client,
+};
+use futures::StreamExt;
+use proptest::prelude::*;
+use proptest::test_runner::{Config, TestCaseError, TestRunner};
+
+use crate::base_url;
+use super::{new_api_key, system_message, user_message};
+
+const DEFAULT_CASES: u32 = 1000;
+
+// Characters that have tripped up JSON, SSE framing or text handling before,
+// mixed in with arbitrary ones so they turn up often
+const TRICKY_CHARS: &[char] = &[
+    ' ', '\n', '\r', '\t', '"', '\\', '/', '!', ':', '\u{0}', '\u{7f}', '\u{a0}', '\u{feff}', '\u{200d}',
+    '\u{2028}', '\u{2029}', '\u{fffd}', 'é', 'ß', '中', '🌟', '👩', '\u{1f3fd}',
+];
+
+#[derive(Debug, Clone)]
+struct Parameters {
+    temperature: Option<f32>,
+    top_p: Option<f32>,
+    presence_penalty: Option<f32>,
+    frequency_penalty: Option<f32>,
+    seed: Option<i64>,
+    // Always enough for the content, so it never truncates
+    max_tokens: Option<u32>,
+}
+
+fn text(max_len: usize) -> impl Strategy<Value = String> {
+    let char = prop_oneof![3 => any::<char>(), 1 => prop::sample::select(TRICKY_CHARS)];
+    prop::collection::vec(char, 1..=max_len).prop_map(String::from_iter)
+}
+
+// Text that doesn't happen to contain an echo directive such as "!delay:5"
+fn plain_text(max_len: usize) -> impl Strategy<Value = String> {
+    text(max_len).prop_filter("contains a directive", |text| {
+        !["delay", "chunks", "finish", "error", "tokens", "fault"]
+            .iter()
+            .any(|name| text.contains(&format!("!{}:", name)))
+    })
+}
+
+fn history() -> impl Strategy<Value = Vec<ChatCompletionRequestMessage>> {
+    let message = (0..3u8, text(40)).prop_map(|(role, content)| match role {
+        0 => system_message(&content),
+        1 => user_message(&content),
+        _ => ChatCompletionRequestAssistantMessageArgs::default()
+            .content(content)
+            .build()
+            .unwrap()
+            .into(),
+    });
+    prop::collection::vec(message, 0..5)
+}
+
+fn parameters() -> impl Strategy<Value = Parameters> {
+    const MAX_SAFE_INTEGER: i64 = (1 << 53) - 1;
+    (
+        prop::option::of(0.0f32..=2.0),
+        prop::option::of(0.0f32..=1.0),
+        prop::option::of(-2.0f32..=2.0),
+        prop::option::of(-2.0f32..=2.0),
+        prop::option::of(-MAX_SAFE_INTEGER..=MAX_SAFE_INTEGER),
+        prop::option::of(1000u32..=4096),
+    )
+        .prop_map(|(temperature, top_p, presence_penalty, frequency_penalty, seed, max_tokens)| Parameters {
+            temperature,
+            top_p,
+            presence_penalty,
+            frequency_penalty,
+            seed,
+            max_tokens,
+        })
+}
+
+fn request(
+    history: &[ChatCompletionRequestMessage],
+    content: &str,
+    parameters: &Parameters,
+    stream: bool,
+) -> async_openai::types::CreateChatCompletionRequest {
+    let mut messages = history.to_vec();
+    messages.push(user_message(content));
+
+    let mut args = CreateChatCompletionRequestArgs::default();
+    args.model("echo").messages(messages).stream(stream);
+    if let Some(temperature) = parameters.temperature {
+        args.temperature(temperature);
+    }
+    if let Some(top_p) = parameters.top_p {
+        args.top_p(top_p);
+    }
+    if let Some(penalty) = parameters.presence_penalty {
+        args.presence_penalty(penalty);
+    }
+    if let Some(penalty) = parameters.frequency_penalty {
+        args.frequency_penalty(penalty);
+    }
+    if let Some(seed) = parameters.seed {
+        args.seed(seed);
+    }
+    if let Some(max_tokens) = parameters.max_tokens {
+        args.max_tokens(max_tokens);
+    }
+    args.build().unwrap()
+}
+
+async fn round_trip(
+    client: &Client<OpenAIConfig>,
+    history: &[ChatCompletionRequestMessage],
+    content: &str,
+    parameters: &Parameters,
+) -> Result<(), TestCaseError> {
+    let fail = |e: async_openai::error::OpenAIError| TestCaseError::fail(e.to_string());
+
+    let response = client.chat().create(request(history, content, parameters, false)).await.map_err(fail)?;
+    prop_assert_eq!(response.choices[0].message.content.as_deref(), Some(content), "blocking");
+
+    let mut stream = client.chat().create_stream(request(history, content, parameters, true)).await.map_err(fail)?;
+    let mut streamed = String::new();
+    while let Some(chunk) = stream.next().await {
+        if let Some(delta) = chunk.map_err(fail)?.choices.first().and_then(|c| c.delta.content.clone()) {
+            streamed.push_str(&delta);
+        }
+    }
+    prop_assert_eq!(streamed.as_str(), content, "streaming");
+    Ok(())
+}
+
+#[test]
+fn test_echo_round_trips_generated_content() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    // Its own key, so thousands of requests don't eat into other tests' rate limit
+    let key = runtime.block_on(new_api_key());
+    let client = Client::with_config(
+        OpenAIConfig::new()
+            .with_api_key(key)
+            .with_api_base(format!("{}/v1", base_url())),
+    )
+    .with_http_client(crate::http_client());
+
+    let cases = std::env::var("PROPTEST_CASES").ok().and_then(|n| n.parse().ok()).unwrap_or(DEFAULT_CASES);
+    let mut runner = TestRunner::new(Config { cases, failure_persistence: None, ..Config::default() });
+    let result = runner.run(&(history(), plain_text(200), parameters()), |(history, content, parameters)| {
+        runtime.block_on(round_trip(&client, &history, &content, &parameters))
+    });
+    if let Err(e) = result {
+        panic!("{}", e);
+    }
+}
@@ -0,0 +1,105 @@
+// OpenAI wraps every error in {"error": {"message", "type", "param", "code"}},
+// always sending param and code (as null when they don't apply). OpenAI doesn't
+// use 422 on these endpoints - validation failures are 400 - so neither do we.
+
+use reqwest::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use crate::raw::{self, assert_error_envelope};
+use crate::{api_key, base_url};
+use super::new_api_key;
+
+async fn send(method: Method, path: &str, api_key: Option<&str>, body: Option<String>) -> (StatusCode, Value) {
+    let mut request = crate::http_client()
+        .request(method, format!("{}{}", base_url(), path))
+        .header("Content-Type", "application/json");
+
+    if let Some(key) = api_key {
+        request = request.bearer_auth(key);
+    }
+    if let Some(body) = body {
+        request = request.body(body);
+    }
+
+    let response = raw::send(request).await;
+    (response.status, response.json())
+}
+
+teenytiny_test!(async fn test_400_invalid_json() {
+    let (status, body) = send(Method::POST, "/v1/chat/completions", Some(&api_key()), Some("{not json".to_string())).await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let error = assert_error_envelope(&body);
+    assert_eq!(error.kind, "invalid_request_error");
+});
+
+teenytiny_test!(async fn test_400_missing_parameter() {
+    let body = json!({"messages": [{"role": "user", "content": "Hi"}]}).to_string();
+    let (status, body) = send(Method::POST, "/v1/chat/completions", Some(&api_key()), Some(body)).await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let error = assert_error_envelope(&body);
+    assert_eq!(error.kind, "invalid_request_error");
+    assert_eq!(error.param.as_deref(), Some("model"));
+});
+
+teenytiny_test!(async fn test_401_missing_api_key() {
+    let (status, body) = send(Method::GET, "/v1/models", None, None).await;
+
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let error = assert_error_envelope(&body);
+    assert_eq!(error.kind, "authentication_error");
+    assert_eq!(error.param, None);
+});
+
+teenytiny_test!(async fn test_401_invalid_api_key() {
+    let (status, body) = send(Method::GET, "/v1/models", Some("invalid-key-12345"), None).await;
+
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let error = assert_error_envelope(&body);
+    assert_eq!(error.kind, "authentication_error");
+    assert_eq!(error.code.as_deref(), Some("invalid_api_key"));
+});
+
+teenytiny_test!(async fn test_404_unknown_route() {
+    let (status, body) = send(Method::GET, "/v1/does-not-exist", Some(&api_key()), None).await;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let error = assert_error_envelope(&body);
+    assert!(error.message.contains("/v1/does-not-exist"), "Expected path in message: {}", error.message);
+});
+
+teenytiny_test!(async fn test_413_request_too_large() {
+    // Comfortably over the server's default 8MB body limit
+    let content = "x".repeat(9 * 1024 * 1024);
+    let body = json!({"model": "echo", "messages": [{"role": "user", "content": content}]}).to_string();
+    let (status, body) = send(Method::POST, "/v1/chat/completions", Some(&api_key()), Some(body)).await;
+
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    let error = assert_error_envelope(&body);
+    assert_eq!(error.kind, "invalid_request_error");
+    assert_eq!(error.code.as_deref(), Some("request_too_large"));
+});
+
+teenytiny_test!(async fn test_429_rate_limited() {
+    let api_key = new_api_key().await;
+    let client = crate::http_client();
+
+    let mut response = None;
+    for _ in 0..2 {
+        response = Some(client
+            .get(format!("{}/v1/models", base_url()))
+            .bearer_auth(&api_key)
+            .header("x-teenytiny-ratelimit-requests", "1")
+            .send()
+            .await
+            .unwrap());
+    }
+    let response = response.unwrap();
+
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    let body: Value = response.json().await.unwrap();
+    let error = assert_error_envelope(&body);
+    assert_eq!(error.kind, "rate_limit_error");
+    assert_eq!(error.code.as_deref(), Some("rate_limit_exceeded"));
+});
@@ -0,0 +1,129 @@
+// The Files API: uploads are real multipart/form-data requests, checked against
+// the file objects OpenAI returns. Files over the server's default 4MB file
+// limit, or its 8MB body limit, are refused with 413.
+
+use async_openai::types::{CreateFileRequest, FileInput, FilePurpose, OpenAIFile, OpenAIFilePurpose};
+use reqwest::multipart::{Form, Part};
+use reqwest::StatusCode;
+use serde_json::Value;
+
+use crate::{api_key, base_url};
+use super::{client_for, new_api_key};
+
+const MAX_FILE_BYTES: usize = 4 * 1024 * 1024;
+
+// Posts a multipart upload without async-openai, so invalid forms can be sent too
+async fn upload_raw(key: &str, filename: &str, content: Vec<u8>, purpose: Option<&str>) -> (StatusCode, Value) {
+    let mut form = Form::new().part("file", Part::bytes(content).file_name(filename.to_string()));
+    if let Some(purpose) = purpose {
+        form = form.text("purpose", purpose.to_string());
+    }
+
+    let response = crate::http_client()
+        .post(format!("{}/v1/files", base_url()))
+        .bearer_auth(key)
+        .multipart(form)
+        .send()
+        .await
+        .unwrap();
+
+    let status = response.status();
+    (status, response.json().await.unwrap())
+}
+
+teenytiny_test!(async fn test_upload_returns_a_file_object() {
+    let content = b"{\"custom_id\": \"only\"}\n".to_vec();
+    let (status, body) = upload_raw(&api_key(), "input.jsonl", content.clone(), Some("batch")).await;
+
+    assert_eq!(status, StatusCode::OK);
+    let keys: Vec<&str> = body.as_object().unwrap().keys().map(String::as_str).collect();
+    for key in ["id", "object", "bytes", "created_at", "filename", "purpose", "status", "status_details"] {
+        assert!(keys.contains(&key), "Missing {} in {}", key, body);
+    }
+
+    let file: OpenAIFile = serde_json::from_value(body).unwrap();
+    assert!(file.id.starts_with("file-"), "Unexpected id: {}", file.id);
+    assert_eq!(file.object, "file");
+    assert_eq!(file.bytes, content.len() as u32);
+    assert_eq!(file.filename, "input.jsonl");
+    assert_eq!(file.purpose, OpenAIFilePurpose::Batch);
+    assert!(file.created_at > 0);
+});
+
+teenytiny_test!(async fn test_content_round_trips(client) {
+    let content: Vec<u8> = (0..=255u8).cycle().take(100_000).collect();
+    let request = CreateFileRequest {
+        file: FileInput::from_vec_u8("blob.bin".to_string(), content.clone()),
+        purpose: FilePurpose::Assistants,
+    };
+
+    let file = client.files().create(request).await.unwrap();
+    assert_eq!(file.bytes, 100_000);
+    assert_eq!(client.files().retrieve(&file.id).await.unwrap().id, file.id);
+
+    let downloaded = client.files().content(&file.id).await.unwrap();
+    assert_eq!(downloaded.to_vec(), content);
+});
+
+teenytiny_test!(async fn test_list_and_delete() {
+    // A fresh key sees only its own files
+    let key = new_api_key().await;
+    let client = client_for(&key);
+    let (_, batch) = upload_raw(&key, "input.jsonl", b"{}".to_vec(), Some("batch")).await;
+    let (_, notes) = upload_raw(&key, "notes.txt", b"notes".to_vec(), Some("assistants")).await;
+
+    let all = client.files().list(&[("limit", "10")]).await.unwrap();
+    let ids: Vec<&str> = all.data.iter().map(|file| file.id.as_str()).collect();
+    assert_eq!(ids, vec![notes["id"].as_str().unwrap(), batch["id"].as_str().unwrap()]);
+
+    let batch_only = client.files().list(&[("purpose", "batch")]).await.unwrap();
+    assert_eq!(batch_only.data.len(), 1);
+    assert_eq!(batch_only.data[0].filename, "input.jsonl");
+
+    let deleted = client.files().delete(notes["id"].as_str().unwrap()).await.unwrap();
+    assert!(deleted.deleted);
+    assert_eq!(client.files().list(&[("limit", "10")]).await.unwrap().data.len(), 1);
+});
+
+teenytiny_test!(async fn test_other_keys_files_are_not_found() {
+    let (_, file) = upload_raw(&api_key(), "notes.txt", b"private".to_vec(), Some("assistants")).await;
+
+    let err = client_for(&new_api_key().await)
+        .files()
+        .retrieve(file["id"].as_str().unwrap())
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("No such File object"), "Unexpected error: {}", err);
+});
+
+teenytiny_test!(async fn test_file_over_the_limit_is_rejected() {
+    let (status, body) = upload_raw(&api_key(), "big.txt", vec![b'x'; MAX_FILE_BYTES + 1], Some("assistants")).await;
+
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(body["error"]["type"], "invalid_request_error");
+    assert_eq!(body["error"]["code"], "file_too_large");
+    assert_eq!(body["error"]["param"], "file");
+});
+
+teenytiny_test!(async fn test_body_over_the_limit_is_rejected() {
+    // Comfortably over the server's default 8MB body limit, so refused before parsing
+    let (status, body) = upload_raw(&api_key(), "huge.txt", vec![b'x'; 9 * 1024 * 1024], Some("assistants")).await;
+
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(body["error"]["code"], "request_too_large");
+});
+
+teenytiny_test!(async fn test_invalid_uploads_are_rejected() {
+    let cases: [(&str, Vec<u8>, Option<&str>, &str); 4] = [
+        ("input.jsonl", b"{}".to_vec(), None, "purpose"),
+        ("input.jsonl", b"{}".to_vec(), Some("batch_output"), "purpose"),
+        ("input.jsonl", Vec::new(), Some("batch"), "file"),
+        ("input.csv", b"a,b".to_vec(), Some("batch"), "file"),
+    ];
+
+    for (filename, content, purpose, param) in cases {
+        let (status, body) = upload_raw(&api_key(), filename, content, purpose).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{:?} {:?}: {}", filename, purpose, body);
+        assert_eq!(body["error"]["param"], param, "{:?} {:?}: {}", filename, purpose, body);
+    }
+});
@@ -0,0 +1,68 @@
+// The filtered model cuts its echo off halfway with finish_reason
+// content_filter, as a provider does when its filter trips. These check a
+// client can tell such a reply from a complete one, blocking and streaming.
+
+use async_openai::types::{CreateChatCompletionRequest, CreateChatCompletionRequestArgs, FinishReason};
+use futures::StreamExt;
+
+use crate::setup_client;
+use super::user_message;
+
+const MESSAGE: &str = "one two three four five six seven";
+const FILTERED: &str = "one two three four";
+
+fn request(model: &str, content: &str, stream: bool) -> CreateChatCompletionRequest {
+    CreateChatCompletionRequestArgs::default()
+        .model(model)
+        .messages([user_message(content)])
+        .stream(stream)
+        .build().unwrap()
+}
+
+// The streamed content, and the finish reason of the final chunk
+async fn stream(model: &str, content: &str) -> (String, Option<FinishReason>) {
+    let mut stream = setup_client().chat().create_stream(request(model, content, true)).await.unwrap();
+
+    let mut text = String::new();
+    let mut finish_reason = None;
+    while let Some(result) = stream.next().await {
+        let chunk = result.unwrap();
+        let Some(choice) = chunk.choices.first() else { continue };
+        assert!(finish_reason.is_none(), "Content arrived after the finish reason");
+        text.push_str(choice.delta.content.as_deref().unwrap_or_default());
+        finish_reason = choice.finish_reason;
+    }
+    (text, finish_reason)
+}
+
+teenytiny_test!(async fn test_blocking_reply_is_filtered() {
+    let response = setup_client().chat().create(request("filtered", MESSAGE, false)).await.unwrap();
+
+    assert_eq!(response.choices[0].finish_reason, Some(FinishReason::ContentFilter));
+    assert_eq!(response.choices[0].message.content.as_deref(), Some(FILTERED));
+    let usage = response.usage.expect("No usage in response");
+    assert_eq!(usage.completion_tokens, 4, "Usage should count only what was sent");
+});
+
+teenytiny_test!(async fn test_streamed_reply_is_filtered() {
+    let (text, finish_reason) = stream("filtered", MESSAGE).await;
+
+    assert_eq!(text, FILTERED);
+    assert_eq!(finish_reason, Some(FinishReason::ContentFilter));
+});
+
+teenytiny_test!(async fn test_filtered_default_reply() {
+    let response = setup_client().chat().create(request("filtered", "", false)).await.unwrap();
+
+    let content = response.choices[0].message.content.clone().unwrap_or_default();
+    assert!(content.starts_with("Hello! I'm the Filtered model."), "Unexpected reply: {}", content);
+    assert_eq!(response.choices[0].finish_reason, Some(FinishReason::ContentFilter));
+});
+
+teenytiny_test!(async fn test_finish_directive_filters_echo() {
+    // Echo keeps the whole message, for apps that only look at the finish reason
+    let (text, finish_reason) = stream("echo", "Nothing to see here !finish:content_filter").await;
+
+    assert_eq!(text, "Nothing to see here");
+    assert_eq!(finish_reason, Some(FinishReason::ContentFilter));
+});
@@ -0,0 +1,98 @@
+// The fixture model is only registered when the server is started with
+// --fixtures, so these tests skip unless it's listed. They expect the
+// example fixtures in service/fixtures. The hot reload test also needs
+// TEENYTINY_FIXTURES_DIR pointing at the same directory the server watches.
+
+use std::time::Duration;
+
+use async_openai::types::CreateChatCompletionRequestArgs;
+use futures::StreamExt;
+
+use crate::config::config;
+use crate::setup_client;
+use super::{skip, user_message};
+
+async fn fixture_model_available() -> bool {
+    let models = setup_client().models().list().await.unwrap();
+    let available = models.data.iter().any(|m| m.id == "fixture");
+    if !available {
+        skip("the server was not started with --fixtures");
+    }
+    available
+}
+
+async fn ask(message: &str) -> String {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("fixture")
+        .messages([user_message(message)])
+        .build().unwrap();
+
+    let response = setup_client().chat().create(request).await.unwrap();
+    response.choices[0].message.content.clone().expect("No content in response")
+}
+
+teenytiny_test!(async fn test_exact_match() {
+    if !fixture_model_available().await { return; }
+
+    assert_eq!(ask("What is the capital of France?").await, "The capital of France is Paris.");
+});
+
+teenytiny_test!(async fn test_regex_match_with_capture() {
+    if !fixture_model_available().await { return; }
+
+    assert_eq!(ask("What is the weather in Lisbon?").await, "It is 21°C and sunny in Lisbon.");
+    assert_eq!(ask("what is the weather in Oslo").await, "It is 21°C and sunny in Oslo.");
+});
+
+teenytiny_test!(async fn test_fixture_chunks_when_streaming() {
+    if !fixture_model_available().await { return; }
+
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("fixture")
+        .messages([user_message("Summarize our refund policy")])
+        .stream(true)
+        .build().unwrap();
+
+    let mut stream = setup_client().chat().create_stream(request).await.unwrap();
+
+    let mut deltas = Vec::new();
+    while let Some(result) = stream.next().await {
+        if let Some(content) = result.unwrap().choices.first().and_then(|c| c.delta.content.clone()) {
+            deltas.push(content);
+        }
+    }
+
+    assert_eq!(deltas, ["Refunds are available", " within 30 days", " of purchase."]);
+});
+
+teenytiny_test!(async fn test_unmatched_message() {
+    if !fixture_model_available().await { return; }
+
+    let content = ask("Something nobody wrote a fixture for").await;
+
+    assert!(content.starts_with("No fixture matches"), "Unexpected reply: {}", content);
+});
+
+teenytiny_test!(async fn test_hot_reload() {
+    if !fixture_model_available().await { return; }
+    let Some(dir) = &config().fixtures_dir else {
+        skip("TEENYTINY_FIXTURES_DIR is not set");
+        return;
+    };
+
+    let message = format!("Hot reload probe {}", std::process::id());
+    let path = dir.join(format!("zz-harness-{}.json", std::process::id()));
+    std::fs::write(&path, serde_json::json!([{"match": message, "response": "Reloaded!"}]).to_string()).unwrap();
+
+    let mut reply = String::new();
+    for _ in 0..20 {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        reply = ask(&message).await;
+        if reply == "Reloaded!" {
+            break;
+        }
+    }
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(reply, "Reloaded!", "New fixture was not picked up within two seconds");
+});
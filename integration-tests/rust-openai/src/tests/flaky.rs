@@ -0,0 +1,240 @@
+// The flaky model fails a share of requests at random. Tests pin the fault
+// they want with a !fault directive, except the one checking the random mix.
+
+use async_openai::error::OpenAIError;
+use async_openai::types::CreateChatCompletionRequestArgs;
+use futures::StreamExt;
+use reqwest::{Response, StatusCode};
+use serde_json::{json, Value};
+
+use crate::{api_key, base_url, setup_client};
+use super::user_message;
+
+async fn send(content: &str, stream: bool) -> Result<Response, reqwest::Error> {
+    crate::http_client()
+        .post(format!("{}/v1/chat/completions", base_url()))
+        .bearer_auth(api_key())
+        .json(&json!({
+            "model": "flaky",
+            "messages": [{"role": "user", "content": content}],
+            "stream": stream,
+        }))
+        .send()
+        .await
+}
+
+// Reads the body chunk by chunk, returning what arrived and whether it ended in an error
+async fn read_body(mut response: Response) -> (String, bool) {
+    let mut body = Vec::new();
+    loop {
+        match response.chunk().await {
+            Ok(Some(chunk)) => body.extend_from_slice(&chunk),
+            Ok(None) => return (String::from_utf8_lossy(&body).into_owned(), false),
+            Err(_) => return (String::from_utf8_lossy(&body).into_owned(), true),
+        }
+    }
+}
+
+teenytiny_test!(async fn test_http_error_faults() {
+    for (fault, status, error_type) in [
+        ("500", StatusCode::INTERNAL_SERVER_ERROR, "api_error"),
+        ("502", StatusCode::BAD_GATEWAY, "api_error"),
+        ("503", StatusCode::SERVICE_UNAVAILABLE, "overloaded_error"),
+    ] {
+        for stream in [false, true] {
+            let response = send(&format!("Hello !fault:{}", fault), stream).await.unwrap();
+
+            assert_eq!(response.status(), status, "Wrong status for !fault:{} (stream: {})", fault, stream);
+            let body: Value = response.json().await.unwrap();
+            assert_eq!(body["error"]["type"], error_type);
+        }
+    }
+});
+
+teenytiny_test!(async fn test_http_error_fault_through_client() {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("flaky")
+        .messages([user_message("Hello !fault:503")])
+        .build().unwrap();
+
+    let result = setup_client().chat().create(request).await;
+
+    assert!(result.is_err(), "Expected the injected 503 to surface as an error");
+});
+
+teenytiny_test!(async fn test_connection_reset_mid_stream() {
+    let response = send("Hello there !fault:reset", true).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK, "A reset should happen after the response starts");
+
+    let (body, errored) = read_body(response).await;
+
+    assert!(errored, "Expected the stream to be cut off, got complete body: {}", body);
+    assert!(body.starts_with("data: "), "Expected some frames before the reset");
+    assert!(!body.contains("[DONE]"), "A reset stream should never finish");
+});
+
+teenytiny_test!(async fn test_connection_reset_without_streaming() {
+    let response = send("Hello there !fault:reset", false).await.unwrap();
+
+    let (body, errored) = read_body(response).await;
+
+    assert!(errored, "Expected the body to be cut off, got: {}", body);
+    assert!(serde_json::from_str::<Value>(&body).is_err(), "A partial body should not parse");
+});
+
+teenytiny_test!(async fn test_connection_reset_through_client() {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("flaky")
+        .messages([user_message("Hello there !fault:reset")])
+        .stream(true)
+        .build().unwrap();
+
+    let mut stream = setup_client().chat().create_stream(request).await.unwrap();
+
+    let mut saw_error = false;
+    while let Some(result) = stream.next().await {
+        if result.is_err() {
+            saw_error = true;
+            break;
+        }
+    }
+
+    assert!(saw_error, "Expected the reset to surface as a stream error");
+});
+
+teenytiny_test!(async fn test_malformed_sse_frame() {
+    let response = send("Hello there !fault:malformed", true).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let (body, errored) = read_body(response).await;
+    assert!(!errored, "A malformed frame should not end the stream");
+
+    let payloads: Vec<&str> = body.split("\n\n")
+        .filter_map(|frame| frame.strip_prefix("data: "))
+        .collect();
+    let malformed = payloads.iter()
+        .filter(|p| **p != "[DONE]" && serde_json::from_str::<Value>(p).is_err())
+        .count();
+
+    assert_eq!(malformed, 1, "Expected exactly one malformed frame in: {}", body);
+    assert_eq!(payloads.last(), Some(&"[DONE]"));
+});
+
+teenytiny_test!(async fn test_malformed_frame_through_client() {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("flaky")
+        .messages([user_message("Hello there !fault:malformed")])
+        .stream(true)
+        .build().unwrap();
+
+    let mut stream = setup_client().chat().create_stream(request).await.unwrap();
+
+    let mut saw_error = false;
+    while let Some(result) = stream.next().await {
+        if result.is_err() {
+            saw_error = true;
+            break;
+        }
+    }
+
+    assert!(saw_error, "Expected the malformed frame to fail deserialization");
+});
+
+teenytiny_test!(async fn test_malformed_json_without_streaming() {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("flaky")
+        .messages([user_message("Hello !fault:malformed")])
+        .build().unwrap();
+
+    let result = setup_client().chat().create(request).await;
+
+    assert!(result.is_err(), "Expected truncated JSON to fail deserialization");
+});
+
+teenytiny_test!(async fn test_error_event_mid_stream() {
+    let response = send("Hello there !fault:error_event", true).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK, "An error event comes after the response starts");
+
+    let (body, errored) = read_body(response).await;
+    assert!(!errored, "The stream should end cleanly after the error event");
+
+    let payloads: Vec<Value> = body.split("\n\n")
+        .filter_map(|frame| frame.strip_prefix("data: "))
+        .map(|payload| serde_json::from_str(payload).unwrap_or_else(|_| panic!("Not JSON: {}", payload)))
+        .collect();
+    let (error, chunks) = payloads.split_last().expect("Expected frames before the error");
+    assert!(!chunks.is_empty(), "Expected some chunks before the error");
+    assert!(chunks.iter().all(|chunk| chunk["object"] == "chat.completion.chunk"));
+    assert_eq!(error["error"]["type"], "api_error");
+    assert_eq!(error["error"]["code"], "stream_error");
+    assert!(!body.contains("[DONE]"), "A failed stream should never finish");
+});
+
+teenytiny_test!(async fn test_error_event_without_streaming() {
+    let response = send("Hello !fault:error_event", false).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["error"]["code"], "stream_error");
+});
+
+// async-openai has no error item for streams: it tries the error envelope as a
+// chunk, so agent code sees a deserialization error with the server's message
+// lost, then a stream error when the connection ends without [DONE]. Polling
+// on after that would reconnect and send the request again.
+teenytiny_test!(async fn test_error_event_through_client() {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("flaky")
+        .messages([user_message("Hello there !fault:error_event")])
+        .stream(true)
+        .build().unwrap();
+
+    let mut stream = setup_client().chat().create_stream(request).await.unwrap();
+
+    let mut chunks = 0;
+    let first_error = loop {
+        match stream.next().await.expect("Stream ended without an error") {
+            Ok(_) => chunks += 1,
+            Err(error) => break error,
+        }
+    };
+    assert!(chunks > 0, "Expected chunks before the error");
+    assert!(
+        matches!(first_error, OpenAIError::JSONDeserialize(_)),
+        "Expected the error event to fail deserialization, got {:?}", first_error
+    );
+    let next = stream.next().await.expect("Expected the end of the stream to be reported");
+    assert!(matches!(next, Err(OpenAIError::StreamError(_))), "Unexpected item after the error: {:?}", next);
+});
+
+teenytiny_test!(async fn test_fault_none_always_succeeds() {
+    for _ in 0..10 {
+        let request = CreateChatCompletionRequestArgs::default()
+            .model("flaky")
+            .messages([user_message("Steady !fault:none")])
+            .build().unwrap();
+
+        let response = setup_client().chat().create(request).await.unwrap();
+
+        assert_eq!(response.choices[0].message.content.as_deref(), Some("Steady"));
+    }
+});
+
+teenytiny_test!(async fn test_random_faults_mix_success_and_failure() {
+    let mut successes = 0;
+    let mut failures = 0;
+
+    for _ in 0..40 {
+        let ok = match send("Roll the dice", false).await {
+            Ok(response) if response.status() == StatusCode::OK => {
+                let (body, errored) = read_body(response).await;
+                !errored && serde_json::from_str::<Value>(&body).is_ok()
+            }
+            _ => false,
+        };
+        if ok { successes += 1 } else { failures += 1 }
+    }
+
+    assert!(successes > 0, "Expected some requests to succeed");
+    assert!(failures > 0, "Expected some requests to fail");
+});
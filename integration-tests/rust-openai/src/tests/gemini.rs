@@ -0,0 +1,162 @@
+// Google's Gemini API, for apps built on Google's SDKs. Replies are
+// candidates with usageMetadata, and streams are one JSON array sent an
+// element at a time, so these read the raw body rather than use a client
+// library.
+
+use reqwest::{Response, StatusCode};
+use serde_json::{json, Value};
+
+use crate::{api_key, base_url, http_client};
+
+async fn post(path: &str, body: Value) -> Response {
+    http_client()
+        .post(format!("{}/v1beta/models/{}", base_url(), path))
+        .header("x-goog-api-key", api_key())
+        .json(&body)
+        .send()
+        .await
+        .unwrap()
+}
+
+fn user(text: &str) -> Value {
+    json!({"contents": [{"role": "user", "parts": [{"text": text}]}]})
+}
+
+// The text of a response's first candidate
+fn text(response: &Value) -> &str {
+    response["candidates"][0]["content"]["parts"][0]["text"].as_str().unwrap_or("")
+}
+
+fn assert_usage(response: &Value) {
+    let usage = &response["usageMetadata"];
+    let prompt = usage["promptTokenCount"].as_u64().expect("promptTokenCount");
+    let candidates = usage["candidatesTokenCount"].as_u64().expect("candidatesTokenCount");
+    assert!(candidates > 0, "Unexpected usage {}", usage);
+    assert_eq!(usage["totalTokenCount"].as_u64(), Some(prompt + candidates));
+}
+
+teenytiny_test!(async fn test_generate_content() {
+    let response = post("echo:generateContent", user("Hello from Gemini")).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value = response.json().await.unwrap();
+    let candidates = body["candidates"].as_array().unwrap();
+    assert_eq!(candidates.len(), 1);
+    assert_eq!(candidates[0]["content"]["role"], "model");
+    assert_eq!(candidates[0]["finishReason"], "STOP");
+    assert_eq!(text(&body), "Hello from Gemini");
+    assert_usage(&body);
+});
+
+teenytiny_test!(async fn test_system_instruction_and_history() {
+    let response = post(
+        "echo:generateContent",
+        json!({
+            "systemInstruction": {"parts": [{"text": "Be brief"}]},
+            "contents": [
+                {"role": "user", "parts": [{"text": "First"}]},
+                {"role": "model", "parts": [{"text": "Reply"}]},
+                {"role": "user", "parts": [{"text": "Second"}]}
+            ]
+        }),
+    )
+    .await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(text(&body), "Second");
+});
+
+teenytiny_test!(async fn test_max_output_tokens_cuts_the_reply_short() {
+    let mut request = user("one two three four five");
+    request["generationConfig"] = json!({"maxOutputTokens": 2});
+    let body: Value = post("echo:generateContent", request).await.json().await.unwrap();
+
+    assert_eq!(body["candidates"][0]["finishReason"], "MAX_TOKENS");
+});
+
+teenytiny_test!(async fn test_stream_is_a_json_array() {
+    let response = post("echo:streamGenerateContent", user("one two three four")).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let content_type = response.headers()["content-type"].to_str().unwrap().to_string();
+    assert!(content_type.starts_with("application/json"), "Unexpected Content-Type {}", content_type);
+
+    let chunks: Vec<Value> = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+    let (last, rest) = chunks.split_last().expect("No chunks");
+    assert!(!rest.is_empty(), "The reply should arrive in pieces");
+    for chunk in rest {
+        assert!(chunk["candidates"][0]["finishReason"].is_null(), "Only the last chunk finishes: {}", chunk);
+    }
+    assert_eq!(last["candidates"][0]["finishReason"], "STOP");
+    assert_usage(last);
+
+    let content: String = chunks.iter().map(text).collect();
+    assert_eq!(content, "one two three four");
+});
+
+teenytiny_test!(async fn test_stream_arrives_element_by_element() {
+    let mut response = post("slow:50:streamGenerateContent", user("one two three four")).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let mut reads = 0;
+    let mut body = String::new();
+    while let Some(chunk) = response.chunk().await.unwrap() {
+        reads += 1;
+        body.push_str(std::str::from_utf8(&chunk).unwrap());
+    }
+    assert!(reads > 1, "The whole stream arrived in one read");
+    assert!(body.starts_with('[') && body.ends_with(']'), "Not an array: {:?}", body);
+    let chunks: Vec<Value> = serde_json::from_str(&body).unwrap();
+    assert_eq!(chunks.len(), 5, "Four words and the finishing chunk");
+});
+
+teenytiny_test!(async fn test_stream_as_server_sent_events() {
+    let response = post("echo:streamGenerateContent?alt=sse", user("Hello events")).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let content_type = response.headers()["content-type"].to_str().unwrap().to_string();
+    assert!(content_type.starts_with("text/event-stream"), "Unexpected Content-Type {}", content_type);
+
+    let body = response.text().await.unwrap();
+    let events: Vec<Value> = body
+        .split("\r\n\r\n")
+        .filter_map(|event| event.strip_prefix("data: "))
+        .map(|data| serde_json::from_str(data).unwrap())
+        .collect();
+    let content: String = events.iter().map(text).collect();
+    assert_eq!(content, "Hello events");
+    assert_eq!(events.last().unwrap()["candidates"][0]["finishReason"], "STOP");
+});
+
+teenytiny_test!(async fn test_key_as_query_parameter() {
+    let response = http_client()
+        .post(format!("{}/v1beta/models/echo:generateContent", base_url()))
+        .query(&[("key", api_key())])
+        .json(&user("Hello"))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+});
+
+teenytiny_test!(async fn test_errors_use_google_status_names() {
+    let response = post("no-such-model:generateContent", user("Hi")).await;
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["error"]["code"], 404);
+    assert_eq!(body["error"]["status"], "NOT_FOUND");
+    assert!(body["error"]["message"].as_str().unwrap().contains("no-such-model"));
+
+    let response = http_client()
+        .post(format!("{}/v1beta/models/echo:generateContent", base_url()))
+        .json(&user("Hi"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["error"]["status"], "UNAUTHENTICATED");
+});
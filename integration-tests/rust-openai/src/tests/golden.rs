@@ -0,0 +1,319 @@
+// Golden snapshot tests: representative requests for every model and endpoint,
+// with the response compared as a whole against a snapshot in snapshots/. Any
+// change to a response's shape, a renamed field or a new one, shows up as a
+// snapshot diff. Values that differ from run to run are canonicalized first.
+//
+// After an intended change, review and accept the new snapshots with
+// `cargo insta review` (or rerun with INSTA_UPDATE=always).
+
+use reqwest::multipart::{Form, Part};
+use serde_json::{json, Map, Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{api_key, base_url};
+use super::post_json;
+
+// Fields that hold ids, timestamps or token counts. Token counts depend on the
+// tokenizer the server was started with, and the tokenizer tests cover them.
+const SCRUBBED: [(&str, &str); 6] = [
+    ("created", "[timestamp]"),
+    ("system_fingerprint", "[fingerprint]"),
+    ("session_id", "[session]"),
+    ("prompt_tokens", "[tokens]"),
+    ("completion_tokens", "[tokens]"),
+    ("total_tokens", "[tokens]"),
+];
+
+// Generated ids have a random part after these prefixes, while ids like
+// "call_0_0" and model ids are deterministic and stay in the snapshot
+const RANDOM_ID_PREFIXES: [&str; 2] = ["chatcmpl-", "modr-"];
+
+// Replaces the values that legitimately change between runs, so a snapshot
+// only changes when the shape or the deterministic content of a response does
+fn canonicalize(value: Value) -> Value {
+    match value {
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .map(|(key, value)| {
+                    let scrubbed = SCRUBBED.iter().find(|(name, _)| *name == key && !value.is_null());
+                    let value = match scrubbed {
+                        Some((_, placeholder)) => json!(placeholder),
+                        None if key == "id" && is_random_id(&value) => json!("[id]"),
+                        None => canonicalize(value),
+                    };
+                    (key, value)
+                })
+                .collect::<Map<_, _>>(),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(canonicalize).collect()),
+        Value::String(text) => Value::String(text.replace(&base_url(), "[base_url]")),
+        other => other,
+    }
+}
+
+fn is_random_id(value: &Value) -> bool {
+    value.as_str().is_some_and(|id| RANDOM_ID_PREFIXES.iter().any(|prefix| id.starts_with(prefix)))
+}
+
+// For models that pick their words at random: only the shape of the reply is stable
+fn without_text(mut response: Value) -> Value {
+    for choice in response["body"]["choices"].as_array_mut().into_iter().flatten() {
+        choice["message"]["content"] = json!("[text]");
+    }
+    response
+}
+
+// The status and canonicalized body of a response, snapshotted together
+async fn snapshot_of(response: reqwest::Response) -> Value {
+    let status = response.status().as_u16();
+    let body: Value = response.json().await.unwrap();
+    canonicalize(json!({"status": status, "body": body}))
+}
+
+async fn post(path: &str, body: Value) -> Value {
+    let (status, response) = post_json(path, body).await;
+    canonicalize(json!({"status": status.as_u16(), "body": response}))
+}
+
+async fn chat(body: Value) -> Value {
+    post("/v1/chat/completions", body).await
+}
+
+// Every event of a streamed completion, with [DONE] kept as a string
+async fn chat_stream(body: Value) -> Value {
+    let response = crate::http_client()
+        .post(format!("{}/v1/chat/completions", base_url()))
+        .bearer_auth(api_key())
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    let status = response.status().as_u16();
+    let text = response.text().await.unwrap();
+
+    let events: Vec<Value> = text
+        .lines()
+        .filter_map(|line| line.strip_prefix("data: "))
+        .map(|data| serde_json::from_str(data).unwrap_or_else(|_| json!(data)))
+        .collect();
+    canonicalize(json!({"status": status, "events": events}))
+}
+
+teenytiny_test!(async fn test_chat_completion_echo() {
+    let response = chat(json!({
+        "model": "echo",
+        "messages": [
+            {"role": "system", "content": "You are a snapshot."},
+            {"role": "user", "content": "Hello, snapshot!"}
+        ]
+    }))
+    .await;
+
+    insta::assert_json_snapshot!("chat_completion_echo", response);
+});
+
+teenytiny_test!(async fn test_chat_completion_echo_streaming() {
+    let response = chat_stream(json!({
+        "model": "echo",
+        "stream": true,
+        "messages": [{"role": "user", "content": "Hello, snapshot!"}]
+    }))
+    .await;
+
+    insta::assert_json_snapshot!("chat_completion_echo_streaming", response);
+});
+
+teenytiny_test!(async fn test_chat_completion_alias_reports_alias() {
+    let response = chat(json!({
+        "model": "gpt-4o-mini",
+        "messages": [{"role": "user", "content": "Who am I talking to?"}]
+    }))
+    .await;
+
+    insta::assert_json_snapshot!("chat_completion_alias", response);
+});
+
+teenytiny_test!(async fn test_chat_completion_echo_directives() {
+    let response = chat(json!({
+        "model": "echo",
+        "messages": [{"role": "user", "content": "Cut short !finish:length"}]
+    }))
+    .await;
+
+    insta::assert_json_snapshot!("chat_completion_echo_directives", response);
+});
+
+teenytiny_test!(async fn test_chat_completion_conversational_models() {
+    for model in ["eliza", "parry", "racter", "lorem"] {
+        let response = chat(json!({
+            "model": model,
+            "messages": [{"role": "user", "content": "I feel like talking today."}]
+        }))
+        .await;
+
+        let response = without_text(response);
+
+        insta::assert_json_snapshot!(format!("chat_completion_{}", model), response);
+    }
+});
+
+teenytiny_test!(async fn test_chat_completion_slow_streaming() {
+    let response = chat_stream(json!({
+        "model": "slow:0",
+        "stream": true,
+        "messages": [{"role": "user", "content": "One word at a time"}]
+    }))
+    .await;
+
+    insta::assert_json_snapshot!("chat_completion_slow_streaming", response);
+});
+
+teenytiny_test!(async fn test_chat_completion_flaky_without_fault() {
+    let response = chat(json!({
+        "model": "flaky",
+        "messages": [{"role": "user", "content": "Steady now !fault:none"}]
+    }))
+    .await;
+
+    insta::assert_json_snapshot!("chat_completion_flaky", response);
+});
+
+fn weather_tool() -> Value {
+    json!({
+        "type": "function",
+        "function": {
+            "name": "get_weather",
+            "description": "Get the weather for a city",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "city": {"type": "string"},
+                    "days": {"type": "integer", "minimum": 1},
+                    "units": {"enum": ["metric", "imperial"]}
+                },
+                "required": ["city", "days", "units"]
+            }
+        }
+    })
+}
+
+teenytiny_test!(async fn test_chat_completion_tooluse() {
+    let response = chat(json!({
+        "model": "tooluse",
+        "tools": [weather_tool()],
+        "messages": [{"role": "user", "content": "What's the weather in Paris?"}]
+    }))
+    .await;
+
+    insta::assert_json_snapshot!("chat_completion_tooluse", response);
+});
+
+teenytiny_test!(async fn test_chat_completion_tooluse_streaming() {
+    let response = chat_stream(json!({
+        "model": "tooluse",
+        "stream": true,
+        "tools": [weather_tool()],
+        "messages": [{"role": "user", "content": "What's the weather in Paris?"}]
+    }))
+    .await;
+
+    insta::assert_json_snapshot!("chat_completion_tooluse_streaming", response);
+});
+
+teenytiny_test!(async fn test_chat_completion_json_schema() {
+    let response = chat(json!({
+        "model": "json",
+        "response_format": {
+            "type": "json_schema",
+            "json_schema": {"name": "weather", "schema": weather_tool()["function"]["parameters"]}
+        },
+        "messages": [{"role": "user", "content": "Describe the weather"}]
+    }))
+    .await;
+
+    insta::assert_json_snapshot!("chat_completion_json_schema", response);
+});
+
+teenytiny_test!(async fn test_chat_completion_errors() {
+    let unknown_model = chat(json!({"model": "no-such-model", "messages": [{"role": "user", "content": "Hi"}]})).await;
+    let missing_messages = chat(json!({"model": "echo"})).await;
+    let invalid_parameter = chat(json!({
+        "model": "echo",
+        "temperature": 5,
+        "messages": [{"role": "user", "content": "Hi"}]
+    }))
+    .await;
+
+    insta::assert_json_snapshot!("chat_completion_unknown_model", unknown_model);
+    insta::assert_json_snapshot!("chat_completion_missing_messages", missing_messages);
+    insta::assert_json_snapshot!("chat_completion_invalid_parameter", invalid_parameter);
+});
+
+teenytiny_test!(async fn test_invalid_api_key() {
+    let response = crate::http_client()
+        .get(format!("{}/v1/models", base_url()))
+        .bearer_auth("not-a-real-key")
+        .send()
+        .await
+        .unwrap();
+    let response = snapshot_of(response).await;
+
+    insta::assert_json_snapshot!("invalid_api_key", response);
+});
+
+teenytiny_test!(async fn test_models_list() {
+    let response = crate::http_client()
+        .get(format!("{}/v1/models", base_url()))
+        .bearer_auth(api_key())
+        .send()
+        .await
+        .unwrap();
+    let mut response = snapshot_of(response).await;
+
+    // Fixture and script models only exist when the server was started with them
+    response["body"]["data"].as_array_mut().unwrap().retain(|model| {
+        let id = model["id"].as_str().unwrap_or_default();
+        id != "fixture" && !id.starts_with("script:")
+    });
+
+    insta::assert_json_snapshot!("models_list", response);
+});
+
+teenytiny_test!(async fn test_moderation() {
+    let response = post("/v1/moderations", json!({"input": ["Have a nice day", "I will attack"]})).await;
+
+    insta::assert_json_snapshot!("moderation", response);
+});
+
+teenytiny_test!(async fn test_image_generation() {
+    let response = post("/v1/images/generations", json!({"prompt": "A tiny robot", "size": "256x256", "n": 2})).await;
+
+    insta::assert_json_snapshot!("image_generation", response);
+});
+
+teenytiny_test!(async fn test_transcription() {
+    let form = Form::new()
+        .text("model", "whisper-1")
+        .text("response_format", "verbose_json")
+        .part("file", Part::bytes(b"not really audio".to_vec()).file_name("note.mp3"));
+    let response = crate::http_client()
+        .post(format!("{}/v1/audio/transcriptions", base_url()))
+        .bearer_auth(api_key())
+        .multipart(form)
+        .send()
+        .await
+        .unwrap();
+    let response = snapshot_of(response).await;
+
+    insta::assert_json_snapshot!("transcription", response);
+});
+
+teenytiny_test!(async fn test_session_say() {
+    // A fresh session each run, since the server keeps history between runs
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+    let path = format!("/session/golden-{}/say", nanos);
+    let response = post(&path, json!({"message": "Remember this"})).await;
+
+    insta::assert_json_snapshot!("session_say", response);
+});
@@ -0,0 +1,31 @@
+use reqwest::StatusCode;
+use serde_json::Value;
+
+use crate::base_url;
+
+async fn get(path: &str) -> (StatusCode, Value) {
+    let response = reqwest::get(format!("{}{}", base_url(), path)).await.unwrap();
+    (response.status(), response.json().await.unwrap())
+}
+
+teenytiny_test!(async fn test_liveness_and_readiness() {
+    let (status, body) = get("/healthz").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "ok");
+
+    let (status, body) = get("/readyz").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "ready");
+    assert!(body["checks"]["models"].as_u64().unwrap() > 0);
+});
+
+teenytiny_test!(async fn test_version_reports_build_metadata() {
+    let (status, body) = get("/version").await;
+    assert_eq!(status, StatusCode::OK);
+
+    for field in ["version", "git_sha", "build_time"] {
+        assert!(body[field].is_string(), "Missing {} in {}", field, body);
+    }
+    let surface = body["api_surface"].as_array().expect("No api_surface in response");
+    assert!(surface.iter().any(|area| area == "chat.completions"));
+});
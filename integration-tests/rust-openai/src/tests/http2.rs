@@ -0,0 +1,251 @@
+// Connection-level behavior: HTTP/2, connection reuse and how idle
+// connections end. The Node.js server speaks HTTP/2 with prior knowledge on
+// its plain HTTP port, and over ALPN when started with --tls-cert and
+// --tls-key; point TEENYTINY_URL at https:// for the ALPN test.
+
+use std::time::Duration;
+
+use reqwest::{StatusCode, Url, Version};
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+use crate::{api_key, server_url};
+use super::skip;
+
+fn chat_body(content: &str) -> Value {
+    json!({"model": "echo", "messages": [{"role": "user", "content": content}]})
+}
+
+fn is_https() -> bool {
+    server_url().starts_with("https://")
+}
+
+async fn connect() -> TcpStream {
+    let url = Url::parse(&server_url()).unwrap();
+    let host = url.host_str().unwrap().to_string();
+    TcpStream::connect((host, url.port_or_known_default().unwrap())).await.unwrap()
+}
+
+async fn chat(client: &reqwest::Client, content: &str) -> reqwest::Response {
+    client
+        .post(format!("{}/v1/chat/completions", server_url()))
+        .bearer_auth(api_key())
+        .json(&chat_body(content))
+        .send()
+        .await
+        .unwrap()
+}
+
+// One HTTP/1.1 response read off a raw connection: status, headers and body
+struct RawResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl RawResponse {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)).map(|(_, value)| value.as_str())
+    }
+}
+
+async fn read_response(reader: &mut BufReader<TcpStream>) -> RawResponse {
+    let mut line = String::new();
+    reader.read_line(&mut line).await.unwrap();
+    let status = line.split_whitespace().nth(1).expect("No status line").parse().unwrap();
+
+    let mut headers = Vec::new();
+    loop {
+        line.clear();
+        reader.read_line(&mut line).await.unwrap();
+        let Some((name, value)) = line.trim_end().split_once(':') else { break };
+        headers.push((name.to_string(), value.trim().to_string()));
+    }
+    let mut response = RawResponse { status, headers, body: String::new() };
+
+    let mut body = Vec::new();
+    if let Some(length) = response.header("content-length") {
+        body.resize(length.parse().unwrap(), 0);
+        reader.read_exact(&mut body).await.unwrap();
+    } else if response.header("transfer-encoding") == Some("chunked") {
+        loop {
+            line.clear();
+            reader.read_line(&mut line).await.unwrap();
+            let size = usize::from_str_radix(line.trim(), 16).unwrap();
+            let mut chunk = vec![0; size + 2]; // The chunk and its CRLF
+            reader.read_exact(&mut chunk).await.unwrap();
+            if size == 0 {
+                break;
+            }
+            body.extend_from_slice(&chunk[..size]);
+        }
+    }
+    response.body = String::from_utf8(body).unwrap();
+    response
+}
+
+async fn send_chat(reader: &mut BufReader<TcpStream>, content: &str) -> RawResponse {
+    let url = Url::parse(&server_url()).unwrap();
+    let body = chat_body(content).to_string();
+    let request = format!(
+        "POST /v1/chat/completions HTTP/1.1\r\nHost: {}\r\nAuthorization: Bearer {}\r\n\
+         Content-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+        url.host_str().unwrap(),
+        api_key(),
+        body.len(),
+        body
+    );
+    reader.get_mut().write_all(request.as_bytes()).await.unwrap();
+    read_response(reader).await
+}
+
+teenytiny_test!(async fn test_completion_over_http2_prior_knowledge() {
+    if is_https() {
+        skip("prior knowledge is for plain HTTP, the https:// server negotiates over ALPN");
+        return;
+    }
+    let client = crate::http_client_builder().http2_prior_knowledge().build().unwrap();
+
+    let response = chat(&client, "Hello over h2c").await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.version(), Version::HTTP_2);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["choices"][0]["message"]["content"], "Hello over h2c");
+});
+
+teenytiny_test!(async fn test_streamed_completion_over_http2() {
+    if is_https() {
+        skip("prior knowledge is for plain HTTP, the https:// server negotiates over ALPN");
+        return;
+    }
+    let client = crate::http_client_builder().http2_prior_knowledge().build().unwrap();
+
+    let response = client
+        .post(format!("{}/v1/chat/completions", server_url()))
+        .bearer_auth(api_key())
+        .json(&json!({"model": "echo", "stream": true, "messages": [{"role": "user", "content": "Streamed"}]}))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.version(), Version::HTTP_2);
+    assert!(response.text().await.unwrap().ends_with("data: [DONE]\n\n"));
+});
+
+teenytiny_test!(async fn test_completion_over_http2_alpn() {
+    if !is_https() {
+        skip("TEENYTINY_URL is not https://");
+        return;
+    }
+    let response = chat(&crate::http_client(), "Hello over h2").await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.version(), Version::HTTP_2, "ALPN should have negotiated h2");
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["choices"][0]["message"]["content"], "Hello over h2");
+});
+
+teenytiny_test!(async fn test_sequential_requests_reuse_the_connection() {
+    if is_https() {
+        skip("raw HTTP/1.1 needs a plain http:// server");
+        return;
+    }
+    let mut reader = BufReader::new(connect().await);
+
+    for content in ["First", "Second", "Third"] {
+        let response = send_chat(&mut reader, content).await;
+
+        assert_eq!(response.status, 200);
+        assert_ne!(response.header("connection"), Some("close"), "The server asked to close after {}", content);
+        let body: Value = serde_json::from_str(&response.body).unwrap();
+        assert_eq!(body["choices"][0]["message"]["content"], content);
+    }
+});
+
+teenytiny_test!(async fn test_sequential_http2_requests_share_one_connection() {
+    if is_https() {
+        skip("prior knowledge is for plain HTTP, the https:// server negotiates over ALPN");
+        return;
+    }
+    let (client, connection) = h2::client::handshake(connect().await).await.unwrap();
+    let connection = tokio::spawn(connection);
+
+    for content in ["First", "Second", "Third"] {
+        let mut client = client.clone().ready().await.unwrap();
+        let request = http::Request::post(format!("{}/v1/chat/completions", server_url()))
+            .header("authorization", format!("Bearer {}", api_key()))
+            .header("content-type", "application/json")
+            .body(())
+            .unwrap();
+        let (response, mut send) = client.send_request(request, false).unwrap();
+        send.send_data(chat_body(content).to_string().into(), true).unwrap();
+
+        let response = response.await.unwrap();
+        assert_eq!(response.status(), 200);
+        let mut body = response.into_body();
+        let mut bytes = Vec::new();
+        while let Some(chunk) = body.data().await {
+            let chunk = chunk.unwrap();
+            body.flow_control().release_capacity(chunk.len()).unwrap();
+            bytes.extend_from_slice(&chunk);
+        }
+        let body: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["choices"][0]["message"]["content"], content);
+    }
+
+    assert!(!connection.is_finished(), "Every request should have gone over the one connection");
+    connection.abort();
+});
+
+teenytiny_test!(async fn test_idle_http1_connection_closes_gracefully() {
+    if is_https() {
+        skip("raw HTTP/1.1 needs a plain http:// server");
+        return;
+    }
+    let mut reader = BufReader::new(connect().await);
+    let response = send_chat(&mut reader, "Then silence").await;
+    assert_eq!(response.status, 200);
+
+    let Some(timeout) = response
+        .header("keep-alive")
+        .and_then(|value| value.split(',').find_map(|param| param.trim().strip_prefix("timeout=")))
+        .map(|seconds| Duration::from_secs(seconds.parse().unwrap()))
+    else {
+        skip("the server advertises no Keep-Alive timeout");
+        return;
+    };
+
+    // Once the timeout passes the server should end the connection with a FIN
+    // (a clean end of stream), not a reset
+    let mut rest = Vec::new();
+    let read = tokio::time::timeout(timeout + Duration::from_secs(5), reader.read_to_end(&mut rest))
+        .await
+        .expect("The idle connection was never closed");
+    assert_eq!(read.expect("The idle connection was reset rather than closed"), 0);
+});
+
+teenytiny_test!(async fn test_idle_http2_connection_ends_with_goaway() {
+    if is_https() {
+        skip("prior knowledge is for plain HTTP, the https:// server negotiates over ALPN");
+        return;
+    }
+    let (client, connection) = h2::client::handshake(connect().await).await.unwrap();
+    let connection = tokio::spawn(connection);
+
+    let mut client = client.ready().await.unwrap();
+    let request = http::Request::get(format!("{}/healthz", server_url())).body(()).unwrap();
+    let (response, _) = client.send_request(request, true).unwrap();
+    let mut body = response.await.unwrap().into_body();
+    while body.data().await.is_some() {}
+
+    // The client stays open, so only the server can end the connection. A
+    // GOAWAY with NO_ERROR lets it finish cleanly; a reset would be an error.
+    let ended = tokio::time::timeout(Duration::from_secs(15), connection)
+        .await
+        .expect("The idle HTTP/2 connection was never closed")
+        .unwrap();
+    assert!(ended.is_ok(), "The idle HTTP/2 connection ended with {:?}", ended);
+    drop(client);
+});
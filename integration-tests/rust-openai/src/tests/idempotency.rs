@@ -0,0 +1,113 @@
+// Request IDs and Idempotency-Key, for testing retry layers: a repeated key
+// must get the first response back, not a second completion that would be
+// charged twice. Eliza answers the same message differently from one request
+// to the next, so a replay is easy to tell from a fresh response.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use reqwest::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use crate::raw::{self, assert_error, RawResponse};
+
+// Keys stay cached on the server across runs, so each test makes its own
+fn unique(prefix: &str) -> String {
+    static COUNTER: AtomicU32 = AtomicU32::new(0);
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+    format!("{}-{}-{}", prefix, nanos, COUNTER.fetch_add(1, Ordering::Relaxed))
+}
+
+fn chat_body(model: &str, content: &str, stream: bool) -> Value {
+    json!({"model": model, "stream": stream, "messages": [{"role": "user", "content": content}]})
+}
+
+async fn chat(body: &Value, headers: &[(&str, &str)]) -> RawResponse {
+    let mut request = raw::request(Method::POST, "/v1/chat/completions").json(body);
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    raw::send(request).await
+}
+
+teenytiny_test!(async fn test_request_id_is_passed_through() {
+    let id = unique("client-request");
+
+    let response = chat(&chat_body("echo", "Hello", false), &[("x-request-id", &id)]).await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.header("x-request-id"), Some(id.as_str()));
+});
+
+teenytiny_test!(async fn test_request_id_is_generated_otherwise() {
+    let body = chat_body("echo", "Hello", false);
+    let first = chat(&body, &[]).await;
+    let second = chat(&body, &[]).await;
+
+    let first_id = first.header("x-request-id").expect("No x-request-id on the response");
+    assert!(!first_id.is_empty());
+    assert_ne!(Some(first_id), second.header("x-request-id"), "Request ids should differ per request");
+});
+
+teenytiny_test!(async fn test_repeated_key_replays_the_response() {
+    let key = unique("replay");
+    let body = chat_body("eliza", "I keep retrying", false);
+
+    let first = chat(&body, &[("idempotency-key", &key)]).await;
+    let repeat = chat(&body, &[("idempotency-key", &key)]).await;
+
+    assert_eq!(first.status, StatusCode::OK, "{}", first.text());
+    assert_eq!(repeat.status, first.status);
+    assert_eq!(repeat.body, first.body, "A repeated key should get the same body");
+    assert_eq!(repeat.json()["id"], first.json()["id"]);
+    assert_eq!(repeat.header("x-request-id"), first.header("x-request-id"));
+    assert_eq!(first.header("idempotent-replayed"), None);
+    assert_eq!(repeat.header("idempotent-replayed"), Some("true"));
+});
+
+teenytiny_test!(async fn test_distinct_keys_get_distinct_responses() {
+    let body = chat_body("eliza", "I keep retrying", false);
+
+    let first = chat(&body, &[("idempotency-key", &unique("distinct"))]).await;
+    let second = chat(&body, &[("idempotency-key", &unique("distinct"))]).await;
+
+    assert_ne!(second.json()["id"], first.json()["id"]);
+    assert_ne!(second.header("x-request-id"), first.header("x-request-id"));
+    assert_eq!(second.header("idempotent-replayed"), None);
+});
+
+teenytiny_test!(async fn test_repeated_key_replays_a_stream() {
+    let key = unique("stream");
+    let body = chat_body("eliza", "Stream it again", true);
+
+    let first = chat(&body, &[("idempotency-key", &key)]).await;
+    let repeat = chat(&body, &[("idempotency-key", &key)]).await;
+
+    assert_eq!(first.status, StatusCode::OK, "{}", first.text());
+    assert!(first.text().ends_with("data: [DONE]\n\n"), "Unexpected stream: {}", first.text());
+    assert_eq!(repeat.text(), first.text(), "A repeated key should get the same events");
+    assert_eq!(repeat.header("content-type"), first.header("content-type"));
+});
+
+teenytiny_test!(async fn test_key_reused_for_another_request_is_rejected() {
+    let key = unique("reused");
+    chat(&chat_body("echo", "First request", false), &[("idempotency-key", &key)]).await;
+
+    let response = chat(&chat_body("echo", "Second request", false), &[("idempotency-key", &key)]).await;
+
+    let error = assert_error(&response, StatusCode::BAD_REQUEST, "invalid_request_error");
+    assert_eq!(error.code.as_deref(), Some("idempotency_key_reused"));
+    assert_eq!(error.param.as_deref(), Some("Idempotency-Key"));
+});
+
+teenytiny_test!(async fn test_server_errors_are_not_replayed() {
+    let key = unique("failed");
+    let body = chat_body("flaky", "Fail this !fault:503", false);
+
+    let first = chat(&body, &[("idempotency-key", &key)]).await;
+    let retry = chat(&body, &[("idempotency-key", &key)]).await;
+
+    assert_eq!(first.status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(retry.status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(retry.header("idempotent-replayed"), None, "A failed request should run again on retry");
+});
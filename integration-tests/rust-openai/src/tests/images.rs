@@ -0,0 +1,131 @@
+use async_openai::types::{
+    CreateImageRequestArgs, Image, ImageModel, ImageResponseFormat, ImageSize,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::StatusCode;
+
+use super::post_json;
+
+// Reads width and height from a PNG's IHDR chunk
+fn png_dimensions(bytes: &[u8]) -> (u32, u32) {
+    assert_eq!(&bytes[..8], b"\x89PNG\r\n\x1a\n", "Not a PNG");
+    assert_eq!(&bytes[12..16], b"IHDR", "PNG should start with IHDR");
+
+    let width = u32::from_be_bytes(bytes[16..20].try_into().unwrap());
+    let height = u32::from_be_bytes(bytes[20..24].try_into().unwrap());
+    (width, height)
+}
+
+teenytiny_test!(async fn test_url_response_format(client) {
+    let request = CreateImageRequestArgs::default()
+        .prompt("A tiny teal square")
+        .model(ImageModel::DallE2)
+        .size(ImageSize::S256x256)
+        .response_format(ImageResponseFormat::Url)
+        .build().unwrap();
+
+    let response = client.images().create(request).await.unwrap();
+
+    assert!(response.created > 0, "Created timestamp should be > 0");
+    assert_eq!(response.data.len(), 1);
+
+    let url = match response.data[0].as_ref() {
+        Image::Url { url, .. } => url.clone(),
+        other => panic!("Expected url image, got: {:?}", other),
+    };
+
+    let image = reqwest::get(&url).await.unwrap();
+    assert_eq!(image.status(), StatusCode::OK);
+    assert_eq!(image.headers()["content-type"], "image/png");
+    assert_eq!(png_dimensions(&image.bytes().await.unwrap()), (256, 256));
+});
+
+teenytiny_test!(async fn test_b64_json_response_format(client) {
+    let request = CreateImageRequestArgs::default()
+        .prompt("A tiny teal square")
+        .size(ImageSize::S512x512)
+        .response_format(ImageResponseFormat::B64Json)
+        .build().unwrap();
+
+    let response = client.images().create(request).await.unwrap();
+
+    let b64_json = match response.data[0].as_ref() {
+        Image::B64Json { b64_json, .. } => b64_json.clone(),
+        other => panic!("Expected b64_json image, got: {:?}", other),
+    };
+
+    let bytes = STANDARD.decode(b64_json.as_bytes()).unwrap();
+    assert_eq!(png_dimensions(&bytes), (512, 512));
+});
+
+teenytiny_test!(async fn test_size_is_echoed_in_image(client) {
+    for (size, expected) in [
+        (ImageSize::S1024x1024, (1024, 1024)),
+        (ImageSize::S1792x1024, (1792, 1024)),
+        (ImageSize::S1024x1792, (1024, 1792)),
+    ] {
+        let request = CreateImageRequestArgs::default()
+            .prompt("Size check")
+            .model(ImageModel::DallE3)
+            .size(size)
+            .response_format(ImageResponseFormat::B64Json)
+            .build().unwrap();
+
+        let response = client.images().create(request).await.unwrap();
+
+        let Image::B64Json { b64_json, .. } = response.data[0].as_ref() else {
+            panic!("Expected b64_json image");
+        };
+        let bytes = STANDARD.decode(b64_json.as_bytes()).unwrap();
+        assert_eq!(png_dimensions(&bytes), expected);
+    }
+});
+
+teenytiny_test!(async fn test_multiple_images(client) {
+    let request = CreateImageRequestArgs::default()
+        .prompt("Three squares")
+        .n(3)
+        .size(ImageSize::S256x256)
+        .build().unwrap();
+
+    let response = client.images().create(request).await.unwrap();
+
+    assert_eq!(response.data.len(), 3);
+    for image in &response.data {
+        assert!(matches!(image.as_ref(), Image::Url { .. }), "Expected url images by default");
+    }
+});
+
+teenytiny_test!(async fn test_revised_prompt(client) {
+    let request = CreateImageRequestArgs::default()
+        .prompt("Revise me")
+        .size(ImageSize::S256x256)
+        .build().unwrap();
+
+    let response = client.images().create(request).await.unwrap();
+
+    let Image::Url { revised_prompt, .. } = response.data[0].as_ref() else {
+        panic!("Expected url image");
+    };
+    assert_eq!(revised_prompt.as_deref(), Some("Revise me"));
+});
+
+teenytiny_test!(async fn test_unsupported_size() {
+    let (status, body) = post_json("/v1/images/generations", serde_json::json!({
+        "prompt": "Odd size",
+        "size": "300x300"
+    })).await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["type"], "invalid_request_error");
+    assert_eq!(body["error"]["param"], "size");
+});
+
+teenytiny_test!(async fn test_missing_prompt() {
+    let (status, body) = post_json("/v1/images/generations", serde_json::json!({
+        "size": "256x256"
+    })).await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["param"], "prompt");
+});
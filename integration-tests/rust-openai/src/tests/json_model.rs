@@ -0,0 +1,131 @@
+// The json model answers with the smallest value satisfying the request's
+// json_schema response_format, so structured output parsing can be tested offline.
+
+use async_openai::types::{CreateChatCompletionRequestArgs, ResponseFormat, ResponseFormatJsonSchema};
+use futures::StreamExt;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::{post_chat_completion, user_message};
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Article {
+    title: String,
+    status: Status,
+    rating: f64,
+    author: Author,
+    tags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Draft,
+    Published,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Author {
+    name: String,
+    age: u32,
+}
+
+fn article_format() -> ResponseFormat {
+    ResponseFormat::JsonSchema {
+        json_schema: ResponseFormatJsonSchema {
+            name: "article".to_string(),
+            description: None,
+            strict: Some(true),
+            schema: Some(json!({
+                "type": "object",
+                "properties": {
+                    "title": {"type": "string", "minLength": 12},
+                    "status": {"type": "string", "enum": ["draft", "published"]},
+                    "rating": {"type": "number", "minimum": 1, "maximum": 5},
+                    "author": {"$ref": "#/$defs/author"},
+                    "tags": {"type": "array", "items": {"type": "string"}, "minItems": 2, "maxItems": 5},
+                    "summary": {"type": "string"},
+                },
+                "required": ["title", "status", "rating", "author", "tags"],
+                "additionalProperties": false,
+                "$defs": {
+                    "author": {
+                        "type": "object",
+                        "properties": {
+                            "name": {"type": "string"},
+                            "age": {"type": "integer", "exclusiveMinimum": 17},
+                        },
+                        "required": ["name", "age"],
+                        "additionalProperties": false,
+                    },
+                },
+            })),
+        },
+    }
+}
+
+teenytiny_test!(async fn test_schema_conforming_output(client) {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("json")
+        .messages([user_message("Write an article")])
+        .response_format(article_format())
+        .build().unwrap();
+
+    let response = client.chat().create(request).await.unwrap();
+    let content = response.choices[0].message.content.clone().unwrap();
+    let article: Article = serde_json::from_str(&content).expect("Output does not match the schema");
+
+    assert!(article.title.len() >= 12);
+    assert_eq!(article.status, Status::Draft);
+    assert!((1.0..=5.0).contains(&article.rating));
+    assert!(!article.author.name.is_empty());
+    assert!(article.author.age > 17);
+    assert_eq!(article.tags.len(), 2);
+});
+
+teenytiny_test!(async fn test_streamed_output_parses(client) {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("json")
+        .messages([user_message("Write an article")])
+        .response_format(article_format())
+        .stream(true)
+        .build().unwrap();
+
+    let mut stream = client.chat().create_stream(request).await.unwrap();
+    let mut content = String::new();
+    while let Some(result) = stream.next().await {
+        if let Some(delta) = result.unwrap().choices.first().and_then(|c| c.delta.content.clone()) {
+            content.push_str(&delta);
+        }
+    }
+
+    let article: Article = serde_json::from_str(&content).expect("Streamed output does not match the schema");
+    assert_eq!(article.status, Status::Draft);
+});
+
+teenytiny_test!(async fn test_json_object_mode_wraps_message(client) {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("json")
+        .messages([user_message("Hello")])
+        .response_format(ResponseFormat::JsonObject)
+        .build().unwrap();
+
+    let response = client.chat().create(request).await.unwrap();
+    let value: Value = serde_json::from_str(&response.choices[0].message.content.clone().unwrap()).unwrap();
+
+    assert_eq!(value, json!({"message": "Hello"}));
+});
+
+teenytiny_test!(async fn test_invalid_response_format_is_rejected() {
+    let (status, body) = post_chat_completion(json!({
+        "model": "json",
+        "messages": [{"role": "user", "content": "Hello"}],
+        "response_format": {"type": "json_schema", "json_schema": {"name": "bad name!", "schema": {}}},
+    }))
+    .await;
+
+    assert_eq!(status, reqwest::StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["param"], "response_format.json_schema.name");
+});
@@ -0,0 +1,145 @@
+// Multi-tenant key tests. These need a server started with provisioned keys,
+// passed to the harness in the same format the server reads them:
+//
+//   TEENYTINY_API_KEYS="tenant-a,tenant-b:echo|eliza"
+//   TEENYTINY_REVOKED_KEYS="tenant-old"
+//
+// A key with a ":model|model" suffix may only use those models. Tests skip
+// when the variables aren't set.
+
+use async_openai::{config::OpenAIConfig, types::CreateChatCompletionRequestArgs, Client};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+
+use crate::base_url;
+use crate::config::config;
+use super::{skip, user_message};
+
+struct ProvisionedKey {
+    key: String,
+    models: Option<Vec<String>>,
+}
+
+fn parse_key_list(entries: &[String]) -> Option<Vec<ProvisionedKey>> {
+    if entries.is_empty() {
+        return None;
+    }
+
+    let keys = entries.iter()
+        .map(|entry| match entry.split_once(':') {
+            Some((key, models)) => ProvisionedKey {
+                key: key.to_string(),
+                models: Some(models.split('|').filter(|m| !m.is_empty()).map(String::from).collect()),
+            },
+            None => ProvisionedKey { key: entry.to_string(), models: None },
+        })
+        .collect();
+
+    Some(keys)
+}
+
+fn provisioned_keys() -> Option<Vec<ProvisionedKey>> {
+    let keys = parse_key_list(&config().api_keys);
+    if keys.is_none() {
+        skip("TEENYTINY_API_KEYS is not set");
+    }
+    keys
+}
+
+fn client_with_key(api_key: &str) -> Client<OpenAIConfig> {
+    let config = OpenAIConfig::new()
+        .with_api_key(api_key)
+        .with_api_base(format!("{}/v1", base_url()));
+
+    Client::with_config(config).with_http_client(crate::http_client())
+}
+
+async fn complete_raw(api_key: &str, model: &str) -> (StatusCode, Value) {
+    let response = crate::http_client()
+        .post(format!("{}/v1/chat/completions", base_url()))
+        .bearer_auth(api_key)
+        .json(&json!({
+            "model": model,
+            "messages": [{"role": "user", "content": "Hello"}],
+        }))
+        .send()
+        .await
+        .unwrap();
+
+    let status = response.status();
+    (status, response.json().await.unwrap())
+}
+
+teenytiny_test!(async fn test_each_provisioned_key_authenticates() {
+    let Some(keys) = provisioned_keys() else { return };
+
+    for provisioned in &keys {
+        let client = client_with_key(&provisioned.key);
+
+        let models = client.models().list().await
+            .unwrap_or_else(|e| panic!("Key {} failed to list models: {}", provisioned.key, e));
+        assert!(!models.data.is_empty());
+    }
+});
+
+teenytiny_test!(async fn test_each_provisioned_key_completes_independently() {
+    let Some(keys) = provisioned_keys() else { return };
+
+    for provisioned in &keys {
+        let model = provisioned.models.as_ref()
+            .and_then(|models| models.first().cloned())
+            .unwrap_or_else(|| "echo".to_string());
+
+        let request = CreateChatCompletionRequestArgs::default()
+            .model(model.as_str())
+            .messages([user_message("Tenant test")])
+            .build().unwrap();
+
+        let response = client_with_key(&provisioned.key).chat().create(request).await
+            .unwrap_or_else(|e| panic!("Key {} failed on {}: {}", provisioned.key, model, e));
+        assert_eq!(response.model, model);
+    }
+});
+
+teenytiny_test!(async fn test_revoked_keys_get_401() {
+    let Some(revoked) = parse_key_list(&config().revoked_keys) else {
+        skip("TEENYTINY_REVOKED_KEYS is not set");
+        return;
+    };
+
+    for provisioned in &revoked {
+        let (status, body) = complete_raw(&provisioned.key, "echo").await;
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "Revoked key {} was accepted", provisioned.key);
+        assert_eq!(body["error"]["type"], "authentication_error");
+    }
+});
+
+teenytiny_test!(async fn test_model_allowlist_enforced_with_403() {
+    let Some(keys) = provisioned_keys() else { return };
+
+    let scoped: Vec<_> = keys.iter()
+        .filter_map(|k| k.models.as_ref().map(|models| (&k.key, models)))
+        .collect();
+    if scoped.is_empty() {
+        skip("no key in TEENYTINY_API_KEYS has a model allowlist");
+        return;
+    }
+
+    let all_models: Vec<String> = client_with_key(scoped[0].0).models().list().await.unwrap()
+        .data.into_iter().map(|m| m.id).collect();
+
+    for (key, allowed) in scoped {
+        for model in &all_models {
+            let (status, body) = complete_raw(key, model).await;
+
+            if allowed.contains(model) {
+                assert_eq!(status, StatusCode::OK, "Key {} should reach {}: {}", key, model, body);
+            } else {
+                assert_eq!(status, StatusCode::FORBIDDEN, "Key {} should not reach {}", key, model);
+                assert_eq!(body["error"]["type"], "permission_error");
+                assert_eq!(body["error"]["code"], "model_not_allowed");
+            }
+        }
+    }
+});
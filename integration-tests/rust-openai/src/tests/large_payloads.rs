@@ -0,0 +1,95 @@
+// Request bodies of exact sizes around the server's body limit, which it
+// reports in /version: everything up to the limit echoes back whole, blocking
+// and streaming, and a byte over gets a 413 before any stream starts.
+
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+
+use crate::raw::{self, assert_error, RawResponse};
+use super::skip;
+
+const MB: usize = 1024 * 1024;
+
+// Splitting the echo keeps each streamed frame a manageable size
+const DIRECTIVE: &str = " !chunks:16";
+
+async fn max_body_bytes() -> Option<usize> {
+    let limit = raw::get("/version").await.json()["limits"]["max_body_bytes"].as_u64();
+    if limit.is_none() {
+        skip("the server does not report its body limit in /version");
+    }
+    limit.map(|limit| limit as usize)
+}
+
+fn chat_body(content: &str, stream: bool) -> String {
+    json!({"model": "echo", "stream": stream, "messages": [{"role": "user", "content": content}]}).to_string()
+}
+
+// A request of exactly `size` bytes, and the padding the echo should return
+fn body_of_size(size: usize, stream: bool) -> (String, String) {
+    let overhead = chat_body("", stream).len() + DIRECTIVE.len();
+    let padding = "x".repeat(size - overhead);
+    let body = chat_body(&format!("{}{}", padding, DIRECTIVE), stream);
+    assert_eq!(body.len(), size);
+    (body, padding)
+}
+
+fn streamed_content(response: &RawResponse) -> String {
+    let text = response.text();
+    assert!(text.trim_end().ends_with("data: [DONE]"), "Stream did not finish");
+    text.lines()
+        .filter_map(|line| line.strip_prefix("data: "))
+        .filter(|data| *data != "[DONE]")
+        .map(|data| serde_json::from_str::<Value>(data).expect("Invalid chunk"))
+        .filter_map(|chunk| chunk["choices"][0]["delta"]["content"].as_str().map(String::from))
+        .collect()
+}
+
+async fn assert_echoed(size: usize) {
+    let (body, padding) = body_of_size(size, false);
+    let response = raw::post("/v1/chat/completions", body).await;
+    assert_eq!(response.status, StatusCode::OK, "{} bytes: {}", size, response.text());
+    assert!(response.json()["choices"][0]["message"]["content"] == padding.as_str(), "{} bytes: echo differs", size);
+
+    let (body, padding) = body_of_size(size, true);
+    let response = raw::post("/v1/chat/completions", body).await;
+    assert_eq!(response.status, StatusCode::OK, "{} bytes streamed: {}", size, response.text());
+    assert!(streamed_content(&response) == padding, "{} bytes streamed: echo differs", size);
+}
+
+teenytiny_test!(async fn test_1mb_payload() {
+    let Some(limit) = max_body_bytes().await else { return };
+    if limit < MB {
+        skip("the body limit is under 1MB");
+        return;
+    }
+    assert_echoed(MB).await;
+});
+
+teenytiny_test!(async fn test_5mb_payload() {
+    let Some(limit) = max_body_bytes().await else { return };
+    if limit < 5 * MB {
+        skip("the body limit is under 5MB");
+        return;
+    }
+    assert_echoed(5 * MB).await;
+});
+
+teenytiny_test!(async fn test_payload_at_the_limit() {
+    let Some(limit) = max_body_bytes().await else { return };
+    assert_echoed(limit).await;
+});
+
+teenytiny_test!(async fn test_payload_just_over_the_limit() {
+    let Some(limit) = max_body_bytes().await else { return };
+
+    for stream in [false, true] {
+        let (body, _) = body_of_size(limit + 1, stream);
+        let response = raw::post("/v1/chat/completions", body).await;
+
+        let error = assert_error(&response, StatusCode::PAYLOAD_TOO_LARGE, "invalid_request_error");
+        assert_eq!(error.code.as_deref(), Some("request_too_large"));
+        assert!(error.message.contains(&limit.to_string()), "Expected the limit in {:?}", error.message);
+        assert!(!response.text().contains("data: "), "A rejected request should not start a stream");
+    }
+});
@@ -0,0 +1,130 @@
+// Injected delays are asked for per request with headers, so they don't slow
+// down tests running alongside. Timing assertions allow generous tolerance,
+// since network and scheduling jitter add to every delay.
+
+use std::time::{Duration, Instant};
+
+use reqwest::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use crate::{api_key, base_url};
+use super::skip;
+
+const TOLERANCE: Duration = Duration::from_millis(100);
+
+async fn chat(headers: &[(&str, &str)], stream: bool) -> reqwest::Response {
+    let mut request = crate::http_client()
+        .post(format!("{}/v1/chat/completions", base_url()))
+        .bearer_auth(api_key())
+        .json(&json!({
+            "model": "echo",
+            "messages": [{"role": "user", "content": "one two three four"}],
+            "stream": stream,
+        }));
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    request.send().await.unwrap()
+}
+
+async fn timed_chat(headers: &[(&str, &str)]) -> Duration {
+    let started = Instant::now();
+    let response = chat(headers, false).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    response.bytes().await.unwrap();
+    started.elapsed()
+}
+
+teenytiny_test!(async fn test_fixed_ttfb_delay_header() {
+    let elapsed = timed_chat(&[("x-teenytiny-delay-ms", "300")]).await;
+
+    let expected = Duration::from_millis(300);
+    assert!(
+        elapsed + TOLERANCE >= expected && elapsed <= expected + TOLERANCE * 5,
+        "Response took {:?}, expected about {:?}", elapsed, expected
+    );
+});
+
+teenytiny_test!(async fn test_jittered_ttfb_delay_stays_in_range() {
+    for _ in 0..3 {
+        let elapsed = timed_chat(&[("x-teenytiny-delay-ms", "200~100")]).await;
+        assert!(
+            elapsed + TOLERANCE >= Duration::from_millis(100) && elapsed <= Duration::from_millis(300) + TOLERANCE * 5,
+            "Response took {:?}, expected 100ms to 300ms", elapsed
+        );
+    }
+});
+
+teenytiny_test!(async fn test_chunk_delay_header_spaces_stream() {
+    let gap = Duration::from_millis(100);
+    let started = Instant::now();
+    let mut response = chat(&[("x-teenytiny-chunk-delay-ms", "100")], true).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let mut body = String::new();
+    let mut arrivals = Vec::new();
+    while let Some(chunk) = response.chunk().await.unwrap() {
+        body.push_str(&String::from_utf8_lossy(&chunk));
+        arrivals.push(Instant::now());
+    }
+    let elapsed = started.elapsed();
+
+    assert!(body.contains("data: [DONE]"));
+    let events = body.matches("data: ").count() as u32;
+    assert!(events >= 3, "Expected several events, got {}", events);
+    assert!(
+        elapsed + TOLERANCE >= gap * (events - 1),
+        "{} events arrived in {:?}, expected at least {:?} between each", events, elapsed, gap
+    );
+    assert!(arrivals.len() > 1, "Delayed chunks should not arrive together");
+});
+
+teenytiny_test!(async fn test_invalid_delay_header_is_rejected() {
+    let response = chat(&[("x-teenytiny-delay-ms", "soon")], false).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["error"]["param"], "x-teenytiny-delay-ms");
+});
+
+// Configures a path no other test uses, then puts the previous profiles back
+teenytiny_test!(async fn test_admin_latency_profile() {
+    let client = crate::http_client();
+    let admin = |method: Method, body: Option<Value>| {
+        let mut request = client
+            .request(method, format!("{}/admin/latency", base_url()))
+            .bearer_auth(api_key());
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+        request.send()
+    };
+
+    let response = admin(Method::GET, None).await.unwrap();
+    if response.status() == StatusCode::FORBIDDEN {
+        skip("TEENYTINY_API_KEY is not the server's key");
+        return;
+    }
+    let previous: Value = response.json().await.unwrap();
+
+    let mut profiles = previous.clone();
+    profiles["/v1/latency-probe"] = json!({"ttfb": {"type": "fixed", "ms": 300}});
+    let response = admin(Method::PUT, Some(profiles)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let started = Instant::now();
+    let probe = client
+        .get(format!("{}/v1/latency-probe", base_url()))
+        .bearer_auth(api_key())
+        .send()
+        .await
+        .unwrap();
+    let elapsed = started.elapsed();
+    admin(Method::PUT, Some(previous)).await.unwrap();
+
+    assert_eq!(probe.status(), StatusCode::NOT_FOUND);
+    assert!(
+        elapsed + TOLERANCE >= Duration::from_millis(300),
+        "Probe took {:?}, expected at least 300ms", elapsed
+    );
+});
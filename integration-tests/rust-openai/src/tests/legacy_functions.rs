@@ -0,0 +1,172 @@
+// The deprecated functions / function_call parameters, as older SDKs and
+// LangChain releases still send them. The tooluse model answers a legacy
+// request with a single message.function_call instead of tool_calls.
+
+use reqwest::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use crate::raw;
+use super::post_chat_completion;
+
+fn functions() -> Value {
+    json!([
+        {
+            "name": "get_weather",
+            "description": "Get the current weather in a city",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "city": {"type": "string", "minLength": 3},
+                    "unit": {"type": "string", "enum": ["celsius", "fahrenheit"]},
+                },
+                "required": ["city", "unit"],
+            },
+        },
+        {"name": "get_time", "parameters": {"type": "object", "properties": {}}},
+    ])
+}
+
+fn question() -> Value {
+    json!({"role": "user", "content": "What's the weather in Paris?"})
+}
+
+teenytiny_test!(async fn test_functions_answer_with_a_function_call() {
+    let (status, body) = post_chat_completion(json!({
+        "model": "tooluse",
+        "messages": [question()],
+        "functions": functions(),
+    }))
+    .await;
+
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let choice = &body["choices"][0];
+    assert_eq!(choice["finish_reason"], "function_call");
+    assert_eq!(choice["message"]["content"], Value::Null);
+    assert!(choice["message"].get("tool_calls").is_none(), "Legacy answers carry no tool_calls: {}", body);
+
+    let call = &choice["message"]["function_call"];
+    assert_eq!(call["name"], "get_weather");
+    let args: Value = serde_json::from_str(call["arguments"].as_str().unwrap()).expect("Arguments are not JSON");
+    assert_eq!(args["unit"], "celsius");
+});
+
+teenytiny_test!(async fn test_named_function_call() {
+    let (status, body) = post_chat_completion(json!({
+        "model": "tooluse",
+        "messages": [{"role": "user", "content": "What time is it?"}],
+        "functions": functions(),
+        "function_call": {"name": "get_time"},
+    }))
+    .await;
+
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["choices"][0]["message"]["function_call"], json!({"name": "get_time", "arguments": "{}"}));
+});
+
+teenytiny_test!(async fn test_function_call_none_answers_in_text() {
+    let (status, body) = post_chat_completion(json!({
+        "model": "tooluse",
+        "messages": [{"role": "user", "content": "Just talk to me"}],
+        "functions": functions(),
+        "function_call": "none",
+    }))
+    .await;
+
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["choices"][0]["finish_reason"], "stop");
+    assert_eq!(body["choices"][0]["message"]["content"], "Just talk to me");
+    assert!(body["choices"][0]["message"].get("function_call").is_none());
+});
+
+teenytiny_test!(async fn test_function_results_are_answered() {
+    let (_, first) = post_chat_completion(json!({
+        "model": "tooluse",
+        "messages": [question()],
+        "functions": functions(),
+    }))
+    .await;
+    let call = first["choices"][0]["message"]["function_call"].clone();
+
+    // The legacy round trip: the assistant's function_call, then a function message with the result
+    let (status, body) = post_chat_completion(json!({
+        "model": "tooluse",
+        "messages": [
+            question(),
+            {"role": "assistant", "content": null, "function_call": call},
+            {"role": "function", "name": "get_weather", "content": "Sunny and 21 degrees"},
+        ],
+        "functions": functions(),
+    }))
+    .await;
+
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["choices"][0]["finish_reason"], "stop");
+    assert_eq!(body["choices"][0]["message"]["content"], "Sunny and 21 degrees");
+});
+
+teenytiny_test!(async fn test_streamed_function_call() {
+    let request = raw::request(Method::POST, "/v1/chat/completions").json(&json!({
+        "model": "tooluse",
+        "stream": true,
+        "messages": [question()],
+        "functions": functions(),
+    }));
+    let response = raw::send(request).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+
+    let mut name = None;
+    let mut arguments = String::new();
+    let mut finish_reason = None;
+    for data in response.text().lines().filter_map(|line| line.strip_prefix("data: ")) {
+        if data == "[DONE]" {
+            continue;
+        }
+        let chunk: Value = serde_json::from_str(data).unwrap_or_else(|e| panic!("Bad chunk {} ({})", data, e));
+        let Some(choice) = chunk["choices"].get(0) else { continue };
+        assert!(choice["delta"].get("tool_calls").is_none(), "Legacy streams carry no tool_calls: {}", data);
+        let call = &choice["delta"]["function_call"];
+        if let Some(fragment) = call["name"].as_str() {
+            assert!(name.is_none(), "Only the first delta names the function: {}", data);
+            name = Some(fragment.to_string());
+        }
+        arguments.push_str(call["arguments"].as_str().unwrap_or(""));
+        if let Some(reason) = choice["finish_reason"].as_str() {
+            finish_reason = Some(reason.to_string());
+        }
+    }
+
+    assert_eq!(name.as_deref(), Some("get_weather"));
+    assert_eq!(finish_reason.as_deref(), Some("function_call"));
+    let args: Value = serde_json::from_str(&arguments).expect("Reassembled arguments are not JSON");
+    assert_eq!(args["unit"], "celsius");
+});
+
+teenytiny_test!(async fn test_tools_still_answer_with_tool_calls() {
+    let (status, body) = post_chat_completion(json!({
+        "model": "tooluse",
+        "messages": [question()],
+        "tools": [{"type": "function", "function": {"name": "get_time"}}],
+    }))
+    .await;
+
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["choices"][0]["finish_reason"], "tool_calls");
+    assert!(body["choices"][0]["message"].get("function_call").is_none());
+});
+
+teenytiny_test!(async fn test_invalid_legacy_requests_are_rejected() {
+    for (request, param) in [
+        (json!({"functions": functions(), "function_call": {"name": "get_stock_price"}}), "function_call"),
+        (json!({"functions": [{"name": "bad name"}]}), "functions[0].name"),
+        (json!({"functions": functions(), "tools": [{"type": "function", "function": {"name": "get_time"}}]}), "functions"),
+    ] {
+        let mut body = request.clone();
+        body["model"] = json!("tooluse");
+        body["messages"] = json!([question()]);
+        let (status, body) = post_chat_completion(body).await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", request);
+        assert_eq!(body["error"]["type"], "invalid_request_error");
+        assert_eq!(body["error"]["param"], param, "{}", request);
+    }
+});
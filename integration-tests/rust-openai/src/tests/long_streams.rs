@@ -0,0 +1,154 @@
+// A ~100k token stream from the lorem model, read the way a long-running
+// client would: chunk by chunk, keeping no more than counters. Checks the
+// chunks arrive in order and at a steady pace, that reading them doesn't grow
+// the client's memory, and that the final usage is right. These take a while,
+// so they only run with --long or TEENYTINY_LONG=1.
+
+use std::time::{Duration, Instant};
+
+use async_openai::types::{CreateChatCompletionRequest, CreateChatCompletionRequestArgs, FinishReason};
+use futures::StreamExt;
+
+use crate::setup_client;
+use super::{skip, user_message};
+
+const MAX_TOKENS: u32 = 100_000;
+const SEED: i64 = 579;
+
+// Generous, since other suites share the process, but far below a client that
+// buffers the stream's events as well as its text
+const MAX_RSS_GROWTH: u64 = 64 * 1024 * 1024;
+
+// No gap between chunks should come close to this while the server is writing
+const MAX_GAP: Duration = Duration::from_secs(2);
+
+fn lorem_request(stream: bool) -> CreateChatCompletionRequest {
+    CreateChatCompletionRequestArgs::default()
+        .model("lorem")
+        .messages([user_message("Write for a long time")])
+        .max_tokens(MAX_TOKENS)
+        .seed(SEED)
+        .stream(stream)
+        .build().unwrap()
+}
+
+// Resident memory of this process, where /proc says
+fn rss_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let kb = status.lines().find_map(|line| line.strip_prefix("VmRSS:"))?;
+    Some(kb.trim().trim_end_matches("kB").trim().parse::<u64>().ok()? * 1024)
+}
+
+#[derive(Default)]
+struct StreamStats {
+    content_chunks: usize,
+    characters: usize,
+    max_gap: Duration,
+    // When the first tenth of the expected text had arrived
+    first_tenth_at: Option<Duration>,
+    elapsed: Duration,
+    peak_rss_growth: u64,
+    finish_reason: Option<FinishReason>,
+    completion_tokens: Option<u32>,
+}
+
+// Reads the stream, checking each delta continues `expected` where the last left off
+async fn read_stream(expected: &str) -> StreamStats {
+    let mut stream = setup_client().chat().create_stream(lorem_request(true)).await.unwrap();
+    let rss_before = rss_bytes();
+
+    let mut stats = StreamStats::default();
+    let start = Instant::now();
+    let mut last = start;
+    let mut id = None;
+    while let Some(result) = stream.next().await {
+        let chunk = result.unwrap();
+        let now = Instant::now();
+        stats.max_gap = stats.max_gap.max(now - last);
+        last = now;
+
+        assert_eq!(id.get_or_insert_with(|| chunk.id.clone()), &chunk.id, "Chunk ids changed mid-stream");
+        if let Some(usage) = &chunk.usage {
+            stats.completion_tokens = Some(usage.completion_tokens);
+        }
+        let Some(choice) = chunk.choices.first() else { continue };
+        if choice.finish_reason.is_some() {
+            stats.finish_reason = choice.finish_reason;
+        }
+        let Some(content) = &choice.delta.content else { continue };
+
+        assert!(
+            expected[stats.characters..].starts_with(content.as_str()),
+            "Chunk {} is out of order at character {}: {:?}", stats.content_chunks, stats.characters, content
+        );
+        stats.characters += content.len();
+        stats.content_chunks += 1;
+        if stats.first_tenth_at.is_none() && stats.characters >= expected.len() / 10 {
+            stats.first_tenth_at = Some(start.elapsed());
+        }
+        if stats.content_chunks % 1000 == 0 {
+            if let (Some(before), Some(now)) = (rss_before, rss_bytes()) {
+                stats.peak_rss_growth = stats.peak_rss_growth.max(now.saturating_sub(before));
+            }
+        }
+    }
+    stats.elapsed = start.elapsed();
+    stats
+}
+
+// The same seed gives the same text, so a blocking completion is the reference
+async fn expected_completion() -> (String, u32) {
+    let response = setup_client().chat().create(lorem_request(false)).await.unwrap();
+    let content = response.choices[0].message.content.clone().expect("No content in response");
+    (content, response.usage.expect("No usage in response").completion_tokens)
+}
+
+teenytiny_test!(tags(long) async fn test_long_stream_arrives_in_order() {
+    let (expected, _) = expected_completion().await;
+
+    let stats = read_stream(&expected).await;
+
+    assert_eq!(stats.characters, expected.len(), "Stream ended early");
+    assert!(stats.content_chunks > 50_000, "Expected one chunk per word, got {}", stats.content_chunks);
+    assert_eq!(stats.finish_reason, Some(FinishReason::Length));
+});
+
+teenytiny_test!(tags(long) async fn test_long_stream_has_steady_cadence() {
+    let (expected, _) = expected_completion().await;
+
+    let stats = read_stream(&expected).await;
+
+    assert!(stats.max_gap < MAX_GAP, "Stream stalled for {:?}", stats.max_gap);
+    // A server that buffered the stream would deliver the first tenth near the end
+    let first_tenth_at = stats.first_tenth_at.expect("Stream ended before a tenth of the text");
+    assert!(
+        first_tenth_at < stats.elapsed / 2,
+        "The first tenth took {:?} of {:?}", first_tenth_at, stats.elapsed
+    );
+});
+
+teenytiny_test!(tags(long) async fn test_long_stream_keeps_client_memory_flat() {
+    if rss_bytes().is_none() {
+        skip("no /proc/self/status to read memory from");
+        return;
+    }
+    let (expected, _) = expected_completion().await;
+
+    let stats = read_stream(&expected).await;
+
+    assert!(
+        stats.peak_rss_growth < MAX_RSS_GROWTH,
+        "Client memory grew by {}MB reading the stream", stats.peak_rss_growth / (1024 * 1024)
+    );
+});
+
+teenytiny_test!(tags(long) async fn test_long_stream_final_usage() {
+    let (expected, blocking_tokens) = expected_completion().await;
+
+    let stats = read_stream(&expected).await;
+
+    let completion_tokens = stats.completion_tokens.expect("No usage in the final chunk");
+    assert_eq!(completion_tokens, blocking_tokens, "Streamed usage differs from the blocking completion");
+    assert!(completion_tokens <= MAX_TOKENS, "{} completion tokens exceeds max_tokens", completion_tokens);
+    assert!(completion_tokens >= MAX_TOKENS - 1, "Expected the budget to be filled, got {}", completion_tokens);
+});
@@ -0,0 +1,127 @@
+use async_openai::types::{CreateChatCompletionRequest, CreateChatCompletionRequestArgs, FinishReason};
+use futures::StreamExt;
+
+use crate::setup_client;
+use super::user_message;
+
+fn lorem_request() -> CreateChatCompletionRequestArgs {
+    let mut args = CreateChatCompletionRequestArgs::default();
+    args.model("lorem").messages([user_message("Write something")]);
+    args
+}
+
+// Returns the content and finish reason of a non-streaming completion
+async fn complete(request: CreateChatCompletionRequest) -> (String, Option<FinishReason>, u32) {
+    let response = setup_client().chat().create(request).await.unwrap();
+
+    let content = response.choices[0].message.content.clone()
+        .expect("No content in response");
+    let completion_tokens = response.usage.expect("No usage in response").completion_tokens;
+
+    (content, response.choices[0].finish_reason, completion_tokens)
+}
+
+teenytiny_test!(async fn test_default_output() {
+    let (content, finish_reason, _) = complete(lorem_request().build().unwrap()).await;
+
+    assert!(content.starts_with("Lorem ipsum dolor sit amet"), "Unexpected opening: {}", content);
+    assert_eq!(content.split("\n\n").count(), 3, "Expected three paragraphs");
+    assert_eq!(finish_reason, Some(FinishReason::Stop));
+});
+
+teenytiny_test!(async fn test_max_tokens_bounds_output() {
+    for max_tokens in [5u16, 50, 500] {
+        let request = lorem_request().max_tokens(max_tokens).build().unwrap();
+
+        let (content, finish_reason, completion_tokens) = complete(request).await;
+
+        assert!(
+            completion_tokens <= u32::from(max_tokens),
+            "{} completion tokens exceeds max_tokens {}", completion_tokens, max_tokens
+        );
+        assert!(
+            completion_tokens >= u32::from(max_tokens) - 1,
+            "Expected the budget to be filled, got {} of {} tokens", completion_tokens, max_tokens
+        );
+        assert!(content.len() <= usize::from(max_tokens) * 4);
+        assert_eq!(finish_reason, Some(FinishReason::Length));
+    }
+});
+
+teenytiny_test!(async fn test_long_output() {
+    let request = lorem_request().max_tokens(4000u16).build().unwrap();
+
+    let (content, _, _) = complete(request).await;
+
+    assert!(content.len() > 15000, "Expected long output, got {} characters", content.len());
+    assert!(content.split("\n\n").count() > 10, "Expected many paragraphs");
+});
+
+teenytiny_test!(async fn test_seed_is_deterministic() {
+    let seeded = |seed: i64| lorem_request().seed(seed).build().unwrap();
+
+    let (first, _, _) = complete(seeded(42)).await;
+    let (second, _, _) = complete(seeded(42)).await;
+    let (other, _, _) = complete(seeded(43)).await;
+
+    assert_eq!(first, second, "Same seed should give the same text");
+    assert_ne!(first, other, "Different seeds should give different text");
+});
+
+teenytiny_test!(async fn test_stop_sequence() {
+    let request = lorem_request().stop("dolor").build().unwrap();
+
+    let (content, finish_reason, _) = complete(request).await;
+
+    assert_eq!(content, "Lorem ipsum");
+    assert_eq!(finish_reason, Some(FinishReason::Stop));
+});
+
+teenytiny_test!(async fn test_stop_sequence_after_budget() {
+    // The budget runs out before "elit" is reached
+    let request = lorem_request().max_tokens(3u16).stop("elit").build().unwrap();
+
+    let (content, finish_reason, _) = complete(request).await;
+
+    assert_eq!(content, "Lorem ipsum");
+    assert_eq!(finish_reason, Some(FinishReason::Length));
+});
+
+teenytiny_test!(async fn test_streaming_matches_non_streaming() {
+    let (expected, _, _) = complete(lorem_request().seed(7).max_tokens(200u16).build().unwrap()).await;
+
+    let request = lorem_request().seed(7).max_tokens(200u16).stream(true).build().unwrap();
+    let mut stream = setup_client().chat().create_stream(request).await.unwrap();
+
+    let mut content = String::new();
+    let mut chunk_count = 0;
+    let mut finish_reason = None;
+    while let Some(result) = stream.next().await {
+        let chunk = result.unwrap();
+        if let Some(choice) = chunk.choices.first() {
+            if let Some(delta) = &choice.delta.content {
+                content.push_str(delta);
+                chunk_count += 1;
+            }
+            finish_reason = finish_reason.or(choice.finish_reason);
+        }
+    }
+
+    assert_eq!(content.trim(), expected);
+    assert!(chunk_count > 50, "Expected word-by-word streaming, got {} chunks", chunk_count);
+    assert_eq!(finish_reason, Some(FinishReason::Length));
+});
+
+teenytiny_test!(async fn test_stop_sequence_when_streaming() {
+    let request = lorem_request().stop("amet").stream(true).build().unwrap();
+    let mut stream = setup_client().chat().create_stream(request).await.unwrap();
+
+    let mut content = String::new();
+    while let Some(result) = stream.next().await {
+        if let Some(delta) = result.unwrap().choices.first().and_then(|c| c.delta.content.clone()) {
+            content.push_str(&delta);
+        }
+    }
+
+    assert_eq!(content.trim(), "Lorem ipsum dolor sit");
+});
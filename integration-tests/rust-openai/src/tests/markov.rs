@@ -0,0 +1,78 @@
+// The markov model writes text from a corpus the server is given with
+// --corpus, picking each word by the two before it. The server the rest of
+// the suite runs against may not have one, so these tests start a server of
+// their own with the example corpus, and are skipped when it can't be started.
+
+use std::collections::HashSet;
+
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+
+use super::own_server;
+use crate::server::TestServer;
+
+const CORPUS: &str = "corpus/example.txt";
+
+async fn chat(server: &TestServer, body: Value) -> reqwest::Response {
+    let mut request = json!({"model": "markov", "messages": [{"role": "user", "content": "Tell me something"}]});
+    request.as_object_mut().unwrap().extend(body.as_object().unwrap().clone());
+    let response = crate::http_client()
+        .post(format!("{}/v1/chat/completions", server.url()))
+        .bearer_auth(server.api_key())
+        .json(&request)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    response
+}
+
+async fn reply(server: &TestServer, body: Value) -> Value {
+    chat(server, body).await.json().await.unwrap()
+}
+
+fn content(body: &Value) -> &str {
+    body["choices"][0]["message"]["content"].as_str().unwrap()
+}
+
+teenytiny_test!(async fn test_markov_is_deterministic_under_a_seed() {
+    let Some(server) = own_server(&["--corpus", CORPUS]).await else { return };
+
+    let first = reply(&server, json!({"seed": 42})).await;
+    let again = reply(&server, json!({"seed": 42})).await;
+    assert_eq!(content(&first), content(&again));
+
+    let mut texts = HashSet::new();
+    for seed in 0..5 {
+        texts.insert(content(&reply(&server, json!({"seed": seed, "max_tokens": 40})).await).to_string());
+    }
+    assert!(texts.len() > 1, "Every seed wrote the same text: {:?}", texts);
+
+    // Streaming writes the same words as a single response
+    let streamed = chat(&server, json!({"seed": 42, "stream": true})).await.text().await.unwrap();
+    let text: String = streamed.lines()
+        .filter_map(|line| line.strip_prefix("data: "))
+        .filter_map(|data| serde_json::from_str::<Value>(data).ok())
+        .filter_map(|chunk| chunk["choices"][0]["delta"]["content"].as_str().map(String::from))
+        .collect();
+    assert_eq!(text, content(&first));
+});
+
+teenytiny_test!(async fn test_markov_writes_from_its_corpus_up_to_max_tokens() {
+    let Some(server) = own_server(&["--corpus", CORPUS]).await else { return };
+
+    let body = reply(&server, json!({"seed": 7, "max_tokens": 60})).await;
+    assert_eq!(body["usage"]["completion_tokens"], 60);
+    assert_eq!(body["choices"][0]["finish_reason"], "length");
+
+    // Every word comes from the corpus, but the last, which max_tokens may
+    // have cut short
+    let corpus = std::fs::read_to_string(std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../../service").join(CORPUS))
+        .unwrap();
+    let words: HashSet<&str> = corpus.split_whitespace().collect();
+    let text = content(&body);
+    let (whole, _cut) = text.rsplit_once(' ').unwrap();
+    for word in whole.split_whitespace() {
+        assert!(words.contains(word), "{:?} isn't in the corpus: {}", word, text);
+    }
+});
@@ -0,0 +1,117 @@
+// The memory model keeps conversations server-side, keyed by the request's
+// user or its x-teenytiny-conversation header, so a client sends only its new
+// message each turn. Its replies number the turn and repeat what the user said
+// before, which shows what the server remembered. Each test mints its own key,
+// since conversations are kept per key.
+
+use async_openai::config::OpenAIConfig;
+use async_openai::types::CreateChatCompletionRequestArgs;
+use async_openai::Client;
+use futures::StreamExt;
+use reqwest::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use crate::base_url;
+use super::{new_api_key, user_message};
+
+const CONVERSATION_HEADER: &str = "x-teenytiny-conversation";
+
+fn client(api_key: &str) -> Client<OpenAIConfig> {
+    let config = OpenAIConfig::new().with_api_key(api_key).with_api_base(format!("{}/v1", base_url()));
+    Client::with_config(config).with_http_client(crate::http_client())
+}
+
+// One turn of the user's conversation, sending only the new message
+async fn say_as_user(api_key: &str, user: &str, message: &str) -> String {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("memory")
+        .user(user)
+        .messages([user_message(message)])
+        .build().unwrap();
+    let response = client(api_key).chat().create(request).await.unwrap();
+    response.choices[0].message.content.clone().unwrap_or_default()
+}
+
+// One turn of the conversation the header names
+async fn say_in(api_key: &str, conversation: &str, message: &str) -> String {
+    let response = crate::http_client()
+        .post(format!("{}/v1/chat/completions", base_url()))
+        .bearer_auth(api_key)
+        .header(CONVERSATION_HEADER, conversation)
+        .json(&json!({"model": "memory", "messages": [{"role": "user", "content": message}]}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value = response.json().await.unwrap();
+    body["choices"][0]["message"]["content"].as_str().unwrap().to_string()
+}
+
+async fn conversations(method: Method, api_key: &str, path: &str) -> Value {
+    let response = crate::http_client()
+        .request(method, format!("{}/admin/conversations{}", base_url(), path))
+        .bearer_auth(api_key)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    response.json().await.unwrap()
+}
+
+teenytiny_test!(async fn test_memory_continues_the_users_conversation() {
+    let key = new_api_key().await;
+
+    let first = say_as_user(&key, "ada", "my name is Ada").await;
+    assert_eq!(first, r#"Turn 1. You said "my name is Ada". I don't remember anything before that."#);
+    say_as_user(&key, "ada", "I like tea").await;
+    let third = say_as_user(&key, "ada", "what do you know?").await;
+    assert_eq!(third, r#"Turn 3. You said "what do you know?". Before that you said "my name is Ada", "I like tea"."#);
+
+    // A streamed turn is remembered once the stream ends
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("memory")
+        .user("ada")
+        .messages([user_message("streamed")])
+        .stream(true)
+        .build().unwrap();
+    let mut stream = client(&key).chat().create_stream(request).await.unwrap();
+    let mut streamed = String::new();
+    while let Some(chunk) = stream.next().await {
+        if let Some(content) = chunk.unwrap().choices.first().and_then(|c| c.delta.content.clone()) {
+            streamed.push_str(&content);
+        }
+    }
+    assert!(streamed.starts_with("Turn 4."), "Unexpected streamed reply: {}", streamed);
+    assert!(say_as_user(&key, "ada", "and now?").await.contains(r#""I like tea", "what do you know?", "streamed""#));
+});
+
+teenytiny_test!(async fn test_conversations_are_kept_apart() {
+    let key = new_api_key().await;
+    say_in(&key, "trip", "book a train").await;
+    say_in(&key, "dinner", "book a table").await;
+
+    let trip = say_in(&key, "trip", "and a hotel").await;
+    assert_eq!(trip, r#"Turn 2. You said "and a hotel". Before that you said "book a train"."#);
+
+    // The header wins over user, and another key's conversation of the same
+    // name is its own
+    assert!(say_as_user(&key, "trip", "hello").await.starts_with("Turn 1."));
+    assert!(say_in(&new_api_key().await, "trip", "hello").await.starts_with("Turn 1."));
+});
+
+teenytiny_test!(async fn test_conversations_can_be_inspected_and_forgotten() {
+    let key = new_api_key().await;
+    say_in(&key, "inspected", "remember me").await;
+
+    let listed = conversations(Method::GET, &key, "").await;
+    let data = listed["data"].as_array().unwrap();
+    assert_eq!(data.len(), 1, "Expected only this key's conversation: {}", listed);
+    assert_eq!((&data[0]["id"], &data[0]["turns"]), (&json!("inspected"), &json!(1)));
+    let roles: Vec<&Value> = data[0]["messages"].as_array().unwrap().iter().map(|m| &m["role"]).collect();
+    assert_eq!(roles, [&json!("user"), &json!("assistant")]);
+    assert!(data[0]["expires_at"].is_string());
+
+    let deleted = conversations(Method::DELETE, &key, "/inspected").await;
+    assert_eq!(deleted["deleted"], true);
+    assert!(say_in(&key, "inspected", "remember me?").await.starts_with("Turn 1."));
+});
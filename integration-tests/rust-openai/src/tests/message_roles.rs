@@ -0,0 +1,118 @@
+// The developer role and the optional name on messages. Newer SDKs send
+// system prompts as developer messages, and multi-agent frameworks name the
+// participants sharing a role, so neither may be rejected.
+
+use async_openai::types::{
+    ChatCompletionRequestDeveloperMessageArgs, ChatCompletionRequestMessage,
+    ChatCompletionRequestSystemMessageArgs, ChatCompletionRequestUserMessageArgs,
+    CreateChatCompletionRequestArgs, CreateChatCompletionResponse,
+};
+use futures::StreamExt;
+use reqwest::StatusCode;
+use serde_json::json;
+
+use crate::setup_client;
+use super::{post_chat_completion, system_message, user_message};
+
+fn developer_message(content: &str) -> ChatCompletionRequestMessage {
+    ChatCompletionRequestDeveloperMessageArgs::default()
+        .content(content)
+        .build()
+        .unwrap()
+        .into()
+}
+
+fn named_user_message(name: &str, content: &str) -> ChatCompletionRequestMessage {
+    ChatCompletionRequestUserMessageArgs::default()
+        .name(name)
+        .content(content)
+        .build()
+        .unwrap()
+        .into()
+}
+
+async fn chat(messages: Vec<ChatCompletionRequestMessage>) -> CreateChatCompletionResponse {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("echo")
+        .messages(messages)
+        .build().unwrap();
+    setup_client().chat().create(request).await.unwrap()
+}
+
+teenytiny_test!(async fn test_developer_message_is_accepted() {
+    let response = chat(vec![developer_message("You are a helpful assistant."), user_message("Test message")]).await;
+
+    // Like a system prompt, the developer message isn't echoed
+    assert_eq!(response.choices[0].message.content.as_deref(), Some("Test message"));
+});
+
+teenytiny_test!(async fn test_developer_only_returns_default() {
+    let response = chat(vec![developer_message("You are a helpful assistant.")]).await;
+
+    let content = response.choices[0].message.content.as_deref().unwrap();
+    assert!(content.contains("Echo model"), "Expected the default greeting, got '{}'", content);
+});
+
+teenytiny_test!(async fn test_developer_message_counts_like_a_system_message() {
+    let developer = chat(vec![developer_message("Be brief."), user_message("Hello")]).await;
+    let system = chat(vec![system_message("Be brief."), user_message("Hello")]).await;
+
+    assert_eq!(developer.usage.unwrap().prompt_tokens, system.usage.unwrap().prompt_tokens);
+});
+
+teenytiny_test!(async fn test_streaming_with_developer_message() {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("echo")
+        .messages([developer_message("You are a helpful assistant."), user_message("Stream this")])
+        .stream(true)
+        .build().unwrap();
+
+    let mut stream = setup_client().chat().create_stream(request).await.unwrap();
+    let mut content = String::new();
+    while let Some(result) = stream.next().await {
+        let response = result.unwrap();
+        if let Some(choice) = response.choices.first() {
+            content.push_str(choice.delta.content.as_deref().unwrap_or(""));
+        }
+    }
+
+    assert_eq!(content, "Stream this");
+});
+
+teenytiny_test!(async fn test_named_messages_are_accepted() {
+    let system = ChatCompletionRequestSystemMessageArgs::default()
+        .name("style_guide")
+        .content("Be brief.")
+        .build().unwrap();
+
+    let response = chat(vec![
+        system.into(),
+        named_user_message("alice", "Hello from Alice"),
+        named_user_message("bob", "Hello from Bob"),
+    ]).await;
+
+    assert_eq!(response.choices[0].message.content.as_deref(), Some("Hello from Bob"));
+});
+
+teenytiny_test!(async fn test_names_are_counted_in_the_prompt() {
+    let named = chat(vec![named_user_message("alice", "Hello")]).await;
+    let unnamed = chat(vec![user_message("Hello")]).await;
+
+    assert!(
+        named.usage.unwrap().prompt_tokens > unnamed.usage.unwrap().prompt_tokens,
+        "A message's name should count towards prompt tokens"
+    );
+});
+
+teenytiny_test!(async fn test_invalid_roles_and_names_are_rejected() {
+    for message in [
+        json!({"role": "narrator", "content": "Once upon a time"}),
+        json!({"role": "user", "name": 42, "content": "Hello"}),
+    ] {
+        let (status, body) = post_chat_completion(json!({"model": "echo", "messages": [message]})).await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", message);
+        assert_eq!(body["error"]["type"], "invalid_request_error");
+        assert_eq!(body["error"]["param"], "messages");
+    }
+});
@@ -0,0 +1,91 @@
+// Negative tests built from middleware layers: each sends an ordinary request
+// and breaks it on the way out, then checks how the server copes.
+
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+
+use crate::middleware::{CorruptBody, DropHeader, Logger, MiddlewareClient, SetHeader, Timings};
+
+fn chat_body(content: &str) -> Value {
+    json!({"model": "echo", "messages": [{"role": "user", "content": content}]})
+}
+
+async fn chat(client: &MiddlewareClient, content: &str) -> (StatusCode, Value) {
+    let response = client.send(client.post("/v1/chat/completions").json(&chat_body(content))).await.unwrap();
+    (response.status(), response.json().await.unwrap())
+}
+
+teenytiny_test!(async fn test_missing_content_type() {
+    let client = MiddlewareClient::new().with(Logger).with(DropHeader("content-type"));
+
+    let (status, body) = chat(&client, "No content type").await;
+
+    // Like OpenAI, the body is read as JSON whatever its content type
+    assert_eq!(status, StatusCode::OK, "Unexpected response: {}", body);
+    assert_eq!(body["choices"][0]["message"]["content"], "No content type");
+});
+
+teenytiny_test!(async fn test_wrong_content_type() {
+    let client = MiddlewareClient::new().with(SetHeader("content-type", "text/plain"));
+
+    let (status, body) = chat(&client, "Plain text").await;
+
+    assert_eq!(status, StatusCode::OK, "Unexpected response: {}", body);
+});
+
+teenytiny_test!(async fn test_missing_authorization() {
+    let client = MiddlewareClient::new().with(DropHeader("authorization"));
+
+    let (status, body) = chat(&client, "Who am I?").await;
+
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["error"]["type"], "authentication_error");
+});
+
+teenytiny_test!(async fn test_truncated_body() {
+    let client = MiddlewareClient::new().with(CorruptBody::Truncate(20));
+
+    let (status, body) = chat(&client, "Cut off partway").await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["type"], "invalid_request_error");
+});
+
+teenytiny_test!(async fn test_replaced_body() {
+    let client = MiddlewareClient::new().with(CorruptBody::Replace(b"[1, 2, 3]"));
+
+    let (status, body) = chat(&client, "Replaced").await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["type"], "invalid_request_error");
+});
+
+teenytiny_test!(async fn test_timings_record_each_exchange() {
+    let timings = Timings::new();
+    let client = MiddlewareClient::new().with_shared(timings.clone());
+
+    chat(&client, "Quick").await;
+    chat(&client, "Slow !delay:300").await;
+
+    let exchanges = timings.exchanges();
+    assert_eq!(exchanges.len(), 2);
+    assert!(exchanges.iter().all(|exchange| exchange.path == "/v1/chat/completions"));
+    assert!(exchanges.iter().all(|exchange| exchange.status == StatusCode::OK));
+    assert!(exchanges[1].elapsed >= Duration::from_millis(300), "Slow request took only {:?}", exchanges[1].elapsed);
+});
+
+teenytiny_test!(async fn test_layers_apply_in_order() {
+    // The later layer sees the request the earlier one left, so the header ends up dropped
+    let timings = Timings::new();
+    let client = MiddlewareClient::new()
+        .with(SetHeader("authorization", "Bearer not-a-key"))
+        .with(DropHeader("authorization"))
+        .with_shared(timings.clone());
+
+    let (status, _) = chat(&client, "Layered").await;
+
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(timings.exchanges()[0].status, StatusCode::UNAUTHORIZED);
+});
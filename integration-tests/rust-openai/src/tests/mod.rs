@@ -1 +0,0 @@
-// This file is no longer needed since tests are now individual #[test] functions
\ No newline at end of file
@@ -0,0 +1,121 @@
+// Per-model defaults set with PUT /admin/model-defaults: a max_tokens cap, a
+// forced temperature and a system prompt. Settings are server-wide, so the
+// test configures a slow variant no other test uses, checks requests to it
+// behave as clamped, then puts the previous settings back.
+
+use reqwest::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use crate::raw::{self, assert_error_envelope, RawResponse};
+use super::{admin, skip};
+
+const MODEL: &str = "slow:3";
+const SYSTEM_PROMPT: &str = "Answer in as few words as you can";
+
+async fn chat(body: Value) -> RawResponse {
+    let response = raw::send(raw::request(Method::POST, "/v1/chat/completions").json(&body)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    response
+}
+
+fn applied(response: &RawResponse) -> Option<&str> {
+    response.header("x-teenytiny-model-defaults")
+}
+
+async fn check_defaults_apply() {
+    let effective = admin(Method::GET, &format!("/model-defaults/{}", MODEL), None).await.1;
+    assert_eq!(effective, json!({"model": MODEL, "max_tokens": 3, "temperature": 0.5, "system_prompt": SYSTEM_PROMPT}));
+
+    // A higher max_tokens is clamped, so the reply stops at the cap
+    let clamped = chat(json!({
+        "model": MODEL, "max_tokens": 100, "temperature": 1.5,
+        "messages": [{"role": "user", "content": "one two three four five six"}]
+    })).await;
+    assert_eq!(applied(&clamped), Some("max_tokens, temperature, system_prompt"));
+    let body = clamped.json();
+    assert_eq!(body["choices"][0]["message"]["content"], "one two three");
+    assert_eq!(body["choices"][0]["finish_reason"], "length");
+    assert_eq!(body["usage"]["completion_tokens"], 3);
+
+    // /debug/parse shows the clamped request itself
+    let parsed = raw::send(raw::request(Method::POST, "/debug/parse").json(&json!({
+        "model": MODEL, "max_tokens": 100, "temperature": 1.5,
+        "messages": [{"role": "user", "content": "one two three four five six"}]
+    }))).await.json();
+    assert_eq!(parsed["applied_defaults"], json!(["max_tokens", "temperature", "system_prompt"]));
+    assert_eq!((&parsed["request"]["max_tokens"], &parsed["request"]["temperature"]), (&json!(3), &json!(0.5)));
+    assert_eq!(parsed["request"]["messages"][0], json!({"role": "system", "content": SYSTEM_PROMPT}));
+
+    // Lower limits, the forced temperature and the request's own system prompt are left alone
+    let kept = chat(json!({
+        "model": MODEL, "max_tokens": 2, "temperature": 0.5,
+        "messages": [{"role": "system", "content": SYSTEM_PROMPT}, {"role": "user", "content": "one two three four five six"}]
+    })).await;
+    assert_eq!(applied(&kept), None);
+    assert_eq!(kept.json()["choices"][0]["message"]["content"], "one two");
+
+    // The default system prompt is counted as if the client had sent it
+    let without_system = chat(json!({
+        "model": MODEL, "messages": [{"role": "user", "content": "one two"}]
+    })).await;
+    let with_system = chat(json!({
+        "model": MODEL, "messages": [{"role": "system", "content": SYSTEM_PROMPT}, {"role": "user", "content": "one two"}]
+    })).await;
+    assert_eq!(applied(&without_system), Some("max_tokens, system_prompt"));
+    assert_eq!(without_system.json()["usage"]["prompt_tokens"], with_system.json()["usage"]["prompt_tokens"]);
+
+    // Streams are clamped too
+    let streamed = chat(json!({
+        "model": MODEL, "stream": true, "messages": [{"role": "user", "content": "one two three four five six"}]
+    })).await;
+    assert_eq!(applied(&streamed), Some("max_tokens, system_prompt"));
+    assert!(streamed.text().contains(r#""finish_reason":"length""#), "Unexpected stream: {}", streamed.text());
+}
+
+teenytiny_test!(async fn test_model_defaults_clamp_requests() {
+    let (status, body) = admin(Method::GET, "/model-defaults", None).await;
+    if status == StatusCode::FORBIDDEN {
+        skip("TEENYTINY_API_KEY is not the server's key");
+        return;
+    }
+    let previous = body;
+
+    let mut defaults = previous.clone();
+    defaults[MODEL] = json!({"max_tokens": 3, "temperature": 0.5, "system_prompt": SYSTEM_PROMPT});
+    let (status, body) = admin(Method::PUT, "/model-defaults", Some(defaults)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    // Put the previous settings back before any assertion can fail
+    let outcome = tokio::spawn(check_defaults_apply()).await;
+    admin(Method::PUT, "/model-defaults", Some(previous)).await;
+    if let Err(error) = outcome {
+        std::panic::resume_unwind(error.into_panic());
+    }
+});
+
+teenytiny_test!(async fn test_invalid_model_defaults_are_rejected() {
+    for defaults in [
+        json!([]),
+        json!({(MODEL): {"max_tokens": 0}}),
+        json!({(MODEL): {"temperature": 5}}),
+        json!({(MODEL): {"top_p": 1}}),
+    ] {
+        let (status, body) = admin(Method::PUT, "/model-defaults", Some(defaults.clone())).await;
+        if status == StatusCode::FORBIDDEN {
+            skip("TEENYTINY_API_KEY is not the server's key");
+            return;
+        }
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+        assert_eq!(assert_error_envelope(&body).kind, "invalid_request_error");
+    }
+});
+
+teenytiny_test!(async fn test_effective_settings_of_an_unknown_model() {
+    let (status, body) = admin(Method::GET, "/model-defaults/no-such-model", None).await;
+    if status == StatusCode::FORBIDDEN {
+        skip("TEENYTINY_API_KEY is not the server's key");
+        return;
+    }
+    assert_eq!(status, StatusCode::NOT_FOUND, "{}", body);
+    assert_eq!(assert_error_envelope(&body).kind, "invalid_request_error");
+});
@@ -0,0 +1,95 @@
+use async_openai::types::CreateChatCompletionRequestArgs;
+use futures::StreamExt;
+use reqwest::StatusCode;
+use serde_json::json;
+
+use super::{post_chat_completion, user_message};
+
+teenytiny_test!(async fn test_unknown_model_is_404() {
+    let (status, body) = post_chat_completion(json!({
+        "model": "does-not-exist",
+        "messages": [{"role": "user", "content": "Hello"}],
+    })).await;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["error"]["type"], "invalid_request_error");
+    assert_eq!(body["error"]["code"], "model_not_found");
+
+    let message = body["error"]["message"].as_str().expect("No error message");
+    assert!(message.contains("does-not-exist"), "Expected model name in message: {}", message);
+});
+
+teenytiny_test!(async fn test_unknown_model_is_404_when_streaming() {
+    let (status, body) = post_chat_completion(json!({
+        "model": "does-not-exist",
+        "messages": [{"role": "user", "content": "Hello"}],
+        "stream": true,
+    })).await;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["error"]["code"], "model_not_found");
+});
+
+teenytiny_test!(async fn test_unknown_model_errors_through_client(client) {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("does-not-exist")
+        .messages([user_message("Hello")])
+        .build().unwrap();
+
+    let result = client.chat().create(request).await;
+
+    let error_msg = format!("{}", result.expect_err("Expected an error for an unknown model"));
+    assert!(
+        error_msg.contains("does-not-exist"),
+        "Expected model name in error, got: {}", error_msg
+    );
+});
+
+teenytiny_test!(async fn test_model_names_are_case_sensitive() {
+    for model in ["Echo", "ECHO", "Eliza", "GPT-3.5-TURBO"] {
+        let (status, body) = post_chat_completion(json!({
+            "model": model,
+            "messages": [{"role": "user", "content": "Hello"}],
+        })).await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND, "Expected {} to be unknown", model);
+        assert_eq!(body["error"]["code"], "model_not_found");
+    }
+});
+
+teenytiny_test!(async fn test_alias_resolves_and_reports_alias(client) {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("gpt-3.5-turbo")
+        .messages([user_message("Alias test")])
+        .build().unwrap();
+
+    let response = client.chat().create(request).await.unwrap();
+
+    let content = response.choices[0].message.content.as_ref()
+        .expect("No content in response");
+
+    // gpt-3.5-turbo is routed to echo
+    assert_eq!(content, "Alias test");
+    assert_eq!(response.model, "gpt-3.5-turbo");
+});
+
+teenytiny_test!(async fn test_alias_reported_in_stream_chunks(client) {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("gpt-3.5-turbo")
+        .messages([user_message("Streaming alias test")])
+        .stream(true)
+        .build().unwrap();
+
+    let mut stream = client.chat().create_stream(request).await.unwrap();
+
+    let mut content = String::new();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.unwrap();
+        assert_eq!(chunk.model, "gpt-3.5-turbo");
+        if let Some(delta) = chunk.choices.first().and_then(|c| c.delta.content.as_ref()) {
+            content.push_str(delta);
+        }
+    }
+
+    assert_eq!(content, "Streaming alias test");
+});
@@ -0,0 +1,126 @@
+use async_openai::types::{CreateModerationRequestArgs, ModerationInput};
+use reqwest::StatusCode;
+use serde_json::json;
+
+use super::post_json;
+
+const CATEGORIES: [&str; 13] = [
+    "harassment",
+    "harassment/threatening",
+    "hate",
+    "hate/threatening",
+    "illicit",
+    "illicit/violent",
+    "self-harm",
+    "self-harm/intent",
+    "self-harm/instructions",
+    "sexual",
+    "sexual/minors",
+    "violence",
+    "violence/graphic",
+];
+
+teenytiny_test!(async fn test_clean_input_is_not_flagged(client) {
+    let request = CreateModerationRequestArgs::default()
+        .input("What a lovely day for a picnic")
+        .build().unwrap();
+
+    let response = client.moderations().create(request).await.unwrap();
+
+    assert!(response.id.starts_with("modr-"), "Unexpected id: {}", response.id);
+    assert_eq!(response.results.len(), 1);
+    assert!(!response.results[0].flagged);
+});
+
+teenytiny_test!(async fn test_keyword_input_is_flagged(client) {
+    let request = CreateModerationRequestArgs::default()
+        .input("I am going to kill this bug")
+        .build().unwrap();
+
+    let response = client.moderations().create(request).await.unwrap();
+
+    let result = &response.results[0];
+    assert!(result.flagged);
+    assert!(result.categories.violence);
+    assert!(!result.categories.sexual);
+    assert!(result.category_scores.violence > result.category_scores.sexual);
+});
+
+teenytiny_test!(async fn test_array_input(client) {
+    let request = CreateModerationRequestArgs::default()
+        .input(ModerationInput::StringArray(vec![
+            "Hello there".to_string(),
+            "You absolute idiot".to_string(),
+            "Goodbye".to_string(),
+        ]))
+        .build().unwrap();
+
+    let response = client.moderations().create(request).await.unwrap();
+
+    let flagged: Vec<bool> = response.results.iter().map(|r| r.flagged).collect();
+    assert_eq!(flagged, [false, true, false]);
+    assert!(response.results[1].categories.harassment);
+});
+
+teenytiny_test!(async fn test_flag_everything_model(client) {
+    let request = CreateModerationRequestArgs::default()
+        .input("Perfectly harmless text")
+        .model("flag-everything")
+        .build().unwrap();
+
+    let response = client.moderations().create(request).await.unwrap();
+
+    assert_eq!(response.model, "flag-everything");
+    assert!(response.results[0].flagged);
+    assert!(response.results[0].categories.hate);
+    assert!(response.results[0].categories.self_harm_instructions);
+});
+
+teenytiny_test!(async fn test_response_structure_has_every_category() {
+    let (status, body) = post_json("/v1/moderations", json!({"input": "Structure test"})).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["model"], "omni-moderation-latest");
+
+    let result = &body["results"][0];
+    for category in CATEGORIES {
+        assert!(result["categories"][category].is_boolean(), "Missing category {}", category);
+        assert!(result["category_scores"][category].is_number(), "Missing score for {}", category);
+        assert!(
+            result["category_applied_input_types"][category].is_array(),
+            "Missing applied input types for {}", category
+        );
+    }
+});
+
+teenytiny_test!(async fn test_multimodal_input() {
+    let (status, body) = post_json("/v1/moderations", json!({
+        "input": [
+            {"type": "text", "text": "Look at this attack"},
+            {"type": "image_url", "image_url": {"url": "https://example.com/picture.png"}}
+        ]
+    })).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["results"].as_array().unwrap().len(), 1);
+    assert_eq!(body["results"][0]["categories"]["violence"], true);
+    assert_eq!(body["results"][0]["category_applied_input_types"]["violence"], json!(["text", "image"]));
+    assert_eq!(body["results"][0]["category_applied_input_types"]["hate"], json!(["text"]));
+});
+
+teenytiny_test!(async fn test_unknown_moderation_model() {
+    let (status, body) = post_json("/v1/moderations", json!({
+        "input": "Hello",
+        "model": "not-a-moderation-model"
+    })).await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["param"], "model");
+});
+
+teenytiny_test!(async fn test_invalid_input_type() {
+    let (status, body) = post_json("/v1/moderations", json!({"input": 42})).await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["param"], "input");
+});
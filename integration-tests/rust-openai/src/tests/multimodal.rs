@@ -0,0 +1,190 @@
+use async_openai::types::{
+    ChatCompletionRequestMessage, ChatCompletionRequestMessageContentPartImageArgs,
+    ChatCompletionRequestMessageContentPartTextArgs, ChatCompletionRequestUserMessageArgs,
+    ChatCompletionRequestUserMessageContentPart, CreateChatCompletionRequestArgs, ImageDetail,
+    ImageUrlArgs,
+};
+use futures::StreamExt;
+use serde_json::json;
+
+use super::post_chat_completion;
+
+// A 1x1 transparent PNG
+const TINY_PNG_DATA_URL: &str = "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAQAAAC1HAwCAAAAC0lEQVR42mNkYAAAAAYAAjCB0C8AAAAASUVORK5CYII=";
+
+fn text_part(text: &str) -> ChatCompletionRequestUserMessageContentPart {
+    ChatCompletionRequestMessageContentPartTextArgs::default()
+        .text(text)
+        .build()
+        .unwrap()
+        .into()
+}
+
+fn image_part(url: &str) -> ChatCompletionRequestUserMessageContentPart {
+    ChatCompletionRequestMessageContentPartImageArgs::default()
+        .image_url(ImageUrlArgs::default().url(url).detail(ImageDetail::Low).build().unwrap())
+        .build()
+        .unwrap()
+        .into()
+}
+
+fn multimodal_message(parts: Vec<ChatCompletionRequestUserMessageContentPart>) -> ChatCompletionRequestMessage {
+    ChatCompletionRequestUserMessageArgs::default()
+        .content(parts)
+        .build()
+        .unwrap()
+        .into()
+}
+
+teenytiny_test!(async fn test_text_and_https_image_parts(client) {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("echo")
+        .messages([multimodal_message(vec![
+            text_part("What is in this image?"),
+            image_part("https://example.com/cat.png"),
+        ])])
+        .build().unwrap();
+
+    let response = client.chat().create(request).await.unwrap();
+
+    let content = response.choices[0].message.content.as_ref()
+        .expect("No content in response");
+
+    // Echo model should return only the text, skipping the image
+    assert_eq!(content, "What is in this image?");
+});
+
+teenytiny_test!(async fn test_data_url_image_part(client) {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("echo")
+        .messages([multimodal_message(vec![
+            image_part(TINY_PNG_DATA_URL),
+            text_part("Describe the pixel"),
+        ])])
+        .build().unwrap();
+
+    let response = client.chat().create(request).await.unwrap();
+
+    let content = response.choices[0].message.content.as_ref()
+        .expect("No content in response");
+
+    assert_eq!(content, "Describe the pixel");
+});
+
+teenytiny_test!(async fn test_multiple_text_parts_are_joined(client) {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("echo")
+        .messages([multimodal_message(vec![
+            text_part("First part"),
+            image_part("https://example.com/a.png"),
+            image_part(TINY_PNG_DATA_URL),
+            text_part("Second part"),
+        ])])
+        .build().unwrap();
+
+    let response = client.chat().create(request).await.unwrap();
+
+    let content = response.choices[0].message.content.as_ref()
+        .expect("No content in response");
+
+    assert_eq!(content, "First part\nSecond part");
+});
+
+teenytiny_test!(async fn test_streaming_multimodal_message(client) {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("echo")
+        .messages([multimodal_message(vec![
+            text_part("Streamed caption"),
+            image_part("https://example.com/dog.jpg"),
+        ])])
+        .stream(true)
+        .build().unwrap();
+
+    let mut stream = client.chat().create_stream(request).await.unwrap();
+
+    let mut received_content = String::new();
+    while let Some(result) = stream.next().await {
+        let chunk = result.unwrap();
+
+        if let Some(choice) = chunk.choices.first() {
+            if let Some(content) = &choice.delta.content {
+                received_content.push_str(content);
+            }
+        }
+    }
+
+    assert_eq!(received_content, "Streamed caption");
+});
+
+teenytiny_test!(async fn test_image_part_missing_url() {
+    let (status, body) = post_chat_completion(json!({
+        "model": "echo",
+        "messages": [{
+            "role": "user",
+            "content": [
+                {"type": "text", "text": "Hello"},
+                {"type": "image_url", "image_url": {}}
+            ]
+        }]
+    })).await;
+
+    assert_eq!(status, 400);
+    assert_eq!(body["error"]["type"], "invalid_request_error");
+
+    let message = body["error"]["message"].as_str().unwrap();
+    assert!(
+        message.contains("messages[0].content[1].image_url.url"),
+        "Expected field path in error message, got: {}", message
+    );
+});
+
+teenytiny_test!(async fn test_image_part_with_unsupported_url_scheme() {
+    let (status, body) = post_chat_completion(json!({
+        "model": "echo",
+        "messages": [{
+            "role": "user",
+            "content": [{"type": "image_url", "image_url": {"url": "ftp://example.com/cat.png"}}]
+        }]
+    })).await;
+
+    assert_eq!(status, 400);
+
+    let message = body["error"]["message"].as_str().unwrap();
+    assert!(
+        message.contains("messages[0].content[0].image_url.url"),
+        "Expected field path in error message, got: {}", message
+    );
+});
+
+teenytiny_test!(async fn test_unknown_content_part_type() {
+    let (status, body) = post_chat_completion(json!({
+        "model": "echo",
+        "messages": [
+            {"role": "system", "content": "You are a helpful assistant."},
+            {"role": "user", "content": [{"type": "video_url", "video_url": {"url": "https://example.com/v.mp4"}}]}
+        ]
+    })).await;
+
+    assert_eq!(status, 400);
+
+    let message = body["error"]["message"].as_str().unwrap();
+    assert!(
+        message.contains("messages[1].content[0].type"),
+        "Expected field path in error message, got: {}", message
+    );
+});
+
+teenytiny_test!(async fn test_text_part_with_non_string_text() {
+    let (status, body) = post_chat_completion(json!({
+        "model": "echo",
+        "messages": [{"role": "user", "content": [{"type": "text", "text": 42}]}]
+    })).await;
+
+    assert_eq!(status, 400);
+
+    let message = body["error"]["message"].as_str().unwrap();
+    assert!(
+        message.contains("messages[0].content[0].text"),
+        "Expected field path in error message, got: {}", message
+    );
+});
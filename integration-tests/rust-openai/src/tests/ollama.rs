@@ -0,0 +1,144 @@
+// Ollama's native API, spoken by local-first tools that know nothing of
+// OpenAI's. Streams are newline-delimited JSON objects, the last of which has
+// "done": true, so these read the raw body rather than use a client library.
+
+use reqwest::{Response, StatusCode};
+use serde_json::{json, Value};
+
+use crate::{api_key, base_url, http_client};
+
+async fn post(path: &str, body: Value) -> Response {
+    http_client()
+        .post(format!("{}{}", base_url(), path))
+        .bearer_auth(api_key())
+        .json(&body)
+        .send()
+        .await
+        .unwrap()
+}
+
+// Every line of an NDJSON body, each of which must be a JSON object on its own
+async fn ndjson(response: Response) -> Vec<Value> {
+    assert_eq!(response.status(), StatusCode::OK);
+    let content_type = response.headers()["content-type"].to_str().unwrap().to_string();
+    assert!(content_type.starts_with("application/x-ndjson"), "Unexpected Content-Type {}", content_type);
+
+    let text = response.text().await.unwrap();
+    assert!(text.ends_with('\n'), "The last line should end with a newline: {:?}", text);
+    text.lines()
+        .map(|line| {
+            let value: Value = serde_json::from_str(line).unwrap_or_else(|e| panic!("Bad line {:?}: {}", line, e));
+            assert!(value.is_object(), "Each line should be an object: {}", line);
+            value
+        })
+        .collect()
+}
+
+// Checks that only the last chunk is done, and that it carries the usage counts
+fn assert_done_once(chunks: &[Value]) {
+    let (last, rest) = chunks.split_last().expect("No chunks");
+    for chunk in rest {
+        assert_eq!(chunk["done"], false, "Only the last chunk may be done: {}", chunk);
+        assert!(chunk["created_at"].is_string());
+    }
+    assert_eq!(last["done"], true);
+    assert_eq!(last["done_reason"], "stop");
+    for field in ["total_duration", "prompt_eval_count", "eval_count", "eval_duration"] {
+        assert!(last[field].is_u64(), "{} should be a count: {}", field, last);
+    }
+    assert!(last["eval_count"].as_u64().unwrap() > 0);
+}
+
+teenytiny_test!(async fn test_chat_streams_ndjson() {
+    let chunks = ndjson(
+        post("/api/chat", json!({"model": "echo", "messages": [{"role": "user", "content": "Hello from Ollama"}]})).await,
+    )
+    .await;
+
+    assert_done_once(&chunks);
+    let content: String = chunks.iter().map(|chunk| chunk["message"]["content"].as_str().unwrap()).collect();
+    assert_eq!(content, "Hello from Ollama");
+    for chunk in &chunks {
+        assert_eq!(chunk["model"], "echo");
+        assert_eq!(chunk["message"]["role"], "assistant");
+    }
+});
+
+teenytiny_test!(async fn test_chat_streams_chunk_by_chunk() {
+    let mut response = post(
+        "/api/chat",
+        json!({"model": "slow:50", "messages": [{"role": "user", "content": "one two three four"}]}),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Each word is written as its own line as the model produces it
+    let mut reads = 0;
+    let mut text = String::new();
+    while let Some(chunk) = response.chunk().await.unwrap() {
+        reads += 1;
+        text.push_str(std::str::from_utf8(&chunk).unwrap());
+    }
+    assert!(reads > 1, "The whole stream arrived in one read");
+    assert_eq!(text.lines().count(), 5, "Four words and the done line: {:?}", text);
+});
+
+teenytiny_test!(async fn test_chat_without_streaming() {
+    let response = post(
+        "/api/chat",
+        json!({"model": "echo", "stream": false, "messages": [{"role": "user", "content": "All at once"}]}),
+    )
+    .await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["done"], true);
+    assert_eq!(body["message"]["content"], "All at once");
+    assert_done_once(&[body]);
+});
+
+teenytiny_test!(async fn test_generate_streams_ndjson() {
+    let chunks = ndjson(post("/api/generate", json!({"model": "echo", "prompt": "Generate me"})).await).await;
+
+    assert_done_once(&chunks);
+    let response: String = chunks.iter().map(|chunk| chunk["response"].as_str().unwrap()).collect();
+    assert_eq!(response, "Generate me");
+});
+
+teenytiny_test!(async fn test_generate_without_streaming() {
+    let response = post("/api/generate", json!({"model": "echo", "prompt": "Generate me", "stream": false})).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["response"], "Generate me");
+    assert_eq!(body["done"], true);
+});
+
+teenytiny_test!(async fn test_num_predict_cuts_the_reply_short() {
+    let chunks = ndjson(
+        post(
+            "/api/generate",
+            json!({"model": "echo", "prompt": "one two three four five", "options": {"num_predict": 2}}),
+        )
+        .await,
+    )
+    .await;
+
+    assert_eq!(chunks.last().unwrap()["done_reason"], "length");
+});
+
+teenytiny_test!(async fn test_errors_are_a_bare_message() {
+    let response = post("/api/chat", json!({"model": "no-such-model", "messages": [{"role": "user", "content": "Hi"}]})).await;
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let body: Value = response.json().await.unwrap();
+    assert!(body["error"].as_str().unwrap().contains("no-such-model"), "Unexpected error {}", body);
+});
+
+teenytiny_test!(async fn test_missing_prompt_is_rejected() {
+    let response = post("/api/generate", json!({"model": "echo"})).await;
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body: Value = response.json().await.unwrap();
+    assert!(body["error"].is_string());
+});
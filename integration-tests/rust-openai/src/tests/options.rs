@@ -1,13 +1,9 @@
 use async_openai::types::CreateChatCompletionRequestArgs;
 use futures::StreamExt;
 
-use crate::setup_client;
 use super::user_message;
 
-#[tokio::test]
-async fn test_custom_temperature_parameter() {
-    let client = setup_client();
-
+teenytiny_test!(async fn test_custom_temperature_parameter(client) {
     let request = CreateChatCompletionRequestArgs::default()
         .model("echo")
         .messages([user_message("Temperature test")])
@@ -24,12 +20,9 @@ async fn test_custom_temperature_parameter() {
 
     // Echo model should accept temperature parameter without errors
     assert_eq!(response.model, "echo");
-}
-
-#[tokio::test]
-async fn test_custom_max_tokens_parameter() {
-    let client = setup_client();
+});
 
+teenytiny_test!(async fn test_custom_max_tokens_parameter(client) {
     let request = CreateChatCompletionRequestArgs::default()
         .model("echo")
         .messages([user_message("Max tokens test")])
@@ -48,12 +41,9 @@ async fn test_custom_max_tokens_parameter() {
     if let Some(usage) = response.usage {
         assert!(usage.total_tokens > 0);
     }
-}
-
-#[tokio::test]
-async fn test_multiple_parameters_combined() {
-    let client = setup_client();
+});
 
+teenytiny_test!(async fn test_multiple_parameters_combined(client) {
     let request = CreateChatCompletionRequestArgs::default()
         .model("echo")
         .messages([user_message("Multiple params test")])
@@ -72,12 +62,9 @@ async fn test_multiple_parameters_combined() {
 
     // Echo model should handle multiple parameters
     assert_eq!(response.model, "echo");
-}
-
-#[tokio::test]
-async fn test_streaming_with_parameters() {
-    let client = setup_client();
+});
 
+teenytiny_test!(async fn test_streaming_with_parameters(client) {
     let request = CreateChatCompletionRequestArgs::default()
         .model("echo")
         .messages([user_message("Streaming params test")])
@@ -100,12 +87,9 @@ async fn test_streaming_with_parameters() {
 
     let full_content = content_parts.join("");
     assert_eq!(full_content, "Streaming params test");
-}
-
-#[tokio::test]
-async fn test_user_parameter_in_request() {
-    let client = setup_client();
+});
 
+teenytiny_test!(async fn test_user_parameter_in_request(client) {
     let request = CreateChatCompletionRequestArgs::default()
         .model("echo")
         .messages([user_message("User param test")])
@@ -122,12 +106,9 @@ async fn test_user_parameter_in_request() {
 
     // Echo model should accept user parameter
     assert_eq!(response.model, "echo");
-}
-
-#[tokio::test]
-async fn test_frequency_and_presence_penalty_parameters() {
-    let client = setup_client();
+});
 
+teenytiny_test!(async fn test_frequency_and_presence_penalty_parameters(client) {
     let request = CreateChatCompletionRequestArgs::default()
         .model("echo")
         .messages([user_message("Penalty params test")])
@@ -145,4 +126,4 @@ async fn test_frequency_and_presence_penalty_parameters() {
 
     // Echo model should accept penalty parameters
     assert_eq!(response.model, "echo");
-}
\ No newline at end of file
+});
\ No newline at end of file
@@ -0,0 +1,87 @@
+// OpenAI-Organization and OpenAI-Project headers. Several SDKs always send
+// them, so the server must accept them, and the request log should show what
+// was sent. A server started with TEENYTINY_ORGANIZATIONS or
+// TEENYTINY_PROJECTS rejects others with OpenAI's 401; otherwise the
+// rejection tests skip.
+
+use async_openai::{config::OpenAIConfig, types::CreateChatCompletionRequestArgs, Client};
+use reqwest::{Method, StatusCode};
+use serde_json::json;
+
+use crate::base_url;
+use crate::raw::{self, assert_error, RawResponse};
+use super::{last_captured_request, new_api_key, skip, user_message};
+
+// No server would be configured to accept these
+const UNKNOWN_ORGANIZATION: &str = "org-teenytiny-unknown";
+const UNKNOWN_PROJECT: &str = "proj_teenytiny_unknown";
+
+fn client(key: &str, organization: &str, project: &str) -> Client<OpenAIConfig> {
+    let config = OpenAIConfig::new()
+        .with_api_key(key)
+        .with_api_base(format!("{}/v1", base_url()))
+        .with_org_id(organization)
+        .with_project_id(project);
+    Client::with_config(config).with_http_client(crate::http_client())
+}
+
+async fn chat(headers: &[(&str, &str)]) -> RawResponse {
+    let mut request = raw::request(Method::POST, "/v1/chat/completions")
+        .json(&json!({"model": "echo", "messages": [{"role": "user", "content": "Hi"}]}));
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    raw::send(request).await
+}
+
+teenytiny_test!(async fn test_sdk_organization_and_project_are_accepted_and_logged() {
+    let key = new_api_key().await;
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("echo")
+        .messages([user_message("Hello from an organization")])
+        .build().unwrap();
+
+    // Only a server that accepts any organization and project takes made-up ones
+    let probe = chat(&[("OpenAI-Organization", UNKNOWN_ORGANIZATION), ("OpenAI-Project", UNKNOWN_PROJECT)]).await;
+    if probe.status != StatusCode::OK {
+        skip("the server only accepts configured organizations and projects");
+        return;
+    }
+    let (organization, project) = ("org-teenytiny-586", "proj_teenytiny_586");
+    let response = client(&key, organization, project).chat().create(request).await.unwrap();
+    assert_eq!(response.choices[0].message.content.as_deref(), Some("Hello from an organization"));
+
+    let captured = last_captured_request(&key).await;
+    assert_eq!(captured["request_headers"]["openai-organization"], organization, "{}", captured);
+    assert_eq!(captured["request_headers"]["openai-project"], project, "{}", captured);
+});
+
+teenytiny_test!(async fn test_requests_without_organization_are_accepted() {
+    let response = chat(&[]).await;
+
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+});
+
+teenytiny_test!(async fn test_unknown_organization_is_rejected_when_configured() {
+    let response = chat(&[("OpenAI-Organization", UNKNOWN_ORGANIZATION)]).await;
+    if response.status == StatusCode::OK {
+        skip("start the server with TEENYTINY_ORGANIZATIONS to test rejection");
+        return;
+    }
+
+    let error = assert_error(&response, StatusCode::UNAUTHORIZED, "invalid_request_error");
+    assert_eq!(error.message, "OpenAI-Organization header should match organization for API key");
+    assert_eq!(error.code.as_deref(), Some("mismatched_organization"));
+});
+
+teenytiny_test!(async fn test_unknown_project_is_rejected_when_configured() {
+    let response = chat(&[("OpenAI-Project", UNKNOWN_PROJECT)]).await;
+    if response.status == StatusCode::OK {
+        skip("start the server with TEENYTINY_PROJECTS to test rejection");
+        return;
+    }
+
+    let error = assert_error(&response, StatusCode::UNAUTHORIZED, "invalid_request_error");
+    assert_eq!(error.message, "OpenAI-Project header should match project for API key");
+    assert_eq!(error.code.as_deref(), Some("mismatched_project"));
+});
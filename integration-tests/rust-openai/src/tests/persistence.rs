@@ -0,0 +1,100 @@
+// A server started with --state keeps its keys, usage, quotas, captured
+// requests, files and batches in a SQLite database, so a restart picks up where
+// it left off. These tests start servers of their own on a database of their
+// own, and are reported as skipped when the first can't be started, as on
+// Node.js older than 22.5.
+
+use std::path::Path;
+
+use reqwest::multipart::{Form, Part};
+use reqwest::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use super::own_server;
+use crate::server::TestServer;
+
+async fn state_server(state: &Path) -> Option<TestServer> {
+    own_server(&["--state", &state.to_string_lossy()]).await
+}
+
+async fn send(server: &TestServer, key: &str, method: Method, path: &str, body: Option<Value>) -> (StatusCode, Value) {
+    let mut request = crate::http_client().request(method, format!("{}{}", server.url(), path)).bearer_auth(key);
+    if let Some(body) = body {
+        request = request.json(&body);
+    }
+    let response = request.send().await.unwrap();
+    (response.status(), response.json().await.unwrap())
+}
+
+async fn upload(server: &TestServer, key: &str, content: &str) -> Value {
+    let form = Form::new()
+        .text("purpose", "batch")
+        .part("file", Part::text(content.to_string()).file_name("input.jsonl"));
+    let response = crate::http_client()
+        .post(format!("{}/v1/files", server.url()))
+        .bearer_auth(key)
+        .multipart(form)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    response.json().await.unwrap()
+}
+
+teenytiny_test!(async fn test_state_survives_a_restart() {
+    let state = std::env::temp_dir().join(format!("teenytiny-state-{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&state);
+
+    let Some(server) = state_server(&state).await else { return };
+    let admin = server.api_key().to_string();
+    let (_, created) = send(&server, &admin, Method::POST, "/admin/keys", Some(json!({"models": ["echo"]}))).await;
+    let key = created["key"].as_str().unwrap().to_string();
+    let (_, revoked) = send(&server, &admin, Method::POST, "/admin/keys", None).await;
+    let revoked = revoked["key"].as_str().unwrap().to_string();
+    send(&server, &admin, Method::DELETE, &format!("/admin/keys/{}", revoked), None).await;
+    let (status, body) = send(&server, &admin, Method::PUT, &format!("/admin/quotas/{}", key), Some(json!({"token_budget": 1000000}))).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let chat = json!({"model": "echo", "messages": [{"role": "user", "content": "Remember me"}]});
+    let (status, body) = send(&server, &key, Method::POST, "/v1/chat/completions", Some(chat)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let line = json!({
+        "custom_id": "kept", "method": "POST", "url": "/v1/chat/completions",
+        "body": {"model": "echo", "messages": [{"role": "user", "content": "Batched"}]}
+    });
+    let file = upload(&server, &key, &format!("{}\n", line)).await;
+    let (status, batch) = send(&server, &key, Method::POST, "/v1/batches", Some(json!({
+        "input_file_id": file["id"], "endpoint": "/v1/chat/completions", "completion_window": "24h"
+    }))).await;
+    assert_eq!(status, StatusCode::OK, "{}", batch);
+    drop(server);
+
+    // It started once, so failing to start again on its own state is a failure
+    let path = state.to_string_lossy().into_owned();
+    let server = tokio::task::spawn_blocking(move || TestServer::start_with(&["--state", &path]))
+        .await
+        .unwrap()
+        .expect("The server should start again on the state it saved");
+    let (_, keys) = send(&server, &admin, Method::GET, "/admin/keys", None).await;
+    assert!(keys["keys"].as_array().unwrap().contains(&json!({"key": key, "models": ["echo"]})), "{}", keys);
+    assert!(keys["revoked"].as_array().unwrap().contains(&json!(revoked)), "{}", keys);
+    let (status, _) = send(&server, &revoked, Method::GET, "/v1/models", None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (_, usage) = send(&server, &key, Method::GET, "/admin/usage", None).await;
+    assert_eq!(usage["totals"]["requests"], 1, "{}", usage);
+    let (_, quotas) = send(&server, &admin, Method::GET, "/admin/quotas", None).await;
+    let quota = json!({"key": key, "token_budget": 1000000, "tokens_used": usage["totals"]["total_tokens"]});
+    assert!(quotas["data"].as_array().unwrap().contains(&quota), "{}", quotas);
+    let (_, requests) = send(&server, &key, Method::GET, "/admin/requests", None).await;
+    assert!(requests["data"].as_array().unwrap().iter().any(|entry| entry["path"] == "/v1/chat/completions"), "{}", requests);
+    let (status, stored) = send(&server, &key, Method::GET, &format!("/v1/files/{}", file["id"].as_str().unwrap()), None).await;
+    assert_eq!(status, StatusCode::OK, "{}", stored);
+    assert_eq!(stored, file);
+    let (status, restored) = send(&server, &key, Method::GET, &format!("/v1/batches/{}", batch["id"].as_str().unwrap()), None).await;
+    assert_eq!(status, StatusCode::OK, "{}", restored);
+    assert_eq!(restored["input_file_id"], file["id"]);
+
+    drop(server);
+    let _ = std::fs::remove_file(&state);
+});
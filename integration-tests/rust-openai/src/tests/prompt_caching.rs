@@ -0,0 +1,115 @@
+// Simulated prompt caching: prompts of 1024 tokens or more report the prefix
+// they share with an earlier prompt as usage.prompt_tokens_details.cached_tokens,
+// in steps of 128 tokens. The cache is per API key, so each test makes its own.
+
+use async_openai::types::{ChatCompletionRequestMessage, CompletionUsage, CreateChatCompletionRequestArgs};
+use futures::StreamExt;
+
+use super::{client_for, new_api_key, system_message, user_message};
+
+const MIN_CACHED_TOKENS: u32 = 1024;
+const INCREMENT: u32 = 128;
+
+// A system prompt long enough to be cached with any tokenizer
+fn long_instructions() -> ChatCompletionRequestMessage {
+    let clauses: Vec<String> = (0..1500).map(|i| format!("rule{}", i)).collect();
+    system_message(&format!("Follow every rule: {}", clauses.join(" ")))
+}
+
+async fn usage(key: &str, messages: Vec<ChatCompletionRequestMessage>) -> CompletionUsage {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("echo")
+        .messages(messages)
+        .max_completion_tokens(5u32)
+        .build().unwrap();
+    client_for(key).chat().create(request).await.unwrap().usage.expect("No usage")
+}
+
+fn cached_tokens(usage: &CompletionUsage) -> u32 {
+    usage.prompt_tokens_details.as_ref()
+        .and_then(|details| details.cached_tokens)
+        .unwrap_or_else(|| panic!("No prompt_tokens_details.cached_tokens in {:?}", usage))
+}
+
+teenytiny_test!(async fn test_repeated_prompt_reports_cached_tokens() {
+    let key = new_api_key().await;
+    let messages = vec![long_instructions(), user_message("What is rule 7?")];
+
+    let first = usage(&key, messages.clone()).await;
+    let second = usage(&key, messages).await;
+
+    assert!(first.prompt_tokens >= MIN_CACHED_TOKENS, "The prompt is too short to cache: {:?}", first);
+    assert_eq!(cached_tokens(&first), 0, "Nothing was cached before the first request");
+
+    let cached = cached_tokens(&second);
+    assert!(cached >= MIN_CACHED_TOKENS, "Expected the repeated prompt cached, got {}", cached);
+    assert_eq!(cached % INCREMENT, 0, "Cached tokens come in steps of {}", INCREMENT);
+    assert!(cached <= second.prompt_tokens);
+    assert_eq!(second.prompt_tokens, first.prompt_tokens, "Caching doesn't change prompt_tokens");
+});
+
+teenytiny_test!(async fn test_growing_conversation_caches_more() {
+    let key = new_api_key().await;
+    let mut messages = vec![long_instructions(), user_message("What is rule 7?")];
+
+    let mut previous = cached_tokens(&usage(&key, messages.clone()).await);
+    for turn in 0..3 {
+        // Each turn adds a few hundred tokens after the prefix already seen
+        let detail: Vec<String> = (0..300).map(|i| format!("turn{}detail{}", turn, i)).collect();
+        messages.push(user_message(&detail.join(" ")));
+        let cached = cached_tokens(&usage(&key, messages.clone()).await);
+
+        assert!(cached > previous, "Turn {}: cached tokens should grow, {} after {}", turn, cached, previous);
+        previous = cached;
+    }
+});
+
+teenytiny_test!(async fn test_different_prefix_is_not_cached() {
+    let key = new_api_key().await;
+    usage(&key, vec![long_instructions(), user_message("First")]).await;
+
+    // The same instructions after another message share no prefix
+    let reordered = usage(&key, vec![user_message("First"), long_instructions()]).await;
+
+    assert_eq!(cached_tokens(&reordered), 0);
+});
+
+teenytiny_test!(async fn test_cache_is_per_api_key() {
+    let messages = vec![long_instructions(), user_message("Whose cache is this?")];
+    usage(&new_api_key().await, messages.clone()).await;
+
+    let other = usage(&new_api_key().await, messages).await;
+
+    assert_eq!(cached_tokens(&other), 0);
+});
+
+teenytiny_test!(async fn test_short_prompts_have_no_details() {
+    let key = new_api_key().await;
+    usage(&key, vec![user_message("Hello")]).await;
+
+    let repeated = usage(&key, vec![user_message("Hello")]).await;
+
+    assert!(repeated.prompt_tokens_details.is_none(), "Short prompts are never cached: {:?}", repeated);
+});
+
+teenytiny_test!(async fn test_streamed_usage_reports_cached_tokens() {
+    let key = new_api_key().await;
+    let messages = vec![long_instructions(), user_message("Stream it")];
+    usage(&key, messages.clone()).await;
+
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("echo")
+        .messages(messages)
+        .stream(true)
+        .build().unwrap();
+    let mut stream = client_for(&key).chat().create_stream(request).await.unwrap();
+    let mut streamed = None;
+    while let Some(result) = stream.next().await {
+        if let Some(chunk_usage) = result.unwrap().usage {
+            streamed = Some(chunk_usage);
+        }
+    }
+
+    let streamed = streamed.expect("No usage in the stream");
+    assert!(cached_tokens(&streamed) >= MIN_CACHED_TOKENS);
+});
@@ -0,0 +1,79 @@
+// proxy:<model> requests are forwarded to a real upstream, so these tests only
+// run when TEENYTINY_UPSTREAM is set, signalling that the server under test was
+// started with the same variable. TEENYTINY_UPSTREAM_MODEL picks the upstream
+// model (default: gpt-4o-mini).
+
+use std::env;
+
+use async_openai::types::{CreateChatCompletionRequestArgs, FinishReason};
+use futures::StreamExt;
+use serde_json::json;
+
+use crate::setup_client;
+use super::{post_chat_completion, user_message};
+
+fn proxy_model() -> Option<String> {
+    if env::var("TEENYTINY_UPSTREAM").is_err() {
+        eprintln!("Skipping: TEENYTINY_UPSTREAM is not set");
+        return None;
+    }
+    let model = env::var("TEENYTINY_UPSTREAM_MODEL").unwrap_or_else(|_| "gpt-4o-mini".to_string());
+    Some(format!("proxy:{}", model))
+}
+
+teenytiny_test!(async fn test_proxied_completion() {
+    let Some(model) = proxy_model() else { return };
+
+    let request = CreateChatCompletionRequestArgs::default()
+        .model(&model)
+        .messages([user_message("Reply with the single word: pong")])
+        .max_tokens(10u16)
+        .build().unwrap();
+
+    let response = setup_client().chat().create(request).await.unwrap();
+
+    assert!(!response.id.is_empty());
+    assert_eq!(response.choices.len(), 1);
+    assert!(response.choices[0].message.content.as_deref().is_some_and(|c| !c.is_empty()));
+    assert!(response.usage.is_some());
+});
+
+teenytiny_test!(async fn test_proxied_stream() {
+    let Some(model) = proxy_model() else { return };
+
+    let request = CreateChatCompletionRequestArgs::default()
+        .model(&model)
+        .messages([user_message("Count from 1 to 5")])
+        .max_tokens(30u16)
+        .stream(true)
+        .build().unwrap();
+
+    let mut stream = setup_client().chat().create_stream(request).await.unwrap();
+    let mut content = String::new();
+    let mut finish_reason = None;
+    while let Some(result) = stream.next().await {
+        let response = result.unwrap();
+        if let Some(choice) = response.choices.first() {
+            content.push_str(choice.delta.content.as_deref().unwrap_or(""));
+            if choice.finish_reason.is_some() {
+                finish_reason = choice.finish_reason;
+            }
+        }
+    }
+
+    assert!(!content.is_empty());
+    assert!(matches!(finish_reason, Some(FinishReason::Stop | FinishReason::Length)));
+});
+
+teenytiny_test!(async fn test_upstream_errors_are_relayed() {
+    let Some(model) = proxy_model() else { return };
+
+    let (status, body) = post_chat_completion(json!({
+        "model": model,
+        "messages": [],
+    }))
+    .await;
+
+    assert_eq!(status, reqwest::StatusCode::BAD_REQUEST);
+    assert!(body["error"]["message"].is_string(), "Upstream error envelope not relayed: {}", body);
+});
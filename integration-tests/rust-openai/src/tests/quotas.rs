@@ -0,0 +1,98 @@
+// Token budgets set through the admin API. Each test budgets a fresh key of its
+// own, so spending it can't disturb tests running alongside.
+
+use async_openai::{error::OpenAIError, types::CreateChatCompletionRequestArgs};
+use reqwest::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use crate::base_url;
+use super::{admin, client_for, new_api_key, user_message};
+
+async fn budgeted_key(tokens: u32) -> String {
+    let key = new_api_key().await;
+    let (status, body) = admin(Method::PUT, &format!("/quotas/{}", key), Some(json!({"token_budget": tokens}))).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    key
+}
+
+async fn chat(key: &str) -> Result<u32, OpenAIError> {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("echo")
+        .messages([user_message("Spend some tokens")])
+        .build().unwrap();
+    let response = client_for(key).chat().create(request).await?;
+    Ok(response.usage.expect("No usage").total_tokens)
+}
+
+fn assert_insufficient_quota(result: Result<u32, OpenAIError>) {
+    match result {
+        Err(OpenAIError::ApiError(error)) => {
+            assert_eq!(error.r#type.as_deref(), Some("insufficient_quota"), "{:?}", error);
+            assert_eq!(error.code.as_deref(), Some("insufficient_quota"), "{:?}", error);
+            assert!(error.message.contains("exceeded your current quota"), "Unexpected message: {}", error.message);
+        }
+        other => panic!("Expected insufficient_quota, got {:?}", other),
+    }
+}
+
+teenytiny_test!(async fn test_requests_fail_once_the_budget_is_spent() {
+    let key = budgeted_key(50).await;
+
+    // Requests succeed until their tokens reach the budget, then fail. The SDK
+    // only retries 429s that aren't insufficient_quota, so this fails fast.
+    let mut spent = 0;
+    while spent < 50 {
+        spent += chat(&key).await.expect("Requests within the budget should succeed");
+    }
+    assert_insufficient_quota(chat(&key).await);
+    assert_insufficient_quota(chat(&key).await);
+});
+
+teenytiny_test!(async fn test_quota_error_is_a_429() {
+    let key = budgeted_key(0).await;
+
+    let response = crate::http_client()
+        .post(format!("{}/v1/chat/completions", base_url()))
+        .bearer_auth(&key)
+        .json(&json!({"model": "echo", "messages": [{"role": "user", "content": "Hi"}]}))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["error"]["type"], "insufficient_quota");
+    assert_eq!(body["error"]["param"], Value::Null);
+});
+
+teenytiny_test!(async fn test_reset_restores_the_budget() {
+    let key = budgeted_key(1).await;
+    chat(&key).await.unwrap();
+    assert_insufficient_quota(chat(&key).await);
+
+    let (status, _) = admin(Method::POST, "/usage/reset", Some(json!({"key": key}))).await;
+    assert_eq!(status, StatusCode::OK);
+
+    chat(&key).await.expect("A reset key should have its budget back");
+});
+
+teenytiny_test!(async fn test_removing_the_budget_lifts_the_limit() {
+    let key = budgeted_key(0).await;
+    assert_insufficient_quota(chat(&key).await);
+
+    let (status, body) = admin(Method::PUT, &format!("/quotas/{}", key), Some(json!({"token_budget": null}))).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    chat(&key).await.expect("A key without a budget is never refused");
+});
+
+teenytiny_test!(async fn test_budgets_are_listed_with_usage() {
+    let key = budgeted_key(1000).await;
+    let tokens = chat(&key).await.unwrap();
+
+    let (status, body) = admin(Method::GET, "/quotas", None).await;
+
+    assert_eq!(status, StatusCode::OK);
+    let quota = body["data"].as_array().unwrap().iter().find(|quota| quota["key"] == key.as_str()).cloned();
+    assert_eq!(quota, Some(json!({"key": key, "token_budget": 1000, "tokens_used": tokens})));
+});
@@ -0,0 +1,162 @@
+// Each test mints its own key and asks for a tiny budget through the
+// x-teenytiny-ratelimit-requests header, so they neither depend on nor
+// disturb the budget of the shared test key.
+
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde_json::{json, Value};
+
+use crate::base_url;
+use super::new_api_key;
+
+const OVERRIDE_HEADER: &str = "x-teenytiny-ratelimit-requests";
+
+fn chat_request(api_key: &str, budget: u32, stream: bool) -> RequestBuilder {
+    crate::http_client()
+        .post(format!("{}/v1/chat/completions", base_url()))
+        .bearer_auth(api_key)
+        .header(OVERRIDE_HEADER, budget.to_string())
+        .json(&json!({
+            "model": "echo",
+            "messages": [{"role": "user", "content": "Rate limit test"}],
+            "stream": stream,
+        }))
+}
+
+fn header<'a>(response: &'a Response, name: &str) -> &'a str {
+    response.headers().get(name)
+        .unwrap_or_else(|| panic!("Missing {} header", name))
+        .to_str()
+        .unwrap()
+}
+
+// Spends the whole budget, asserting each request succeeds
+async fn exhaust_budget(api_key: &str, budget: u32) {
+    for _ in 0..budget {
+        let response = chat_request(api_key, budget, false).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
+
+teenytiny_test!(async fn test_rate_limit_headers_on_success() {
+    let api_key = new_api_key().await;
+
+    let response = chat_request(&api_key, 5, false).send().await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(header(&response, "x-ratelimit-limit-requests"), "5");
+    assert_eq!(header(&response, "x-ratelimit-remaining-requests"), "4");
+    assert!(header(&response, "x-ratelimit-reset-requests").ends_with('s'));
+});
+
+teenytiny_test!(async fn test_remaining_requests_counts_down() {
+    let api_key = new_api_key().await;
+
+    let mut remaining = Vec::new();
+    for _ in 0..3 {
+        let response = chat_request(&api_key, 3, false).send().await.unwrap();
+        remaining.push(header(&response, "x-ratelimit-remaining-requests").to_string());
+    }
+
+    assert_eq!(remaining, ["2", "1", "0"]);
+});
+
+teenytiny_test!(async fn test_429_after_budget_is_spent() {
+    let api_key = new_api_key().await;
+    exhaust_budget(&api_key, 3).await;
+
+    let response = chat_request(&api_key, 3, false).send().await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(header(&response, "x-ratelimit-limit-requests"), "3");
+    assert_eq!(header(&response, "x-ratelimit-remaining-requests"), "0");
+
+    let retry_after: u64 = header(&response, "retry-after").parse()
+        .expect("Retry-After should be a number of seconds");
+    assert!((1..=60).contains(&retry_after), "Unexpected Retry-After: {}", retry_after);
+
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["error"]["type"], "rate_limit_error");
+    assert_eq!(body["error"]["code"], "rate_limit_exceeded");
+});
+
+teenytiny_test!(async fn test_concurrent_burst_is_limited() {
+    let api_key = new_api_key().await;
+
+    let requests = (0..10).map(|_| chat_request(&api_key, 4, false).send());
+    let responses = futures::future::join_all(requests).await;
+
+    let ok = responses.iter().filter(|r| r.as_ref().unwrap().status() == StatusCode::OK).count();
+    let limited = responses.iter()
+        .filter(|r| r.as_ref().unwrap().status() == StatusCode::TOO_MANY_REQUESTS)
+        .count();
+
+    assert_eq!(ok, 4, "Exactly the budget should succeed");
+    assert_eq!(limited, 6, "Everything over the budget should be rate limited");
+});
+
+teenytiny_test!(async fn test_streaming_gets_429_before_any_sse_data() {
+    let api_key = new_api_key().await;
+    exhaust_budget(&api_key, 2).await;
+
+    let response = chat_request(&api_key, 2, true).send().await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(
+        header(&response, "content-type").starts_with("application/json"),
+        "429 should be a JSON error, not an event stream"
+    );
+    assert!(response.headers().contains_key("retry-after"));
+
+    let body = response.text().await.unwrap();
+    assert!(!body.contains("data:"), "No SSE data should be sent, got: {}", body);
+
+    let body: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["error"]["code"], "rate_limit_exceeded");
+});
+
+teenytiny_test!(async fn test_budgets_are_per_key() {
+    let first_key = new_api_key().await;
+    let second_key = new_api_key().await;
+    exhaust_budget(&first_key, 2).await;
+
+    let response = chat_request(&second_key, 2, false).send().await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+});
+
+teenytiny_test!(async fn test_overrides_take_from_the_keys_normal_budget() {
+    let api_key = new_api_key().await;
+    let unlimited = |api_key: &str| {
+        crate::http_client()
+            .post(format!("{}/v1/chat/completions", base_url()))
+            .bearer_auth(api_key)
+            .json(&json!({"model": "echo", "messages": [{"role": "user", "content": "Rate limit test"}]}))
+            .send()
+    };
+    let first = unlimited(&api_key).await.unwrap();
+    let before: u64 = header(&first, "x-ratelimit-remaining-requests").parse().unwrap();
+
+    exhaust_budget(&api_key, 2).await;
+
+    let after = unlimited(&api_key).await.unwrap();
+    let after: u64 = header(&after, "x-ratelimit-remaining-requests").parse().unwrap();
+    assert_eq!(after, before - 3, "Overridden requests should count against the normal budget too");
+});
+
+teenytiny_test!(async fn test_keys_sent_in_vendor_headers_get_their_own_budgets() {
+    let first_key = new_api_key().await;
+    let second_key = new_api_key().await;
+    let gemini = |api_key: &str| {
+        crate::http_client()
+            .post(format!("{}/v1beta/models/echo:generateContent", base_url()))
+            .header("x-goog-api-key", api_key)
+            .header(OVERRIDE_HEADER, "1")
+            .json(&json!({"contents": [{"role": "user", "parts": [{"text": "Rate limit test"}]}]}))
+            .send()
+    };
+
+    assert_eq!(gemini(&first_key).await.unwrap().status(), StatusCode::OK);
+    assert_eq!(gemini(&first_key).await.unwrap().status(), StatusCode::TOO_MANY_REQUESTS);
+
+    assert_eq!(gemini(&second_key).await.unwrap().status(), StatusCode::OK);
+});
@@ -0,0 +1,62 @@
+// Requests no client library would send - broken JSON, the wrong content type,
+// bodies over the size limit - sent as raw bytes through crate::raw.
+
+use reqwest::StatusCode;
+use serde_json::json;
+
+use crate::raw::{self, assert_error};
+
+const JSON_ENDPOINTS: [&str; 6] = [
+    "/v1/chat/completions",
+    "/v1/moderations",
+    "/v1/images/generations",
+    "/v1/audio/speech",
+    "/v1/batches",
+    "/v1/responses",
+];
+
+fn chat_body(content: &str) -> Vec<u8> {
+    json!({"model": "echo", "messages": [{"role": "user", "content": content}]}).to_string().into_bytes()
+}
+
+teenytiny_test!(async fn test_truncated_json_on_every_endpoint() {
+    for path in JSON_ENDPOINTS {
+        let response = raw::post(path, r#"{"model": "echo", "inp"#).await;
+
+        let error = assert_error(&response, StatusCode::BAD_REQUEST, "invalid_request_error");
+        assert!(error.message.contains("JSON"), "{}: unexpected message {:?}", path, error.message);
+    }
+});
+
+teenytiny_test!(async fn test_body_that_is_not_an_object() {
+    for body in ["[]", "\"hello\"", "42", "null"] {
+        let response = raw::post("/v1/chat/completions", body).await;
+
+        assert_error(&response, StatusCode::BAD_REQUEST, "invalid_request_error");
+    }
+});
+
+teenytiny_test!(async fn test_content_type_is_not_required() {
+    // Like OpenAI, the body is read as JSON whatever it is labelled
+    for content_type in [None, Some("text/plain"), Some("application/x-www-form-urlencoded")] {
+        let response = raw::post_as("/v1/chat/completions", content_type, chat_body("Labelled oddly")).await;
+
+        assert_eq!(response.status, StatusCode::OK, "{:?}: {}", content_type, response.text());
+        assert_eq!(response.json()["choices"][0]["message"]["content"], "Labelled oddly");
+    }
+});
+
+teenytiny_test!(async fn test_huge_payload() {
+    // Over the server's default 8MB body limit, in one message
+    let response = raw::post("/v1/chat/completions", chat_body(&"x".repeat(9 * 1024 * 1024))).await;
+
+    let error = assert_error(&response, StatusCode::PAYLOAD_TOO_LARGE, "invalid_request_error");
+    assert_eq!(error.code.as_deref(), Some("request_too_large"));
+});
+
+teenytiny_test!(async fn test_large_payload_under_the_limit() {
+    let response = raw::post("/v1/chat/completions", chat_body(&"word ".repeat(200_000))).await;
+
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    assert!(response.header("x-ratelimit-limit-requests").is_some(), "Expected rate limit headers");
+});
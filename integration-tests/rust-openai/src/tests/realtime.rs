@@ -0,0 +1,247 @@
+// The Realtime API over a WebSocket: JSON events in both directions, with
+// each reply streamed as response.output_text.delta events and closed by
+// response.done. async-openai has no realtime client, so these speak the
+// protocol over tokio-tungstenite directly.
+
+use std::time::{Duration, Instant};
+
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::{header::AUTHORIZATION, StatusCode};
+use tokio_tungstenite::tungstenite::{Error, Message};
+use tokio_tungstenite::{connect_async_tls_with_config, Connector, MaybeTlsStream, WebSocketStream};
+
+use crate::{api_key, server_url, ca_cert};
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+// How long to wait for any one event before giving up on the server
+const EVENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+// ws:// or wss:// to match the server under test
+fn realtime_url(model: &str) -> String {
+    format!("{}/v1/realtime?model={}", server_url().replacen("http", "ws", 1), model)
+}
+
+// TLS that trusts TEENYTINY_CA_CERT, like the harness's HTTP clients
+fn connector() -> Option<Connector> {
+    let path = ca_cert()?;
+    let pem = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("Can't read TEENYTINY_CA_CERT {}: {}", path, e));
+    let mut builder = native_tls::TlsConnector::builder();
+    for certificate in pem.split_inclusive("-----END CERTIFICATE-----").filter(|pem| pem.contains("BEGIN")) {
+        builder.add_root_certificate(native_tls::Certificate::from_pem(certificate.as_bytes()).unwrap());
+    }
+    Some(Connector::NativeTls(builder.build().unwrap()))
+}
+
+async fn try_connect(model: &str, key: Option<&str>) -> Result<Socket, Error> {
+    let mut request = realtime_url(model).into_client_request().unwrap();
+    if let Some(key) = key {
+        request.headers_mut().insert(AUTHORIZATION, format!("Bearer {}", key).parse().unwrap());
+    }
+    let (socket, response) = connect_async_tls_with_config(request, None, false, connector()).await?;
+    assert_eq!(response.status(), StatusCode::SWITCHING_PROTOCOLS);
+    Ok(socket)
+}
+
+// Connects and reads past session.created, returning it with the socket
+async fn connect(model: &str) -> (Socket, Value) {
+    let mut socket = try_connect(model, Some(&api_key())).await.unwrap();
+    let created = next_event(&mut socket).await;
+    assert_eq!(created["type"], "session.created", "The first event should be session.created: {}", created);
+    (socket, created)
+}
+
+async fn send(socket: &mut Socket, event: Value) {
+    socket.send(Message::Text(event.to_string())).await.unwrap();
+}
+
+async fn next_event(socket: &mut Socket) -> Value {
+    loop {
+        match timeout(EVENT_TIMEOUT, socket.next()).await.expect("No event from the server") {
+            Some(Ok(Message::Text(text))) => {
+                let event: Value = serde_json::from_str(&text).unwrap();
+                assert!(event["event_id"].is_string(), "Every event should have an event_id: {}", event);
+                return event;
+            }
+            Some(Ok(Message::Ping(_) | Message::Pong(_))) => continue,
+            other => panic!("Expected an event, got {:?}", other),
+        }
+    }
+}
+
+// Every event up to and including the first of the given type
+async fn events_until(socket: &mut Socket, event_type: &str) -> Vec<Value> {
+    let mut events = Vec::new();
+    loop {
+        let event = next_event(socket).await;
+        let done = event["type"] == event_type;
+        events.push(event);
+        if done {
+            return events;
+        }
+    }
+}
+
+fn user_item(text: &str) -> Value {
+    json!({
+        "type": "conversation.item.create",
+        "item": {"type": "message", "role": "user", "content": [{"type": "input_text", "text": text}]}
+    })
+}
+
+// Says something and collects the response to it
+async fn respond_to(socket: &mut Socket, text: &str) -> Vec<Value> {
+    send(socket, user_item(text)).await;
+    send(socket, json!({"type": "response.create"})).await;
+    events_until(socket, "response.done").await
+}
+
+fn deltas(events: &[Value]) -> String {
+    events
+        .iter()
+        .filter(|event| event["type"] == "response.output_text.delta")
+        .map(|event| event["delta"].as_str().unwrap())
+        .collect()
+}
+
+teenytiny_test!(async fn test_session_created_on_connect() {
+    let (_socket, created) = connect("echo").await;
+
+    let session = &created["session"];
+    assert_eq!(session["object"], "realtime.session");
+    assert_eq!(session["model"], "echo");
+    assert!(session["id"].as_str().unwrap().starts_with("sess_"));
+});
+
+teenytiny_test!(async fn test_session_update() {
+    let (mut socket, _) = connect("echo").await;
+
+    send(&mut socket, json!({"type": "session.update", "session": {"instructions": "Be brief"}})).await;
+    let updated = next_event(&mut socket).await;
+
+    assert_eq!(updated["type"], "session.updated");
+    assert_eq!(updated["session"]["instructions"], "Be brief");
+});
+
+teenytiny_test!(async fn test_response_streams_text_deltas() {
+    let (mut socket, _) = connect("echo").await;
+
+    let events = respond_to(&mut socket, "Hello realtime").await;
+    let types: Vec<&str> = events.iter().map(|event| event["type"].as_str().unwrap()).collect();
+
+    let created = types.iter().position(|t| *t == "response.created").expect("No response.created");
+    let first_delta = types.iter().position(|t| *t == "response.output_text.delta").expect("No deltas");
+    let text_done = types.iter().position(|t| *t == "response.output_text.done").expect("No response.output_text.done");
+    assert!(created < first_delta && first_delta < text_done, "Events out of order: {:?}", types);
+    assert_eq!(deltas(&events), "Hello realtime");
+    assert_eq!(events[text_done]["text"], "Hello realtime");
+
+    // Deltas name the response and item they belong to
+    let response_id = events[created]["response"]["id"].as_str().unwrap();
+    for delta in events.iter().filter(|event| event["type"] == "response.output_text.delta") {
+        assert_eq!(delta["response_id"], response_id);
+        assert!(delta["item_id"].is_string());
+    }
+
+    let response = &events.last().unwrap()["response"];
+    assert_eq!(response["id"], response_id);
+    assert_eq!(response["status"], "completed");
+    assert_eq!(response["output"][0]["content"][0]["text"], "Hello realtime");
+    let usage = &response["usage"];
+    assert!(usage["output_tokens"].as_u64().unwrap() > 0);
+    assert_eq!(
+        usage["total_tokens"].as_u64(),
+        Some(usage["input_tokens"].as_u64().unwrap() + usage["output_tokens"].as_u64().unwrap())
+    );
+});
+
+teenytiny_test!(async fn test_deltas_arrive_as_they_are_generated() {
+    let (mut socket, _) = connect("slow:100").await;
+
+    send(&mut socket, user_item("one two three four")).await;
+    send(&mut socket, json!({"type": "response.create"})).await;
+
+    let mut arrivals = Vec::new();
+    loop {
+        let event = next_event(&mut socket).await;
+        if event["type"] == "response.output_text.delta" {
+            arrivals.push(Instant::now());
+        }
+        if event["type"] == "response.done" {
+            break;
+        }
+    }
+
+    assert_eq!(arrivals.len(), 4, "One delta per word");
+    let spread = arrivals.last().unwrap().duration_since(arrivals[0]);
+    assert!(spread >= Duration::from_millis(200), "Deltas arrived together, {:?} apart", spread);
+});
+
+teenytiny_test!(async fn test_conversation_carries_over() {
+    let (mut socket, _) = connect("echo").await;
+
+    assert_eq!(deltas(&respond_to(&mut socket, "First turn").await), "First turn");
+    assert_eq!(deltas(&respond_to(&mut socket, "Second turn").await), "Second turn");
+});
+
+teenytiny_test!(async fn test_response_cancel() {
+    let (mut socket, _) = connect("slow:100").await;
+
+    send(&mut socket, user_item("one two three four five six seven eight")).await;
+    send(&mut socket, json!({"type": "response.create"})).await;
+    events_until(&mut socket, "response.output_text.delta").await;
+    send(&mut socket, json!({"type": "response.cancel"})).await;
+
+    let events = events_until(&mut socket, "response.done").await;
+    let response = &events.last().unwrap()["response"];
+    assert_eq!(response["status"], "cancelled");
+    assert_ne!(response["output"][0]["content"][0]["text"], "one two three four five six seven eight");
+});
+
+teenytiny_test!(async fn test_bad_events_get_error_events() {
+    let (mut socket, _) = connect("echo").await;
+
+    socket.send(Message::Text("not json".to_string())).await.unwrap();
+    let error = next_event(&mut socket).await;
+    assert_eq!(error["type"], "error");
+    assert_eq!(error["error"]["code"], "invalid_json");
+
+    send(&mut socket, json!({"type": "no.such.event", "event_id": "evt_client"})).await;
+    let error = next_event(&mut socket).await;
+    assert_eq!(error["type"], "error");
+    assert_eq!(error["error"]["event_id"], "evt_client");
+
+    // The connection stays usable
+    assert_eq!(deltas(&respond_to(&mut socket, "Still here").await), "Still here");
+});
+
+teenytiny_test!(async fn test_close_handshake() {
+    let (mut socket, _) = connect("echo").await;
+
+    socket.close(None).await.unwrap();
+    loop {
+        match timeout(EVENT_TIMEOUT, socket.next()).await.expect("The server didn't close") {
+            Some(Ok(Message::Close(_))) | None => break,
+            Some(Ok(_)) => continue,
+            Some(Err(error)) => panic!("Unclean close: {}", error),
+        }
+    }
+});
+
+teenytiny_test!(async fn test_upgrade_needs_an_api_key() {
+    match try_connect("echo", None).await {
+        Err(Error::Http(response)) => assert_eq!(response.status(), StatusCode::UNAUTHORIZED),
+        other => panic!("Expected the upgrade to be refused, got {:?}", other.map(|_| ())),
+    }
+});
+
+teenytiny_test!(async fn test_upgrade_to_an_unknown_model() {
+    match try_connect("no-such-model", Some(&api_key())).await {
+        Err(Error::Http(response)) => assert_eq!(response.status(), StatusCode::NOT_FOUND),
+        other => panic!("Expected the upgrade to be refused, got {:?}", other.map(|_| ())),
+    }
+});
@@ -0,0 +1,156 @@
+// The reasoning model emulates o1: it reasons before echoing, reports the
+// reasoning as reasoning_content and as completion_tokens_details, and
+// refuses sampling parameters reasoning models don't support.
+
+use async_openai::types::{CreateChatCompletionRequestArgs, ReasoningEffort};
+use futures::StreamExt;
+use reqwest::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use crate::raw::{self, assert_error};
+use crate::setup_client;
+use super::{post_chat_completion, user_message};
+
+async fn reasoning_tokens(effort: ReasoningEffort) -> u32 {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("reasoning")
+        .messages([user_message("How many words")])
+        .reasoning_effort(effort)
+        .build().unwrap();
+
+    let response = setup_client().chat().create(request).await.unwrap();
+    let usage = response.usage.expect("No usage");
+    let details = usage.completion_tokens_details.expect("No completion_tokens_details");
+    details.reasoning_tokens.expect("No reasoning_tokens")
+}
+
+teenytiny_test!(async fn test_reasoning_tokens_deserialize() {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("reasoning")
+        .messages([user_message("Think it over")])
+        .max_completion_tokens(100u32)
+        .build().unwrap();
+
+    let response = setup_client().chat().create(request).await.unwrap();
+
+    assert_eq!(response.choices[0].message.content.as_deref(), Some("Think it over"));
+    let usage = response.usage.expect("No usage");
+    let reasoning = usage.completion_tokens_details.and_then(|details| details.reasoning_tokens).unwrap();
+    assert!(reasoning > 0, "Expected reasoning tokens");
+    assert!(usage.completion_tokens > reasoning, "Reasoning tokens are part of completion_tokens");
+    assert_eq!(usage.total_tokens, usage.prompt_tokens + usage.completion_tokens);
+});
+
+teenytiny_test!(async fn test_reasoning_content_is_returned() {
+    let (status, body) = post_chat_completion(json!({
+        "model": "reasoning",
+        "messages": [{"role": "user", "content": "Think it over"}],
+    }))
+    .await;
+
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let reasoning = body["choices"][0]["message"]["reasoning_content"].as_str().expect("No reasoning_content");
+    assert!(reasoning.starts_with("The user wrote 3 words."), "Unexpected reasoning: {}", reasoning);
+    assert_eq!(body["choices"][0]["message"]["content"], "Think it over");
+});
+
+teenytiny_test!(async fn test_more_effort_reasons_longer() {
+    let low = reasoning_tokens(ReasoningEffort::Low).await;
+    let medium = reasoning_tokens(ReasoningEffort::Medium).await;
+    let high = reasoning_tokens(ReasoningEffort::High).await;
+
+    assert!(low < medium && medium < high, "Expected low < medium < high, got {} {} {}", low, medium, high);
+});
+
+teenytiny_test!(async fn test_streamed_reasoning_comes_first() {
+    let request = raw::request(Method::POST, "/v1/chat/completions").json(&json!({
+        "model": "reasoning",
+        "stream": true,
+        "stream_options": {"include_usage": true},
+        "messages": [{"role": "user", "content": "Think it over"}],
+    }));
+    let response = raw::send(request).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+
+    let mut reasoning = String::new();
+    let mut content = String::new();
+    let mut usage = Value::Null;
+    for data in response.text().lines().filter_map(|line| line.strip_prefix("data: ")) {
+        if data == "[DONE]" {
+            continue;
+        }
+        let chunk: Value = serde_json::from_str(data).unwrap_or_else(|e| panic!("Bad chunk {} ({})", data, e));
+        if !chunk["usage"].is_null() {
+            usage = chunk["usage"].clone();
+        }
+        let delta = &chunk["choices"][0]["delta"];
+        if let Some(fragment) = delta["reasoning_content"].as_str() {
+            assert!(content.is_empty(), "Reasoning should arrive before the answer: {}", data);
+            reasoning.push_str(fragment);
+        }
+        content.push_str(delta["content"].as_str().unwrap_or(""));
+    }
+
+    assert!(reasoning.starts_with("The user wrote 3 words."), "Unexpected reasoning: {}", reasoning);
+    assert_eq!(content, "Think it over");
+    assert!(usage["completion_tokens_details"]["reasoning_tokens"].as_u64().unwrap_or(0) > 0, "{}", usage);
+});
+
+teenytiny_test!(async fn test_sdk_stream_ignores_reasoning_deltas() {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("reasoning")
+        .messages([user_message("Think it over")])
+        .stream(true)
+        .build().unwrap();
+
+    let mut stream = setup_client().chat().create_stream(request).await.unwrap();
+    let mut content = String::new();
+    while let Some(result) = stream.next().await {
+        let response = result.expect("Reasoning deltas should still deserialize");
+        if let Some(choice) = response.choices.first() {
+            content.push_str(choice.delta.content.as_deref().unwrap_or(""));
+        }
+    }
+
+    assert_eq!(content, "Think it over");
+});
+
+teenytiny_test!(async fn test_default_sampling_values_are_accepted() {
+    let (status, body) = post_chat_completion(json!({
+        "model": "reasoning",
+        "messages": [{"role": "user", "content": "Hello"}],
+        "temperature": 1,
+        "top_p": 1,
+        "presence_penalty": 0,
+    }))
+    .await;
+
+    assert_eq!(status, StatusCode::OK, "{}", body);
+});
+
+teenytiny_test!(async fn test_unsupported_values_are_rejected() {
+    for (param, value) in [("temperature", json!(0.7)), ("top_p", json!(0.5)), ("frequency_penalty", json!(1))] {
+        let mut body = json!({"model": "reasoning", "messages": [{"role": "user", "content": "Hello"}]});
+        body[param] = value;
+        let response = raw::send(raw::request(Method::POST, "/v1/chat/completions").json(&body)).await;
+
+        let error = assert_error(&response, StatusCode::BAD_REQUEST, "invalid_request_error");
+        assert_eq!(error.param.as_deref(), Some(param));
+        assert_eq!(error.code.as_deref(), Some("unsupported_value"));
+        assert!(error.message.contains("Only the default"), "Unexpected message: {}", error.message);
+    }
+});
+
+teenytiny_test!(async fn test_max_tokens_is_rejected() {
+    let response = raw::send(raw::request(Method::POST, "/v1/chat/completions").json(&json!({
+        "model": "o1-mini",
+        "messages": [{"role": "user", "content": "Hello"}],
+        "max_tokens": 50,
+    })))
+    .await;
+
+    let error = assert_error(&response, StatusCode::BAD_REQUEST, "invalid_request_error");
+    assert_eq!(error.param.as_deref(), Some("max_tokens"));
+    assert_eq!(error.code.as_deref(), Some("unsupported_parameter"));
+    assert!(error.message.contains("max_completion_tokens"), "Unexpected message: {}", error.message);
+});
@@ -0,0 +1,99 @@
+// Record and replay runs per API key, so each test mints its own key and its
+// recordings never affect other tests running against the same server.
+
+use reqwest::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use crate::base_url;
+use super::{admin_as, new_api_key};
+
+async fn complete(key: &str, content: &str, stream: bool) -> (StatusCode, String) {
+    let response = crate::http_client()
+        .post(format!("{}/v1/chat/completions", base_url()))
+        .bearer_auth(key)
+        .json(&json!({
+            "model": "echo",
+            "stream": stream,
+            "messages": [{"role": "user", "content": content}],
+        }))
+        .send()
+        .await
+        .unwrap();
+    (response.status(), response.text().await.unwrap())
+}
+
+// Cassette names are shared by all keys, so tests name theirs after the key's random tail
+fn cassette_name(key: &str) -> String {
+    let chars: Vec<char> = key.chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+    format!("rust-{}", chars[chars.len().saturating_sub(16)..].iter().collect::<String>())
+}
+
+teenytiny_test!(async fn test_streamed_replay_is_byte_for_byte() {
+    let key = new_api_key().await;
+    let name = cassette_name(&key);
+
+    assert_eq!(admin_as(&key, Method::POST, &format!("/cassettes/{}/record", name), Some(json!({}))).await.0, StatusCode::OK);
+    let (_, recorded) = complete(&key, "Record this stream", true).await;
+    let (_, stopped) = admin_as(&key, Method::POST, "/cassettes/stop", Some(json!({}))).await;
+    assert_eq!(stopped["interactions"], 1);
+
+    admin_as(&key, Method::POST, &format!("/cassettes/{}/replay", name), Some(json!({"realtime": false}))).await;
+    let (status, replayed) = complete(&key, "Record this stream", true).await;
+    admin_as(&key, Method::POST, "/cassettes/stop", Some(json!({}))).await;
+
+    assert_eq!(status, StatusCode::OK);
+    // Live completions get a fresh id each time, so equality proves the replay
+    assert_eq!(replayed, recorded);
+    assert!(replayed.ends_with("data: [DONE]\n\n"));
+});
+
+teenytiny_test!(async fn test_blocking_replay_and_misses() {
+    let key = new_api_key().await;
+    let name = format!("{}-blocking", cassette_name(&key));
+
+    admin_as(&key, Method::POST, &format!("/cassettes/{}/record", name), Some(json!({}))).await;
+    let (_, recorded) = complete(&key, "Hello", false).await;
+    admin_as(&key, Method::POST, "/cassettes/stop", Some(json!({}))).await;
+
+    admin_as(&key, Method::POST, &format!("/cassettes/{}/replay", name), Some(json!({"realtime": false}))).await;
+    let (_, replayed) = complete(&key, "Hello", false).await;
+    let (status, miss) = complete(&key, "Never recorded", false).await;
+    admin_as(&key, Method::POST, "/cassettes/stop", Some(json!({}))).await;
+
+    assert_eq!(replayed, recorded);
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let miss: Value = serde_json::from_str(&miss).unwrap();
+    assert_eq!(miss["error"]["code"], "cassette_miss");
+});
+
+teenytiny_test!(async fn test_replay_keeps_chunk_timing() {
+    let key = new_api_key().await;
+    let name = format!("{}-timing", cassette_name(&key));
+
+    admin_as(&key, Method::POST, &format!("/cassettes/{}/record", name), Some(json!({}))).await;
+    complete(&key, "Slow start !delay:400", true).await;
+    admin_as(&key, Method::POST, "/cassettes/stop", Some(json!({}))).await;
+
+    admin_as(&key, Method::POST, &format!("/cassettes/{}/replay", name), Some(json!({}))).await;
+    let started = std::time::Instant::now();
+    complete(&key, "Slow start !delay:400", true).await;
+    let elapsed = started.elapsed();
+    admin_as(&key, Method::POST, "/cassettes/stop", Some(json!({}))).await;
+
+    assert!(elapsed.as_millis() >= 300, "Replay ignored recorded timing: {:?}", elapsed);
+});
+
+teenytiny_test!(async fn test_list_and_busy_recorder() {
+    let key = new_api_key().await;
+    let name = format!("{}-listed", cassette_name(&key));
+
+    admin_as(&key, Method::POST, &format!("/cassettes/{}/record", name), Some(json!({}))).await;
+    let (status, _) = admin_as(&key, Method::POST, "/cassettes/other/record", Some(json!({}))).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    admin_as(&key, Method::POST, "/cassettes/stop", Some(json!({}))).await;
+
+    let (_, listing) = admin_as(&key, Method::GET, "/cassettes", None).await;
+
+    assert!(listing["data"].as_array().unwrap().iter().any(|n| n == name.as_str()));
+    assert_eq!(listing["recorder"]["mode"], "idle");
+});
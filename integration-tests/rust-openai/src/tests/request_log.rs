@@ -0,0 +1,97 @@
+// Each test uses its own key, so the last request logged for it is the one it made
+
+use async_openai::types::CreateChatCompletionRequestArgs;
+use futures::StreamExt;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use crate::base_url;
+use super::{assert_forwarded, client_for, last_captured_request, new_api_key, user_message};
+
+teenytiny_test!(async fn test_sampling_parameters_are_forwarded() {
+    let key = new_api_key().await;
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("echo")
+        .messages([user_message("Forwarding test")])
+        .temperature(0.5)
+        .top_p(0.25)
+        .max_tokens(64u16)
+        .stop(["END"])
+        .build().unwrap();
+
+    client_for(&key).chat().create(request).await.unwrap();
+
+    assert_forwarded(&key, "temperature", json!(0.5)).await;
+    assert_forwarded(&key, "top_p", json!(0.25)).await;
+    assert_forwarded(&key, "max_tokens", json!(64)).await;
+    assert_forwarded(&key, "stop", json!(["END"])).await;
+    assert_forwarded(&key, "messages", json!([{"role": "user", "content": "Forwarding test"}])).await;
+
+    let captured = last_captured_request(&key).await;
+    assert_eq!(captured["path"], "/v1/chat/completions");
+    assert_eq!(captured["status"], 200);
+    assert_eq!(captured["response_body"]["choices"][0]["message"]["content"], "Forwarding test");
+    assert!(captured["request_headers"].get("authorization").is_none());
+});
+
+teenytiny_test!(async fn test_logit_bias_is_forwarded() {
+    // Some client libraries always send logit_bias, up to OpenAI's limit of 300 entries
+    for entries in [1, 300] {
+        let key = new_api_key().await;
+        let bias: HashMap<String, Value> = (0..entries)
+            .map(|i| ((50_000 + i).to_string(), json!(if i % 2 == 0 { -100 } else { 25 })))
+            .collect();
+        let request = CreateChatCompletionRequestArgs::default()
+            .model("echo")
+            .messages([user_message("Biased")])
+            .logit_bias(bias.clone())
+            .build().unwrap();
+
+        let response = client_for(&key).chat().create(request).await.unwrap();
+
+        assert_eq!(response.choices[0].message.content.as_deref(), Some("Biased"));
+        assert_forwarded(&key, "logit_bias", json!(bias)).await;
+    }
+});
+
+teenytiny_test!(async fn test_streamed_request_is_logged() {
+    let key = new_api_key().await;
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("echo")
+        .messages([user_message("Stream log test")])
+        .build().unwrap();
+
+    let mut stream = client_for(&key).chat().create_stream(request).await.unwrap();
+    while let Some(chunk) = stream.next().await {
+        chunk.unwrap();
+    }
+
+    let captured = last_captured_request(&key).await;
+    assert_eq!(captured["stream"], true);
+    assert_eq!(captured["request_body"]["stream"], true);
+    assert_eq!(captured["response_body"], Value::Null);
+});
+
+teenytiny_test!(async fn test_filter_by_status() {
+    let key = new_api_key().await;
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("no-such-model")
+        .messages([user_message("Missing model")])
+        .build().unwrap();
+    assert!(client_for(&key).chat().create(request).await.is_err());
+
+    let response: Value = crate::http_client()
+        .get(format!("{}/admin/requests?status=404", base_url()))
+        .bearer_auth(&key)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+
+    let data = response["data"].as_array().expect("No data in response");
+    assert_eq!(data.len(), 1);
+    assert_eq!(data[0]["model"], "no-such-model");
+    assert_eq!(data[0]["response_body"]["error"]["code"], "model_not_found");
+});
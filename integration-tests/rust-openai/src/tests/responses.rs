@@ -0,0 +1,331 @@
+// The Responses API: input items in, output items out, with streams of typed
+// events and previous_response_id chaining against responses kept per key.
+// async-openai covers creating responses; streams, retrieval and input item
+// listing are read from the raw body.
+
+use async_openai::{
+    error::OpenAIError,
+    types::responses::{
+        Content, CreateResponse, CreateResponseArgs, FunctionArgs, Input, InputContent, InputItem,
+        InputMessageArgs, OutputContent, Response, Role, Status, ToolDefinition,
+    },
+};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+
+use crate::{api_key, base_url, http_client};
+use super::new_api_key;
+
+fn text_request(model: &str, input: &str) -> CreateResponse {
+    CreateResponseArgs::default()
+        .model(model)
+        .input(Input::Text(input.to_string()))
+        .build()
+        .unwrap()
+}
+
+fn weather_tool() -> ToolDefinition {
+    ToolDefinition::Function(
+        FunctionArgs::default()
+            .name("get_weather")
+            .description("Current weather for a city")
+            .parameters(json!({
+                "type": "object",
+                "properties": { "city": { "type": "string" } },
+                "required": ["city"],
+            }))
+            .build()
+            .unwrap(),
+    )
+}
+
+// All the output_text of a response's messages, as SDKs put together for output_text
+fn output_text(response: &Response) -> String {
+    response
+        .output
+        .iter()
+        .filter_map(|item| match item {
+            OutputContent::Message(message) => Some(&message.content),
+            _ => None,
+        })
+        .flatten()
+        .map(|content| match content {
+            Content::OutputText(text) => text.text.as_str(),
+            Content::Refusal(refusal) => panic!("Unexpected refusal: {}", refusal.refusal),
+        })
+        .collect()
+}
+
+async fn get(path: &str, key: &str) -> (StatusCode, Value) {
+    let response = http_client()
+        .get(format!("{}/v1/responses/{}", base_url(), path))
+        .bearer_auth(key)
+        .send()
+        .await
+        .unwrap();
+    let status = response.status();
+    (status, response.json().await.unwrap())
+}
+
+// Posts a streaming request, returning each event's name and data
+async fn stream_events(body: Value) -> Vec<(String, Value)> {
+    let response = http_client()
+        .post(format!("{}/v1/responses", base_url()))
+        .bearer_auth(api_key())
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let content_type = response.headers()["content-type"].to_str().unwrap().to_string();
+    assert!(content_type.starts_with("text/event-stream"), "Unexpected Content-Type {}", content_type);
+
+    let body = response.text().await.unwrap();
+    body.split("\n\n")
+        .filter(|frame| !frame.trim().is_empty())
+        .map(|frame| {
+            let (event, data) = frame.split_once('\n').unwrap();
+            let name = event.strip_prefix("event: ").expect("event line").to_string();
+            let data: Value = serde_json::from_str(data.strip_prefix("data: ").expect("data line")).unwrap();
+            assert_eq!(data["type"], name.as_str());
+            (name, data)
+        })
+        .collect()
+}
+
+teenytiny_test!(async fn test_create_response(client) {
+    let request = CreateResponseArgs::default()
+        .model("echo")
+        .instructions("Be brief")
+        .input(Input::Text("Hello responses".to_string()))
+        .build()
+        .unwrap();
+
+    let response = client.responses().create(request).await.unwrap();
+
+    assert!(response.id.starts_with("resp_"), "Unexpected id: {}", response.id);
+    assert_eq!(response.object, "response");
+    assert_eq!(response.status, Status::Completed);
+    assert_eq!(response.model, "echo");
+    assert_eq!(response.instructions.as_deref(), Some("Be brief"));
+    assert_eq!(output_text(&response), "Hello responses");
+    match &response.output[0] {
+        OutputContent::Message(message) => assert_eq!(message.role, Role::Assistant),
+        other => panic!("Expected a message, got {:?}", other),
+    }
+
+    let usage = response.usage.unwrap();
+    assert!(usage.input_tokens > 0 && usage.output_tokens > 0);
+    assert_eq!(usage.total_tokens, usage.input_tokens + usage.output_tokens);
+});
+
+teenytiny_test!(async fn test_input_items(client) {
+    let message = |role: Role, text: &str| {
+        InputItem::Message(
+            InputMessageArgs::default()
+                .role(role)
+                .content(InputContent::TextInput(text.to_string()))
+                .build()
+                .unwrap(),
+        )
+    };
+    let request = CreateResponseArgs::default()
+        .model("echo")
+        .input(Input::Items(vec![
+            message(Role::Developer, "Answer in one word"),
+            message(Role::User, "First question"),
+            message(Role::Assistant, "First answer"),
+            message(Role::User, "Second question"),
+        ]))
+        .build()
+        .unwrap();
+
+    let response = client.responses().create(request).await.unwrap();
+    assert_eq!(output_text(&response), "Second question");
+});
+
+teenytiny_test!(async fn test_streaming_events() {
+    let events = stream_events(json!({"model": "echo", "input": "Hello streaming events", "stream": true})).await;
+    let names: Vec<&str> = events.iter().map(|(name, _)| name.as_str()).collect();
+
+    assert_eq!(
+        names[..4],
+        ["response.created", "response.in_progress", "response.output_item.added", "response.content_part.added"]
+    );
+    assert_eq!(
+        names[names.len() - 4..],
+        ["response.output_text.done", "response.content_part.done", "response.output_item.done", "response.completed"]
+    );
+    for (i, (_, data)) in events.iter().enumerate() {
+        assert_eq!(data["sequence_number"], i as u64);
+    }
+
+    let deltas: Vec<&str> = events
+        .iter()
+        .filter(|(name, _)| name == "response.output_text.delta")
+        .map(|(_, data)| data["delta"].as_str().unwrap())
+        .collect();
+    assert!(deltas.len() > 1, "Expected the text in pieces: {:?}", deltas);
+    assert_eq!(deltas.concat(), "Hello streaming events");
+
+    let done = &events.iter().find(|(name, _)| name == "response.output_text.done").unwrap().1;
+    assert_eq!(done["text"], "Hello streaming events");
+
+    let created = &events[0].1["response"];
+    let completed = &events.last().unwrap().1["response"];
+    assert_eq!(created["status"], "in_progress");
+    assert_eq!(completed["id"], created["id"]);
+    assert_eq!(completed["status"], "completed");
+    assert_eq!(completed["output"][0]["content"][0]["text"], "Hello streaming events");
+    assert!(completed["usage"]["total_tokens"].as_u64().unwrap() > 0);
+
+    // A streamed response is kept like any other
+    let (status, kept) = get(completed["id"].as_str().unwrap(), &api_key()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(kept["output"], completed["output"]);
+});
+
+teenytiny_test!(requires(Tools) async fn test_streaming_function_call() {
+    let events = stream_events(json!({
+        "model": "tooluse",
+        "input": "What's the weather in Paris?",
+        "tools": [{"type": "function", "name": "get_weather", "parameters": {"type": "object", "properties": {"city": {"type": "string"}}}}],
+        "stream": true,
+    }))
+    .await;
+
+    let added = &events.iter().find(|(name, _)| name == "response.output_item.added").unwrap().1;
+    assert_eq!(added["item"]["type"], "function_call");
+    assert_eq!(added["item"]["name"], "get_weather");
+
+    let arguments: String = events
+        .iter()
+        .filter(|(name, _)| name == "response.function_call_arguments.delta")
+        .map(|(_, data)| data["delta"].as_str().unwrap())
+        .collect();
+    let done = &events.iter().find(|(name, _)| name == "response.function_call_arguments.done").unwrap().1;
+    assert_eq!(done["arguments"], arguments.as_str());
+    let parsed: Value = serde_json::from_str(&arguments).unwrap();
+    assert!(parsed["city"].is_string(), "Unexpected arguments: {}", parsed);
+});
+
+teenytiny_test!(requires(Tools) async fn test_previous_response_id_completes_a_tool_call(client) {
+    let request = CreateResponseArgs::default()
+        .model("tooluse")
+        .input(Input::Text("What's the weather in Paris?".to_string()))
+        .tools(vec![weather_tool()])
+        .build()
+        .unwrap();
+    let first = client.responses().create(request).await.unwrap();
+    let call = match &first.output[0] {
+        OutputContent::FunctionCall(call) => call.clone(),
+        other => panic!("Expected a function call, got {:?}", other),
+    };
+    assert_eq!(call.name, "get_weather");
+
+    let request = CreateResponseArgs::default()
+        .model("tooluse")
+        .previous_response_id(first.id.clone())
+        .input(Input::Items(vec![InputItem::Custom(json!({
+            "type": "function_call_output",
+            "call_id": call.call_id,
+            "output": "Sunny, 21C",
+        }))]))
+        .tools(vec![weather_tool()])
+        .build()
+        .unwrap();
+    let second = client.responses().create(request).await.unwrap();
+
+    assert_eq!(second.previous_response_id.as_deref(), Some(first.id.as_str()));
+    assert_eq!(output_text(&second), "Sunny, 21C");
+});
+
+teenytiny_test!(async fn test_previous_response_id_carries_the_conversation(client) {
+    let first = client.responses().create(text_request("echo", "Remember this")).await.unwrap();
+
+    let fresh = client.responses().create(text_request("echo", "And this")).await.unwrap();
+    let mut chained = text_request("echo", "And this");
+    chained.previous_response_id = Some(first.id.clone());
+    let chained = client.responses().create(chained).await.unwrap();
+
+    assert_eq!(output_text(&chained), "And this");
+    assert!(
+        chained.usage.unwrap().input_tokens > fresh.usage.unwrap().input_tokens,
+        "The earlier turn wasn't counted as input"
+    );
+});
+
+teenytiny_test!(async fn test_retrieve_input_items_and_delete(client) {
+    let response = client.responses().create(text_request("echo", "Keep me")).await.unwrap();
+
+    let (status, kept) = get(&response.id, &api_key()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(kept["id"], response.id.as_str());
+    assert_eq!(kept["output"][0]["content"][0]["text"], "Keep me");
+
+    let (status, items) = get(&format!("{}/input_items", response.id), &api_key()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(items["object"], "list");
+    assert_eq!(items["data"][0]["type"], "message");
+    assert_eq!(items["data"][0]["role"], "user");
+    assert_eq!(items["data"][0]["content"], json!([{"type": "input_text", "text": "Keep me"}]));
+
+    // Another key can't see it
+    let (status, _) = get(&response.id, &new_api_key().await).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let deleted: Value = http_client()
+        .delete(format!("{}/v1/responses/{}", base_url(), response.id))
+        .bearer_auth(api_key())
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(deleted["deleted"], true);
+    let (status, body) = get(&response.id, &api_key()).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert!(body["error"]["message"].as_str().unwrap().contains(&response.id));
+});
+
+teenytiny_test!(async fn test_unstored_response_cannot_be_continued(client) {
+    let mut request = text_request("echo", "Forget me");
+    request.store = Some(false);
+    let response = client.responses().create(request).await.unwrap();
+    assert_eq!(output_text(&response), "Forget me");
+
+    let (status, _) = get(&response.id, &api_key()).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let mut chained = text_request("echo", "Hello again");
+    chained.previous_response_id = Some(response.id.clone());
+    match client.responses().create(chained).await {
+        Err(OpenAIError::ApiError(err)) => {
+            assert_eq!(err.param.as_deref(), Some("previous_response_id"));
+            assert_eq!(err.code.as_deref(), Some("previous_response_not_found"));
+        }
+        other => panic!("Expected an API error, got {:?}", other),
+    }
+});
+
+teenytiny_test!(async fn test_max_output_tokens_makes_it_incomplete(client) {
+    let mut request = text_request("echo", &"word ".repeat(40));
+    request.max_output_tokens = Some(16);
+
+    let response = client.responses().create(request).await.unwrap();
+
+    assert_eq!(response.status, Status::Incomplete);
+    assert_eq!(response.incomplete_details.unwrap().reason, "max_output_tokens");
+    assert!(response.usage.unwrap().output_tokens <= 16);
+});
+
+teenytiny_test!(async fn test_unsupported_tools_are_rejected(client) {
+    let mut request = text_request("echo", "Search the web");
+    request.tools = Some(vec![ToolDefinition::WebSearchPreview(Default::default())]);
+
+    match client.responses().create(request).await {
+        Err(OpenAIError::ApiError(err)) => assert_eq!(err.param.as_deref(), Some("tools")),
+        other => panic!("Expected an API error, got {:?}", other),
+    }
+});
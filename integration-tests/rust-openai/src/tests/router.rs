@@ -0,0 +1,81 @@
+// A router model hands each message to a canned reply or another model,
+// picked by the first of its regexes the last user message matches. Routers
+// are set in the server's config file, so these tests start a server of
+// their own with one, and are skipped when it can't be started.
+
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+
+use super::own_server;
+use crate::server::TestServer;
+
+const CONFIG: &str = r#"
+[[routers.agent.routes]]
+match = "weather in (\\w+)"
+flags = "i"
+reply = "It's sunny in $1."
+
+[[routers.agent.routes]]
+match = "weather"
+reply = "It's sunny."
+
+[[routers.agent.routes]]
+match = "^explain"
+model = "template"
+
+[[routers.strict.routes]]
+match = "^yes$"
+reply = "Agreed."
+
+[routers.strict]
+fallback = "eliza"
+"#;
+
+async fn router_server() -> Option<TestServer> {
+    let file = std::env::temp_dir().join(format!("teenytiny-router-{}.toml", std::process::id()));
+    std::fs::write(&file, CONFIG).unwrap();
+    own_server(&["--config", &file.to_string_lossy()]).await
+}
+
+async fn chat(server: &TestServer, model: &str, message: &str) -> (StatusCode, Value) {
+    let response = crate::http_client()
+        .post(format!("{}/v1/chat/completions", server.url()))
+        .bearer_auth(server.api_key())
+        .json(&json!({"model": model, "messages": [{"role": "user", "content": message}]}))
+        .send()
+        .await
+        .unwrap();
+    (response.status(), response.json().await.unwrap())
+}
+
+async fn reply(server: &TestServer, model: &str, message: &str) -> String {
+    let (status, body) = chat(server, model, message).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["model"], model);
+    body["choices"][0]["message"]["content"].as_str().unwrap().to_string()
+}
+
+teenytiny_test!(async fn test_router_takes_the_first_matching_route() {
+    let Some(server) = router_server().await else { return };
+
+    assert_eq!(reply(&server, "router:agent", "What's the Weather in Oslo?").await, "It's sunny in Oslo.");
+    assert_eq!(reply(&server, "router:agent", "Any weather today?").await, "It's sunny.");
+    // Both the weather and explain routes match, and weather comes first
+    assert_eq!(reply(&server, "router:agent", "explain the weather").await, "It's sunny.");
+    assert_eq!(
+        reply(&server, "router:agent", "explain routing").await,
+        r#"You said "explain routing" in a conversation of 1 messages with router:agent."#
+    );
+});
+
+teenytiny_test!(async fn test_router_falls_through_to_its_fallback() {
+    let Some(server) = router_server().await else { return };
+
+    assert_eq!(reply(&server, "router:agent", "Hello there").await, "Hello there");
+    assert_eq!(reply(&server, "router:strict", "yes").await, "Agreed.");
+    let eliza = reply(&server, "router:strict", "yes please").await;
+    assert_ne!(eliza, "yes please", "router:strict should fall through to eliza, not echo");
+
+    let (status, body) = chat(&server, "router:missing", "Hello").await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "{}", body);
+});
@@ -0,0 +1,122 @@
+// A failure scenario scripts the outcome of each chat completion a key or a
+// session sends in turn, such as 429, 429, 500, then success, so a client's
+// retries can be tested against an exact sequence. Scenarios are replaced
+// server-wide with PUT /admin/scenarios, so the test adds its own alongside
+// any there are, for a fresh key and a session no other test uses, and puts
+// the previous ones back afterwards.
+
+use std::time::Instant;
+
+use async_openai::config::OpenAIConfig;
+use async_openai::types::CreateChatCompletionRequestArgs;
+use async_openai::Client;
+use reqwest::{Method, StatusCode};
+use serde_json::json;
+
+use crate::base_url;
+use crate::raw::{self, assert_error, assert_error_envelope, RawResponse};
+use super::{admin, new_api_key, skip, user_message};
+
+const SESSION_HEADER: &str = "x-teenytiny-session";
+
+async fn chat_in(session: &str, stream: bool) -> RawResponse {
+    raw::send(raw::request(Method::POST, "/v1/chat/completions").header(SESSION_HEADER, session).json(&json!({
+        "model": "echo", "stream": stream, "messages": [{"role": "user", "content": "one two three four five"}]
+    }))).await
+}
+
+// async-openai retries rate limits with exponential backoff, so one call
+// plays the whole scenario
+async fn retry_through(key: &str) {
+    let config = OpenAIConfig::new().with_api_key(key).with_api_base(format!("{}/v1", base_url()));
+    let client = Client::with_config(config).with_http_client(crate::http_client());
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("echo")
+        .messages([user_message("Hello after retries")])
+        .build().unwrap();
+
+    let started = Instant::now();
+    let response = client.chat().create(request).await.expect("The client should retry until the scenario lets it through");
+    assert_eq!(response.choices[0].message.content.as_deref(), Some("Hello after retries"));
+    // Three retries of a backoff starting at 500ms, less its randomization,
+    // take over a second
+    assert!(started.elapsed().as_millis() >= 1000, "Retried after only {:?}", started.elapsed());
+}
+
+async fn play_session(session: &str) {
+    let limited = chat_in(session, false).await;
+    assert_error(&limited, StatusCode::TOO_MANY_REQUESTS, "rate_limit_error");
+    assert_eq!(limited.json()["error"]["code"], "rate_limit_exceeded");
+    assert_eq!(limited.header("retry-after"), Some("2"));
+
+    let bad_gateway = chat_in(session, false).await;
+    assert_error(&bad_gateway, StatusCode::BAD_GATEWAY, "api_error");
+    assert_eq!(bad_gateway.header("retry-after"), None);
+
+    // A fault that happens once the stream has started
+    let streamed = chat_in(session, true).await;
+    assert_eq!(streamed.status, StatusCode::OK);
+    assert!(streamed.text().contains("\"error\""), "Expected an error event: {}", streamed.text());
+    assert!(!streamed.text().contains("[DONE]"), "A failed stream shouldn't finish: {}", streamed.text());
+
+    // Then the scenario is played out, and requests go through as usual
+    for _ in 0..2 {
+        let ok = chat_in(session, false).await;
+        assert_eq!(ok.status, StatusCode::OK, "{}", ok.text());
+        assert_eq!(ok.json()["choices"][0]["message"]["content"], "one two three four five");
+    }
+}
+
+teenytiny_test!(async fn test_scenarios_script_a_retry_sequence() {
+    let (status, body) = admin(Method::GET, "/scenarios", None).await;
+    if status == StatusCode::FORBIDDEN {
+        skip("TEENYTINY_API_KEY is not the server's key");
+        return;
+    }
+    let previous = body;
+
+    let key = new_api_key().await;
+    let session = format!("rust-{}", std::process::id());
+    let by_key = format!("rust-key-{}", std::process::id());
+    let by_session = format!("rust-session-{}", std::process::id());
+    let mut scenarios = previous.clone();
+    scenarios[&by_key] = json!({"key": key, "steps": ["429", "429", "429", "ok"]});
+    scenarios[&by_session] = json!({
+        "session": session, "steps": [{"outcome": "429", "retry_after": 2}, "502", "error_event", "ok"]
+    });
+    let (status, body) = admin(Method::PUT, "/scenarios", Some(scenarios)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    // Put the previous scenarios back before any assertion can fail
+    let outcome = tokio::spawn(async move {
+        retry_through(&key).await;
+        play_session(&session).await;
+
+        let played = admin(Method::GET, "/scenarios", None).await.1;
+        assert_eq!(played[&by_key]["played"], 4, "{}", played);
+        assert_eq!(played[&by_session]["played"], 4, "{}", played);
+    })
+    .await;
+    admin(Method::PUT, "/scenarios", Some(previous)).await;
+    if let Err(error) = outcome {
+        std::panic::resume_unwind(error.into_panic());
+    }
+});
+
+teenytiny_test!(async fn test_invalid_scenarios_are_rejected() {
+    for scenarios in [
+        json!([]),
+        json!({"bad": {"key": "k", "steps": []}}),
+        json!({"bad": {"steps": ["429"]}}),
+        json!({"bad": {"key": "k", "steps": ["418"]}}),
+        json!({"bad": {"session": "s", "steps": [{"outcome": "429", "retry_after": "soon"}]}}),
+    ] {
+        let (status, body) = admin(Method::PUT, "/scenarios", Some(scenarios)).await;
+        if status == StatusCode::FORBIDDEN {
+            skip("TEENYTINY_API_KEY is not the server's key");
+            return;
+        }
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+        assert_eq!(assert_error_envelope(&body).kind, "invalid_request_error");
+    }
+});
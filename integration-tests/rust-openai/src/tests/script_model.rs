@@ -0,0 +1,82 @@
+// Scripted models are only registered when the server is started with
+// --scripts, so these tests skip unless script:example is listed. They expect
+// the example script in service/scripts.
+
+use async_openai::types::{CreateChatCompletionRequestArgs, FinishReason};
+use futures::StreamExt;
+
+use crate::setup_client;
+use super::{system_message, user_message};
+
+async fn script_model_available() -> bool {
+    let models = setup_client().models().list().await.unwrap();
+    let available = models.data.iter().any(|m| m.id == "script:example");
+    if !available {
+        eprintln!("Skipping: the server was not started with --scripts");
+    }
+    available
+}
+
+teenytiny_test!(async fn test_script_content() {
+    if !script_model_available().await { return; }
+
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("script:example")
+        .messages([user_message("Hello")])
+        .build().unwrap();
+
+    let response = setup_client().chat().create(request).await.unwrap();
+
+    assert_eq!(response.model, "script:example");
+    assert_eq!(response.choices[0].message.content.as_deref(), Some("Script received: Hello"));
+    assert_eq!(response.choices[0].finish_reason, Some(FinishReason::Stop));
+});
+
+teenytiny_test!(async fn test_script_sees_whole_conversation() {
+    if !script_model_available().await { return; }
+
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("script:example")
+        .messages([system_message("Be brief"), user_message("Hi"), user_message("summarize")])
+        .build().unwrap();
+
+    let response = setup_client().chat().create(request).await.unwrap();
+
+    assert_eq!(
+        response.choices[0].message.content.as_deref(),
+        Some("This conversation has 3 messages, 2 from the user.")
+    );
+});
+
+teenytiny_test!(async fn test_script_chunks_when_streaming() {
+    if !script_model_available().await { return; }
+
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("script:example")
+        .messages([user_message("count to 5")])
+        .stream(true)
+        .build().unwrap();
+
+    let mut stream = setup_client().chat().create_stream(request).await.unwrap();
+    let mut chunks = Vec::new();
+    while let Some(result) = stream.next().await {
+        if let Some(content) = result.unwrap().choices.first().and_then(|c| c.delta.content.clone()) {
+            chunks.push(content);
+        }
+    }
+
+    assert_eq!(chunks, ["1", " 2", " 3", " 4", " 5"]);
+});
+
+teenytiny_test!(async fn test_script_finish_reason() {
+    if !script_model_available().await { return; }
+
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("script:example")
+        .messages([user_message("Something forbidden")])
+        .build().unwrap();
+
+    let response = setup_client().chat().create(request).await.unwrap();
+
+    assert_eq!(response.choices[0].finish_reason, Some(FinishReason::ContentFilter));
+});
@@ -0,0 +1,89 @@
+// service_tier names the tier a request should be served on, and responses
+// report the tier that served it. 'auto' is served on 'default'.
+
+use async_openai::types::{CreateChatCompletionRequestArgs, ServiceTier, ServiceTierResponse};
+use futures::StreamExt;
+use reqwest::{Method, StatusCode};
+use serde_json::json;
+
+use crate::raw::{self, assert_error};
+use crate::setup_client;
+use super::{post_chat_completion, user_message};
+
+async fn served_tier(tier: ServiceTier) -> Option<ServiceTierResponse> {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("echo")
+        .messages([user_message("Which tier?")])
+        .service_tier(tier)
+        .build().unwrap();
+
+    let response = setup_client().chat().create(request).await.unwrap();
+    assert_eq!(response.choices[0].message.content.as_deref(), Some("Which tier?"));
+    response.service_tier
+}
+
+teenytiny_test!(async fn test_default_tier_round_trips() {
+    assert_eq!(served_tier(ServiceTier::Default).await, Some(ServiceTierResponse::Default));
+});
+
+teenytiny_test!(async fn test_auto_is_served_on_default() {
+    assert_eq!(served_tier(ServiceTier::Auto).await, Some(ServiceTierResponse::Default));
+});
+
+teenytiny_test!(async fn test_flex_and_priority_tiers_round_trip() {
+    // Newer than the SDK's tiers, so checked raw
+    for tier in ["flex", "priority"] {
+        let (status, body) = post_chat_completion(json!({
+            "model": "echo",
+            "service_tier": tier,
+            "messages": [{"role": "user", "content": "Which tier?"}],
+        }))
+        .await;
+
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["service_tier"], tier);
+    }
+});
+
+teenytiny_test!(async fn test_no_service_tier_without_one_requested() {
+    let (status, body) = post_chat_completion(json!({
+        "model": "echo",
+        "messages": [{"role": "user", "content": "Any tier"}],
+    }))
+    .await;
+
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert!(body.get("service_tier").is_none(), "Unexpected service_tier: {}", body);
+});
+
+teenytiny_test!(async fn test_streamed_chunks_report_service_tier() {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("echo")
+        .messages([user_message("Stream it")])
+        .service_tier(ServiceTier::Default)
+        .stream(true)
+        .build().unwrap();
+
+    let mut stream = setup_client().chat().create_stream(request).await.unwrap();
+    let mut chunks = 0;
+    while let Some(result) = stream.next().await {
+        let chunk = result.unwrap();
+        assert_eq!(chunk.service_tier, Some(ServiceTierResponse::Default));
+        chunks += 1;
+    }
+
+    assert!(chunks > 0, "No chunks streamed");
+});
+
+teenytiny_test!(async fn test_invalid_service_tier_is_rejected() {
+    let response = raw::send(raw::request(Method::POST, "/v1/chat/completions").json(&json!({
+        "model": "echo",
+        "service_tier": "turbo",
+        "messages": [{"role": "user", "content": "Hello"}],
+    })))
+    .await;
+
+    let error = assert_error(&response, StatusCode::BAD_REQUEST, "invalid_request_error");
+    assert_eq!(error.param.as_deref(), Some("service_tier"));
+    assert!(error.message.contains("turbo"), "Unexpected message: {}", error.message);
+});
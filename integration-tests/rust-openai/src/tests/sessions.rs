@@ -0,0 +1,111 @@
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{api_key, base_url};
+use super::own_server_with_env;
+
+// Session ids must be unique per run since the server keeps history between runs
+fn unique_session_id(name: &str) -> String {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+    format!("{}-{}", name, nanos)
+}
+
+async fn say(session_id: &str, body: Value) -> (StatusCode, Value) {
+    say_to(&base_url(), &api_key(), session_id, body).await
+}
+
+async fn say_to(url: &str, key: &str, session_id: &str, body: Value) -> (StatusCode, Value) {
+    let response = crate::http_client()
+        .post(format!("{}/session/{}/say", url, session_id))
+        .bearer_auth(key)
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+
+    let status = response.status();
+    (status, response.json().await.unwrap())
+}
+
+teenytiny_test!(async fn test_session_reply() {
+    let session_id = unique_session_id("reply");
+
+    let (status, body) = say(&session_id, json!({"message": "Hello session"})).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["session_id"], session_id);
+    assert_eq!(body["model"], "echo");
+    assert_eq!(body["reply"], "Hello session");
+});
+
+teenytiny_test!(async fn test_session_history_accumulates() {
+    let session_id = unique_session_id("history");
+
+    say(&session_id, json!({"message": "First"})).await;
+    say(&session_id, json!({"message": "Second"})).await;
+    let (status, body) = say(&session_id, json!({"message": "Third"})).await;
+
+    assert_eq!(status, StatusCode::OK);
+
+    let messages = body["messages"].as_array().expect("messages should be an array");
+    let roles: Vec<&str> = messages.iter().map(|m| m["role"].as_str().unwrap()).collect();
+    assert_eq!(roles, ["user", "assistant", "user", "assistant", "user", "assistant"]);
+    assert_eq!(messages[0]["content"], "First");
+    assert_eq!(messages[4]["content"], "Third");
+});
+
+teenytiny_test!(async fn test_sessions_are_isolated() {
+    let first = unique_session_id("isolated-a");
+    let second = unique_session_id("isolated-b");
+
+    say(&first, json!({"message": "Only in first"})).await;
+    let (_, body) = say(&second, json!({"message": "Only in second"})).await;
+
+    assert_eq!(body["messages"].as_array().unwrap().len(), 2);
+});
+
+// Idle sessions are forgotten after TEENYTINY_SESSION_TTL_MS, so this starts a
+// server of its own where that is short
+teenytiny_test!(async fn test_session_expires_when_idle() {
+    let Some(server) = own_server_with_env(&[], &[("TEENYTINY_SESSION_TTL_MS", "500")]).await else { return };
+    let session_id = unique_session_id("idle");
+
+    say_to(server.url(), server.api_key(), &session_id, json!({"message": "Before"})).await;
+    let (_, kept) = say_to(server.url(), server.api_key(), &session_id, json!({"message": "Soon after"})).await;
+    assert_eq!(kept["messages"].as_array().unwrap().len(), 4, "{}", kept);
+
+    tokio::time::sleep(std::time::Duration::from_millis(800)).await;
+    let (status, body) = say_to(server.url(), server.api_key(), &session_id, json!({"message": "After"})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["messages"].as_array().unwrap().len(), 2, "{}", body);
+    assert_eq!(body["messages"][0]["content"], "After");
+});
+
+teenytiny_test!(async fn test_session_with_other_model() {
+    let session_id = unique_session_id("eliza");
+
+    let (status, body) = say(&session_id, json!({"message": "I feel sad", "model": "eliza"})).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["model"], "eliza");
+    assert!(!body["reply"].as_str().unwrap().is_empty(), "Expected a reply from eliza");
+});
+
+teenytiny_test!(async fn test_session_missing_message() {
+    let (status, body) = say(&unique_session_id("invalid"), json!({"text": "wrong field"})).await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["param"], "message");
+});
+
+teenytiny_test!(async fn test_session_requires_api_key() {
+    let response = crate::http_client()
+        .post(format!("{}/session/{}/say", base_url(), unique_session_id("unauthorized")))
+        .json(&json!({"message": "Hello"}))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+});
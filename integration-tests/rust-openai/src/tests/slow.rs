@@ -0,0 +1,160 @@
+// The slow model echoes one word per interval, taken from the model name
+// ("slow:200") or from metadata.interval_ms. Timing assertions allow generous
+// tolerance, since network and scheduling jitter add to every gap.
+
+use std::time::{Duration, Instant};
+
+use async_openai::types::CreateChatCompletionRequestArgs;
+use futures::StreamExt;
+use reqwest::StatusCode;
+use serde_json::json;
+
+use crate::{api_key, base_url, setup_client};
+use super::{post_chat_completion, user_message};
+
+const TOLERANCE: Duration = Duration::from_millis(100);
+
+// Streams the message, returning each content delta with the time it arrived
+async fn timed_deltas(model: &str, message: &str) -> Vec<(String, Instant)> {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model(model)
+        .messages([user_message(message)])
+        .stream(true)
+        .build().unwrap();
+
+    let mut stream = setup_client().chat().create_stream(request).await.unwrap();
+
+    let mut deltas = Vec::new();
+    while let Some(result) = stream.next().await {
+        if let Some(content) = result.unwrap().choices.first().and_then(|c| c.delta.content.clone()) {
+            deltas.push((content, Instant::now()));
+        }
+    }
+    deltas
+}
+
+fn assert_gaps_near(deltas: &[(String, Instant)], interval: Duration) {
+    for pair in deltas.windows(2) {
+        let gap = pair[1].1.duration_since(pair[0].1);
+        assert!(
+            gap + TOLERANCE >= interval && gap <= interval + TOLERANCE,
+            "Gap of {:?} between {:?} and {:?} is outside {:?} ± {:?}",
+            gap, pair[0].0, pair[1].0, interval, TOLERANCE
+        );
+    }
+}
+
+teenytiny_test!(async fn test_streams_one_word_per_chunk() {
+    let deltas = timed_deltas("slow:10", "one two three four").await;
+
+    let words: Vec<&str> = deltas.iter().map(|(content, _)| content.as_str()).collect();
+    assert_eq!(words, ["one", " two", " three", " four"]);
+});
+
+teenytiny_test!(async fn test_inter_chunk_gaps_from_model_suffix() {
+    let deltas = timed_deltas("slow:200", "tick tock tick tock tick").await;
+
+    assert_eq!(deltas.len(), 5);
+    assert_gaps_near(&deltas, Duration::from_millis(200));
+});
+
+teenytiny_test!(async fn test_default_interval() {
+    let deltas = timed_deltas("slow", "one two three").await;
+
+    assert_gaps_near(&deltas, Duration::from_millis(100));
+});
+
+teenytiny_test!(async fn test_reports_requested_model_name() {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("slow:20")
+        .messages([user_message("Named slowly")])
+        .build().unwrap();
+
+    let start = Instant::now();
+    let response = setup_client().chat().create(request).await.unwrap();
+
+    assert_eq!(response.model, "slow:20");
+    assert_eq!(response.choices[0].message.content.as_deref(), Some("Named slowly"));
+    assert!(start.elapsed() >= Duration::from_millis(40), "Two words should take at least 40ms");
+});
+
+teenytiny_test!(async fn test_metadata_interval_takes_precedence() {
+    let client = crate::http_client();
+
+    // slow:5000 would take 15 seconds for three words; metadata brings that down
+    let start = Instant::now();
+    let response = client
+        .post(format!("{}/v1/chat/completions", base_url()))
+        .bearer_auth(api_key())
+        .json(&json!({
+            "model": "slow:5000",
+            "messages": [{"role": "user", "content": "fast after all"}],
+            "metadata": {"interval_ms": "10"},
+        }))
+        .timeout(Duration::from_secs(5))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["choices"][0]["message"]["content"], "fast after all");
+    assert!(start.elapsed() < Duration::from_secs(5));
+});
+
+teenytiny_test!(async fn test_invalid_interval_is_unknown_model() {
+    for model in ["slow:abc", "slow:-1", "slow:999999", "slow:"] {
+        let (status, body) = post_chat_completion(json!({
+            "model": model,
+            "messages": [{"role": "user", "content": "Hello"}],
+        })).await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND, "Expected {} to be rejected", model);
+        assert_eq!(body["error"]["code"], "model_not_found");
+    }
+});
+
+teenytiny_test!(async fn test_client_timeout_fires() {
+    let client = crate::http_client_builder()
+        .timeout(Duration::from_millis(300))
+        .build()
+        .unwrap();
+
+    let result = client
+        .post(format!("{}/v1/chat/completions", base_url()))
+        .bearer_auth(api_key())
+        .json(&json!({
+            "model": "slow:2000",
+            "messages": [{"role": "user", "content": "never in time"}],
+        }))
+        .send()
+        .await;
+
+    let error = result.expect_err("Expected the request to time out");
+    assert!(error.is_timeout(), "Expected a timeout, got: {}", error);
+});
+
+teenytiny_test!(async fn test_dropping_stream_stops_early() {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("slow:100")
+        .messages([user_message("a b c d e f g h i j k l m n o p q r s t")])
+        .stream(true)
+        .build().unwrap();
+
+    let start = Instant::now();
+    let mut stream = setup_client().chat().create_stream(request).await.unwrap();
+
+    let mut received = 0;
+    while let Some(result) = stream.next().await {
+        if result.unwrap().choices.first().and_then(|c| c.delta.content.as_ref()).is_some() {
+            received += 1;
+            if received == 2 {
+                break;
+            }
+        }
+    }
+    drop(stream);
+
+    // The full message would take two seconds
+    assert!(start.elapsed() < Duration::from_secs(1), "Took {:?} to cancel", start.elapsed());
+});
@@ -1,13 +1,10 @@
 use async_openai::types::{CreateChatCompletionRequestArgs, FinishReason};
 use futures::StreamExt;
 
-use crate::setup_client;
+use crate::fixtures::{unicode_corpus, Conversation, Language};
 use super::user_message;
 
-#[tokio::test]
-async fn test_basic_streaming_completion() {
-    let client = setup_client();
-
+teenytiny_test!(async fn test_basic_streaming_completion(client) {
     let request = CreateChatCompletionRequestArgs::default()
         .model("echo")
         .messages([user_message("Hello World")])
@@ -29,16 +26,14 @@ async fn test_basic_streaming_completion() {
 
     let full_content = content_parts.join("");
     assert_eq!(full_content, "Hello World");
-}
+});
 
-#[tokio::test]
-async fn test_streaming_content_reconstruction() {
-    let client = setup_client();
-    let multiline_message = "Line 1\nLine 2\nLine 3 with more content\nFinal line";
+teenytiny_test!(async fn test_streaming_content_reconstruction(client) {
+    let conversation = Conversation::new().turns(4).words(50..=200).language(Language::Mixed);
 
     let request = CreateChatCompletionRequestArgs::default()
         .model("echo")
-        .messages([user_message(multiline_message)])
+        .messages(conversation.messages())
         .stream(true)
         .build().unwrap();
 
@@ -56,12 +51,10 @@ async fn test_streaming_content_reconstruction() {
     }
 
     let reconstructed_content = content_parts.join("");
-    assert_eq!(reconstructed_content, multiline_message);
-}
+    assert_eq!(reconstructed_content, conversation.last_user_message());
+});
 
-#[tokio::test]
-async fn test_streaming_with_multiline_content() {
-    let client = setup_client();
+teenytiny_test!(async fn test_streaming_with_multiline_content(client) {
     let test_content = "First line\nSecond line\nThird line";
 
     let request = CreateChatCompletionRequestArgs::default()
@@ -84,16 +77,14 @@ async fn test_streaming_with_multiline_content() {
     }
 
     assert_eq!(received_content, test_content);
-}
+});
 
-#[tokio::test]
-async fn test_streaming_with_special_characters() {
-    let client = setup_client();
-    let special_chars = "Hello! 🌟 Special chars: @#$%^&*()_+-={}[]|\\:;\"'<>?,./ 中文";
+teenytiny_test!(async fn test_streaming_unicode_corpus(client) {
+    let corpus = unicode_corpus(20_000, 2);
 
     let request = CreateChatCompletionRequestArgs::default()
         .model("echo")
-        .messages([user_message(special_chars)])
+        .messages([user_message(&corpus)])
         .stream(true)
         .build().unwrap();
 
@@ -102,7 +93,7 @@ async fn test_streaming_with_special_characters() {
     let mut received_content = String::new();
     while let Some(result) = stream.next().await {
         let chunk = result.unwrap();
-        
+
         if let Some(choice) = chunk.choices.first() {
             if let Some(content) = &choice.delta.content {
                 received_content.push_str(content);
@@ -110,13 +101,11 @@ async fn test_streaming_with_special_characters() {
         }
     }
 
-    assert_eq!(received_content, special_chars);
-}
-
-#[tokio::test]
-async fn test_streaming_response_structure() {
-    let client = setup_client();
+    // Chunk boundaries must not split characters or swallow whitespace
+    assert_eq!(received_content, corpus);
+});
 
+teenytiny_test!(async fn test_streaming_response_structure(client) {
     let request = CreateChatCompletionRequestArgs::default()
         .model("echo")
         .messages([user_message("Structure test")])
@@ -149,4 +138,4 @@ async fn test_streaming_response_structure() {
 
     assert!(chunk_count > 0, "Should receive at least one chunk");
     assert!(has_finish_reason, "Should receive finish_reason in final chunk");
-}
\ No newline at end of file
+});
\ No newline at end of file
@@ -0,0 +1,233 @@
+// End-to-end tests for the first-party client in clients/rust, which speaks
+// to the server without async-openai and adds directives and the admin API.
+
+use std::time::{Duration, Instant};
+
+use futures::StreamExt;
+use serde_json::json;
+use teenytiny_client::{
+    ChatCompletionChunk, ChatCompletionRequest, Client, Directive, Error, ErrorKind, Fault, FinishReason, Message,
+    Tool,
+};
+
+use crate::{api_key, base_url};
+use super::new_api_key;
+
+fn client_for(key: &str) -> Client {
+    Client::builder()
+        .base_url(base_url())
+        .api_key(key)
+        .http_client(crate::http_client())
+        .build()
+}
+
+fn echo(message: &str) -> teenytiny_client::ChatCompletionRequestBuilder {
+    ChatCompletionRequest::builder("echo").message(Message::user(message))
+}
+
+async fn collect(client: &Client, request: &ChatCompletionRequest) -> Vec<Result<ChatCompletionChunk, Error>> {
+    let stream = client.chat_completion_stream(request).await.unwrap();
+    stream.collect().await
+}
+
+teenytiny_test!(async fn test_chat_completion() {
+    let client = client_for(&api_key());
+
+    let completion = client.chat_completion(&echo("Hello from the client").build()).await.unwrap();
+
+    assert_eq!(completion.object, "chat.completion");
+    assert_eq!(completion.content(), Some("Hello from the client"));
+    assert_eq!(completion.choices[0].finish_reason, Some(FinishReason::Stop));
+    assert_eq!(completion.usage.total_tokens, completion.usage.prompt_tokens + completion.usage.completion_tokens);
+});
+
+teenytiny_test!(async fn test_stream_reassembles_content() {
+    let client = client_for(&api_key());
+    let request = echo("One two three four").directive(Directive::Chunks(4)).build();
+
+    let chunks: Vec<ChatCompletionChunk> = collect(&client, &request).await.into_iter().map(Result::unwrap).collect();
+
+    let deltas: Vec<&str> = chunks.iter()
+        .filter_map(|chunk| chunk.choices.first()?.delta.content.as_deref())
+        .filter(|content| !content.is_empty())
+        .collect();
+    assert_eq!(deltas.len(), 4);
+    assert_eq!(deltas.concat(), "One two three four");
+
+    let finish_reason = chunks.iter().find_map(|chunk| chunk.choices.first()?.finish_reason);
+    assert_eq!(finish_reason, Some(FinishReason::Stop));
+});
+
+teenytiny_test!(async fn test_directives_shape_the_reply() {
+    let client = client_for(&api_key());
+    let request = echo("Shaped")
+        .directive(Directive::Finish(FinishReason::Length))
+        .directive(Directive::Tokens(42))
+        .build();
+
+    let completion = client.chat_completion(&request).await.unwrap();
+
+    assert_eq!(completion.content(), Some("Shaped"));
+    assert_eq!(completion.choices[0].finish_reason, Some(FinishReason::Length));
+    assert_eq!(completion.usage.completion_tokens, 42);
+});
+
+teenytiny_test!(async fn test_delay_directive() {
+    let client = client_for(&api_key());
+    let request = echo("Wait").directive(Directive::Delay(Duration::from_millis(300))).build();
+
+    let start = Instant::now();
+    client.chat_completion(&request).await.unwrap();
+
+    assert!(start.elapsed() >= Duration::from_millis(300), "Replied after only {:?}", start.elapsed());
+});
+
+teenytiny_test!(async fn test_error_directive_maps_to_error_kind() {
+    let client = client_for(&api_key());
+
+    for (status, kind) in [(429, ErrorKind::RateLimitError), (503, ErrorKind::OverloadedError)] {
+        let error = client.chat_completion(&echo("Fail").directive(Directive::Error(status)).build()).await.unwrap_err();
+
+        assert_eq!(error.status(), Some(status));
+        assert_eq!(error.api_error().map(|error| error.kind), Some(kind));
+    }
+});
+
+teenytiny_test!(async fn test_error_before_stream_starts() {
+    let client = client_for(&api_key());
+    let request = echo("Fail").directive(Directive::Error(502)).build();
+
+    let error = client.chat_completion_stream(&request).await.err().expect("Expected the stream to fail");
+
+    assert!(matches!(error, Error::Api { status: 502, .. }), "Unexpected error: {:?}", error);
+});
+
+teenytiny_test!(async fn test_unknown_model_error() {
+    let client = client_for(&api_key());
+    let request = ChatCompletionRequest::builder("no-such-model").message(Message::user("Hi")).build();
+
+    let error = client.chat_completion(&request).await.unwrap_err();
+
+    assert_eq!(error.status(), Some(404));
+    assert_eq!(error.code(), Some("model_not_found"));
+    assert_eq!(error.api_error().unwrap().param.as_deref(), Some("model"));
+});
+
+teenytiny_test!(async fn test_malformed_frame_is_a_decode_error() {
+    let client = client_for(&api_key());
+    let request = ChatCompletionRequest::builder("flaky")
+        .message(Message::user("Hello there"))
+        .directive(Directive::Fault(Fault::Malformed))
+        .build();
+
+    let results = collect(&client, &request).await;
+
+    let decode_errors = results.iter().filter(|result| matches!(result, Err(Error::Decode(_)))).count();
+    assert_eq!(decode_errors, 1);
+    assert!(results.last().unwrap().is_ok(), "The stream should carry on after a malformed frame");
+});
+
+teenytiny_test!(async fn test_error_event_is_a_typed_stream_error() {
+    let client = client_for(&api_key());
+    let request = ChatCompletionRequest::builder("flaky")
+        .message(Message::user("Hello there"))
+        .directive(Directive::Fault(Fault::ErrorEvent))
+        .build();
+
+    let results = collect(&client, &request).await;
+
+    let (last, chunks) = results.split_last().unwrap();
+    assert!(!chunks.is_empty() && chunks.iter().all(Result::is_ok), "Expected chunks before the error");
+    match last {
+        Err(Error::Stream(error)) => {
+            assert_eq!(error.kind, ErrorKind::ApiError);
+            assert_eq!(error.code.as_deref(), Some("stream_error"));
+        }
+        other => panic!("Expected the stream to end with its error event, got {:?}", other),
+    }
+});
+
+teenytiny_test!(async fn test_tool_round_trip() {
+    let client = client_for(&api_key());
+    let weather = Tool::function(
+        "get_weather",
+        "Current weather for a city",
+        json!({"type": "object", "properties": {"city": {"type": "string"}}, "required": ["city"]}),
+    );
+    let question = Message::user("What's the weather?");
+
+    let request = ChatCompletionRequest::builder("tooluse").message(question.clone()).tool(weather.clone()).build();
+    let completion = client.chat_completion(&request).await.unwrap();
+    assert_eq!(completion.choices[0].finish_reason, Some(FinishReason::ToolCalls));
+    let reply = completion.choices[0].message.clone();
+    let call = reply.tool_calls.as_ref().expect("No tool calls")[0].clone();
+    assert_eq!(call.function.name, "get_weather");
+
+    let request = ChatCompletionRequest::builder("tooluse")
+        .messages([question, reply, Message::tool(call.id, "Sunny, 21C")])
+        .tool(weather)
+        .build();
+    let completion = client.chat_completion(&request).await.unwrap();
+
+    assert_eq!(completion.choices[0].finish_reason, Some(FinishReason::Stop));
+    assert!(completion.content().unwrap().contains("Sunny, 21C"));
+});
+
+teenytiny_test!(async fn test_models() {
+    let models = client_for(&api_key()).models().await.unwrap();
+
+    assert!(models.iter().any(|model| model.id == "echo"));
+});
+
+teenytiny_test!(async fn test_admin_keys() {
+    let admin = client_for(&api_key());
+
+    let created = admin.admin().create_key(Some(&["echo"])).await.unwrap();
+    assert_eq!(created.models, Some(vec!["echo".to_string()]));
+    let scoped = client_for(&created.key);
+    scoped.chat_completion(&echo("Allowed").build()).await.unwrap();
+    let error = scoped.chat_completion(&ChatCompletionRequest::builder("eliza").message(Message::user("Hi")).build())
+        .await
+        .unwrap_err();
+    assert_eq!(error.status(), Some(403));
+
+    admin.admin().revoke_key(&created.key).await.unwrap();
+    let error = scoped.chat_completion(&echo("Revoked").build()).await.unwrap_err();
+    assert_eq!(error.api_error().map(|error| error.kind), Some(ErrorKind::AuthenticationError));
+});
+
+// Writes back the settings already in place, so tests running alongside are undisturbed
+teenytiny_test!(async fn test_admin_settings_round_trip() {
+    let admin = client_for(&api_key());
+
+    let limit = admin.admin().rate_limit().await.unwrap();
+    assert_eq!(admin.admin().set_rate_limit(limit).await.unwrap(), limit);
+
+    let faults = admin.admin().faults().await.unwrap();
+    assert_eq!(admin.admin().set_faults(&faults).await.unwrap(), faults);
+
+    let models = admin.admin().models().await.unwrap();
+    assert!(models["models"].is_array());
+});
+
+teenytiny_test!(async fn test_admin_requests_and_usage() {
+    let key = new_api_key().await;
+    let client = client_for(&key);
+    client.chat_completion(&echo("Logged").build()).await.unwrap();
+
+    let requests = client.admin().requests(1).await.unwrap();
+    assert_eq!(requests[0].path, "/v1/chat/completions");
+    assert_eq!(requests[0].model.as_deref(), Some("echo"));
+    assert_eq!(requests[0].api_key, key);
+
+    client_for(&api_key()).admin().reset_usage(Some(&key)).await.unwrap();
+});
+
+teenytiny_test!(async fn test_admin_requires_server_key() {
+    let key = new_api_key().await;
+
+    let error = client_for(&key).admin().faults().await.unwrap_err();
+
+    assert_eq!(error.status(), Some(403));
+    assert_eq!(error.code(), Some("admin_required"));
+});
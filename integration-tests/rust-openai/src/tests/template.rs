@@ -0,0 +1,112 @@
+// The template model replies with a template whose {{variables}} are filled in
+// from the request. Templates are set server-wide with PUT /admin/templates,
+// which replaces them all, so the test adds one under a name no other test
+// uses, checks each variable, then puts the previous templates back.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use reqwest::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use crate::raw::{self, assert_error, assert_error_envelope};
+use super::{admin, skip};
+
+const VARIABLES: &[&str] = &[
+    "last_user_message", "message_count", "model", "temperature", "top_p", "max_tokens", "seed", "n", "user", "now",
+    "timestamp",
+];
+
+async fn chat(body: Value) -> Value {
+    let response = raw::send(raw::request(Method::POST, "/v1/chat/completions").json(&body)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    response.json()
+}
+
+// Each variable's value, from a reply to a template of all of them joined by |
+fn values(reply: &Value) -> Vec<String> {
+    reply.as_str().unwrap().split('|').map(String::from).collect()
+}
+
+async fn check_variables(model: &str) {
+    let body = chat(json!({
+        "model": model,
+        "messages": [
+            {"role": "system", "content": "Be brief"},
+            {"role": "user", "content": "first"},
+            {"role": "assistant", "content": "ok"},
+            {"role": "user", "content": "second"}
+        ],
+        "temperature": 0.5, "top_p": 0.9, "max_tokens": 200, "seed": 7, "n": 2, "user": "ada"
+    })).await;
+    let filled = values(&body["choices"][0]["message"]["content"]);
+    assert_eq!(filled[..9], ["second", "4", model, "0.5", "0.9", "200", "7", "2", "ada"]);
+    assert_eq!(body["choices"][1]["message"]["content"], body["choices"][0]["message"]["content"]);
+
+    // now and timestamp are the server's clock, so only roughly the test's
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let timestamp: u64 = filled[10].parse().expect("timestamp should be Unix seconds");
+    assert!(now.abs_diff(timestamp) < 120, "timestamp {} is far from now ({})", timestamp, now);
+    assert!(filled[9].ends_with('Z') && filled[9].contains('T'), "now isn't ISO 8601: {}", filled[9]);
+
+    // Parameters the request didn't set are empty, streamed too
+    let streamed = raw::send(raw::request(Method::POST, "/v1/chat/completions").json(&json!({
+        "model": model, "stream": true, "messages": [{"role": "user", "content": "bare"}]
+    }))).await.text();
+    let content: String = streamed.lines()
+        .filter_map(|line| line.strip_prefix("data: "))
+        .filter_map(|data| serde_json::from_str::<Value>(data).ok())
+        .filter_map(|chunk| chunk["choices"][0]["delta"]["content"].as_str().map(String::from))
+        .collect();
+    assert_eq!(values(&json!(content))[..9], ["bare", "1", model, "", "", "", "", "", ""]);
+}
+
+teenytiny_test!(async fn test_default_template_reflects_the_request() {
+    let body = chat(json!({
+        "model": "template",
+        "messages": [{"role": "system", "content": "Be brief"}, {"role": "user", "content": "Hello there"}]
+    })).await;
+    assert_eq!(
+        body["choices"][0]["message"]["content"],
+        r#"You said "Hello there" in a conversation of 2 messages with template."#
+    );
+});
+
+teenytiny_test!(async fn test_template_variables_are_filled_in() {
+    let (status, body) = admin(Method::GET, "/templates", None).await;
+    if status == StatusCode::FORBIDDEN {
+        skip("TEENYTINY_API_KEY is not the server's key");
+        return;
+    }
+    let previous = body;
+
+    let name = format!("rust-{}", std::process::id());
+    let template = VARIABLES.iter().map(|variable| format!("{{{{{}}}}}", variable)).collect::<Vec<_>>().join("|");
+    let mut templates = previous.clone();
+    templates[&name] = json!(template);
+    let (status, body) = admin(Method::PUT, "/templates", Some(templates)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    // Put the previous templates back before any assertion can fail
+    let outcome = tokio::spawn(async move { check_variables(&format!("template:{}", name)).await }).await;
+    admin(Method::PUT, "/templates", Some(previous)).await;
+    if let Err(error) = outcome {
+        std::panic::resume_unwind(error.into_panic());
+    }
+});
+
+teenytiny_test!(async fn test_invalid_templates_are_rejected() {
+    for templates in [json!([]), json!({"bad": "{{weather}}"}), json!({"bad": 5})] {
+        let (status, body) = admin(Method::PUT, "/templates", Some(templates)).await;
+        if status == StatusCode::FORBIDDEN {
+            skip("TEENYTINY_API_KEY is not the server's key");
+            return;
+        }
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+        assert_eq!(assert_error_envelope(&body).kind, "invalid_request_error");
+    }
+
+    let response = raw::send(raw::request(Method::POST, "/v1/chat/completions").json(&json!({
+        "model": "template:no-such-template", "messages": [{"role": "user", "content": "Hi"}]
+    }))).await;
+    assert_error(&response, StatusCode::NOT_FOUND, "invalid_request_error");
+});
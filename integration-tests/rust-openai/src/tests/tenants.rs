@@ -0,0 +1,124 @@
+// Keys can belong to a tenant, and a tenant's keys see only their tenant's
+// keys, requests, usage and cassettes, so one server can serve several teams'
+// CI. Changing keys, quotas and usage is left to the server's key. The tenants here are named after this process, so the
+// tests leave the rest of the server as it was.
+
+use reqwest::{Method, StatusCode};
+use serde_json::{json, Value};
+use teenytiny_client::{ApiKey, Client};
+
+use crate::{api_key, base_url};
+
+fn tenant(team: &str) -> String {
+    format!("{}-{}", team, std::process::id())
+}
+
+async fn create(tenant: &str) -> ApiKey {
+    let admin = Client::builder().base_url(base_url()).api_key(api_key()).http_client(crate::http_client()).build();
+    admin.admin().create_tenant_key(tenant, Some(&["echo"])).await.unwrap()
+}
+
+async fn send(key: &str, method: Method, path: &str, body: Option<Value>, tenant: Option<&str>) -> (StatusCode, Value) {
+    let mut request = crate::http_client().request(method, format!("{}{}", base_url(), path)).bearer_auth(key);
+    if let Some(body) = body {
+        request = request.json(&body);
+    }
+    if let Some(tenant) = tenant {
+        request = request.header("x-teenytiny-tenant", tenant);
+    }
+    let response = request.send().await.unwrap();
+    (response.status(), response.json().await.unwrap())
+}
+
+async fn chat(key: &str) {
+    let body = json!({"model": "echo", "messages": [{"role": "user", "content": "Hello tenants"}]});
+    let (status, body) = send(key, Method::POST, "/v1/chat/completions", Some(body), None).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+}
+
+fn keys_in(list: &Value) -> Vec<String> {
+    let mut keys: Vec<String> =
+        list["data"].as_array().unwrap().iter().map(|entry| entry["api_key"].as_str().unwrap().to_string()).collect();
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+teenytiny_test!(async fn test_tenants_see_only_their_own_keys() {
+    let (team_a, team_b) = (tenant("team-a"), tenant("team-b"));
+    let (a1, a2, b1) = (create(&team_a).await, create(&team_a).await, create(&team_b).await);
+    assert_eq!(a1.tenant.as_deref(), Some(team_a.as_str()));
+    for key in [&a1, &a2, &b1] {
+        chat(&key.key).await;
+    }
+    let mut team_a_keys = vec![a1.key.clone(), a2.key.clone()];
+    team_a_keys.sort();
+
+    let (status, requests) = send(&a1.key, Method::GET, "/admin/requests", None, None).await;
+    assert_eq!(status, StatusCode::OK, "{}", requests);
+    assert_eq!(keys_in(&requests), team_a_keys);
+    let (_, usage) = send(&a2.key, Method::GET, "/admin/usage", None, None).await;
+    assert_eq!(keys_in(&usage), team_a_keys);
+    let (_, keys) = send(&b1.key, Method::GET, "/admin/keys", None, None).await;
+    assert_eq!(keys["keys"], json!([{"key": b1.key, "models": ["echo"], "tenant": team_b}]));
+
+    // The server's key can look at one tenant by naming it
+    let (_, requests) = send(&api_key(), Method::GET, "/admin/requests", None, Some(&team_b)).await;
+    assert_eq!(keys_in(&requests), vec![b1.key.clone()]);
+});
+
+teenytiny_test!(async fn test_tenants_cant_reach_each_other() {
+    let (team_a, team_b) = (tenant("team-c"), tenant("team-d"));
+    let (a, b) = (create(&team_a).await, create(&team_b).await);
+
+    let other = &b.key;
+    for path in [format!("/admin/requests?key={}", other), format!("/admin/usage?key={}", other)] {
+        let (status, body) = send(&a.key, Method::GET, &path, None, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "{}: {}", path, body);
+    }
+    let (status, body) = send(&api_key(), Method::DELETE, &format!("/admin/keys/{}", other), None, Some(&team_a)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "{}", body);
+    chat(other).await;
+
+    let (status, body) = send(&a.key, Method::GET, "/admin/requests", None, Some(&team_b)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{}", body);
+    assert_eq!(body["error"]["code"], "tenant_override_not_allowed");
+    let (status, body) = send(&a.key, Method::POST, "/admin/keys", Some(json!({"tenant": team_b})), None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{}", body);
+    let (status, body) = send(&a.key, Method::GET, "/admin/faults", None, None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{}", body);
+    assert_eq!(body["error"]["code"], "admin_required");
+});
+
+teenytiny_test!(async fn test_tenants_cant_change_keys_quotas_or_usage() {
+    let a = create(&tenant("team-g")).await;
+
+    for (method, path, body) in [
+        (Method::POST, "/admin/keys".to_string(), Some(json!({}))),
+        (Method::DELETE, format!("/admin/keys/{}", a.key), None),
+        (Method::PUT, format!("/admin/quotas/{}", a.key), Some(json!({"token_budget": 1000000}))),
+        (Method::POST, "/admin/usage/reset".to_string(), Some(json!({"key": a.key}))),
+    ] {
+        let (status, body) = send(&a.key, method.clone(), &path, body, None).await;
+        assert_eq!(status, StatusCode::FORBIDDEN, "{} {}: {}", method, path, body);
+        assert_eq!(body["error"]["code"], "admin_required");
+    }
+    chat(&a.key).await;
+});
+
+teenytiny_test!(async fn test_cassettes_stay_with_their_tenant() {
+    let (team_a, team_b) = (tenant("team-e"), tenant("team-f"));
+    let (a1, a2, b) = (create(&team_a).await, create(&team_a).await, create(&team_b).await);
+    let name = format!("{}-tape", team_a);
+
+    let (status, body) = send(&a1.key, Method::POST, &format!("/admin/cassettes/{}/record", name), None, None).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    send(&a1.key, Method::POST, "/admin/cassettes/stop", None, None).await;
+
+    let (_, listed) = send(&a2.key, Method::GET, "/admin/cassettes", None, None).await;
+    assert!(listed["data"].as_array().unwrap().contains(&json!(name)), "{}", listed);
+    let (_, listed) = send(&b.key, Method::GET, "/admin/cassettes", None, None).await;
+    assert!(!listed["data"].as_array().unwrap().contains(&json!(name)), "{}", listed);
+    let (status, body) = send(&b.key, Method::POST, &format!("/admin/cassettes/{}/replay", name), None, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "{}", body);
+});
@@ -0,0 +1,121 @@
+// The server over HTTPS, as started with --tls-cert and --tls-key. These skip
+// unless TEENYTINY_URL is https://; for a self-signed certificate, set
+// TEENYTINY_CA_CERT to it so every client in the harness trusts it.
+
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+use async_openai::types::CreateChatCompletionRequestArgs;
+use futures::StreamExt;
+use reqwest::{StatusCode, Url};
+use serde_json::json;
+
+use crate::{api_key, server_url, ca_cert, http_client, http_client_builder, setup_client};
+use super::user_message;
+
+fn https_url() -> Option<Url> {
+    let url = Url::parse(&server_url()).unwrap();
+    if url.scheme() == "https" {
+        Some(url)
+    } else {
+        eprintln!("Skipping: TEENYTINY_URL is not https://");
+        None
+    }
+}
+
+async fn server_addr(url: &Url) -> SocketAddr {
+    let host = url.host_str().unwrap();
+    tokio::net::lookup_host((host, url.port_or_known_default().unwrap())).await.unwrap().next().unwrap()
+}
+
+teenytiny_test!(async fn test_handshake_succeeds() {
+    let Some(url) = https_url() else { return };
+
+    let response = http_client().get(format!("{}/v1/models", server_url())).bearer_auth(api_key()).send().await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.url().scheme(), "https");
+    assert_eq!(response.url().host_str(), url.host_str());
+});
+
+teenytiny_test!(async fn test_untrusted_certificate_is_rejected() {
+    let Some(_) = https_url() else { return };
+    if ca_cert().is_none() {
+        eprintln!("Skipping: TEENYTINY_CA_CERT is not set, so the certificate is publicly trusted");
+        return;
+    }
+
+    // Only the system's roots, which don't include a self-signed certificate
+    let error = reqwest::Client::new().get(format!("{}/healthz", server_url())).send().await.unwrap_err();
+
+    assert!(error.is_connect(), "Expected the handshake to fail, got {:?}", error);
+});
+
+teenytiny_test!(async fn test_sni_names_the_host_being_called() {
+    let Some(url) = https_url() else { return };
+    let host = url.host_str().unwrap();
+    if host.parse::<std::net::IpAddr>().is_ok() {
+        eprintln!("Skipping: TEENYTINY_URL uses an IP address, so no server name is sent");
+        return;
+    }
+    let addr = server_addr(&url).await;
+
+    // Same server, same certificate: only the server name in the handshake differs
+    let matching = http_client_builder().resolve(host, addr).build().unwrap();
+    let response = matching.get(format!("{}/healthz", server_url())).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let other = "not-teenytiny.invalid";
+    let mismatched = http_client_builder().resolve(other, addr).build().unwrap();
+    let mut other_url = url.clone();
+    other_url.set_host(Some(other)).unwrap();
+    let error = mismatched.get(other_url.join("/healthz").unwrap()).send().await.unwrap_err();
+    assert!(error.is_connect(), "A certificate for {} should not satisfy {}, got {:?}", host, other, error);
+});
+
+teenytiny_test!(async fn test_streaming_over_tls() {
+    let Some(_) = https_url() else { return };
+    let start = Instant::now();
+
+    let mut response = http_client()
+        .post(format!("{}/v1/chat/completions", server_url()))
+        .bearer_auth(api_key())
+        .json(&json!({"model": "slow:200", "stream": true, "messages": [{"role": "user", "content": "one two three four five"}]}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // TLS records are flushed as they're written, so words still arrive one by one
+    let mut arrivals = Vec::new();
+    let mut text = String::new();
+    while let Some(chunk) = response.chunk().await.unwrap() {
+        let chunk = String::from_utf8_lossy(&chunk).to_string();
+        if chunk.contains("\"content\"") {
+            arrivals.push(start.elapsed());
+        }
+        text.push_str(&chunk);
+    }
+    assert!(text.contains("data: [DONE]"));
+    let spread = arrivals.last().unwrap().saturating_sub(arrivals[0]);
+    assert!(spread >= Duration::from_millis(400), "Content arrived all at once ({:?} apart)", spread);
+});
+
+teenytiny_test!(async fn test_openai_client_streams_over_tls() {
+    let Some(_) = https_url() else { return };
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("echo")
+        .messages([user_message("Hello over TLS")])
+        .stream(true)
+        .build().unwrap();
+
+    let mut stream = setup_client().chat().create_stream(request).await.unwrap();
+
+    let mut content = String::new();
+    while let Some(result) = stream.next().await {
+        if let Some(delta) = result.unwrap().choices.first().and_then(|choice| choice.delta.content.clone()) {
+            content.push_str(&delta);
+        }
+    }
+    assert_eq!(content, "Hello over TLS");
+});
@@ -0,0 +1,93 @@
+// Usage should match what clients compute with tiktoken. The server only
+// counts BPE tokens when started with --tokenizer; these tests skip otherwise.
+
+use async_openai::types::CreateChatCompletionRequestArgs;
+use serde_json::Value;
+use tiktoken_rs::{cl100k_base, o200k_base, CoreBPE};
+
+use crate::{base_url, setup_client};
+use super::{skip, system_message, user_message};
+
+async fn server_tokenizer() -> Option<CoreBPE> {
+    let version: Value = reqwest::get(format!("{}/version", base_url()))
+        .await
+        .ok()?
+        .json()
+        .await
+        .ok()?;
+
+    match version["tokenizer"].as_str() {
+        Some("o200k_base") => Some(o200k_base().unwrap()),
+        Some("cl100k_base") => Some(cl100k_base().unwrap()),
+        other => {
+            skip(&format!("the server's tokenizer is {:?}, not one of tiktoken's", other));
+            None
+        }
+    }
+}
+
+// OpenAI's formula for chat prompts: 3 tokens around each message and 3 to prime the reply
+fn chat_tokens(bpe: &CoreBPE, messages: &[(&str, &str)]) -> u32 {
+    let content: usize = messages
+        .iter()
+        .map(|(role, text)| 3 + bpe.encode_with_special_tokens(role).len() + bpe.encode_with_special_tokens(text).len())
+        .sum();
+    (content + 3) as u32
+}
+
+async fn assert_usage_matches(bpe: &CoreBPE, system: &str, prompt: &str) {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("echo")
+        .messages([system_message(system), user_message(prompt)])
+        .build().unwrap();
+
+    let response = setup_client().chat().create(request).await.unwrap();
+    let usage = response.usage.expect("No usage in response");
+    let content = response.choices[0].message.content.clone().expect("No content in response");
+
+    assert_eq!(
+        usage.prompt_tokens,
+        chat_tokens(bpe, &[("system", system), ("user", prompt)]),
+        "prompt_tokens for {:?}", prompt
+    );
+    assert_eq!(
+        usage.completion_tokens,
+        bpe.encode_with_special_tokens(&content).len() as u32,
+        "completion_tokens for {:?}", content
+    );
+    assert_eq!(usage.total_tokens, usage.prompt_tokens + usage.completion_tokens);
+}
+
+teenytiny_test!(async fn test_usage_matches_tiktoken_for_english() {
+    let Some(bpe) = server_tokenizer().await else { return };
+
+    assert_usage_matches(&bpe, "You are a helpful assistant.", "The quick brown fox jumps over the lazy dog.").await;
+    assert_usage_matches(&bpe, "Answer in code.", "fn main() {\n    println!(\"{}\", 1 + 2);\n}").await;
+});
+
+teenytiny_test!(async fn test_usage_matches_tiktoken_for_multilingual_prompts() {
+    let Some(bpe) = server_tokenizer().await else { return };
+
+    for prompt in [
+        "Bonjour, je m'appelle Zoë et j'habite à Paris.",
+        "こんにちは世界、今日はいい天気ですね。",
+        "Привет, как дела? Всё хорошо!",
+        "مرحبا بالعالم",
+        "नमस्ते दुनिया",
+        "안녕하세요 세계",
+    ] {
+        assert_usage_matches(&bpe, "Translate to English.", prompt).await;
+    }
+});
+
+teenytiny_test!(async fn test_usage_matches_tiktoken_for_emoji() {
+    let Some(bpe) = server_tokenizer().await else { return };
+
+    for prompt in [
+        "🎉🎉🎉 Party time! 🥳🍕🍺",
+        "Family: 👨‍👩‍👧‍👦 Flags: 🇯🇵🇫🇷 Skin tones: 👋🏽👍🏿",
+        "I ❤️ Rust 🦀 and TypeScript 💙",
+    ] {
+        assert_usage_matches(&bpe, "React with emoji.", prompt).await;
+    }
+});
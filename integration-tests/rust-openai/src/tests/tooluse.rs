@@ -0,0 +1,428 @@
+// The tooluse model calls every offered tool with arguments derived from the
+// tool's parameter schema, then echoes tool results back as its answer.
+
+use async_openai::types::{
+    ChatCompletionMessageToolCall, ChatCompletionNamedToolChoice, ChatCompletionRequestAssistantMessageArgs,
+    ChatCompletionRequestToolMessageArgs, ChatCompletionTool, ChatCompletionToolArgs,
+    ChatCompletionToolChoiceOption, ChatCompletionToolType, CreateChatCompletionRequestArgs, FinishReason,
+    FunctionCall, FunctionName, FunctionObjectArgs,
+};
+use futures::StreamExt;
+use reqwest::{Method, StatusCode};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+use crate::raw;
+use super::{post_chat_completion, user_message};
+
+fn weather_tool() -> ChatCompletionTool {
+    ChatCompletionToolArgs::default()
+        .function(
+            FunctionObjectArgs::default()
+                .name("get_weather")
+                .description("Get the current weather in a city")
+                .parameters(json!({
+                    "type": "object",
+                    "properties": {
+                        "city": {"type": "string", "minLength": 3},
+                        "unit": {"type": "string", "enum": ["celsius", "fahrenheit"]},
+                        "days": {"type": "integer", "minimum": 1, "maximum": 7},
+                        "verbose": {"type": "boolean"},
+                    },
+                    "required": ["city", "unit", "days"],
+                }))
+                .build()
+                .unwrap(),
+        )
+        .build()
+        .unwrap()
+}
+
+fn time_tool() -> ChatCompletionTool {
+    ChatCompletionToolArgs::default()
+        .function(FunctionObjectArgs::default().name("get_time").build().unwrap())
+        .build()
+        .unwrap()
+}
+
+teenytiny_test!(async fn test_blocking_tool_calls(client) {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("tooluse")
+        .messages([user_message("What's the weather in Paris?")])
+        .tools(vec![weather_tool(), time_tool()])
+        .build().unwrap();
+
+    let response = client.chat().create(request).await.unwrap();
+    let choice = &response.choices[0];
+
+    assert_eq!(choice.finish_reason, Some(FinishReason::ToolCalls));
+    assert_eq!(choice.message.content, None);
+
+    let calls = choice.message.tool_calls.as_ref().expect("No tool calls");
+    assert_eq!(calls.len(), 2);
+    assert_eq!(calls[0].id, "call_0_0");
+    assert_eq!(calls[0].r#type, ChatCompletionToolType::Function);
+    assert_eq!(calls[0].function.name, "get_weather");
+    assert_eq!(calls[1].function.name, "get_time");
+    assert_eq!(calls[1].function.arguments, "{}");
+});
+
+teenytiny_test!(async fn test_arguments_conform_to_schema(client) {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("tooluse")
+        .messages([user_message("Forecast please")])
+        .tools(vec![weather_tool()])
+        .build().unwrap();
+
+    let response = client.chat().create(request).await.unwrap();
+    let calls = response.choices[0].message.tool_calls.clone().unwrap();
+    let args: Value = serde_json::from_str(&calls[0].function.arguments).expect("Arguments are not JSON");
+
+    assert!(args["city"].as_str().unwrap().len() >= 3);
+    assert_eq!(args["unit"], "celsius");
+    assert_eq!(args["days"], 1);
+    assert!(args.get("verbose").is_none(), "Optional properties should be left out");
+});
+
+teenytiny_test!(async fn test_streaming_tool_call_reassembly(client) {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("tooluse")
+        .messages([user_message("What's the weather in Paris?")])
+        .tools(vec![weather_tool(), time_tool()])
+        .stream(true)
+        .build().unwrap();
+
+    let mut stream = client.chat().create_stream(request).await.unwrap();
+
+    // Reassemble calls by index, as a client would
+    let mut calls: BTreeMap<u32, (String, String, String)> = BTreeMap::new();
+    let mut fragments = 0;
+    let mut finish_reason = None;
+    while let Some(result) = stream.next().await {
+        let response = result.unwrap();
+        let Some(choice) = response.choices.first() else { continue };
+        if let Some(chunks) = &choice.delta.tool_calls {
+            for chunk in chunks {
+                let entry = calls.entry(chunk.index).or_default();
+                if let Some(id) = &chunk.id {
+                    entry.0 = id.clone();
+                }
+                if let Some(function) = &chunk.function {
+                    if let Some(name) = &function.name {
+                        entry.1 = name.clone();
+                    }
+                    if let Some(arguments) = &function.arguments {
+                        entry.2.push_str(arguments);
+                    }
+                }
+                fragments += 1;
+            }
+        }
+        if choice.finish_reason.is_some() {
+            finish_reason = choice.finish_reason;
+        }
+    }
+
+    assert_eq!(finish_reason, Some(FinishReason::ToolCalls));
+    assert_eq!(calls.len(), 2);
+    assert!(fragments > calls.len(), "Arguments should arrive in several fragments");
+
+    let (id, name, arguments) = &calls[&0];
+    assert_eq!(id, "call_0_0");
+    assert_eq!(name, "get_weather");
+    let args: Value = serde_json::from_str(arguments).expect("Reassembled arguments are not JSON");
+    assert_eq!(args["unit"], "celsius");
+
+    assert_eq!(calls[&1].1, "get_time");
+    assert_eq!(calls[&1].2, "{}");
+});
+
+// The raw tool call deltas of a streamed completion, in the order they arrived
+async fn streamed_tool_call_deltas(tools: Value, parallel_tool_calls: Option<bool>) -> (Vec<Value>, Option<String>) {
+    let mut body = json!({
+        "model": "tooluse",
+        "stream": true,
+        "messages": [{"role": "user", "content": "What's the weather in Paris?"}],
+        "tools": tools,
+    });
+    if let Some(parallel) = parallel_tool_calls {
+        body["parallel_tool_calls"] = json!(parallel);
+    }
+    let request = raw::request(Method::POST, "/v1/chat/completions").json(&body);
+    let response = raw::send(request).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+
+    let mut deltas = Vec::new();
+    let mut finish_reason = None;
+    for data in response.text().lines().filter_map(|line| line.strip_prefix("data: ")) {
+        if data == "[DONE]" {
+            continue;
+        }
+        let chunk: Value = serde_json::from_str(data).unwrap_or_else(|e| panic!("Bad chunk {} ({})", data, e));
+        let Some(choice) = chunk["choices"].get(0) else { continue };
+        if let Some(calls) = choice["delta"]["tool_calls"].as_array() {
+            deltas.extend(calls.iter().cloned());
+        }
+        if let Some(reason) = choice["finish_reason"].as_str() {
+            finish_reason = Some(reason.to_string());
+        }
+    }
+    (deltas, finish_reason)
+}
+
+teenytiny_test!(async fn test_streamed_tool_call_deltas_are_incremental() {
+    let tools = json!([weather_tool(), time_tool()]);
+    let (deltas, finish_reason) = streamed_tool_call_deltas(tools, None).await;
+
+    assert_eq!(finish_reason.as_deref(), Some("tool_calls"));
+
+    // Like OpenAI: the first delta of a call names it, the rest only add to its arguments
+    let mut seen: Vec<u64> = Vec::new();
+    for delta in &deltas {
+        let index = delta["index"].as_u64().unwrap_or_else(|| panic!("Delta without an index: {}", delta));
+        if !seen.contains(&index) {
+            assert_eq!(index, seen.len() as u64, "Calls should be indexed in order: {:?}", deltas);
+            seen.push(index);
+
+            assert_eq!(delta["id"], format!("call_0_{}", index), "{}", delta);
+            assert_eq!(delta["type"], "function", "{}", delta);
+            assert!(delta["function"]["name"].is_string(), "{}", delta);
+            assert_eq!(delta["function"]["arguments"], "", "{}", delta);
+        } else {
+            assert!(delta.get("id").is_none(), "Only a call's first delta carries its id: {}", delta);
+            assert!(delta.get("type").is_none(), "Only a call's first delta carries its type: {}", delta);
+            assert!(delta["function"].get("name").is_none(), "Only a call's first delta carries its name: {}", delta);
+            assert!(!delta["function"]["arguments"].as_str().unwrap().is_empty(), "{}", delta);
+        }
+    }
+    assert_eq!(seen, vec![0, 1]);
+
+    let weather_fragments = deltas.iter().filter(|delta| delta["index"] == 0).count() - 1;
+    assert!(weather_fragments > 1, "get_weather arguments should be split across chunks: {:?}", deltas);
+});
+
+teenytiny_test!(async fn test_streamed_arguments_reassemble_into_the_blocking_arguments(client) {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("tooluse")
+        .messages([user_message("What's the weather in Paris?")])
+        .tools(vec![weather_tool(), time_tool()])
+        .build().unwrap();
+    let blocking = client.chat().create(request).await.unwrap();
+    let calls = blocking.choices[0].message.tool_calls.clone().unwrap();
+
+    let (deltas, _) = streamed_tool_call_deltas(json!([weather_tool(), time_tool()]), None).await;
+
+    for (index, call) in calls.iter().enumerate() {
+        let arguments: String = deltas.iter()
+            .filter(|delta| delta["index"] == index)
+            .filter_map(|delta| delta["function"]["arguments"].as_str())
+            .collect();
+        let streamed: Value = serde_json::from_str(&arguments)
+            .unwrap_or_else(|e| panic!("Reassembled arguments {:?} are not JSON ({})", arguments, e));
+        let expected: Value = serde_json::from_str(&call.function.arguments).unwrap();
+        assert_eq!(streamed, expected, "Arguments of {} differ between streaming and blocking", call.function.name);
+    }
+});
+
+teenytiny_test!(async fn test_agent_loop_answers_from_tool_results(client) {
+    let question = user_message("What's the weather in Paris?");
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("tooluse")
+        .messages([question.clone()])
+        .tools(vec![weather_tool()])
+        .build().unwrap();
+
+    let response = client.chat().create(request).await.unwrap();
+    let calls: Vec<ChatCompletionMessageToolCall> = response.choices[0].message.tool_calls.clone().unwrap();
+
+    let assistant = ChatCompletionRequestAssistantMessageArgs::default()
+        .tool_calls(calls.clone())
+        .build().unwrap();
+    let result = ChatCompletionRequestToolMessageArgs::default()
+        .tool_call_id(calls[0].id.clone())
+        .content("Sunny and 21 degrees")
+        .build().unwrap();
+
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("tooluse")
+        .messages([question, assistant.into(), result.into()])
+        .tools(vec![weather_tool()])
+        .build().unwrap();
+
+    let response = client.chat().create(request).await.unwrap();
+    let choice = &response.choices[0];
+
+    assert_eq!(choice.finish_reason, Some(FinishReason::Stop));
+    assert_eq!(choice.message.content.as_deref(), Some("Sunny and 21 degrees"));
+    assert!(choice.message.tool_calls.is_none());
+});
+
+teenytiny_test!(async fn test_tool_choice_none_answers_in_text(client) {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("tooluse")
+        .messages([user_message("Just talk to me")])
+        .tools(vec![weather_tool()])
+        .tool_choice(ChatCompletionToolChoiceOption::None)
+        .build().unwrap();
+
+    let response = client.chat().create(request).await.unwrap();
+
+    assert_eq!(response.choices[0].finish_reason, Some(FinishReason::Stop));
+    assert_eq!(response.choices[0].message.content.as_deref(), Some("Just talk to me"));
+});
+
+teenytiny_test!(async fn test_tool_choice_none_streams_text(client) {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("tooluse")
+        .messages([user_message("Just talk to me")])
+        .tools(vec![weather_tool(), time_tool()])
+        .tool_choice(ChatCompletionToolChoiceOption::None)
+        .stream(true)
+        .build().unwrap();
+
+    let mut stream = client.chat().create_stream(request).await.unwrap();
+    let mut content = String::new();
+    let mut finish_reason = None;
+    while let Some(result) = stream.next().await {
+        let response = result.unwrap();
+        let Some(choice) = response.choices.first() else { continue };
+        assert!(choice.delta.tool_calls.is_none(), "tool_choice none should never call a tool");
+        content.push_str(choice.delta.content.as_deref().unwrap_or(""));
+        finish_reason = choice.finish_reason.or(finish_reason);
+    }
+
+    assert_eq!(content, "Just talk to me");
+    assert_eq!(finish_reason, Some(FinishReason::Stop));
+});
+
+teenytiny_test!(async fn test_tool_choice_required_calls_after_tool_results(client) {
+    let question = user_message("What's the weather in Paris?");
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("tooluse")
+        .messages([question.clone()])
+        .tools(vec![weather_tool()])
+        .build().unwrap();
+    let calls = client.chat().create(request).await.unwrap().choices[0].message.tool_calls.clone().unwrap();
+
+    let assistant = ChatCompletionRequestAssistantMessageArgs::default()
+        .tool_calls(calls.clone())
+        .build().unwrap();
+    let result = ChatCompletionRequestToolMessageArgs::default()
+        .tool_call_id(calls[0].id.clone())
+        .content("Sunny and 21 degrees")
+        .build().unwrap();
+
+    // With 'auto' the model would answer from the result now, 'required' makes it call again
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("tooluse")
+        .messages([question, assistant.into(), result.into()])
+        .tools(vec![weather_tool()])
+        .tool_choice(ChatCompletionToolChoiceOption::Required)
+        .build().unwrap();
+
+    let response = client.chat().create(request).await.unwrap();
+    let choice = &response.choices[0];
+
+    assert_eq!(choice.finish_reason, Some(FinishReason::ToolCalls));
+    assert_eq!(choice.message.content, None);
+    let calls = choice.message.tool_calls.as_ref().expect("No tool calls");
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].id, "call_1_0");
+    assert_eq!(calls[0].function.name, "get_weather");
+});
+
+teenytiny_test!(async fn test_tool_choice_required_without_tools_is_rejected() {
+    let (status, body) = post_chat_completion(json!({
+        "model": "tooluse",
+        "messages": [{"role": "user", "content": "What time is it?"}],
+        "tool_choice": "required",
+    }))
+    .await;
+
+    assert_eq!(status, reqwest::StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["type"], "invalid_request_error");
+    assert_eq!(body["error"]["param"], "tool_choice");
+});
+
+teenytiny_test!(async fn test_named_tool_choice(client) {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("tooluse")
+        .messages([user_message("What time is it?")])
+        .tools(vec![weather_tool(), time_tool()])
+        .tool_choice(ChatCompletionToolChoiceOption::Named(ChatCompletionNamedToolChoice {
+            r#type: ChatCompletionToolType::Function,
+            function: FunctionName { name: "get_time".to_string() },
+        }))
+        .build().unwrap();
+
+    let response = client.chat().create(request).await.unwrap();
+    let calls = response.choices[0].message.tool_calls.clone().unwrap();
+
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].function, FunctionCall { name: "get_time".to_string(), arguments: "{}".to_string() });
+});
+
+teenytiny_test!(async fn test_parallel_tool_calls_disabled(client) {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("tooluse")
+        .messages([user_message("Weather and time please")])
+        .tools(vec![weather_tool(), time_tool()])
+        .parallel_tool_calls(false)
+        .build().unwrap();
+
+    let response = client.chat().create(request).await.unwrap();
+    let calls = response.choices[0].message.tool_calls.clone().unwrap();
+
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].function.name, "get_weather");
+});
+
+teenytiny_test!(async fn test_parallel_tool_calls_enabled(client) {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("tooluse")
+        .messages([user_message("Weather and time please")])
+        .tools(vec![weather_tool(), time_tool()])
+        .parallel_tool_calls(true)
+        .build().unwrap();
+
+    let response = client.chat().create(request).await.unwrap();
+    let calls = response.choices[0].message.tool_calls.clone().unwrap();
+
+    let names: Vec<&str> = calls.iter().map(|call| call.function.name.as_str()).collect();
+    assert_eq!(names, ["get_weather", "get_time"]);
+    assert_ne!(calls[0].id, calls[1].id);
+});
+
+teenytiny_test!(async fn test_streamed_parallel_calls_interleave() {
+    let (deltas, finish_reason) = streamed_tool_call_deltas(json!([weather_tool(), time_tool()]), Some(true)).await;
+
+    assert_eq!(finish_reason.as_deref(), Some("tool_calls"));
+    // Both calls start before either finishes, so a client must reassemble by index
+    let indexes: Vec<u64> = deltas.iter().map(|delta| delta["index"].as_u64().unwrap()).collect();
+    let last_of_first = indexes.iter().rposition(|&index| index == 0).unwrap();
+    let first_of_second = indexes.iter().position(|&index| index == 1).unwrap();
+    assert!(first_of_second < last_of_first, "Parallel calls should interleave: {:?}", indexes);
+});
+
+teenytiny_test!(async fn test_streamed_single_call_without_parallel_tool_calls() {
+    let (deltas, finish_reason) = streamed_tool_call_deltas(json!([weather_tool(), time_tool()]), Some(false)).await;
+
+    assert_eq!(finish_reason.as_deref(), Some("tool_calls"));
+    assert!(deltas.iter().all(|delta| delta["index"] == 0), "Expected one call: {:?}", deltas);
+    assert_eq!(deltas[0]["function"]["name"], "get_weather");
+});
+
+teenytiny_test!(async fn test_unknown_named_tool_is_rejected() {
+    let (status, body) = post_chat_completion(json!({
+        "model": "tooluse",
+        "messages": [{"role": "user", "content": "What time is it?"}],
+        "tools": [{"type": "function", "function": {"name": "get_weather"}}],
+        "tool_choice": {"type": "function", "function": {"name": "get_time"}},
+    }))
+    .await;
+
+    assert_eq!(status, reqwest::StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["type"], "invalid_request_error");
+    assert_eq!(body["error"]["param"], "tool_choice");
+    assert!(body["error"]["message"].as_str().unwrap_or("").contains("get_time"), "{}", body);
+});
@@ -0,0 +1,142 @@
+// Usage accounting, whatever the tokenizer: totals add up for every model,
+// prompts count every message and its role, n choices are each counted, and a
+// stream reports the same usage as the blocking completion it mirrors. Token
+// counts of plain text come from the echo model, which replies with its input.
+
+use async_openai::types::{
+    ChatCompletionRequestAssistantMessageArgs, ChatCompletionRequestMessage, CompletionUsage,
+    CreateChatCompletionRequest, CreateChatCompletionRequestArgs,
+};
+use futures::StreamExt;
+
+use crate::setup_client;
+use super::{system_message, user_message};
+
+const PROMPT: &str = "Count the tokens in this short prompt";
+const SEED: i64 = 583;
+
+// Overhead OpenAI counts around each chat message
+const TOKENS_PER_MESSAGE: u32 = 3;
+
+// Models whose reply depends only on the request, so two requests match
+const DETERMINISTIC: &[&str] = &["echo", "lorem", "slow", "tooluse", "json", "filtered", "reasoning"];
+
+fn request(model: &str, messages: Vec<ChatCompletionRequestMessage>, n: u8, stream: bool) -> CreateChatCompletionRequest {
+    CreateChatCompletionRequestArgs::default()
+        .model(model)
+        .messages(messages)
+        // Reasoning models refuse max_tokens, and every model honors this
+        .max_completion_tokens(50u32)
+        .seed(SEED)
+        .n(n)
+        .stream(stream)
+        .build().unwrap()
+}
+
+fn assistant_message(content: &str) -> ChatCompletionRequestMessage {
+    ChatCompletionRequestAssistantMessageArgs::default()
+        .content(content)
+        .build()
+        .unwrap()
+        .into()
+}
+
+async fn blocking_usage(request: CreateChatCompletionRequest) -> CompletionUsage {
+    let model = request.model.clone();
+    let response = setup_client().chat().create(request).await
+        .unwrap_or_else(|e| panic!("{}: {}", model, e));
+    response.usage.unwrap_or_else(|| panic!("{}: no usage in response", model))
+}
+
+async fn streamed_usage(request: CreateChatCompletionRequest) -> CompletionUsage {
+    let model = request.model.clone();
+    let mut stream = setup_client().chat().create_stream(request).await
+        .unwrap_or_else(|e| panic!("{}: {}", model, e));
+
+    let mut usage = None;
+    while let Some(result) = stream.next().await {
+        let chunk = result.unwrap_or_else(|e| panic!("{}: {}", model, e));
+        if let Some(chunk_usage) = chunk.usage {
+            assert!(usage.is_none(), "{}: usage sent more than once", model);
+            usage = Some(chunk_usage);
+        }
+    }
+    usage.unwrap_or_else(|| panic!("{}: no usage in the stream", model))
+}
+
+// Tokens in a piece of text, as the server counts them
+async fn count_tokens(text: &str) -> u32 {
+    blocking_usage(request("echo", vec![user_message(text)], 1, false)).await.completion_tokens
+}
+
+fn assert_adds_up(model: &str, usage: &CompletionUsage) {
+    assert_eq!(
+        usage.total_tokens,
+        usage.prompt_tokens + usage.completion_tokens,
+        "{}: total_tokens isn't prompt_tokens + completion_tokens", model
+    );
+}
+
+teenytiny_test!(async fn test_usage_adds_up_for_every_model() {
+    let models = setup_client().models().list().await.unwrap();
+
+    for model in models.data.iter().map(|model| model.id.as_str()) {
+        if model == "flaky" {
+            // Fails a share of requests by design; see the flaky suite
+            continue;
+        }
+        let blocking = blocking_usage(request(model, vec![user_message(PROMPT)], 1, false)).await;
+        assert_adds_up(model, &blocking);
+        let streamed = streamed_usage(request(model, vec![user_message(PROMPT)], 1, true)).await;
+        assert_adds_up(model, &streamed);
+
+        // Other models reply differently each time, but not to a different prompt
+        assert_eq!(streamed.prompt_tokens, blocking.prompt_tokens, "{}: prompt_tokens differ when streamed", model);
+    }
+});
+
+teenytiny_test!(async fn test_streamed_usage_matches_blocking() {
+    for &model in DETERMINISTIC {
+        let blocking = blocking_usage(request(model, vec![user_message(PROMPT)], 1, false)).await;
+        let streamed = streamed_usage(request(model, vec![user_message(PROMPT)], 1, true)).await;
+
+        assert_eq!(streamed, blocking, "{}: streamed usage differs from blocking", model);
+    }
+});
+
+teenytiny_test!(async fn test_prompt_counts_every_message_and_role() {
+    let system = "You are a careful assistant";
+    let assistant = "An earlier reply";
+    let single = blocking_usage(request("echo", vec![user_message(PROMPT)], 1, false)).await;
+    let conversation = blocking_usage(request(
+        "echo",
+        vec![system_message(system), user_message("An earlier question"), assistant_message(assistant), user_message(PROMPT)],
+        1,
+        false,
+    )).await;
+
+    let mut added = 0;
+    for (role, content) in [("system", system), ("user", "An earlier question"), ("assistant", assistant)] {
+        added += TOKENS_PER_MESSAGE + count_tokens(role).await + count_tokens(content).await;
+    }
+    assert_eq!(conversation.prompt_tokens, single.prompt_tokens + added);
+    // The reply is to the last message either way
+    assert_eq!(conversation.completion_tokens, single.completion_tokens);
+});
+
+teenytiny_test!(async fn test_n_choices_multiply_completion_tokens() {
+    for &model in DETERMINISTIC {
+        let one = blocking_usage(request(model, vec![user_message(PROMPT)], 1, false)).await;
+        let response = setup_client().chat().create(request(model, vec![user_message(PROMPT)], 3, false)).await.unwrap();
+
+        let indexes: Vec<u32> = response.choices.iter().map(|choice| choice.index).collect();
+        assert_eq!(indexes, [0, 1, 2], "{}: expected one choice per n", model);
+        let three = response.usage.expect("No usage in response");
+        assert_eq!(three.prompt_tokens, one.prompt_tokens, "{}: the prompt is only counted once", model);
+        assert_eq!(three.completion_tokens, 3 * one.completion_tokens, "{}: each choice should be counted", model);
+        assert_adds_up(model, &three);
+
+        let streamed = streamed_usage(request(model, vec![user_message(PROMPT)], 3, true)).await;
+        assert_eq!(streamed, three, "{}: streamed usage with n=3 differs from blocking", model);
+    }
+});
@@ -0,0 +1,115 @@
+// GET /admin/usage meters tokens and requests per key and model. Each test uses
+// its own key, so its usage is exactly what it made.
+
+use async_openai::types::{CompletionUsage, CreateChatCompletionRequestArgs};
+use futures::StreamExt;
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+
+use crate::{api_key, base_url};
+use super::{client_for, new_api_key, user_message};
+
+async fn metered(key: &str, query: &str) -> Value {
+    let response = crate::http_client()
+        .get(format!("{}/admin/usage{}", base_url(), query))
+        .bearer_auth(key)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    response.json().await.unwrap()
+}
+
+async fn chat(key: &str, model: &str, content: &str) -> CompletionUsage {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model(model)
+        .messages([user_message(content)])
+        .build().unwrap();
+    client_for(key).chat().create(request).await.unwrap().usage.expect("No usage")
+}
+
+teenytiny_test!(async fn test_metered_totals_match_reported_usage() {
+    let key = new_api_key().await;
+    let mut prompt_tokens = 0;
+    let mut completion_tokens = 0;
+    for i in 0..5 {
+        let usage = chat(&key, "echo", &format!("Request number {}", i)).await;
+        prompt_tokens += usage.prompt_tokens;
+        completion_tokens += usage.completion_tokens;
+    }
+
+    let metered = metered(&key, "").await;
+
+    let totals = &metered["totals"];
+    assert_eq!(totals["requests"], 5, "{}", metered);
+    assert_eq!(totals["prompt_tokens"], prompt_tokens);
+    assert_eq!(totals["completion_tokens"], completion_tokens);
+    assert_eq!(totals["total_tokens"], prompt_tokens + completion_tokens);
+});
+
+teenytiny_test!(async fn test_usage_is_grouped_by_model() {
+    let key = new_api_key().await;
+    chat(&key, "echo", "Hello").await;
+    chat(&key, "echo", "Hello again").await;
+    chat(&key, "lorem", "Hello").await;
+
+    let metered = metered(&key, "?bucket=hour").await;
+
+    let buckets = metered["data"].as_array().expect("No data");
+    let requests = |model: &str| -> u64 {
+        buckets.iter().filter(|bucket| bucket["model"] == model).map(|bucket| bucket["requests"].as_u64().unwrap()).sum()
+    };
+    assert_eq!(requests("echo"), 2, "{}", metered);
+    assert_eq!(requests("lorem"), 1, "{}", metered);
+    assert!(buckets.iter().all(|bucket| bucket["api_key"] == key.as_str()));
+});
+
+teenytiny_test!(async fn test_streamed_requests_are_metered() {
+    let key = new_api_key().await;
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("echo")
+        .messages([user_message("Count this stream")])
+        .stream(true)
+        .build().unwrap();
+    let mut stream = client_for(&key).chat().create_stream(request).await.unwrap();
+    let mut streamed = None;
+    while let Some(result) = stream.next().await {
+        if let Some(usage) = result.unwrap().usage {
+            streamed = Some(usage);
+        }
+    }
+    let streamed = streamed.expect("No usage in the stream");
+
+    let metered = metered(&key, "").await;
+
+    assert_eq!(metered["totals"]["requests"], 1, "{}", metered);
+    assert_eq!(metered["totals"]["total_tokens"], streamed.total_tokens);
+});
+
+teenytiny_test!(async fn test_keys_only_see_their_own_usage() {
+    let key = new_api_key().await;
+    let other = new_api_key().await;
+    chat(&key, "echo", "Mine").await;
+
+    assert_eq!(metered(&other, "").await["totals"]["requests"], 0);
+    // The server's key can look at any key's usage
+    assert_eq!(metered(&api_key(), &format!("?key={}", key)).await["totals"]["requests"], 1);
+});
+
+teenytiny_test!(async fn test_ollama_and_gemini_requests_are_metered() {
+    let key = new_api_key().await;
+    let post = |path: &str, body: Value| {
+        crate::http_client().post(format!("{}{}", base_url(), path)).bearer_auth(&key).json(&body).send()
+    };
+    let ollama = post("/api/chat", json!({"model": "echo", "stream": false, "messages": [{"role": "user", "content": "Hello"}]}))
+        .await.unwrap();
+    assert_eq!(ollama.status(), StatusCode::OK);
+    let gemini = post("/v1beta/models/echo:generateContent", json!({"contents": [{"role": "user", "parts": [{"text": "Hello"}]}]}))
+        .await.unwrap();
+    assert_eq!(gemini.status(), StatusCode::OK);
+
+    let metered = metered(&key, "?model=echo").await;
+
+    assert_eq!(metered["totals"]["requests"], 2, "{}", metered);
+    assert!(metered["totals"]["total_tokens"].as_u64().unwrap() > 0, "{}", metered);
+});
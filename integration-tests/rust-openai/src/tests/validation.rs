@@ -0,0 +1,187 @@
+// Malformed chat completion bodies, sent raw so the client library can't
+// refuse to build them. Each should be a 400 naming the offending param.
+
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+
+use super::post_chat_completion;
+
+fn hello() -> Value {
+    json!([{"role": "user", "content": "Hello"}])
+}
+
+// Sends the body and asserts a 400 invalid_request_error naming `param`, returning the message
+async fn assert_rejected(body: Value, param: &str) -> String {
+    let (status, response) = post_chat_completion(body.clone()).await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST, "Expected 400 for {}, got: {}", body, response);
+    assert_eq!(response["error"]["type"], "invalid_request_error");
+    assert_eq!(response["error"]["param"], param, "Wrong param for {}: {}", body, response);
+
+    response["error"]["message"].as_str().expect("No error message").to_string()
+}
+
+teenytiny_test!(async fn test_missing_model() {
+    let message = assert_rejected(json!({"messages": hello()}), "model").await;
+    assert!(message.contains("model"), "Unexpected message: {}", message);
+});
+
+teenytiny_test!(async fn test_wrong_typed_model() {
+    assert_rejected(json!({"model": 42, "messages": hello()}), "model").await;
+});
+
+teenytiny_test!(async fn test_missing_messages() {
+    assert_rejected(json!({"model": "echo"}), "messages").await;
+});
+
+teenytiny_test!(async fn test_messages_as_string() {
+    let message = assert_rejected(json!({"model": "echo", "messages": "Hello"}), "messages").await;
+    assert!(message.contains("array"), "Unexpected message: {}", message);
+});
+
+teenytiny_test!(async fn test_messages_as_object() {
+    assert_rejected(
+        json!({"model": "echo", "messages": {"role": "user", "content": "Hello"}}),
+        "messages",
+    ).await;
+});
+
+teenytiny_test!(async fn test_message_with_invalid_role() {
+    assert_rejected(
+        json!({"model": "echo", "messages": [{"role": "wizard", "content": "Hello"}]}),
+        "messages",
+    ).await;
+});
+
+teenytiny_test!(async fn test_message_with_numeric_content() {
+    assert_rejected(
+        json!({"model": "echo", "messages": [{"role": "user", "content": 42}]}),
+        "messages",
+    ).await;
+});
+
+teenytiny_test!(async fn test_unknown_field() {
+    let message = assert_rejected(
+        json!({"model": "echo", "messages": hello(), "temprature": 0.5}),
+        "temprature",
+    ).await;
+    assert!(message.contains("temprature"), "Expected field name in message: {}", message);
+});
+
+teenytiny_test!(async fn test_known_but_unused_fields_are_accepted() {
+    let (status, response) = post_chat_completion(json!({
+        "model": "echo",
+        "messages": hello(),
+        "seed": 7,
+        "logit_bias": {},
+        "response_format": {"type": "text"},
+    })).await;
+
+    assert_eq!(status, StatusCode::OK, "Unexpected error: {}", response);
+});
+
+teenytiny_test!(async fn test_logit_bias_with_invalid_token_ids() {
+    for bias in [json!({"hello": 5}), json!({"-1": 5}), json!({"1.5": 5})] {
+        let message = assert_rejected(
+            json!({"model": "echo", "messages": hello(), "logit_bias": bias}),
+            "logit_bias",
+        ).await;
+        assert!(message.contains("non-negative integers"), "Unexpected message: {}", message);
+    }
+});
+
+teenytiny_test!(async fn test_logit_bias_out_of_range() {
+    for bias in [json!({"50256": 101}), json!({"50256": -100.5}), json!({"50256": "ban"})] {
+        assert_rejected(json!({"model": "echo", "messages": hello(), "logit_bias": bias}), "logit_bias").await;
+    }
+});
+
+teenytiny_test!(async fn test_logit_bias_with_too_many_entries() {
+    let bias: serde_json::Map<String, Value> = (0..301).map(|i| (i.to_string(), json!(1))).collect();
+
+    let message = assert_rejected(
+        json!({"model": "echo", "messages": hello(), "logit_bias": bias}),
+        "logit_bias",
+    ).await;
+    assert!(message.contains("300"), "Expected the limit in message: {}", message);
+});
+
+teenytiny_test!(async fn test_logit_bias_as_array() {
+    assert_rejected(json!({"model": "echo", "messages": hello(), "logit_bias": [50256]}), "logit_bias").await;
+});
+
+teenytiny_test!(async fn test_temperature_above_range() {
+    let message = assert_rejected(
+        json!({"model": "echo", "messages": hello(), "temperature": 2.5}),
+        "temperature",
+    ).await;
+    assert!(message.contains("2.5"), "Expected value in message: {}", message);
+});
+
+teenytiny_test!(async fn test_temperature_below_range() {
+    assert_rejected(
+        json!({"model": "echo", "messages": hello(), "temperature": -0.1}),
+        "temperature",
+    ).await;
+});
+
+teenytiny_test!(async fn test_temperature_as_string() {
+    assert_rejected(
+        json!({"model": "echo", "messages": hello(), "temperature": "hot"}),
+        "temperature",
+    ).await;
+});
+
+teenytiny_test!(async fn test_temperature_boundaries_accepted() {
+    for temperature in [0.0, 2.0] {
+        let (status, response) = post_chat_completion(json!({
+            "model": "echo",
+            "messages": hello(),
+            "temperature": temperature,
+        })).await;
+
+        assert_eq!(status, StatusCode::OK, "temperature {} rejected: {}", temperature, response);
+    }
+});
+
+teenytiny_test!(async fn test_top_p_above_range() {
+    assert_rejected(
+        json!({"model": "echo", "messages": hello(), "top_p": 1.5}),
+        "top_p",
+    ).await;
+});
+
+teenytiny_test!(async fn test_negative_max_tokens() {
+    assert_rejected(
+        json!({"model": "echo", "messages": hello(), "max_tokens": -5}),
+        "max_tokens",
+    ).await;
+});
+
+teenytiny_test!(async fn test_zero_max_tokens() {
+    assert_rejected(
+        json!({"model": "echo", "messages": hello(), "max_tokens": 0}),
+        "max_tokens",
+    ).await;
+});
+
+teenytiny_test!(async fn test_fractional_max_tokens() {
+    assert_rejected(
+        json!({"model": "echo", "messages": hello(), "max_tokens": 10.5}),
+        "max_tokens",
+    ).await;
+});
+
+teenytiny_test!(async fn test_non_boolean_stream() {
+    assert_rejected(
+        json!({"model": "echo", "messages": hello(), "stream": "yes"}),
+        "stream",
+    ).await;
+});
+
+teenytiny_test!(async fn test_body_not_an_object() {
+    let (status, response) = post_chat_completion(json!(["echo", "Hello"])).await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(response["error"]["type"], "invalid_request_error");
+});
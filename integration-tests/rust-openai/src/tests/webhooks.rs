@@ -0,0 +1,154 @@
+// Webhooks POST request lifecycle events to a URL set with PUT
+// /admin/webhooks, signed with HMAC-SHA256 over a timestamp and the body,
+// which teenytiny_client::WebhookVerifier checks. The receiver here is a
+// hyper listener on localhost, which a remote server can't reach, and
+// webhooks are replaced server-wide, so these tests start a server of their
+// own, and are skipped when it can't be started.
+
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+use std::time::{Duration, SystemTime};
+
+use http_body_util::{BodyExt, Empty};
+use hyper::body::{Bytes, Incoming};
+use hyper::service::service_fn;
+use hyper::{Request, Response};
+use hyper_util::rt::TokioIo;
+use reqwest::{Method, StatusCode};
+use serde_json::{json, Value};
+use teenytiny_client::{WebhookError, WebhookVerifier, WEBHOOK_SIGNATURE_HEADER};
+use tokio::sync::mpsc;
+
+use super::own_server;
+use crate::server::TestServer;
+
+const SECRET: &str = "whsec-rust";
+
+struct Delivery {
+    path: String,
+    event: String,
+    signature: String,
+    body: Bytes,
+}
+
+impl Delivery {
+    fn payload(&self) -> Value {
+        serde_json::from_slice(&self.body).unwrap()
+    }
+}
+
+// Listens on localhost, passing on everything POSTed to it
+async fn receiver() -> (String, mpsc::UnboundedReceiver<Delivery>) {
+    let listener = tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let (sender, deliveries) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        loop {
+            let Ok((stream, _)) = listener.accept().await else { continue };
+            let sender = sender.clone();
+            tokio::spawn(async move {
+                let service = service_fn(move |request: Request<Incoming>| {
+                    let sender = sender.clone();
+                    async move {
+                        let header = |name: &str| {
+                            request.headers().get(name).and_then(|value| value.to_str().ok()).unwrap_or_default().to_string()
+                        };
+                        let (path, event, signature) =
+                            (request.uri().path().to_string(), header("x-teenytiny-event"), header(WEBHOOK_SIGNATURE_HEADER));
+                        let body = request.into_body().collect().await?.to_bytes();
+                        let _ = sender.send(Delivery { path, event, signature, body });
+                        Ok::<_, hyper::Error>(Response::new(Empty::<Bytes>::new()))
+                    }
+                });
+                let _ = hyper::server::conn::http1::Builder::new().serve_connection(TokioIo::new(stream), service).await;
+            });
+        }
+    });
+    (url, deliveries)
+}
+
+async fn send(server: &TestServer, method: Method, path: &str, body: Value) -> reqwest::Response {
+    crate::http_client()
+        .request(method, format!("{}{}", server.url(), path))
+        .bearer_auth(server.api_key())
+        .json(&body)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn chat(server: &TestServer, model: &str, stream: bool) -> (StatusCode, String) {
+    let response = send(server, Method::POST, "/v1/chat/completions", json!({
+        "model": model, "stream": stream, "messages": [{"role": "user", "content": "Hello hooks"}]
+    })).await;
+    (response.status(), response.text().await.unwrap())
+}
+
+// Changed bodies, other secrets and deliveries sent again later are refused
+fn check_tampering(verifier: &WebhookVerifier, delivery: &Delivery) {
+    let mut tampered = delivery.body.to_vec();
+    tampered.extend_from_slice(b" ");
+    assert_eq!(verifier.verify(&delivery.signature, &tampered), Err(WebhookError::Mismatch));
+    assert_eq!(WebhookVerifier::new("another secret").verify(&delivery.signature, &delivery.body), Err(WebhookError::Mismatch));
+
+    let replayed = SystemTime::now() + Duration::from_secs(600);
+    assert!(matches!(verifier.verify_at(&delivery.signature, &delivery.body, replayed), Err(WebhookError::Stale { .. })));
+}
+
+teenytiny_test!(async fn test_webhooks_receive_signed_lifecycle_events() {
+    let Some(server) = own_server(&[]).await else { return };
+    let (url, mut deliveries) = receiver().await;
+
+    let webhooks = json!([
+        {"url": format!("{}/all", url), "secret": SECRET},
+        {"url": format!("{}/errors", url), "secret": SECRET, "events": ["error.emitted"]},
+    ]);
+    let response = send(&server, Method::PUT, "/admin/webhooks", webhooks).await;
+    assert_eq!(response.status(), StatusCode::OK, "{}", response.text().await.unwrap());
+
+    let (status, streamed) = chat(&server, "echo", true).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(streamed.contains("[DONE]"), "{}", streamed);
+    let (status, _) = chat(&server, "no-such-model", false).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Two requests received, one stream completed and one error, plus the
+    // error again for the webhook that only wants errors
+    let verifier = WebhookVerifier::new(SECRET);
+    let mut events: HashMap<(String, String), Vec<Value>> = HashMap::new();
+    for _ in 0..5 {
+        let delivery = tokio::time::timeout(Duration::from_secs(10), deliveries.recv())
+            .await
+            .expect("Every event should be delivered within 10s")
+            .unwrap();
+        assert_eq!(verifier.verify(&delivery.signature, &delivery.body), Ok(()), "{}", delivery.signature);
+        let payload = delivery.payload();
+        assert!(delivery.signature.starts_with(&format!("t={},", payload["created"])), "{}", delivery.signature);
+        check_tampering(&verifier, &delivery);
+        assert_eq!(payload["type"], delivery.event.as_str());
+        assert!(payload["id"].as_str().unwrap().starts_with("evt_"), "{}", payload);
+        events.entry((delivery.path, delivery.event)).or_default().push(payload);
+    }
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(deliveries.try_recv().is_err(), "Expected only five deliveries");
+
+    let all = |event: &str| events.get(&("/all".to_string(), event.to_string())).cloned().unwrap_or_default();
+    assert_eq!(all("request.received").len(), 2);
+    assert!(all("request.received").iter().all(|event| event["data"]["path"] == "/v1/chat/completions"));
+
+    let completed = all("stream.completed");
+    assert_eq!(completed.len(), 1);
+    assert_eq!(completed[0]["data"]["model"], "echo");
+    assert_eq!(completed[0]["data"]["status"], 200);
+    assert_eq!(completed[0]["data"]["cancelled"], false);
+
+    let errors = all("error.emitted");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0]["data"]["status"], 404);
+    assert_eq!(errors[0]["data"]["error"]["code"], "model_not_found");
+    let only_errors = &events[&("/errors".to_string(), "error.emitted".to_string())];
+    assert_eq!(only_errors[0]["data"], errors[0]["data"]);
+
+    let response = send(&server, Method::PUT, "/admin/webhooks", json!([{"url": url, "secret": SECRET, "events": ["request.sent"]}])).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+});
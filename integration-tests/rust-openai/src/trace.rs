@@ -0,0 +1,360 @@
+// HTTP traces for --verbose-http: every exchange a test has with the server,
+// written out so a failure against a remote deployment can be diagnosed from
+// the files rather than by running it again.
+//
+// async-openai owns its requests, so the traces come from a recording proxy
+// in front of the server instead of from the clients. With the setting on,
+// base_url() points at the proxy, which forwards each request and writes it,
+// the response headers and every chunk of the body (each SSE frame, for a
+// stream) to <trace_dir>/<test>/<n>-<method>-<path>.txt as they pass through.
+// Requests carry the test's name in a header the proxy strips; requests made
+// from a spawned task don't, and go under unattributed/.
+//
+// Authorization headers, API keys and secret-looking JSON fields are redacted.
+// WebSockets and raw connections can't be proxied, so the realtime, tls and
+// http2 suites talk to the server directly and aren't traced.
+//
+// The same proxy times streamed responses for stream timing (see
+// stream_timing.rs), with or without traces being written.
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fmt::Write as _;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write as _;
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use anyhow::{Context, Result};
+use futures::stream::{self, StreamExt};
+use http_body_util::{BodyExt, Full, StreamBody};
+use hyper::body::{Bytes, Frame, Incoming};
+use hyper::header::{HeaderMap, HeaderName, HeaderValue};
+use hyper::service::service_fn;
+use hyper::{Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+
+use crate::stream_timing::{self, Clock};
+
+/// Names the test a request belongs to
+pub const TEST_HEADER: &str = "x-teenytiny-test";
+
+const SECRET_HEADERS: &[&str] =
+    &["authorization", "proxy-authorization", "api-key", "x-api-key", "x-goog-api-key", "cookie", "set-cookie"];
+
+const SECRET_FIELDS: &[&str] = &["key", "api_key", "apiKey", "secret", "client_secret", "password"];
+
+// Connection-level headers, which belong to each hop rather than the exchange
+const HOP_HEADERS: &[&str] =
+    &["connection", "keep-alive", "transfer-encoding", "upgrade", "te", "trailer", "host", "content-length"];
+
+tokio::task_local! {
+    static CURRENT_TEST: String;
+}
+
+/// Runs a test with its name attached to the requests it makes
+pub async fn with_test<F: std::future::Future>(name: &str, test: F) -> F::Output {
+    CURRENT_TEST.scope(name.to_string(), test).await
+}
+
+/// The running test, when called from its own task
+pub fn current_test() -> Option<String> {
+    CURRENT_TEST.try_with(Clone::clone).ok()
+}
+
+/// Where a test's traces go: its path with dots for the module separators
+pub fn test_dir(root: &Path, test: Option<&str>) -> PathBuf {
+    let name = test.unwrap_or("unattributed").replace("::", ".");
+    root.join(name.replace(|c: char| !c.is_ascii_alphanumeric() && c != '.' && c != '_' && c != '-', "_"))
+}
+
+/// Clears what an earlier run traced for a test, so its directory only holds this run
+pub fn reset_test(root: &Path, test: &str) {
+    let _ = fs::remove_dir_all(test_dir(root, Some(test)));
+}
+
+/// The recording proxy, running on a thread of its own so it outlives any one
+/// test's runtime
+pub struct TraceProxy {
+    url: String,
+}
+
+impl TraceProxy {
+    /// Starts forwarding to the server at `upstream`, writing traces under
+    /// `root` if given and timing streams if asked to
+    pub fn start(upstream: &str, root: Option<&Path>, time_streams: bool, secrets: Vec<String>) -> Result<TraceProxy> {
+        let listener = std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).context("Can't start the trace proxy")?;
+        listener.set_nonblocking(true)?;
+        let address = listener.local_addr()?;
+        let proxy = Arc::new(Proxy {
+            upstream: upstream.trim_end_matches('/').to_string(),
+            root: root.map(Path::to_path_buf),
+            time_streams,
+            secrets,
+            http: crate::http_client_builder().redirect(reqwest::redirect::Policy::none()).build()?,
+            counters: Mutex::new(HashMap::new()),
+        });
+
+        let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build()?;
+        std::thread::spawn(move || runtime.block_on(proxy.serve(listener)));
+        Ok(TraceProxy { url: format!("http://{}", address) })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+struct Proxy {
+    upstream: String,
+    root: Option<PathBuf>,
+    time_streams: bool,
+    // Values to blank wherever they appear, such as the harness's API key
+    secrets: Vec<String>,
+    http: reqwest::Client,
+    // Exchanges written so far per test directory, to number the files
+    counters: Mutex<HashMap<PathBuf, usize>>,
+}
+
+type ProxyBody = http_body_util::combinators::UnsyncBoxBody<Bytes, reqwest::Error>;
+
+impl Proxy {
+    async fn serve(self: Arc<Self>, listener: std::net::TcpListener) {
+        let listener = tokio::net::TcpListener::from_std(listener).expect("A listener for the trace proxy");
+        loop {
+            let Ok((stream, _)) = listener.accept().await else { continue };
+            let proxy = self.clone();
+            tokio::spawn(async move {
+                let service = service_fn(move |request| proxy.clone().forward(request));
+                let _ = hyper::server::conn::http1::Builder::new().serve_connection(TokioIo::new(stream), service).await;
+            });
+        }
+    }
+
+    async fn forward(self: Arc<Self>, request: Request<Incoming>) -> Result<Response<ProxyBody>, Infallible> {
+        let (parts, body) = request.into_parts();
+        let test = parts.headers.get(TEST_HEADER).and_then(|value| value.to_str().ok()).map(String::from);
+        let body = body.collect().await.map(|body| body.to_bytes()).unwrap_or_default();
+        let path = parts.uri.path_and_query().map_or("/", |path| path.as_str());
+
+        let mut trace = self.trace_file(test.as_deref(), parts.method.as_str(), parts.uri.path());
+        let mut text = format!("{} {}\n", parts.method, path);
+        write_headers(&mut text, &parts.headers);
+        text.push('\n');
+        text.push_str(&self.redact_body(&body));
+        self.append(&mut trace, &text);
+
+        let mut upstream = self.http.request(parts.method.clone(), format!("{}{}", self.upstream, path)).body(body);
+        for (name, value) in &parts.headers {
+            if !HOP_HEADERS.contains(&name.as_str()) && name != TEST_HEADER {
+                upstream = upstream.header(name, value);
+            }
+        }
+        let sent = Instant::now();
+        let response = match upstream.send().await {
+            Ok(response) => response,
+            Err(e) => {
+                self.append(&mut trace, &format!("\n\n--- no response: {}\n", e));
+                let body = Full::new(Bytes::from(format!("Trace proxy: {}", e))).map_err(|never| match never {}).boxed_unsync();
+                return Ok(Response::builder().status(StatusCode::BAD_GATEWAY).body(body).unwrap());
+            }
+        };
+
+        let content_type = response.headers().get("content-type").and_then(|value| value.to_str().ok()).unwrap_or("");
+        let clock = match &test {
+            Some(test) if self.time_streams && stream_timing::is_stream(content_type) => Some(Clock::start(test, sent)),
+            _ => None,
+        };
+
+        let mut text = format!("\n\n--- {}\n", response.status());
+        write_headers(&mut text, response.headers());
+        text.push('\n');
+        self.append(&mut trace, &text);
+
+        let mut builder = Response::builder().status(response.status());
+        for (name, value) in response.headers() {
+            if !HOP_HEADERS.contains(&name.as_str()) || name == "content-length" {
+                builder = builder.header(name, value);
+            }
+        }
+        // Each chunk is written as it passes, so a stream that hangs or breaks
+        // still leaves what arrived before it did
+        let proxy = self.clone();
+        let chunks = stream::unfold(Some((response, trace, clock)), move |state| {
+            let proxy = proxy.clone();
+            async move {
+                let (mut response, mut trace, mut clock) = state?;
+                match response.chunk().await {
+                    Ok(Some(chunk)) => {
+                        if let Some(clock) = &mut clock {
+                            clock.chunk();
+                        }
+                        proxy.append(&mut trace, &proxy.redact(&String::from_utf8_lossy(&chunk)));
+                        Some((Ok(Frame::data(chunk)), Some((response, trace, clock))))
+                    }
+                    Ok(None) => None,
+                    Err(e) => {
+                        proxy.append(&mut trace, &format!("\n--- body failed: {}\n", e));
+                        Some((Err(e), None))
+                    }
+                }
+            }
+        });
+        Ok(builder.body(StreamBody::new(chunks.boxed()).boxed_unsync()).unwrap())
+    }
+
+    fn trace_file(&self, test: Option<&str>, method: &str, path: &str) -> Option<File> {
+        let dir = test_dir(self.root.as_ref()?, test);
+        let n = {
+            let mut counters = self.counters.lock().unwrap();
+            let n = counters.entry(dir.clone()).or_default();
+            *n += 1;
+            *n
+        };
+        let path: String = path.trim_matches('/').chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+            .take(60)
+            .collect();
+        fs::create_dir_all(&dir).ok()?;
+        OpenOptions::new().create(true).write(true).truncate(true).open(dir.join(format!("{:03}-{}-{}.txt", n, method, path))).ok()
+    }
+
+    // Traces are best effort: a full disk shouldn't fail the test being traced
+    fn append(&self, file: &mut Option<File>, text: &str) {
+        if let Some(f) = file {
+            if f.write_all(text.as_bytes()).is_err() {
+                *file = None;
+            }
+        }
+    }
+
+    fn redact_body(&self, body: &[u8]) -> String {
+        match std::str::from_utf8(body) {
+            Ok(text) => self.redact(text),
+            Err(_) => format!("<{} bytes of binary data>", body.len()),
+        }
+    }
+
+    // Bodies are written as they were sent, but for the secrets in them
+    fn redact(&self, text: &str) -> String {
+        let text = self.secrets.iter()
+            .filter(|secret| !secret.is_empty())
+            .fold(text.to_string(), |text, secret| text.replace(secret.as_str(), "[redacted]"));
+        redact_fields(&text)
+    }
+}
+
+fn write_headers(text: &mut String, headers: &HeaderMap) {
+    for (name, value) in headers.iter().filter(|(name, _)| *name != TEST_HEADER) {
+        let _ = writeln!(text, "{}: {}", name, redact_header(name, value));
+    }
+}
+
+fn redact_header(name: &HeaderName, value: &HeaderValue) -> String {
+    let value = String::from_utf8_lossy(value.as_bytes());
+    if !SECRET_HEADERS.contains(&name.as_str()) {
+        return value.into_owned();
+    }
+    // Keep the scheme, which tells a missing Bearer from a wrong key
+    match value.split_once(' ') {
+        Some((scheme, _)) if name.as_str().ends_with("authorization") => format!("{} [redacted]", scheme),
+        _ => "[redacted]".to_string(),
+    }
+}
+
+/// Blanks the string values of JSON fields named like secrets, such as
+/// `"key": "tt-..."`, leaving the rest of the text as it is
+pub fn redact_fields(text: &str) -> String {
+    let mut text = text.to_string();
+    for field in SECRET_FIELDS {
+        let name = format!("\"{}\"", field);
+        let mut from = 0;
+        while let Some(found) = text[from..].find(&name) {
+            let after = from + found + name.len();
+            from = after;
+            let rest = &text[after..];
+            let Some(value) = rest.trim_start().strip_prefix(':').map(str::trim_start) else { continue };
+            let Some(value) = value.strip_prefix('"') else { continue };
+            let start = after + (rest.len() - value.len());
+            // The closing quote is the first one not escaped
+            let mut escaped = false;
+            let Some(end) = value.find(|c| {
+                let close = c == '"' && !escaped;
+                escaped = c == '\\' && !escaped;
+                close
+            }) else {
+                continue;
+            };
+            text.replace_range(start..start + end, "[redacted]");
+            from = start + "[redacted]".len() + 1;
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secrets_are_redacted() {
+        assert_eq!(
+            redact_fields(r#"{"key": "tt-new", "data": [{"api_key":"sk-\"1\"", "max_tokens": 5}], "keys": 2, "model": "key"}"#),
+            r#"{"key": "[redacted]", "data": [{"api_key":"[redacted]", "max_tokens": 5}], "keys": 2, "model": "key"}"#
+        );
+        assert_eq!(redact_fields(r#"data: {"secret": "#), r#"data: {"secret": "#);
+
+        let header = |name: &str, value: &str| {
+            redact_header(&HeaderName::from_bytes(name.as_bytes()).unwrap(), &HeaderValue::from_str(value).unwrap())
+        };
+        assert_eq!(header("authorization", "Bearer testkey"), "Bearer [redacted]");
+        assert_eq!(header("x-goog-api-key", "AIza"), "[redacted]");
+        assert_eq!(header("content-type", "application/json"), "application/json");
+    }
+
+    #[test]
+    fn test_test_dirs() {
+        let root = Path::new("/tmp/traces");
+        assert_eq!(test_dir(root, Some("tests::basic::test_completion")), root.join("tests.basic.test_completion"));
+        assert_eq!(test_dir(root, None), root.join("unattributed"));
+        assert_eq!(test_dir(root, Some("a/../b")), root.join("a_.._b"));
+    }
+
+    #[tokio::test]
+    async fn test_exchanges_are_traced() {
+        let upstream = std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let url = format!("http://{}", upstream.local_addr().unwrap());
+        std::thread::spawn(move || {
+            use std::io::Read;
+            let (mut socket, _) = upstream.accept().unwrap();
+            let _ = socket.read(&mut [0; 4096]);
+            let body = "data: secret-key says hi\n\n";
+            let head = format!("HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\ncontent-length: {}\r\n\r\n", body.len());
+            socket.write_all(format!("{}{}", head, body).as_bytes()).unwrap();
+        });
+        let root = std::env::temp_dir().join(format!("trace-proxy-{}", std::process::id()));
+        let proxy = TraceProxy::start(&url, Some(&root), true, vec!["secret-key".to_string()]).unwrap();
+
+        let response = reqwest::Client::new()
+            .post(format!("{}/v1/chat/completions?stream=1", proxy.url()))
+            .header(TEST_HEADER, "tests::basic::test_a")
+            .bearer_auth("secret-key")
+            .body(r#"{"model": "echo", "api_key": "sk-1"}"#)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.text().await.unwrap(), "data: secret-key says hi\n\n");
+
+        let trace = fs::read_to_string(root.join("tests.basic.test_a/001-POST-v1_chat_completions.txt")).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+        assert!(trace.starts_with("POST /v1/chat/completions?stream=1\n"), "{}", trace);
+        assert!(trace.contains("authorization: Bearer [redacted]"), "{}", trace);
+        assert!(trace.contains(r#"{"model": "echo", "api_key": "[redacted]"}"#), "{}", trace);
+        assert!(trace.contains("--- 200 OK"), "{}", trace);
+        assert!(trace.contains("data: [redacted] says hi"), "{}", trace);
+        assert!(!trace.contains("secret-key") && !trace.contains("sk-1") && !trace.contains(TEST_HEADER), "{}", trace);
+        assert_eq!(stream_timing::take("tests::basic::test_a").len(), 1);
+    }
+}
@@ -0,0 +1,55 @@
+import { bench, describe } from 'vitest';
+import { createApp } from '../src/app.js';
+import {
+  rejectUnknownParameters,
+  validateResponseFormat,
+  validateSamplingParameters,
+  validateTools,
+} from '../src/openai-protocol/validation.js';
+
+const apiKey = 'tt-bench-key';
+
+// A mid-sized conversation with tools, as an agent framework would send it
+const body = JSON.stringify({
+  model: 'echo',
+  temperature: 0.7,
+  top_p: 0.9,
+  max_tokens: 512,
+  stop: ['END'],
+  tools: Array.from({ length: 8 }, (_, i) => ({
+    type: 'function',
+    function: {
+      name: `tool_${i}`,
+      description: 'Looks something up',
+      parameters: { type: 'object', properties: { query: { type: 'string' } }, required: ['query'] },
+    },
+  })),
+  messages: [
+    { role: 'system', content: 'You are a helpful assistant.' },
+    ...Array.from({ length: 20 }, (_, i) => ({
+      role: i % 2 === 0 ? 'user' : 'assistant',
+      content: `Message ${i}: the quick brown fox jumps over the lazy dog. `.repeat(5),
+    })),
+  ],
+});
+
+describe('request deserialization', () => {
+  bench('parse and validate', () => {
+    const request = JSON.parse(body);
+    rejectUnknownParameters(request);
+    validateSamplingParameters(request);
+    validateTools(request);
+    validateResponseFormat(request);
+  });
+
+  const app = createApp({ auth: { apiKey } });
+
+  bench('POST /v1/chat/completions', async () => {
+    const res = await app.request('/v1/chat/completions', {
+      method: 'POST',
+      headers: { 'Authorization': `Bearer ${apiKey}`, 'Content-Type': 'application/json' },
+      body,
+    });
+    await res.text();
+  });
+});
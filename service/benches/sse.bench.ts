@@ -0,0 +1,32 @@
+import { bench, describe } from 'vitest';
+import { OpenAIAdapter } from '../src/openai-protocol/adapter.js';
+import type { ChatCompletionStreamResponse } from '../src/openai-protocol/types.js';
+import { EchoModel } from '../src/models/echo-model.js';
+
+const adapter = new OpenAIAdapter(new EchoModel(), 'echo');
+const request = {
+  model: 'echo',
+  stream: true,
+  messages: [{ role: 'user' as const, content: 'The quick brown fox jumps over the lazy dog. '.repeat(40) }],
+};
+const encoder = new TextEncoder();
+
+// Chunks of a finished stream, to time encoding on its own
+const chunks: ChatCompletionStreamResponse[] = [];
+for await (const chunk of adapter.completeStream(request)) {
+  chunks.push(chunk);
+}
+
+describe('SSE chunk encoding', () => {
+  bench(`encode ${chunks.length} chunks`, () => {
+    for (const chunk of chunks) {
+      encoder.encode(`data: ${JSON.stringify(chunk)}\n\n`);
+    }
+  });
+
+  bench('generate and encode a stream', async () => {
+    for await (const chunk of adapter.completeStream(request)) {
+      encoder.encode(`data: ${JSON.stringify(chunk)}\n\n`);
+    }
+  });
+});
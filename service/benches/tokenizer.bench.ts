@@ -0,0 +1,56 @@
+import { bench, describe } from 'vitest';
+import { BpeTokenizer } from '../src/tokenizer/bpe.js';
+import { WhitespaceTokenizer, countChatTokens } from '../src/tokenizer/tokenizer.js';
+
+const english = 'The quick brown fox jumps over the lazy dog, then naps until 3:45pm. '.repeat(150);
+const mixed = 'Grüße aus Zürich! 東京の天気は晴れです。 Привет, мир 👋 '.repeat(150);
+
+// OpenAI's rank files are too large to check in, so the BPE tokenizer gets a
+// stand-in vocabulary: every byte, every pair of lowercase letters and the
+// words of the English sample, enough to exercise the merge loop
+function syntheticRanks(): string {
+  const tokens = new Set<string>();
+  for (let byte = 0; byte < 256; byte++) {
+    tokens.add(String.fromCharCode(byte));
+  }
+  const letters = 'abcdefghijklmnopqrstuvwxyz';
+  for (const a of letters) {
+    for (const b of letters) {
+      tokens.add(a + b);
+    }
+  }
+  for (const word of english.match(/[a-z]+/gi) ?? []) {
+    tokens.add(` ${word}`);
+  }
+  return [...tokens].map((token, rank) => `${btoa(token)} ${rank}`).join('\n');
+}
+
+const whitespace = new WhitespaceTokenizer();
+const bpe = new BpeTokenizer('o200k_base', syntheticRanks());
+
+const conversation = Array.from({ length: 20 }, (_, i) => ({
+  role: i % 2 === 0 ? ('user' as const) : ('assistant' as const),
+  content: english.slice(0, 500),
+}));
+
+describe('token counting', () => {
+  bench('whitespace, English', () => {
+    whitespace.count(english);
+  });
+
+  bench('whitespace, mixed scripts', () => {
+    whitespace.count(mixed);
+  });
+
+  bench('BPE, English', () => {
+    bpe.count(english);
+  });
+
+  bench('BPE, mixed scripts', () => {
+    bpe.count(mixed);
+  });
+
+  bench('chat prompt of 20 messages', () => {
+    countChatTokens(whitespace, conversation);
+  });
+});
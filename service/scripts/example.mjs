@@ -0,0 +1,23 @@
+// An example scripted model, served as script:example with --scripts scripts
+//
+// The default export receives { messages, input, metadata } and returns a
+// string, or { content } or { chunks } with an optional finish_reason.
+
+export default function respond({ messages, input }) {
+  const count = /^count to (\d+)$/i.exec(input);
+  if (count) {
+    const n = Math.min(Number(count[1]), 100);
+    return { chunks: Array.from({ length: n }, (_, i) => (i === 0 ? '1' : ` ${i + 1}`)) };
+  }
+
+  if (/forbidden/i.test(input)) {
+    return { content: '', finish_reason: 'content_filter' };
+  }
+
+  if (/^summarize$/i.test(input)) {
+    const turns = messages.filter(message => message.role === 'user').length;
+    return `This conversation has ${messages.length} messages, ${turns} from the user.`;
+  }
+
+  return { content: `Script received: ${input}` };
+}
@@ -1,70 +1,1050 @@
 import { Hono } from "hono";
+import type { Context, MiddlewareHandler } from "hono";
+import type { UpgradeWebSocket } from "hono/ws";
 
 // Define types for Hono context variables
 type Variables = {
   requestId: string;
+  apiKey: string;
+  // Reported in the request's log line
+  model?: string;
+  usage?: ChatCompletionUsage;
 };
 import { stream } from "hono/streaming";
-import type { ChatCompletionRequest } from "./openai-protocol/types.js";
+import type {
+  ChatCompletionRequest,
+  ChatCompletionRequestMessage,
+  ChatCompletionStreamResponse,
+  ChatCompletionUsage,
+} from "./openai-protocol/types.js";
 import {
+  APIError,
+  ErrorTypes,
   InvalidRequestError,
+  ModelNotFoundError,
   NotFoundError,
+  PermissionDeniedError,
+  ServerOverloadedError,
 } from "./openai-protocol/errors.js";
+import {
+  generateRandomString,
+  getCurrentTimestamp,
+} from "./openai-protocol/types.js";
+import {
+  PROXY_PREFIX,
+  forwardChatCompletion,
+} from "./openai-protocol/proxy.js";
+import type { UpstreamConfig } from "./openai-protocol/proxy.js";
+import {
+  fromLegacyFunctions,
+  toLegacyResponse,
+  toLegacyStream,
+} from "./openai-protocol/legacy-functions.js";
+import {
+  rejectUnknownParameters,
+  validateFunctions,
+  validateResponseFormat,
+  validateSamplingParameters,
+  validateTools,
+} from "./openai-protocol/validation.js";
 import { ModelRegistry } from "./models/model-registry.js";
 import { OpenAIModelRegistry } from "./openai-protocol/openai-model-registry.js";
 import { EchoModel } from "./models/echo-model.js";
 import { ElizaModel } from "./models/eliza-model.js";
 import { ParryModel } from "./models/parry-model.js";
 import { RacterModel } from "./models/racter-model.js";
+import { LoremModel } from "./models/lorem-model.js";
+import { MemoryModel } from "./models/memory-model.js";
+import {
+  checkTemplate,
+  DEFAULT_TEMPLATE,
+  TemplateModel,
+} from "./models/template-model.js";
+import { RouterModel, routerTargets } from "./models/router-model.js";
+import type { Router } from "./models/router-model.js";
+import { SlowModel, parseInterval } from "./models/slow-model.js";
+import { FixtureModel } from "./models/fixture-model.js";
+import type { FixtureSource } from "./models/fixture-model.js";
+import { JsonModel } from "./models/json-model.js";
+import { MarkovModel } from "./models/markov-model.js";
+import { FilteredModel } from "./models/filtered-model.js";
+import { ScriptModel } from "./models/script-model.js";
+import type { Script } from "./models/script-model.js";
 import { createAuthMiddleware } from "./middleware/auth.js";
 import { corsMiddleware } from "./middleware/cors.js";
+import type { CorsConfig } from "./middleware/cors.js";
+import { createCompressionMiddleware } from "./middleware/compression.js";
+import type { Compressors } from "./middleware/compression.js";
 import { createLoggingMiddleware } from "./middleware/logging.js";
 import { createErrorHandler } from "./middleware/errors.js";
+import { createBodyLimitMiddleware } from "./middleware/body-limit.js";
+import { IdempotencyCache } from "./middleware/idempotency.js";
+import {
+  PromptCache,
+  withCachedTokens,
+} from "./openai-protocol/prompt-cache.js";
+import {
+  servedTier,
+  validateServiceTier,
+} from "./openai-protocol/service-tier.js";
+import type { ServiceTierDelays } from "./openai-protocol/service-tier.js";
+import {
+  RateLimiter,
+  createRateLimitMiddleware,
+} from "./middleware/rate-limit.js";
+import {
+  DEFAULT_MIDDLEWARE,
+  validateMiddlewareConfig,
+} from "./middleware/middleware-config.js";
+import type {
+  MiddlewareConfig,
+  MiddlewareName,
+} from "./middleware/middleware-config.js";
 import { SingleKeyAuthenticator } from "./auth/single-key-authenticator.js";
 import { EncryptedKeyAuthenticator } from "./auth/encrypted-key-authenticator.js";
 import { FallbackKeyAuthenticator } from "./auth/fallback-key-authenticator.js";
-import type { Authenticator } from "./auth/authenticator.js";
-import type { AuthConfig } from "./auth/auth-config.js";
+import { ScopedKeyAuthenticator } from "./auth/scoped-key-authenticator.js";
+import { RevokedKeyAuthenticator } from "./auth/revoked-key-authenticator.js";
+import type { AuthConfig, ScopedKey } from "./auth/auth-config.js";
+import { buildInfo, type BuildInfo } from "./build-info.js";
+import { Metrics } from "./utils/metrics.js";
+import {
+  MAX_STREAMS_HEADER,
+  STREAM_RETRY_AFTER_SECONDS,
+  StreamLimiter,
+} from "./utils/stream-limiter.js";
+import {
+  DEFAULT_DRAIN_GRACE_MS,
+  DRAIN_RETRY_AFTER_SECONDS,
+  Drain,
+} from "./utils/drain.js";
+import { sleep } from "./utils/sleep.js";
+import { toBase64 } from "./utils/base64.js";
+import { UsageMeter, parseUsageFilter } from "./utils/usage-meter.js";
+import type { MinuteUsage } from "./utils/usage-meter.js";
+import { Quotas } from "./utils/quotas.js";
+import type { QuotaSnapshot } from "./utils/quotas.js";
+import { Logger, loggedWhenDone } from "./utils/logger.js";
+import type { ServerSettings } from "./config-file.js";
+import type { ProcessStats } from "./utils/metrics.js";
+import { Recorder } from "./recording/recorder.js";
+import { MemoryCassetteStore } from "./recording/cassette.js";
+import type { CassetteOwner, CassetteStore } from "./recording/cassette.js";
+import {
+  RequestCapture,
+  parseCaptureSettings,
+  parseRequestLogFilter,
+} from "./capture/capture.js";
+import { MemoryRequestLog } from "./capture/request-log.js";
+import { Webhooks, parseWebhooks } from "./webhooks/webhooks.js";
+import {
+  createLatencyMiddleware,
+  parseLatencyConfig,
+  sampleDelay,
+} from "./middleware/latency.js";
+import type { LatencyConfig } from "./middleware/latency.js";
+import { WhitespaceTokenizer } from "./tokenizer/tokenizer.js";
+import type { Tokenizer } from "./tokenizer/tokenizer.js";
+import type {
+  CapturedRequest,
+  RequestLogStore,
+} from "./capture/request-log.js";
+import type { StateStore } from "./persistence/state-store.js";
+import { SessionStore } from "./sessions/session-store.js";
+import { KeywordModerator } from "./openai-protocol/moderations.js";
+import type { ModerationKeywords } from "./openai-protocol/moderations.js";
+import {
+  FileStore,
+  parsePurpose,
+  validateUpload,
+} from "./openai-protocol/files.js";
+import type { FileSnapshot } from "./openai-protocol/files.js";
+import {
+  BatchStore,
+  DEFAULT_BATCH_STEP_MS,
+  parseCreateBatchRequest,
+} from "./openai-protocol/batches.js";
+import type { BatchSnapshot } from "./openai-protocol/batches.js";
+import { AssistantStore } from "./openai-protocol/assistants.js";
+import {
+  ResponseStore,
+  completeResponse,
+  failedEvent,
+  parseCreateResponseRequest,
+  responseEvents,
+} from "./openai-protocol/responses.js";
+import {
+  imageDimensions,
+  parseImageSize,
+  renderPlaceholderPng,
+} from "./openai-protocol/images.js";
+import {
+  OLLAMA_CONTENT_TYPE,
+  ollamaResponse,
+  ollamaStream,
+  parseChatRequest,
+  parseGenerateRequest,
+} from "./ollama-protocol/ollama.js";
+import type { OllamaEndpoint } from "./ollama-protocol/ollama.js";
+import {
+  DEFAULT_REALTIME_MODEL,
+  REALTIME_PATH,
+  RealtimeSession,
+} from "./realtime-protocol/realtime.js";
+import {
+  deploymentModel,
+  requireApiVersion,
+} from "./azure-protocol/azure.js";
+import type { AzureDeployments } from "./azure-protocol/azure.js";
+import {
+  geminiError,
+  geminiResponse,
+  geminiStream,
+  parseGenerateContentRequest,
+  parseModelMethod,
+} from "./gemini-protocol/gemini.js";
+import {
+  DEFAULT_FAULT_CONFIG,
+  FAULT_KINDS,
+  faultyJsonResponse,
+  faultyStreamResponse,
+  parseFaultSettings,
+} from "./openai-protocol/faults.js";
+import type { FaultConfig, FaultSettings } from "./openai-protocol/faults.js";
+import {
+  parseScenarios,
+  playStep,
+  Scenarios,
+  SCENARIO_SESSION_HEADER,
+} from "./openai-protocol/scenarios.js";
+import type { Scenario } from "./openai-protocol/scenarios.js";
+import { CHUNKING_HEADER, parseChunking } from "./openai-protocol/chunking.js";
+import { DETERMINISTIC_HEADER, stampFor } from "./openai-protocol/deterministic.js";
+import {
+  MODEL_DEFAULTS_HEADER,
+  applyModelDefaults,
+  defaultsFor,
+  parseModelDefaults,
+} from "./openai-protocol/model-defaults.js";
+import type { ModelDefaultsConfig } from "./openai-protocol/model-defaults.js";
+import type { Chunking } from "./openai-protocol/chunking.js";
+import {
+  CANNED_TRANSCRIPT,
+  SPEECH_FORMATS,
+  formatTranscription,
+  isSpeechFormat,
+  parseTranscriptionFormat,
+  synthesizeSpeech,
+  wavDurationSeconds,
+} from "./openai-protocol/audio.js";
 
 export interface AppConfig {
   auth: AuthConfig;
+  // Ordered middleware per route group, defaults to DEFAULT_MIDDLEWARE
+  middleware?: MiddlewareConfig;
+  // Idle time after which /session conversations are forgotten
+  sessions?: { ttlMs: number };
+  // Idle time after which the memory model's conversations are forgotten
+  memory?: { ttlMs: number };
+  // Keywords per category that the moderation stub flags
+  moderation?: { keywords: ModerationKeywords };
+  // Largest accepted request body, defaults to DEFAULT_MAX_BODY_BYTES
+  limits?: { maxBodyBytes: number };
+  // Largest file /v1/files accepts, defaults to DEFAULT_MAX_FILE_BYTES; the
+  // body limit applies to uploads too
+  files?: { maxFileBytes: number };
+  // Origins browsers may call from, any by default
+  cors?: CorsConfig;
+  // Encodings responses can be compressed with, none by default
+  compression?: Compressors;
+  // Requests per minute per API key, defaults to DEFAULT_REQUESTS_PER_MINUTE
+  rateLimit?: { requestsPerMinute: number };
+  // Streamed responses open at once, beyond which streams get a 503,
+  // unlimited by default
+  streams?: { maxConcurrent: number };
+  // How long a response is replayed for a repeated Idempotency-Key, defaults
+  // to DEFAULT_IDEMPOTENCY_TTL_MS
+  idempotency?: { ttlMs: number };
+  // How long a prompt prefix is reported as cached after its last use,
+  // defaults to DEFAULT_PROMPT_CACHE_TTL_MS
+  promptCache?: { ttlMs: number };
+  // Tokens each API key may spend before getting insufficient_quota errors,
+  // unlimited by default
+  quotas?: Record<string, number>;
+  // Extra delay before answering a request per service tier that serves it,
+  // none by default
+  serviceTiers?: ServiceTierDelays;
+  // How often the flaky model fails, defaults to DEFAULT_FAULT_CONFIG
+  faults?: FaultConfig;
+  // Scripted outcomes for keys' and sessions' chat completions, none by default
+  scenarios?: Record<string, Scenario>;
+  // Canned responses for the fixture model, which is only available when set
+  fixtures?: FixtureSource;
+  // Text the markov model is trained on, which is only available when set
+  corpus?: string;
+  // Scripted models, served as script:<name> (see models/script-model.ts)
+  scripts?: Record<string, Script>;
+  // Real OpenAI-compatible API that proxy:<model> requests are forwarded to
+  upstream?: UpstreamConfig;
+  // Time each simulated step of a batch takes, defaults to DEFAULT_BATCH_STEP_MS
+  batches?: { stepMs: number };
+  // Models that Azure deployment names map to, each its own model by default
+  azure?: { deployments: AzureDeployments };
+  // Where recorded cassettes are kept, in memory by default
+  cassettes?: CassetteStore;
+  // Where the request log is kept, in memory by default
+  requestLog?: RequestLogStore;
+  // Where keys, usage, files and batches are kept between restarts, nowhere
+  // by default
+  state?: StateStore;
+  // Delays injected per endpoint, none by default
+  latency?: LatencyConfig;
+  // How streamed chat completions are cut into chunks, as the model yields
+  // them by default; the x-teenytiny-chunking header overrides it
+  chunking?: Chunking;
+  // Defaults and clamps per chat model, none by default
+  modelDefaults?: ModelDefaultsConfig;
+  // Counts usage and max_tokens, defaults to the whitespace fallback
+  tokenizer?: Tokenizer;
+  // Version, git sha and build time reported by /version
+  build?: BuildInfo;
+  // Where log lines go and which are written, info and up to stdout by
+  // default
+  logger?: Logger;
+  // Memory and connection counts for /metrics, where the runtime has them
+  processStats?: () => ProcessStats;
+  // Accepts WebSocket connections, for /v1/realtime; without it the
+  // Realtime API isn't served
+  upgradeWebSocket?: UpgradeWebSocket;
+  // Settings from a config file, applied over the rest of this config
+  settings?: ServerSettings;
+  // Reads the config file again, for POST /admin/reload; without it there is
+  // nothing to reload
+  reload?: () => Promise<ServerSettings> | ServerSettings;
+}
+
+export const DEFAULT_MAX_BODY_BYTES = 8 * 1024 * 1024;
+export const DEFAULT_MAX_FILE_BYTES = 4 * 1024 * 1024;
+export const DEFAULT_REQUESTS_PER_MINUTE = 3000;
+
+// Names the conversation the memory model continues, instead of the request's user
+export const CONVERSATION_HEADER = "x-teenytiny-conversation";
+
+// Lets the server's key act for one tenant in the admin API
+export const TENANT_HEADER = "x-teenytiny-tenant";
+
+// Where the memory model keeps a conversation: per API key, so one key can't
+// continue another's
+function conversationKey(
+  apiKey: string,
+  id: string | undefined,
+): string | undefined {
+  return id ? JSON.stringify([apiKey, id]) : undefined;
 }
 
+// Paths that still answer while draining, besides the admin API
+const DRAIN_EXEMPT_PATHS = new Set(["/health", "/healthz", "/readyz", "/version", "/metrics"]);
+
 // Helper function to create pretty-printed JSON responses
 function prettyJson(c: any, data: any) {
   c.header("Content-Type", "application/json");
   return c.body(JSON.stringify(data, null, 2));
 }
 
+// Reads a JSON request body, treating an empty one as {}
+async function readJson(c: Context): Promise<unknown> {
+  const text = await c.req.text();
+  if (text.trim() === "") {
+    return {};
+  }
+  try {
+    return JSON.parse(text);
+  } catch (error) {
+    throw new InvalidRequestError("Invalid JSON in request body");
+  }
+}
+
+// Reads a chat completion request's body, far enough to name its model. The
+// model comes from the path instead for Azure deployments
+async function readChatRequest(
+  c: Context,
+  pathModel?: string,
+): Promise<ChatCompletionRequest> {
+  let request: ChatCompletionRequest;
+  try {
+    request = await c.req.json<ChatCompletionRequest>();
+  } catch (error) {
+    throw new InvalidRequestError("Invalid JSON in request body");
+  }
+
+  if (!request || typeof request !== "object" || Array.isArray(request)) {
+    throw new InvalidRequestError("Request body must be a JSON object");
+  }
+
+  if (pathModel !== undefined) {
+    request.model = pathModel;
+  }
+
+  // Validate required fields
+  if (!request.model) {
+    throw new InvalidRequestError("Missing required parameter: model", "model");
+  }
+
+  if (typeof request.model !== "string") {
+    throw new InvalidRequestError(
+      "Invalid type for 'model': expected a string",
+      "model",
+    );
+  }
+
+  return request;
+}
+
+// Checks a chat completion request the way the local models need it, and
+// normalizes it: legacy functions become tools and developer messages system
+// ones. Returns whether the request used legacy functions
+function validateChatRequest(request: ChatCompletionRequest): boolean {
+  if (request.messages !== undefined && !Array.isArray(request.messages)) {
+    throw new InvalidRequestError(
+      "Invalid type for 'messages': expected an array of messages",
+      "messages",
+    );
+  }
+
+  if (!request.messages || request.messages.length === 0) {
+    throw new InvalidRequestError(
+      "Missing required parameter: messages",
+      "messages",
+    );
+  }
+
+  rejectUnknownParameters(request);
+  validateSamplingParameters(request as unknown as Record<string, unknown>);
+  validateTools(request as unknown as Record<string, unknown>);
+  validateFunctions(request as unknown as Record<string, unknown>);
+  validateResponseFormat(request as unknown as Record<string, unknown>);
+  validateServiceTier(request as unknown as Record<string, unknown>);
+  const legacy = fromLegacyFunctions(request);
+
+  // Validate message structure
+  for (let i = 0; i < request.messages.length; i++) {
+    const message = request.messages[i];
+
+    if (!message || typeof message !== "object") {
+      throw new InvalidRequestError(
+        `Invalid message at index ${i}: must be an object`,
+        "messages",
+      );
+    }
+
+    if (!message.role) {
+      throw new InvalidRequestError(
+        `Invalid message at index ${i}: missing required field 'role'`,
+        "messages",
+      );
+    }
+
+    if (
+      typeof message.role !== "string" ||
+      !["system", "developer", "user", "assistant", "tool"].includes(message.role)
+    ) {
+      throw new InvalidRequestError(
+        `Invalid message at index ${i}: 'role' must be one of 'system', 'developer', 'user', 'assistant', or 'tool'`,
+        "messages",
+      );
+    }
+
+    // Newer SDKs send system prompts as developer messages, which models
+    // treat the same way
+    if ((message.role as string) === "developer") {
+      message.role = "system";
+    }
+
+    if (message.role === "tool" && typeof message.tool_call_id !== "string") {
+      throw new InvalidRequestError(
+        `Invalid message at index ${i}: tool messages require 'tool_call_id'`,
+        "messages",
+      );
+    }
+
+    if (message.name !== undefined && typeof message.name !== "string") {
+      throw new InvalidRequestError(
+        `Invalid message at index ${i}: 'name' must be a string`,
+        "messages",
+      );
+    }
+
+    if (message.tool_calls !== undefined) {
+      validateToolCalls(message, i);
+    }
+
+    // Assistant messages that only call tools may leave content out
+    const hasToolCalls =
+      message.role === "assistant" &&
+      Array.isArray(message.tool_calls) &&
+      message.tool_calls.length > 0;
+    if (
+      (message.content === undefined || message.content === null) &&
+      !hasToolCalls
+    ) {
+      throw new InvalidRequestError(
+        `Invalid message at index ${i}: missing required field 'content'`,
+        "messages",
+      );
+    }
+
+    if (Array.isArray(message.content)) {
+      message.content.forEach((part, j) =>
+        validateContentPart(part, `messages[${i}].content[${j}]`),
+      );
+    } else if (
+      message.content !== undefined &&
+      message.content !== null &&
+      typeof message.content !== "string"
+    ) {
+      throw new InvalidRequestError(
+        `Invalid message at index ${i}: 'content' must be a string or an array of content parts`,
+        "messages",
+      );
+    }
+  }
+
+  return legacy;
+}
+
+// The API areas this build serves, as listed by /version
+function apiSurface(config: AppConfig): string[] {
+  return [
+    "models",
+    "chat.completions",
+    "audio.transcriptions",
+    "audio.speech",
+    "moderations",
+    "images.generations",
+    "files",
+    "batches",
+    "assistants",
+    "responses",
+    "ollama",
+    "gemini",
+    "azure",
+    "sessions",
+    "admin",
+    "cassettes",
+    "requests",
+    "debug",
+    ...(config.upstream ? ["proxy"] : []),
+    ...(config.upgradeWebSocket ? ["realtime"] : []),
+  ];
+}
+
+// Validates the tool_calls an assistant message made on an earlier turn
+function validateToolCalls(
+  message: ChatCompletionRequestMessage,
+  index: number,
+): void {
+  if (message.role !== "assistant" || !Array.isArray(message.tool_calls)) {
+    throw new InvalidRequestError(
+      `Invalid message at index ${index}: 'tool_calls' must be an array on an assistant message`,
+      "messages",
+    );
+  }
+  message.tool_calls.forEach((call: any, j) => {
+    if (
+      typeof call?.id !== "string" ||
+      typeof call.function?.name !== "string" ||
+      typeof call.function.arguments !== "string"
+    ) {
+      throw new InvalidRequestError(
+        `Invalid tool call at messages[${index}].tool_calls[${j}]: expected an id, a function name and string arguments`,
+        "messages",
+      );
+    }
+  });
+}
+
+// Validates one entry of a multimodal content array, naming its path on failure
+function validateContentPart(part: any, path: string) {
+  if (!part || typeof part !== "object") {
+    throw new InvalidRequestError(
+      `Invalid content part at ${path}: must be an object`,
+      "messages",
+    );
+  }
+
+  if (part.type === "text") {
+    if (typeof part.text !== "string") {
+      throw new InvalidRequestError(
+        `Invalid content part at ${path}.text: must be a string`,
+        "messages",
+      );
+    }
+  } else if (part.type === "image_url") {
+    if (!part.image_url || typeof part.image_url.url !== "string") {
+      throw new InvalidRequestError(
+        `Invalid content part at ${path}.image_url.url: must be a string`,
+        "messages",
+      );
+    }
+    if (!/^(https?:|data:image\/)/.test(part.image_url.url)) {
+      throw new InvalidRequestError(
+        `Invalid content part at ${path}.image_url.url: expected an http(s) URL or a data:image/ URL`,
+        "messages",
+      );
+    }
+  } else {
+    throw new InvalidRequestError(
+      `Invalid content part at ${path}.type: must be one of 'text' or 'image_url'`,
+      "messages",
+    );
+  }
+}
+
 export function createApp(config: AppConfig) {
+  const middlewareConfig = config.middleware ?? DEFAULT_MIDDLEWARE;
+  validateMiddlewareConfig(middlewareConfig);
+
   const app = new Hono<{ Variables: Variables }>();
 
   // Initialize authenticator with fallback chain for graceful migration to new key formats
-  const authenticator: Authenticator = new FallbackKeyAuthenticator([
-    // Primary: EncryptedKeyAuthenticator - generates new secure encrypted keys (~52 chars, AES-256-GCM)
-    new EncryptedKeyAuthenticator(
-      config.auth.apiKey /* for lack of a better secret */,
-    ),
-    // Fallback: SingleKeyAuthenticator - accepts legacy hardcoded keys for backward compatibility
-    new SingleKeyAuthenticator(config.auth.apiKey),
-  ]);
+  const scopedKeys = new ScopedKeyAuthenticator(config.auth.keys ?? []);
+  const authenticator = new RevokedKeyAuthenticator(
+    new FallbackKeyAuthenticator([
+      // Primary: EncryptedKeyAuthenticator - generates new secure encrypted keys (~52 chars, AES-256-GCM)
+      new EncryptedKeyAuthenticator(
+        config.auth.apiKey /* for lack of a better secret */,
+      ),
+      // Fallback: SingleKeyAuthenticator - accepts legacy hardcoded keys for backward compatibility
+      new SingleKeyAuthenticator(config.auth.apiKey),
+      // Fallback: ScopedKeyAuthenticator - additional provisioned keys, optionally model-restricted
+      scopedKeys,
+    ]),
+    config.auth.revokedKeys ?? [],
+  );
+
+  // Keys provisioned with a model allowlist may only use those models
+  function checkModelAccess(apiKey: string, model: string) {
+    const allowedModels = scopedKeys.allowedModels(apiKey);
+    if (allowedModels && !allowedModels.includes(model)) {
+      throw new PermissionDeniedError(
+        `This API key does not have access to model \`${model}\``,
+        "model_not_allowed",
+      );
+    }
+  }
+
+  // Only the server's own key may change its configuration at runtime
+  function checkAdminAccess(apiKey: string) {
+    if (apiKey !== config.auth.apiKey) {
+      throw new PermissionDeniedError(
+        "Admin endpoints require the server's API key",
+        "admin_required",
+      );
+    }
+  }
+
+  // The tenant an admin request acts for: the caller's key's, or the one the
+  // server's key names in the tenant header. The server's key without the
+  // header acts for every tenant.
+  function tenantOf(c: Context<{ Variables: Variables }>): string | undefined {
+    const apiKey = c.get("apiKey");
+    const named = c.req.header(TENANT_HEADER);
+    if (apiKey === config.auth.apiKey) {
+      return named || undefined;
+    }
+    const own = scopedKeys.tenant(apiKey);
+    if (named !== undefined && named !== own) {
+      throw new PermissionDeniedError(
+        "Only the server's API key may act for another tenant",
+        "tenant_override_not_allowed",
+      );
+    }
+    return own;
+  }
+
+  // Admin endpoints that look at keys answer the server's key, and keys of a
+  // tenant for their own tenant's keys. Changing them takes the server's key.
+  function checkTenantAccess(
+    c: Context<{ Variables: Variables }>,
+  ): string | undefined {
+    const tenant = tenantOf(c);
+    if (tenant === undefined) {
+      checkAdminAccess(c.get("apiKey"));
+    }
+    return tenant;
+  }
+
+  // Another tenant's key is answered as if it didn't exist
+  function checkTenantKey(tenant: string | undefined, key: string) {
+    if (tenant !== undefined && scopedKeys.tenant(key) !== tenant) {
+      throw new NotFoundError(`No such API key in tenant '${tenant}'`);
+    }
+  }
+
+  // Admin bodies whose fields are all optional: a missing or unreadable body
+  // reads as no fields, but JSON that isn't an object is refused
+  async function optionalBody(
+    c: Context<{ Variables: Variables }>,
+  ): Promise<Record<string, any>> {
+    const body = await c.req.json().catch(() => ({}));
+    if (!body || typeof body !== "object" || Array.isArray(body)) {
+      throw new InvalidRequestError("Request body must be a JSON object");
+    }
+    return body;
+  }
+
+  // Narrows a request log or usage query to the keys the caller may see: a
+  // tenant's keys, or a key's own without a tenant. The server's key sees
+  // every key. Any of them can pick one key they see with ?key=.
+  function scopeToKeys(
+    c: Context<{ Variables: Variables }>,
+    filter: { apiKey?: string; apiKeys?: string[] },
+  ) {
+    const apiKey = c.get("apiKey");
+    const key = c.req.query("key");
+    const tenant = tenantOf(c);
+    if (tenant !== undefined) {
+      filter.apiKeys = scopedKeys.tenantKeys(tenant);
+      if (key !== undefined) {
+        checkTenantKey(tenant, key);
+        filter.apiKey = key;
+      }
+    } else if (apiKey !== config.auth.apiKey) {
+      filter.apiKey = apiKey;
+    } else if (key !== undefined) {
+      filter.apiKey = key;
+    }
+  }
+
+  // Settings the admin API can change while the server runs
+  const faults: FaultConfig = { ...(config.faults ?? DEFAULT_FAULT_CONFIG) };
+  let requestsPerMinute =
+    config.rateLimit?.requestsPerMinute ?? DEFAULT_REQUESTS_PER_MINUTE;
+  let latency: LatencyConfig = config.latency ?? {};
+  let modelDefaults: ModelDefaultsConfig = config.modelDefaults ?? {};
+  // Templates by name, set with PUT /admin/templates
+  let templates: Record<string, string> = {};
+  // Routers by name, set by the config file
+  let routers: Record<string, Router> = {};
 
   // Initialize model registries
   const coreRegistry = new ModelRegistry();
-  const openaiRegistry = new OpenAIModelRegistry(coreRegistry);
+  const tokenizer = config.tokenizer ?? new WhitespaceTokenizer();
+  const openaiRegistry = new OpenAIModelRegistry(coreRegistry, tokenizer);
 
   // Register models directly without any modelware decorations for fast responses
-  openaiRegistry.register("echo", new EchoModel());
+  openaiRegistry.register("echo", new EchoModel(), { directives: true });
   openaiRegistry.register("eliza", new ElizaModel());
   openaiRegistry.register("parry", new ParryModel());
   openaiRegistry.register("racter", new RacterModel());
+  openaiRegistry.register("lorem", new LoremModel());
+  openaiRegistry.register("slow", new SlowModel());
+  openaiRegistry.register("flaky", new EchoModel(), {
+    directives: true,
+    faults,
+  });
+  openaiRegistry.register("tooluse", new EchoModel(), { toolCalls: true });
+  openaiRegistry.register("json", new JsonModel());
+  openaiRegistry.register("filtered", new FilteredModel());
+  openaiRegistry.register("reasoning", new EchoModel(), { reasoning: true });
+  openaiRegistry.register("memory", new MemoryModel(), { memory: true });
+  openaiRegistry.register(
+    "template",
+    new TemplateModel(() => templates.default ?? DEFAULT_TEMPLATE),
+  );
+  if (config.fixtures) {
+    openaiRegistry.register("fixture", new FixtureModel(config.fixtures));
+  }
+  if (config.corpus) {
+    openaiRegistry.register("markov", new MarkovModel(config.corpus));
+  }
+  for (const [name, script] of Object.entries(config.scripts ?? {})) {
+    openaiRegistry.register(`script:${name}`, new ScriptModel(name, script));
+  }
+  openaiRegistry.registerVariants("slow", (suffix) => {
+    const interval = parseInterval(suffix);
+    return interval === undefined ? undefined : new SlowModel(interval);
+  });
+  openaiRegistry.registerVariants("template", (name) => {
+    const template = Object.hasOwn(templates, name) ? templates[name] : undefined;
+    return template === undefined ? undefined : new TemplateModel(() => template);
+  });
+  openaiRegistry.registerVariants("router", (name) => {
+    const router = Object.hasOwn(routers, name) ? routers[name] : undefined;
+    return router && new RouterModel(router, (id) => openaiRegistry.model(id));
+  });
+
+  // Aliases so clients hardcoded to OpenAI model names work out of the box
+  openaiRegistry.alias("gpt-3.5-turbo", "echo");
+  openaiRegistry.alias("gpt-4o-mini", "echo");
+  openaiRegistry.alias("o1-mini", "reasoning");
+
+  const logger = config.logger ?? new Logger();
+  const metrics = new Metrics(config.processStats);
+  const streamLimiter = new StreamLimiter(config.streams?.maxConcurrent);
+  const drain = new Drain();
+  const sessions = new SessionStore(config.sessions?.ttlMs);
+  const memory = new SessionStore(config.memory?.ttlMs);
+  const moderator = new KeywordModerator(config.moderation?.keywords);
+  const rateLimiter = new RateLimiter();
+  const idempotency = new IdempotencyCache(config.idempotency?.ttlMs);
+  const promptCache = new PromptCache(tokenizer, config.promptCache?.ttlMs);
+  const usageMeter = new UsageMeter();
+  const quotas = new Quotas(config.quotas);
+  const requestLog = config.requestLog ?? new MemoryRequestLog();
+  const capture = new RequestCapture(requestLog);
+  const webhooks = new Webhooks(logger);
+  const scenarios = new Scenarios();
+  if (config.scenarios) {
+    scenarios.replace(config.scenarios);
+  }
+  const files = new FileStore();
+  // Each request of a batch goes through the app again, as its owner
+  const batches = new BatchStore(
+    files,
+    (apiKey, url, body) =>
+      Promise.resolve(
+        app.request(url, {
+          method: "POST",
+          headers: {
+            Authorization: `Bearer ${apiKey}`,
+            "Content-Type": "application/json",
+          },
+          body: JSON.stringify(body),
+        }),
+      ),
+    config.batches?.stepMs ?? DEFAULT_BATCH_STEP_MS,
+  );
+  // Keys created through the admin API. Only these are kept between restarts:
+  // keys from the config file or TEENYTINY_API_KEYS are read from there again,
+  // so taking one out of the file or the environment stops it working.
+  const provisionedKeys = new Set<string>();
+  // Keys given a budget through the admin API, kept between restarts as the
+  // keys are, while the config file's budgets are read from it again
+  const budgetedKeys = new Set<string>();
+  // The state kept between restarts when there's a state store, read back
+  // before the config file is applied so the file's keys win
+  const persistedState: Record<
+    string,
+    {
+      version: () => number;
+      snapshot: () => unknown;
+      restore: (value: any) => void;
+    }
+  > = {
+    keys: {
+      version: () => scopedKeys.version + authenticator.version,
+      snapshot: () => ({
+        keys: scopedKeys.list().filter(({ key }) => provisionedKeys.has(key)),
+        revoked: authenticator.list(),
+      }),
+      restore: ({ keys, revoked }: { keys: ScopedKey[]; revoked: string[] }) => {
+        keys.forEach((key) => {
+          scopedKeys.add(key);
+          provisionedKeys.add(key.key);
+        });
+        revoked.forEach((key) => authenticator.revoke(key));
+      },
+    },
+    usage: {
+      version: () => usageMeter.version,
+      snapshot: () => usageMeter.snapshot(),
+      restore: (minutes: MinuteUsage[]) => usageMeter.restore(minutes),
+    },
+    files: {
+      version: () => files.version,
+      snapshot: () => files.snapshot(),
+      restore: (stored: FileSnapshot[]) => files.restore(stored),
+    },
+    batches: {
+      version: () => batches.version,
+      snapshot: () => batches.snapshot(),
+      restore: (stored: BatchSnapshot[]) => batches.restore(stored),
+    },
+    // Budgets set with PUT /admin/quotas, and what every budgeted key spent
+    quotas: {
+      version: () => quotas.version,
+      snapshot: () => {
+        const { budgets, used } = quotas.snapshot();
+        return {
+          budgets: Object.fromEntries(
+            Object.entries(budgets).filter(([key]) => budgetedKeys.has(key)),
+          ),
+          used,
+        };
+      },
+      restore: (stored: QuotaSnapshot) => {
+        quotas.restore(stored);
+        Object.keys(stored.budgets).forEach((key) => budgetedKeys.add(key));
+      },
+    },
+  };
+  // A request log of its own, such as a SQLite one, keeps itself
+  if (requestLog instanceof MemoryRequestLog) {
+    persistedState.requests = {
+      version: () => requestLog.version,
+      snapshot: () => requestLog.snapshot(),
+      restore: (entries: CapturedRequest[]) => requestLog.restore(entries),
+    };
+  }
+  // The version of each part last saved, or restored
+  const savedVersions = new Map<string, number>();
+  if (config.state) {
+    for (const [name, part] of Object.entries(persistedState)) {
+      const json = config.state.load(name);
+      if (json !== undefined) {
+        part.restore(JSON.parse(json));
+      }
+      savedVersions.set(name, part.version());
+    }
+  }
+  // Keys and budgets the config file added, replaced when it's reloaded
+  let settingsKeys: string[] = [];
+  let settingsQuotas: string[] = [];
+  if (config.settings) {
+    applySettings(config.settings);
+  }
+  const recorder = new Recorder(config.cassettes ?? new MemoryCassetteStore());
+  // Runs complete in the background, so are metered as they go
+  const assistants = new AssistantStore(
+    (apiKey, model) => {
+      const adapter = openaiRegistry.get(model);
+      if (!adapter) {
+        throw new ModelNotFoundError(model);
+      }
+      checkModelAccess(apiKey, model);
+      quotas.check(apiKey);
+      return adapter;
+    },
+    charge,
+  );
+  const responses = new ResponseStore();
+
+  // Saves the parts of the state that changed since they were last saved.
+  // Each store counts its changes, so a request that changed nothing, such as
+  // a health check, serializes nothing.
+  function saveState() {
+    if (!config.state) {
+      return;
+    }
+    for (const [name, part] of Object.entries(persistedState)) {
+      const version = part.version();
+      if (savedVersions.get(name) !== version) {
+        config.state.save(name, JSON.stringify(part.snapshot()));
+        savedVersions.set(name, version);
+      }
+    }
+  }
+
+  // Meters a key's usage and charges it to the key's quota
+  function charge(apiKey: string, model: string, usage: ChatCompletionUsage) {
+    usageMeter.record(apiKey, model, usage);
+    quotas.spend(apiKey, usage.total_tokens);
+  }
+
+  // Meters a request's usage, charges it to the key's quota and logs it
+  function meter(
+    c: Context<{ Variables: Variables }>,
+    model: string,
+    usage: ChatCompletionUsage,
+  ) {
+    charge(c.get("apiKey"), model, usage);
+    c.set("usage", usage);
+  }
+
+  // Takes a place for a streamed response, returning the function that gives
+  // it back, or turns the request away when the server has too many open. The
+  // stream is in flight until then, so a drain waits for it.
+  function admitStream(c: Context<{ Variables: Variables }>): () => void {
+    const override = Number(c.req.header(MAX_STREAMS_HEADER));
+    const release = streamLimiter.acquire(
+      c.get("apiKey"),
+      Number.isInteger(override) && override > 0 ? override : undefined,
+    );
+    if (!release) {
+      c.header("Retry-After", String(STREAM_RETRY_AFTER_SECONDS));
+      throw new ServerOverloadedError(
+        "The server has too many streams open, please try again shortly",
+      );
+    }
+    const finished = drain.track();
+    return () => {
+      release();
+      finished();
+    };
+  }
 
-  // Global middleware (applies to all routes)
-  app.use("*", corsMiddleware());
-  app.use("*", createLoggingMiddleware());
+  // Passes a stream through, metering the usage its final chunk carries
+  async function* metered(
+    c: Context<{ Variables: Variables }>,
+    model: string,
+    chunks: AsyncIterable<ChatCompletionStreamResponse>,
+  ): AsyncIterable<ChatCompletionStreamResponse> {
+    for await (const chunk of chunks) {
+      if (chunk.usage) {
+        meter(c, model, chunk.usage);
+      }
+      yield chunk;
+    }
+  }
 
-  // Auth middleware (only for API routes)
-  app.use("/v1/*", createAuthMiddleware(authenticator));
+  // Middleware stack, in the configured order per route group
+  const middlewareFactories: Record<MiddlewareName, () => MiddlewareHandler> = {
+    cors: () => corsMiddleware(config.cors),
+    logging: () => createLoggingMiddleware(logger),
+    compression: () => createCompressionMiddleware(config.compression ?? {}),
+    auth: () => createAuthMiddleware(authenticator, config.auth),
+    "rate-limit": () =>
+      createRateLimitMiddleware(rateLimiter, () => requestsPerMinute),
+    "body-limit": () =>
+      createBodyLimitMiddleware(
+        config.limits?.maxBodyBytes ?? DEFAULT_MAX_BODY_BYTES,
+      ),
+    capture: () => capture.middleware(),
+    webhooks: () => webhooks.middleware(),
+    idempotency: () => idempotency.middleware(),
+    recorder: () => recorder.middleware(),
+    latency: () => createLatencyMiddleware(() => latency),
+  };
+  // Saves what a request changed once its response is done, streams included
+  if (config.state) {
+    app.use("*", async (c, next) => {
+      await next();
+      const stream = (c.res.headers.get("content-type") ?? "").startsWith(
+        "text/event-stream",
+      );
+      if (stream && c.res.body) {
+        c.res = new Response(loggedWhenDone(c.res.body, saveState), c.res);
+      } else {
+        saveState();
+      }
+    });
+  }
+  // Once draining, new requests are turned away before any other middleware
+  // sees them. Health checks, metrics and the admin API still answer, so the
+  // drain can be watched.
+  app.use("*", async (c, next) => {
+    if (DRAIN_EXEMPT_PATHS.has(c.req.path) || c.req.path.startsWith("/admin/")) {
+      return next();
+    }
+    if (drain.draining) {
+      c.header("Retry-After", String(DRAIN_RETRY_AFTER_SECONDS));
+      throw new ServerOverloadedError(
+        "The server is shutting down, please try again shortly",
+      );
+    }
+    const finished = drain.track();
+    try {
+      await next();
+    } finally {
+      finished();
+    }
+  });
+  for (const [route, names] of Object.entries(middlewareConfig)) {
+    for (const name of names) {
+      app.use(route, middlewareFactories[name]());
+    }
+  }
 
   // Error handler
   app.onError(createErrorHandler());
@@ -78,116 +1058,219 @@ export function createApp(config: AppConfig) {
     });
   });
 
+  // Liveness: the process is up and answering
+  app.get("/healthz", (c) => {
+    return prettyJson(c, { status: "ok" });
+  });
+
+  // Readiness: there are models to serve requests with, and the server isn't
+  // draining
+  app.get("/readyz", (c) => {
+    const modelCount = openaiRegistry.listAsResponse().data.length;
+    const ready = modelCount > 0 && !drain.draining;
+    c.status(ready ? 200 : 503);
+    return prettyJson(c, {
+      status: drain.draining ? "draining" : ready ? "ready" : "not_ready",
+      checks: { models: modelCount, draining: drain.draining },
+    });
+  });
+
+  // Build metadata, so test reports can name the server build under test
+  app.get("/version", (c) => {
+    const build = config.build ?? buildInfo({});
+    return prettyJson(c, {
+      service: "teenytiny-api",
+      version: build.version,
+      git_sha: build.gitSha,
+      build_time: build.buildTime,
+      tokenizer: tokenizer.name,
+      api_surface: apiSurface(config),
+      limits: {
+        max_body_bytes: config.limits?.maxBodyBytes ?? DEFAULT_MAX_BODY_BYTES,
+        max_file_bytes: config.files?.maxFileBytes ?? DEFAULT_MAX_FILE_BYTES,
+      },
+    });
+  });
+
+  // Metrics endpoint
+  app.get("/metrics", (c) => {
+    return prettyJson(c, {
+      ...metrics.snapshot(),
+      ...streamLimiter.snapshot(),
+    });
+  });
+
   // Models endpoint
   app.get("/v1/models", (c) => {
     const response = openaiRegistry.listAsResponse();
 
-    console.log(
-      JSON.stringify({
-        level: "info",
-        message: "Models listed",
-        request_id: c.get("requestId"),
-        model_count: response.data.length,
-      }),
-    );
+    logger.debug("Models listed", {
+      request_id: c.get("requestId"),
+      model_count: response.data.length,
+    });
 
     return prettyJson(c, response);
   });
 
-  // Chat completions endpoint
-  app.post("/v1/chat/completions", async (c) => {
+  // Chat completions, for /v1 and for Azure deployments, whose model comes
+  // from the path rather than the body
+  async function chatCompletions(
+    c: Context<{ Variables: Variables }>,
+    pathModel?: string,
+  ) {
     const requestId = c.get("requestId") as string;
+    const chunkingHeader = c.req.header(CHUNKING_HEADER);
+    const chunking =
+      chunkingHeader === undefined
+        ? config.chunking
+        : parseChunking(chunkingHeader);
+    // A seed to derive the id and timestamps from, for byte-stable responses
+    const seed = c.req.header(DETERMINISTIC_HEADER);
+    const stamp = seed === undefined ? undefined : stampFor(seed);
 
-    // Parse and validate request
-    let request: ChatCompletionRequest;
-    try {
-      request = await c.req.json<ChatCompletionRequest>();
-    } catch (error) {
-      throw new InvalidRequestError("Invalid JSON in request body");
-    }
+    const request = await readChatRequest(c, pathModel);
 
-    // Validate required fields
-    if (!request.model) {
-      throw new InvalidRequestError(
-        "Missing required parameter: model",
-        "model",
-      );
-    }
+    // Proxied requests skip local validation, so whatever the upstream
+    // accepts or rejects is what the client sees
+    if (request.model.startsWith(PROXY_PREFIX) && config.upstream) {
+      const model = request.model.slice(PROXY_PREFIX.length);
+      checkModelAccess(c.get("apiKey"), request.model);
 
-    if (!request.messages || request.messages.length === 0) {
-      throw new InvalidRequestError(
-        "Missing required parameter: messages",
-        "messages",
+      logger.debug("Proxying chat completion request", {
+        request_id: requestId,
+        model,
+        upstream: config.upstream.baseUrl,
+      });
+
+      return forwardChatCompletion(
+        config.upstream,
+        request as unknown as Record<string, unknown>,
+        model,
+        c.req.raw.signal,
       );
     }
 
-    // Validate message structure
-    for (let i = 0; i < request.messages.length; i++) {
-      const message = request.messages[i];
+    const legacy = validateChatRequest(request);
 
-      if (!message || typeof message !== "object") {
-        throw new InvalidRequestError(
-          `Invalid message at index ${i}: must be an object`,
-          "messages",
-        );
-      }
+    // Get model adapter
+    c.set("model", request.model);
+    const adapter = openaiRegistry.get(request.model);
+    if (!adapter) {
+      throw new ModelNotFoundError(request.model);
+    }
+    checkModelAccess(c.get("apiKey"), request.model);
+    quotas.check(c.get("apiKey"));
 
-      if (!message.role) {
-        throw new InvalidRequestError(
-          `Invalid message at index ${i}: missing required field 'role'`,
-          "messages",
-        );
+    // Models that remember are sent the conversation so far, and this turn is
+    // added to it once it's answered
+    const conversation = adapter.remembers
+      ? conversationKey(
+          c.get("apiKey"),
+          c.req.header(CONVERSATION_HEADER) ?? request.user,
+        )
+      : undefined;
+    const sent = request.messages;
+    if (conversation !== undefined) {
+      request.messages = [...memory.history(conversation), ...sent];
+    }
+    const remember = (reply: ChatCompletionRequestMessage) => {
+      if (conversation !== undefined) {
+        memory.append(conversation, ...sent, reply);
       }
+    };
 
-      if (
-        typeof message.role !== "string" ||
-        !["system", "user", "assistant"].includes(message.role)
-      ) {
-        throw new InvalidRequestError(
-          `Invalid message at index ${i}: 'role' must be one of 'system', 'user', or 'assistant'`,
-          "messages",
-        );
-      }
+    const applied = applyModelDefaults(
+      request,
+      defaultsFor(modelDefaults, request.model),
+    );
+    if (applied.length > 0) {
+      c.header(MODEL_DEFAULTS_HEADER, applied.join(", "));
+    }
+    // A scenario's next step, when one is scripted for this session or key,
+    // takes the place of whatever the model would do
+    const step = scenarios.next(
+      c.get("apiKey"),
+      c.req.header(SCENARIO_SESSION_HEADER),
+    );
+    if (step?.retryAfter !== undefined) {
+      c.header("Retry-After", String(step.retryAfter));
+    }
+    const fault =
+      step && step.outcome !== "ok"
+        ? playStep(step)
+        : adapter.preflight(request);
 
-      if (message.content === undefined || message.content === null) {
-        throw new InvalidRequestError(
-          `Invalid message at index ${i}: missing required field 'content'`,
-          "messages",
-        );
-      }
+    const isStreaming = request.stream === true;
 
-      if (typeof message.content !== "string") {
-        throw new InvalidRequestError(
-          `Invalid message at index ${i}: 'content' must be a string`,
-          "messages",
-        );
+    // Usage is metered, long prompts report what the prompt cache would have
+    // served, requests naming a service tier report the tier that served
+    // them, and answers to the deprecated 'functions' take the legacy
+    // function_call shape
+    const report = (usage: ChatCompletionUsage) => {
+      const reported = withCachedTokens(
+        usage,
+        promptCache.use(c.get("apiKey"), request.messages, usage.prompt_tokens),
+      );
+      meter(c, request.model, reported);
+      return reported;
+    };
+    const tier = servedTier(request.service_tier);
+    const completeStream = async function* (signal: AbortSignal) {
+      let reply = "";
+      const chunks = adapter.completeStream(request, signal, chunking);
+      for await (const chunk of legacy ? toLegacyStream(chunks) : chunks) {
+        reply += chunk.choices.find((choice) => choice.index === 0)?.delta.content ?? "";
+        yield {
+          ...chunk,
+          ...stamp,
+          ...(chunk.usage ? { usage: report(chunk.usage) } : {}),
+          ...(tier ? { service_tier: tier } : {}),
+        };
       }
-    }
+      if (!signal.aborted) {
+        remember({ role: "assistant", content: reply });
+      }
+    };
+    const complete = async () => {
+      const response = await adapter.complete(request, c.req.raw.signal);
+      remember(response.choices[0]!.message);
+      response.usage = report(response.usage);
+      if (tier) {
+        response.service_tier = tier;
+      }
+      if (stamp) {
+        Object.assign(response, stamp);
+      }
+      return legacy ? toLegacyResponse(response) : response;
+    };
 
-    // Get model adapter
-    const adapter = openaiRegistry.get(request.model);
-    if (!adapter) {
-      throw new InvalidRequestError(
-        `Model not found: ${request.model}`,
-        "model",
-      );
+    const tierDelay = config.serviceTiers?.[tier ?? "default"];
+    if (tierDelay) {
+      await sleep(sampleDelay(tierDelay), c.req.raw.signal);
     }
 
-    const isStreaming = request.stream === true;
+    logger.debug("Chat completion request", {
+      request_id: requestId,
+      model: request.model,
+      message_count: request.messages.length,
+      streaming: isStreaming,
+    });
 
-    console.log(
-      JSON.stringify({
-        level: "info",
-        message: "Chat completion request",
+    if (fault) {
+      logger.debug("Injecting fault", {
         request_id: requestId,
         model: request.model,
-        message_count: request.messages.length,
-        streaming: isStreaming,
-      }),
-    );
+        fault,
+      });
+
+      return isStreaming
+        ? faultyStreamResponse(completeStream(c.req.raw.signal), fault)
+        : faultyJsonResponse(await complete(), fault);
+    }
 
     if (isStreaming) {
       // Streaming response
+      const release = admitStream(c);
       return stream(c, async (stream) => {
         c.header("Content-Type", "text/event-stream");
         c.header("Cache-Control", "no-cache");
@@ -195,8 +1278,13 @@ export function createApp(config: AppConfig) {
 
         let totalTokens = 0;
 
+        // Propagate client disconnects down to the model so it stops generating
+        const cancellation = new AbortController();
+        stream.onAbort(() => cancellation.abort());
+        metrics.streamStarted(requestId);
+
         try {
-          for await (const chunk of adapter.completeStream(request)) {
+          for await (const chunk of completeStream(cancellation.signal)) {
             // Track token usage from final chunk
             if (chunk.usage) {
               totalTokens = chunk.usage.total_tokens;
@@ -205,56 +1293,1530 @@ export function createApp(config: AppConfig) {
             await stream.write(`data: ${JSON.stringify(chunk)}\n\n`);
           }
 
-          await stream.write("data: [DONE]\n\n");
+          if (cancellation.signal.aborted) {
+            metrics.cancelledGenerations++;
 
-          console.log(
-            JSON.stringify({
-              level: "info",
-              message: "Streaming completion finished",
+            logger.debug("Streaming completion cancelled", {
               request_id: requestId,
               model: request.model,
-              total_tokens: totalTokens,
-            }),
-          );
+            });
+            return;
+          }
+
+          await stream.write("data: [DONE]\n\n");
+
+          logger.debug("Streaming completion finished", {
+            request_id: requestId,
+            model: request.model,
+            total_tokens: totalTokens,
+          });
         } catch (error) {
-          console.error(
-            JSON.stringify({
-              level: "error",
-              message: "Streaming completion failed",
-              request_id: requestId,
-              error: error instanceof Error ? error.message : String(error),
-            }),
-          );
+          logger.error("Streaming completion failed", {
+            request_id: requestId,
+            error: error instanceof Error ? error.message : String(error),
+          });
 
           await stream.write(
             `data: ${JSON.stringify({
               error: {
                 message: "Streaming failed",
                 type: "api_error",
+                param: null,
+                code: null,
               },
             })}\n\n`,
           );
+        } finally {
+          metrics.streamEnded(requestId);
+          release();
         }
       });
     } else {
       // Non-streaming response
-      const response = await adapter.complete(request);
+      const response = await complete();
 
-      console.log(
-        JSON.stringify({
-          level: "info",
-          message: "Chat completion completed",
+      if (c.req.raw.signal.aborted) {
+        metrics.cancelledGenerations++;
+
+        logger.debug("Chat completion cancelled", {
           request_id: requestId,
           model: request.model,
-          prompt_tokens: response.usage.prompt_tokens,
-          completion_tokens: response.usage.completion_tokens,
-        }),
+        });
+        return prettyJson(c, response);
+      }
+
+      logger.debug("Chat completion completed", {
+        request_id: requestId,
+        model: request.model,
+        prompt_tokens: response.usage.prompt_tokens,
+        completion_tokens: response.usage.completion_tokens,
+      });
+
+      return prettyJson(c, response);
+    }
+  }
+
+  app.post("/v1/chat/completions", (c) => chatCompletions(c));
+
+  // Azure OpenAI's deployment paths, e.g.
+  // /openai/deployments/gpt-4o/chat/completions?api-version=2024-10-21
+  app.post("/openai/deployments/:deployment/chat/completions", (c) => {
+    requireApiVersion(c.req.query("api-version"));
+    return chatCompletions(
+      c,
+      deploymentModel(config.azure?.deployments, c.req.param("deployment")),
+    );
+  });
+
+  // A chat completion request as the model would get it: validated,
+  // normalized and with the model's defaults applied, but not run. Tests use
+  // it to check parameters reach the server rather than only not breaking it
+  app.post("/debug/parse", async (c) => {
+    const request = await readChatRequest(c);
+    const legacy = validateChatRequest(request);
+    if (!openaiRegistry.get(request.model)) {
+      throw new ModelNotFoundError(request.model);
+    }
+    checkModelAccess(c.get("apiKey"), request.model);
+    const applied = applyModelDefaults(
+      request,
+      defaultsFor(modelDefaults, request.model),
+    );
+
+    return prettyJson(c, {
+      request,
+      applied_defaults: applied,
+      legacy_functions: legacy,
+    });
+  });
+
+  // OpenAI's Realtime API over a WebSocket, where the runtime has them
+  const upgradeWebSocket = config.upgradeWebSocket;
+  if (upgradeWebSocket) {
+    app.get(
+      REALTIME_PATH,
+      async (c, next) => {
+        if (c.req.header("upgrade")?.toLowerCase() !== "websocket") {
+          throw new APIError(
+            `${REALTIME_PATH} only accepts WebSocket connections`,
+            ErrorTypes.INVALID_REQUEST,
+            426,
+          );
+        }
+        await next();
+      },
+      upgradeWebSocket((c) => {
+        const requestId = c.get("requestId") as string;
+        const model = c.req.query("model") ?? DEFAULT_REALTIME_MODEL;
+        const adapter = openaiRegistry.get(model);
+        if (!adapter) {
+          throw new ModelNotFoundError(model);
+        }
+        const apiKey = c.get("apiKey");
+        checkModelAccess(apiKey, model);
+        quotas.check(apiKey);
+
+        let session: RealtimeSession | undefined;
+        return {
+          onOpen: (_event, ws) => {
+            session = new RealtimeSession(
+              adapter,
+              model,
+              (event) => ws.send(JSON.stringify(event)),
+              {
+                check: () => quotas.check(apiKey),
+                record: (usage) => charge(apiKey, model, usage),
+              },
+            );
+            metrics.streamStarted(requestId);
+            logger.debug("Realtime session opened", {
+              request_id: requestId,
+              session_id: session.id,
+              model,
+            });
+            session.start();
+          },
+          onMessage: (event) => {
+            if (typeof event.data === "string") {
+              void session?.handle(event.data);
+            }
+          },
+          onClose: () => {
+            session?.close();
+            metrics.streamEnded(requestId);
+          },
+        };
+      }),
+    );
+  }
+
+  // Ollama's chat and generate endpoints, answered by the same adapters as /v1
+  async function ollama(
+    c: Context<{ Variables: Variables }>,
+    endpoint: OllamaEndpoint,
+  ) {
+    const requestId = c.get("requestId") as string;
+    const startedAt = Date.now();
+
+    let body: unknown;
+    try {
+      body = await c.req.json();
+    } catch (error) {
+      throw new InvalidRequestError("Invalid JSON in request body");
+    }
+    const request =
+      endpoint === "chat" ? parseChatRequest(body) : parseGenerateRequest(body);
+
+    c.set("model", request.model);
+    const adapter = openaiRegistry.get(request.model);
+    if (!adapter) {
+      throw new ModelNotFoundError(request.model);
+    }
+    checkModelAccess(c.get("apiKey"), request.model);
+    quotas.check(c.get("apiKey"));
+    // Status faults are thrown here; ones that break a response partway are
+    // particular to OpenAI's wire format, so Ollama replies go out whole
+    adapter.preflight(request);
+
+    logger.debug("Ollama request", {
+      request_id: requestId,
+      endpoint,
+      model: request.model,
+      streaming: request.stream,
+    });
+
+    if (!request.stream) {
+      const response = await adapter.complete(request, c.req.raw.signal);
+      meter(c, request.model, response.usage);
+      return prettyJson(
+        c,
+        ollamaResponse(endpoint, request.model, response, startedAt),
+      );
+    }
+
+    const release = admitStream(c);
+    return stream(c, async (stream) => {
+      c.header("Content-Type", OLLAMA_CONTENT_TYPE);
+      c.header("Cache-Control", "no-cache");
+
+      const cancellation = new AbortController();
+      stream.onAbort(() => cancellation.abort());
+      metrics.streamStarted(requestId);
+
+      try {
+        for await (const line of ollamaStream(
+          endpoint,
+          request.model,
+          metered(
+            c,
+            request.model,
+            adapter.completeStream(request, cancellation.signal),
+          ),
+          startedAt,
+        )) {
+          await stream.write(`${JSON.stringify(line)}\n`);
+        }
+        if (cancellation.signal.aborted) {
+          metrics.cancelledGenerations++;
+        }
+      } catch (error) {
+        logger.error("Ollama stream failed", {
+          request_id: requestId,
+          error: error instanceof Error ? error.message : String(error),
+        });
+
+        await stream.write(`${JSON.stringify({ error: "Streaming failed" })}\n`);
+      } finally {
+        metrics.streamEnded(requestId);
+        release();
+      }
+    });
+  }
+
+  app.post("/api/chat", (c) => ollama(c, "chat"));
+  app.post("/api/generate", (c) => ollama(c, "generate"));
+
+  // Gemini's generateContent and streamGenerateContent, e.g.
+  // /v1beta/models/echo:generateContent
+  app.post("/v1beta/models/:call", async (c) => {
+    const requestId = c.get("requestId") as string;
+    const call = parseModelMethod(c.req.param("call"));
+    if (!call) {
+      throw new NotFoundError(`Unknown method: ${c.req.param("call")}`);
+    }
+
+    let body: unknown;
+    try {
+      body = await c.req.json();
+    } catch (error) {
+      throw new InvalidRequestError("Invalid JSON in request body");
+    }
+    const streaming = call.method === "streamGenerateContent";
+    const request = parseGenerateContentRequest(body, call.model, streaming);
+
+    c.set("model", call.model);
+    const adapter = openaiRegistry.get(call.model);
+    if (!adapter) {
+      throw new ModelNotFoundError(call.model);
+    }
+    checkModelAccess(c.get("apiKey"), call.model);
+    quotas.check(c.get("apiKey"));
+    // As for Ollama, only status faults apply outside OpenAI's wire format
+    adapter.preflight(request);
+
+    logger.debug("Gemini request", {
+      request_id: requestId,
+      method: call.method,
+      model: call.model,
+    });
+
+    if (!streaming) {
+      const response = await adapter.complete(request, c.req.raw.signal);
+      meter(c, call.model, response.usage);
+      return prettyJson(c, geminiResponse(call.model, response));
+    }
+
+    // Google's SDKs ask for server-sent events; otherwise the stream is one
+    // JSON array, sent an element at a time
+    const sse = c.req.query("alt") === "sse";
+    const release = admitStream(c);
+    return stream(c, async (stream) => {
+      c.header("Content-Type", sse ? "text/event-stream" : "application/json");
+      c.header("Cache-Control", "no-cache");
+
+      const cancellation = new AbortController();
+      stream.onAbort(() => cancellation.abort());
+      metrics.streamStarted(requestId);
+
+      let first = true;
+      try {
+        for await (const response of geminiStream(
+          call.model,
+          metered(
+            c,
+            call.model,
+            adapter.completeStream(request, cancellation.signal),
+          ),
+        )) {
+          const json = JSON.stringify(response);
+          await stream.write(
+            sse ? `data: ${json}\r\n\r\n` : `${first ? "[" : ",\r\n"}${json}`,
+          );
+          first = false;
+        }
+        if (cancellation.signal.aborted) {
+          metrics.cancelledGenerations++;
+          return;
+        }
+        if (!sse) {
+          await stream.write("]");
+        }
+      } catch (error) {
+        logger.error("Gemini stream failed", {
+          request_id: requestId,
+          error: error instanceof Error ? error.message : String(error),
+        });
+
+        const failure = JSON.stringify(geminiError("Streaming failed", 500));
+        await stream.write(
+          sse
+            ? `data: ${failure}\r\n\r\n`
+            : `${first ? "[" : ",\r\n"}${failure}]`,
+        );
+      } finally {
+        metrics.streamEnded(requestId);
+        release();
+      }
+    });
+  });
+
+  // Audio transcription endpoint (stub, returns a canned transcript)
+  app.post("/v1/audio/transcriptions", async (c) => {
+    let form: Record<string, string | File>;
+    try {
+      form = await c.req.parseBody();
+    } catch (error) {
+      throw new InvalidRequestError(
+        "Invalid multipart/form-data in request body",
+      );
+    }
+
+    const file = form["file"];
+    if (!(file instanceof File)) {
+      throw new InvalidRequestError("Missing required parameter: file", "file");
+    }
+
+    if (!form["model"]) {
+      throw new InvalidRequestError(
+        "Missing required parameter: model",
+        "model",
+      );
+    }
+
+    const format = parseTranscriptionFormat(form["response_format"]);
+    const audio = new Uint8Array(await file.arrayBuffer());
+    const transcription = formatTranscription(
+      CANNED_TRANSCRIPT,
+      format,
+      wavDurationSeconds(audio),
+    );
+
+    logger.debug("Audio transcription completed", {
+      request_id: c.get("requestId"),
+      file_bytes: audio.length,
+      response_format: format,
+    });
+
+    c.header("Content-Type", transcription.contentType);
+    return c.body(transcription.body);
+  });
+
+  // Text-to-speech endpoint (stub, returns silence)
+  app.post("/v1/audio/speech", async (c) => {
+    let request: any;
+    try {
+      request = await c.req.json();
+    } catch (error) {
+      throw new InvalidRequestError("Invalid JSON in request body");
+    }
+
+    if (!request.model) {
+      throw new InvalidRequestError(
+        "Missing required parameter: model",
+        "model",
+      );
+    }
+
+    if (typeof request.input !== "string" || request.input.length === 0) {
+      throw new InvalidRequestError(
+        "Missing required parameter: input",
+        "input",
+      );
+    }
+
+    if (!request.voice) {
+      throw new InvalidRequestError(
+        "Missing required parameter: voice",
+        "voice",
+      );
+    }
+
+    const format = request.response_format ?? "mp3";
+    if (!isSpeechFormat(format)) {
+      throw new InvalidRequestError(
+        `Unsupported response_format: ${format}. Supported formats: ${Object.keys(SPEECH_FORMATS).join(", ")}`,
+        "response_format",
+      );
+    }
+
+    c.header("Content-Type", SPEECH_FORMATS[format]);
+    return c.body(synthesizeSpeech(request.input, format));
+  });
+
+  // Moderation endpoint (stub, flags configured keywords)
+  app.post("/v1/moderations", async (c) => {
+    let request: any;
+    try {
+      request = await c.req.json();
+    } catch (error) {
+      throw new InvalidRequestError("Invalid JSON in request body");
+    }
+
+    if (request.input === undefined) {
+      throw new InvalidRequestError(
+        "Missing required parameter: input",
+        "input",
+      );
+    }
+
+    const response = moderator.moderate(request.input, request.model);
+
+    logger.debug("Moderation completed", {
+      request_id: c.get("requestId"),
+      model: response.model,
+      input_count: response.results.length,
+      flagged_count: response.results.filter((result) => result.flagged)
+        .length,
+    });
+
+    return prettyJson(c, response);
+  });
+
+  // Image generation endpoint (stub, returns placeholder images)
+  app.post("/v1/images/generations", async (c) => {
+    let request: any;
+    try {
+      request = await c.req.json();
+    } catch (error) {
+      throw new InvalidRequestError("Invalid JSON in request body");
+    }
+
+    if (typeof request.prompt !== "string" || request.prompt.length === 0) {
+      throw new InvalidRequestError(
+        "Missing required parameter: prompt",
+        "prompt",
+      );
+    }
+
+    const n = request.n ?? 1;
+    if (!Number.isInteger(n) || n < 1 || n > 10) {
+      throw new InvalidRequestError(
+        "Invalid n: must be an integer between 1 and 10",
+        "n",
       );
+    }
+
+    const size = parseImageSize(request.size);
+    const responseFormat = request.response_format ?? "url";
+    if (responseFormat !== "url" && responseFormat !== "b64_json") {
+      throw new InvalidRequestError(
+        "Invalid response_format: must be one of url, b64_json",
+        "response_format",
+      );
+    }
+
+    let image: { url: string } | { b64_json: string };
+    if (responseFormat === "url") {
+      image = {
+        url: `${new URL(c.req.url).origin}/images/placeholder/${size}.png`,
+      };
+    } else {
+      const { width, height } = imageDimensions(size);
+      image = { b64_json: toBase64(await renderPlaceholderPng(width, height)) };
+    }
+
+    logger.debug("Image generation completed", {
+      request_id: c.get("requestId"),
+      n,
+      size,
+      response_format: responseFormat,
+    });
+
+    return prettyJson(c, {
+      created: getCurrentTimestamp(),
+      data: Array.from({ length: n }, () => ({
+        ...image,
+        revised_prompt: request.prompt,
+      })),
+    });
+  });
+
+  // Files API, holding batch input and output (in memory, per API key)
+  app.post("/v1/files", async (c) => {
+    let form: Record<string, string | File>;
+    try {
+      form = await c.req.parseBody();
+    } catch (error) {
+      throw new InvalidRequestError(
+        "Invalid multipart/form-data in request body",
+      );
+    }
+
+    const file = form["file"];
+    if (!(file instanceof File)) {
+      throw new InvalidRequestError("Missing required parameter: file", "file");
+    }
+    const purpose = parsePurpose(form["purpose"]);
+    const content = new Uint8Array(await file.arrayBuffer());
+    validateUpload(
+      file.name,
+      purpose,
+      content,
+      config.files?.maxFileBytes ?? DEFAULT_MAX_FILE_BYTES,
+    );
+
+    const created = files.create(c.get("apiKey"), file.name, purpose, content);
+    return prettyJson(c, created);
+  });
+
+  app.get("/v1/files", (c) => {
+    return prettyJson(c, {
+      object: "list",
+      data: files.list(c.get("apiKey"), c.req.query("purpose")),
+      has_more: false,
+    });
+  });
+
+  app.get("/v1/files/:id", (c) => {
+    return prettyJson(c, files.get(c.get("apiKey"), c.req.param("id")));
+  });
+
+  app.get("/v1/files/:id/content", (c) => {
+    const content = files.content(c.get("apiKey"), c.req.param("id"));
+    c.header("Content-Type", "application/octet-stream");
+    return c.body(content.slice().buffer as ArrayBuffer);
+  });
+
+  app.delete("/v1/files/:id", (c) => {
+    return prettyJson(c, files.delete(c.get("apiKey"), c.req.param("id")));
+  });
+
+  // Batch API, running uploaded requests with simulated progress
+  app.post("/v1/batches", async (c) => {
+    let body: unknown;
+    try {
+      body = await c.req.json();
+    } catch (error) {
+      throw new InvalidRequestError("Invalid JSON in request body");
+    }
+
+    const batch = batches.create(c.get("apiKey"), parseCreateBatchRequest(body));
+
+    logger.debug("Batch created", {
+      request_id: c.get("requestId"),
+      batch_id: batch.id,
+      endpoint: batch.endpoint,
+    });
+
+    return prettyJson(c, batch);
+  });
+
+  app.get("/v1/batches", async (c) => {
+    const limit = c.req.query("limit");
+    return prettyJson(
+      c,
+      await batches.list(
+        c.get("apiKey"),
+        c.req.query("after"),
+        limit === undefined ? undefined : Number(limit),
+      ),
+    );
+  });
+
+  app.get("/v1/batches/:id", async (c) => {
+    return prettyJson(c, await batches.get(c.get("apiKey"), c.req.param("id")));
+  });
+
+  app.post("/v1/batches/:id/cancel", async (c) => {
+    return prettyJson(
+      c,
+      await batches.cancel(c.get("apiKey"), c.req.param("id")),
+    );
+  });
+
+  // Assistants API: assistants, threads and their messages, and runs that
+  // answer threads in the background (in memory, per API key)
+  app.post("/v1/assistants", async (c) => {
+    return prettyJson(
+      c,
+      assistants.createAssistant(c.get("apiKey"), await readJson(c)),
+    );
+  });
+
+  app.get("/v1/assistants", (c) => {
+    return prettyJson(
+      c,
+      assistants.listAssistants(c.get("apiKey"), c.req.query()),
+    );
+  });
+
+  app.get("/v1/assistants/:id", (c) => {
+    return prettyJson(
+      c,
+      assistants.getAssistant(c.get("apiKey"), c.req.param("id")),
+    );
+  });
+
+  app.post("/v1/assistants/:id", async (c) => {
+    return prettyJson(
+      c,
+      assistants.updateAssistant(
+        c.get("apiKey"),
+        c.req.param("id"),
+        await readJson(c),
+      ),
+    );
+  });
+
+  app.delete("/v1/assistants/:id", (c) => {
+    return prettyJson(
+      c,
+      assistants.deleteAssistant(c.get("apiKey"), c.req.param("id")),
+    );
+  });
+
+  app.post("/v1/threads", async (c) => {
+    return prettyJson(
+      c,
+      assistants.createThread(c.get("apiKey"), await readJson(c)),
+    );
+  });
+
+  app.post("/v1/threads/runs", async (c) => {
+    const run = assistants.createThreadAndRun(
+      c.get("apiKey"),
+      await readJson(c),
+    );
+    logRunCreated(c, run.id, run.model);
+    return prettyJson(c, run);
+  });
+
+  app.get("/v1/threads/:thread", (c) => {
+    return prettyJson(
+      c,
+      assistants.getThread(c.get("apiKey"), c.req.param("thread")),
+    );
+  });
+
+  app.post("/v1/threads/:thread", async (c) => {
+    return prettyJson(
+      c,
+      assistants.updateThread(
+        c.get("apiKey"),
+        c.req.param("thread"),
+        await readJson(c),
+      ),
+    );
+  });
+
+  app.delete("/v1/threads/:thread", (c) => {
+    return prettyJson(
+      c,
+      assistants.deleteThread(c.get("apiKey"), c.req.param("thread")),
+    );
+  });
+
+  app.post("/v1/threads/:thread/messages", async (c) => {
+    return prettyJson(
+      c,
+      assistants.createMessage(
+        c.get("apiKey"),
+        c.req.param("thread"),
+        await readJson(c),
+      ),
+    );
+  });
+
+  app.get("/v1/threads/:thread/messages", (c) => {
+    return prettyJson(
+      c,
+      assistants.listMessages(
+        c.get("apiKey"),
+        c.req.param("thread"),
+        c.req.query(),
+      ),
+    );
+  });
+
+  app.get("/v1/threads/:thread/messages/:id", (c) => {
+    return prettyJson(
+      c,
+      assistants.getMessage(
+        c.get("apiKey"),
+        c.req.param("thread"),
+        c.req.param("id"),
+      ),
+    );
+  });
+
+  app.post("/v1/threads/:thread/runs", async (c) => {
+    const run = assistants.createRun(
+      c.get("apiKey"),
+      c.req.param("thread"),
+      await readJson(c),
+    );
+    logRunCreated(c, run.id, run.model);
+    return prettyJson(c, run);
+  });
+
+  app.get("/v1/threads/:thread/runs", (c) => {
+    return prettyJson(
+      c,
+      assistants.listRuns(
+        c.get("apiKey"),
+        c.req.param("thread"),
+        c.req.query(),
+      ),
+    );
+  });
+
+  app.get("/v1/threads/:thread/runs/:id", (c) => {
+    return prettyJson(
+      c,
+      assistants.getRun(
+        c.get("apiKey"),
+        c.req.param("thread"),
+        c.req.param("id"),
+      ),
+    );
+  });
+
+  app.post("/v1/threads/:thread/runs/:id/cancel", (c) => {
+    return prettyJson(
+      c,
+      assistants.cancelRun(
+        c.get("apiKey"),
+        c.req.param("thread"),
+        c.req.param("id"),
+      ),
+    );
+  });
+
+  app.post("/v1/threads/:thread/runs/:id/submit_tool_outputs", async (c) => {
+    return prettyJson(
+      c,
+      assistants.submitToolOutputs(
+        c.get("apiKey"),
+        c.req.param("thread"),
+        c.req.param("id"),
+        await readJson(c),
+      ),
+    );
+  });
+
+  app.get("/v1/threads/:thread/runs/:run/steps", (c) => {
+    return prettyJson(
+      c,
+      assistants.listRunSteps(
+        c.get("apiKey"),
+        c.req.param("thread"),
+        c.req.param("run"),
+        c.req.query(),
+      ),
+    );
+  });
 
+  app.get("/v1/threads/:thread/runs/:run/steps/:id", (c) => {
+    return prettyJson(
+      c,
+      assistants.getRunStep(
+        c.get("apiKey"),
+        c.req.param("thread"),
+        c.req.param("run"),
+        c.req.param("id"),
+      ),
+    );
+  });
+
+  function logRunCreated(
+    c: Context<{ Variables: Variables }>,
+    runId: string,
+    model: string,
+  ) {
+    logger.debug("Run created", {
+      request_id: c.get("requestId"),
+      run_id: runId,
+      model,
+    });
+  }
+
+  // Responses API, answered by the chat completion adapters and kept (in
+  // memory, per API key) for previous_response_id
+  app.post("/v1/responses", async (c) => {
+    const requestId = c.get("requestId") as string;
+    const apiKey = c.get("apiKey");
+    const parsed = parseCreateResponseRequest(await readJson(c), (id) =>
+      responses.history(apiKey, id),
+    );
+    const { request, response } = parsed;
+
+    c.set("model", request.model);
+    const adapter = openaiRegistry.get(request.model);
+    if (!adapter) {
+      throw new ModelNotFoundError(request.model);
+    }
+    checkModelAccess(apiKey, request.model);
+    quotas.check(apiKey);
+    // As for Gemini, only status faults apply outside the chat wire format
+    adapter.preflight(request);
+
+    logger.debug("Responses request", {
+      request_id: requestId,
+      response_id: response.id,
+      model: request.model,
+      streaming: parsed.stream,
+      previous_response_id: response.previous_response_id,
+    });
+
+    if (!parsed.stream) {
+      const completion = await adapter.complete(request, c.req.raw.signal);
+      meter(c, request.model, completion.usage);
+      completeResponse(response, completion);
+      if (parsed.store) {
+        responses.save(apiKey, parsed);
+      }
       return prettyJson(c, response);
     }
+
+    const release = admitStream(c);
+    return stream(c, async (stream) => {
+      c.header("Content-Type", "text/event-stream");
+      c.header("Cache-Control", "no-cache");
+
+      const cancellation = new AbortController();
+      stream.onAbort(() => cancellation.abort());
+      metrics.streamStarted(requestId);
+
+      let sequence = 0;
+      try {
+        for await (const event of responseEvents(
+          response,
+          metered(
+            c,
+            request.model,
+            adapter.completeStream(request, cancellation.signal),
+          ),
+        )) {
+          await stream.write(
+            `event: ${event.type}\ndata: ${JSON.stringify(event)}\n\n`,
+          );
+          sequence = event.sequence_number + 1;
+        }
+        if (cancellation.signal.aborted) {
+          metrics.cancelledGenerations++;
+          return;
+        }
+        if (parsed.store) {
+          responses.save(apiKey, parsed);
+        }
+      } catch (error) {
+        logger.error("Responses stream failed", {
+          request_id: requestId,
+          error: error instanceof Error ? error.message : String(error),
+        });
+
+        const failed = failedEvent(response, sequence);
+        await stream.write(
+          `event: ${failed.type}\ndata: ${JSON.stringify(failed)}\n\n`,
+        );
+      } finally {
+        metrics.streamEnded(requestId);
+        release();
+      }
+    });
+  });
+
+  app.get("/v1/responses/:id", (c) => {
+    return prettyJson(c, responses.get(c.get("apiKey"), c.req.param("id")));
+  });
+
+  app.delete("/v1/responses/:id", (c) => {
+    return prettyJson(
+      c,
+      responses.delete(c.get("apiKey"), c.req.param("id")),
+    );
+  });
+
+  app.get("/v1/responses/:id/input_items", (c) => {
+    return prettyJson(
+      c,
+      responses.inputItems(c.get("apiKey"), c.req.param("id"), c.req.query()),
+    );
+  });
+
+  // Placeholder images linked from url-format image generations (no auth, like signed URLs)
+  app.get("/images/placeholder/:file", async (c) => {
+    const file = c.req.param("file");
+    if (!file.endsWith(".png")) {
+      throw new NotFoundError(`Not found: ${c.req.method} ${c.req.path}`);
+    }
+
+    const { width, height } = imageDimensions(
+      parseImageSize(file.slice(0, -".png".length)),
+    );
+    const png = await renderPlaceholderPng(width, height);
+
+    c.header("Content-Type", "image/png");
+    c.header("Cache-Control", "public, max-age=86400");
+    return c.body(png.buffer as ArrayBuffer);
+  });
+
+  // Session conversation endpoint - a non-OpenAI convenience for demos that
+  // keeps the history server-side so clients only send the new message
+  app.post("/session/:id/say", async (c) => {
+    const sessionId = c.req.param("id");
+    // Sessions are per API key, like the memory model's conversations
+    const historyKey = conversationKey(c.get("apiKey"), sessionId)!;
+
+    let body: any;
+    try {
+      body = await c.req.json();
+    } catch (error) {
+      throw new InvalidRequestError("Invalid JSON in request body");
+    }
+
+    if (!body || typeof body !== "object" || Array.isArray(body)) {
+      throw new InvalidRequestError("Request body must be a JSON object");
+    }
+    if (typeof body.message !== "string") {
+      throw new InvalidRequestError(
+        "Missing required parameter: message",
+        "message",
+      );
+    }
+
+    const model = body.model ?? "echo";
+    const adapter = openaiRegistry.get(model);
+    if (!adapter) {
+      throw new ModelNotFoundError(model);
+    }
+    checkModelAccess(c.get("apiKey"), model);
+    quotas.check(c.get("apiKey"));
+
+    const userMessage: ChatCompletionRequestMessage = {
+      role: "user",
+      content: body.message,
+    };
+    const messages = [...sessions.history(historyKey), userMessage];
+    const response = await adapter.complete(
+      { model, messages },
+      c.req.raw.signal,
+    );
+    meter(c, model, response.usage);
+    const reply = response.choices[0]!.message;
+    sessions.append(historyKey, userMessage, reply);
+
+    logger.debug("Session message completed", {
+      request_id: c.get("requestId"),
+      model,
+      message_count: messages.length + 1,
+    });
+
+    return prettyJson(c, {
+      session_id: sessionId,
+      model,
+      reply: reply.content,
+      messages: [...messages, reply],
+    });
+  });
+
+  // Runtime configuration - a non-OpenAI admin surface for the server's owner
+  app.get("/admin/models", (c) => {
+    checkAdminAccess(c.get("apiKey"));
+    return prettyJson(c, openaiRegistry.describe());
+  });
+
+  app.get("/admin/keys", (c) => {
+    const tenant = checkTenantAccess(c);
+    const revoked = authenticator.list();
+    const visible = (key: string) =>
+      tenant === undefined || scopedKeys.tenant(key) === tenant;
+    return prettyJson(c, {
+      keys: scopedKeys
+        .list()
+        .filter(({ key }) => visible(key) && !revoked.includes(key)),
+      revoked: revoked.filter(visible),
+    });
+  });
+
+  app.post("/admin/keys", async (c) => {
+    checkAdminAccess(c.get("apiKey"));
+    const callerTenant = tenantOf(c);
+    const body = await optionalBody(c);
+    const models = body.models;
+    if (
+      models !== undefined &&
+      (!Array.isArray(models) ||
+        models.some((model: unknown) => typeof model !== "string"))
+    ) {
+      throw new InvalidRequestError(
+        "Invalid type for 'models': expected an array of model names",
+        "models",
+      );
+    }
+
+    if (
+      body.tenant !== undefined &&
+      (typeof body.tenant !== "string" || body.tenant === "")
+    ) {
+      throw new InvalidRequestError(
+        "Invalid type for 'tenant': expected a non-empty string",
+        "tenant",
+      );
+    }
+    if (
+      callerTenant !== undefined &&
+      body.tenant !== undefined &&
+      body.tenant !== callerTenant
+    ) {
+      throw new PermissionDeniedError(
+        "Keys can only be created in the tenant the request acts for",
+        "tenant_override_not_allowed",
+      );
+    }
+    const tenant: string | undefined = body.tenant ?? callerTenant;
+
+    const key = `tt-${generateRandomString(32)}`;
+    scopedKeys.add({
+      key,
+      ...(models === undefined ? {} : { models }),
+      ...(tenant === undefined ? {} : { tenant }),
+    });
+    provisionedKeys.add(key);
+    return prettyJson(c, {
+      key,
+      models: models ?? null,
+      tenant: tenant ?? null,
+    });
+  });
+
+  app.delete("/admin/keys/:key", (c) => {
+    checkAdminAccess(c.get("apiKey"));
+    const tenant = tenantOf(c);
+    const key = c.req.param("key");
+    checkTenantKey(tenant, key);
+    if (key === config.auth.apiKey) {
+      throw new InvalidRequestError(
+        "The server's API key cannot be revoked",
+        "key",
+      );
+    }
+
+    // A tenant's key stays the tenant's, so its requests and usage can still
+    // be looked at; being revoked is what stops it
+    if (scopedKeys.tenant(key) === undefined) {
+      scopedKeys.remove(key);
+      provisionedKeys.delete(key);
+    }
+    authenticator.revoke(key);
+    return prettyJson(c, { key, revoked: true });
+  });
+
+  app.get("/admin/rate-limit", (c) => {
+    checkAdminAccess(c.get("apiKey"));
+    return prettyJson(c, { requests_per_minute: requestsPerMinute });
+  });
+
+  app.put("/admin/rate-limit", async (c) => {
+    checkAdminAccess(c.get("apiKey"));
+    const body = await optionalBody(c);
+    const value = body.requests_per_minute;
+    if (!Number.isInteger(value) || value < 1) {
+      throw new InvalidRequestError(
+        "Invalid 'requests_per_minute': expected a positive integer",
+        "requests_per_minute",
+      );
+    }
+
+    requestsPerMinute = value;
+    return prettyJson(c, { requests_per_minute: requestsPerMinute });
+  });
+
+  app.get("/admin/faults", (c) => {
+    checkAdminAccess(c.get("apiKey"));
+    return prettyJson(c, faultSettings());
+  });
+
+  app.put("/admin/faults", async (c) => {
+    checkAdminAccess(c.get("apiKey"));
+    applyFaultSettings(
+      parseFaultSettings(await c.req.json().catch(() => ({}))),
+    );
+    return prettyJson(c, faultSettings());
+  });
+
+  app.get("/admin/scenarios", (c) => {
+    checkAdminAccess(c.get("apiKey"));
+    return prettyJson(c, scenarios.snapshot());
+  });
+
+  // Replaces every scenario, starting each from its played count
+  app.put("/admin/scenarios", async (c) => {
+    checkAdminAccess(c.get("apiKey"));
+    const body = await c.req.json().catch(() => undefined);
+    scenarios.replace(parseScenarios(body));
+    return prettyJson(c, scenarios.snapshot());
+  });
+
+  function applyFaultSettings(settings: FaultSettings) {
+    if (settings.failureRate !== undefined) {
+      faults.failureRate = settings.failureRate;
+    }
+    if (settings.kinds === null) delete faults.kinds;
+    else if (settings.kinds !== undefined) faults.kinds = settings.kinds;
+  }
+
+  // Re-reads the config file and applies it, as SIGHUP does
+  app.post("/admin/reload", async (c) => {
+    checkAdminAccess(c.get("apiKey"));
+    if (!config.reload) {
+      throw new InvalidRequestError(
+        "The server was started without a config file to reload",
+      );
+    }
+
+    applySettings(await config.reload());
+    return prettyJson(c, { reloaded: true });
+  });
+
+  // Checks every model name first, so a bad file changes nothing
+  function applySettings(settings: ServerSettings) {
+    const known = openaiRegistry.describe();
+    const unknown = settings.models?.find(
+      (model) =>
+        !known.models.includes(model) &&
+        !Object.hasOwn(known.aliases, model) &&
+        !known.variants.includes(model),
+    );
+    if (unknown !== undefined) {
+      throw new InvalidRequestError(
+        `Invalid 'models': unknown model '${unknown}'`,
+        "models",
+      );
+    }
+    for (const [name, router] of Object.entries(settings.routers ?? {})) {
+      const target = routerTargets(router).find(
+        (id) => !openaiRegistry.model(id),
+      );
+      if (target !== undefined) {
+        throw new InvalidRequestError(
+          `Invalid 'routers.${name}': unknown model '${target}'`,
+          `routers.${name}`,
+        );
+      }
+    }
+
+    openaiRegistry.enable(settings.models);
+    if (settings.keys) {
+      settingsKeys.forEach((key) => scopedKeys.remove(key));
+      settings.keys.forEach((key) => scopedKeys.add(key));
+      settingsKeys = settings.keys.map(({ key }) => key);
+    }
+    if (settings.requestsPerMinute !== undefined) {
+      requestsPerMinute = settings.requestsPerMinute;
+    }
+    if (settings.faults) applyFaultSettings(settings.faults);
+    if (settings.latency) latency = settings.latency;
+    if (settings.modelDefaults) modelDefaults = settings.modelDefaults;
+    if (settings.routers) routers = settings.routers;
+    if (settings.capture) capture.configure(settings.capture);
+    if (settings.webhooks) webhooks.replace(settings.webhooks);
+    if (settings.quotas) {
+      settingsQuotas.forEach((key) => quotas.set(key, null));
+      for (const [key, budget] of Object.entries(settings.quotas)) {
+        quotas.set(key, budget);
+      }
+      settingsQuotas = Object.keys(settings.quotas);
+    }
+  }
+
+  function faultSettings() {
+    return {
+      failure_rate: faults.failureRate,
+      kinds: faults.kinds ?? [...FAULT_KINDS],
+    };
+  }
+
+  app.get("/admin/drain", (c) => {
+    checkAdminAccess(c.get("apiKey"));
+    return prettyJson(c, drain.snapshot());
+  });
+
+  // Stops taking new requests, giving those in flight grace_ms to finish. The
+  // server keeps running; SIGTERM drains, then exits once it's done.
+  app.post("/admin/drain", async (c) => {
+    checkAdminAccess(c.get("apiKey"));
+    const body = await optionalBody(c);
+    const graceMs = body.grace_ms ?? DEFAULT_DRAIN_GRACE_MS;
+    if (!Number.isInteger(graceMs) || graceMs < 0) {
+      throw new InvalidRequestError(
+        "Invalid 'grace_ms': expected a non-negative integer",
+        "grace_ms",
+      );
+    }
+
+    drain.start(graceMs);
+    return prettyJson(c, drain.snapshot());
+  });
+
+  app.get("/admin/latency", (c) => {
+    checkAdminAccess(c.get("apiKey"));
+    return prettyJson(c, latency);
+  });
+
+  // Replaces every latency profile; an empty object turns injection off
+  app.put("/admin/latency", async (c) => {
+    checkAdminAccess(c.get("apiKey"));
+    latency = parseLatencyConfig(await c.req.json().catch(() => null));
+    return prettyJson(c, latency);
+  });
+
+  app.get("/admin/model-defaults", (c) => {
+    checkAdminAccess(c.get("apiKey"));
+    return prettyJson(c, modelDefaults);
+  });
+
+  // The settings a model's requests get, with "*" merged in
+  app.get("/admin/model-defaults/:model", (c) => {
+    checkAdminAccess(c.get("apiKey"));
+    const model = c.req.param("model");
+    if (!openaiRegistry.get(model)) {
+      throw new ModelNotFoundError(model);
+    }
+    const settings = defaultsFor(modelDefaults, model);
+    return prettyJson(c, {
+      model,
+      max_tokens: settings.max_tokens ?? null,
+      temperature: settings.temperature ?? null,
+      system_prompt: settings.system_prompt ?? null,
+    });
+  });
+
+  // Replaces every model's defaults; an empty object turns them off
+  app.put("/admin/model-defaults", async (c) => {
+    checkAdminAccess(c.get("apiKey"));
+    modelDefaults = parseModelDefaults(await c.req.json().catch(() => null));
+    return prettyJson(c, modelDefaults);
+  });
+
+  app.get("/admin/templates", (c) => {
+    checkAdminAccess(c.get("apiKey"));
+    return prettyJson(c, templates);
+  });
+
+  // Replaces every template, each served as template:<name>; "default" is
+  // also what plain "template" serves
+  app.put("/admin/templates", async (c) => {
+    checkAdminAccess(c.get("apiKey"));
+    const body = await c.req.json().catch(() => null);
+    if (!body || typeof body !== "object" || Array.isArray(body)) {
+      throw new InvalidRequestError(
+        "Invalid templates: expected an object of template names to templates",
+      );
+    }
+    for (const [name, template] of Object.entries(body)) {
+      if (typeof template !== "string") {
+        throw new InvalidRequestError(
+          `Invalid '${name}': expected a string`,
+          name,
+        );
+      }
+      try {
+        checkTemplate(template);
+      } catch (error) {
+        throw new InvalidRequestError(
+          `Invalid '${name}': ${(error as Error).message}`,
+          name,
+        );
+      }
+    }
+
+    templates = body as Record<string, string>;
+    return prettyJson(c, templates);
+  });
+
+  app.get("/admin/quotas", (c) => {
+    const tenant = checkTenantAccess(c);
+    const data = quotas
+      .list()
+      .filter(
+        ({ key }) => tenant === undefined || scopedKeys.tenant(key) === tenant,
+      );
+    return prettyJson(c, { object: "list", data });
   });
 
+  // Sets a key's token budget, or removes it with {"token_budget": null}
+  app.put("/admin/quotas/:key", async (c) => {
+    checkAdminAccess(c.get("apiKey"));
+    const tenant = tenantOf(c);
+    const key = c.req.param("key");
+    checkTenantKey(tenant, key);
+    const body = await optionalBody(c);
+    const budget = body.token_budget;
+    if (budget !== null && (!Number.isInteger(budget) || budget < 0)) {
+      throw new InvalidRequestError(
+        "Invalid 'token_budget': expected a non-negative integer or null",
+        "token_budget",
+      );
+    }
+
+    quotas.set(key, budget);
+    if (budget === null) {
+      budgetedKeys.delete(key);
+    } else {
+      budgetedKeys.add(key);
+    }
+    return prettyJson(
+      c,
+      quotas.get(key) ?? { key, token_budget: null, tokens_used: 0 },
+    );
+  });
+
+  // Resets rate limit windows, metered usage and spent quota for one key, or
+  // every counter when no key is given
+  app.post("/admin/usage/reset", async (c) => {
+    checkAdminAccess(c.get("apiKey"));
+    const tenant = tenantOf(c);
+    const body = await optionalBody(c);
+    if (body.key !== undefined && typeof body.key !== "string") {
+      throw new InvalidRequestError(
+        "Invalid type for 'key': expected a string",
+        "key",
+      );
+    }
+
+    // Acting for a tenant resets its keys, one or all of them
+    if (body.key !== undefined) {
+      checkTenantKey(tenant, body.key);
+    } else if (tenant !== undefined) {
+      for (const key of scopedKeys.tenantKeys(tenant)) {
+        rateLimiter.reset(key);
+        usageMeter.reset(key);
+        quotas.reset(key);
+      }
+      return prettyJson(c, { reset: true, key: null });
+    }
+
+    rateLimiter.reset(body.key);
+    usageMeter.reset(body.key);
+    quotas.reset(body.key);
+    if (body.key === undefined) {
+      metrics.reset();
+      streamLimiter.reset();
+    }
+    return prettyJson(c, { reset: true, key: body.key ?? null });
+  });
+
+  // Metered usage per time bucket, API key and model, with totals. Each key
+  // sees its own usage, or its tenant's; the server's key sees everyone's.
+  app.get("/admin/usage", (c) => {
+    const filter = parseUsageFilter(c.req.query());
+    scopeToKeys(c, filter);
+    return prettyJson(c, {
+      object: "list",
+      bucket: filter.bucket,
+      ...usageMeter.query(filter),
+    });
+  });
+
+  // Conversations the memory model remembers. Each key sees its own, or its
+  // tenant's; the server's key sees everyone's and can filter with ?key=.
+  app.get("/admin/conversations", (c) => {
+    const scope: { apiKey?: string; apiKeys?: string[] } = {};
+    scopeToKeys(c, scope);
+    const data = memory
+      .list()
+      .map(({ id, messages, lastUsed, expiresAt }) => {
+        const [owner, conversation] = JSON.parse(id) as [string, string];
+        return {
+          id: conversation,
+          key: owner,
+          turns: messages.filter((message) => message.role === "user").length,
+          messages,
+          last_used_at: new Date(lastUsed).toISOString(),
+          expires_at: new Date(expiresAt).toISOString(),
+        };
+      })
+      .filter(
+        (entry) =>
+          (scope.apiKey === undefined || entry.key === scope.apiKey) &&
+          (scope.apiKeys === undefined || scope.apiKeys.includes(entry.key)),
+      );
+    return prettyJson(c, { object: "list", data });
+  });
+
+  // Forgets a conversation, so the memory model starts it afresh. It's the
+  // caller's own, or that of a key it may see named with ?key=.
+  app.delete("/admin/conversations/:id", (c) => {
+    const id = c.req.param("id");
+    const scope: { apiKey?: string; apiKeys?: string[] } = {};
+    scopeToKeys(c, scope);
+    const key = scope.apiKey ?? c.get("apiKey");
+    const deleted = memory.delete(conversationKey(key, id)!);
+    return prettyJson(c, { id, key, deleted });
+  });
+
+  // Which keys' bodies the request log keeps, and what it redacts from them
+  app.get("/admin/capture", (c) => {
+    checkAdminAccess(c.get("apiKey"));
+    return prettyJson(c, capture.settings());
+  });
+
+  app.put("/admin/capture", async (c) => {
+    checkAdminAccess(c.get("apiKey"));
+    capture.configure(
+      parseCaptureSettings(await c.req.json().catch(() => ({}))),
+    );
+    return prettyJson(c, capture.settings());
+  });
+
+  app.get("/admin/webhooks", (c) => {
+    checkAdminAccess(c.get("apiKey"));
+    return prettyJson(c, webhooks.list());
+  });
+
+  app.put("/admin/webhooks", async (c) => {
+    checkAdminAccess(c.get("apiKey"));
+    webhooks.replace(parseWebhooks(await c.req.json().catch(() => ({}))));
+    return prettyJson(c, webhooks.list());
+  });
+
+  // Recently received /v1 requests, newest first. Each key sees its own
+  // requests, or its tenant's; the server's key sees everyone's.
+  app.get("/admin/requests", (c) => {
+    const filter = parseRequestLogFilter(c.req.query());
+    scopeToKeys(c, filter);
+    return prettyJson(c, { object: "list", data: capture.query(filter) });
+  });
+
+  // Record and replay of /v1 traffic - a non-OpenAI admin surface. Each API
+  // key controls the recording of its own requests, and sees the cassettes
+  // its tenant, or the key itself, recorded.
+  app.get("/admin/cassettes", (c) => {
+    return prettyJson(c, {
+      object: "list",
+      data: recorder.list(cassetteOwner(c)),
+      recorder: recorder.status(c.get("apiKey")),
+    });
+  });
+
+  app.get("/admin/cassettes/:name", (c) => {
+    return prettyJson(
+      c,
+      recorder.load(c.req.param("name"), cassetteOwner(c)),
+    );
+  });
+
+  app.post("/admin/cassettes/:name/record", (c) => {
+    recorder.startRecording(
+      c.get("apiKey"),
+      c.req.param("name"),
+      cassetteOwner(c),
+    );
+    return prettyJson(c, recorder.status(c.get("apiKey")));
+  });
+
+  app.post("/admin/cassettes/:name/replay", async (c) => {
+    const body = await optionalBody(c);
+    if (body.realtime !== undefined && typeof body.realtime !== "boolean") {
+      throw new InvalidRequestError(
+        "Invalid type for 'realtime': expected a boolean",
+        "realtime",
+      );
+    }
+    recorder.startReplay(
+      c.get("apiKey"),
+      c.req.param("name"),
+      body.realtime ?? true,
+      cassetteOwner(c),
+    );
+    return prettyJson(c, recorder.status(c.get("apiKey")));
+  });
+
+  app.post("/admin/cassettes/stop", (c) => {
+    const stopped = recorder.stop(c.get("apiKey"));
+
+    logger.debug("Recorder stopped", {
+      request_id: c.get("requestId"),
+      ...stopped,
+    });
+
+    return prettyJson(c, stopped);
+  });
+
+  // Whose cassettes a request works with: its tenant's, or its key's own. The
+  // server's key works with them all, or a tenant's when it names one.
+  function cassetteOwner(
+    c: Context<{ Variables: Variables }>,
+  ): CassetteOwner | undefined {
+    const apiKey = c.get("apiKey");
+    const tenant = tenantOf(c);
+    if (tenant !== undefined) {
+      return { tenant };
+    }
+    return apiKey === config.auth.apiKey ? undefined : { key: apiKey };
+  }
+
   // Website-specific endpoints (no auth required)
   app.post("/site/new-key", async (c) => {
     const apiKey = await authenticator.generateApiKey();
@@ -3,4 +3,50 @@
  */
 export interface AuthConfig {
   apiKey: string;
-}
\ No newline at end of file
+  /** Additional keys, each optionally limited to a set of models */
+  keys?: ScopedKey[];
+  /** Keys that are rejected even if they would otherwise validate */
+  revokedKeys?: string[];
+  /** Organizations the OpenAI-Organization header may name, or undefined for any */
+  organizations?: string[] | undefined;
+  /** Projects the OpenAI-Project header may name, or undefined for any */
+  projects?: string[] | undefined;
+}
+
+export interface ScopedKey {
+  key: string;
+  /** Models this key may use, or undefined for all models */
+  models?: string[];
+  /** The tenant the key belongs to, whose keys' requests and usage it sees */
+  tenant?: string;
+}
+
+/**
+ * Parses a comma-separated key list such as "key1,key2:echo|eliza", where the
+ * optional suffix after ':' restricts that key to the '|'-separated models
+ */
+export function parseKeyList(value: string | undefined): ScopedKey[] {
+  if (!value) {
+    return [];
+  }
+
+  return value
+    .split(',')
+    .map(entry => entry.trim())
+    .filter(entry => entry.length > 0)
+    .map(entry => {
+      const [key, models] = entry.split(':', 2) as [string, string | undefined];
+      return models === undefined
+        ? { key }
+        : { key, models: models.split('|').filter(model => model.length > 0) };
+    });
+}
+
+/**
+ * Parses a comma-separated list such as TEENYTINY_ORGANIZATIONS, or undefined
+ * when it names nothing
+ */
+export function parseNameList(value: string | undefined): string[] | undefined {
+  const names = (value ?? '').split(',').map(name => name.trim()).filter(Boolean);
+  return names.length > 0 ? names : undefined;
+}
@@ -0,0 +1,41 @@
+import type { Authenticator } from './authenticator.js';
+
+/**
+ * RevokedKeyAuthenticator - Rejects revoked keys before delegating
+ * 
+ * Encrypted keys are self-validating, so revoking one means keeping a deny
+ * list in front of whichever authenticator would otherwise accept it.
+ */
+export class RevokedKeyAuthenticator implements Authenticator {
+  private revoked: Set<string>;
+  private changes = 0;
+
+  constructor(private inner: Authenticator, revokedKeys: string[]) {
+    this.revoked = new Set(revokedKeys);
+  }
+
+  async generateApiKey(): Promise<string> {
+    return this.inner.generateApiKey();
+  }
+
+  revoke(key: string): void {
+    this.revoked.add(key);
+    this.changes++;
+  }
+
+  // Goes up with every revocation
+  get version(): number {
+    return this.changes;
+  }
+
+  list(): string[] {
+    return [...this.revoked];
+  }
+
+  async validateApiKey(key: string): Promise<boolean> {
+    if (this.revoked.has(key)) {
+      return false;
+    }
+    return this.inner.validateApiKey(key);
+  }
+}
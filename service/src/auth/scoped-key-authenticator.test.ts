@@ -0,0 +1,76 @@
+import { describe, it, expect } from 'vitest';
+import { parseKeyList } from './auth-config.js';
+import { ScopedKeyAuthenticator } from './scoped-key-authenticator.js';
+import { RevokedKeyAuthenticator } from './revoked-key-authenticator.js';
+import { SingleKeyAuthenticator } from './single-key-authenticator.js';
+
+describe('parseKeyList', () => {
+  it('should parse plain and model-scoped keys', () => {
+    expect(parseKeyList('alpha, beta:echo|eliza')).toEqual([
+      { key: 'alpha' },
+      { key: 'beta', models: ['echo', 'eliza'] },
+    ]);
+  });
+
+  it('should treat a missing or empty list as no keys', () => {
+    expect(parseKeyList(undefined)).toEqual([]);
+    expect(parseKeyList(' , ')).toEqual([]);
+  });
+});
+
+describe('ScopedKeyAuthenticator', () => {
+  const authenticator = new ScopedKeyAuthenticator(parseKeyList('alpha,beta:echo'));
+
+  it('should validate each provisioned key', async () => {
+    expect(await authenticator.validateApiKey('alpha')).toBe(true);
+    expect(await authenticator.validateApiKey('beta')).toBe(true);
+    expect(await authenticator.validateApiKey('gamma')).toBe(false);
+  });
+
+  it('should report model allowlists', () => {
+    expect(authenticator.allowedModels('alpha')).toBeUndefined();
+    expect(authenticator.allowedModels('beta')).toEqual(['echo']);
+  });
+
+  it('should report the tenant each key belongs to', () => {
+    const tenants = new ScopedKeyAuthenticator([
+      { key: 'alpha', tenant: 'team-a' },
+      { key: 'beta', tenant: 'team-b' },
+      { key: 'gamma', tenant: 'team-a' },
+      { key: 'delta' },
+    ]);
+
+    expect(tenants.tenant('alpha')).toBe('team-a');
+    expect(tenants.tenant('delta')).toBeUndefined();
+    expect(tenants.tenantKeys('team-a')).toEqual(['alpha', 'gamma']);
+    expect(tenants.tenantKeys('team-c')).toEqual([]);
+  });
+
+  it('should refuse to generate keys', async () => {
+    await expect(authenticator.generateApiKey()).rejects.toThrow();
+  });
+});
+
+describe('RevokedKeyAuthenticator', () => {
+  it('should reject revoked keys that would otherwise validate', async () => {
+    const authenticator = new RevokedKeyAuthenticator(new SingleKeyAuthenticator('alpha'), ['alpha']);
+
+    expect(await authenticator.validateApiKey('alpha')).toBe(false);
+  });
+
+  it('should delegate keys that are not revoked', async () => {
+    const authenticator = new RevokedKeyAuthenticator(new SingleKeyAuthenticator('alpha'), ['beta']);
+
+    expect(await authenticator.validateApiKey('alpha')).toBe(true);
+    expect(await authenticator.generateApiKey()).toBe('alpha');
+  });
+
+  it('should revoke keys at runtime', async () => {
+    const authenticator = new RevokedKeyAuthenticator(new SingleKeyAuthenticator('alpha'), []);
+
+    authenticator.revoke('alpha');
+
+    expect(await authenticator.validateApiKey('alpha')).toBe(false);
+    expect(authenticator.list()).toEqual(['alpha']);
+  });
+});
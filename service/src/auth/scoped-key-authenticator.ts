@@ -0,0 +1,68 @@
+import type { Authenticator } from './authenticator.js';
+import type { ScopedKey } from './auth-config.js';
+
+/**
+ * ScopedKeyAuthenticator - Validates a list of provisioned keys
+ * 
+ * Each key may be limited to a set of models, letting one deployment stand in
+ * for several tenants with different model access. Keys are provisioned by
+ * configuration or the admin API, so this authenticator cannot generate new ones.
+ */
+export class ScopedKeyAuthenticator implements Authenticator {
+  private keys: Map<string, ScopedKey>;
+  private changes = 0;
+
+  constructor(keys: ScopedKey[]) {
+    this.keys = new Map(keys.map(scoped => [scoped.key, scoped]));
+  }
+
+  async generateApiKey(): Promise<string> {
+    throw new Error('ScopedKeyAuthenticator only validates provisioned keys');
+  }
+
+  async validateApiKey(key: string): Promise<boolean> {
+    return this.keys.has(key);
+  }
+
+  add(scoped: ScopedKey): void {
+    this.keys.set(scoped.key, scoped);
+    this.changes++;
+  }
+
+  remove(key: string): boolean {
+    this.changes++;
+    return this.keys.delete(key);
+  }
+
+  /**
+   * Goes up with every key added or removed, so a caller can tell the keys changed
+   */
+  get version(): number {
+    return this.changes;
+  }
+
+  list(): ScopedKey[] {
+    return [...this.keys.values()];
+  }
+
+  /**
+   * Returns the models a key may use, or undefined if it isn't restricted
+   */
+  allowedModels(key: string): string[] | undefined {
+    return this.keys.get(key)?.models;
+  }
+
+  /**
+   * Returns the tenant a key belongs to, or undefined if it has none
+   */
+  tenant(key: string): string | undefined {
+    return this.keys.get(key)?.tenant;
+  }
+
+  /**
+   * Returns every key of a tenant, revoked ones included
+   */
+  tenantKeys(tenant: string): string[] {
+    return this.list().filter(scoped => scoped.tenant === tenant).map(scoped => scoped.key);
+  }
+}
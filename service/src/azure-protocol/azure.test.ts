@@ -0,0 +1,108 @@
+import { describe, it, expect } from "vitest";
+import { createApp } from "../app.js";
+import { deploymentModel, parseDeployments } from "./azure.js";
+
+const testAPIKey = "tt-azure-key";
+const app = createApp({
+  auth: { apiKey: testAPIKey },
+  azure: { deployments: { "gpt-4o": "eliza", "gpt-35-turbo": "echo" } },
+});
+
+const hello = { messages: [{ role: "user", content: "Hello there" }] };
+
+function post(deployment: string, body: unknown, query = "?api-version=2024-10-21") {
+  return app.request(`/openai/deployments/${deployment}/chat/completions${query}`, {
+    method: "POST",
+    headers: { "api-key": testAPIKey, "Content-Type": "application/json" },
+    body: JSON.stringify(body),
+  });
+}
+
+describe("parseDeployments", () => {
+  it("should parse deployment=model pairs", () => {
+    expect(parseDeployments("gpt-4o=eliza, gpt-35-turbo = echo")).toEqual({
+      "gpt-4o": "eliza",
+      "gpt-35-turbo": "echo",
+    });
+  });
+
+  it("should return undefined when unset or empty", () => {
+    expect(parseDeployments(undefined)).toBeUndefined();
+    expect(parseDeployments(" , ")).toBeUndefined();
+  });
+
+  it("should reject entries without a model", () => {
+    expect(() => parseDeployments("gpt-4o")).toThrow("gpt-4o");
+    expect(() => parseDeployments("=eliza")).toThrow("<deployment>=<model>");
+  });
+});
+
+describe("deploymentModel", () => {
+  it("should fall back to the deployment name", () => {
+    expect(deploymentModel({ "gpt-4o": "eliza" }, "gpt-4o")).toBe("eliza");
+    expect(deploymentModel({ "gpt-4o": "eliza" }, "echo")).toBe("echo");
+    expect(deploymentModel(undefined, "echo")).toBe("echo");
+  });
+});
+
+describe("/openai/deployments/{deployment}/chat/completions", () => {
+  it("should answer with the deployment's model, whatever the body says", async () => {
+    const res = await post("gpt-35-turbo", { ...hello, model: "eliza" });
+
+    expect(res.status).toBe(200);
+    const data = await res.json();
+    expect(data.model).toBe("echo");
+    expect(data.choices[0].message.content).toBe("Hello there");
+  });
+
+  it("should take an unmapped deployment as a model name", async () => {
+    const res = await post("echo", hello);
+
+    expect(res.status).toBe(200);
+    expect((await res.json()).model).toBe("echo");
+  });
+
+  it("should stream like /v1", async () => {
+    const res = await post("gpt-35-turbo", { ...hello, stream: true });
+
+    expect(res.headers.get("content-type")).toContain("text/event-stream");
+    const text = await res.text();
+    expect(text).toContain("Hello there");
+    expect(text.trimEnd().endsWith("data: [DONE]")).toBe(true);
+  });
+
+  it("should require an api-version", async () => {
+    const res = await post("echo", hello, "");
+
+    expect(res.status).toBe(400);
+    expect((await res.json()).error.message).toContain("api-version");
+  });
+
+  it("should not know deployments of unknown models", async () => {
+    const res = await post("no-such-deployment", hello);
+
+    expect(res.status).toBe(404);
+  });
+
+  it("should need a valid api-key, and accept a bearer token", async () => {
+    const wrongKey = await app.request("/openai/deployments/echo/chat/completions?api-version=2024-10-21", {
+      method: "POST",
+      headers: { "api-key": "wrong" },
+      body: JSON.stringify(hello),
+    });
+    const bearer = await app.request("/openai/deployments/echo/chat/completions?api-version=2024-10-21", {
+      method: "POST",
+      headers: { Authorization: `Bearer ${testAPIKey}` },
+      body: JSON.stringify(hello),
+    });
+
+    expect(wrongKey.status).toBe(401);
+    expect(bearer.status).toBe(200);
+  });
+
+  it("should only take api-key under /openai", async () => {
+    const res = await app.request("/v1/models", { headers: { "api-key": testAPIKey } });
+
+    expect(res.status).toBe(401);
+  });
+});
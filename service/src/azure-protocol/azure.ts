@@ -0,0 +1,54 @@
+// Azure OpenAI's URL shape, for enterprise clients hard-coded to it
+//
+// Azure names a deployment in the path rather than a model in the body, as
+// /openai/deployments/{deployment}/chat/completions?api-version=..., and
+// takes the key in an api-key header. Deployments map onto teenytiny models;
+// one without a mapping is taken as a model name, so /deployments/echo/...
+// talks to echo.
+
+import { InvalidRequestError } from '../openai-protocol/errors.js';
+
+// Requests under this path may authenticate with an api-key header
+export const AZURE_PATH_PREFIX = '/openai/';
+
+// Deployment name to model, e.g. { "gpt-4o": "eliza" }
+export type AzureDeployments = Record<string, string>;
+
+/**
+ * Parses TEENYTINY_AZURE_DEPLOYMENTS, e.g. "gpt-4o=eliza,gpt-35-turbo=echo".
+ * Returns undefined when unset or empty.
+ */
+export function parseDeployments(value: string | undefined): AzureDeployments | undefined {
+  const entries = (value ?? '')
+    .split(',')
+    .map(entry => entry.trim())
+    .filter(entry => entry !== '');
+  if (entries.length === 0) {
+    return undefined;
+  }
+
+  const deployments: AzureDeployments = {};
+  for (const entry of entries) {
+    const equals = entry.indexOf('=');
+    const deployment = entry.slice(0, equals).trim();
+    const model = entry.slice(equals + 1).trim();
+    if (equals <= 0 || !deployment || !model) {
+      throw new Error(`Invalid Azure deployment "${entry}": expected <deployment>=<model>`);
+    }
+    deployments[deployment] = model;
+  }
+  return deployments;
+}
+
+// The model a deployment is served by
+export function deploymentModel(deployments: AzureDeployments | undefined, deployment: string): string {
+  return deployments?.[deployment] ?? deployment;
+}
+
+// Azure rejects requests that don't pin an API version, so clients that forget it fail here too
+export function requireApiVersion(apiVersion: string | undefined): string {
+  if (!apiVersion) {
+    throw new InvalidRequestError('Missing required query parameter: api-version', 'api-version');
+  }
+  return apiVersion;
+}
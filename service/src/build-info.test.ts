@@ -0,0 +1,20 @@
+import { describe, it, expect } from 'vitest';
+import { buildInfo } from './build-info.js';
+
+describe('buildInfo', () => {
+  it('keeps the values it was given', () => {
+    expect(buildInfo({ version: '1.0.0', gitSha: 'abc1234', buildTime: '2024-01-01T00:00:00Z' })).toEqual({
+      version: '1.0.0',
+      gitSha: 'abc1234',
+      buildTime: '2024-01-01T00:00:00Z',
+    });
+  });
+
+  it('reports missing or empty values as unknown', () => {
+    expect(buildInfo({ version: '1.0.0', gitSha: '' })).toEqual({
+      version: '1.0.0',
+      gitSha: 'unknown',
+      buildTime: 'unknown',
+    });
+  });
+});
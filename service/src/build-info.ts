@@ -0,0 +1,18 @@
+// Identifies the build serving requests, reported by /version
+
+export interface BuildInfo {
+  version: string;
+  gitSha: string;
+  buildTime: string;
+}
+
+export const UNKNOWN = 'unknown';
+
+// Fills in whatever the runtime couldn't tell us with "unknown"
+export function buildInfo(values: Partial<Record<keyof BuildInfo, string | undefined>>): BuildInfo {
+  return {
+    version: values.version || UNKNOWN,
+    gitSha: values.gitSha || UNKNOWN,
+    buildTime: values.buildTime || UNKNOWN,
+  };
+}
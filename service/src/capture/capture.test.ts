@@ -0,0 +1,211 @@
+import { describe, it, expect } from "vitest";
+import { Hono } from "hono";
+import { RequestCapture, parseCaptureSettings, parseRequestLogFilter, redact } from "./capture.js";
+import { MemoryRequestLog } from "./request-log.js";
+import { createErrorHandler } from "../middleware/errors.js";
+import { InvalidRequestError } from "../openai-protocol/errors.js";
+
+function createTestApp(capture: RequestCapture) {
+  const app = new Hono<{ Variables: { apiKey: string } }>();
+  app.onError(createErrorHandler());
+  app.use("*", async (c, next) => {
+    c.set("apiKey", c.req.header("x-key") ?? "key-a");
+    await next();
+  });
+  app.use("*", capture.middleware());
+  app.post("/v1/echo", async (c) => c.json({ received: await c.req.json() }));
+  app.post("/v1/fail", () => {
+    throw new InvalidRequestError("Nope", "model");
+  });
+  app.post("/v1/stream", () =>
+    new Response("data: [DONE]\n\n", { headers: { "Content-Type": "text/event-stream" } }),
+  );
+  // Sends one event and then never finishes
+  app.post("/v1/endless", () =>
+    new Response(
+      new ReadableStream({
+        start(controller) {
+          controller.enqueue(new TextEncoder().encode("data: {}\n\n"));
+        },
+      }),
+      { headers: { "Content-Type": "text/event-stream" } },
+    ),
+  );
+  return app;
+}
+
+const post = (app: ReturnType<typeof createTestApp>, path: string, body: unknown, key = "key-a") =>
+  app.request(path, {
+    method: "POST",
+    headers: { "x-key": key, "Content-Type": "application/json", Authorization: "Bearer secret" },
+    body: JSON.stringify(body),
+  });
+
+describe("RequestCapture", () => {
+  it("should log what the server received and answered", async () => {
+    const capture = new RequestCapture(new MemoryRequestLog());
+    const app = createTestApp(capture);
+
+    const res = await post(app, "/v1/echo", { model: "echo", temperature: 0.5 });
+    expect(await res.json()).toEqual({ received: { model: "echo", temperature: 0.5 } });
+
+    const [entry] = capture.query({ limit: 10 });
+    expect(entry).toMatchObject({
+      api_key: "key-a",
+      method: "POST",
+      path: "/v1/echo",
+      model: "echo",
+      status: 200,
+      stream: false,
+      cancelled: false,
+      request_body: { model: "echo", temperature: 0.5 },
+      response_body: { received: { model: "echo", temperature: 0.5 } },
+    });
+    expect(entry?.request_headers["content-type"]).toBe("application/json");
+    expect(entry?.request_headers).not.toHaveProperty("authorization");
+  });
+
+  it("should log errors and streams without their bodies", async () => {
+    const capture = new RequestCapture(new MemoryRequestLog());
+    const app = createTestApp(capture);
+
+    await post(app, "/v1/fail", { model: "echo" });
+    await (await post(app, "/v1/stream", { model: "echo", stream: true })).text();
+
+    const [stream, failed] = capture.query({ limit: 10 });
+    expect(stream).toMatchObject({ status: 200, stream: true, cancelled: false, response_body: null });
+    expect(failed?.status).toBe(400);
+    expect(failed?.response_body).toMatchObject({ error: { param: "model" } });
+  });
+
+  it("should log a stream once the client abandons it", async () => {
+    const capture = new RequestCapture(new MemoryRequestLog());
+    const app = createTestApp(capture);
+
+    const res = await post(app, "/v1/endless", { model: "slow", stream: true });
+    const reader = res.body!.getReader();
+    await reader.read();
+    expect(capture.query({ limit: 10 })).toEqual([]);
+
+    await reader.cancel();
+    expect(capture.query({ limit: 10 })).toMatchObject([{ path: "/v1/endless", stream: true, cancelled: true }]);
+  });
+
+  it("should filter by key, model and status, newest first", async () => {
+    const capture = new RequestCapture(new MemoryRequestLog());
+    const app = createTestApp(capture);
+
+    await post(app, "/v1/echo", { model: "echo", n: 1 });
+    await post(app, "/v1/echo", { model: "eliza", n: 2 });
+    await post(app, "/v1/echo", { model: "echo", n: 3 }, "key-b");
+    await post(app, "/v1/fail", { model: "echo", n: 4 });
+
+    const bodies = (filter: Parameters<RequestCapture["query"]>[0]) =>
+      capture.query(filter).map((entry) => (entry.request_body as { n: number }).n);
+    expect(bodies({ limit: 10 })).toEqual([4, 3, 2, 1]);
+    expect(bodies({ limit: 2 })).toEqual([4, 3]);
+    expect(bodies({ apiKey: "key-a", limit: 10 })).toEqual([4, 2, 1]);
+    expect(bodies({ apiKeys: ["key-b", "key-c"], limit: 10 })).toEqual([3]);
+    expect(bodies({ apiKeys: [], limit: 10 })).toEqual([]);
+    expect(bodies({ model: "echo", status: 200, limit: 10 })).toEqual([3, 1]);
+    expect(bodies({ since: Date.now() + 60_000, limit: 10 })).toEqual([]);
+  });
+});
+
+describe("Capture settings", () => {
+  it("should keep bodies only for the keys it is given", async () => {
+    const capture = new RequestCapture(new MemoryRequestLog());
+    const app = createTestApp(capture);
+    capture.configure({ keys: ["key-b"] });
+
+    await post(app, "/v1/echo", { model: "echo", n: 1 });
+    await post(app, "/v1/echo", { model: "echo", n: 2 }, "key-b");
+
+    const [kept, withheld] = capture.query({ limit: 10 });
+    expect(kept).toMatchObject({ api_key: "key-b", request_body: { n: 2 }, response_body: { received: { n: 2 } } });
+    expect(withheld).toMatchObject({ api_key: "key-a", model: "echo", status: 200, request_body: null, response_body: null });
+
+    capture.configure({ keys: null });
+    await post(app, "/v1/echo", { n: 3 });
+    expect(capture.query({ limit: 1 })[0]?.request_body).toEqual({ n: 3 });
+  });
+
+  it("should redact bodies and headers before storing them", async () => {
+    const capture = new RequestCapture(new MemoryRequestLog());
+    const app = createTestApp(capture);
+    capture.configure({ redact: ["\\b\\d{3}-\\d{2}-\\d{4}\\b", "[\\w.]+@[\\w.]+"] });
+
+    const res = await app.request("/v1/echo", {
+      method: "POST",
+      headers: { "x-key": "key-a", "Content-Type": "application/json", "x-user": "ada@example.com" },
+      body: JSON.stringify({ messages: [{ role: "user", content: "I'm ada@example.com, SSN 123-45-6789" }], n: 123 }),
+    });
+    // Only what is stored is redacted
+    expect((await res.json()).received.messages[0].content).toContain("123-45-6789");
+
+    const [entry] = capture.query({ limit: 1 });
+    const content = "I'm [REDACTED], SSN [REDACTED]";
+    expect(entry?.request_body).toEqual({ messages: [{ role: "user", content }], n: 123 });
+    expect(entry?.response_body).toEqual({ received: { messages: [{ role: "user", content }], n: 123 } });
+    expect(entry?.request_headers["x-user"]).toBe("[REDACTED]");
+    expect(JSON.stringify(entry)).not.toMatch(/6789|example\.com/);
+  });
+
+  it("should redact every string, however deep", () => {
+    expect(redact({ a: ["secret", { b: "my secret" }], secret: 1 }, [/secret/g])).toEqual({
+      a: ["[REDACTED]", { b: "my [REDACTED]" }],
+      secret: 1,
+    });
+  });
+
+  it("should validate settings", () => {
+    expect(parseCaptureSettings({ keys: null, redact: ["\\d+"] })).toEqual({ keys: null, redact: ["\\d+"] });
+    expect(parseCaptureSettings({})).toEqual({});
+    for (const [body, param] of [
+      [{ keys: "key-a" }, "keys"],
+      [{ redact: ["("] }, "redact"],
+      [{ redact: [""] }, "redact"],
+    ] as const) {
+      expect(() => parseCaptureSettings(body)).toThrow(expect.objectContaining({ param }));
+    }
+  });
+});
+
+describe("MemoryRequestLog", () => {
+  it("should keep only the most recent requests", async () => {
+    const capture = new RequestCapture(new MemoryRequestLog(2));
+    const app = createTestApp(capture);
+
+    for (let n = 1; n <= 3; n++) {
+      await post(app, "/v1/echo", { n });
+    }
+
+    expect(capture.query({ limit: 10 }).map((entry) => entry.request_body)).toEqual([{ n: 3 }, { n: 2 }]);
+  });
+});
+
+describe("parseRequestLogFilter", () => {
+  it("should read filters from the query string", () => {
+    expect(
+      parseRequestLogFilter({
+        model: "echo",
+        status: "429",
+        since: "2024-01-01T00:00:00Z",
+        until: "1704067260000",
+        limit: "5",
+      }),
+    ).toEqual({ model: "echo", status: 429, since: 1704067200000, until: 1704067260000, limit: 5 });
+  });
+
+  it("should name the invalid parameter", () => {
+    for (const [name, value] of [
+      ["status", "ok"],
+      ["since", "yesterday"],
+      ["limit", "0"],
+    ] as const) {
+      expect(() => parseRequestLogFilter({ [name]: value })).toThrow(
+        expect.objectContaining({ param: name }),
+      );
+    }
+  });
+});
@@ -0,0 +1,231 @@
+import { Context, Next } from 'hono';
+import { InvalidRequestError } from '../openai-protocol/errors.js';
+import type { CapturedRequest, RequestLogFilter, RequestLogStore } from './request-log.js';
+import { loggedWhenDone } from '../utils/logger.js';
+
+export const DEFAULT_QUERY_LIMIT = 50;
+export const MAX_QUERY_LIMIT = 1000;
+
+// What each match of a redaction pattern is replaced with
+export const REDACTED = '[REDACTED]';
+
+// Whose bodies are kept, and what is taken out of them first
+export interface CaptureConfig {
+  // Keys whose bodies are kept, or undefined for every key
+  keys?: string[];
+  // Regexes whose matches are replaced with REDACTED
+  redact: string[];
+}
+
+// Changes to a CaptureConfig, as PUT /admin/capture and config files give
+// them. Keys of null go back to every key.
+export interface CaptureSettings {
+  keys?: string[] | null;
+  redact?: string[];
+}
+
+/**
+ * RequestCapture - logs each request passing through its middleware
+ *
+ * Entries hold the headers and body the server received and the response it
+ * gave, so tests can check what a client library actually sent, e.g. whether
+ * a temperature setting was forwarded. Only JSON bodies are kept; streamed
+ * responses are logged without their body, once the stream has ended. In a
+ * shared environment bodies can be kept for only some keys, and redacted
+ * before they are stored.
+ */
+export class RequestCapture {
+  private config: CaptureConfig = { redact: [] };
+  private patterns: RegExp[] = [];
+
+  constructor(
+    private store: RequestLogStore,
+    private now: () => number = Date.now
+  ) {}
+
+  query(filter: RequestLogFilter): CapturedRequest[] {
+    return this.store.query(filter);
+  }
+
+  settings(): { keys: string[] | null; redact: string[] } {
+    return { keys: this.config.keys ?? null, redact: this.config.redact };
+  }
+
+  configure(settings: CaptureSettings): void {
+    if (settings.keys === null) delete this.config.keys;
+    else if (settings.keys !== undefined) this.config.keys = settings.keys;
+    if (settings.redact !== undefined) {
+      this.config.redact = settings.redact;
+      this.patterns = settings.redact.map(pattern => new RegExp(pattern, 'g'));
+    }
+  }
+
+  // A body as it is stored: redacted, or left out for keys whose bodies
+  // aren't kept
+  private keep(apiKey: string, body: unknown): unknown {
+    if (this.config.keys && !this.config.keys.includes(apiKey)) {
+      return null;
+    }
+    return redact(body, this.patterns);
+  }
+
+  // Must run after auth, which identifies the caller's API key
+  middleware() {
+    return async (c: Context, next: Next) => {
+      const started = this.now();
+      const requestBody = isJson(c.req.header('content-type')) ? parseBody(await c.req.text()) : null;
+
+      await next();
+
+      const stream = (c.res.headers.get('content-type') ?? '').startsWith('text/event-stream');
+      const responseBody =
+        !stream && isJson(c.res.headers.get('content-type') ?? undefined)
+          ? parseBody(await c.res.clone().text())
+          : null;
+
+      const headers: Record<string, string> = {};
+      c.req.raw.headers.forEach((value, name) => {
+        if (name !== 'authorization') {
+          headers[name] = redact(value, this.patterns) as string;
+        }
+      });
+
+      const apiKey: string = c.get('apiKey') ?? '';
+      const entry: CapturedRequest = {
+        id: c.get('requestId') ?? globalThis.crypto.randomUUID(),
+        timestamp: new Date(started).toISOString(),
+        api_key: apiKey,
+        method: c.req.method,
+        path: c.req.path,
+        model: modelOf(requestBody),
+        status: c.res.status,
+        duration_ms: this.now() - started,
+        stream,
+        cancelled: false,
+        request_headers: headers,
+        request_body: this.keep(apiKey, requestBody),
+        response_body: this.keep(apiKey, responseBody),
+      };
+
+      if (stream && c.res.body) {
+        // Streams are logged once they end, so the entry records whether the
+        // client went away first
+        const body = loggedWhenDone(c.res.body, cancelled => this.store.add({ ...entry, cancelled }));
+        c.res = new Response(body, c.res);
+      } else {
+        this.store.add({ ...entry, cancelled: c.req.raw.signal.aborted });
+      }
+    };
+  }
+}
+
+/**
+ * Validates {"keys": ["tt-team-a"], "redact": ["\\d{3}-\\d{2}-\\d{4}"]}, either of
+ * which may be left out
+ */
+export function parseCaptureSettings(body: unknown): CaptureSettings {
+  if (!body || typeof body !== 'object' || Array.isArray(body)) {
+    throw new InvalidRequestError('Invalid capture settings: expected an object with keys and/or redact');
+  }
+  const { keys, redact: patterns } = body as Record<string, unknown>;
+  if (keys !== undefined && keys !== null && (!Array.isArray(keys) || !keys.every(key => typeof key === 'string'))) {
+    throw new InvalidRequestError("Invalid 'keys': expected an array of API keys, or null for every key", 'keys');
+  }
+  if (patterns !== undefined) {
+    if (!Array.isArray(patterns) || !patterns.every(pattern => typeof pattern === 'string' && pattern !== '')) {
+      throw new InvalidRequestError("Invalid 'redact': expected an array of regexes", 'redact');
+    }
+    for (const pattern of patterns) {
+      try {
+        new RegExp(pattern, 'g');
+      } catch (error) {
+        throw new InvalidRequestError(`Invalid 'redact': ${(error as Error).message}`, 'redact');
+      }
+    }
+  }
+
+  const settings: CaptureSettings = {};
+  if (keys !== undefined) settings.keys = keys as string[] | null;
+  if (patterns !== undefined) settings.redact = patterns as string[];
+  return settings;
+}
+
+// Replaces every match in every string of a parsed body, object keys aside
+export function redact(value: unknown, patterns: RegExp[]): unknown {
+  if (patterns.length === 0) {
+    return value;
+  }
+  if (typeof value === 'string') {
+    return patterns.reduce((text, pattern) => text.replace(pattern, REDACTED), value);
+  }
+  if (Array.isArray(value)) {
+    return value.map(item => redact(item, patterns));
+  }
+  if (value && typeof value === 'object') {
+    return Object.fromEntries(Object.entries(value).map(([name, item]) => [name, redact(item, patterns)]));
+  }
+  return value;
+}
+
+// Reads ?model=&status=&since=&until=&limit= from a query string. Times are
+// ISO 8601 or epoch milliseconds.
+export function parseRequestLogFilter(query: Record<string, string>): RequestLogFilter {
+  const filter: RequestLogFilter = { limit: DEFAULT_QUERY_LIMIT };
+
+  if (query.model !== undefined) {
+    filter.model = query.model;
+  }
+  if (query.status !== undefined) {
+    const status = Number(query.status);
+    if (!Number.isInteger(status) || status < 100 || status > 599) {
+      throw new InvalidRequestError("Invalid 'status': expected an HTTP status code", 'status');
+    }
+    filter.status = status;
+  }
+  for (const name of ['since', 'until'] as const) {
+    const value = query[name];
+    if (value !== undefined) {
+      const time = /^\d+$/.test(value) ? Number(value) : Date.parse(value);
+      if (Number.isNaN(time)) {
+        throw new InvalidRequestError(
+          `Invalid '${name}': expected an ISO 8601 time or epoch milliseconds`,
+          name
+        );
+      }
+      filter[name] = time;
+    }
+  }
+  if (query.limit !== undefined) {
+    const limit = Number(query.limit);
+    if (!Number.isInteger(limit) || limit < 1 || limit > MAX_QUERY_LIMIT) {
+      throw new InvalidRequestError(
+        `Invalid 'limit': expected an integer from 1 to ${MAX_QUERY_LIMIT}`,
+        'limit'
+      );
+    }
+    filter.limit = limit;
+  }
+  return filter;
+}
+
+function isJson(contentType: string | undefined): boolean {
+  return /^application\/([\w.+-]+\+)?json\b/i.test(contentType ?? '');
+}
+
+function parseBody(text: string): unknown {
+  if (text === '') {
+    return null;
+  }
+  try {
+    return JSON.parse(text);
+  } catch {
+    return text;
+  }
+}
+
+function modelOf(body: unknown): string | null {
+  if (body && typeof body === 'object' && typeof (body as { model?: unknown }).model === 'string') {
+    return (body as { model: string }).model;
+  }
+  return null;
+}
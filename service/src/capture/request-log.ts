@@ -0,0 +1,101 @@
+// Request log: recent requests as the server received them, for test assertions
+
+export interface CapturedRequest {
+  id: string;
+  // ISO 8601 time the request arrived
+  timestamp: string;
+  api_key: string;
+  method: string;
+  path: string;
+  // The model named in a JSON body, if any
+  model: string | null;
+  status: number;
+  // Milliseconds until the response started, so excludes streaming
+  duration_ms: number;
+  stream: boolean;
+  // The client disconnected before the response was complete
+  cancelled: boolean;
+  // Authorization is left out; api_key identifies the caller
+  request_headers: Record<string, string>;
+  // Parsed JSON bodies, the raw text for anything that isn't JSON, null for
+  // non-JSON requests and streamed responses
+  request_body: unknown;
+  response_body: unknown;
+}
+
+export interface RequestLogFilter {
+  apiKey?: string;
+  // Any of these keys, as for a tenant
+  apiKeys?: string[];
+  model?: string;
+  status?: number;
+  // Epoch milliseconds, inclusive
+  since?: number;
+  until?: number;
+  limit: number;
+}
+
+// Where captured requests are kept. Queries return the newest first.
+export interface RequestLogStore {
+  add(entry: CapturedRequest): void;
+  query(filter: RequestLogFilter): CapturedRequest[];
+}
+
+export const DEFAULT_REQUEST_LOG_CAPACITY = 1000;
+
+export function matchesFilter(entry: CapturedRequest, filter: RequestLogFilter): boolean {
+  const time = Date.parse(entry.timestamp);
+  return (
+    (filter.apiKey === undefined || entry.api_key === filter.apiKey) &&
+    (filter.apiKeys === undefined || filter.apiKeys.includes(entry.api_key)) &&
+    (filter.model === undefined || entry.model === filter.model) &&
+    (filter.status === undefined || entry.status === filter.status) &&
+    (filter.since === undefined || time >= filter.since) &&
+    (filter.until === undefined || time <= filter.until)
+  );
+}
+
+/**
+ * In-memory storage, used when no request log file is configured. Keeps the
+ * most recent requests up to its capacity. On Cloudflare Workers the log only
+ * covers requests handled by the same isolate.
+ */
+export class MemoryRequestLog implements RequestLogStore {
+  private entries: CapturedRequest[] = [];
+  private changes = 0;
+
+  constructor(private capacity: number = DEFAULT_REQUEST_LOG_CAPACITY) {}
+
+  add(entry: CapturedRequest): void {
+    this.entries.push(entry);
+    if (this.entries.length > this.capacity) {
+      this.entries.splice(0, this.entries.length - this.capacity);
+    }
+    this.changes++;
+  }
+
+  // Every entry kept, oldest first, to be restored after a restart
+  snapshot(): CapturedRequest[] {
+    return [...this.entries];
+  }
+
+  restore(entries: CapturedRequest[]): void {
+    this.entries = entries.slice(-this.capacity);
+  }
+
+  // Goes up with every request logged
+  get version(): number {
+    return this.changes;
+  }
+
+  query(filter: RequestLogFilter): CapturedRequest[] {
+    const found: CapturedRequest[] = [];
+    for (let i = this.entries.length - 1; i >= 0 && found.length < filter.limit; i--) {
+      const entry = this.entries[i]!;
+      if (matchesFilter(entry, filter)) {
+        found.push(entry);
+      }
+    }
+    return found;
+  }
+}
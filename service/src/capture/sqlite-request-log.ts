@@ -0,0 +1,63 @@
+// Node.js only: keeps the request log in a SQLite database (node:sqlite)
+import { DatabaseSync } from 'node:sqlite';
+import { DEFAULT_REQUEST_LOG_CAPACITY } from './request-log.js';
+import type { CapturedRequest, RequestLogFilter, RequestLogStore } from './request-log.js';
+
+/**
+ * The log survives restarts and can be inspected with any SQLite client.
+ * Filtered columns are stored alongside the full entry as JSON. Only the
+ * most recent requests up to the capacity are kept.
+ */
+export class SqliteRequestLog implements RequestLogStore {
+  private db: DatabaseSync;
+
+  constructor(file: string, private capacity: number = DEFAULT_REQUEST_LOG_CAPACITY) {
+    this.db = new DatabaseSync(file);
+    this.db.exec(`
+      CREATE TABLE IF NOT EXISTS requests (
+        seq INTEGER PRIMARY KEY AUTOINCREMENT,
+        time_ms INTEGER NOT NULL,
+        api_key TEXT NOT NULL,
+        model TEXT,
+        status INTEGER NOT NULL,
+        entry TEXT NOT NULL
+      )
+    `);
+  }
+
+  add(entry: CapturedRequest): void {
+    this.db
+      .prepare('INSERT INTO requests (time_ms, api_key, model, status, entry) VALUES (?, ?, ?, ?, ?)')
+      .run(Date.parse(entry.timestamp), entry.api_key, entry.model, entry.status, JSON.stringify(entry));
+    this.db
+      .prepare('DELETE FROM requests WHERE seq <= (SELECT MAX(seq) FROM requests) - ?')
+      .run(this.capacity);
+  }
+
+  query(filter: RequestLogFilter): CapturedRequest[] {
+    const clauses: string[] = [];
+    const params: (string | number)[] = [];
+    const where = (clause: string, value: string | number | undefined) => {
+      if (value !== undefined) {
+        clauses.push(clause);
+        params.push(value);
+      }
+    };
+    where('api_key = ?', filter.apiKey);
+    if (filter.apiKeys !== undefined) {
+      clauses.push(filter.apiKeys.length > 0 ? `api_key IN (${filter.apiKeys.map(() => '?').join(', ')})` : '0');
+      params.push(...filter.apiKeys);
+    }
+    where('model = ?', filter.model);
+    where('status = ?', filter.status);
+    where('time_ms >= ?', filter.since);
+    where('time_ms <= ?', filter.until);
+
+    const sql =
+      'SELECT entry FROM requests' +
+      (clauses.length > 0 ? ` WHERE ${clauses.join(' AND ')}` : '') +
+      ' ORDER BY seq DESC LIMIT ?';
+    const rows = this.db.prepare(sql).all(...params, filter.limit) as { entry: string }[];
+    return rows.map((row): CapturedRequest => JSON.parse(row.entry));
+  }
+}
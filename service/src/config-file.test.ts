@@ -0,0 +1,152 @@
+import { describe, it, expect } from 'vitest';
+import { parseConfigFile } from './config-file.js';
+
+describe('parseConfigFile', () => {
+  it('reads each section of a TOML file', () => {
+    const text = `
+port = 9000
+api_key = "secret"
+state = "teenytiny.db"
+models = ["echo", "eliza"]
+
+[rate_limit]
+requests_per_minute = 60
+
+[faults]
+failure_rate = 0.2
+kinds = ["503"]
+
+[latency."/v1/*".ttfb]
+type = "fixed"
+ms = 100
+
+[model_defaults.echo]
+max_tokens = 10
+
+[quotas]
+budgeted = 500
+
+[capture]
+keys = ["scoped"]
+redact = ["\\\\d{4}"]
+
+[[keys]]
+key = "scoped"
+models = ["echo"]
+tenant = "team-a"
+
+[[webhooks]]
+url = "http://localhost:9000/hooks"
+secret = "whsec"
+events = ["error.emitted"]
+`;
+
+    expect(parseConfigFile(text, 'teenytiny.toml')).toEqual({
+      port: 9000,
+      apiKey: 'secret',
+      state: 'teenytiny.db',
+      settings: {
+        models: ['echo', 'eliza'],
+        keys: [{ key: 'scoped', models: ['echo'], tenant: 'team-a' }],
+        requestsPerMinute: 60,
+        faults: { failureRate: 0.2, kinds: ['503'] },
+        latency: { '/v1/*': { ttfb: { type: 'fixed', ms: 100 } } },
+        modelDefaults: { echo: { max_tokens: 10 } },
+        quotas: { budgeted: 500 },
+        capture: { keys: ['scoped'], redact: ['\\d{4}'] },
+        webhooks: [{ url: 'http://localhost:9000/hooks', secret: 'whsec', events: ['error.emitted'] }],
+      },
+    });
+  });
+
+  it('reads JSON files and leaves out what they leave out', () => {
+    expect(parseConfigFile('{"rate_limit": {"requests_per_minute": 5}}', 'teenytiny.json')).toEqual({
+      settings: { requestsPerMinute: 5 },
+    });
+    expect(parseConfigFile('', 'empty.toml')).toEqual({ settings: {} });
+  });
+
+  it('reads routers, keeping their routes in order', () => {
+    const text = `
+[routers.agent]
+fallback = "eliza"
+
+[[routers.agent.routes]]
+match = "weather"
+flags = "i"
+reply = "It's sunny."
+
+[[routers.agent.routes]]
+match = "."
+model = "lorem"
+`;
+
+    expect(parseConfigFile(text, 'teenytiny.toml').settings.routers).toEqual({
+      agent: {
+        fallback: 'eliza',
+        routes: [
+          { match: 'weather', flags: 'i', reply: "It's sunny." },
+          { match: '.', model: 'lorem' },
+        ],
+      },
+    });
+  });
+
+  it('reads the middleware order, keeping auth on every API route', () => {
+    const text = `
+[middleware]
+"*" = ["cors", "compression"]
+"/v1/*" = ["auth", "logging", "latency"]
+"/openai/*" = ["auth"]
+"/api/*" = ["auth"]
+"/v1beta/*" = ["auth"]
+"/session/*" = ["auth"]
+"/admin/*" = ["auth"]
+"/debug/*" = ["auth"]
+`;
+
+    const { middleware } = parseConfigFile(text, 'teenytiny.toml');
+    expect(Object.keys(middleware!)).toEqual(['*', '/v1/*', '/openai/*', '/api/*', '/v1beta/*', '/session/*', '/admin/*', '/debug/*']);
+    expect(middleware!['/v1/*']).toEqual(['auth', 'logging', 'latency']);
+    expect(() => parseConfigFile(text.replace('"/v1/*" = ["auth", ', '"/v1/*" = ['), 'teenytiny.toml')).toThrow(
+      expect.objectContaining({ statusCode: 400, param: 'middleware./v1/*' }),
+    );
+    expect(() => parseConfigFile(text.replace('"/admin/*" = ["auth"]', ''), 'teenytiny.toml')).toThrow(
+      expect.objectContaining({ statusCode: 400, param: 'middleware./admin/*' }),
+    );
+  });
+
+  it('rejects invalid files', () => {
+    for (const text of [
+      'port = "8080"',
+      'api_key = ""',
+      'state = true',
+      'unknown = 1',
+      'models = "echo"',
+      '[rate_limit]\nrequests_per_minute = 0',
+      '[faults]\nfailure_rate = 2',
+      '[quotas]\nkey = -1',
+      '[[keys]]\nmodels = ["echo"]',
+      '[[keys]]\nkey = "a"\nmodel = "echo"',
+      '[[keys]]\nkey = "a"\ntenant = ""',
+      'a = ',
+      '[routers.agent]\nfallback = "echo"',
+      '[[routers.agent.routes]]\nmatch = "("\nreply = "hi"',
+      '[[routers.agent.routes]]\nmatch = "hi"',
+      '[[routers.agent.routes]]\nmatch = "hi"\nreply = "hi"\nmodel = "echo"',
+      '[[routers.agent.routes]]\nmatch = "hi"\nmodel = "router:agent"',
+      'middleware = ["auth"]',
+      '[middleware]\n"*" = ["auth", "cache"]',
+      '[middleware]\n"*" = ["auth", "auth"]',
+      '[middleware]\n"*" = "auth"',
+      '[capture]\nredact = ["("]',
+      '[capture]\nkeys = "scoped"',
+      '[[webhooks]]\nurl = "ftp://localhost/hooks"\nsecret = "whsec"',
+      '[[webhooks]]\nurl = "http://localhost:9000/hooks"\nsecret = "whsec"\nevents = ["request.sent"]',
+    ]) {
+      expect(() => parseConfigFile(text, 'teenytiny.toml')).toThrow(
+        expect.objectContaining({ statusCode: 400, type: 'invalid_request_error' }),
+      );
+    }
+  });
+});
@@ -0,0 +1,309 @@
+// Server settings read from a --config file, TOML or (for .json files) JSON
+//
+// Each section takes the same shape as the admin endpoint that changes it, so
+// a file can be written by copying what GET /admin/... returns:
+//
+//   port = 8080
+//   api_key = "testkey"
+//   state = "teenytiny.db"
+//   models = ["echo", "eliza", "slow"]
+//
+//   [rate_limit]
+//   requests_per_minute = 60
+//
+//   [faults]
+//   failure_rate = 0.2
+//   kinds = ["503", "reset"]
+//
+//   [latency."/v1/*".ttfb]
+//   type = "fixed"
+//   ms = 200
+//
+//   [model_defaults.echo]
+//   max_tokens = 100
+//
+//   [quotas]
+//   budgeted-key = 10000
+//
+//   [[keys]]
+//   key = "echo-only"
+//   models = ["echo"]
+//
+//   [capture]
+//   keys = ["team-a-key"]
+//   redact = ["\\d{3}-\\d{2}-\\d{4}"]
+//
+//   [[webhooks]]
+//   url = "http://localhost:9000/hooks"
+//   secret = "whsec"
+//   events = ["error.emitted"]
+//
+//   [routers.agent]
+//   fallback = "echo"
+//
+//   [[routers.agent.routes]]
+//   match = "weather"
+//   flags = "i"
+//   reply = "It's sunny."
+//
+//   [middleware]
+//   "*" = ["cors", "compression"]
+//   "/v1/*" = ["auth", "logging", "rate-limit", "body-limit", "latency"]
+//   "/openai/*" = ["auth", "rate-limit", "body-limit"]
+//   "/api/*" = ["auth"]
+//   "/v1beta/*" = ["auth"]
+//   "/session/*" = ["auth"]
+//   "/admin/*" = ["auth"]
+//   "/debug/*" = ["auth"]
+//
+// The port, API key, state database and middleware order are read once at
+// startup; everything else is applied again when the server reloads the file.
+
+import { InvalidRequestError } from './openai-protocol/errors.js';
+import { parseFaultSettings, type FaultSettings } from './openai-protocol/faults.js';
+import { parseModelDefaults, type ModelDefaultsConfig } from './openai-protocol/model-defaults.js';
+import { parseLatencyConfig, type LatencyConfig } from './middleware/latency.js';
+import { parseMiddlewareConfig, type MiddlewareConfig } from './middleware/middleware-config.js';
+import type { ScopedKey } from './auth/auth-config.js';
+import { parseCaptureSettings, type CaptureSettings } from './capture/capture.js';
+import { parseWebhooks, type Webhook } from './webhooks/webhooks.js';
+import type { Route, Router } from './models/router-model.js';
+import { parseToml, TomlError } from './utils/toml.js';
+
+// Settings that can change while the server runs. Sections a file leaves out
+// keep whatever value the server has.
+export interface ServerSettings {
+  // Models clients may use, every model when left out
+  models?: string[];
+  keys?: ScopedKey[];
+  requestsPerMinute?: number;
+  faults?: FaultSettings;
+  latency?: LatencyConfig;
+  modelDefaults?: ModelDefaultsConfig;
+  // Token budgets by key
+  quotas?: Record<string, number>;
+  // Router models by name, served as router:<name>
+  routers?: Record<string, Router>;
+  // Whose bodies the request log keeps, and what it redacts
+  capture?: CaptureSettings;
+  // Where request lifecycle events are sent
+  webhooks?: Webhook[];
+}
+
+export interface ConfigFile {
+  port?: number;
+  apiKey?: string;
+  state?: string;
+  // Ordered middleware per route group, in place of the default stack
+  middleware?: MiddlewareConfig;
+  settings: ServerSettings;
+}
+
+const SECTIONS = ['port', 'api_key', 'state', 'models', 'keys', 'rate_limit', 'faults', 'latency', 'model_defaults', 'quotas', 'routers', 'capture', 'webhooks', 'middleware'];
+
+/**
+ * Parses a config file's text, throwing InvalidRequestError on the first
+ * problem. The file name picks the format.
+ */
+export function parseConfigFile(text: string, file: string): ConfigFile {
+  let body: unknown;
+  try {
+    body = file.endsWith('.json') ? JSON.parse(text) : parseToml(text);
+  } catch (error) {
+    if (error instanceof SyntaxError || error instanceof TomlError) {
+      throw new InvalidRequestError(`Invalid config file ${file}: ${error.message}`);
+    }
+    throw error;
+  }
+  if (!isObject(body)) {
+    throw new InvalidRequestError(`Invalid config file ${file}: expected an object of settings`);
+  }
+
+  for (const name of Object.keys(body)) {
+    if (!SECTIONS.includes(name)) {
+      throw new InvalidRequestError(`Unknown setting '${name}': expected one of ${SECTIONS.join(', ')}`, name);
+    }
+  }
+
+  const config: ConfigFile = { settings: {} };
+  const { settings } = config;
+  if (body.port !== undefined) {
+    if (!Number.isInteger(body.port) || (body.port as number) < 1 || (body.port as number) > 65535) {
+      throw new InvalidRequestError("Invalid 'port': expected an integer from 1 to 65535", 'port');
+    }
+    config.port = body.port as number;
+  }
+  if (body.api_key !== undefined) {
+    if (typeof body.api_key !== 'string' || body.api_key === '') {
+      throw new InvalidRequestError("Invalid 'api_key': expected a non-empty string", 'api_key');
+    }
+    config.apiKey = body.api_key;
+  }
+  if (body.state !== undefined) {
+    if (typeof body.state !== 'string' || body.state === '') {
+      throw new InvalidRequestError("Invalid 'state': expected the path of a SQLite database", 'state');
+    }
+    config.state = body.state;
+  }
+  if (body.middleware !== undefined) {
+    config.middleware = parseMiddlewareConfig(body.middleware);
+  }
+  if (body.models !== undefined) {
+    settings.models = parseNames(body.models, 'models');
+  }
+  if (body.keys !== undefined) {
+    settings.keys = parseKeys(body.keys);
+  }
+  if (body.rate_limit !== undefined) {
+    const rpm = isObject(body.rate_limit) ? body.rate_limit.requests_per_minute : undefined;
+    if (!Number.isInteger(rpm) || (rpm as number) < 1) {
+      throw new InvalidRequestError(
+        "Invalid 'rate_limit.requests_per_minute': expected a positive integer",
+        'rate_limit.requests_per_minute'
+      );
+    }
+    settings.requestsPerMinute = rpm as number;
+  }
+  if (body.faults !== undefined) {
+    settings.faults = parseFaultSettings(body.faults);
+  }
+  if (body.latency !== undefined) {
+    settings.latency = parseLatencyConfig(body.latency);
+  }
+  if (body.model_defaults !== undefined) {
+    settings.modelDefaults = parseModelDefaults(body.model_defaults);
+  }
+  if (body.quotas !== undefined) {
+    settings.quotas = parseQuotas(body.quotas);
+  }
+  if (body.routers !== undefined) {
+    settings.routers = parseRouters(body.routers);
+  }
+  if (body.capture !== undefined) {
+    settings.capture = parseCaptureSettings(body.capture);
+  }
+  if (body.webhooks !== undefined) {
+    settings.webhooks = parseWebhooks(body.webhooks);
+  }
+  return config;
+}
+
+function parseNames(value: unknown, param: string): string[] {
+  if (!Array.isArray(value) || !value.every(name => typeof name === 'string')) {
+    throw new InvalidRequestError(`Invalid '${param}': expected an array of model names`, param);
+  }
+  return value;
+}
+
+// [[keys]] entries, each a key and optionally the models it may use and the
+// tenant it belongs to
+function parseKeys(value: unknown): ScopedKey[] {
+  if (!Array.isArray(value)) {
+    throw new InvalidRequestError("Invalid 'keys': expected an array of tables with key and models", 'keys');
+  }
+  return value.map((entry, i) => {
+    const param = `keys[${i}]`;
+    if (!isObject(entry) || typeof entry.key !== 'string' || entry.key === '') {
+      throw new InvalidRequestError(`Invalid '${param}': expected a table with a key`, param);
+    }
+    const unknown = Object.keys(entry).find(name => !['key', 'models', 'tenant'].includes(name));
+    if (unknown !== undefined) {
+      throw new InvalidRequestError(`Unknown setting '${param}.${unknown}': expected key, models or tenant`, `${param}.${unknown}`);
+    }
+    if (entry.tenant !== undefined && (typeof entry.tenant !== 'string' || entry.tenant === '')) {
+      throw new InvalidRequestError(`Invalid '${param}.tenant': expected a non-empty string`, `${param}.tenant`);
+    }
+    return {
+      key: entry.key,
+      ...(entry.models === undefined ? {} : { models: parseNames(entry.models, `${param}.models`) }),
+      ...(entry.tenant === undefined ? {} : { tenant: entry.tenant }),
+    };
+  });
+}
+
+function parseQuotas(value: unknown): Record<string, number> {
+  if (!isObject(value)) {
+    throw new InvalidRequestError("Invalid 'quotas': expected a table of keys to token budgets", 'quotas');
+  }
+  for (const [key, budget] of Object.entries(value)) {
+    if (!Number.isInteger(budget) || (budget as number) < 0) {
+      throw new InvalidRequestError(`Invalid 'quotas.${key}': expected a non-negative integer`, `quotas.${key}`);
+    }
+  }
+  return value as Record<string, number>;
+}
+
+// [routers.<name>] tables, each an optional fallback model and [[routes]]
+function parseRouters(value: unknown): Record<string, Router> {
+  if (!isObject(value)) {
+    throw new InvalidRequestError("Invalid 'routers': expected a table of router names to routers", 'routers');
+  }
+  return Object.fromEntries(Object.entries(value).map(([name, router]) => {
+    const param = `routers.${name}`;
+    if (!isObject(router) || !Array.isArray(router.routes)) {
+      throw new InvalidRequestError(`Invalid '${param}': expected a table with routes`, param);
+    }
+    const unknown = Object.keys(router).find(setting => setting !== 'routes' && setting !== 'fallback');
+    if (unknown !== undefined) {
+      throw new InvalidRequestError(`Unknown setting '${param}.${unknown}': expected routes or fallback`, `${param}.${unknown}`);
+    }
+    const parsed: Router = { routes: router.routes.map((route, i) => parseRoute(route, `${param}.routes[${i}]`)) };
+    if (router.fallback !== undefined) {
+      parsed.fallback = parseTarget(router.fallback, `${param}.fallback`);
+    }
+    return [name, parsed];
+  }));
+}
+
+function parseRoute(route: unknown, param: string): Route {
+  if (!isObject(route) || typeof route.match !== 'string') {
+    throw new InvalidRequestError(`Invalid '${param}': expected a table with a match regex`, param);
+  }
+  const unknown = Object.keys(route).find(setting => !['match', 'flags', 'model', 'reply'].includes(setting));
+  if (unknown !== undefined) {
+    throw new InvalidRequestError(
+      `Unknown setting '${param}.${unknown}': expected match, flags, model or reply`,
+      `${param}.${unknown}`
+    );
+  }
+  if (route.flags !== undefined && typeof route.flags !== 'string') {
+    throw new InvalidRequestError(`Invalid '${param}.flags': expected a string`, `${param}.flags`);
+  }
+  try {
+    new RegExp(route.match, route.flags);
+  } catch (error) {
+    throw new InvalidRequestError(`Invalid '${param}.match': ${(error as Error).message}`, `${param}.match`);
+  }
+
+  if ((route.model === undefined) === (route.reply === undefined)) {
+    throw new InvalidRequestError(`Invalid '${param}': expected either a model or a reply`, param);
+  }
+  const parsed: Route = { match: route.match };
+  if (route.flags !== undefined) {
+    parsed.flags = route.flags;
+  }
+  if (route.model !== undefined) {
+    parsed.model = parseTarget(route.model, `${param}.model`);
+  } else if (typeof route.reply === 'string') {
+    parsed.reply = route.reply;
+  } else {
+    throw new InvalidRequestError(`Invalid '${param}.reply': expected a string`, `${param}.reply`);
+  }
+  return parsed;
+}
+
+// A model a router hands requests to. Routers can't hand them to routers, so
+// routes can't go round in circles.
+function parseTarget(value: unknown, param: string): string {
+  if (typeof value !== 'string' || value === '') {
+    throw new InvalidRequestError(`Invalid '${param}': expected a model name`, param);
+  }
+  if (value.startsWith('router:')) {
+    throw new InvalidRequestError(`Invalid '${param}': a router can't hand requests to another router`, param);
+  }
+  return value;
+}
+
+function isObject(value: unknown): value is Record<string, unknown> {
+  return typeof value === 'object' && value !== null && !Array.isArray(value);
+}
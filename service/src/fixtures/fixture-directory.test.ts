@@ -0,0 +1,53 @@
+import { describe, it, expect, afterEach } from "vitest";
+import fs from "fs";
+import os from "os";
+import path from "path";
+import { FixtureDirectory } from "./fixture-directory.js";
+
+describe("FixtureDirectory", () => {
+  const dirs: string[] = [];
+  const tempDir = () => {
+    const dir = fs.mkdtempSync(path.join(os.tmpdir(), "fixtures-"));
+    dirs.push(dir);
+    return dir;
+  };
+
+  afterEach(() => {
+    for (const dir of dirs.splice(0)) {
+      fs.rmSync(dir, { recursive: true, force: true });
+    }
+  });
+
+  it("should load every JSON file in name order", () => {
+    const dir = tempDir();
+    fs.writeFileSync(path.join(dir, "b.json"), JSON.stringify([{ match: "b", response: "B" }]));
+    fs.writeFileSync(path.join(dir, "a.json"), JSON.stringify([{ match: "a", response: "A" }]));
+    fs.writeFileSync(path.join(dir, "notes.txt"), "ignored");
+
+    const source = new FixtureDirectory(dir);
+
+    expect(source.fixtures().map(f => f.match)).toEqual(["a", "b"]);
+  });
+
+  it("should keep the previous fixtures when a reload fails", () => {
+    const dir = tempDir();
+    fs.writeFileSync(path.join(dir, "a.json"), JSON.stringify([{ match: "a", response: "A" }]));
+    const source = new FixtureDirectory(dir);
+
+    fs.writeFileSync(path.join(dir, "a.json"), "{ not json");
+    source.reload();
+
+    expect(source.fixtures()).toHaveLength(1);
+  });
+
+  it("should pick up edits on reload", () => {
+    const dir = tempDir();
+    fs.writeFileSync(path.join(dir, "a.json"), JSON.stringify([{ match: "a", response: "A" }]));
+    const source = new FixtureDirectory(dir);
+
+    fs.writeFileSync(path.join(dir, "a.json"), JSON.stringify([{ match: "a", response: "Changed" }]));
+    source.reload();
+
+    expect(source.fixtures()[0]!.response).toBe("Changed");
+  });
+});
@@ -0,0 +1,67 @@
+// Node.js only: loads fixtures from a directory and reloads them on change
+import fs from 'fs';
+import path from 'path';
+import { parseFixtures } from '../models/fixture-model.js';
+import type { Fixture, FixtureSource } from '../models/fixture-model.js';
+
+const RELOAD_DEBOUNCE_MS = 100;
+
+/**
+ * Every *.json file in the directory holds an array of fixtures; files are read
+ * in name order. Edits are picked up without a restart. If a reload fails, the
+ * error is logged and the previous fixtures stay in use.
+ */
+export class FixtureDirectory implements FixtureSource {
+  private loaded: Fixture[];
+  private watcher: fs.FSWatcher | undefined;
+  private reloadTimer: ReturnType<typeof setTimeout> | undefined;
+
+  constructor(private dir: string) {
+    this.loaded = this.load();
+  }
+
+  fixtures(): Fixture[] {
+    return this.loaded;
+  }
+
+  watch(): void {
+    this.watcher = fs.watch(this.dir, () => {
+      clearTimeout(this.reloadTimer);
+      this.reloadTimer = setTimeout(() => this.reload(), RELOAD_DEBOUNCE_MS);
+    });
+  }
+
+  close(): void {
+    clearTimeout(this.reloadTimer);
+    this.watcher?.close();
+  }
+
+  reload(): void {
+    try {
+      this.loaded = this.load();
+      console.log(JSON.stringify({
+        level: 'info',
+        message: 'Fixtures reloaded',
+        dir: this.dir,
+        fixture_count: this.loaded.length,
+      }));
+    } catch (error) {
+      console.error(JSON.stringify({
+        level: 'error',
+        message: 'Fixture reload failed, keeping previous fixtures',
+        dir: this.dir,
+        error: error instanceof Error ? error.message : String(error),
+      }));
+    }
+  }
+
+  private load(): Fixture[] {
+    return fs.readdirSync(this.dir)
+      .filter(file => file.endsWith('.json'))
+      .sort()
+      .flatMap(file => {
+        const text = fs.readFileSync(path.join(this.dir, file), 'utf8');
+        return parseFixtures(JSON.parse(text), file);
+      });
+  }
+}
@@ -0,0 +1,240 @@
+import { describe, it, expect } from "vitest";
+import { createApp } from "../app.js";
+import { parseGenerateContentRequest, parseModelMethod } from "./gemini.js";
+
+const testAPIKey = "tt-gemini-key";
+const app = createApp({ auth: { apiKey: testAPIKey } });
+
+function post(path: string, body: unknown) {
+  return app.request(path, {
+    method: "POST",
+    headers: { "x-goog-api-key": testAPIKey, "Content-Type": "application/json" },
+    body: JSON.stringify(body),
+  });
+}
+
+const hello = { contents: [{ role: "user", parts: [{ text: "Hello there" }] }] };
+
+describe("parseModelMethod", () => {
+  it("should split at the last colon", () => {
+    expect(parseModelMethod("echo:generateContent")).toEqual({ model: "echo", method: "generateContent" });
+    expect(parseModelMethod("slow:200:streamGenerateContent")).toEqual({
+      model: "slow:200",
+      method: "streamGenerateContent",
+    });
+  });
+
+  it("should reject unknown methods", () => {
+    expect(parseModelMethod("echo:countTokens")).toBeUndefined();
+    expect(parseModelMethod("echo")).toBeUndefined();
+  });
+});
+
+describe("parseGenerateContentRequest", () => {
+  it("should map contents, roles and the system instruction onto messages", () => {
+    const request = parseGenerateContentRequest(
+      {
+        systemInstruction: { parts: [{ text: "Be brief" }] },
+        contents: [
+          { role: "user", parts: [{ text: "Hi" }] },
+          { role: "model", parts: [{ text: "Hello" }] },
+          { role: "user", parts: [{ text: "Look" }, { inlineData: { mimeType: "image/jpeg", data: "/9j/4AAQ" } }] },
+        ],
+      },
+      "echo",
+      false,
+    );
+
+    expect(request.messages).toEqual([
+      { role: "system", content: "Be brief" },
+      { role: "user", content: "Hi" },
+      { role: "assistant", content: "Hello" },
+      {
+        role: "user",
+        content: [
+          { type: "text", text: "Look" },
+          { type: "image_url", image_url: { url: "data:image/jpeg;base64,/9j/4AAQ" } },
+        ],
+      },
+    ]);
+  });
+
+  it("should map generationConfig onto chat completion parameters", () => {
+    const request = parseGenerateContentRequest(
+      {
+        ...hello,
+        generationConfig: {
+          temperature: 0.2,
+          topP: 0.9,
+          seed: 7,
+          maxOutputTokens: 16,
+          stopSequences: ["END"],
+          responseMimeType: "application/json",
+        },
+      },
+      "echo",
+      true,
+    );
+
+    expect(request).toMatchObject({
+      stream: true,
+      temperature: 0.2,
+      top_p: 0.9,
+      seed: 7,
+      max_tokens: 16,
+      stop: ["END"],
+      response_format: { type: "json_object" },
+    });
+  });
+
+  it("should translate function declarations and lower their schema types", () => {
+    const request = parseGenerateContentRequest(
+      {
+        ...hello,
+        tools: [
+          {
+            functionDeclarations: [
+              {
+                name: "get_weather",
+                parameters: { type: "OBJECT", properties: { city: { type: "STRING" } }, required: ["city"] },
+              },
+            ],
+          },
+        ],
+        toolConfig: { functionCallingConfig: { mode: "ANY" } },
+      },
+      "echo",
+      false,
+    );
+
+    expect(request.tools).toEqual([
+      {
+        type: "function",
+        function: {
+          name: "get_weather",
+          parameters: { type: "object", properties: { city: { type: "string" } }, required: ["city"] },
+        },
+      },
+    ]);
+    expect(request.tool_choice).toBe("required");
+  });
+
+  it("should reject requests without contents", () => {
+    expect(() => parseGenerateContentRequest({}, "echo", false)).toThrow("contents");
+  });
+});
+
+describe("/v1beta/models/{model}:generateContent", () => {
+  it("should answer with candidates and usageMetadata", async () => {
+    const res = await post("/v1beta/models/echo:generateContent", hello);
+
+    expect(res.status).toBe(200);
+    const data = await res.json();
+    expect(data.candidates).toHaveLength(1);
+    expect(data.candidates[0]).toMatchObject({
+      content: { role: "model", parts: [{ text: "Hello there" }] },
+      finishReason: "STOP",
+      index: 0,
+    });
+    const usage = data.usageMetadata;
+    expect(usage.candidatesTokenCount).toBeGreaterThan(0);
+    expect(usage.totalTokenCount).toBe(usage.promptTokenCount + usage.candidatesTokenCount);
+  });
+
+  it("should report replies cut short by maxOutputTokens", async () => {
+    const res = await post("/v1beta/models/echo:generateContent", {
+      contents: [{ parts: [{ text: "one two three four five" }] }],
+      generationConfig: { maxOutputTokens: 2 },
+    });
+
+    expect((await res.json()).candidates[0].finishReason).toBe("MAX_TOKENS");
+  });
+
+  it("should return function calls with their arguments as objects", async () => {
+    const res = await post("/v1beta/models/tooluse:generateContent", {
+      contents: [{ role: "user", parts: [{ text: "Weather?" }] }],
+      tools: [
+        {
+          functionDeclarations: [
+            {
+              name: "get_weather",
+              parameters: { type: "OBJECT", properties: { city: { type: "STRING" } }, required: ["city"] },
+            },
+          ],
+        },
+      ],
+    });
+
+    const call = (await res.json()).candidates[0].content.parts[0].functionCall;
+    expect(call.name).toBe("get_weather");
+    expect(typeof call.args.city).toBe("string");
+  });
+
+  it("should accept the key as a query parameter or a bearer token", async () => {
+    const byQuery = await app.request(`/v1beta/models/echo:generateContent?key=${testAPIKey}`, {
+      method: "POST",
+      body: JSON.stringify(hello),
+    });
+    const byBearer = await app.request("/v1beta/models/echo:generateContent", {
+      method: "POST",
+      headers: { Authorization: `Bearer ${testAPIKey}` },
+      body: JSON.stringify(hello),
+    });
+
+    expect(byQuery.status).toBe(200);
+    expect(byBearer.status).toBe(200);
+  });
+
+  it("should only take x-goog-api-key under /v1beta", async () => {
+    const res = await app.request("/v1/models", { headers: { "x-goog-api-key": testAPIKey } });
+
+    expect(res.status).toBe(401);
+  });
+
+  it("should send errors as Google does", async () => {
+    const missing = await post("/v1beta/models/no-such-model:generateContent", hello);
+    expect(missing.status).toBe(404);
+    expect((await missing.json()).error).toMatchObject({ code: 404, status: "NOT_FOUND" });
+
+    const unauthorized = await app.request("/v1beta/models/echo:generateContent", {
+      method: "POST",
+      body: JSON.stringify(hello),
+    });
+    expect(unauthorized.status).toBe(401);
+    expect((await unauthorized.json()).error).toMatchObject({ code: 401, status: "UNAUTHENTICATED" });
+  });
+
+  it("should not know other methods", async () => {
+    const res = await post("/v1beta/models/echo:countTokens", hello);
+
+    expect(res.status).toBe(404);
+  });
+});
+
+describe("/v1beta/models/{model}:streamGenerateContent", () => {
+  it("should stream a JSON array whose last element finishes with usage", async () => {
+    const res = await post("/v1beta/models/echo:streamGenerateContent", hello);
+
+    expect(res.status).toBe(200);
+    expect(res.headers.get("content-type")).toContain("application/json");
+    const chunks: any[] = JSON.parse(await res.text());
+    const last = chunks.at(-1);
+    expect(chunks.length).toBeGreaterThan(1);
+    expect(chunks.slice(0, -1).every((chunk) => chunk.candidates[0].finishReason === undefined)).toBe(true);
+    expect(chunks.map((chunk) => chunk.candidates[0].content.parts[0].text).join("")).toBe("Hello there");
+    expect(last.candidates[0].finishReason).toBe("STOP");
+    expect(last.usageMetadata.candidatesTokenCount).toBeGreaterThan(0);
+  });
+
+  it("should send server-sent events for alt=sse", async () => {
+    const res = await post("/v1beta/models/echo:streamGenerateContent?alt=sse", hello);
+
+    expect(res.headers.get("content-type")).toContain("text/event-stream");
+    const events = (await res.text())
+      .split("\r\n\r\n")
+      .filter((event) => event.startsWith("data: "))
+      .map((event) => JSON.parse(event.slice("data: ".length)));
+    expect(events.map((event) => event.candidates[0].content.parts[0].text).join("")).toBe("Hello there");
+    expect(events.at(-1).candidates[0].finishReason).toBe("STOP");
+  });
+});
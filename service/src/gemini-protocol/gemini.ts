@@ -0,0 +1,366 @@
+// Google's Gemini API, so apps built on Google's SDKs can be tested offline
+//
+// generateContent and streamGenerateContent requests are translated into chat
+// completion requests and answered by the same adapters as /v1. Replies go
+// back as candidates with usageMetadata. Streams are a JSON array sent one
+// element at a time, or server-sent events when the request asks for alt=sse,
+// as Google's SDKs do.
+
+import { InvalidRequestError } from '../openai-protocol/errors.js';
+import type {
+  ChatCompletionContentPart,
+  ChatCompletionFinishReason,
+  ChatCompletionMessageToolCall,
+  ChatCompletionRequest,
+  ChatCompletionRequestMessage,
+  ChatCompletionResponse,
+  ChatCompletionStreamResponse,
+  ChatCompletionTool,
+  ChatCompletionToolChoice,
+  ChatCompletionUsage,
+} from '../openai-protocol/types.js';
+import {
+  validateResponseFormat,
+  validateSamplingParameters,
+  validateTools,
+} from '../openai-protocol/validation.js';
+
+// Requests under this path authenticate and fail the way Google's API does
+export const GEMINI_PATH_PREFIX = '/v1beta/';
+
+export const GEMINI_METHODS = ['generateContent', 'streamGenerateContent'] as const;
+export type GeminiMethod = typeof GEMINI_METHODS[number];
+
+export interface GeminiPart {
+  text?: string;
+  inlineData?: { mimeType: string; data: string };
+  functionCall?: { name: string; args?: Record<string, unknown> };
+  functionResponse?: { name: string; response: Record<string, unknown> };
+}
+
+export interface GeminiContent {
+  // user, or model for earlier replies; function for function responses in older clients
+  role?: 'user' | 'model' | 'function';
+  parts: GeminiPart[];
+}
+
+export interface GeminiGenerationConfig {
+  temperature?: number;
+  topP?: number;
+  seed?: number;
+  maxOutputTokens?: number;
+  stopSequences?: string[];
+  responseMimeType?: string;
+  responseSchema?: Record<string, unknown>;
+}
+
+export interface GeminiFunctionDeclaration {
+  name: string;
+  description?: string;
+  parameters?: Record<string, unknown>;
+}
+
+export interface GeminiGenerateContentRequest {
+  contents: GeminiContent[];
+  systemInstruction?: GeminiContent;
+  generationConfig?: GeminiGenerationConfig;
+  tools?: { functionDeclarations?: GeminiFunctionDeclaration[] }[];
+  toolConfig?: { functionCallingConfig?: { mode?: 'AUTO' | 'ANY' | 'NONE'; allowedFunctionNames?: string[] } };
+}
+
+// Google names its statuses after gRPC codes
+const STATUS_NAMES: Record<number, string> = {
+  400: 'INVALID_ARGUMENT',
+  401: 'UNAUTHENTICATED',
+  403: 'PERMISSION_DENIED',
+  404: 'NOT_FOUND',
+  409: 'ALREADY_EXISTS',
+  413: 'INVALID_ARGUMENT',
+  415: 'INVALID_ARGUMENT',
+  429: 'RESOURCE_EXHAUSTED',
+  499: 'CANCELLED',
+  500: 'INTERNAL',
+  502: 'UNAVAILABLE',
+  503: 'UNAVAILABLE',
+  504: 'DEADLINE_EXCEEDED',
+};
+
+// Google's error body, {"error": {"code", "message", "status"}}
+export function geminiError(message: string, code: number) {
+  return { error: { code, message, status: STATUS_NAMES[code] ?? 'UNKNOWN' } };
+}
+
+/**
+ * Splits the last path segment, such as "slow:200:generateContent", into the
+ * model and method. Model names may themselves contain colons.
+ */
+export function parseModelMethod(segment: string): { model: string; method: GeminiMethod } | undefined {
+  const colon = segment.lastIndexOf(':');
+  const method = segment.slice(colon + 1);
+  if (colon <= 0 || !(GEMINI_METHODS as readonly string[]).includes(method)) {
+    return undefined;
+  }
+  return { model: segment.slice(0, colon), method: method as GeminiMethod };
+}
+
+function isObject(value: unknown): value is Record<string, any> {
+  return typeof value === 'object' && value !== null && !Array.isArray(value);
+}
+
+// Google's schemas spell types in capitals ("OBJECT"); JSON Schema uses lower case
+export function lowerSchemaTypes(schema: unknown): unknown {
+  if (Array.isArray(schema)) {
+    return schema.map(lowerSchemaTypes);
+  }
+  if (!isObject(schema)) {
+    return schema;
+  }
+  return Object.fromEntries(
+    Object.entries(schema).map(([key, value]) => [
+      key,
+      key === 'type' && typeof value === 'string' ? value.toLowerCase() : lowerSchemaTypes(value),
+    ])
+  );
+}
+
+function partsOf(content: unknown, path: string): GeminiPart[] {
+  if (!isObject(content) || !Array.isArray(content.parts)) {
+    throw new InvalidRequestError(`Invalid ${path}: expected an object with parts`, path);
+  }
+  content.parts.forEach((part, i) => {
+    if (!isObject(part)) {
+      throw new InvalidRequestError(`Invalid ${path}.parts[${i}]: must be an object`, path);
+    }
+    if (part.inlineData !== undefined && !String(part.inlineData?.mimeType).startsWith('image/')) {
+      throw new InvalidRequestError(`Invalid ${path}.parts[${i}].inlineData: only images are supported`, path);
+    }
+  });
+  return content.parts;
+}
+
+// The text and images of a content's parts, as chat completion content
+function messageContent(parts: GeminiPart[]): string | ChatCompletionContentPart[] {
+  const text = parts.map(part => (typeof part.text === 'string' ? part.text : '')).join('');
+  const images = parts.filter(part => part.inlineData);
+  if (images.length === 0) {
+    return text;
+  }
+  return [
+    { type: 'text', text },
+    ...images.map((part): ChatCompletionContentPart => ({
+      type: 'image_url',
+      image_url: { url: `data:${part.inlineData!.mimeType};base64,${part.inlineData!.data}` },
+    })),
+  ];
+}
+
+// One content becomes one message, or one tool message per function response
+function toMessages(content: unknown, index: number, turn: { calls: number }): ChatCompletionRequestMessage[] {
+  const path = `contents[${index}]`;
+  const parts = partsOf(content, path);
+  const role = (content as GeminiContent).role ?? 'user';
+  if (!['user', 'model', 'function'].includes(role)) {
+    throw new InvalidRequestError(`Invalid ${path}.role: must be one of 'user' or 'model'`, path);
+  }
+
+  // Gemini matches function responses to calls by name, so the ids only need to be distinct
+  const responses = parts.filter(part => part.functionResponse);
+  if (responses.length > 0) {
+    return responses.map((part): ChatCompletionRequestMessage => ({
+      role: 'tool',
+      content: JSON.stringify(part.functionResponse!.response ?? {}),
+      tool_call_id: part.functionResponse!.name,
+    }));
+  }
+
+  const calls = parts.filter(part => part.functionCall);
+  if (role === 'model') {
+    const message: ChatCompletionRequestMessage = { role: 'assistant', content: messageContent(parts) };
+    if (calls.length > 0) {
+      message.tool_calls = calls.map((part): ChatCompletionMessageToolCall => ({
+        id: `call_${turn.calls++}`,
+        type: 'function',
+        function: { name: part.functionCall!.name, arguments: JSON.stringify(part.functionCall!.args ?? {}) },
+      }));
+    }
+    return [message];
+  }
+  return [{ role: 'user', content: messageContent(parts) }];
+}
+
+function toTools(tools: unknown): ChatCompletionTool[] {
+  if (!Array.isArray(tools)) {
+    throw new InvalidRequestError("Invalid 'tools': expected an array", 'tools');
+  }
+  return tools.flatMap(tool => (isObject(tool) && Array.isArray(tool.functionDeclarations) ? tool.functionDeclarations : []))
+    .map((declaration: any): ChatCompletionTool => ({
+      type: 'function',
+      function: {
+        name: declaration?.name,
+        ...(declaration?.description !== undefined ? { description: declaration.description } : {}),
+        ...(declaration?.parameters !== undefined
+          ? { parameters: lowerSchemaTypes(declaration.parameters) as Record<string, unknown> }
+          : {}),
+      },
+    }));
+}
+
+function toToolChoice(config: unknown): ChatCompletionToolChoice | undefined {
+  const calling = isObject(config) ? config.functionCallingConfig : undefined;
+  if (!isObject(calling)) {
+    return undefined;
+  }
+  const allowed = calling.allowedFunctionNames;
+  if (calling.mode === 'ANY' && Array.isArray(allowed) && allowed.length === 1) {
+    return { type: 'function', function: { name: allowed[0] } };
+  }
+  return calling.mode === 'ANY' ? 'required' : calling.mode === 'NONE' ? 'none' : 'auto';
+}
+
+// Copies generationConfig onto the equivalent chat completion parameters
+function applyGenerationConfig(request: ChatCompletionRequest, config: unknown): void {
+  if (config === undefined) {
+    return;
+  }
+  if (!isObject(config)) {
+    throw new InvalidRequestError("Invalid 'generationConfig': expected an object", 'generationConfig');
+  }
+  if (config.temperature !== undefined) request.temperature = config.temperature;
+  if (config.topP !== undefined) request.top_p = config.topP;
+  if (config.seed !== undefined) request.seed = config.seed;
+  if (config.maxOutputTokens !== undefined) request.max_tokens = config.maxOutputTokens;
+  if (config.stopSequences !== undefined) request.stop = config.stopSequences;
+
+  if (config.responseSchema !== undefined) {
+    request.response_format = {
+      type: 'json_schema',
+      json_schema: { name: 'response', schema: lowerSchemaTypes(config.responseSchema) as Record<string, unknown> },
+    };
+  } else if (config.responseMimeType === 'application/json') {
+    request.response_format = { type: 'json_object' };
+  }
+}
+
+/**
+ * Translates a generateContent request body for the model named in its path.
+ * The system instruction becomes a system message ahead of the contents.
+ */
+export function parseGenerateContentRequest(body: unknown, model: string, stream: boolean): ChatCompletionRequest {
+  if (!isObject(body)) {
+    throw new InvalidRequestError('Request body must be a JSON object');
+  }
+  if (!Array.isArray(body.contents) || body.contents.length === 0) {
+    throw new InvalidRequestError('Missing required parameter: contents', 'contents');
+  }
+
+  const messages: ChatCompletionRequestMessage[] = [];
+  if (body.systemInstruction !== undefined) {
+    messages.push({ role: 'system', content: messageContent(partsOf(body.systemInstruction, 'systemInstruction')) });
+  }
+  const turn = { calls: 0 };
+  body.contents.forEach((content: unknown, i: number) => messages.push(...toMessages(content, i, turn)));
+
+  const request: ChatCompletionRequest = { model, messages, stream };
+  if (body.tools !== undefined) request.tools = toTools(body.tools);
+  const toolChoice = toToolChoice(body.toolConfig);
+  if (toolChoice !== undefined) request.tool_choice = toolChoice;
+  applyGenerationConfig(request, body.generationConfig);
+
+  const parameters = request as unknown as Record<string, unknown>;
+  validateSamplingParameters(parameters);
+  validateTools(parameters);
+  validateResponseFormat(parameters);
+  return request;
+}
+
+function finishReason(reason: ChatCompletionFinishReason | null | undefined): string {
+  switch (reason) {
+    case 'length':
+      return 'MAX_TOKENS';
+    case 'content_filter':
+      return 'SAFETY';
+    default:
+      return 'STOP';
+  }
+}
+
+function usageMetadata(usage: ChatCompletionUsage) {
+  return {
+    promptTokenCount: usage.prompt_tokens,
+    candidatesTokenCount: usage.completion_tokens,
+    totalTokenCount: usage.total_tokens,
+  };
+}
+
+function functionCallParts(calls: ChatCompletionMessageToolCall[]): GeminiPart[] {
+  return calls.map(call => ({
+    functionCall: { name: call.function.name, args: JSON.parse(call.function.arguments || '{}') },
+  }));
+}
+
+// One response object, as a whole reply or one piece of a stream
+function candidateResponse(
+  model: string,
+  parts: GeminiPart[],
+  finish?: ChatCompletionFinishReason | null,
+  usage?: ChatCompletionUsage
+): Record<string, unknown> {
+  return {
+    candidates: [
+      {
+        content: { role: 'model', parts },
+        ...(finish !== undefined ? { finishReason: finishReason(finish) } : {}),
+        index: 0,
+      },
+    ],
+    ...(usage ? { usageMetadata: usageMetadata(usage) } : {}),
+    modelVersion: model,
+  };
+}
+
+// A whole reply, for generateContent
+export function geminiResponse(model: string, response: ChatCompletionResponse): Record<string, unknown> {
+  const choice = response.choices[0];
+  const parts: GeminiPart[] = choice?.message.tool_calls
+    ? functionCallParts(choice.message.tool_calls)
+    : [{ text: choice?.message.content ?? '' }];
+  return candidateResponse(model, parts, choice?.finish_reason ?? 'stop', response.usage);
+}
+
+/**
+ * Rewrites a chat completion stream as streamGenerateContent responses. Text
+ * goes out as it arrives; function calls, which stream in fragments, are
+ * gathered and sent whole. The last response has the finish reason and usage.
+ */
+export async function* geminiStream(
+  model: string,
+  chunks: AsyncIterable<ChatCompletionStreamResponse>
+): AsyncGenerator<Record<string, unknown>> {
+  const toolCalls: ChatCompletionMessageToolCall[] = [];
+  let finish: ChatCompletionFinishReason | null | undefined;
+  let usage: ChatCompletionUsage | undefined;
+
+  for await (const chunk of chunks) {
+    const choice = chunk.choices[0];
+    usage = chunk.usage ?? usage;
+    finish = choice?.finish_reason ?? finish;
+
+    for (const fragment of choice?.delta.tool_calls ?? []) {
+      const call = (toolCalls[fragment.index] ??= {
+        id: fragment.id ?? '',
+        type: 'function',
+        function: { name: '', arguments: '' },
+      });
+      call.function.name += fragment.function.name ?? '';
+      call.function.arguments += fragment.function.arguments ?? '';
+    }
+
+    const text = choice?.delta.content;
+    if (text) {
+      yield candidateResponse(model, [{ text }]);
+    }
+  }
+
+  const parts = toolCalls.length > 0 ? functionCallParts(toolCalls) : [{ text: '' }];
+  yield candidateResponse(model, parts, finish ?? 'stop', usage);
+}
@@ -1,9 +1,32 @@
 // Cloudflare Worker entry point
+import { upgradeWebSocket } from 'hono/cloudflare-workers';
 import { createApp } from './app.js';
+import { parseKeyList, parseNameList } from './auth/auth-config.js';
+import { parseUpstream } from './openai-protocol/proxy.js';
+import { parseOrigins } from './middleware/cors.js';
+import { parseDeployments } from './azure-protocol/azure.js';
+import { buildInfo } from './build-info.js';
 
 // Environment interface for Cloudflare Workers
 export interface Env {
   API_KEY?: string;
+  // Extra keys, e.g. "key1,key2:echo|eliza" limits key2 to echo and eliza
+  TEENYTINY_API_KEYS?: string;
+  TEENYTINY_REVOKED_KEYS?: string;
+  // Comma-separated OpenAI-Organization and OpenAI-Project values to accept, any if unset
+  TEENYTINY_ORGANIZATIONS?: string;
+  TEENYTINY_PROJECTS?: string;
+  // Comma-separated origins browsers may call from, any if unset
+  TEENYTINY_CORS_ORIGINS?: string;
+  // OpenAI-compatible base URL and key that proxy:<model> requests are forwarded to
+  TEENYTINY_UPSTREAM?: string;
+  TEENYTINY_UPSTREAM_KEY?: string;
+  // Azure deployments and the models serving them, e.g. "gpt-4o=eliza"
+  TEENYTINY_AZURE_DEPLOYMENTS?: string;
+  // Build metadata reported by /version, set at deploy time
+  TEENYTINY_VERSION?: string;
+  TEENYTINY_GIT_SHA?: string;
+  TEENYTINY_BUILD_TIME?: string;
 }
 
 // Create the app instance
@@ -17,10 +40,26 @@ const app = createApp({
 export default {
   async fetch(request: Request, env: Env, ctx: ExecutionContext): Promise<Response> {
     // Update the auth config with the environment variable
+    const upstream = parseUpstream(env.TEENYTINY_UPSTREAM, env.TEENYTINY_UPSTREAM_KEY);
+    const corsOrigins = parseOrigins(env.TEENYTINY_CORS_ORIGINS);
+    const deployments = parseDeployments(env.TEENYTINY_AZURE_DEPLOYMENTS);
     const appWithEnv = createApp({
+      upgradeWebSocket,
       auth: {
         apiKey: env.API_KEY || 'tt-1234567890abcdef',
+        keys: parseKeyList(env.TEENYTINY_API_KEYS),
+        revokedKeys: parseKeyList(env.TEENYTINY_REVOKED_KEYS).map(({ key }) => key),
+        organizations: parseNameList(env.TEENYTINY_ORGANIZATIONS),
+        projects: parseNameList(env.TEENYTINY_PROJECTS),
       },
+      ...(corsOrigins ? { cors: { allowOrigins: corsOrigins } } : {}),
+      ...(upstream ? { upstream } : {}),
+      ...(deployments ? { azure: { deployments } } : {}),
+      build: buildInfo({
+        version: env.TEENYTINY_VERSION,
+        gitSha: env.TEENYTINY_GIT_SHA,
+        buildTime: env.TEENYTINY_BUILD_TIME,
+      }),
     });
 
     return appWithEnv.fetch(request, env, ctx);
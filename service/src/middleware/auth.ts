@@ -1,30 +1,73 @@
 import { Context, Next } from 'hono';
-import { AuthenticationError } from '../openai-protocol/errors.js';
+import { AccountMismatchError, AuthenticationError } from '../openai-protocol/errors.js';
 import type { Authenticator } from '../auth/authenticator.js';
+import type { AuthConfig } from '../auth/auth-config.js';
+import { GEMINI_PATH_PREFIX } from '../gemini-protocol/gemini.js';
+import { AZURE_PATH_PREFIX } from '../azure-protocol/azure.js';
 
-export function createAuthMiddleware(authenticator: Authenticator) {
+// Extracts the token from an Authorization header. The scheme is matched
+// case-insensitively (RFC 7235), and surrounding whitespace is ignored.
+export function parseBearerToken(header: string): string | undefined {
+  const match = /^\s*bearer\s+(\S+)\s*$/i.exec(header);
+  return match?.[1];
+}
+
+const PUBLIC_PATHS = new Set(['/health', '/healthz', '/readyz', '/version']);
+
+// Google's and Azure's SDKs send the key in their own header, or for Google as ?key=
+function vendorApiKey(c: Context): string | undefined {
+  if (c.req.path.startsWith(GEMINI_PATH_PREFIX)) {
+    return c.req.header('x-goog-api-key') ?? c.req.query('key');
+  }
+  if (c.req.path.startsWith(AZURE_PATH_PREFIX)) {
+    return c.req.header('api-key');
+  }
+  return undefined;
+}
+
+// OpenAI's SDKs send these when configured with an organization or project
+export const ORGANIZATION_HEADER = 'OpenAI-Organization';
+export const PROJECT_HEADER = 'OpenAI-Project';
+
+// Any organization or project is accepted unless the config lists them
+function checkAccounts(c: Context, accounts: Pick<AuthConfig, 'organizations' | 'projects'>): void {
+  const organization = c.req.header(ORGANIZATION_HEADER)?.trim();
+  if (organization !== undefined && accounts.organizations && !accounts.organizations.includes(organization)) {
+    throw new AccountMismatchError('organization');
+  }
+  const project = c.req.header(PROJECT_HEADER)?.trim();
+  if (project !== undefined && accounts.projects && !accounts.projects.includes(project)) {
+    throw new AccountMismatchError('project');
+  }
+}
+
+export function createAuthMiddleware(
+  authenticator: Authenticator,
+  accounts: Pick<AuthConfig, 'organizations' | 'projects'> = {}
+) {
   return async (c: Context, next: Next) => {
-    // Skip auth for health check
-    if (c.req.path === '/health') {
+    // Skip auth for health, readiness and version checks
+    if (PUBLIC_PATHS.has(c.req.path)) {
       await next();
       return;
     }
 
     const authHeader = c.req.header('Authorization');
-    if (!authHeader) {
+    const token = authHeader ? parseBearerToken(authHeader) : vendorApiKey(c);
+    if (!authHeader && !token) {
       throw new AuthenticationError('No authorization header provided');
     }
-
-    const bearerPrefix = 'Bearer ';
-    if (!authHeader.startsWith(bearerPrefix)) {
+    if (!token) {
       throw new AuthenticationError('Invalid authorization header format. Expected "Bearer <token>"');
     }
 
-    const token = authHeader.slice(bearerPrefix.length);
     const isValid = await authenticator.validateApiKey(token);
     if (!isValid) {
       throw new AuthenticationError('Invalid API key');
     }
+    checkAccounts(c, accounts);
+
+    c.set('apiKey', token);
 
     await next();
   };
@@ -0,0 +1,11 @@
+import { bodyLimit } from 'hono/body-limit';
+import { RequestTooLargeError } from '../openai-protocol/errors.js';
+
+export function createBodyLimitMiddleware(maxBytes: number) {
+  return bodyLimit({
+    maxSize: maxBytes,
+    onError: (c) => {
+      return c.json(new RequestTooLargeError(maxBytes).toErrorResponse(), 413);
+    },
+  });
+}
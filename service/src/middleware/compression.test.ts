@@ -0,0 +1,155 @@
+import { describe, it, expect } from "vitest";
+import { brotliDecompressSync, deflateSync, gunzipSync, gzipSync } from "zlib";
+import { createApp } from "../app.js";
+import { chooseEncoding } from "./compression.js";
+import { NODE_COMPRESSORS } from "./node-compressors.js";
+
+const testAPIKey = "tt-compression-key";
+
+const app = createApp({
+  auth: { apiKey: testAPIKey },
+  compression: NODE_COMPRESSORS,
+  limits: { maxBodyBytes: 4096 },
+});
+
+function chatBody(content: string, stream = false) {
+  return JSON.stringify({ model: "echo", stream, messages: [{ role: "user", content }] });
+}
+
+function post(body: BodyInit, headers: Record<string, string>) {
+  return app.request("/v1/chat/completions", {
+    method: "POST",
+    headers: { Authorization: `Bearer ${testAPIKey}`, "Content-Type": "application/json", ...headers },
+    body,
+  });
+}
+
+async function bytes(res: Response): Promise<Buffer> {
+  return Buffer.from(await res.arrayBuffer());
+}
+
+describe("chooseEncoding", () => {
+  const both = ["br", "gzip"] as const;
+
+  it("should prefer brotli when both are accepted equally", () => {
+    expect(chooseEncoding("gzip, deflate, br", both)).toBe("br");
+  });
+
+  it("should follow q-values", () => {
+    expect(chooseEncoding("br;q=0.5, gzip", both)).toBe("gzip");
+    expect(chooseEncoding("*;q=0.1, gzip;q=0", both)).toBe("br");
+  });
+
+  it("should only pick encodings that are available and accepted", () => {
+    expect(chooseEncoding("br", ["gzip"])).toBeUndefined();
+    expect(chooseEncoding("identity", both)).toBeUndefined();
+    expect(chooseEncoding("br;q=0, gzip;q=0", both)).toBeUndefined();
+    expect(chooseEncoding(undefined, both)).toBeUndefined();
+  });
+});
+
+describe("Response compression", () => {
+  const content = "A reply long enough to be worth compressing. ".repeat(50);
+
+  it("should gzip JSON responses for clients that accept it", async () => {
+    const res = await post(chatBody(content), { "Accept-Encoding": "gzip" });
+
+    expect(res.status).toBe(200);
+    expect(res.headers.get("content-encoding")).toBe("gzip");
+    expect(res.headers.get("vary")).toContain("Accept-Encoding");
+    const data = JSON.parse(gunzipSync(await bytes(res)).toString());
+    expect(data.choices[0].message.content).toBe(content.trim());
+  });
+
+  it("should prefer brotli", async () => {
+    const res = await post(chatBody(content), { "Accept-Encoding": "gzip, br" });
+
+    expect(res.headers.get("content-encoding")).toBe("br");
+    const data = JSON.parse(brotliDecompressSync(await bytes(res)).toString());
+    expect(data.choices[0].message.content).toBe(content.trim());
+  });
+
+  it("should leave responses alone for clients that don't ask", async () => {
+    const res = await post(chatBody("Hello"), {});
+
+    expect(res.headers.get("content-encoding")).toBeNull();
+    expect((await res.json()).choices[0].message.content).toBe("Hello");
+  });
+
+  it("should compress error responses too", async () => {
+    const res = await post("{not json", { "Accept-Encoding": "gzip" });
+
+    expect(res.status).toBe(400);
+    expect(res.headers.get("content-encoding")).toBe("gzip");
+    expect(JSON.parse(gunzipSync(await bytes(res)).toString()).error.type).toBe("invalid_request_error");
+  });
+
+  it("should never compress event streams", async () => {
+    const res = await post(chatBody(content, true), { "Accept-Encoding": "gzip, br" });
+
+    expect(res.headers.get("content-type")).toContain("text/event-stream");
+    expect(res.headers.get("content-encoding")).toBeNull();
+    const text = await res.text();
+    expect(text).toContain("data: [DONE]");
+  });
+
+  it("should not compress without compressors, as on Cloudflare Workers", async () => {
+    const edge = createApp({ auth: { apiKey: testAPIKey } });
+
+    const res = await edge.request("/v1/models", {
+      headers: { Authorization: `Bearer ${testAPIKey}`, "Accept-Encoding": "gzip, br" },
+    });
+
+    expect(res.headers.get("content-encoding")).toBeNull();
+    expect((await res.json()).object).toBe("list");
+  });
+});
+
+describe("Request decompression", () => {
+  it("should accept gzip-compressed request bodies", async () => {
+    const res = await post(gzipSync(chatBody("Compressed hello")), { "Content-Encoding": "gzip" });
+
+    expect(res.status).toBe(200);
+    expect((await res.json()).choices[0].message.content).toBe("Compressed hello");
+  });
+
+  it("should accept deflate-compressed request bodies", async () => {
+    const res = await post(deflateSync(chatBody("Deflated hello")), { "Content-Encoding": "deflate" });
+
+    expect(res.status).toBe(200);
+    expect((await res.json()).choices[0].message.content).toBe("Deflated hello");
+  });
+
+  it("should round-trip a compressed request and response", async () => {
+    const content = "Round trip ".repeat(100).trim();
+
+    const res = await post(gzipSync(chatBody(content)), { "Content-Encoding": "gzip", "Accept-Encoding": "gzip" });
+
+    expect(res.headers.get("content-encoding")).toBe("gzip");
+    expect(JSON.parse(gunzipSync(await bytes(res)).toString()).choices[0].message.content).toBe(content);
+  });
+
+  it("should reject encodings it can't decompress", async () => {
+    const res = await post(chatBody("Hi"), { "Content-Encoding": "zstd" });
+
+    expect(res.status).toBe(415);
+    const data = await res.json();
+    expect(data.error.code).toBe("unsupported_content_encoding");
+  });
+
+  it("should reject bodies that aren't valid gzip", async () => {
+    const res = await post(chatBody("Hi"), { "Content-Encoding": "gzip" });
+
+    expect(res.status).toBe(400);
+  });
+
+  it("should apply the body limit to the decompressed size", async () => {
+    // A few dozen bytes compressed, far over the limit once inflated
+    const bomb = gzipSync(chatBody("a".repeat(100_000)));
+
+    const res = await post(bomb, { "Content-Encoding": "gzip" });
+
+    expect(bomb.length).toBeLessThan(4096);
+    expect(res.status).toBe(413);
+  });
+});
@@ -0,0 +1,111 @@
+import { Context, Next } from 'hono';
+import { UnsupportedContentEncodingError } from '../openai-protocol/errors.js';
+
+// Response encodings, most preferred first when a client accepts several equally
+export const RESPONSE_ENCODINGS = ['br', 'gzip'] as const;
+export type ResponseEncoding = typeof RESPONSE_ENCODINGS[number];
+
+// Request encodings, which every runtime can decompress
+export const REQUEST_ENCODINGS = ['gzip', 'deflate'] as const;
+type RequestEncoding = typeof REQUEST_ENCODINGS[number];
+
+// Makes a stream that compresses whatever is written to it
+export type Compressor = () => ReadableWritablePair<Uint8Array, Uint8Array>;
+
+// The response encodings the runtime can produce. Cloudflare compresses at the
+// edge, so the Worker leaves this empty.
+export type Compressors = Partial<Record<ResponseEncoding, Compressor>>;
+
+// Responses known to be smaller than this go out uncompressed
+export const MIN_COMPRESSED_BYTES = 1024;
+
+// JSON and text, but never event streams: a compressor holds output back until
+// it has a block's worth, which would stall tokens on their way to the client
+const COMPRESSIBLE_TYPE = /^\s*(application\/json|text\/(?!event-stream\b))/i;
+
+/**
+ * Picks the response encoding for an Accept-Encoding header such as
+ * "gzip;q=0.8, br", or undefined to send the response as is
+ */
+export function chooseEncoding(
+  acceptEncoding: string | undefined,
+  available: readonly ResponseEncoding[]
+): ResponseEncoding | undefined {
+  const weights = new Map<string, number>();
+  for (const part of (acceptEncoding ?? '').split(',')) {
+    const [name, ...params] = part.trim().toLowerCase().split(';');
+    if (!name) continue;
+    const q = params.map(param => param.trim()).find(param => param.startsWith('q='));
+    const weight = q === undefined ? 1 : Number(q.slice(2));
+    weights.set(name.trim(), Number.isFinite(weight) ? weight : 0);
+  }
+
+  let best: ResponseEncoding | undefined;
+  let bestWeight = 0;
+  for (const encoding of RESPONSE_ENCODINGS) {
+    if (!available.includes(encoding)) continue;
+    const weight = weights.get(encoding) ?? weights.get('*') ?? 0;
+    if (weight > bestWeight) {
+      best = encoding;
+      bestWeight = weight;
+    }
+  }
+  return best;
+}
+
+// Swaps in a request whose body decompresses as it's read. It has no
+// Content-Length, so body-limit counts the decompressed bytes as they arrive.
+function decompressRequest(c: Context): void {
+  const encoding = c.req.header('Content-Encoding')?.trim().toLowerCase();
+  if (!encoding || encoding === 'identity') {
+    return;
+  }
+  if (!(REQUEST_ENCODINGS as readonly string[]).includes(encoding)) {
+    throw new UnsupportedContentEncodingError(encoding, REQUEST_ENCODINGS);
+  }
+
+  const raw = c.req.raw;
+  const headers = new Headers(raw.headers);
+  headers.delete('Content-Encoding');
+  headers.delete('Content-Length');
+  c.req.raw = new Request(raw, {
+    headers,
+    body: raw.body?.pipeThrough(new DecompressionStream(encoding as RequestEncoding)) ?? null,
+    duplex: 'half',
+  } as RequestInit);
+}
+
+function compressResponse(c: Context, compressors: Compressors): void {
+  const res = c.res;
+  if (
+    !res.body ||
+    res.headers.has('Content-Encoding') ||
+    c.req.method === 'HEAD' ||
+    !COMPRESSIBLE_TYPE.test(res.headers.get('Content-Type') ?? '')
+  ) {
+    return;
+  }
+
+  res.headers.append('Vary', 'Accept-Encoding');
+  const length = res.headers.get('Content-Length');
+  if (length !== null && Number(length) < MIN_COMPRESSED_BYTES) {
+    return;
+  }
+  const available = RESPONSE_ENCODINGS.filter(encoding => compressors[encoding]);
+  const encoding = chooseEncoding(c.req.header('Accept-Encoding'), available);
+  if (!encoding) {
+    return;
+  }
+
+  c.res = new Response(res.body.pipeThrough(compressors[encoding]!()), res);
+  c.res.headers.delete('Content-Length');
+  c.res.headers.set('Content-Encoding', encoding);
+}
+
+export function createCompressionMiddleware(compressors: Compressors) {
+  return async (c: Context, next: Next) => {
+    decompressRequest(c);
+    await next();
+    compressResponse(c, compressors);
+  };
+}
@@ -0,0 +1,102 @@
+import { describe, it, expect } from "vitest";
+import { createApp } from "../app.js";
+import { parseOrigins } from "./cors.js";
+import type { CorsConfig } from "./cors.js";
+
+const testAPIKey = "tt-cors-key";
+const playground = "https://playground.example.com";
+
+function appWith(cors?: CorsConfig) {
+  return createApp({ auth: { apiKey: testAPIKey }, ...(cors ? { cors } : {}) });
+}
+
+function preflight(app: ReturnType<typeof createApp>, origin: string) {
+  return app.request("/v1/chat/completions", {
+    method: "OPTIONS",
+    headers: {
+      Origin: origin,
+      "Access-Control-Request-Method": "POST",
+      "Access-Control-Request-Headers": "authorization, content-type",
+    },
+  });
+}
+
+function chat(app: ReturnType<typeof createApp>, origin: string, stream = false) {
+  return app.request("/v1/chat/completions", {
+    method: "POST",
+    headers: {
+      Origin: origin,
+      Authorization: `Bearer ${testAPIKey}`,
+      "Content-Type": "application/json",
+    },
+    body: JSON.stringify({ model: "echo", stream, messages: [{ role: "user", content: "Hi" }] }),
+  });
+}
+
+describe("parseOrigins", () => {
+  it("should split and trim a comma-separated list", () => {
+    expect(parseOrigins(" https://a.example , https://b.example,")).toEqual(["https://a.example", "https://b.example"]);
+  });
+
+  it("should treat an empty or missing list as unset", () => {
+    expect(parseOrigins(undefined)).toBeUndefined();
+    expect(parseOrigins(" , ")).toBeUndefined();
+  });
+});
+
+describe("CORS", () => {
+  it("should answer preflights from any origin by default", async () => {
+    const res = await preflight(appWith(), playground);
+
+    expect(res.status).toBe(200);
+    expect(res.headers.get("access-control-allow-origin")).toBe("*");
+    expect(res.headers.get("access-control-allow-methods")).toContain("POST");
+    expect(res.headers.get("access-control-allow-headers")).toContain("Authorization");
+    expect(res.headers.get("vary")).toBeNull();
+  });
+
+  it("should echo back an allowed origin", async () => {
+    const app = appWith({ allowOrigins: ["https://other.example", playground] });
+
+    const res = await preflight(app, playground);
+
+    expect(res.headers.get("access-control-allow-origin")).toBe(playground);
+    expect(res.headers.get("access-control-allow-headers")).toContain("Authorization");
+    expect(res.headers.get("vary")).toBe("Origin");
+  });
+
+  it("should leave out CORS headers for origins not allowed", async () => {
+    const app = appWith({ allowOrigins: [playground] });
+
+    const pre = await preflight(app, "https://evil.example");
+    const res = await chat(app, "https://evil.example");
+
+    expect(pre.headers.get("access-control-allow-origin")).toBeNull();
+    expect(res.headers.get("access-control-allow-origin")).toBeNull();
+    expect(res.headers.get("vary")).toBe("Origin");
+  });
+
+  it("should add CORS headers to completions", async () => {
+    const res = await chat(appWith({ allowOrigins: [playground] }), playground);
+
+    expect(res.status).toBe(200);
+    expect(res.headers.get("access-control-allow-origin")).toBe(playground);
+  });
+
+  it("should add CORS headers to streamed completions", async () => {
+    const res = await chat(appWith({ allowOrigins: [playground] }), playground, true);
+
+    expect(res.headers.get("content-type")).toContain("text/event-stream");
+    expect(res.headers.get("access-control-allow-origin")).toBe(playground);
+    expect(await res.text()).toContain("data: [DONE]");
+  });
+
+  it("should add CORS headers to errors, so browsers can read them", async () => {
+    const app = appWith({ allowOrigins: [playground] });
+
+    const res = await app.request("/v1/models", { headers: { Origin: playground } });
+
+    expect(res.status).toBe(401);
+    expect(res.headers.get("access-control-allow-origin")).toBe(playground);
+  });
+});
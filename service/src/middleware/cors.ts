@@ -1,16 +1,55 @@
 import { Context, Next } from 'hono';
 
-export function corsMiddleware() {
+export interface CorsConfig {
+  // Origins browsers may call from, e.g. "https://playground.example.com",
+  // or "*" for any
+  allowOrigins: string[];
+}
+
+export const DEFAULT_CORS_CONFIG: CorsConfig = { allowOrigins: ['*'] };
+
+const ALLOW_METHODS = 'GET, POST, OPTIONS';
+const ALLOW_HEADERS = 'Content-Type, Authorization';
+
+// Parses a comma-separated origin list such as TEENYTINY_CORS_ORIGINS
+export function parseOrigins(value: string | undefined): string[] | undefined {
+  const origins = (value ?? '').split(',').map(origin => origin.trim()).filter(Boolean);
+  return origins.length > 0 ? origins : undefined;
+}
+
+// The Access-Control-Allow-Origin to send, or undefined to let the browser block the request
+function allowedOrigin(config: CorsConfig, origin: string | undefined): string | undefined {
+  if (config.allowOrigins.includes('*')) {
+    return '*';
+  }
+  return origin !== undefined && config.allowOrigins.includes(origin) ? origin : undefined;
+}
+
+export function corsMiddleware(config: CorsConfig = DEFAULT_CORS_CONFIG) {
   return async (c: Context, next: Next) => {
-    c.header('Access-Control-Allow-Origin', '*');
-    c.header('Access-Control-Allow-Methods', 'GET, POST, OPTIONS');
-    c.header('Access-Control-Allow-Headers', 'Content-Type, Authorization');
+    const allowOrigin = allowedOrigin(config, c.req.header('Origin'));
+    const setHeaders = (headers: Headers) => {
+      if (allowOrigin !== undefined) {
+        headers.set('Access-Control-Allow-Origin', allowOrigin);
+        headers.set('Access-Control-Allow-Methods', ALLOW_METHODS);
+        headers.set('Access-Control-Allow-Headers', ALLOW_HEADERS);
+      }
+      // The answer depends on the Origin, so caches must not share it across origins
+      if (allowOrigin !== '*') {
+        headers.append('Vary', 'Origin');
+      }
+    };
 
     if (c.req.method === 'OPTIONS') {
-      return c.text('', 200);
+      const response = c.text('', 200);
+      setHeaders(response.headers);
+      return response;
     }
 
     await next();
+    // Set on the finished response, so streams, errors and responses built
+    // outside Hono (proxied, replayed or faulty ones) carry them too
+    setHeaders(c.res.headers);
     return;
   };
-}
\ No newline at end of file
+}
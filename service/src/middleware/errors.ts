@@ -1,23 +1,43 @@
 import { Context } from 'hono';
 import { HTTPException } from 'hono/http-exception';
 import { APIError } from '../openai-protocol/errors.js';
+import type { ErrorResponse } from '../openai-protocol/types.js';
+import { OLLAMA_PATH_PREFIX } from '../ollama-protocol/ollama.js';
+import { GEMINI_PATH_PREFIX, geminiError } from '../gemini-protocol/gemini.js';
+
+// Errors in the shape each API's clients parse
+function errorBody(path: string, body: ErrorResponse, status: number): unknown {
+  if (path.startsWith(OLLAMA_PATH_PREFIX)) {
+    // Ollama clients only read a message, as {"error": "..."}
+    return { error: body.error.message };
+  }
+  if (path.startsWith(GEMINI_PATH_PREFIX)) {
+    return geminiError(body.error.message, status);
+  }
+  return body;
+}
 
 export function createErrorHandler() {
   return async (err: Error, c: Context) => {
     console.error('Request error:', err);
 
+    const respond = (body: ErrorResponse, status: number) =>
+      c.json(errorBody(c.req.path, body, status) as object, status as any);
+
     // Handle APIError instances
     if (err instanceof APIError) {
-      return c.json(err.toErrorResponse(), err.statusCode as any);
+      return respond(err.toErrorResponse(), err.statusCode);
     }
 
     // Handle Hono HTTP exceptions
     if (err instanceof HTTPException) {
-      return c.json(
+      return respond(
         {
           error: {
             message: err.message,
             type: 'api_error',
+            param: null,
+            code: null,
           },
         },
         err.status
@@ -25,14 +45,16 @@ export function createErrorHandler() {
     }
 
     // Handle unknown errors
-    return c.json(
+    return respond(
       {
         error: {
           message: 'Internal server error',
           type: 'api_error',
+          param: null,
+          code: null,
         },
       },
       500
     );
   };
-}
\ No newline at end of file
+}
@@ -0,0 +1,125 @@
+import { describe, it, expect } from "vitest";
+import { Hono } from "hono";
+import { IdempotencyCache } from "./idempotency.js";
+import { createErrorHandler } from "./errors.js";
+
+// A tiny app whose responses change on every request, so replayed ones stand out
+function createTestApp(cache: IdempotencyCache) {
+  let counter = 0;
+  const app = new Hono<{ Variables: { apiKey: string } }>();
+  app.onError(createErrorHandler());
+  app.use("*", async (c, next) => {
+    c.set("apiKey", c.req.header("x-key") ?? "key-a");
+    await next();
+  });
+  app.use("*", cache.middleware());
+  app.post("/v1/count", (c) => {
+    c.header("X-Request-ID", `req-${counter}`);
+    return c.text(`count ${counter++}`);
+  });
+  app.post("/v1/fail", (c) => c.text(`fail ${counter++}`, 500));
+  app.post("/v1/stream", () => {
+    const encoder = new TextEncoder();
+    const n = counter++;
+    return new Response(
+      new ReadableStream({
+        start(controller) {
+          controller.enqueue(encoder.encode(`data: ${n}\n\n`));
+          controller.enqueue(encoder.encode("data: [DONE]\n\n"));
+          controller.close();
+        },
+      }),
+      { headers: { "Content-Type": "text/event-stream" } },
+    );
+  });
+  return app;
+}
+
+const post = (
+  app: ReturnType<typeof createTestApp>,
+  path: string,
+  body: unknown,
+  headers: Record<string, string> = {},
+) =>
+  app.request(path, {
+    method: "POST",
+    headers: { "Content-Type": "application/json", ...headers },
+    body: JSON.stringify(body),
+  });
+
+describe("IdempotencyCache", () => {
+  it("should replay the first response to a repeated key", async () => {
+    const app = createTestApp(new IdempotencyCache());
+
+    const first = await post(app, "/v1/count", { a: 1 }, { "Idempotency-Key": "k1" });
+    expect(await first.text()).toBe("count 0");
+    expect(first.headers.get("Idempotent-Replayed")).toBeNull();
+
+    const repeat = await post(app, "/v1/count", { a: 1 }, { "Idempotency-Key": "k1" });
+    expect(await repeat.text()).toBe("count 0");
+    expect(repeat.headers.get("X-Request-ID")).toBe("req-0");
+    expect(repeat.headers.get("Idempotent-Replayed")).toBe("true");
+  });
+
+  it("should run requests without a key or with another key", async () => {
+    const app = createTestApp(new IdempotencyCache());
+
+    await post(app, "/v1/count", { a: 1 }, { "Idempotency-Key": "k1" }).then((res) => res.text());
+
+    expect(await (await post(app, "/v1/count", { a: 1 })).text()).toBe("count 1");
+    expect(await (await post(app, "/v1/count", { a: 1 }, { "Idempotency-Key": "k2" })).text()).toBe("count 2");
+    // Keys belong to the API key that sent them
+    const other = await post(app, "/v1/count", { a: 1 }, { "Idempotency-Key": "k1", "x-key": "key-b" });
+    expect(await other.text()).toBe("count 3");
+  });
+
+  it("should replay streams once fully sent", async () => {
+    const app = createTestApp(new IdempotencyCache());
+
+    const first = await (await post(app, "/v1/stream", {}, { "Idempotency-Key": "s" })).text();
+    const repeat = await post(app, "/v1/stream", {}, { "Idempotency-Key": "s" });
+
+    expect(await repeat.text()).toBe(first);
+    expect(repeat.headers.get("Content-Type")).toBe("text/event-stream");
+  });
+
+  it("should reject a key reused for a different request", async () => {
+    const app = createTestApp(new IdempotencyCache());
+    await post(app, "/v1/count", { a: 1 }, { "Idempotency-Key": "k1" }).then((res) => res.text());
+
+    const res = await post(app, "/v1/count", { a: 2 }, { "Idempotency-Key": "k1" });
+
+    expect(res.status).toBe(400);
+    expect((await res.json()).error.code).toBe("idempotency_key_reused");
+  });
+
+  it("should reject a repeat while the first request is in progress", async () => {
+    const app = createTestApp(new IdempotencyCache());
+    // The stream's body hasn't been read, so it hasn't been kept yet
+    const first = await post(app, "/v1/stream", {}, { "Idempotency-Key": "s" });
+
+    const res = await post(app, "/v1/stream", {}, { "Idempotency-Key": "s" });
+
+    expect(res.status).toBe(409);
+    expect((await res.json()).error.code).toBe("idempotency_key_in_use");
+    await first.text();
+  });
+
+  it("should not keep server errors", async () => {
+    const app = createTestApp(new IdempotencyCache());
+
+    expect(await (await post(app, "/v1/fail", {}, { "Idempotency-Key": "f" })).text()).toBe("fail 0");
+    expect(await (await post(app, "/v1/fail", {}, { "Idempotency-Key": "f" })).text()).toBe("fail 1");
+  });
+
+  it("should forget responses after the TTL", async () => {
+    let now = 0;
+    const app = createTestApp(new IdempotencyCache(1000, () => now));
+    await post(app, "/v1/count", {}, { "Idempotency-Key": "k1" }).then((res) => res.text());
+
+    now = 999;
+    expect(await (await post(app, "/v1/count", {}, { "Idempotency-Key": "k1" })).text()).toBe("count 0");
+    now = 1000;
+    expect(await (await post(app, "/v1/count", {}, { "Idempotency-Key": "k1" })).text()).toBe("count 1");
+  });
+});
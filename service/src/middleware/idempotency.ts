@@ -0,0 +1,173 @@
+import { Context, Next } from 'hono';
+import { APIError, ErrorTypes, InvalidRequestError } from '../openai-protocol/errors.js';
+import { requestKey } from '../recording/cassette.js';
+
+// A client retrying a request sends the same key, and gets the first response again
+export const IDEMPOTENCY_KEY_HEADER = 'Idempotency-Key';
+// Set on responses replayed from the cache
+export const IDEMPOTENT_REPLAYED_HEADER = 'Idempotent-Replayed';
+
+// How long a response is replayed for, as Stripe does
+export const DEFAULT_IDEMPOTENCY_TTL_MS = 24 * 60 * 60 * 1000;
+
+const MAX_KEY_LENGTH = 255;
+// Oldest responses are forgotten first past this many
+const MAX_ENTRIES = 10_000;
+
+type Entry =
+  | { state: 'pending'; fingerprint: string; expires: number }
+  | {
+      state: 'done';
+      fingerprint: string;
+      expires: number;
+      status: number;
+      headers: Record<string, string>;
+      body: ArrayBuffer;
+    };
+
+/**
+ * IdempotencyCache - Replays the response to a repeated Idempotency-Key
+ *
+ * The first POST with a key runs as usual, and its response is kept for the
+ * TTL, streamed ones once the stream has been fully sent. A repeat of the same
+ * request with that key gets the kept response byte-for-byte, including its
+ * X-Request-ID and completion id, so a retry layer can be tested for
+ * duplicate-charge safety. Server errors aren't kept, so those can be retried.
+ *
+ * Keys are scoped to the API key. Reusing one for a different request is
+ * rejected, as is a repeat while the first is still in progress.
+ */
+export class IdempotencyCache {
+  private entries = new Map<string, Entry>();
+
+  constructor(
+    private ttlMs: number = DEFAULT_IDEMPOTENCY_TTL_MS,
+    private now: () => number = Date.now
+  ) {}
+
+  // Must run after auth, which identifies the caller's API key
+  middleware() {
+    return async (c: Context, next: Next) => {
+      const key = c.req.header(IDEMPOTENCY_KEY_HEADER);
+      if (key === undefined || c.req.method !== 'POST') {
+        await next();
+        return;
+      }
+      if (key === '' || key.length > MAX_KEY_LENGTH) {
+        throw new InvalidRequestError(
+          `Invalid ${IDEMPOTENCY_KEY_HEADER}: must be 1-${MAX_KEY_LENGTH} characters`,
+          IDEMPOTENCY_KEY_HEADER
+        );
+      }
+
+      this.prune();
+      const cacheKey = `${c.get('apiKey') ?? ''}:${key}`;
+      const fingerprint = requestKey(c.req.method, c.req.path, await c.req.text());
+      const entry = this.entries.get(cacheKey);
+      if (entry) {
+        if (entry.fingerprint !== fingerprint) {
+          throw new APIError(
+            'Keys for idempotent requests can only be used with the same request they were first used with',
+            ErrorTypes.INVALID_REQUEST,
+            400,
+            IDEMPOTENCY_KEY_HEADER,
+            'idempotency_key_reused'
+          );
+        }
+        if (entry.state === 'pending') {
+          throw new APIError(
+            'Another request with this idempotency key is still in progress',
+            ErrorTypes.INVALID_REQUEST,
+            409,
+            IDEMPOTENCY_KEY_HEADER,
+            'idempotency_key_in_use'
+          );
+        }
+        return c.body(entry.body, entry.status as any, {
+          ...entry.headers,
+          [IDEMPOTENT_REPLAYED_HEADER]: 'true',
+        });
+      }
+
+      const expires = this.now() + this.ttlMs;
+      this.entries.set(cacheKey, { state: 'pending', fingerprint, expires });
+      try {
+        await next();
+      } catch (error) {
+        this.entries.delete(cacheKey);
+        throw error;
+      }
+      c.res = this.keep(cacheKey, fingerprint, expires, c.res);
+    };
+  }
+
+  // Passes the response through unchanged, keeping it once fully sent
+  private keep(cacheKey: string, fingerprint: string, expires: number, response: Response): Response {
+    const forget = () => this.entries.delete(cacheKey);
+    if (response.status >= 500) {
+      forget();
+      return response;
+    }
+
+    const headers: Record<string, string> = {};
+    response.headers.forEach((value, name) => {
+      if (name !== 'content-length') headers[name] = value;
+    });
+    const save = (body: ArrayBuffer) => {
+      this.entries.set(cacheKey, { state: 'done', fingerprint, expires, status: response.status, headers, body });
+    };
+
+    if (!response.body) {
+      save(new ArrayBuffer(0));
+      return response;
+    }
+
+    const chunks: Uint8Array[] = [];
+    const reader = response.body.getReader();
+    const body = new ReadableStream<Uint8Array>({
+      async pull(controller) {
+        try {
+          const { done, value } = await reader.read();
+          if (done) {
+            save(concat(chunks));
+            controller.close();
+            return;
+          }
+          chunks.push(value);
+          controller.enqueue(value);
+        } catch (error) {
+          forget();
+          controller.error(error);
+        }
+      },
+      // The client went away, so a retry should run the request again
+      cancel(reason) {
+        forget();
+        return reader.cancel(reason);
+      },
+    });
+    return new Response(body, response);
+  }
+
+  private prune(): void {
+    const now = this.now();
+    for (const [cacheKey, entry] of this.entries) {
+      if (entry.expires <= now) this.entries.delete(cacheKey);
+    }
+    // Map keeps insertion order, so the first entries are the oldest
+    for (const cacheKey of this.entries.keys()) {
+      if (this.entries.size < MAX_ENTRIES) break;
+      this.entries.delete(cacheKey);
+    }
+  }
+}
+
+function concat(chunks: Uint8Array[]): ArrayBuffer {
+  const body = new Uint8Array(chunks.reduce((total, chunk) => total + chunk.length, 0));
+  let offset = 0;
+  for (const chunk of chunks) {
+    body.set(chunk, offset);
+    offset += chunk.length;
+  }
+  return body.buffer;
+}
@@ -0,0 +1,157 @@
+import { describe, it, expect } from "vitest";
+import { Hono } from "hono";
+import {
+  createLatencyMiddleware,
+  parseDelayHeader,
+  parseLatencyConfig,
+  profileFor,
+  sampleDelay,
+  MAX_DELAY_MS,
+} from "./latency.js";
+import type { LatencyConfig } from "./latency.js";
+import { createErrorHandler } from "./errors.js";
+
+describe("sampleDelay", () => {
+  it("should spread jitter evenly around the base delay", () => {
+    const jitter = { type: "jitter", ms: 100, jitter_ms: 20 } as const;
+
+    expect(sampleDelay(jitter, () => 0)).toBe(80);
+    expect(sampleDelay(jitter, () => 0.5)).toBe(100);
+    expect(sampleDelay(jitter, () => 0.999999)).toBe(120);
+  });
+
+  it("should sample distributions around their mean", () => {
+    let seed = 1;
+    const random = () => {
+      seed = (seed * 16807) % 2147483647;
+      return seed / 2147483647;
+    };
+    const mean = (delay: Parameters<typeof sampleDelay>[0]) => {
+      let total = 0;
+      for (let i = 0; i < 2000; i++) total += sampleDelay(delay, random);
+      return total / 2000;
+    };
+
+    expect(mean({ type: "normal", mean_ms: 200, stddev_ms: 20 })).toBeCloseTo(200, -1);
+    expect(mean({ type: "exponential", mean_ms: 200 })).toBeGreaterThan(180);
+    expect(mean({ type: "exponential", mean_ms: 200 })).toBeLessThan(220);
+  });
+
+  it("should clamp samples to the allowed range", () => {
+    expect(sampleDelay({ type: "normal", mean_ms: 0, stddev_ms: 100 }, () => 0.5)).toBe(0);
+    expect(sampleDelay({ type: "exponential", mean_ms: MAX_DELAY_MS }, () => 0.999)).toBe(MAX_DELAY_MS);
+  });
+});
+
+describe("parseDelayHeader", () => {
+  it("should parse each form", () => {
+    expect(parseDelayHeader("250", "h")).toEqual({ type: "fixed", ms: 250 });
+    expect(parseDelayHeader("250~50", "h")).toEqual({ type: "jitter", ms: 250, jitter_ms: 50 });
+    expect(parseDelayHeader("normal:250:50", "h")).toEqual({ type: "normal", mean_ms: 250, stddev_ms: 50 });
+    expect(parseDelayHeader("exponential:250", "h")).toEqual({ type: "exponential", mean_ms: 250 });
+  });
+
+  it("should reject anything else, naming the header", () => {
+    for (const value of ["", "-5", "fast", "250~", "normal:250", "exponential:1:2", `${MAX_DELAY_MS + 1}`]) {
+      expect(() => parseDelayHeader(value, "x-teenytiny-delay-ms")).toThrow(
+        expect.objectContaining({ param: "x-teenytiny-delay-ms" }),
+      );
+    }
+  });
+});
+
+describe("parseLatencyConfig", () => {
+  it("should accept profiles by path", () => {
+    const config = {
+      "/v1/chat/completions": { ttfb: { type: "fixed", ms: 100 }, chunk: { type: "exponential", mean_ms: 20 } },
+      "/v1/*": { ttfb: { type: "jitter", ms: 50, jitter_ms: 10 } },
+    };
+
+    expect(parseLatencyConfig(config)).toEqual(config);
+  });
+
+  it("should name the invalid part", () => {
+    const param = (body: unknown) => {
+      try {
+        parseLatencyConfig(body);
+      } catch (error) {
+        return (error as { param?: string }).param;
+      }
+      return "accepted";
+    };
+
+    expect(param({ "v1": {} })).toBe("v1");
+    expect(param({ "/v1/*": { first_byte: { type: "fixed", ms: 1 } } })).toBe("/v1/*.first_byte");
+    expect(param({ "/v1/*": { ttfb: { type: "gamma" } } })).toBe("/v1/*.ttfb");
+    expect(param({ "/v1/*": { ttfb: { type: "jitter", ms: 10 } } })).toBe("/v1/*.ttfb.jitter_ms");
+    expect(param({ "/v1/*": { ttfb: { type: "fixed", ms: -1 } } })).toBe("/v1/*.ttfb.ms");
+  });
+});
+
+describe("profileFor", () => {
+  it("should prefer exact paths, then the longest prefix", () => {
+    const config: LatencyConfig = {
+      "*": { ttfb: { type: "fixed", ms: 1 } },
+      "/v1/*": { ttfb: { type: "fixed", ms: 2 } },
+      "/v1/chat/completions": { ttfb: { type: "fixed", ms: 3 } },
+    };
+
+    expect(profileFor(config, "/v1/chat/completions")?.ttfb).toEqual({ type: "fixed", ms: 3 });
+    expect(profileFor(config, "/v1/models")?.ttfb).toEqual({ type: "fixed", ms: 2 });
+    expect(profileFor(config, "/session/a/say")?.ttfb).toEqual({ type: "fixed", ms: 1 });
+    expect(profileFor({}, "/v1/models")).toBeUndefined();
+  });
+});
+
+describe("createLatencyMiddleware", () => {
+  function createTestApp(config: LatencyConfig) {
+    const app = new Hono();
+    app.onError(createErrorHandler());
+    app.use("*", createLatencyMiddleware(() => config));
+    app.get("/fast", (c) => c.text("ok"));
+    app.get("/stream", () => {
+      const encoder = new TextEncoder();
+      return new Response(
+        new ReadableStream({
+          start(controller) {
+            for (const n of [1, 2, 3]) controller.enqueue(encoder.encode(`data: ${n}\n\n`));
+            controller.close();
+          },
+        }),
+        { headers: { "Content-Type": "text/event-stream" } },
+      );
+    });
+    return app;
+  }
+
+  const timed = async (run: () => Promise<unknown>) => {
+    const started = Date.now();
+    await run();
+    return Date.now() - started;
+  };
+
+  it("should delay the first byte of configured paths", async () => {
+    const app = createTestApp({ "/fast": { ttfb: { type: "fixed", ms: 80 } } });
+
+    expect(await timed(() => app.request("/fast"))).toBeGreaterThanOrEqual(75);
+    expect(await timed(() => app.request("/stream"))).toBeLessThan(50);
+  });
+
+  it("should space streamed chunks, letting headers override the config", async () => {
+    const app = createTestApp({});
+    const elapsed = await timed(async () => {
+      const res = await app.request("/stream", { headers: { "x-teenytiny-chunk-delay-ms": "40" } });
+      expect(await res.text()).toBe("data: 1\n\ndata: 2\n\ndata: 3\n\n");
+    });
+
+    // Two gaps between three chunks
+    expect(elapsed).toBeGreaterThanOrEqual(75);
+  });
+
+  it("should reject an invalid delay header", async () => {
+    const res = await createTestApp({}).request("/fast", { headers: { "x-teenytiny-delay-ms": "soon" } });
+
+    expect(res.status).toBe(400);
+    expect((await res.json()).error.param).toBe("x-teenytiny-delay-ms");
+  });
+});
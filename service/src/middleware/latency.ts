@@ -0,0 +1,187 @@
+import { Context, Next } from 'hono';
+import { InvalidRequestError } from '../openai-protocol/errors.js';
+import { sleep } from '../utils/sleep.js';
+
+// Per-request delays, overriding any configured for the endpoint
+export const DELAY_HEADER = 'x-teenytiny-delay-ms';
+export const CHUNK_DELAY_HEADER = 'x-teenytiny-chunk-delay-ms';
+
+// No single sampled delay is longer than this
+export const MAX_DELAY_MS = 60_000;
+
+export type Delay =
+  | { type: 'fixed'; ms: number }
+  // Uniform between ms - jitter_ms and ms + jitter_ms
+  | { type: 'jitter'; ms: number; jitter_ms: number }
+  | { type: 'normal'; mean_ms: number; stddev_ms: number }
+  | { type: 'exponential'; mean_ms: number };
+
+export interface LatencyProfile {
+  // Before the response starts
+  ttfb?: Delay;
+  // Between streamed chunks
+  chunk?: Delay;
+}
+
+// Profiles by path. A path ending in * matches any path with that prefix; the
+// longest matching pattern wins.
+export type LatencyConfig = Record<string, LatencyProfile>;
+
+/**
+ * Draws one delay in milliseconds, never negative or above MAX_DELAY_MS
+ */
+export function sampleDelay(delay: Delay, random: () => number = Math.random): number {
+  let ms: number;
+  switch (delay.type) {
+    case 'fixed':
+      ms = delay.ms;
+      break;
+    case 'jitter':
+      ms = delay.ms + (random() * 2 - 1) * delay.jitter_ms;
+      break;
+    case 'normal': {
+      // Box-Muller
+      const u = 1 - random();
+      const v = random();
+      ms = delay.mean_ms + delay.stddev_ms * Math.sqrt(-2 * Math.log(u)) * Math.cos(2 * Math.PI * v);
+      break;
+    }
+    case 'exponential':
+      ms = -delay.mean_ms * Math.log(1 - random());
+      break;
+  }
+  return Math.min(MAX_DELAY_MS, Math.max(0, Math.round(ms)));
+}
+
+/**
+ * Parses the header form of a delay: "200" (fixed), "200~50" (jitter),
+ * "normal:200:50" or "exponential:200"
+ */
+export function parseDelayHeader(value: string, header: string): Delay {
+  const [type, ...args] = value.includes(':') ? value.split(':') : ['', ...value.split('~')];
+  const ms = args.map(arg => (/^\d+$/.test(arg) && Number(arg) <= MAX_DELAY_MS ? Number(arg) : undefined));
+  const [a, b] = ms;
+  if (a !== undefined && !ms.includes(undefined)) {
+    if (type === '' && ms.length === 1) return { type: 'fixed', ms: a };
+    if (type === '' && ms.length === 2 && b !== undefined) return { type: 'jitter', ms: a, jitter_ms: b };
+    if (type === 'normal' && ms.length === 2 && b !== undefined) return { type: 'normal', mean_ms: a, stddev_ms: b };
+    if (type === 'exponential' && ms.length === 1) return { type: 'exponential', mean_ms: a };
+  }
+  throw new InvalidRequestError(
+    `Invalid ${header} header: expected "200", "200~50", "normal:200:50" or "exponential:200", up to ${MAX_DELAY_MS}ms`,
+    header
+  );
+}
+
+/**
+ * Validates a latency config from the admin API, throwing on the first problem
+ */
+export function parseLatencyConfig(body: unknown): LatencyConfig {
+  if (!body || typeof body !== 'object' || Array.isArray(body)) {
+    throw new InvalidRequestError('Invalid latency config: expected an object of paths to profiles');
+  }
+
+  const config: LatencyConfig = {};
+  for (const [path, profile] of Object.entries(body)) {
+    if (!path.startsWith('/') && path !== '*') {
+      throw new InvalidRequestError(`Invalid path '${path}': expected a path such as /v1/chat/completions or /v1/*`, path);
+    }
+    if (!profile || typeof profile !== 'object' || Array.isArray(profile)) {
+      throw new InvalidRequestError(`Invalid profile for '${path}': expected an object with ttfb and/or chunk`, path);
+    }
+    const parsed: LatencyProfile = {};
+    for (const [stage, delay] of Object.entries(profile)) {
+      if (stage !== 'ttfb' && stage !== 'chunk') {
+        throw new InvalidRequestError(`Invalid stage '${stage}' for '${path}': expected ttfb or chunk`, `${path}.${stage}`);
+      }
+      parsed[stage] = parseDelay(delay, `${path}.${stage}`);
+    }
+    config[path] = parsed;
+  }
+  return config;
+}
+
+const DELAY_FIELDS: Record<Delay['type'], string[]> = {
+  fixed: ['ms'],
+  jitter: ['ms', 'jitter_ms'],
+  normal: ['mean_ms', 'stddev_ms'],
+  exponential: ['mean_ms'],
+};
+
+function parseDelay(value: any, param: string): Delay {
+  if (typeof value?.type !== 'string' || !Object.hasOwn(DELAY_FIELDS, value.type)) {
+    throw new InvalidRequestError(
+      `Invalid delay type for '${param}': expected one of ${Object.keys(DELAY_FIELDS).join(', ')}`,
+      param
+    );
+  }
+  const fields = DELAY_FIELDS[value.type as Delay['type']];
+  for (const field of fields) {
+    const ms = value[field];
+    if (typeof ms !== 'number' || !Number.isFinite(ms) || ms < 0 || ms > MAX_DELAY_MS) {
+      throw new InvalidRequestError(
+        `Invalid '${field}' for '${param}': expected milliseconds from 0 to ${MAX_DELAY_MS}`,
+        `${param}.${field}`
+      );
+    }
+  }
+  return Object.fromEntries([['type', value.type], ...fields.map(field => [field, value[field]])]) as Delay;
+}
+
+// The profile for a path: an exact match, else the longest matching prefix pattern
+export function profileFor(config: LatencyConfig, path: string): LatencyProfile | undefined {
+  if (config[path]) {
+    return config[path];
+  }
+  let best: string | undefined;
+  for (const pattern of Object.keys(config)) {
+    if (pattern.endsWith('*') && path.startsWith(pattern.slice(0, -1)) && (!best || pattern.length > best.length)) {
+      best = pattern;
+    }
+  }
+  return best === undefined ? undefined : config[best];
+}
+
+/**
+ * Delays responses to simulate network and model latency
+ *
+ * The time to first byte is spent before the request is handled, and the chunk
+ * delay between chunks of the response body, which matters for streams. The
+ * config is read on every request, so it can be changed at runtime; the delay
+ * headers override it for a single request.
+ */
+export function createLatencyMiddleware(config: () => LatencyConfig, random: () => number = Math.random) {
+  return async (c: Context, next: Next) => {
+    const profile: LatencyProfile = { ...profileFor(config(), c.req.path) };
+    const ttfbHeader = c.req.header(DELAY_HEADER);
+    if (ttfbHeader !== undefined) {
+      profile.ttfb = parseDelayHeader(ttfbHeader, DELAY_HEADER);
+    }
+    const chunkHeader = c.req.header(CHUNK_DELAY_HEADER);
+    if (chunkHeader !== undefined) {
+      profile.chunk = parseDelayHeader(chunkHeader, CHUNK_DELAY_HEADER);
+    }
+
+    const signal = c.req.raw.signal;
+    if (profile.ttfb) {
+      await sleep(sampleDelay(profile.ttfb, random), signal);
+    }
+
+    await next();
+
+    const chunk = profile.chunk;
+    if (chunk && c.res.body) {
+      let first = true;
+      const gaps = new TransformStream<Uint8Array, Uint8Array>({
+        async transform(data, controller) {
+          if (!first) {
+            await sleep(sampleDelay(chunk, random), signal);
+          }
+          first = false;
+          controller.enqueue(data);
+        },
+      });
+      c.res = new Response(c.res.body.pipeThrough(gaps), c.res);
+    }
+  };
+}
@@ -1,16 +1,34 @@
 import { Context, Next } from 'hono';
+import type { ChatCompletionUsage } from '../openai-protocol/types.js';
+import { loggedWhenDone, type Logger } from '../utils/logger.js';
 
 type Variables = {
   requestId: string;
+  // Set by later middleware and handlers, when they know them
+  apiKey?: string;
+  model?: string;
+  usage?: ChatCompletionUsage;
 };
 
-export function createLoggingMiddleware() {
+// Printable ASCII without spaces, so a passed-through ID can't break log lines or headers
+const REQUEST_ID_PATTERN = /^[\x21-\x7e]{1,200}$/;
+
+/**
+ * Logs one line per request once its response is done, streams included,
+ * with the model and token usage when the handler reported them. Keys are
+ * logged as a hash, which tells callers apart without leaking the key.
+ */
+export function createLoggingMiddleware(logger: Logger) {
   return async (c: Context<{ Variables: Variables }>, next: Next) => {
     const start = Date.now();
     
-    // Generate request ID (compatible with both Node.js and CF Workers)
-    const requestId = globalThis.crypto?.randomUUID?.() || 
-      Math.random().toString(36).substring(2, 15) + Math.random().toString(36).substring(2, 15);
+    // Keep the caller's request ID so logs on both sides line up, otherwise
+    // generate one (compatible with both Node.js and CF Workers)
+    const incoming = c.req.header('X-Request-ID');
+    const requestId = incoming !== undefined && REQUEST_ID_PATTERN.test(incoming)
+      ? incoming
+      : globalThis.crypto?.randomUUID?.() ||
+        Math.random().toString(36).substring(2, 15) + Math.random().toString(36).substring(2, 15);
     
     // Add request ID to context
     c.set('requestId', requestId);
@@ -18,26 +36,45 @@ export function createLoggingMiddleware() {
     // Add request ID to response headers
     c.header('X-Request-ID', requestId);
 
-    console.log(JSON.stringify({
-      level: 'info',
-      message: 'Request started',
+    logger.debug('Request started', {
       request_id: requestId,
       method: c.req.method,
       path: c.req.path,
       user_agent: c.req.header('User-Agent'),
-    }));
+    });
 
     await next();
 
-    const duration = Date.now() - start;
-    console.log(JSON.stringify({
-      level: 'info',
-      message: 'Request completed',
-      request_id: requestId,
-      method: c.req.method,
-      path: c.req.path,
-      status: c.res.status,
-      duration_ms: duration,
-    }));
+    const apiKey = c.get('apiKey');
+    const key = apiKey ? await keyId(apiKey) : null;
+    const log = (cancelled: boolean) => {
+      const usage = c.get('usage');
+      logger.info('Request completed', {
+        request_id: requestId,
+        method: c.req.method,
+        path: c.req.path,
+        model: c.get('model') ?? null,
+        status: c.res.status,
+        duration_ms: Date.now() - start,
+        prompt_tokens: usage?.prompt_tokens ?? null,
+        completion_tokens: usage?.completion_tokens ?? null,
+        total_tokens: usage?.total_tokens ?? null,
+        key_id: key,
+        ...(cancelled ? { cancelled } : {}),
+      });
+    };
+
+    // A stream is logged once it ends, when its usage is known
+    if ((c.res.headers.get('content-type') ?? '').startsWith('text/event-stream') && c.res.body) {
+      c.res = new Response(loggedWhenDone(c.res.body, log), c.res);
+    } else {
+      log(false);
+    }
   };
-}
\ No newline at end of file
+}
+
+// The first 16 hex digits of the key's SHA-256
+export async function keyId(apiKey: string): Promise<string> {
+  const digest = await globalThis.crypto.subtle.digest('SHA-256', new TextEncoder().encode(apiKey));
+  return [...new Uint8Array(digest).slice(0, 8)].map(byte => byte.toString(16).padStart(2, '0')).join('');
+}
@@ -0,0 +1,79 @@
+import { InvalidRequestError } from '../openai-protocol/errors.js';
+
+/**
+ * Declarative middleware stack configuration
+ *
+ * Maps a route group (any Hono path pattern) to the ordered list of middleware
+ * applied to it. Groups are registered in insertion order, so for a request
+ * matching several groups the earlier group's layers run first.
+ */
+export const MIDDLEWARE_NAMES = ['cors', 'logging', 'compression', 'auth', 'webhooks', 'rate-limit', 'body-limit', 'capture', 'idempotency', 'recorder', 'latency'] as const;
+
+export type MiddlewareName = typeof MIDDLEWARE_NAMES[number];
+
+export type MiddlewareConfig = Record<string, MiddlewareName[]>;
+
+export const DEFAULT_MIDDLEWARE: MiddlewareConfig = {
+  '*': ['cors', 'logging', 'compression'],
+  '/v1/*': ['auth', 'webhooks', 'rate-limit', 'body-limit', 'capture', 'idempotency', 'recorder', 'latency'],
+  '/openai/*': ['auth', 'webhooks', 'rate-limit', 'body-limit', 'capture', 'idempotency', 'recorder', 'latency'],
+  '/api/*': ['auth', 'rate-limit', 'body-limit', 'latency'],
+  '/v1beta/*': ['auth', 'rate-limit', 'body-limit', 'latency'],
+  '/session/*': ['auth', 'rate-limit', 'body-limit'],
+  '/admin/*': ['auth', 'body-limit'],
+  '/debug/*': ['auth', 'body-limit'],
+};
+
+// Route groups that serve the API, each of which has to authenticate
+const AUTHENTICATED_ROUTES = Object.keys(DEFAULT_MIDDLEWARE).filter(route => DEFAULT_MIDDLEWARE[route]!.includes('auth'));
+
+/**
+ * Parses the middleware section of a config file, an object of route groups
+ * to middleware names, throwing InvalidRequestError on the first problem
+ */
+export function parseMiddlewareConfig(body: unknown): MiddlewareConfig {
+  if (!body || typeof body !== 'object' || Array.isArray(body)) {
+    throw new InvalidRequestError("Invalid 'middleware': expected a table of route groups to middleware names", 'middleware');
+  }
+  const config = body as MiddlewareConfig;
+  validateMiddlewareConfig(config);
+  return config;
+}
+
+/**
+ * Validates a middleware configuration, throwing InvalidRequestError on the
+ * first problem found
+ *
+ * Run at startup so a typo in the config fails fast instead of silently
+ * dropping a layer. Every API route group has to keep auth, either in its own
+ * group or in one that matches every path.
+ */
+export function validateMiddlewareConfig(config: MiddlewareConfig): void {
+  for (const [route, names] of Object.entries(config)) {
+    const param = `middleware.${route}`;
+    if (!Array.isArray(names)) {
+      throw new InvalidRequestError(`Invalid '${param}': expected a list of middleware names`, param);
+    }
+
+    const seen = new Set<string>();
+    for (const name of names) {
+      if (!(MIDDLEWARE_NAMES as readonly string[]).includes(name)) {
+        throw new InvalidRequestError(
+          `Unknown middleware "${name}" for "${route}". Expected one of: ${MIDDLEWARE_NAMES.join(', ')}`,
+          param
+        );
+      }
+      if (seen.has(name)) {
+        throw new InvalidRequestError(`Middleware "${name}" is listed more than once for "${route}"`, param);
+      }
+      seen.add(name);
+    }
+  }
+
+  const everywhere = ['*', '/*'].some(route => config[route]?.includes('auth'));
+  for (const route of AUTHENTICATED_ROUTES) {
+    if (!everywhere && !config[route]?.includes('auth')) {
+      throw new InvalidRequestError(`Invalid 'middleware.${route}': API routes have to include auth`, `middleware.${route}`);
+    }
+  }
+}
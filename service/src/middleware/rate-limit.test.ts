@@ -0,0 +1,65 @@
+import { describe, it, expect } from "vitest";
+import { RateLimiter } from "./rate-limit.js";
+
+describe("RateLimiter", () => {
+  it("should allow requests up to the limit within a window", () => {
+    const limiter = new RateLimiter(() => 0);
+
+    expect(limiter.consume("key", 2)).toMatchObject({ allowed: true, remaining: 1 });
+    expect(limiter.consume("key", 2)).toMatchObject({ allowed: true, remaining: 0 });
+    expect(limiter.consume("key", 2)).toMatchObject({ allowed: false, remaining: 0 });
+  });
+
+  it("should report seconds until the window resets", () => {
+    let now = 0;
+    const limiter = new RateLimiter(() => now);
+
+    limiter.consume("key", 1);
+    now = 45_500;
+
+    expect(limiter.consume("key", 1).resetSeconds).toBe(15);
+  });
+
+  it("should start a fresh window after a minute", () => {
+    let now = 0;
+    const limiter = new RateLimiter(() => now);
+
+    limiter.consume("key", 1);
+    expect(limiter.consume("key", 1).allowed).toBe(false);
+
+    now = 60_000;
+    expect(limiter.consume("key", 1).allowed).toBe(true);
+  });
+
+  it("should track buckets independently", () => {
+    const limiter = new RateLimiter(() => 0);
+
+    limiter.consume("a", 1);
+
+    expect(limiter.consume("b", 1).allowed).toBe(true);
+  });
+
+  it("should count a request against every bucket only when all have room", () => {
+    const limiter = new RateLimiter(() => 0);
+
+    expect(limiter.consumeAll([["key:3", 3], ["key:override:1", 1]])).toMatchObject({ allowed: true, remaining: 0 });
+    expect(limiter.consumeAll([["key:3", 3], ["key:override:1", 1]]).allowed).toBe(false);
+
+    // The refused request took nothing from the wider bucket
+    expect(limiter.consume("key:3", 3)).toMatchObject({ allowed: true, remaining: 1 });
+  });
+
+  it("should reset one key's buckets or all of them", () => {
+    const limiter = new RateLimiter(() => 0);
+    limiter.consume("a:1", 1);
+    limiter.consume("b:1", 1);
+
+    limiter.reset("a");
+
+    expect(limiter.consume("a:1", 1).allowed).toBe(true);
+    expect(limiter.consume("b:1", 1).allowed).toBe(false);
+
+    limiter.reset();
+    expect(limiter.consume("b:1", 1).allowed).toBe(true);
+  });
+});
@@ -0,0 +1,106 @@
+import { Context, Next } from 'hono';
+import { RateLimitError } from '../openai-protocol/errors.js';
+
+const WINDOW_MS = 60 * 1000;
+
+// Lets a client opt into a smaller budget, e.g. to test 429 handling without sending thousands of requests
+export const RATE_LIMIT_OVERRIDE_HEADER = 'x-teenytiny-ratelimit-requests';
+
+interface Window {
+  start: number;
+  count: number;
+}
+
+/**
+ * RateLimiter - Fixed one-minute windows of request counts per bucket
+ */
+export class RateLimiter {
+  private windows = new Map<string, Window>();
+
+  constructor(private now: () => number = Date.now) {}
+
+  /**
+   * Forgets the windows of one API key's buckets, or of every bucket
+   */
+  reset(apiKey?: string): void {
+    if (apiKey === undefined) {
+      this.windows.clear();
+      return;
+    }
+    for (const bucket of [...this.windows.keys()]) {
+      if (bucket.startsWith(`${apiKey}:`)) {
+        this.windows.delete(bucket);
+      }
+    }
+  }
+
+  /**
+   * Counts a request against the bucket, returning whether it fits in the limit
+   */
+  consume(bucket: string, limit: number) {
+    return this.consumeAll([[bucket, limit]]);
+  }
+
+  /**
+   * Counts a request against every bucket if it fits in all of their limits,
+   * reporting the bucket with the least room left
+   */
+  consumeAll(buckets: [string, number][]) {
+    const now = this.now();
+    const windows = buckets.map(([bucket, limit]) => {
+      let window = this.windows.get(bucket);
+      if (!window || now - window.start >= WINDOW_MS) {
+        window = { start: now, count: 0 };
+        this.windows.set(bucket, window);
+      }
+      return { window, limit };
+    });
+
+    const allowed = windows.every(({ window, limit }) => window.count < limit);
+    if (allowed) {
+      for (const { window } of windows) {
+        window.count++;
+      }
+    }
+
+    const tightest = windows.reduce((a, b) => (b.limit - b.window.count < a.limit - a.window.count ? b : a));
+    return {
+      allowed,
+      remaining: Math.max(0, tightest.limit - tightest.window.count),
+      resetSeconds: Math.max(1, Math.ceil((tightest.window.start + WINDOW_MS - now) / 1000)),
+    };
+  }
+}
+
+// The limit is read on every request, so it can be changed at runtime
+export function createRateLimitMiddleware(limiter: RateLimiter, requestsPerMinute: () => number) {
+  return async (c: Context, next: Next) => {
+    // The key auth resolved, so keys sent in Azure's api-key or Gemini's x-goog-api-key get buckets of their own
+    const apiKey: string = c.get('apiKey') ?? '';
+
+    // An override gets its own bucket but still takes from the key's normal budget, so it can only tighten it
+    const configured = requestsPerMinute();
+    const buckets: [string, number][] = [[`${apiKey}:${configured}`, configured]];
+    let limit = configured;
+    const override = Number(c.req.header(RATE_LIMIT_OVERRIDE_HEADER));
+    if (Number.isInteger(override) && override > 0) {
+      buckets.push([`${apiKey}:override:${override}`, override]);
+      limit = Math.min(override, configured);
+    }
+
+    const result = limiter.consumeAll(buckets);
+
+    c.header('x-ratelimit-limit-requests', String(limit));
+    c.header('x-ratelimit-remaining-requests', String(result.remaining));
+    c.header('x-ratelimit-reset-requests', `${result.resetSeconds}s`);
+
+    if (!result.allowed) {
+      c.header('Retry-After', String(result.resetSeconds));
+      throw new RateLimitError(
+        `Rate limit reached for requests per min (RPM): Limit ${limit}. Please try again in ${result.resetSeconds}s.`
+      );
+    }
+
+    await next();
+  };
+}
@@ -0,0 +1,30 @@
+import { describe, it, expect } from "vitest";
+import { FilteredModel } from "./filtered-model.js";
+
+async function run(input: string): Promise<{ chunks: string[]; finishReason?: string }> {
+  const result: { chunks: string[]; finishReason?: string } = { chunks: [] };
+  const options = { setFinishReason: (reason: string) => (result.finishReason = reason) };
+  for await (const chunk of new FilteredModel().process(input, undefined, options)) {
+    result.chunks.push(chunk);
+  }
+  return result;
+}
+
+describe("FilteredModel", () => {
+  it("should write the first half of the input and report the filter", async () => {
+    const { chunks, finishReason } = await run("one two three four five");
+
+    expect(chunks).toEqual(["one", " two", " three"]);
+    expect(finishReason).toBe("content_filter");
+  });
+
+  it("should write something even for a single word", async () => {
+    expect((await run("hello")).chunks).toEqual(["hello"]);
+  });
+
+  it("should have a default reply to cut short", async () => {
+    const { chunks } = await run("");
+
+    expect(chunks.join("")).toBe("Hello! I'm the Filtered model. My replies");
+  });
+});
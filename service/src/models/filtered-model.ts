@@ -0,0 +1,33 @@
+import { Model } from './model.js';
+import type { GenerationOptions } from './model.js';
+
+/**
+ * FILTERED - Content Filter Simulator
+ *
+ * ORIGIN:
+ * A testing utility created for TeenyTiny AI. Real providers stop a reply
+ * partway when their content filter trips, sending what was written so far
+ * with finish_reason "content_filter". Apps need to notice and tell the user,
+ * but it's hard to make a real model do it on purpose.
+ *
+ * CONVERSATION EXPERIENCE:
+ * FILTERED echoes the last user message one word at a time, like Slow without
+ * the wait, and stops halfway through as if the filter had cut it off. Every
+ * reply, blocking or streamed, finishes with "content_filter".
+ *
+ * HOW IT WORKS:
+ * 1. The input is split into words
+ * 2. The first half of them, rounded up, are written
+ * 3. The finish reason is set to content_filter
+ */
+export class FilteredModel implements Model {
+  async *process(input: string, signal?: AbortSignal, options?: GenerationOptions): AsyncGenerator<string> {
+    const words = (input || "Hello! I'm the Filtered model. My replies are cut short by a content filter.").split(' ');
+
+    options?.setFinishReason?.('content_filter');
+    for (let i = 0; i < Math.ceil(words.length / 2); i++) {
+      if (signal?.aborted) return;
+      yield i === 0 ? words[i]! : ` ${words[i]}`;
+    }
+  }
+}
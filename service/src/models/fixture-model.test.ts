@@ -0,0 +1,62 @@
+import { describe, it, expect } from "vitest";
+import { FixtureModel, matchFixture, parseFixtures } from "./fixture-model.js";
+import type { Fixture } from "./fixture-model.js";
+
+const fixtures: Fixture[] = [
+  { match: { regex: "^weather in (\\w+)", flags: "i" }, response: "It is sunny in $1." },
+  { match: "Weather in Paris", response: "Exact match wins." },
+  { match: "stream me", chunks: ["one", " two", " three"] },
+];
+
+describe("FixtureModel", () => {
+  it("should prefer exact matches over regexes", () => {
+    expect(matchFixture(fixtures, "Weather in Paris")).toEqual(["Exact match wins."]);
+  });
+
+  it("should substitute regex captures", () => {
+    expect(matchFixture(fixtures, "weather in Oslo today")).toEqual(["It is sunny in Oslo."]);
+  });
+
+  it("should stream the configured chunks", async () => {
+    const model = new FixtureModel({ fixtures: () => fixtures });
+    const chunks: string[] = [];
+
+    for await (const chunk of model.process("stream me")) {
+      chunks.push(chunk);
+    }
+
+    expect(chunks).toEqual(["one", " two", " three"]);
+  });
+
+  it("should say so when nothing matches", async () => {
+    const model = new FixtureModel({ fixtures: () => fixtures });
+    const chunks: string[] = [];
+
+    for await (const chunk of model.process("unknown")) {
+      chunks.push(chunk);
+    }
+
+    expect(chunks).toEqual(["No fixture matches this message: unknown"]);
+  });
+
+  it("should fill in a template from the request", async () => {
+    const model = new FixtureModel({
+      fixtures: () => [{ match: { regex: "^order (\\d+)$" }, template: "Order $1 for {{user}}, {{message_count}} messages in" }],
+    });
+    const chunks: string[] = [];
+
+    const options = { messages: [{ role: "user", content: "order 42" }], parameters: { user: "ada" } };
+    for await (const chunk of model.process("order 42", undefined, options)) {
+      chunks.push(chunk);
+    }
+
+    expect(chunks).toEqual(["Order 42 for ada, 1 messages in"]);
+  });
+
+  it("should name the offending entry of an invalid file", () => {
+    expect(() => parseFixtures([{ match: "hi" }], "greetings.json")).toThrow(/greetings.json\[0\]/);
+    expect(() => parseFixtures([{ match: { regex: "(" }, response: "x" }], "bad.json")).toThrow();
+    expect(() => parseFixtures({}, "object.json")).toThrow(/expected an array/);
+    expect(() => parseFixtures([{ match: "hi", template: "{{nope}}" }], "t.json")).toThrow(/t.json\[0\].template: unknown variable/);
+  });
+});
@@ -0,0 +1,112 @@
+import { Model } from './model.js';
+import type { GenerationOptions } from './model.js';
+import { checkTemplate, renderTemplate, templateVariables, type TemplateVariable } from './template-model.js';
+
+/**
+ * A canned response, served when a user message matches. `match` is either the
+ * exact message or { regex } - regex captures can be used in the response as
+ * $1, $2, and so on. `chunks` gives the exact streaming chunks instead of
+ * `response`, and `template` a response with {{variables}} filled in from the
+ * request (see template-model.ts).
+ */
+export interface Fixture {
+  match: string | { regex: string; flags?: string };
+  response?: string;
+  chunks?: string[];
+  template?: string;
+}
+
+// Where the fixture model reads its fixtures from on every request, so sources can reload them
+export interface FixtureSource {
+  fixtures(): Fixture[];
+}
+
+// Validates parsed fixture file contents, naming the file and entry on failure
+export function parseFixtures(data: unknown, file: string): Fixture[] {
+  if (!Array.isArray(data)) {
+    throw new Error(`${file}: expected an array of fixtures`);
+  }
+
+  return data.map((entry, i) => {
+    const where = `${file}[${i}]`;
+    if (!entry || typeof entry !== 'object') {
+      throw new Error(`${where}: expected an object`);
+    }
+
+    const { match, response, chunks, template } = entry as Record<string, unknown>;
+    if (typeof match === 'object' && match !== null) {
+      const { regex, flags } = match as Record<string, unknown>;
+      if (typeof regex !== 'string' || (flags !== undefined && typeof flags !== 'string')) {
+        throw new Error(`${where}.match: expected a string or { "regex": string, "flags"?: string }`);
+      }
+      new RegExp(regex, flags as string | undefined); // Fail at load time on a bad pattern
+    } else if (typeof match !== 'string') {
+      throw new Error(`${where}.match: expected a string or { "regex": string, "flags"?: string }`);
+    }
+
+    const hasChunks = Array.isArray(chunks) && chunks.every(chunk => typeof chunk === 'string');
+    if (typeof template === 'string') {
+      try {
+        checkTemplate(template);
+      } catch (error) {
+        throw new Error(`${where}.template: ${(error as Error).message}`);
+      }
+    } else if (typeof response !== 'string' && !hasChunks) {
+      throw new Error(
+        `${where}: expected "response" or "template" as a string, or "chunks" as an array of strings`
+      );
+    }
+
+    return entry as Fixture;
+  });
+}
+
+/**
+ * Serves canned responses from fixtures, so teenytiny can stand in as a
+ * deterministic mock of a team's production prompts. The first matching
+ * fixture wins; exact matches are checked before regexes.
+ */
+export class FixtureModel implements Model {
+  constructor(private source: FixtureSource) {}
+
+  async *process(input: string, _signal?: AbortSignal, options?: GenerationOptions): AsyncGenerator<string> {
+    const chunks = matchFixture(this.source.fixtures(), input, templateVariables(input, options));
+    if (!chunks) {
+      yield `No fixture matches this message: ${input}`;
+      return;
+    }
+    yield* chunks;
+  }
+}
+
+// A fixture's reply, before any regex captures are substituted. Templates are
+// left as they are without variables to fill them in.
+function reply(fixture: Fixture, variables?: Record<TemplateVariable, string>): string[] {
+  if (fixture.template !== undefined) {
+    return [variables ? renderTemplate(fixture.template, variables) : fixture.template];
+  }
+  return fixture.chunks ?? [fixture.response!];
+}
+
+export function matchFixture(
+  fixtures: Fixture[],
+  input: string,
+  variables?: Record<TemplateVariable, string>
+): string[] | undefined {
+  const exact = fixtures.find(fixture => fixture.match === input);
+  if (exact) {
+    return reply(exact, variables);
+  }
+
+  for (const fixture of fixtures) {
+    if (typeof fixture.match === 'string') continue;
+
+    const found = new RegExp(fixture.match.regex, fixture.match.flags).exec(input);
+    if (found) {
+      const substitute = (text: string) =>
+        text.replace(/\$(\d+)/g, (placeholder, group: string) => found[Number(group)] ?? placeholder);
+      return reply(fixture, variables).map(substitute);
+    }
+  }
+  return undefined;
+}
@@ -0,0 +1,48 @@
+import { describe, it, expect } from "vitest";
+import { JsonModel } from "./json-model.js";
+import type { GenerationOptions } from "./model.js";
+
+async function generate(input: string, options?: GenerationOptions): Promise<unknown> {
+  const model = new JsonModel();
+  let output = "";
+  for await (const chunk of model.process(input, undefined, options)) {
+    output += chunk;
+  }
+  return JSON.parse(output);
+}
+
+describe("JsonModel", () => {
+  it("should wrap the message without a schema", async () => {
+    expect(await generate("Hello")).toEqual({ message: "Hello" });
+    expect(await generate("Hello", { responseFormat: { type: "json_object" } })).toEqual({ message: "Hello" });
+  });
+
+  it("should satisfy a schema with nested objects, arrays, enums and bounds", async () => {
+    const schema = {
+      type: "object",
+      properties: {
+        title: { type: "string" },
+        status: { type: "string", enum: ["draft", "published"] },
+        rating: { type: "number", minimum: 1, maximum: 5 },
+        author: {
+          type: "object",
+          properties: { name: { type: "string" }, age: { type: "integer", exclusiveMinimum: 17 } },
+          required: ["name", "age"],
+          additionalProperties: false,
+        },
+        tags: { type: "array", items: { type: "string", minLength: 2 }, minItems: 2 },
+        summary: { type: "string" },
+      },
+      required: ["title", "status", "rating", "author", "tags"],
+      additionalProperties: false,
+    };
+
+    expect(await generate("Ignored", { responseFormat: { type: "json_schema", schema } })).toEqual({
+      title: "string",
+      status: "draft",
+      rating: 1,
+      author: { name: "string", age: 18 },
+      tags: ["string", "string"],
+    });
+  });
+});
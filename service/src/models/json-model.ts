@@ -0,0 +1,33 @@
+import { Model } from './model.js';
+import type { GenerationOptions } from './model.js';
+import { synthesize } from '../utils/json-schema.js';
+
+/**
+ * JSON - Structured Output Generator
+ *
+ * ORIGIN:
+ * A testing utility created for TeenyTiny AI. Structured outputs let clients
+ * hand the model a JSON schema and parse the reply straight into their own
+ * types, and that parsing code deserves tests that don't need a real model.
+ *
+ * CONVERSATION EXPERIENCE:
+ * With a json_schema response_format, JSON replies with the smallest value that
+ * satisfies the schema: required properties only, the first enum value, and
+ * numbers, strings and arrays at their lower bounds. Without a schema it wraps
+ * the last user message in a JSON object.
+ *
+ * HOW IT WORKS:
+ * 1. The schema is walked from the root, resolving local $refs
+ * 2. Each node becomes a placeholder value of its type (see utils/json-schema.ts)
+ * 3. The result is serialized compactly as a single chunk
+ */
+export class JsonModel implements Model {
+  async *process(input: string, _signal?: AbortSignal, options?: GenerationOptions): AsyncGenerator<string> {
+    const format = options?.responseFormat;
+    if (format?.type === 'json_schema') {
+      yield JSON.stringify(synthesize(format.schema));
+      return;
+    }
+    yield JSON.stringify({ message: input });
+  }
+}
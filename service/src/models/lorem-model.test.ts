@@ -0,0 +1,38 @@
+import { describe, it, expect } from "vitest";
+import { LoremModel } from "./lorem-model.js";
+import type { GenerationOptions } from "./model.js";
+
+async function generate(options?: GenerationOptions): Promise<string> {
+  const model = new LoremModel();
+  let output = "";
+  for await (const chunk of model.process("anything", undefined, options)) {
+    output += chunk;
+  }
+  return output;
+}
+
+describe("LoremModel", () => {
+  it("should open with the classic sentence", async () => {
+    const output = await generate({ seed: 1 });
+
+    expect(output.startsWith("Lorem ipsum dolor sit amet, consectetur adipiscing elit.")).toBe(true);
+  });
+
+  it("should write three paragraphs without a token budget", async () => {
+    const output = await generate({ seed: 1 });
+
+    expect(output.split("\n\n")).toHaveLength(3);
+  });
+
+  it("should be deterministic for a seed", async () => {
+    expect(await generate({ seed: 42 })).toBe(await generate({ seed: 42 }));
+    expect(await generate({ seed: 42 })).not.toBe(await generate({ seed: 43 }));
+  });
+
+  it("should write just past a max_tokens budget", async () => {
+    const output = await generate({ seed: 7, maxTokens: 1000 });
+
+    expect(output.length).toBeGreaterThan(8000);
+    expect(output.length).toBeLessThan(8100);
+  });
+});
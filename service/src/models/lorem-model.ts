@@ -0,0 +1,94 @@
+import { Model } from './model.js';
+import type { GenerationOptions } from './model.js';
+
+/**
+ * LOREM - Placeholder Text Generator
+ * 
+ * ORIGIN:
+ * "Lorem ipsum" is scrambled Latin from Cicero's "De finibus bonorum et malorum"
+ * (45 BC), used by typesetters since the 1500s as filler that looks like prose
+ * without distracting the reader with meaning.
+ * 
+ * CONVERSATION EXPERIENCE:
+ * LOREM ignores what it is told and writes filler paragraphs. With max_tokens it
+ * keeps writing until the budget runs out, so clients can test truncation,
+ * scrolling and token counting against realistically long output. The same seed
+ * always produces the same text.
+ * 
+ * HOW IT WORKS:
+ * 1. A seeded pseudo-random generator picks words from the classic vocabulary
+ * 2. Words form sentences of 6-14 words, and sentences form paragraphs of 4-7
+ * 3. Output streams one word at a time
+ */
+
+const OPENING = 'Lorem ipsum dolor sit amet, consectetur adipiscing elit.';
+
+const WORDS = [
+  'lorem', 'ipsum', 'dolor', 'sit', 'amet', 'consectetur', 'adipiscing', 'elit',
+  'sed', 'do', 'eiusmod', 'tempor', 'incididunt', 'ut', 'labore', 'et', 'dolore',
+  'magna', 'aliqua', 'enim', 'ad', 'minim', 'veniam', 'quis', 'nostrud',
+  'exercitation', 'ullamco', 'laboris', 'nisi', 'aliquip', 'ex', 'ea', 'commodo',
+  'consequat', 'duis', 'aute', 'irure', 'in', 'reprehenderit', 'voluptate',
+  'velit', 'esse', 'cillum', 'fugiat', 'nulla', 'pariatur', 'excepteur', 'sint',
+  'occaecat', 'cupidatat', 'non', 'proident', 'sunt', 'culpa', 'qui', 'officia',
+  'deserunt', 'mollit', 'anim', 'id', 'est', 'laborum',
+];
+
+// Paragraphs written when no max_tokens budget is given
+const DEFAULT_PARAGRAPHS = 3;
+
+export class LoremModel implements Model {
+  async *process(_input: string, signal?: AbortSignal, options?: GenerationOptions): AsyncGenerator<string> {
+    const random = mulberry32(options?.seed ?? Math.floor(Math.random() * 2 ** 32));
+
+    // No tokenizer counts fewer than one token per 8 characters of lorem ipsum,
+    // so write that much and let the adapter cut the output at exactly max_tokens
+    const budget = options?.maxTokens === undefined ? undefined : options.maxTokens * 8;
+    let written = 0;
+
+    for (let paragraph = 0; budget !== undefined || paragraph < DEFAULT_PARAGRAPHS; paragraph++) {
+      const sentences = paragraph === 0 ? [OPENING] : [];
+      const sentenceCount = 4 + Math.floor(random() * 4);
+      while (sentences.length < sentenceCount) {
+        sentences.push(sentence(random));
+      }
+
+      const words = sentences.join(' ').split(' ');
+      for (let i = 0; i < words.length; i++) {
+        if (signal?.aborted) return;
+        if (budget !== undefined && written > budget) return;
+
+        const separator = i > 0 ? ' ' : paragraph > 0 ? '\n\n' : '';
+        const chunk = separator + words[i];
+        written += chunk.length;
+        yield chunk;
+      }
+    }
+  }
+}
+
+function sentence(random: () => number): string {
+  const length = 6 + Math.floor(random() * 9);
+  const words = Array.from({ length }, () => WORDS[Math.floor(random() * WORDS.length)]!);
+
+  // An occasional comma makes the rhythm read like prose
+  if (length > 8) {
+    const comma = 2 + Math.floor(random() * (length - 4));
+    words[comma] += ',';
+  }
+
+  const text = words.join(' ');
+  return text.charAt(0).toUpperCase() + text.slice(1) + '.';
+}
+
+// Small, fast seeded PRNG returning floats in [0, 1)
+export function mulberry32(seed: number): () => number {
+  let state = seed >>> 0;
+  return () => {
+    state = (state + 0x6d2b79f5) >>> 0;
+    let t = state;
+    t = Math.imul(t ^ (t >>> 15), t | 1);
+    t ^= t + Math.imul(t ^ (t >>> 7), t | 61);
+    return ((t ^ (t >>> 14)) >>> 0) / 4294967296;
+  };
+}
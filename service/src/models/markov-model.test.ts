@@ -0,0 +1,59 @@
+import { describe, it, expect } from "vitest";
+import { MarkovModel } from "./markov-model.js";
+import type { GenerationOptions } from "./model.js";
+
+const CORPUS = `The cat sat on the mat. The dog sat on the rug and the cat ran away.
+The bird sang on the roof while the dog slept on the rug. A cat can sleep all day.`;
+
+async function generate(input: string, options?: GenerationOptions): Promise<string> {
+  const model = new MarkovModel(CORPUS);
+  let output = "";
+  for await (const chunk of model.process(input, undefined, options)) {
+    output += chunk;
+  }
+  return output;
+}
+
+describe("MarkovModel", () => {
+  it("should be deterministic for a seed", async () => {
+    expect(await generate("Hi", { seed: 42 })).toBe(await generate("Hi", { seed: 42 }));
+    expect(await generate("Hi", { seed: 42, maxTokens: 50 })).toBe(await generate("Hi", { seed: 42, maxTokens: 50 }));
+
+    const outputs = new Set<string>();
+    for (let seed = 0; seed < 10; seed++) {
+      outputs.add(await generate("Hi", { seed, maxTokens: 30 }));
+    }
+    expect(outputs.size).toBeGreaterThan(1);
+  });
+
+  it("should only follow a word with a word that follows it in the corpus", async () => {
+    const corpus = CORPUS.split(/\s+/);
+    const pairs = new Set(corpus.slice(1).map((word, i) => `${corpus[i]} ${word}`));
+    const words = (await generate("Hi", { seed: 3, maxTokens: 40 })).split(" ");
+
+    // A word that only ends the corpus is followed by the start of a sentence
+    const unexpected = words.slice(1).filter((word, i) => !pairs.has(`${words[i]} ${word}`) && words[i] !== "day.");
+    expect(unexpected).toEqual([]);
+  });
+
+  it("should write three sentences without a token budget", async () => {
+    const output = await generate("Hi", { seed: 1 });
+
+    expect(output.match(/[.!?](?= |$)/g)).toHaveLength(3);
+    expect(output.endsWith(".")).toBe(true);
+  });
+
+  it("should write just past a max_tokens budget", async () => {
+    const output = await generate("Hi", { seed: 7, maxTokens: 100 });
+
+    expect(output.split(" ")).toHaveLength(101);
+  });
+
+  it("should continue the message when the corpus has its last words", async () => {
+    expect(await generate("Tell me where the bird sang", { seed: 5, maxTokens: 1 })).toBe("on the");
+  });
+
+  it("should refuse a corpus too short to learn from", () => {
+    expect(() => new MarkovModel("too short")).toThrow(/more than 2 words/);
+  });
+});
@@ -0,0 +1,111 @@
+import { Model } from './model.js';
+import type { GenerationOptions } from './model.js';
+import { mulberry32 } from './lorem-model.js';
+
+/**
+ * MARKOV - Text Generated From a Corpus
+ *
+ * ORIGIN:
+ * Andrey Markov counted letter pairs in Pushkin's "Eugene Onegin" in 1913, and
+ * Claude Shannon generated English-looking text from word pairs in 1948. Word
+ * chains trained on a text have written parody prose ever since.
+ *
+ * CONVERSATION EXPERIENCE:
+ * MARKOV is trained on a corpus given with --corpus when the server starts, and
+ * writes text that reads like it, word by word. It continues the user's message
+ * when that ends in words the corpus has, so demos show varied, realistic text
+ * in their own domain. The same seed always produces the same text.
+ *
+ * HOW IT WORKS:
+ * 1. Every run of two words in the corpus is mapped to the words that follow it
+ * 2. Text starts from the end of the user's message, or a sentence of the corpus,
+ *    and each next word is picked from those that followed the last two
+ * 3. A run that only ends the corpus starts again from another sentence
+ * 4. Output streams one word at a time, for three sentences or, with
+ *    max_tokens, until the budget runs out
+ */
+
+// Words of context each next word is picked by
+export const DEFAULT_ORDER = 2;
+
+// Sentences written when no max_tokens budget is given
+const DEFAULT_SENTENCES = 3;
+
+// Ends the reply of a corpus without sentence ends
+const MAX_WORDS = 200;
+
+function endsSentence(word: string): boolean {
+  return /[.!?]["')\]]*$/.test(word);
+}
+
+export class MarkovChain {
+  private next = new Map<string, string[]>();
+  // The words each sentence of the corpus starts with
+  private starts: string[][] = [];
+
+  constructor(corpus: string, private order: number = DEFAULT_ORDER) {
+    const words = corpus.split(/\s+/).filter(word => word !== '');
+    if (words.length <= order) {
+      throw new Error(`expected a corpus of more than ${order} words, got ${words.length}`);
+    }
+
+    for (let i = 0; i + order < words.length; i++) {
+      const state = words.slice(i, i + order);
+      const key = state.join(' ');
+      this.next.set(key, [...(this.next.get(key) ?? []), words[i + order]!]);
+      if (i === 0 || endsSentence(words[i - 1]!)) {
+        this.starts.push(state);
+      }
+    }
+  }
+
+  // Words without end, continuing the prompt when the corpus has its last words
+  *walk(random: () => number, prompt: string = ''): Generator<string> {
+    const pick = <T>(choices: T[]): T => choices[Math.floor(random() * choices.length)]!;
+
+    let state = prompt.split(/\s+/).filter(word => word !== '').slice(-this.order);
+    if (state.length < this.order || !this.next.has(state.join(' '))) {
+      state = pick(this.starts);
+      yield* state;
+    }
+    for (;;) {
+      const choices = this.next.get(state.join(' '));
+      if (!choices) {
+        state = pick(this.starts);
+        yield* state;
+        continue;
+      }
+      const word = pick(choices);
+      yield word;
+      state = [...state.slice(1), word];
+    }
+  }
+}
+
+export class MarkovModel implements Model {
+  private chain: MarkovChain;
+
+  // Throws when the corpus is too short to learn from
+  constructor(corpus: string, order: number = DEFAULT_ORDER) {
+    this.chain = new MarkovChain(corpus, order);
+  }
+
+  async *process(input: string, signal?: AbortSignal, options?: GenerationOptions): AsyncGenerator<string> {
+    const random = mulberry32(options?.seed ?? Math.floor(Math.random() * 2 ** 32));
+
+    // Every tokenizer counts a word as at least one token, so write one word
+    // past the budget and let the adapter cut the output at exactly max_tokens
+    const budget = options?.maxTokens;
+    let written = 0;
+    let sentences = 0;
+
+    for (const word of this.chain.walk(random, input)) {
+      if (signal?.aborted) return;
+      if (budget !== undefined ? written > budget : sentences >= DEFAULT_SENTENCES || written >= MAX_WORDS) return;
+
+      yield written > 0 ? ` ${word}` : word;
+      written++;
+      if (endsSentence(word)) sentences++;
+    }
+  }
+}
@@ -0,0 +1,35 @@
+import { describe, it, expect } from "vitest";
+import { MemoryModel } from "./memory-model.js";
+import type { ConversationMessage } from "./model.js";
+
+async function reply(messages: ConversationMessage[]): Promise<string> {
+  const model = new MemoryModel();
+  let output = "";
+  for await (const chunk of model.process(messages.at(-1)!.content, undefined, { messages })) {
+    output += chunk;
+  }
+  return output;
+}
+
+describe("MemoryModel", () => {
+  it("should say when it remembers nothing earlier", async () => {
+    expect(await reply([{ role: "user", content: "hello" }])).toBe(
+      `Turn 1. You said "hello". I don't remember anything before that.`,
+    );
+  });
+
+  it("should repeat every earlier user message, oldest first", async () => {
+    const output = await reply([
+      { role: "system", content: "Be brief" },
+      { role: "user", content: "my name is Ada" },
+      { role: "assistant", content: "Turn 1." },
+      { role: "user", content: "I like tea" },
+      { role: "assistant", content: "Turn 2." },
+      { role: "user", content: "what do you know?" },
+    ]);
+
+    expect(output).toBe(
+      `Turn 3. You said "what do you know?". Before that you said "my name is Ada", "I like tea".`,
+    );
+  });
+});
@@ -0,0 +1,45 @@
+import { Model } from './model.js';
+import type { GenerationOptions } from './model.js';
+
+/**
+ * MEMORY - Server-Side Conversation State
+ *
+ * ORIGIN:
+ * A testing utility created for TeenyTiny AI. Some APIs keep the conversation
+ * on the server, so a client sends only its new message and relies on the
+ * server to remember the rest. Clients built that way need a model whose
+ * replies show what it remembers.
+ *
+ * CONVERSATION EXPERIENCE:
+ * MEMORY numbers the turn and repeats everything the user has said so far,
+ * oldest first. The server keeps its conversations, keyed by the request's
+ * user or its x-teenytiny-conversation header (see app.ts), so each reply
+ * grows by one message. Without either it remembers only what the request
+ * itself carries.
+ *
+ * HOW IT WORKS:
+ * 1. The user messages of the conversation, remembered ones first, are collected
+ * 2. The reply names the turn, the newest message and those before it
+ * 3. Output streams one word at a time
+ */
+export class MemoryModel implements Model {
+  async *process(input: string, signal?: AbortSignal, options?: GenerationOptions): AsyncGenerator<string> {
+    const said = (options?.messages ?? [{ role: 'user', content: input }])
+      .filter(message => message.role === 'user')
+      .map(message => `"${message.content}"`);
+    const earlier = said.slice(0, -1);
+
+    const reply = [
+      `Turn ${said.length}. You said ${said.at(-1) ?? '""'}.`,
+      earlier.length > 0
+        ? `Before that you said ${earlier.join(', ')}.`
+        : "I don't remember anything before that.",
+    ].join(' ');
+
+    const words = reply.split(' ');
+    for (let i = 0; i < words.length; i++) {
+      if (signal?.aborted) return;
+      yield i === 0 ? words[i]! : ` ${words[i]}`;
+    }
+  }
+}
@@ -1,4 +1,35 @@
+// The output format a request asked for, with any JSON schema it must satisfy
+export type ResponseFormat =
+  | { type: 'text' }
+  | { type: 'json_object' }
+  | { type: 'json_schema'; schema: Record<string, unknown> };
+
+// Per-request generation settings a model may honor
+export interface GenerationOptions {
+  // The adapter truncates output beyond this anyway; models may use it to size their output
+  maxTokens?: number;
+  // Makes sampling deterministic for models that use randomness
+  seed?: number;
+  // The request's metadata, for models with per-request settings
+  metadata?: Record<string, string>;
+  // The request's response_format, for models that can produce structured output
+  responseFormat?: ResponseFormat;
+  // The conversation as text, for models that look beyond the last user message
+  messages?: ConversationMessage[];
+  // The model and parameters the request was sent with, such as temperature,
+  // for models that report them
+  parameters?: Record<string, unknown>;
+  // Lets a model pick the finish reason; truncation and !finish directives take precedence
+  setFinishReason?: (reason: 'stop' | 'length' | 'content_filter') => void;
+}
+
+export interface ConversationMessage {
+  role: string;
+  content: string;
+}
+
 // Simple text-based model interface
 export interface Model {
-  process(input: string): AsyncGenerator<string>;
-}
\ No newline at end of file
+  // The signal is aborted when the client goes away; slow models should stop early
+  process(input: string, signal?: AbortSignal, options?: GenerationOptions): AsyncGenerator<string>;
+}
@@ -0,0 +1,51 @@
+import { describe, it, expect } from "vitest";
+import { RouterModel, routerTargets } from "./router-model.js";
+import type { Router } from "./router-model.js";
+import { EchoModel } from "./echo-model.js";
+import { TemplateModel } from "./template-model.js";
+import type { Model } from "./model.js";
+
+const MODELS: Record<string, Model> = {
+  echo: new EchoModel(),
+  shout: new TemplateModel(() => "SHOUTED"),
+};
+
+async function reply(router: Router, message: string): Promise<string> {
+  const model = new RouterModel(router, (id) => MODELS[id]);
+  let output = "";
+  for await (const chunk of model.process(message)) {
+    output += chunk;
+  }
+  return output;
+}
+
+describe("RouterModel", () => {
+  const agent: Router = {
+    routes: [
+      { match: "weather in (\\w+)", flags: "i", reply: "It's sunny in $1." },
+      { match: "weather", reply: "It's sunny." },
+      { match: "^loud", model: "shout" },
+    ],
+  };
+
+  it("should answer with the first route that matches", async () => {
+    expect(await reply(agent, "What's the Weather in Paris?")).toBe("It's sunny in Paris.");
+    expect(await reply(agent, "any weather today?")).toBe("It's sunny.");
+    expect(await reply(agent, "loud weather")).toBe("It's sunny.");
+  });
+
+  it("should hand the request to a route's model", async () => {
+    expect(await reply(agent, "loud noises")).toBe("SHOUTED");
+  });
+
+  it("should fall through to echo, or the fallback it's given", async () => {
+    expect(await reply(agent, "hello there")).toBe("hello there");
+    expect(await reply({ ...agent, fallback: "shout" }, "hello there")).toBe("SHOUTED");
+    expect(await reply({ routes: [] }, "anything")).toBe("anything");
+  });
+
+  it("should name every model it may hand a request to", () => {
+    expect(routerTargets(agent)).toEqual(["shout", "echo"]);
+    expect(routerTargets({ routes: agent.routes, fallback: "eliza" })).toEqual(["shout", "eliza"]);
+  });
+});
@@ -0,0 +1,79 @@
+import { Model } from './model.js';
+import type { GenerationOptions } from './model.js';
+
+/**
+ * ROUTER - Hands Each Message to the Model Its Routes Pick
+ *
+ * ORIGIN:
+ * A testing utility created for TeenyTiny AI. A mocked agent rarely gives the
+ * same kind of answer to everything: a question about the weather should get
+ * a canned forecast, while anything else can fall through to a plain model.
+ *
+ * CONVERSATION EXPERIENCE:
+ * ROUTER is configured in the server's config file under [routers.<name>] and
+ * served as router:<name>. Each message is answered by a canned reply or by
+ * another model, depending on which of the router's regexes it matches.
+ *
+ * HOW IT WORKS:
+ * 1. Routes are tried against the last user message in the order they're
+ *    written, and the first whose regex matches wins
+ * 2. A route either replies with its text, where $1, $2 and so on stand for
+ *    regex captures, or hands the request to its model
+ * 3. A message no route matches goes to the fallback model, echo unless set
+ *
+ * Models a route hands a request to generate its text only; the response
+ * still comes from router:<name>, so features of their own endpoint, such as
+ * echo's directives or tooluse's tool calls, don't apply.
+ */
+
+export interface Route {
+  match: string;
+  flags?: string;
+  // Exactly one of these
+  model?: string;
+  reply?: string;
+}
+
+export interface Router {
+  routes: Route[];
+  fallback?: string;
+}
+
+export const DEFAULT_FALLBACK = 'echo';
+
+// Every model a router may hand a request to, for checking they exist
+export function routerTargets(router: Router): string[] {
+  const targets = router.routes.flatMap(route => (route.model === undefined ? [] : [route.model]));
+  return [...targets, router.fallback ?? DEFAULT_FALLBACK];
+}
+
+export class RouterModel implements Model {
+  // Resolves the models routes name, whether or not clients may use them
+  constructor(
+    private router: Router,
+    private resolve: (id: string) => Model | undefined
+  ) {}
+
+  async *process(input: string, signal?: AbortSignal, options?: GenerationOptions): AsyncGenerator<string> {
+    for (const route of this.router.routes) {
+      const found = new RegExp(route.match, route.flags).exec(input);
+      if (!found) continue;
+
+      if (route.reply !== undefined) {
+        yield route.reply.replace(/\$(\d+)/g, (placeholder, group: string) => found[Number(group)] ?? placeholder);
+        return;
+      }
+      yield* this.handTo(route.model!, input, signal, options);
+      return;
+    }
+    yield* this.handTo(this.router.fallback ?? DEFAULT_FALLBACK, input, signal, options);
+  }
+
+  private handTo(id: string, input: string, signal?: AbortSignal, options?: GenerationOptions): AsyncGenerator<string> {
+    const model = this.resolve(id);
+    if (!model) {
+      throw new Error(`Router can't hand a request to unknown model ${id}`);
+    }
+    return model.process(input, signal, options);
+  }
+}
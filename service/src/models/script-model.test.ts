@@ -0,0 +1,48 @@
+import { describe, it, expect } from "vitest";
+import { ScriptModel } from "./script-model.js";
+import type { Script } from "./script-model.js";
+import type { GenerationOptions } from "./model.js";
+
+async function run(script: Script, options: GenerationOptions = {}): Promise<string[]> {
+  const chunks: string[] = [];
+  for await (const chunk of new ScriptModel("test", script).process("Hello", undefined, options)) {
+    chunks.push(chunk);
+  }
+  return chunks;
+}
+
+describe("ScriptModel", () => {
+  it("should pass the conversation and metadata to the script", async () => {
+    const script: Script = ({ messages, input, metadata }) =>
+      `${messages.length} ${input} ${metadata["mood"]}`;
+
+    const chunks = await run(script, {
+      messages: [
+        { role: "system", content: "Be brief" },
+        { role: "user", content: "Hello" },
+      ],
+      metadata: { mood: "calm" },
+    });
+
+    expect(chunks).toEqual(["2 Hello calm"]);
+  });
+
+  it("should stream the chunks a script returns", async () => {
+    expect(await run(async () => ({ chunks: ["a", "b", "c"] }))).toEqual(["a", "b", "c"]);
+  });
+
+  it("should report the finish reason a script chooses", async () => {
+    let reported: string | undefined;
+    await run(() => ({ content: "", finish_reason: "content_filter" }), {
+      setFinishReason: reason => {
+        reported = reason;
+      },
+    });
+
+    expect(reported).toBe("content_filter");
+  });
+
+  it("should reject results without content", async () => {
+    await expect(run(() => ({}) as never)).rejects.toThrow(/Script test returned neither/);
+  });
+});
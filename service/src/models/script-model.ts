@@ -0,0 +1,72 @@
+import { Model } from './model.js';
+import type { ConversationMessage, GenerationOptions } from './model.js';
+
+/**
+ * What a script receives: the conversation as text, the last user message,
+ * and the request's metadata.
+ */
+export interface ScriptRequest {
+  messages: ConversationMessage[];
+  input: string;
+  metadata: Record<string, string>;
+}
+
+/**
+ * What a script returns: a plain string, or `content` or exact streaming
+ * `chunks` with an optional finish reason.
+ */
+export type ScriptResult =
+  | string
+  | {
+      content?: string;
+      chunks?: string[];
+      finish_reason?: 'stop' | 'length' | 'content_filter';
+    };
+
+export type Script = (request: ScriptRequest) => ScriptResult | Promise<ScriptResult>;
+
+const FINISH_REASONS = ['stop', 'length', 'content_filter'];
+
+/**
+ * Runs a user-supplied script for every request, turning teenytiny into a
+ * programmable simulator: the script decides the content, how it is chunked,
+ * and why it finished.
+ */
+export class ScriptModel implements Model {
+  constructor(
+    private name: string,
+    private script: Script
+  ) {}
+
+  async *process(input: string, _signal?: AbortSignal, options?: GenerationOptions): AsyncGenerator<string> {
+    const result = await this.script({
+      messages: options?.messages ?? [{ role: 'user', content: input }],
+      input,
+      metadata: options?.metadata ?? {},
+    });
+
+    if (typeof result === 'string') {
+      yield result;
+      return;
+    }
+    if (!result || typeof result !== 'object') {
+      throw new Error(`Script ${this.name} returned ${typeof result}, expected a string or an object`);
+    }
+
+    const { content, chunks, finish_reason } = result;
+    if (finish_reason !== undefined) {
+      if (!FINISH_REASONS.includes(finish_reason)) {
+        throw new Error(`Script ${this.name} returned an unknown finish_reason: ${finish_reason}`);
+      }
+      options?.setFinishReason?.(finish_reason);
+    }
+
+    if (Array.isArray(chunks) && chunks.every(chunk => typeof chunk === 'string')) {
+      yield* chunks;
+    } else if (typeof content === 'string') {
+      yield content;
+    } else {
+      throw new Error(`Script ${this.name} returned neither "content" as a string nor "chunks" as an array of strings`);
+    }
+  }
+}
@@ -0,0 +1,47 @@
+import { describe, it, expect } from "vitest";
+import { SlowModel, parseInterval } from "./slow-model.js";
+
+describe("SlowModel", () => {
+  it("should echo the input one word at a time", async () => {
+    const model = new SlowModel(1);
+    const chunks: string[] = [];
+
+    for await (const chunk of model.process("one two three")) {
+      chunks.push(chunk);
+    }
+
+    expect(chunks).toEqual(["one", " two", " three"]);
+  });
+
+  it("should wait the interval before each word", async () => {
+    const model = new SlowModel(20);
+    const start = Date.now();
+
+    const chunks: string[] = [];
+    for await (const chunk of model.process("one two three")) {
+      chunks.push(chunk);
+    }
+
+    expect(Date.now() - start).toBeGreaterThanOrEqual(55);
+  });
+
+  it("should prefer the interval from request metadata", async () => {
+    const model = new SlowModel(5000);
+    const start = Date.now();
+
+    const chunks: string[] = [];
+    for await (const chunk of model.process("quick", undefined, { metadata: { interval_ms: "1" } })) {
+      chunks.push(chunk);
+    }
+
+    expect(Date.now() - start).toBeLessThan(1000);
+  });
+
+  it("should only accept whole millisecond intervals within range", () => {
+    expect(parseInterval("200")).toBe(200);
+    expect(parseInterval("0")).toBe(0);
+    expect(parseInterval("-5")).toBeUndefined();
+    expect(parseInterval("1.5")).toBeUndefined();
+    expect(parseInterval("20000")).toBeUndefined();
+  });
+});
@@ -0,0 +1,37 @@
+import { Model } from './model.js';
+import type { GenerationOptions } from './model.js';
+import { sleep } from '../utils/sleep.js';
+
+// Longest interval accepted, so a typo can't hold a connection open for hours
+export const MAX_SLOW_INTERVAL_MS = 10000;
+
+// Parses an interval such as "200", or undefined if it isn't a whole number of milliseconds in range
+export function parseInterval(value: string | undefined): number | undefined {
+  if (value === undefined || !/^\d+$/.test(value)) {
+    return undefined;
+  }
+  const interval = Number(value);
+  return interval <= MAX_SLOW_INTERVAL_MS ? interval : undefined;
+}
+
+/**
+ * Echoes the input one word at a time, waiting a fixed interval before each
+ * word, so client timeout, spinner and cancellation logic can be exercised.
+ * 
+ * The interval comes from the model name ("slow:200"), or per request from
+ * metadata.interval_ms, which takes precedence.
+ */
+export class SlowModel implements Model {
+  constructor(private intervalMs: number = 100) {}
+
+  async *process(input: string, signal?: AbortSignal, options?: GenerationOptions): AsyncGenerator<string> {
+    const interval = parseInterval(options?.metadata?.['interval_ms']) ?? this.intervalMs;
+    const words = (input || "Hello! I'm the Slow model. I take my time.").split(' ');
+
+    for (let i = 0; i < words.length; i++) {
+      await sleep(interval, signal);
+      if (signal?.aborted) return;
+      yield i === 0 ? words[i]! : ` ${words[i]}`;
+    }
+  }
+}
@@ -0,0 +1,64 @@
+import { describe, it, expect } from "vitest";
+import { checkTemplate, renderTemplate, templateVariables, TemplateModel, TEMPLATE_VARIABLES } from "./template-model.js";
+
+const NOW = new Date("2024-05-01T12:00:00.000Z");
+
+describe("TemplateModel", () => {
+  it("should fill in every variable from the request", () => {
+    const variables = templateVariables(
+      "ignored",
+      {
+        messages: [
+          { role: "system", content: "Be brief" },
+          { role: "user", content: "first" },
+          { role: "assistant", content: "ok" },
+          { role: "user", content: "second" },
+        ],
+        parameters: { model: "template", temperature: 0.5, top_p: 1, max_tokens: 20, seed: 7, n: 2, user: "ada" },
+      },
+      NOW,
+    );
+
+    expect(variables).toEqual({
+      last_user_message: "second",
+      message_count: "4",
+      model: "template",
+      temperature: "0.5",
+      top_p: "1",
+      max_tokens: "20",
+      seed: "7",
+      n: "2",
+      user: "ada",
+      now: "2024-05-01T12:00:00.000Z",
+      timestamp: "1714564800",
+    });
+    expect(Object.keys(variables)).toEqual([...TEMPLATE_VARIABLES]);
+  });
+
+  it("should leave parameters the request didn't set empty", () => {
+    const variables = templateVariables("hi", {}, NOW);
+
+    expect(renderTemplate("[{{temperature}}] {{ last_user_message }}", variables)).toBe("[] hi");
+  });
+
+  it("should reject variables it can't fill in", () => {
+    expect(() => checkTemplate("{{model}} {{ seed }}")).not.toThrow();
+    expect(() => checkTemplate("{{weather}}")).toThrow(/unknown variable \{\{weather\}\}/);
+  });
+
+  it("should read the template afresh for every request", async () => {
+    let template = "one {{n}}";
+    const model = new TemplateModel(() => template);
+    const reply = async () => {
+      let output = "";
+      for await (const chunk of model.process("hi", undefined, { parameters: { n: 3 } })) {
+        output += chunk;
+      }
+      return output;
+    };
+
+    expect(await reply()).toBe("one 3");
+    template = "two";
+    expect(await reply()).toBe("two");
+  });
+});
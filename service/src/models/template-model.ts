@@ -0,0 +1,99 @@
+import { Model } from './model.js';
+import type { GenerationOptions } from './model.js';
+
+/**
+ * TEMPLATE - Replies Rendered From Request Details
+ *
+ * ORIGIN:
+ * A testing utility created for TeenyTiny AI. Fixtures give fixed answers, but
+ * some tests need an answer that reflects the request: the message that was
+ * sent, how long the conversation was, or the parameters it was sent with.
+ *
+ * CONVERSATION EXPERIENCE:
+ * TEMPLATE replies with a template whose {{variables}} are filled in from the
+ * request. Templates are set by name through PUT /admin/templates and served
+ * as template:<name>; plain "template" serves the one named "default", or a
+ * built-in one until that is set. Fixtures can reply with a template too.
+ *
+ * HOW IT WORKS:
+ * 1. The template is read afresh on every request, so changes apply at once
+ * 2. Each {{variable}} is replaced with its value; parameters the request
+ *    didn't set are left empty
+ * 3. The reply is sent as a single chunk
+ */
+
+// What each {{variable}} stands for
+export const TEMPLATE_VARIABLES = [
+  'last_user_message',
+  'message_count',
+  'model',
+  'temperature',
+  'top_p',
+  'max_tokens',
+  'seed',
+  'n',
+  'user',
+  // ISO 8601
+  'now',
+  // Unix seconds
+  'timestamp',
+] as const;
+
+export type TemplateVariable = typeof TEMPLATE_VARIABLES[number];
+
+export const DEFAULT_TEMPLATE =
+  'You said "{{last_user_message}}" in a conversation of {{message_count}} messages with {{model}}.';
+
+const PLACEHOLDER = /\{\{\s*([a-z_]+)\s*\}\}/g;
+
+// Throws on a variable the template can't fill in, naming it
+export function checkTemplate(template: string): void {
+  for (const [, name] of template.matchAll(PLACEHOLDER)) {
+    if (!(TEMPLATE_VARIABLES as readonly string[]).includes(name!)) {
+      throw new Error(`unknown variable {{${name}}}; expected one of ${TEMPLATE_VARIABLES.join(', ')}`);
+    }
+  }
+}
+
+export function renderTemplate(template: string, variables: Record<TemplateVariable, string>): string {
+  return template.replace(PLACEHOLDER, (placeholder, name: string) =>
+    Object.hasOwn(variables, name) ? variables[name as TemplateVariable] : placeholder
+  );
+}
+
+// The values a request gives the variables
+export function templateVariables(
+  input: string,
+  options: GenerationOptions = {},
+  now: Date = new Date()
+): Record<TemplateVariable, string> {
+  const messages = options.messages ?? [];
+  const lastUser = [...messages].reverse().find(message => message.role === 'user');
+  const parameter = (name: string) => {
+    const value = options.parameters?.[name];
+    return value === undefined || value === null ? '' : String(value);
+  };
+
+  return {
+    last_user_message: lastUser?.content ?? input,
+    message_count: String(messages.length),
+    model: parameter('model'),
+    temperature: parameter('temperature'),
+    top_p: parameter('top_p'),
+    max_tokens: parameter('max_tokens'),
+    seed: parameter('seed'),
+    n: parameter('n'),
+    user: parameter('user'),
+    now: now.toISOString(),
+    timestamp: String(Math.floor(now.getTime() / 1000)),
+  };
+}
+
+export class TemplateModel implements Model {
+  // Reads the template on every request
+  constructor(private template: () => string) {}
+
+  async *process(input: string, _signal?: AbortSignal, options?: GenerationOptions): AsyncGenerator<string> {
+    yield renderTemplate(this.template(), templateVariables(input, options));
+  }
+}
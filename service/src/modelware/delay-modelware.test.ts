@@ -1,5 +1,6 @@
 import { describe, it, expect } from "vitest";
 import { DelayModelware } from "./delay-modelware.js";
+import { StreamSplitModelware } from "./stream-split-modelware.js";
 import { EchoModel } from "../models/echo-model.js";
 
 describe("DelayModelware", () => {
@@ -32,4 +33,29 @@ describe("DelayModelware", () => {
 
     expect(chunks).toEqual(["test input"]);
   });
-});
\ No newline at end of file
+
+  it("should stop generating promptly when the signal is aborted", async () => {
+    const baseModel = new StreamSplitModelware(
+      new EchoModel(),
+      StreamSplitModelware.WORDS,
+    );
+    const delayModel = new DelayModelware(baseModel, 1000);
+    const cancellation = new AbortController();
+
+    const start = Date.now();
+    const chunks: string[] = [];
+
+    for await (const chunk of delayModel.process(
+      "one two three four",
+      cancellation.signal,
+    )) {
+      chunks.push(chunk);
+      cancellation.abort();
+    }
+
+    const duration = Date.now() - start;
+
+    expect(chunks).toEqual(["one"]);
+    expect(duration).toBeLessThan(500); // Did not wait out the 1000ms delay
+  });
+});
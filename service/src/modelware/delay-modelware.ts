@@ -1,4 +1,6 @@
 import { Model } from '../models/model.js';
+import type { GenerationOptions } from '../models/model.js';
+import { sleep } from '../utils/sleep.js';
 
 export class DelayModelware implements Model {
   constructor(
@@ -6,10 +8,11 @@ export class DelayModelware implements Model {
     private delayMs: number = 50
   ) {}
 
-  async *process(input: string): AsyncGenerator<string> {
-    for await (const chunk of this.model.process(input)) {
+  async *process(input: string, signal?: AbortSignal, options?: GenerationOptions): AsyncGenerator<string> {
+    for await (const chunk of this.model.process(input, signal, options)) {
+      if (signal?.aborted) return;
       yield chunk;
-      await new Promise(resolve => setTimeout(resolve, this.delayMs));
+      await sleep(this.delayMs, signal);
     }
   }
-}
\ No newline at end of file
+}
@@ -1,4 +1,5 @@
 import { Model } from '../models/model.js';
+import type { GenerationOptions } from '../models/model.js';
 
 export class StreamSplitModelware implements Model {
   // Common split patterns
@@ -12,8 +13,8 @@ export class StreamSplitModelware implements Model {
     private splitPattern: RegExp = StreamSplitModelware.WORDS
   ) {}
 
-  async *process(input: string): AsyncGenerator<string> {
-    for await (const chunk of this.model.process(input)) {
+  async *process(input: string, signal?: AbortSignal, options?: GenerationOptions): AsyncGenerator<string> {
+    for await (const chunk of this.model.process(input, signal, options)) {
       if (this.splitPattern === StreamSplitModelware.WORDS) {
         // Special handling for WORDS to match original EchoModel behavior
         const words = chunk.split(' ');
@@ -0,0 +1,69 @@
+import { describe, it, expect, afterEach } from 'vitest';
+import { request } from 'http';
+import { connect } from 'http2';
+import type { AddressInfo, Server } from 'net';
+import { createNodeServer } from './node-server.js';
+
+const fetch = (req: Request) => new Response(`Hello from ${new URL(req.url).pathname}`);
+
+let server: Server | undefined;
+
+afterEach(() => {
+  server?.close();
+  server = undefined;
+});
+
+async function listen(idleTimeoutMs = 5000): Promise<string> {
+  server = createNodeServer(fetch, { idleTimeoutMs });
+  await new Promise<void>(resolve => server!.listen(0, '127.0.0.1', resolve));
+  return `http://127.0.0.1:${(server!.address() as AddressInfo).port}`;
+}
+
+describe('createNodeServer', () => {
+  it('serves HTTP/1.1', async () => {
+    const url = await listen();
+
+    const { version, body } = await new Promise<{ version: string; body: string }>((resolve, reject) => {
+      request(`${url}/one`, res => {
+        let body = '';
+        res.on('data', chunk => (body += chunk));
+        res.on('end', () => resolve({ version: res.httpVersion, body }));
+      })
+        .on('error', reject)
+        .end();
+    });
+
+    expect(version).toBe('1.1');
+    expect(body).toBe('Hello from /one');
+  });
+
+  it('serves HTTP/2 with prior knowledge on the same port', async () => {
+    const url = await listen();
+    const session = connect(url);
+
+    const body = await new Promise<string>((resolve, reject) => {
+      let body = '';
+      const stream = session.request({ ':path': '/two' });
+      stream.on('data', chunk => (body += chunk));
+      stream.on('end', () => resolve(body));
+      stream.on('error', reject);
+    });
+    session.close();
+
+    expect(body).toBe('Hello from /two');
+  });
+
+  it('sends GOAWAY to idle HTTP/2 sessions', async () => {
+    const url = await listen(100);
+    const session = connect(url);
+    const goaway = new Promise<number>(resolve => session.once('goaway', resolve));
+
+    await new Promise<void>(resolve => {
+      const stream = session.request({ ':path': '/' });
+      stream.resume();
+      stream.on('end', resolve);
+    });
+
+    expect(await goaway).toBe(0); // NO_ERROR
+  });
+});
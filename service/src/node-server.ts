@@ -0,0 +1,75 @@
+import { getRequestListener } from '@hono/node-server';
+import { createServer as createHttpServer } from 'http';
+import { createSecureServer, createServer as createHttp2Server, type Http2Session } from 'http2';
+import { createServer as createNetServer, type Server } from 'net';
+import type { UpgradeListener } from './node-websocket.js';
+
+// How long a connection may sit idle before the server closes it, matching
+// Node.js' default HTTP/1.1 keep-alive timeout
+export const IDLE_TIMEOUT_MS = 5000;
+
+// Every HTTP/2 connection opens with "PRI * HTTP/2.0"; no HTTP/1.1 method starts with PRI
+const HTTP2_PREFACE = Buffer.from('PRI');
+
+export interface NodeServerOptions {
+  // PEM certificate and key. With them the server speaks HTTPS and offers
+  // HTTP/2 and HTTP/1.1 over ALPN; without, plain HTTP/1.1 and HTTP/2 with
+  // prior knowledge share the port.
+  tls?: { cert: Buffer; key: Buffer };
+  idleTimeoutMs?: number;
+  // Answers HTTP/1.1 upgrade requests, such as for WebSockets
+  upgrade?: UpgradeListener;
+}
+
+type Fetch = (request: Request, env?: unknown) => Response | Promise<Response>;
+
+// Closing an idle session sends GOAWAY, so clients stop opening streams on it
+// instead of finding it reset under them
+function closeWhenIdle(session: Http2Session, idleTimeoutMs: number) {
+  session.setTimeout(idleTimeoutMs, () => session.close());
+}
+
+/**
+ * The server that accepts connections for the app. The returned server's
+ * 'connection' events see every socket, whichever protocol it goes on to speak.
+ */
+export function createNodeServer(fetch: Fetch, options: NodeServerOptions = {}): Server {
+  const listener = getRequestListener(fetch);
+  const idleTimeoutMs = options.idleTimeoutMs ?? IDLE_TIMEOUT_MS;
+
+  if (options.tls) {
+    const server = createSecureServer({ ...options.tls, allowHTTP1: true }, listener);
+    // Read by the HTTP/1.1 fallback, though missing from the HTTP/2 server's types
+    Object.assign(server, { keepAliveTimeout: idleTimeoutMs });
+    server.on('session', session => closeWhenIdle(session, idleTimeoutMs));
+    if (options.upgrade) {
+      // Emitted by the HTTP/1.1 fallback
+      server.on('upgrade', options.upgrade);
+    }
+    return server;
+  }
+
+  const http1 = createHttpServer(listener);
+  http1.keepAliveTimeout = idleTimeoutMs;
+  if (options.upgrade) {
+    http1.on('upgrade', options.upgrade);
+  }
+  const http2 = createHttp2Server(listener);
+  http2.on('session', session => closeWhenIdle(session, idleTimeoutMs));
+
+  // Peeks at the first bytes to hand the socket to the server for its protocol
+  return createNetServer(socket => {
+    socket.once('data', (head: Buffer) => {
+      socket.pause();
+      socket.unshift(head);
+      const prefix = head.subarray(0, HTTP2_PREFACE.length);
+      if (HTTP2_PREFACE.subarray(0, prefix.length).equals(prefix)) {
+        // The session reads what was put back itself
+        http2.emit('connection', socket);
+      } else {
+        http1.emit('connection', socket);
+        socket.resume();
+      }
+    });
+  });
+}
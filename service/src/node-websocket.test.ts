@@ -0,0 +1,98 @@
+import { describe, it, expect, afterEach } from 'vitest';
+import { request } from 'http';
+import type { AddressInfo, Server } from 'net';
+import { createApp } from './app.js';
+import { createNodeServer } from './node-server.js';
+import { createNodeWebSocket } from './node-websocket.js';
+
+const testAPIKey = 'tt-websocket-key';
+
+let server: Server | undefined;
+
+afterEach(() => {
+  server?.close();
+  server = undefined;
+});
+
+async function listen(): Promise<string> {
+  const websocket = createNodeWebSocket();
+  const app = createApp({ auth: { apiKey: testAPIKey }, upgradeWebSocket: websocket.upgradeWebSocket });
+  server = createNodeServer(app.fetch, { upgrade: websocket.upgradeListener(app.fetch) });
+  await new Promise<void>(resolve => server!.listen(0, '127.0.0.1', resolve));
+  return `127.0.0.1:${(server!.address() as AddressInfo).port}`;
+}
+
+// Resolves with every event received, once one of the given type arrives
+function collectUntil(socket: WebSocket, type: string): Promise<any[]> {
+  const events: any[] = [];
+  return new Promise((resolve, reject) => {
+    socket.addEventListener('message', message => {
+      const event = JSON.parse(message.data as string);
+      events.push(event);
+      if (event.type === type) resolve(events);
+    });
+    socket.addEventListener('error', reject);
+  });
+}
+
+describe('createNodeWebSocket', () => {
+  it('runs a realtime session through the app', async () => {
+    const host = await listen();
+    // Node's WebSocket (undici) takes headers as an extension to the standard
+    const socket = new WebSocket(`ws://${host}/v1/realtime?model=echo`, {
+      headers: { Authorization: `Bearer ${testAPIKey}` },
+    } as any);
+
+    const created = await collectUntil(socket, 'session.created');
+    expect(created[0].session.model).toBe('echo');
+
+    const done = collectUntil(socket, 'response.done');
+    socket.send(
+      JSON.stringify({
+        type: 'conversation.item.create',
+        item: { type: 'message', role: 'user', content: [{ type: 'input_text', text: 'Over the wire' }] },
+      })
+    );
+    socket.send(JSON.stringify({ type: 'response.create' }));
+    const events = await done;
+    socket.close();
+
+    const text = events
+      .filter(event => event.type === 'response.output_text.delta')
+      .map(event => event.delta)
+      .join('');
+    expect(text).toBe('Over the wire');
+    expect(events.at(-1).response.status).toBe('completed');
+  });
+
+  it('refuses upgrades the app rejects', async () => {
+    const host = await listen();
+
+    const status = await new Promise<number>((resolve, reject) => {
+      request(`http://${host}/v1/realtime`, {
+        headers: {
+          Connection: 'Upgrade',
+          Upgrade: 'websocket',
+          'Sec-WebSocket-Key': 'dGhlIHNhbXBsZSBub25jZQ==',
+          'Sec-WebSocket-Version': '13',
+        },
+      })
+        .on('response', res => resolve(res.statusCode!))
+        .on('upgrade', () => resolve(101))
+        .on('error', reject)
+        .end();
+    });
+
+    expect(status).toBe(401);
+  });
+
+  it('answers plain requests to /v1/realtime with 426', async () => {
+    const host = await listen();
+
+    const res = await fetch(`http://${host}/v1/realtime`, {
+      headers: { Authorization: `Bearer ${testAPIKey}` },
+    });
+
+    expect(res.status).toBe(426);
+  });
+});
@@ -0,0 +1,266 @@
+import { createHash } from 'crypto';
+import { STATUS_CODES, type IncomingMessage } from 'http';
+import type { Duplex } from 'stream';
+import { WSContext } from 'hono/ws';
+import type { UpgradeWebSocket, WSEvents, WSReadyState } from 'hono/ws';
+
+// WebSockets for the Node.js server (RFC 6455), enough for the Realtime API:
+// text and binary messages, fragmentation, ping/pong and the closing
+// handshake. Upgrade requests are routed through the app first, so auth and
+// every other middleware run as for any request; a route that accepts the
+// upgrade leaves its events here for the handshake to pick up.
+
+const HANDSHAKE_GUID = '258EAFA5-E914-47DA-95CA-C5AB0DC85B11';
+
+// Largest message accepted from a client, across all of its fragments
+export const MAX_MESSAGE_BYTES = 1024 * 1024;
+
+const OPCODE = { continuation: 0x0, text: 0x1, binary: 0x2, close: 0x8, ping: 0x9, pong: 0xa } as const;
+
+// Close codes used here
+const CLOSE = { normal: 1000, protocolError: 1002, tooBig: 1009 } as const;
+
+type Fetch = (request: Request, env?: unknown) => Response | Promise<Response>;
+
+export type UpgradeListener = (request: IncomingMessage, socket: Duplex, head: Buffer) => void;
+
+export interface NodeWebSocket {
+  // Passed to createApp, for routes that accept WebSocket connections
+  upgradeWebSocket: UpgradeWebSocket<Duplex>;
+  // The server's 'upgrade' listener, routing each request through fetch
+  upgradeListener(fetch: Fetch): UpgradeListener;
+}
+
+function frame(opcode: number, payload: Buffer): Buffer {
+  const length = payload.length;
+  const header =
+    length < 126
+      ? Buffer.from([0x80 | opcode, length])
+      : length < 0x10000
+        ? Buffer.from([0x80 | opcode, 126, length >> 8, length & 0xff])
+        : Buffer.concat([Buffer.from([0x80 | opcode, 127]), bigEndian64(length)]);
+  return Buffer.concat([header, payload]);
+}
+
+function bigEndian64(value: number): Buffer {
+  const bytes = Buffer.alloc(8);
+  bytes.writeBigUInt64BE(BigInt(value));
+  return bytes;
+}
+
+function closePayload(code: number, reason = ''): Buffer {
+  const payload = Buffer.alloc(2 + Buffer.byteLength(reason));
+  payload.writeUInt16BE(code);
+  payload.write(reason, 2);
+  return payload;
+}
+
+// Writes a response the app gave instead of accepting the upgrade, then hangs up
+async function refuse(socket: Duplex, response: Response): Promise<void> {
+  const body = Buffer.from(await response.arrayBuffer());
+  const headers = [...response.headers]
+    .filter(([name]) => name !== 'content-length' && name !== 'transfer-encoding')
+    .map(([name, value]) => `${name}: ${value}\r\n`)
+    .join('');
+  socket.write(
+    `HTTP/1.1 ${response.status} ${STATUS_CODES[response.status] ?? ''}\r\n${headers}` +
+      `Content-Length: ${body.length}\r\nConnection: close\r\n\r\n`
+  );
+  socket.end(body);
+}
+
+function toRequest(incoming: IncomingMessage): Request {
+  const headers = new Headers();
+  for (let i = 0; i < incoming.rawHeaders.length; i += 2) {
+    headers.append(incoming.rawHeaders[i]!, incoming.rawHeaders[i + 1]!);
+  }
+  return new Request(new URL(incoming.url ?? '/', `http://${incoming.headers.host ?? 'localhost'}`), { headers });
+}
+
+/**
+ * Runs an accepted connection: reads the client's frames, hands messages to
+ * the route's events and writes what the route sends back.
+ */
+function serve(socket: Duplex, head: Buffer, events: WSEvents<Duplex>, url: string): void {
+  let readyState: WSReadyState = 1;
+  let buffered = head;
+  let fragments: Buffer[] = [];
+  let fragmentBytes = 0;
+  let fragmentOpcode = 0;
+
+  const write = (opcode: number, payload: Buffer) => {
+    if (readyState === 1 || (opcode === OPCODE.close && readyState === 2)) {
+      socket.write(frame(opcode, payload));
+    }
+  };
+
+  const close = (code: number = CLOSE.normal, reason?: string) => {
+    if (readyState !== 1) {
+      return;
+    }
+    readyState = 2;
+    socket.write(frame(OPCODE.close, closePayload(code, reason)));
+    // The client answers with its own close frame; don't wait forever for it
+    setTimeout(() => socket.destroy(), 1000).unref();
+  };
+
+  const ws = new WSContext<Duplex>({
+    send: data =>
+      typeof data === 'string'
+        ? write(OPCODE.text, Buffer.from(data))
+        : write(OPCODE.binary, Buffer.from(data instanceof ArrayBuffer ? new Uint8Array(data) : data)),
+    close,
+    raw: socket,
+    get readyState() {
+      return readyState;
+    },
+    url,
+    protocol: null,
+  });
+
+  let closeEvent = { code: 1006, reason: '' };
+  const message = (opcode: number, payload: Buffer) => {
+    const data = opcode === OPCODE.text ? payload.toString('utf8') : new Uint8Array(payload).buffer;
+    events.onMessage?.(new MessageEvent('message', { data }), ws);
+  };
+
+  const onFrame = (fin: boolean, opcode: number, payload: Buffer) => {
+    switch (opcode) {
+      case OPCODE.ping:
+        write(OPCODE.pong, payload);
+        return;
+      case OPCODE.pong:
+        return;
+      case OPCODE.close: {
+        const code = payload.length >= 2 ? payload.readUInt16BE(0) : 1005;
+        closeEvent = { code, reason: payload.subarray(2).toString('utf8') };
+        if (readyState === 1) {
+          readyState = 2;
+          write(OPCODE.close, payload.subarray(0, 2));
+        }
+        socket.end();
+        return;
+      }
+      case OPCODE.text:
+      case OPCODE.binary:
+      case OPCODE.continuation: {
+        if ((opcode === OPCODE.continuation) !== (fragments.length > 0)) {
+          close(CLOSE.protocolError, 'Unexpected continuation');
+          return;
+        }
+        if (opcode !== OPCODE.continuation) {
+          fragmentOpcode = opcode;
+        }
+        fragments.push(payload);
+        fragmentBytes += payload.length;
+        if (fragmentBytes > MAX_MESSAGE_BYTES) {
+          close(CLOSE.tooBig, 'Message too big');
+          return;
+        }
+        if (fin) {
+          const whole = Buffer.concat(fragments);
+          fragments = [];
+          fragmentBytes = 0;
+          message(fragmentOpcode, whole);
+        }
+        return;
+      }
+      default:
+        close(CLOSE.protocolError, 'Unknown opcode');
+    }
+  };
+
+  // Takes as many whole frames off the front of the buffer as there are
+  const parse = () => {
+    while (buffered.length >= 2 && readyState !== 3) {
+      const fin = (buffered[0]! & 0x80) !== 0;
+      const opcode = buffered[0]! & 0x0f;
+      const masked = (buffered[1]! & 0x80) !== 0;
+      let length = buffered[1]! & 0x7f;
+      let offset = 2;
+      if (length === 126) {
+        if (buffered.length < 4) return;
+        length = buffered.readUInt16BE(2);
+        offset = 4;
+      } else if (length === 127) {
+        if (buffered.length < 10) return;
+        length = Number(buffered.readBigUInt64BE(2));
+        offset = 10;
+      }
+      // Clients must mask every frame
+      if (!masked) {
+        close(CLOSE.protocolError, 'Frames must be masked');
+        return;
+      }
+      if (length > MAX_MESSAGE_BYTES) {
+        close(CLOSE.tooBig, 'Message too big');
+        return;
+      }
+      if (buffered.length < offset + 4 + length) return;
+
+      const mask = buffered.subarray(offset, offset + 4);
+      const payload = Buffer.from(buffered.subarray(offset + 4, offset + 4 + length));
+      for (let i = 0; i < payload.length; i++) {
+        payload[i]! ^= mask[i % 4]!;
+      }
+      buffered = buffered.subarray(offset + 4 + length);
+      onFrame(fin, opcode, payload);
+    }
+  };
+
+  socket.on('data', (chunk: Buffer) => {
+    buffered = buffered.length > 0 ? Buffer.concat([buffered, chunk]) : chunk;
+    parse();
+  });
+  socket.on('error', () => events.onError?.(new Event('error'), ws));
+  socket.once('close', () => {
+    readyState = 3;
+    events.onClose?.(Object.assign(new Event('close'), { ...closeEvent, wasClean: closeEvent.code !== 1006 }) as CloseEvent, ws);
+  });
+
+  events.onOpen?.(new Event('open'), ws);
+  parse();
+}
+
+export function createNodeWebSocket(): NodeWebSocket {
+  // Events left by routes that accepted an upgrade, until its handshake
+  const accepted = new WeakMap<IncomingMessage, WSEvents<Duplex>>();
+
+  const upgradeWebSocket: UpgradeWebSocket<Duplex> = createEvents => async (c, next) => {
+    const incoming = (c.env as { incoming?: IncomingMessage } | undefined)?.incoming;
+    if (c.req.header('upgrade')?.toLowerCase() !== 'websocket' || !incoming) {
+      await next();
+      return;
+    }
+    accepted.set(incoming, await createEvents(c));
+    // Never sent; the handshake answers the request instead
+    return new Response(null);
+  };
+
+  const upgradeListener = (fetch: Fetch): UpgradeListener => (incoming, socket, head) => {
+    socket.on('error', () => socket.destroy());
+    void (async () => {
+      const response = await fetch(toRequest(incoming), { incoming });
+      const events = accepted.get(incoming);
+      accepted.delete(incoming);
+      const key = incoming.headers['sec-websocket-key'];
+      if (!events || !response.ok) {
+        await refuse(socket, response);
+        return;
+      }
+      if (!key || incoming.headers['sec-websocket-version'] !== '13') {
+        await refuse(socket, new Response('Expected a version 13 WebSocket handshake', { status: 400 }));
+        return;
+      }
+
+      const accept = createHash('sha1').update(key + HANDSHAKE_GUID).digest('base64');
+      socket.write(
+        'HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n' +
+          `Sec-WebSocket-Accept: ${accept}\r\n\r\n`
+      );
+      serve(socket, head, events, incoming.url ?? '/');
+    })().catch(() => socket.destroy());
+  };
+
+  return { upgradeWebSocket, upgradeListener };
+}
@@ -0,0 +1,184 @@
+import { describe, it, expect } from "vitest";
+import { createApp } from "../app.js";
+import { parseChatRequest, parseGenerateRequest } from "./ollama.js";
+
+const testAPIKey = "tt-ollama-key";
+const app = createApp({ auth: { apiKey: testAPIKey } });
+
+function post(path: string, body: unknown) {
+  return app.request(path, {
+    method: "POST",
+    headers: { Authorization: `Bearer ${testAPIKey}`, "Content-Type": "application/json" },
+    body: JSON.stringify(body),
+  });
+}
+
+async function lines(res: Response): Promise<any[]> {
+  const text = await res.text();
+  expect(text.endsWith("\n")).toBe(true);
+  return text.trimEnd().split("\n").map((line) => JSON.parse(line));
+}
+
+describe("parseChatRequest", () => {
+  it("should stream unless told not to", () => {
+    const messages = [{ role: "user", content: "Hi" }];
+
+    expect(parseChatRequest({ model: "echo", messages }).stream).toBe(true);
+    expect(parseChatRequest({ model: "echo", messages, stream: false }).stream).toBe(false);
+  });
+
+  it("should map options and format onto chat completion parameters", () => {
+    const request = parseChatRequest({
+      model: "echo",
+      messages: [{ role: "user", content: "Hi" }],
+      format: "json",
+      options: { temperature: 0.2, top_p: 0.9, seed: 7, num_predict: 16, stop: ["END"] },
+    });
+
+    expect(request).toMatchObject({
+      temperature: 0.2,
+      top_p: 0.9,
+      seed: 7,
+      max_tokens: 16,
+      stop: ["END"],
+      response_format: { type: "json_object" },
+    });
+  });
+
+  it("should treat a negative num_predict as no limit", () => {
+    const request = parseChatRequest({
+      model: "echo",
+      messages: [{ role: "user", content: "Hi" }],
+      options: { num_predict: -1 },
+    });
+
+    expect(request.max_tokens).toBeUndefined();
+  });
+
+  it("should turn images into image parts", () => {
+    const request = parseChatRequest({
+      model: "echo",
+      messages: [{ role: "user", content: "What's this?", images: ["iVBORw0KGgo="] }],
+    });
+
+    expect(request.messages[0]!.content).toEqual([
+      { type: "text", text: "What's this?" },
+      { type: "image_url", image_url: { url: "data:image/png;base64,iVBORw0KGgo=" } },
+    ]);
+  });
+
+  it("should reject requests without messages or with unknown roles", () => {
+    expect(() => parseChatRequest({ model: "echo" })).toThrow("messages");
+    expect(() => parseChatRequest({ model: "echo", messages: [{ role: "robot", content: "Hi" }] })).toThrow("role");
+  });
+});
+
+describe("parseGenerateRequest", () => {
+  it("should send the prompt as the user message, after any system prompt", () => {
+    const request = parseGenerateRequest({ model: "echo", prompt: "Hello", system: "Be brief" });
+
+    expect(request.messages).toEqual([
+      { role: "system", content: "Be brief" },
+      { role: "user", content: "Hello" },
+    ]);
+  });
+
+  it("should require a prompt", () => {
+    expect(() => parseGenerateRequest({ model: "echo" })).toThrow("prompt");
+  });
+});
+
+describe("/api/chat", () => {
+  it("should stream NDJSON ending with done", async () => {
+    const res = await post("/api/chat", { model: "echo", messages: [{ role: "user", content: "Hello there" }] });
+
+    expect(res.status).toBe(200);
+    expect(res.headers.get("content-type")).toContain("application/x-ndjson");
+    const chunks = await lines(res);
+    const last = chunks.at(-1);
+    expect(chunks.slice(0, -1).every((chunk) => chunk.done === false)).toBe(true);
+    expect(chunks.map((chunk) => chunk.message.content).join("")).toBe("Hello there");
+    expect(last).toMatchObject({ model: "echo", done: true, done_reason: "stop", message: { role: "assistant", content: "" } });
+    expect(last.eval_count).toBeGreaterThan(0);
+  });
+
+  it("should answer with one object when not streaming", async () => {
+    const res = await post("/api/chat", {
+      model: "echo",
+      stream: false,
+      messages: [{ role: "user", content: "Hello there" }],
+    });
+
+    expect(res.status).toBe(200);
+    const data = await res.json();
+    expect(data).toMatchObject({ model: "echo", done: true, message: { role: "assistant", content: "Hello there" } });
+    expect(new Date(data.created_at).toString()).not.toBe("Invalid Date");
+  });
+
+  it("should report replies cut short by num_predict", async () => {
+    const res = await post("/api/chat", {
+      model: "echo",
+      stream: false,
+      messages: [{ role: "user", content: "one two three four five" }],
+      options: { num_predict: 2 },
+    });
+
+    expect((await res.json()).done_reason).toBe("length");
+  });
+
+  it("should return tool calls with their arguments as objects", async () => {
+    const res = await post("/api/chat", {
+      model: "tooluse",
+      stream: false,
+      messages: [{ role: "user", content: "Weather?" }],
+      tools: [
+        {
+          type: "function",
+          function: {
+            name: "get_weather",
+            parameters: { type: "object", properties: { city: { type: "string" } }, required: ["city"] },
+          },
+        },
+      ],
+    });
+
+    const call = (await res.json()).message.tool_calls[0];
+    expect(call.function.name).toBe("get_weather");
+    expect(typeof call.function.arguments.city).toBe("string");
+  });
+
+  it("should send errors as Ollama does", async () => {
+    const res = await post("/api/chat", { model: "no-such-model", messages: [{ role: "user", content: "Hi" }] });
+
+    expect(res.status).toBe(404);
+    const data = await res.json();
+    expect(typeof data.error).toBe("string");
+    expect(data.error).toContain("no-such-model");
+  });
+
+  it("should need an API key", async () => {
+    const res = await app.request("/api/chat", {
+      method: "POST",
+      body: JSON.stringify({ model: "echo", messages: [{ role: "user", content: "Hi" }] }),
+    });
+
+    expect(res.status).toBe(401);
+    expect(typeof (await res.json()).error).toBe("string");
+  });
+});
+
+describe("/api/generate", () => {
+  it("should stream the reply in response fields", async () => {
+    const res = await post("/api/generate", { model: "echo", prompt: "Generate this" });
+
+    const chunks = await lines(res);
+    expect(chunks.map((chunk) => chunk.response).join("")).toBe("Generate this");
+    expect(chunks.at(-1)).toMatchObject({ done: true, done_reason: "stop", response: "" });
+  });
+
+  it("should answer with one object when not streaming", async () => {
+    const res = await post("/api/generate", { model: "echo", prompt: "Generate this", stream: false });
+
+    expect(await res.json()).toMatchObject({ model: "echo", response: "Generate this", done: true });
+  });
+});
@@ -0,0 +1,333 @@
+// Ollama's native API, for local-first tools that speak nothing else
+//
+// /api/chat and /api/generate requests are translated into chat completion
+// requests and answered by the same adapters as /v1, so every model, alias
+// and directive works the same. Replies go back as Ollama's objects: one per
+// line (NDJSON) while streaming, which is the default, ending with a line
+// that has "done": true and the usage counts.
+
+import { InvalidRequestError } from '../openai-protocol/errors.js';
+import type {
+  ChatCompletionContentPart,
+  ChatCompletionFinishReason,
+  ChatCompletionMessageToolCall,
+  ChatCompletionRequest,
+  ChatCompletionRequestMessage,
+  ChatCompletionResponse,
+  ChatCompletionStreamResponse,
+  ChatCompletionTool,
+  ChatCompletionUsage,
+} from '../openai-protocol/types.js';
+import {
+  validateResponseFormat,
+  validateSamplingParameters,
+  validateTools,
+} from '../openai-protocol/validation.js';
+
+// Requests under this path get Ollama's error shape, {"error": "..."}
+export const OLLAMA_PATH_PREFIX = '/api/';
+
+export const OLLAMA_CONTENT_TYPE = 'application/x-ndjson';
+
+export type OllamaEndpoint = 'chat' | 'generate';
+
+// The subset of Ollama's runtime options that mean something here
+export interface OllamaOptions {
+  temperature?: number;
+  top_p?: number;
+  seed?: number;
+  // Most tokens to generate; -1 means no limit
+  num_predict?: number;
+  stop?: string[];
+}
+
+export interface OllamaToolCall {
+  function: { name: string; arguments: Record<string, unknown> };
+}
+
+export interface OllamaMessage {
+  role: 'system' | 'user' | 'assistant' | 'tool';
+  content: string;
+  // Base64-encoded images, without a data: prefix
+  images?: string[];
+  tool_calls?: OllamaToolCall[];
+}
+
+// "json" for any JSON object, or a JSON schema the reply must satisfy
+export type OllamaFormat = 'json' | Record<string, unknown>;
+
+export interface OllamaChatRequest {
+  model: string;
+  messages: OllamaMessage[];
+  stream?: boolean;
+  format?: OllamaFormat;
+  options?: OllamaOptions;
+  tools?: ChatCompletionTool[];
+}
+
+export interface OllamaGenerateRequest {
+  model: string;
+  prompt: string;
+  system?: string;
+  images?: string[];
+  stream?: boolean;
+  format?: OllamaFormat;
+  options?: OllamaOptions;
+}
+
+const ROLES = ['system', 'user', 'assistant', 'tool'];
+
+function isObject(value: unknown): value is Record<string, any> {
+  return typeof value === 'object' && value !== null && !Array.isArray(value);
+}
+
+function withImages(text: string, images: unknown, field: string): string | ChatCompletionContentPart[] {
+  if (images === undefined) {
+    return text;
+  }
+  if (!Array.isArray(images) || !images.every(image => typeof image === 'string')) {
+    throw new InvalidRequestError(`Invalid '${field}': expected an array of base64-encoded images`, field);
+  }
+  return [
+    { type: 'text', text },
+    ...images.map((image): ChatCompletionContentPart => ({
+      type: 'image_url',
+      image_url: { url: `data:image/png;base64,${image}` },
+    })),
+  ];
+}
+
+function toToolCalls(calls: unknown, index: number): ChatCompletionMessageToolCall[] {
+  if (!Array.isArray(calls)) {
+    throw new InvalidRequestError(`Invalid message at index ${index}: 'tool_calls' must be an array`, 'messages');
+  }
+  return calls.map((call, j): ChatCompletionMessageToolCall => {
+    if (!isObject(call) || typeof call.function?.name !== 'string') {
+      throw new InvalidRequestError(
+        `Invalid tool call at messages[${index}].tool_calls[${j}]: expected a function name`,
+        'messages'
+      );
+    }
+    return {
+      id: `call_${index}_${j}`,
+      type: 'function',
+      function: { name: call.function.name, arguments: JSON.stringify(call.function.arguments ?? {}) },
+    };
+  });
+}
+
+function toMessage(message: unknown, index: number): ChatCompletionRequestMessage {
+  if (!isObject(message)) {
+    throw new InvalidRequestError(`Invalid message at index ${index}: must be an object`, 'messages');
+  }
+  if (!ROLES.includes(message.role)) {
+    throw new InvalidRequestError(
+      `Invalid message at index ${index}: 'role' must be one of 'system', 'user', 'assistant', or 'tool'`,
+      'messages'
+    );
+  }
+  // Assistant messages that only call tools often leave content out
+  const content = message.content ?? (message.tool_calls ? '' : undefined);
+  if (typeof content !== 'string') {
+    throw new InvalidRequestError(`Invalid message at index ${index}: 'content' must be a string`, 'messages');
+  }
+
+  const converted: ChatCompletionRequestMessage = {
+    role: message.role,
+    content: withImages(content, message.images, `messages[${index}].images`),
+  };
+  if (message.tool_calls !== undefined) {
+    converted.tool_calls = toToolCalls(message.tool_calls, index);
+  }
+  if (message.role === 'tool') {
+    // Ollama answers tool calls by name rather than by id
+    converted.tool_call_id = typeof message.tool_name === 'string' ? message.tool_name : `call_${index}`;
+  }
+  return converted;
+}
+
+// Copies Ollama's options and format onto the equivalent chat completion parameters
+function applyOptions(request: ChatCompletionRequest, body: Record<string, any>): void {
+  const { options, format } = body;
+  if (options !== undefined) {
+    if (!isObject(options)) {
+      throw new InvalidRequestError("Invalid 'options': expected an object", 'options');
+    }
+    if (options.temperature !== undefined) request.temperature = options.temperature;
+    if (options.top_p !== undefined) request.top_p = options.top_p;
+    if (options.seed !== undefined) request.seed = options.seed;
+    if (options.stop !== undefined) request.stop = options.stop;
+    if (options.num_predict !== undefined) {
+      if (typeof options.num_predict !== 'number' || !Number.isInteger(options.num_predict)) {
+        throw new InvalidRequestError("Invalid 'options.num_predict': expected an integer", 'options.num_predict');
+      }
+      if (options.num_predict >= 0) request.max_tokens = options.num_predict;
+    }
+  }
+
+  if (format === 'json') {
+    request.response_format = { type: 'json_object' };
+  } else if (isObject(format)) {
+    request.response_format = { type: 'json_schema', json_schema: { name: 'format', schema: format } };
+  } else if (format !== undefined && format !== '') {
+    throw new InvalidRequestError("Invalid 'format': expected \"json\" or a JSON schema", 'format');
+  }
+
+  const parameters = request as unknown as Record<string, unknown>;
+  validateSamplingParameters(parameters);
+  validateTools(parameters);
+  validateResponseFormat(parameters);
+}
+
+function requireModel(body: Record<string, any>): string {
+  if (typeof body.model !== 'string' || body.model === '') {
+    throw new InvalidRequestError('Missing required parameter: model', 'model');
+  }
+  return body.model;
+}
+
+function requireObject(body: unknown): Record<string, any> {
+  if (!isObject(body)) {
+    throw new InvalidRequestError('Request body must be a JSON object');
+  }
+  return body;
+}
+
+/**
+ * Translates an /api/chat request body. Ollama streams unless told not to,
+ * so stream is only false when the body says so.
+ */
+export function parseChatRequest(body: unknown): ChatCompletionRequest {
+  const chat = requireObject(body);
+  const model = requireModel(chat);
+  if (!Array.isArray(chat.messages) || chat.messages.length === 0) {
+    throw new InvalidRequestError('Missing required parameter: messages', 'messages');
+  }
+
+  const request: ChatCompletionRequest = {
+    model,
+    messages: chat.messages.map(toMessage),
+    stream: chat.stream !== false,
+  };
+  if (chat.tools !== undefined) request.tools = chat.tools;
+  applyOptions(request, chat);
+  return request;
+}
+
+// Translates an /api/generate request body, whose prompt becomes the user message
+export function parseGenerateRequest(body: unknown): ChatCompletionRequest {
+  const generate = requireObject(body);
+  const model = requireModel(generate);
+  if (typeof generate.prompt !== 'string') {
+    throw new InvalidRequestError('Missing required parameter: prompt', 'prompt');
+  }
+  if (generate.system !== undefined && typeof generate.system !== 'string') {
+    throw new InvalidRequestError("Invalid 'system': expected a string", 'system');
+  }
+
+  const messages: ChatCompletionRequestMessage[] = [];
+  if (generate.system) {
+    messages.push({ role: 'system', content: generate.system });
+  }
+  messages.push({ role: 'user', content: withImages(generate.prompt, generate.images, 'images') });
+
+  const request: ChatCompletionRequest = { model, messages, stream: generate.stream !== false };
+  applyOptions(request, generate);
+  return request;
+}
+
+// The text of a reply, where each endpoint puts it
+function reply(endpoint: OllamaEndpoint, content: string, toolCalls?: OllamaToolCall[]): Record<string, unknown> {
+  if (endpoint === 'generate') {
+    return { response: content };
+  }
+  return { message: { role: 'assistant', content, ...(toolCalls?.length ? { tool_calls: toolCalls } : {}) } };
+}
+
+// Ollama only tells a finished reply apart from one cut short
+function doneReason(finishReason: ChatCompletionFinishReason | null | undefined): string {
+  return finishReason === 'length' ? 'length' : 'stop';
+}
+
+// The closing fields of a reply: why it ended, token counts and nanosecond timings
+function summary(
+  finishReason: ChatCompletionFinishReason | null | undefined,
+  usage: ChatCompletionUsage | undefined,
+  startedAt: number
+): Record<string, unknown> {
+  const elapsed = Math.round((Date.now() - startedAt) * 1e6);
+  return {
+    done: true,
+    done_reason: doneReason(finishReason),
+    total_duration: elapsed,
+    load_duration: 0,
+    prompt_eval_count: usage?.prompt_tokens ?? 0,
+    prompt_eval_duration: 0,
+    eval_count: usage?.completion_tokens ?? 0,
+    eval_duration: elapsed,
+  };
+}
+
+function ollamaToolCalls(calls: ChatCompletionMessageToolCall[]): OllamaToolCall[] {
+  return calls.map(call => ({
+    function: { name: call.function.name, arguments: JSON.parse(call.function.arguments || '{}') },
+  }));
+}
+
+// A whole reply, for requests with "stream": false
+export function ollamaResponse(
+  endpoint: OllamaEndpoint,
+  model: string,
+  response: ChatCompletionResponse,
+  startedAt: number
+): Record<string, unknown> {
+  const choice = response.choices[0];
+  const toolCalls = choice?.message.tool_calls ? ollamaToolCalls(choice.message.tool_calls) : undefined;
+  return {
+    model,
+    created_at: new Date().toISOString(),
+    ...reply(endpoint, choice?.message.content ?? '', toolCalls),
+    ...summary(choice?.finish_reason, response.usage, startedAt),
+  };
+}
+
+/**
+ * Rewrites a chat completion stream as Ollama's stream objects. Tool calls
+ * arrive in fragments, so they're gathered and sent together before the end.
+ */
+export async function* ollamaStream(
+  endpoint: OllamaEndpoint,
+  model: string,
+  chunks: AsyncIterable<ChatCompletionStreamResponse>,
+  startedAt: number
+): AsyncGenerator<Record<string, unknown>> {
+  const toolCalls: ChatCompletionMessageToolCall[] = [];
+  let finishReason: ChatCompletionFinishReason | null | undefined;
+  let usage: ChatCompletionUsage | undefined;
+
+  for await (const chunk of chunks) {
+    const choice = chunk.choices[0];
+    usage = chunk.usage ?? usage;
+    finishReason = choice?.finish_reason ?? finishReason;
+
+    for (const fragment of choice?.delta.tool_calls ?? []) {
+      const call = (toolCalls[fragment.index] ??= {
+        id: fragment.id ?? '',
+        type: 'function',
+        function: { name: '', arguments: '' },
+      });
+      call.function.name += fragment.function.name ?? '';
+      call.function.arguments += fragment.function.arguments ?? '';
+    }
+
+    const content = choice?.delta.content;
+    if (content) {
+      yield { model, created_at: new Date().toISOString(), ...reply(endpoint, content), done: false };
+    }
+  }
+
+  if (toolCalls.length > 0) {
+    yield { model, created_at: new Date().toISOString(), ...reply(endpoint, '', ollamaToolCalls(toolCalls)), done: false };
+  }
+  yield { model, created_at: new Date().toISOString(), ...reply(endpoint, ''), ...summary(finishReason, usage, startedAt) };
+}
@@ -2,29 +2,219 @@ import type {
   ChatCompletionRequest,
   ChatCompletionResponse,
   ChatCompletionStreamResponse,
-  ChatCompletionMessage,
+  ChatCompletionRequestMessage,
+  ChatCompletionTextContentPart,
+  ChatCompletionMessageToolCall,
+  ChatCompletionUsage,
 } from './types.js';
 import {
   generateChatCompletionId,
   getCurrentTimestamp,
 } from './types.js';
 import { Model } from '../models/model.js';
+import type { GenerationOptions } from '../models/model.js';
+import { sleep } from '../utils/sleep.js';
+import { directiveError, parseDirectives, splitIntoChunks } from './directives.js';
+import type { Directives, FinishReason } from './directives.js';
+import { OutputLimiter, normalizeStop } from './output-limits.js';
+import { chooseFault, raiseFault } from './faults.js';
+import type { FaultConfig, StreamFault } from './faults.js';
+import { planToolCalls, toolCallDeltas } from './tool-calls.js';
+import { checkReasoningParameters, reasoningFor } from './reasoning.js';
+import { rechunk } from './chunking.js';
+import type { Chunking } from './chunking.js';
+import { WhitespaceTokenizer, countChatTokens } from '../tokenizer/tokenizer.js';
+import type { Tokenizer } from '../tokenizer/tokenizer.js';
+
+export interface AdapterOptions {
+  // Honor !directives in user messages (see directives.ts)
+  directives?: boolean;
+  // Inject random faults into this share of requests (see faults.ts)
+  faults?: FaultConfig;
+  // Call the offered tools instead of answering (see tool-calls.ts)
+  toolCalls?: boolean;
+  // Reason before answering and refuse unsupported parameters, like o1 (see reasoning.ts)
+  reasoning?: boolean;
+  // Remember conversations server-side, keyed by user or x-teenytiny-conversation (see app.ts)
+  memory?: boolean;
+}
+
+// What the model reported about its output, alongside the output itself
+interface ModelOutcome {
+  finishReason?: FinishReason;
+}
 
 export class OpenAIAdapter {
-  constructor(private model: Model, private modelId: string) {}
+  constructor(
+    private model: Model,
+    private modelId: string,
+    private options: AdapterOptions = {},
+    private tokenizer: Tokenizer = new WhitespaceTokenizer()
+  ) {}
+
+  get remembers(): boolean {
+    return this.options.memory === true;
+  }
+
+  // Rejects a request up front, so errors are sent with their HTTP status
+  // rather than inside an already started stream. Returns any fault that
+  // should instead break the response partway through.
+  preflight(request: ChatCompletionRequest): StreamFault | undefined {
+    if (this.options.reasoning) {
+      checkReasoningParameters(request);
+    }
+    const { directives } = this.prepare(request);
+
+    const fault = directives.fault ?? (this.options.faults && chooseFault(this.options.faults));
+    return fault === undefined || fault === 'none' ? undefined : raiseFault(fault);
+  }
+
+  async complete(request: ChatCompletionRequest, signal?: AbortSignal): Promise<ChatCompletionResponse> {
+    const { input, directives } = this.prepare(request);
+    const toolCalls = this.planToolCalls(request);
+    if (toolCalls.length > 0) {
+      return this.toolCallResponse(request, toolCalls);
+    }
+    const choices: ChatCompletionResponse['choices'] = [];
+    let completionTokens = 0;
+    const reasoning = this.reasoning(input, request);
+    let reasoningTokens = 0;
+    for (let index = 0; index < (request.n ?? 1); index++) {
+      const limiter = this.createLimiter(request);
+      const outcome: ModelOutcome = {};
 
-  async complete(request: ChatCompletionRequest): Promise<ChatCompletionResponse> {
-    const input = this.extractTextFromMessages(request.messages);
-    
-    // Collect all chunks from the streaming model
-    const chunks: string[] = [];
-    for await (const chunk of this.model.process(input)) {
-      chunks.push(chunk);
+      // Collect all chunks from the streaming model
+      const chunks: string[] = [];
+      for await (const chunk of this.generate(input, request, directives, limiter, outcome, signal)) {
+        if (signal?.aborted) break;
+        chunks.push(chunk);
+      }
+
+      const responseContent = chunks.join('').trim();
+      completionTokens += directives.completionTokens ?? this.tokenizer.count(responseContent);
+      choices.push({
+        index,
+        message: {
+          role: 'assistant',
+          content: responseContent,
+          ...(reasoning !== undefined ? { reasoning_content: reasoning } : {}),
+        },
+        finish_reason: directives.finishReason ?? limiter.finishReason ?? outcome.finishReason ?? 'stop',
+      });
+      if (reasoning !== undefined) {
+        reasoningTokens += this.tokenizer.count(reasoning);
+      }
     }
-    
-    const responseContent = chunks.join('').trim();
-    const promptTokens = this.estimateTokens(input);
-    const completionTokens = this.estimateTokens(responseContent);
+    completionTokens += reasoningTokens;
+    const promptTokens = countChatTokens(this.tokenizer, request.messages);
+
+    return {
+      id: generateChatCompletionId(),
+      object: 'chat.completion',
+      created: getCurrentTimestamp(),
+      model: this.modelId,
+      choices,
+      usage: this.usage(promptTokens, completionTokens, reasoning !== undefined ? reasoningTokens : undefined),
+    };
+  }
+
+  async *completeStream(
+    request: ChatCompletionRequest,
+    signal?: AbortSignal,
+    chunking: Chunking = { type: 'model' }
+  ): AsyncIterable<ChatCompletionStreamResponse> {
+    const { input, directives } = this.prepare(request);
+    const toolCalls = this.planToolCalls(request);
+    if (toolCalls.length > 0) {
+      yield* this.toolCallStream(request, toolCalls);
+      return;
+    }
+    const id = generateChatCompletionId();
+    const created = getCurrentTimestamp();
+    const chunk = (choice: ChatCompletionStreamResponse['choices'][number]): ChatCompletionStreamResponse => ({
+      id,
+      object: 'chat.completion.chunk',
+      created,
+      model: this.modelId,
+      choices: [choice],
+    });
+
+    // With n > 1, each choice is streamed in full before the next begins
+    const n = request.n ?? 1;
+    let completionTokens = 0;
+    const reasoning = this.reasoning(input, request);
+    let reasoningTokens = 0;
+    for (let index = 0; index < n; index++) {
+      const limiter = this.createLimiter(request);
+      const outcome: ModelOutcome = {};
+
+      // Send initial chunk with role
+      yield chunk({ index, delta: { role: 'assistant' } });
+
+      // Reasoning streams ahead of the answer, a sentence at a time
+      if (reasoning !== undefined) {
+        for (const sentence of reasoning.match(/[^.]+\.\s*/g) ?? [reasoning]) {
+          yield chunk({ index, delta: { reasoning_content: sentence } });
+        }
+        reasoningTokens += this.tokenizer.count(reasoning);
+      }
+
+      // Stream content chunks
+      let totalContent = '';
+      const output = this.generate(input, request, directives, limiter, outcome, signal);
+      for await (const content of rechunk(output, chunking, this.tokenizer)) {
+        // Client went away - stop generating, there is nobody to send the final chunk to
+        if (signal?.aborted) return;
+        totalContent += content;
+
+        yield chunk({ index, delta: { content } });
+      }
+
+      completionTokens += directives.completionTokens ?? this.tokenizer.count(totalContent.trim());
+      const finish = chunk({
+        index,
+        delta: {},
+        finish_reason: directives.finishReason ?? limiter.finishReason ?? outcome.finishReason ?? 'stop',
+      });
+      if (index < n - 1) {
+        yield finish;
+        continue;
+      }
+
+      // The last choice's final chunk carries the usage for all of them
+      const promptTokens = countChatTokens(this.tokenizer, request.messages);
+      yield {
+        ...finish,
+        usage: this.usage(
+          promptTokens,
+          completionTokens + reasoningTokens,
+          reasoning !== undefined ? reasoningTokens : undefined
+        ),
+      };
+    }
+  }
+
+  // What a reasoning model thinks before answering, undefined for other models
+  private reasoning(input: string, request: ChatCompletionRequest): string | undefined {
+    return this.options.reasoning ? reasoningFor(input, request.reasoning_effort) : undefined;
+  }
+
+  private usage(promptTokens: number, completionTokens: number, reasoningTokens?: number): ChatCompletionUsage {
+    return {
+      prompt_tokens: promptTokens,
+      completion_tokens: completionTokens,
+      total_tokens: promptTokens + completionTokens,
+      ...(reasoningTokens !== undefined ? { completion_tokens_details: { reasoning_tokens: reasoningTokens } } : {}),
+    };
+  }
+
+  private planToolCalls(request: ChatCompletionRequest): ChatCompletionMessageToolCall[] {
+    return this.options.toolCalls ? planToolCalls(request) : [];
+  }
+
+  private toolCallResponse(request: ChatCompletionRequest, toolCalls: ChatCompletionMessageToolCall[]): ChatCompletionResponse {
+    const promptTokens = countChatTokens(this.tokenizer, request.messages);
+    const completionTokens = this.tokenizer.count(JSON.stringify(toolCalls));
 
     return {
       id: generateChatCompletionId(),
@@ -36,9 +226,10 @@ export class OpenAIAdapter {
           index: 0,
           message: {
             role: 'assistant',
-            content: responseContent,
+            content: null,
+            tool_calls: toolCalls,
           },
-          finish_reason: 'stop',
+          finish_reason: 'tool_calls',
         },
       ],
       usage: {
@@ -49,60 +240,26 @@ export class OpenAIAdapter {
     };
   }
 
-  async *completeStream(request: ChatCompletionRequest): AsyncIterable<ChatCompletionStreamResponse> {
-    const input = this.extractTextFromMessages(request.messages);
+  private *toolCallStream(request: ChatCompletionRequest, toolCalls: ChatCompletionMessageToolCall[]): Iterable<ChatCompletionStreamResponse> {
     const id = generateChatCompletionId();
     const created = getCurrentTimestamp();
-
-    // Send initial chunk with role
-    yield {
+    const chunk = (choice: ChatCompletionStreamResponse['choices'][number]): ChatCompletionStreamResponse => ({
       id,
       object: 'chat.completion.chunk',
       created,
       model: this.modelId,
-      choices: [
-        {
-          index: 0,
-          delta: { role: 'assistant' },
-        },
-      ],
-    };
+      choices: [choice],
+    });
 
-    // Stream content chunks
-    let totalContent = '';
-    for await (const chunk of this.model.process(input)) {
-      totalContent += chunk;
-      
-      yield {
-        id,
-        object: 'chat.completion.chunk',
-        created,
-        model: this.modelId,
-        choices: [
-          {
-            index: 0,
-            delta: { content: chunk },
-          },
-        ],
-      };
+    yield chunk({ index: 0, delta: { role: 'assistant' } });
+    for (const delta of toolCallDeltas(toolCalls)) {
+      yield chunk({ index: 0, delta: { tool_calls: [delta] } });
     }
 
-    // Send final chunk with finish reason and usage
-    const promptTokens = this.estimateTokens(input);
-    const completionTokens = this.estimateTokens(totalContent.trim());
-
+    const promptTokens = countChatTokens(this.tokenizer, request.messages);
+    const completionTokens = this.tokenizer.count(JSON.stringify(toolCalls));
     yield {
-      id,
-      object: 'chat.completion.chunk',
-      created,
-      model: this.modelId,
-      choices: [
-        {
-          index: 0,
-          delta: {},
-          finish_reason: 'stop',
-        },
-      ],
+      ...chunk({ index: 0, delta: {}, finish_reason: 'tool_calls' }),
       usage: {
         prompt_tokens: promptTokens,
         completion_tokens: completionTokens,
@@ -111,18 +268,127 @@ export class OpenAIAdapter {
     };
   }
 
-  private extractTextFromMessages(messages: ChatCompletionMessage[]): string {
-    // Find the last user message
+  private prepare(request: ChatCompletionRequest): { input: string; directives: Directives } {
+    const text = this.extractTextFromMessages(request.messages);
+    if (!this.options.directives) {
+      return { input: text, directives: {} };
+    }
+
+    const { text: input, directives } = parseDirectives(text);
+    if (directives.errorStatus !== undefined) {
+      throw directiveError(directives.errorStatus);
+    }
+    return { input, directives };
+  }
+
+  private createLimiter(request: ChatCompletionRequest): OutputLimiter {
+    const maxTokens = request.max_completion_tokens ?? request.max_tokens ?? undefined;
+    return new OutputLimiter(maxTokens, normalizeStop(request.stop), text => this.tokenizer.count(text));
+  }
+
+  // Runs the model, applying directives and then max_tokens and stop sequences to its output
+  private async *generate(
+    input: string,
+    request: ChatCompletionRequest,
+    directives: Directives,
+    limiter: OutputLimiter,
+    outcome: ModelOutcome,
+    signal?: AbortSignal
+  ): AsyncGenerator<string> {
+    for await (const chunk of this.shape(input, request, directives, outcome, signal)) {
+      const out = limiter.push(chunk);
+      if (out) yield out;
+      if (limiter.done) return;
+    }
+
+    const rest = limiter.flush();
+    if (rest) yield rest;
+  }
+
+  // Runs the model, applying any delay and chunking directives to its output
+  private async *shape(
+    input: string,
+    request: ChatCompletionRequest,
+    directives: Directives,
+    outcome: ModelOutcome,
+    signal?: AbortSignal
+  ): AsyncGenerator<string> {
+    const options: GenerationOptions = {
+      messages: request.messages.map(message => ({
+        role: message.role,
+        content: this.extractText(message.content),
+      })),
+      parameters: {
+        model: this.modelId,
+        temperature: request.temperature,
+        top_p: request.top_p,
+        max_tokens: request.max_completion_tokens ?? request.max_tokens,
+        seed: request.seed,
+        n: request.n,
+        user: request.user,
+      },
+      setFinishReason: reason => {
+        outcome.finishReason = reason;
+      },
+    };
+    const maxTokens = request.max_completion_tokens ?? request.max_tokens ?? undefined;
+    if (maxTokens !== undefined) options.maxTokens = maxTokens;
+    if (typeof request.seed === 'number') options.seed = request.seed;
+    if (request.metadata && typeof request.metadata === 'object') options.metadata = request.metadata;
+    const format = request.response_format;
+    if (format?.type === 'json_schema') {
+      options.responseFormat = { type: 'json_schema', schema: format.json_schema.schema ?? {} };
+    } else if (format) {
+      options.responseFormat = { type: format.type };
+    }
+
+    if (directives.delayMs) {
+      await sleep(directives.delayMs, signal);
+    }
+
+    if (directives.chunks === undefined) {
+      yield* this.model.process(input, signal, options);
+      return;
+    }
+
+    let output = '';
+    for await (const chunk of this.model.process(input, signal, options)) {
+      output += chunk;
+    }
+    yield* splitIntoChunks(output, directives.chunks);
+  }
+
+  private extractTextFromMessages(messages: ChatCompletionRequestMessage[]): string {
+    // A conversation ending in tool results is answered from those results
+    const toolResults: string[] = [];
+    for (let i = messages.length - 1; i >= 0 && messages[i]?.role === 'tool'; i--) {
+      toolResults.unshift(this.extractText(messages[i]!.content));
+    }
+    if (toolResults.length > 0) {
+      return toolResults.join('\n');
+    }
+
+    // Otherwise find the last user message
     for (let i = messages.length - 1; i >= 0; i--) {
       if (messages[i]?.role === 'user') {
-        return messages[i]!.content;
+        return this.extractText(messages[i]!.content);
       }
     }
     return '';
   }
 
-  private estimateTokens(text: string): number {
-    // Simple estimation: roughly 1 token per 4 characters
-    return Math.ceil(text.trim().length / 4);
+  private extractText(content: ChatCompletionRequestMessage['content']): string {
+    if (typeof content === 'string') {
+      return content;
+    }
+    if (content === null) {
+      return '';
+    }
+
+    // Multimodal message - models only understand text, so drop the images
+    return content
+      .filter((part): part is ChatCompletionTextContentPart => part.type === 'text')
+      .map(part => part.text)
+      .join('\n');
   }
 }
\ No newline at end of file
@@ -0,0 +1,239 @@
+import { describe, it, expect } from "vitest";
+import { OpenAIAdapter } from "./adapter.js";
+import { EchoModel } from "../models/echo-model.js";
+import { SlowModel } from "../models/slow-model.js";
+import { AssistantStore, paginate } from "./assistants.js";
+import type { MeterUsage, Run } from "./assistants.js";
+import { InvalidRequestError, ModelNotFoundError, NotFoundError } from "./errors.js";
+
+const owner = "tt-assistants-key";
+
+function store(meter?: MeterUsage) {
+  return new AssistantStore((_owner, model) => {
+    switch (model) {
+      case "echo":
+        return new OpenAIAdapter(new EchoModel(), "echo");
+      case "tooluse":
+        return new OpenAIAdapter(new EchoModel(), "tooluse", { toolCalls: true });
+      case "slow":
+        return new OpenAIAdapter(new SlowModel(50), "slow");
+      default:
+        throw new ModelNotFoundError(model);
+    }
+  }, meter);
+}
+
+const weather = {
+  type: "function",
+  function: {
+    name: "get_weather",
+    parameters: { type: "object", properties: { city: { type: "string" } }, required: ["city"] },
+  },
+};
+
+// Polls a run until it stops moving, as a client would
+async function settle(assistants: AssistantStore, run: Run): Promise<Run> {
+  for (;;) {
+    const current = assistants.getRun(owner, run.thread_id, run.id);
+    if (!["queued", "in_progress", "cancelling"].includes(current.status)) {
+      return current;
+    }
+    await new Promise((resolve) => setTimeout(resolve, 5));
+  }
+}
+
+function setup(model = "echo", tools: unknown[] = []) {
+  const assistants = store();
+  const assistant = assistants.createAssistant(owner, { model, name: "Helper", instructions: "Be helpful", tools });
+  const thread = assistants.createThread(owner, {
+    messages: [{ role: "user", content: "Hello assistant" }],
+  });
+  return { assistants, assistant, thread };
+}
+
+describe("AssistantStore", () => {
+  it("should answer a thread with a run", async () => {
+    const { assistants, assistant, thread } = setup();
+
+    const queued = assistants.createRun(owner, thread.id, { assistant_id: assistant.id });
+    expect(queued).toMatchObject({ status: "queued", model: "echo", instructions: "Be helpful", usage: null });
+    const done = await settle(assistants, queued);
+
+    expect(done.status).toBe("completed");
+    expect(done.usage!.total_tokens).toBeGreaterThan(0);
+    const messages = assistants.listMessages(owner, thread.id, {}).data;
+    expect(messages.map((message) => [message.role, message.content[0]!.text.value])).toEqual([
+      ["assistant", "Hello assistant"],
+      ["user", "Hello assistant"],
+    ]);
+    expect(messages[0]).toMatchObject({ assistant_id: assistant.id, run_id: done.id });
+
+    const steps = assistants.listRunSteps(owner, thread.id, done.id, {}).data;
+    expect(steps).toHaveLength(1);
+    expect(steps[0]).toMatchObject({
+      type: "message_creation",
+      status: "completed",
+      step_details: { message_creation: { message_id: messages[0]!.id } },
+    });
+  });
+
+  it("should report each run's usage to its owner", async () => {
+    const metered: [string, string, number][] = [];
+    const assistants = store((key, model, usage) => metered.push([key, model, usage.total_tokens]));
+    const assistant = assistants.createAssistant(owner, { model: "echo" });
+    const thread = assistants.createThread(owner, { messages: [{ role: "user", content: "Hello" }] });
+
+    const done = await settle(assistants, assistants.createRun(owner, thread.id, { assistant_id: assistant.id }));
+
+    expect(metered).toEqual([[owner, "echo", done.usage!.total_tokens]]);
+  });
+
+  it("should wait for tool outputs, then answer from them", async () => {
+    const { assistants, assistant, thread } = setup("tooluse", [weather]);
+
+    const waiting = await settle(assistants, assistants.createRun(owner, thread.id, { assistant_id: assistant.id }));
+    expect(waiting.status).toBe("requires_action");
+    const calls = waiting.required_action!.submit_tool_outputs.tool_calls;
+    expect(calls).toHaveLength(1);
+    expect(calls[0]!.function.name).toBe("get_weather");
+    expect(JSON.parse(calls[0]!.function.arguments)).toHaveProperty("city");
+
+    const resumed = assistants.submitToolOutputs(owner, thread.id, waiting.id, {
+      tool_outputs: [{ tool_call_id: calls[0]!.id, output: "Sunny, 21C" }],
+    });
+    expect(resumed.status).toBe("queued");
+    const done = await settle(assistants, resumed);
+
+    expect(done.status).toBe("completed");
+    const reply = assistants.listMessages(owner, thread.id, { limit: "1" }).data[0]!;
+    expect(reply.content[0]!.text.value).toBe("Sunny, 21C");
+    const steps = assistants.listRunSteps(owner, thread.id, done.id, { order: "asc" }).data;
+    expect(steps.map((step) => [step.type, step.status])).toEqual([
+      ["tool_calls", "completed"],
+      ["message_creation", "completed"],
+    ]);
+    expect(steps[0]!.step_details).toMatchObject({
+      tool_calls: [{ id: calls[0]!.id, function: { name: "get_weather", output: "Sunny, 21C" } }],
+    });
+  });
+
+  it("should insist on an output for every tool call", async () => {
+    const { assistants, assistant, thread } = setup("tooluse", [weather]);
+    const waiting = await settle(assistants, assistants.createRun(owner, thread.id, { assistant_id: assistant.id }));
+
+    expect(() => assistants.submitToolOutputs(owner, thread.id, waiting.id, { tool_outputs: [] })).toThrow(
+      InvalidRequestError,
+    );
+    expect(() =>
+      assistants.submitToolOutputs(owner, thread.id, waiting.id, {
+        tool_outputs: [{ tool_call_id: "call_unknown", output: "?" }],
+      }),
+    ).toThrow(InvalidRequestError);
+  });
+
+  it("should cancel a run in progress", async () => {
+    const { assistants, assistant } = setup("slow");
+    const thread = assistants.createThread(owner, {
+      messages: [{ role: "user", content: "one two three four five six seven eight" }],
+    });
+    const run = assistants.createRun(owner, thread.id, { assistant_id: assistant.id });
+
+    await new Promise((resolve) => setTimeout(resolve, 60));
+    expect(assistants.cancelRun(owner, thread.id, run.id).status).toBe("cancelling");
+    const done = await settle(assistants, run);
+
+    expect(done.status).toBe("cancelled");
+    expect(done.cancelled_at).not.toBeNull();
+    expect(() => assistants.cancelRun(owner, thread.id, run.id)).toThrow(InvalidRequestError);
+  });
+
+  it("should allow one active run per thread", async () => {
+    const { assistants, assistant, thread } = setup("tooluse", [weather]);
+    await settle(assistants, assistants.createRun(owner, thread.id, { assistant_id: assistant.id }));
+
+    expect(() => assistants.createRun(owner, thread.id, { assistant_id: assistant.id })).toThrow(InvalidRequestError);
+    expect(() => assistants.createMessage(owner, thread.id, { role: "user", content: "More" })).toThrow(
+      InvalidRequestError,
+    );
+  });
+
+  it("should mark runs cut short by max_completion_tokens as incomplete", async () => {
+    const { assistants, assistant } = setup();
+    const thread = assistants.createThread(owner, { messages: [{ role: "user", content: "one two three four" }] });
+
+    const done = await settle(
+      assistants,
+      assistants.createRun(owner, thread.id, { assistant_id: assistant.id, max_completion_tokens: 2 }),
+    );
+
+    expect(done).toMatchObject({ status: "incomplete", incomplete_details: { reason: "max_completion_tokens" } });
+  });
+
+  it("should create a thread and run it in one call", async () => {
+    const { assistants, assistant } = setup();
+
+    const run = assistants.createThreadAndRun(owner, {
+      assistant_id: assistant.id,
+      thread: { messages: [{ role: "user", content: [{ type: "text", text: "All at once" }] }] },
+    });
+    await settle(assistants, run);
+
+    const reply = assistants.listMessages(owner, run.thread_id, { run_id: run.id }).data;
+    expect(reply.map((message) => message.content[0]!.text.value)).toEqual(["All at once"]);
+  });
+
+  it("should update and delete assistants", () => {
+    const { assistants, assistant } = setup();
+
+    const updated = assistants.updateAssistant(owner, assistant.id, { name: "Renamed", metadata: { team: "qa" } });
+    expect(updated).toMatchObject({ name: "Renamed", instructions: "Be helpful", metadata: { team: "qa" } });
+
+    expect(assistants.deleteAssistant(owner, assistant.id)).toEqual({
+      id: assistant.id,
+      object: "assistant.deleted",
+      deleted: true,
+    });
+    expect(() => assistants.getAssistant(owner, assistant.id)).toThrow(NotFoundError);
+  });
+
+  it("should keep each key's objects to itself", () => {
+    const { assistants, assistant, thread } = setup();
+
+    expect(() => assistants.getAssistant("someone-else", assistant.id)).toThrow(NotFoundError);
+    expect(() => assistants.getThread("someone-else", thread.id)).toThrow(NotFoundError);
+    expect(assistants.listAssistants("someone-else", {}).data).toEqual([]);
+  });
+
+  it("should reject unknown models and bad tools", () => {
+    const assistants = store();
+
+    expect(() => assistants.createAssistant(owner, { model: "no-such-model" })).toThrow(ModelNotFoundError);
+    expect(() => assistants.createAssistant(owner, { model: "echo", tools: [{ type: "browser" }] })).toThrow(
+      InvalidRequestError,
+    );
+  });
+});
+
+describe("paginate", () => {
+  const items = ["a", "b", "c", "d"].map((id) => ({ id }));
+
+  it("should list newest first by default", () => {
+    expect(paginate(items, { limit: "2" })).toEqual({
+      object: "list",
+      data: [{ id: "d" }, { id: "c" }],
+      first_id: "d",
+      last_id: "c",
+      has_more: true,
+    });
+  });
+
+  it("should page with after and before cursors", () => {
+    expect(paginate(items, { order: "asc", after: "b" }).data).toEqual([{ id: "c" }, { id: "d" }]);
+    expect(paginate(items, { order: "asc", before: "c" }).data).toEqual([{ id: "a" }, { id: "b" }]);
+  });
+
+  it("should reject bad limits and orders", () => {
+    expect(() => paginate(items, { limit: "0" })).toThrow(InvalidRequestError);
+    expect(() => paginate(items, { order: "sideways" })).toThrow(InvalidRequestError);
+  });
+});
@@ -0,0 +1,864 @@
+// Minimal emulation of the OpenAI Assistants API (v2)
+//
+// Assistants, threads, their messages and runs are kept in memory, separately
+// for each API key. A run answers its thread with the same adapters as
+// /v1/chat/completions, in the background: clients poll it from queued
+// through in_progress to completed. When the model calls function tools the
+// run stops at requires_action until the outputs are submitted, then carries
+// on. Every model call is recorded as a run step.
+
+import { APIError, InvalidRequestError, NotFoundError } from './errors.js';
+import type { OpenAIAdapter } from './adapter.js';
+import type {
+  ChatCompletionMessageToolCall,
+  ChatCompletionRequest,
+  ChatCompletionRequestMessage,
+  ChatCompletionTool,
+  ChatCompletionToolChoice,
+  ChatCompletionUsage,
+} from './types.js';
+import { generateRandomString, getCurrentTimestamp } from './types.js';
+import { validateTools } from './validation.js';
+
+// Tool types an assistant may have; only function tools reach the model
+const TOOL_TYPES = ['function', 'code_interpreter', 'file_search'];
+
+// How long a run waits for tool outputs before it would expire
+const RUN_EXPIRY_SECONDS = 10 * 60;
+
+const DEFAULT_LIST_LIMIT = 20;
+const MAX_LIST_LIMIT = 100;
+
+type Metadata = Record<string, string>;
+
+export interface AssistantTool {
+  type: 'function' | 'code_interpreter' | 'file_search';
+  function?: ChatCompletionTool['function'];
+}
+
+export interface Assistant {
+  id: string;
+  object: 'assistant';
+  created_at: number;
+  name: string | null;
+  description: string | null;
+  model: string;
+  instructions: string | null;
+  tools: AssistantTool[];
+  tool_resources: Record<string, unknown>;
+  metadata: Metadata;
+  temperature: number | null;
+  top_p: number | null;
+  response_format: unknown;
+}
+
+export interface Thread {
+  id: string;
+  object: 'thread';
+  created_at: number;
+  tool_resources: Record<string, unknown>;
+  metadata: Metadata;
+}
+
+interface TextContent {
+  type: 'text';
+  text: { value: string; annotations: unknown[] };
+}
+
+export interface ThreadMessage {
+  id: string;
+  object: 'thread.message';
+  created_at: number;
+  thread_id: string;
+  status: 'completed' | 'incomplete';
+  incomplete_details: { reason: string } | null;
+  completed_at: number | null;
+  incomplete_at: number | null;
+  role: 'user' | 'assistant';
+  content: TextContent[];
+  assistant_id: string | null;
+  run_id: string | null;
+  attachments: unknown[];
+  metadata: Metadata;
+}
+
+export type RunStatus =
+  | 'queued'
+  | 'in_progress'
+  | 'requires_action'
+  | 'cancelling'
+  | 'cancelled'
+  | 'failed'
+  | 'completed'
+  | 'incomplete'
+  | 'expired';
+
+interface RunError {
+  code: 'server_error' | 'rate_limit_exceeded' | 'invalid_prompt';
+  message: string;
+}
+
+export interface Run {
+  id: string;
+  object: 'thread.run';
+  created_at: number;
+  thread_id: string;
+  assistant_id: string;
+  status: RunStatus;
+  required_action: {
+    type: 'submit_tool_outputs';
+    submit_tool_outputs: { tool_calls: ChatCompletionMessageToolCall[] };
+  } | null;
+  last_error: RunError | null;
+  expires_at: number | null;
+  started_at: number | null;
+  cancelled_at: number | null;
+  failed_at: number | null;
+  completed_at: number | null;
+  incomplete_details: { reason: 'max_completion_tokens' } | null;
+  model: string;
+  instructions: string;
+  tools: AssistantTool[];
+  metadata: Metadata;
+  usage: ChatCompletionUsage | null;
+  temperature: number | null;
+  top_p: number | null;
+  max_prompt_tokens: number | null;
+  max_completion_tokens: number | null;
+  truncation_strategy: { type: 'auto'; last_messages: null };
+  response_format: unknown;
+  tool_choice: unknown;
+  parallel_tool_calls: boolean;
+}
+
+interface StepToolCall {
+  id: string;
+  type: 'function';
+  function: { name: string; arguments: string; output: string | null };
+}
+
+export interface RunStep {
+  id: string;
+  object: 'thread.run.step';
+  created_at: number;
+  assistant_id: string;
+  thread_id: string;
+  run_id: string;
+  type: 'message_creation' | 'tool_calls';
+  status: 'in_progress' | 'cancelled' | 'failed' | 'completed' | 'expired';
+  step_details:
+    | { type: 'message_creation'; message_creation: { message_id: string } }
+    | { type: 'tool_calls'; tool_calls: StepToolCall[] };
+  last_error: RunError | null;
+  expired_at: number | null;
+  cancelled_at: number | null;
+  failed_at: number | null;
+  completed_at: number | null;
+  metadata: Metadata;
+  usage: ChatCompletionUsage | null;
+}
+
+export interface ListPage<T> {
+  object: 'list';
+  data: T[];
+  first_id: string | null;
+  last_id: string | null;
+  has_more: boolean;
+}
+
+// Finds the adapter for a model, throwing if the key can't use it
+export type ResolveAdapter = (owner: string, model: string) => OpenAIAdapter;
+// Told what each completion of a run used, as it happens in the background
+export type MeterUsage = (owner: string, model: string, usage: ChatCompletionUsage) => void;
+
+interface StoredThread {
+  owner: string;
+  thread: Thread;
+  messages: ThreadMessage[];
+  runs: StoredRun[];
+}
+
+interface StoredRun {
+  run: Run;
+  steps: RunStep[];
+  adapter: OpenAIAdapter;
+  // The chat request the run sends, growing with each round of tool calls
+  request: ChatCompletionRequest;
+  cancellation: AbortController;
+}
+
+function isObject(value: unknown): value is Record<string, any> {
+  return typeof value === 'object' && value !== null && !Array.isArray(value);
+}
+
+function newId(prefix: string): string {
+  return `${prefix}_${generateRandomString(24)}`;
+}
+
+// Clients send null for parameters they leave out as often as they omit them
+function given(value: unknown): boolean {
+  return value !== undefined && value !== null;
+}
+
+function optionalString(body: Record<string, any>, name: string): string | null {
+  const value = body[name];
+  if (!given(value)) {
+    return null;
+  }
+  if (typeof value !== 'string') {
+    throw new InvalidRequestError(`Invalid type for '${name}': expected a string`, name);
+  }
+  return value;
+}
+
+function parseMetadata(value: unknown, param = 'metadata'): Metadata {
+  if (!given(value)) {
+    return {};
+  }
+  if (!isObject(value) || Object.values(value).some(entry => typeof entry !== 'string')) {
+    throw new InvalidRequestError(`Invalid type for '${param}': expected an object of strings`, param);
+  }
+  return { ...value };
+}
+
+function parseTools(value: unknown): AssistantTool[] {
+  if (!given(value)) {
+    return [];
+  }
+  if (!Array.isArray(value) || value.some(tool => !isObject(tool) || !TOOL_TYPES.includes(tool.type))) {
+    throw new InvalidRequestError(
+      `Invalid 'tools': each tool must have a type of ${TOOL_TYPES.join(', ')}`,
+      'tools'
+    );
+  }
+  validateTools({ tools: value.filter(tool => tool.type === 'function') });
+  return value;
+}
+
+function parseContent(value: unknown): TextContent[] {
+  const text = (value: string): TextContent => ({ type: 'text', text: { value, annotations: [] } });
+  if (typeof value === 'string') {
+    return [text(value)];
+  }
+  if (Array.isArray(value) && value.length > 0) {
+    return value.map((part, i) => {
+      if (!isObject(part) || part.type !== 'text' || typeof part.text !== 'string') {
+        throw new InvalidRequestError(`Invalid 'content[${i}]': only text content is supported`, `content[${i}]`);
+      }
+      return text(part.text);
+    });
+  }
+  throw new InvalidRequestError("Invalid 'content': expected a string or an array of text parts", 'content');
+}
+
+function textOf(message: ThreadMessage): string {
+  return message.content.map(part => part.text.value).join('\n');
+}
+
+// Sorts, cursors and limits a list of objects by ?order=&after=&before=&limit=,
+// newest first by default, as OpenAI's list endpoints do
+export function paginate<T extends { id: string }>(items: T[], query: Record<string, string>): ListPage<T> {
+  const order = query.order ?? 'desc';
+  if (order !== 'asc' && order !== 'desc') {
+    throw new InvalidRequestError("Invalid 'order': expected 'asc' or 'desc'", 'order');
+  }
+  const limit = query.limit === undefined ? DEFAULT_LIST_LIMIT : Number(query.limit);
+  if (!Number.isInteger(limit) || limit < 1 || limit > MAX_LIST_LIMIT) {
+    throw new InvalidRequestError(`Invalid 'limit': expected an integer from 1 to ${MAX_LIST_LIMIT}`, 'limit');
+  }
+
+  let sorted = order === 'asc' ? [...items] : [...items].reverse();
+  for (const cursor of ['after', 'before'] as const) {
+    const id = query[cursor];
+    if (id === undefined) {
+      continue;
+    }
+    const index = sorted.findIndex(item => item.id === id);
+    if (index === -1) {
+      throw new InvalidRequestError(`Invalid '${cursor}': no object with id '${id}'`, cursor);
+    }
+    sorted = cursor === 'after' ? sorted.slice(index + 1) : sorted.slice(0, index);
+  }
+
+  const data = sorted.slice(0, limit);
+  return {
+    object: 'list',
+    data,
+    first_id: data[0]?.id ?? null,
+    last_id: data.at(-1)?.id ?? null,
+    has_more: sorted.length > limit,
+  };
+}
+
+export class AssistantStore {
+  private assistants = new Map<string, { owner: string; assistant: Assistant }>();
+  private threads = new Map<string, StoredThread>();
+
+  constructor(
+    private resolve: ResolveAdapter,
+    private meter: MeterUsage = () => {}
+  ) {}
+
+  // Assistants
+
+  createAssistant(owner: string, body: unknown): Assistant {
+    if (!isObject(body)) {
+      throw new InvalidRequestError('Request body must be a JSON object');
+    }
+    if (typeof body.model !== 'string' || body.model === '') {
+      throw new InvalidRequestError('Missing required parameter: model', 'model');
+    }
+    this.resolve(owner, body.model);
+
+    const assistant: Assistant = {
+      id: newId('asst'),
+      object: 'assistant',
+      created_at: getCurrentTimestamp(),
+      name: optionalString(body, 'name'),
+      description: optionalString(body, 'description'),
+      model: body.model,
+      instructions: optionalString(body, 'instructions'),
+      tools: parseTools(body.tools),
+      tool_resources: {},
+      metadata: parseMetadata(body.metadata),
+      temperature: typeof body.temperature === 'number' ? body.temperature : 1,
+      top_p: typeof body.top_p === 'number' ? body.top_p : 1,
+      response_format: body.response_format ?? 'auto',
+    };
+    this.assistants.set(assistant.id, { owner, assistant });
+    return { ...assistant };
+  }
+
+  getAssistant(owner: string, id: string): Assistant {
+    return { ...this.assistant(owner, id) };
+  }
+
+  listAssistants(owner: string, query: Record<string, string>): ListPage<Assistant> {
+    const owned = [...this.assistants.values()].filter(stored => stored.owner === owner);
+    return paginate(
+      owned.map(stored => stored.assistant),
+      query
+    );
+  }
+
+  // Changes only the fields given
+  updateAssistant(owner: string, id: string, body: unknown): Assistant {
+    const assistant = this.assistant(owner, id);
+    if (!isObject(body)) {
+      throw new InvalidRequestError('Request body must be a JSON object');
+    }
+    if (given(body.model)) {
+      if (typeof body.model !== 'string') {
+        throw new InvalidRequestError("Invalid type for 'model': expected a string", 'model');
+      }
+      this.resolve(owner, body.model);
+      assistant.model = body.model;
+    }
+    for (const name of ['name', 'description', 'instructions'] as const) {
+      if (given(body[name])) {
+        assistant[name] = optionalString(body, name);
+      }
+    }
+    if (given(body.tools)) {
+      assistant.tools = parseTools(body.tools);
+    }
+    if (given(body.metadata)) {
+      assistant.metadata = parseMetadata(body.metadata);
+    }
+    return { ...assistant };
+  }
+
+  deleteAssistant(owner: string, id: string): { id: string; object: 'assistant.deleted'; deleted: true } {
+    this.assistant(owner, id);
+    this.assistants.delete(id);
+    return { id, object: 'assistant.deleted', deleted: true };
+  }
+
+  // Threads
+
+  createThread(owner: string, body: unknown): Thread {
+    if (given(body) && !isObject(body)) {
+      throw new InvalidRequestError('Request body must be a JSON object');
+    }
+    const params: Record<string, any> = isObject(body) ? body : {};
+    if (given(params.messages) && !Array.isArray(params.messages)) {
+      throw new InvalidRequestError("Invalid type for 'messages': expected an array of messages", 'messages');
+    }
+
+    const thread: Thread = {
+      id: newId('thread'),
+      object: 'thread',
+      created_at: getCurrentTimestamp(),
+      tool_resources: {},
+      metadata: parseMetadata(params.metadata),
+    };
+    const stored: StoredThread = { owner, thread, messages: [], runs: [] };
+    for (const message of params.messages ?? []) {
+      this.addMessage(stored, message);
+    }
+    this.threads.set(thread.id, stored);
+    return { ...thread };
+  }
+
+  getThread(owner: string, id: string): Thread {
+    return { ...this.thread(owner, id).thread };
+  }
+
+  updateThread(owner: string, id: string, body: unknown): Thread {
+    const { thread } = this.thread(owner, id);
+    if (isObject(body) && given(body.metadata)) {
+      thread.metadata = parseMetadata(body.metadata);
+    }
+    return { ...thread };
+  }
+
+  deleteThread(owner: string, id: string): { id: string; object: 'thread.deleted'; deleted: true } {
+    const stored = this.thread(owner, id);
+    for (const { cancellation } of stored.runs) {
+      cancellation.abort();
+    }
+    this.threads.delete(id);
+    return { id, object: 'thread.deleted', deleted: true };
+  }
+
+  // Messages
+
+  createMessage(owner: string, threadId: string, body: unknown): ThreadMessage {
+    const stored = this.thread(owner, threadId);
+    const active = this.activeRun(stored);
+    if (active) {
+      throw new InvalidRequestError(
+        `Can't add messages to ${threadId} while a run ${active.run.id} is active.`
+      );
+    }
+    return { ...this.addMessage(stored, body) };
+  }
+
+  getMessage(owner: string, threadId: string, id: string): ThreadMessage {
+    const message = this.thread(owner, threadId).messages.find(message => message.id === id);
+    if (!message) {
+      throw new NotFoundError(`No message found with id '${id}'.`);
+    }
+    return { ...message };
+  }
+
+  listMessages(owner: string, threadId: string, query: Record<string, string>): ListPage<ThreadMessage> {
+    const { messages } = this.thread(owner, threadId);
+    const runId = query.run_id;
+    return paginate(
+      runId === undefined ? messages : messages.filter(message => message.run_id === runId),
+      query
+    );
+  }
+
+  // Runs
+
+  createRun(owner: string, threadId: string, body: unknown): Run {
+    const stored = this.thread(owner, threadId);
+    if (!isObject(body)) {
+      throw new InvalidRequestError('Request body must be a JSON object');
+    }
+    if (body.stream === true) {
+      throw new InvalidRequestError('Streaming runs are not supported', 'stream');
+    }
+    if (typeof body.assistant_id !== 'string') {
+      throw new InvalidRequestError('Missing required parameter: assistant_id', 'assistant_id');
+    }
+    const assistant = this.assistant(owner, body.assistant_id);
+    const active = this.activeRun(stored);
+    if (active) {
+      throw new InvalidRequestError(`Thread ${threadId} already has an active run ${active.run.id}.`);
+    }
+
+    const model = optionalString(body, 'model') ?? assistant.model;
+    const adapter = this.resolve(owner, model);
+    const tools = given(body.tools) ? parseTools(body.tools) : assistant.tools;
+    const toolChoice = body.tool_choice ?? 'auto';
+    const parallelToolCalls = body.parallel_tool_calls ?? true;
+    const functions = tools.filter(tool => tool.type === 'function') as ChatCompletionTool[];
+    validateTools({
+      tools: functions,
+      parallel_tool_calls: parallelToolCalls,
+      ...(typeof toolChoice === 'string' || toolChoice.type === 'function' ? { tool_choice: toolChoice } : {}),
+    });
+    const maxCompletionTokens = given(body.max_completion_tokens) ? body.max_completion_tokens : null;
+    if (maxCompletionTokens !== null && (!Number.isInteger(maxCompletionTokens) || maxCompletionTokens < 1)) {
+      throw new InvalidRequestError(
+        "Invalid 'max_completion_tokens': expected a positive integer",
+        'max_completion_tokens'
+      );
+    }
+    let instructions = optionalString(body, 'instructions') ?? assistant.instructions ?? '';
+    const additional = optionalString(body, 'additional_instructions');
+    if (additional) {
+      instructions = instructions ? `${instructions}\n${additional}` : additional;
+    }
+    const metadata = parseMetadata(body.metadata);
+    if (given(body.additional_messages) && !Array.isArray(body.additional_messages)) {
+      throw new InvalidRequestError(
+        "Invalid type for 'additional_messages': expected an array of messages",
+        'additional_messages'
+      );
+    }
+    for (const message of body.additional_messages ?? []) {
+      this.addMessage(stored, message);
+    }
+
+    const created_at = getCurrentTimestamp();
+    const run: Run = {
+      id: newId('run'),
+      object: 'thread.run',
+      created_at,
+      thread_id: threadId,
+      assistant_id: assistant.id,
+      status: 'queued',
+      required_action: null,
+      last_error: null,
+      expires_at: created_at + RUN_EXPIRY_SECONDS,
+      started_at: null,
+      cancelled_at: null,
+      failed_at: null,
+      completed_at: null,
+      incomplete_details: null,
+      model,
+      instructions,
+      tools,
+      metadata,
+      usage: null,
+      temperature: typeof body.temperature === 'number' ? body.temperature : assistant.temperature,
+      top_p: typeof body.top_p === 'number' ? body.top_p : assistant.top_p,
+      max_prompt_tokens: null,
+      max_completion_tokens: maxCompletionTokens,
+      truncation_strategy: { type: 'auto', last_messages: null },
+      response_format: body.response_format ?? assistant.response_format,
+      tool_choice: toolChoice,
+      parallel_tool_calls: parallelToolCalls,
+    };
+
+    const messages: ChatCompletionRequestMessage[] = [];
+    if (instructions) {
+      messages.push({ role: 'system', content: instructions });
+    }
+    for (const message of stored.messages) {
+      messages.push({ role: message.role, content: textOf(message) });
+    }
+    const request: ChatCompletionRequest = {
+      model,
+      messages,
+      parallel_tool_calls: parallelToolCalls,
+      ...(functions.length > 0 ? { tools: functions } : {}),
+      ...(typeof toolChoice === 'string' || toolChoice.type === 'function'
+        ? { tool_choice: toolChoice as ChatCompletionToolChoice }
+        : {}),
+      ...(maxCompletionTokens !== null ? { max_completion_tokens: maxCompletionTokens } : {}),
+    };
+
+    const storedRun: StoredRun = { run, steps: [], adapter, request, cancellation: new AbortController() };
+    stored.runs.push(storedRun);
+    return this.start(stored, storedRun);
+  }
+
+  // Creates a thread from body.thread and runs it in one call
+  createThreadAndRun(owner: string, body: unknown): Run {
+    if (!isObject(body)) {
+      throw new InvalidRequestError('Request body must be a JSON object');
+    }
+    const { thread, ...run } = body;
+    if (typeof run.assistant_id !== 'string') {
+      throw new InvalidRequestError('Missing required parameter: assistant_id', 'assistant_id');
+    }
+    this.assistant(owner, run.assistant_id);
+    const created = this.createThread(owner, thread ?? {});
+    return this.createRun(owner, created.id, run);
+  }
+
+  getRun(owner: string, threadId: string, id: string): Run {
+    return { ...this.run(owner, threadId, id).run };
+  }
+
+  listRuns(owner: string, threadId: string, query: Record<string, string>): ListPage<Run> {
+    return paginate(
+      this.thread(owner, threadId).runs.map(stored => stored.run),
+      query
+    );
+  }
+
+  cancelRun(owner: string, threadId: string, id: string): Run {
+    const stored = this.run(owner, threadId, id);
+    const { run } = stored;
+    const now = getCurrentTimestamp();
+    switch (run.status) {
+      case 'queued':
+      case 'in_progress':
+        // Finished off as cancelled once the model call stops
+        run.status = 'cancelling';
+        stored.cancellation.abort();
+        break;
+      case 'requires_action':
+        run.status = 'cancelled';
+        run.cancelled_at = now;
+        run.required_action = null;
+        for (const step of stored.steps.filter(step => step.status === 'in_progress')) {
+          step.status = 'cancelled';
+          step.cancelled_at = now;
+        }
+        break;
+      default:
+        throw new InvalidRequestError(`Cannot cancel run with status '${run.status}'.`);
+    }
+    return { ...run };
+  }
+
+  // Continues a run waiting at requires_action with the output of every call
+  submitToolOutputs(owner: string, threadId: string, id: string, body: unknown): Run {
+    const stored = this.run(owner, threadId, id);
+    const { run } = stored;
+    if (run.status !== 'requires_action' || !run.required_action) {
+      throw new InvalidRequestError(`Runs in status '${run.status}' do not accept tool outputs.`);
+    }
+    if (isObject(body) && body.stream === true) {
+      throw new InvalidRequestError('Streaming runs are not supported', 'stream');
+    }
+    const outputs = isObject(body) ? body.tool_outputs : undefined;
+    if (
+      !Array.isArray(outputs) ||
+      outputs.some(output => !isObject(output) || typeof output.tool_call_id !== 'string' || typeof output.output !== 'string')
+    ) {
+      throw new InvalidRequestError(
+        "Invalid 'tool_outputs': expected an array of objects with a tool_call_id and a string output",
+        'tool_outputs'
+      );
+    }
+    const calls = run.required_action.submit_tool_outputs.tool_calls;
+    const expected = calls.map(call => call.id);
+    const received: string[] = outputs.map(output => output.tool_call_id);
+    if (expected.length !== received.length || expected.some(callId => !received.includes(callId))) {
+      throw new InvalidRequestError(
+        `Expected tool outputs for call_ids ${JSON.stringify(expected)}, got ${JSON.stringify(received)}`,
+        'tool_outputs'
+      );
+    }
+    // Carrying on asks the model again, so the key has to still be allowed to
+    this.resolve(owner, run.model);
+
+    const now = getCurrentTimestamp();
+    const step = stored.steps.find(step => step.status === 'in_progress' && step.type === 'tool_calls');
+    for (const call of calls) {
+      const output: string = outputs.find(output => output.tool_call_id === call.id).output;
+      stored.request.messages.push({ role: 'tool', tool_call_id: call.id, content: output });
+      if (step?.step_details.type === 'tool_calls') {
+        const stepCall = step.step_details.tool_calls.find(stepCall => stepCall.id === call.id);
+        if (stepCall) {
+          stepCall.function.output = output;
+        }
+      }
+    }
+    if (step) {
+      step.status = 'completed';
+      step.completed_at = now;
+    }
+    run.required_action = null;
+    run.status = 'queued';
+    return this.start(this.thread(owner, threadId), stored);
+  }
+
+  getRunStep(owner: string, threadId: string, runId: string, id: string): RunStep {
+    const step = this.run(owner, threadId, runId).steps.find(step => step.id === id);
+    if (!step) {
+      throw new NotFoundError(`No run step found with id '${id}'.`);
+    }
+    return { ...step };
+  }
+
+  listRunSteps(owner: string, threadId: string, runId: string, query: Record<string, string>): ListPage<RunStep> {
+    return paginate(this.run(owner, threadId, runId).steps, query);
+  }
+
+  private assistant(owner: string, id: string): Assistant {
+    const stored = this.assistants.get(id);
+    if (!stored || stored.owner !== owner) {
+      throw new NotFoundError(`No assistant found with id '${id}'.`);
+    }
+    return stored.assistant;
+  }
+
+  private thread(owner: string, id: string): StoredThread {
+    const stored = this.threads.get(id);
+    if (!stored || stored.owner !== owner) {
+      throw new NotFoundError(`No thread found with id '${id}'.`);
+    }
+    return stored;
+  }
+
+  private run(owner: string, threadId: string, id: string): StoredRun {
+    const stored = this.thread(owner, threadId).runs.find(stored => stored.run.id === id);
+    if (!stored) {
+      throw new NotFoundError(`No run found with id '${id}'.`);
+    }
+    return stored;
+  }
+
+  private activeRun(thread: StoredThread): StoredRun | undefined {
+    return thread.runs.find(stored =>
+      ['queued', 'in_progress', 'requires_action', 'cancelling'].includes(stored.run.status)
+    );
+  }
+
+  private addMessage(thread: StoredThread, body: unknown, from?: { assistant_id: string; run_id: string }): ThreadMessage {
+    if (!isObject(body)) {
+      throw new InvalidRequestError("Invalid 'messages': each message must be an object", 'messages');
+    }
+    if (body.role !== 'user' && body.role !== 'assistant') {
+      throw new InvalidRequestError("Invalid 'role': expected 'user' or 'assistant'", 'role');
+    }
+    const created_at = getCurrentTimestamp();
+    const message: ThreadMessage = {
+      id: newId('msg'),
+      object: 'thread.message',
+      created_at,
+      thread_id: thread.thread.id,
+      status: 'completed',
+      incomplete_details: null,
+      completed_at: from ? created_at : null,
+      incomplete_at: null,
+      role: body.role,
+      content: parseContent(body.content),
+      assistant_id: from?.assistant_id ?? null,
+      run_id: from?.run_id ?? null,
+      attachments: Array.isArray(body.attachments) ? body.attachments : [],
+      metadata: parseMetadata(body.metadata),
+    };
+    thread.messages.push(message);
+    return message;
+  }
+
+  // Sets the run going in the background, answering with it as it was queued
+  private start(thread: StoredThread, stored: StoredRun): Run {
+    const queued = { ...stored.run };
+    void this.execute(thread, stored);
+    return queued;
+  }
+
+  private async execute(thread: StoredThread, stored: StoredRun): Promise<void> {
+    const { run } = stored;
+    run.status = 'in_progress';
+    run.started_at ??= getCurrentTimestamp();
+
+    let response;
+    try {
+      // Faults that would break a stream fail the run all the same
+      if (stored.adapter.preflight(stored.request) !== undefined) {
+        this.fail(stored, undefined);
+        return;
+      }
+      response = await stored.adapter.complete(stored.request, stored.cancellation.signal);
+    } catch (error) {
+      if (stored.cancellation.signal.aborted) {
+        this.finish(stored, 'cancelled');
+      } else {
+        this.fail(stored, error);
+      }
+      return;
+    }
+    if (stored.cancellation.signal.aborted) {
+      this.finish(stored, 'cancelled');
+      return;
+    }
+
+    const usage = response.usage;
+    this.meter(thread.owner, stored.request.model, usage);
+    run.usage = run.usage
+      ? {
+          prompt_tokens: run.usage.prompt_tokens + usage.prompt_tokens,
+          completion_tokens: run.usage.completion_tokens + usage.completion_tokens,
+          total_tokens: run.usage.total_tokens + usage.total_tokens,
+        }
+      : { ...usage };
+
+    const choice = response.choices[0];
+    const toolCalls = choice?.message.tool_calls ?? [];
+    if (toolCalls.length > 0) {
+      stored.request.messages.push({ role: 'assistant', content: null, tool_calls: toolCalls });
+      this.addStep(stored, 'in_progress', usage, {
+        type: 'tool_calls',
+        tool_calls: toolCalls.map(call => ({ ...call, function: { ...call.function, output: null } })),
+      });
+      run.status = 'requires_action';
+      run.required_action = { type: 'submit_tool_outputs', submit_tool_outputs: { tool_calls: toolCalls } };
+      return;
+    }
+
+    const text = choice?.message.content ?? '';
+    const message = this.addMessage(
+      thread,
+      { role: 'assistant', content: text },
+      { assistant_id: run.assistant_id, run_id: run.id }
+    );
+    stored.request.messages.push({ role: 'assistant', content: text });
+    this.addStep(stored, 'completed', usage, {
+      type: 'message_creation',
+      message_creation: { message_id: message.id },
+    });
+    if (choice?.finish_reason === 'length') {
+      message.status = 'incomplete';
+      message.incomplete_details = { reason: 'max_tokens' };
+      message.incomplete_at = message.completed_at;
+      message.completed_at = null;
+      run.incomplete_details = { reason: 'max_completion_tokens' };
+      this.finish(stored, 'incomplete');
+    } else {
+      this.finish(stored, 'completed');
+    }
+  }
+
+  private addStep(
+    stored: StoredRun,
+    status: RunStep['status'],
+    usage: ChatCompletionUsage,
+    details: RunStep['step_details']
+  ): void {
+    const created_at = getCurrentTimestamp();
+    stored.steps.push({
+      id: newId('step'),
+      object: 'thread.run.step',
+      created_at,
+      assistant_id: stored.run.assistant_id,
+      thread_id: stored.run.thread_id,
+      run_id: stored.run.id,
+      type: details.type,
+      status,
+      step_details: details,
+      last_error: null,
+      expired_at: null,
+      cancelled_at: null,
+      failed_at: null,
+      completed_at: status === 'completed' ? created_at : null,
+      metadata: {},
+      usage: { ...usage },
+    });
+  }
+
+  private finish(stored: StoredRun, status: 'completed' | 'incomplete' | 'cancelled'): void {
+    const { run } = stored;
+    run.status = status;
+    if (status === 'cancelled') {
+      run.cancelled_at = getCurrentTimestamp();
+    } else if (status === 'completed') {
+      run.completed_at = getCurrentTimestamp();
+    }
+  }
+
+  // Model errors end the run as failed, as a rate limit or a server error
+  private fail(stored: StoredRun, error: unknown): void {
+    const { run } = stored;
+    run.status = 'failed';
+    run.failed_at = getCurrentTimestamp();
+    run.last_error =
+      error instanceof APIError
+        ? {
+            code: error.statusCode === 429 ? 'rate_limit_exceeded' : 'server_error',
+            message: error.message,
+          }
+        : { code: 'server_error', message: 'The run failed' };
+  }
+}
@@ -0,0 +1,39 @@
+import { describe, it, expect } from "vitest";
+import {
+  formatTranscription,
+  synthesizeSpeech,
+  wavDurationSeconds,
+} from "./audio.js";
+
+describe("Audio stubs", () => {
+  it("should format a transcript as SRT", () => {
+    const result = formatTranscription("hi there", "srt", 1.5);
+
+    expect(result.body).toBe("1\n00:00:00,000 --> 00:00:01,500\nhi there\n\n");
+  });
+
+  it("should include a segment in verbose_json", () => {
+    const result = formatTranscription("hi there", "verbose_json", 2);
+    const data = JSON.parse(result.body);
+
+    expect(data).toMatchObject({ task: "transcribe", duration: 2, text: "hi there" });
+    expect(data.segments).toHaveLength(1);
+  });
+
+  it("should read back the duration of synthesized WAV audio", () => {
+    const wav = new Uint8Array(synthesizeSpeech("x".repeat(30), "wav"));
+
+    expect(wavDurationSeconds(wav)).toBe(2);
+  });
+
+  it("should report zero duration for non-WAV input", () => {
+    expect(wavDurationSeconds(new Uint8Array(100))).toBe(0);
+  });
+
+  it("should start each MP3 frame with a sync word", () => {
+    const mp3 = new Uint8Array(synthesizeSpeech("hello", "mp3"));
+
+    expect(mp3[0]).toBe(0xff);
+    expect(mp3[1]).toBe(0xfb);
+  });
+});
@@ -0,0 +1,186 @@
+// Stub implementations of the OpenAI audio endpoints
+//
+// There is no real speech model behind these: transcriptions return a canned
+// transcript and speech returns silence, which is enough for client code to
+// exercise multipart uploads, response formats and binary downloads.
+
+import { InvalidRequestError } from './errors.js';
+
+export const CANNED_TRANSCRIPT = 'Hello from TeenyTiny AI. This is a stub transcript.';
+
+export const TRANSCRIPTION_FORMATS = ['json', 'text', 'srt', 'verbose_json', 'vtt'] as const;
+export type TranscriptionFormat = typeof TRANSCRIPTION_FORMATS[number];
+
+export interface FormattedTranscription {
+  contentType: string;
+  body: string;
+}
+
+export function formatTranscription(
+  text: string,
+  format: TranscriptionFormat,
+  durationSeconds: number
+): FormattedTranscription {
+  switch (format) {
+    case 'json':
+      return { contentType: 'application/json', body: JSON.stringify({ text }, null, 2) };
+
+    case 'text':
+      return { contentType: 'text/plain; charset=utf-8', body: `${text}\n` };
+
+    case 'srt':
+      return {
+        contentType: 'text/plain; charset=utf-8',
+        body: `1\n${srtTimestamp(0)} --> ${srtTimestamp(durationSeconds)}\n${text}\n\n`,
+      };
+
+    case 'vtt':
+      return {
+        contentType: 'text/plain; charset=utf-8',
+        body: `WEBVTT\n\n${vttTimestamp(0)} --> ${vttTimestamp(durationSeconds)}\n${text}\n\n`,
+      };
+
+    case 'verbose_json':
+      return {
+        contentType: 'application/json',
+        body: JSON.stringify(
+          {
+            task: 'transcribe',
+            language: 'english',
+            duration: durationSeconds,
+            text,
+            segments: [
+              {
+                id: 0,
+                seek: 0,
+                start: 0,
+                end: durationSeconds,
+                text,
+                tokens: [],
+                temperature: 0,
+                avg_logprob: 0,
+                compression_ratio: 1,
+                no_speech_prob: 0,
+              },
+            ],
+          },
+          null,
+          2
+        ),
+      };
+  }
+}
+
+// Reads the duration from a PCM WAV header, or 0 for anything else
+export function wavDurationSeconds(bytes: Uint8Array): number {
+  const ascii = (offset: number, length: number) =>
+    String.fromCharCode(...bytes.subarray(offset, offset + length));
+
+  if (bytes.length < 44 || ascii(0, 4) !== 'RIFF' || ascii(8, 4) !== 'WAVE') {
+    return 0;
+  }
+
+  const view = new DataView(bytes.buffer, bytes.byteOffset, bytes.byteLength);
+  const byteRate = view.getUint32(28, true);
+  const dataSize = view.getUint32(40, true);
+  return byteRate > 0 ? dataSize / byteRate : 0;
+}
+
+export const SPEECH_FORMATS = {
+  mp3: 'audio/mpeg',
+  wav: 'audio/wav',
+  pcm: 'audio/pcm',
+} as const;
+export type SpeechFormat = keyof typeof SPEECH_FORMATS;
+
+export function isSpeechFormat(format: string): format is SpeechFormat {
+  return Object.prototype.hasOwnProperty.call(SPEECH_FORMATS, format);
+}
+
+const SAMPLE_RATE = 24000;
+
+// Silence whose length grows with the input, roughly like real speech would
+export function synthesizeSpeech(input: string, format: SpeechFormat): ArrayBuffer {
+  const durationSeconds = Math.max(0.5, input.length / 15);
+
+  switch (format) {
+    case 'pcm':
+      return silentPcm(durationSeconds).buffer as ArrayBuffer;
+    case 'wav':
+      return wrapWav(silentPcm(durationSeconds)).buffer as ArrayBuffer;
+    case 'mp3':
+      return silentMp3(durationSeconds).buffer as ArrayBuffer;
+  }
+}
+
+export function parseTranscriptionFormat(value: unknown): TranscriptionFormat {
+  if (value === undefined) {
+    return 'json';
+  }
+  if (typeof value !== 'string' || !(TRANSCRIPTION_FORMATS as readonly string[]).includes(value)) {
+    throw new InvalidRequestError(
+      `Invalid response_format: must be one of ${TRANSCRIPTION_FORMATS.join(', ')}`,
+      'response_format'
+    );
+  }
+  return value as TranscriptionFormat;
+}
+
+// 16-bit mono samples, all zero
+function silentPcm(durationSeconds: number): Uint8Array {
+  return new Uint8Array(Math.round(durationSeconds * SAMPLE_RATE) * 2);
+}
+
+function wrapWav(pcm: Uint8Array): Uint8Array {
+  const wav = new Uint8Array(44 + pcm.length);
+  const view = new DataView(wav.buffer);
+  const writeAscii = (offset: number, text: string) => {
+    for (let i = 0; i < text.length; i++) view.setUint8(offset + i, text.charCodeAt(i));
+  };
+
+  writeAscii(0, 'RIFF');
+  view.setUint32(4, 36 + pcm.length, true);
+  writeAscii(8, 'WAVE');
+  writeAscii(12, 'fmt ');
+  view.setUint32(16, 16, true); // fmt chunk size
+  view.setUint16(20, 1, true); // PCM
+  view.setUint16(22, 1, true); // mono
+  view.setUint32(24, SAMPLE_RATE, true);
+  view.setUint32(28, SAMPLE_RATE * 2, true); // byte rate
+  view.setUint16(32, 2, true); // block align
+  view.setUint16(34, 16, true); // bits per sample
+  writeAscii(36, 'data');
+  view.setUint32(40, pcm.length, true);
+  wav.set(pcm, 44);
+  return wav;
+}
+
+// MPEG-1 Layer III frames (128kbps, 44.1kHz, mono) with empty payloads decode as silence
+function silentMp3(durationSeconds: number): Uint8Array {
+  const frameLength = 417;
+  const frameSeconds = 1152 / 44100;
+  const frames = Math.ceil(durationSeconds / frameSeconds);
+
+  const mp3 = new Uint8Array(frames * frameLength);
+  for (let i = 0; i < frames; i++) {
+    mp3.set([0xff, 0xfb, 0x90, 0xc4], i * frameLength);
+  }
+  return mp3;
+}
+
+function srtTimestamp(seconds: number): string {
+  return timestamp(seconds, ',');
+}
+
+function vttTimestamp(seconds: number): string {
+  return timestamp(seconds, '.');
+}
+
+function timestamp(seconds: number, separator: string): string {
+  const totalMs = Math.round(seconds * 1000);
+  const pad = (n: number, width: number) => String(n).padStart(width, '0');
+  const h = Math.floor(totalMs / 3600000);
+  const m = Math.floor((totalMs % 3600000) / 60000);
+  const s = Math.floor((totalMs % 60000) / 1000);
+  return `${pad(h, 2)}:${pad(m, 2)}:${pad(s, 2)}${separator}${pad(totalMs % 1000, 3)}`;
+}
@@ -0,0 +1,248 @@
+import { describe, it, expect } from "vitest";
+import { createApp } from "../app.js";
+import { BatchStore, parseBatchInput, parseCreateBatchRequest } from "./batches.js";
+import type { BatchDispatch } from "./batches.js";
+import { FileStore } from "./files.js";
+import { InvalidRequestError, NotFoundError } from "./errors.js";
+
+const owner = "tt-batch-key";
+
+function line(custom_id: string, content: string, extra: Record<string, unknown> = {}) {
+  return JSON.stringify({
+    custom_id,
+    method: "POST",
+    url: "/v1/chat/completions",
+    body: { model: "echo", messages: [{ role: "user", content }] },
+    ...extra,
+  });
+}
+
+// Answers each request with its content, failing those that say "fail"
+const dispatch: BatchDispatch = async (_owner, _url, body) => {
+  const content = (body.messages as any)[0].content;
+  return content === "fail"
+    ? new Response(JSON.stringify({ error: { message: "Failed" } }), { status: 400 })
+    : new Response(JSON.stringify({ content }), { headers: { "X-Request-ID": `req-${content}` } });
+};
+
+function setup(lines: string[], stepMs = 100) {
+  let now = 1_700_000_000_000;
+  const files = new FileStore();
+  const store = new BatchStore(files, dispatch, stepMs, () => now);
+  const input = files.create(owner, "input.jsonl", "batch", new TextEncoder().encode(lines.join("\n")));
+  const batch = store.create(owner, {
+    input_file_id: input.id,
+    endpoint: "/v1/chat/completions",
+    completion_window: "24h",
+  });
+  const advance = (ms: number) => {
+    now += ms;
+  };
+  const results = (id: string | null) =>
+    new TextDecoder()
+      .decode(files.content(owner, id!))
+      .trim()
+      .split("\n")
+      .map((text) => JSON.parse(text));
+  return { store, batch, advance, results };
+}
+
+describe("BatchStore", () => {
+  it("should step through the lifecycle as time passes", async () => {
+    const { store, batch, advance } = setup([line("a", "one"), line("b", "two")]);
+    expect(batch.status).toBe("validating");
+
+    const seen = [];
+    for (let i = 0; i < 5; i++) {
+      advance(100);
+      const polled = await store.get(owner, batch.id);
+      seen.push([polled.status, polled.request_counts.completed]);
+    }
+
+    expect(seen).toEqual([
+      ["in_progress", 0],
+      ["in_progress", 1],
+      ["finalizing", 2],
+      ["completed", 2],
+      ["completed", 2],
+    ]);
+  });
+
+  it("should not move before a step has passed", async () => {
+    const { store, batch, advance } = setup([line("a", "one")]);
+
+    advance(99);
+
+    expect((await store.get(owner, batch.id)).status).toBe("validating");
+  });
+
+  it("should write successes and failures to separate files", async () => {
+    const { store, batch, results } = setup([line("a", "one"), line("b", "fail")], 0);
+
+    const done = await store.get(owner, batch.id);
+
+    expect(done).toMatchObject({ status: "completed", request_counts: { total: 2, completed: 1, failed: 1 } });
+    expect(done.completed_at).not.toBeNull();
+    expect(results(done.output_file_id)).toEqual([
+      {
+        id: expect.stringMatching(/^batch_req_/),
+        custom_id: "a",
+        response: { status_code: 200, request_id: "req-one", body: { content: "one" } },
+        error: null,
+      },
+    ]);
+    expect(results(done.error_file_id)[0]).toMatchObject({ custom_id: "b", response: { status_code: 400 } });
+  });
+
+  it("should fail batches whose input doesn't validate", async () => {
+    const { store, batch } = setup([line("a", "one"), line("a", "two"), "not json"], 0);
+
+    const done = await store.get(owner, batch.id);
+
+    expect(done.status).toBe("failed");
+    expect(done.errors!.data.map((error) => [error.code, error.line])).toEqual([
+      ["duplicate_custom_id", 2],
+      ["invalid_json_line", 3],
+    ]);
+    expect(done.request_counts.total).toBe(0);
+  });
+
+  it("should cancel a batch in progress, keeping results so far", async () => {
+    const { store, batch, advance, results } = setup([line("a", "one"), line("b", "two"), line("c", "three")]);
+    advance(200);
+    await store.get(owner, batch.id);
+
+    const cancelling = await store.cancel(owner, batch.id);
+    advance(100);
+    const cancelled = await store.get(owner, batch.id);
+
+    expect(cancelling.status).toBe("cancelling");
+    expect(cancelled).toMatchObject({ status: "cancelled", request_counts: { total: 3, completed: 1 } });
+    expect(results(cancelled.output_file_id).map((result) => result.custom_id)).toEqual(["a"]);
+    await expect(store.cancel(owner, batch.id)).rejects.toThrow(InvalidRequestError);
+  });
+
+  it("should keep each key's batches to itself", async () => {
+    const { store, batch } = setup([line("a", "one")]);
+
+    await expect(store.get("someone-else", batch.id)).rejects.toThrow(NotFoundError);
+    expect((await store.list("someone-else")).data).toEqual([]);
+  });
+
+  it("should list batches newest first, a page at a time", async () => {
+    const { store, batch } = setup([line("a", "one")]);
+    const input = batch.input_file_id;
+    const second = store.create(owner, { input_file_id: input, endpoint: "/v1/chat/completions", completion_window: "24h" });
+
+    const first = await store.list(owner, undefined, 1);
+    const rest = await store.list(owner, first.last_id!, 1);
+
+    expect(first).toMatchObject({ first_id: second.id, last_id: second.id, has_more: true });
+    expect(rest).toMatchObject({ first_id: batch.id, has_more: false });
+  });
+
+  it("should only accept input files uploaded for batches", () => {
+    const files = new FileStore();
+    const store = new BatchStore(files, dispatch);
+    const file = files.create(owner, "notes.txt", "assistants", new Uint8Array());
+
+    expect(() =>
+      store.create(owner, { input_file_id: file.id, endpoint: "/v1/chat/completions", completion_window: "24h" }),
+    ).toThrow(InvalidRequestError);
+  });
+
+  it("should pick up where a snapshot left off, with the steps that fell due since", async () => {
+    let now = 1_700_000_000_000;
+    const files = new FileStore();
+    const store = new BatchStore(files, dispatch, 100, () => now);
+    const input = files.create(owner, "input.jsonl", "batch", new TextEncoder().encode([line("a", "one"), line("b", "two")].join("\n")));
+    const batch = store.create(owner, { input_file_id: input.id, endpoint: "/v1/chat/completions", completion_window: "24h" });
+    now += 200;
+    expect((await store.get(owner, batch.id)).request_counts.completed).toBe(1);
+
+    const restoredFiles = new FileStore();
+    restoredFiles.restore(JSON.parse(JSON.stringify(files.snapshot())));
+    const restored = new BatchStore(restoredFiles, dispatch, 100, () => now);
+    restored.restore(JSON.parse(JSON.stringify(store.snapshot())));
+    now += 300;
+
+    const done = await restored.get(owner, batch.id);
+    expect(done).toMatchObject({ status: "completed", request_counts: { total: 2, completed: 2 } });
+    expect(new TextDecoder().decode(restoredFiles.content(owner, done.output_file_id!))).toContain("req-two");
+  });
+
+  it("should count the steps it takes as changes, but not polls that find nothing due", async () => {
+    const { store, batch, advance } = setup([line("a", "one")]);
+    const created = store.version;
+
+    await store.get(owner, batch.id);
+    expect(store.version).toBe(created);
+    advance(100);
+    await store.get(owner, batch.id);
+    expect(store.version).toBe(created + 1);
+  });
+});
+
+describe("parseCreateBatchRequest", () => {
+  it("should reject unsupported endpoints and windows", () => {
+    const valid = { input_file_id: "file-1", endpoint: "/v1/chat/completions", completion_window: "24h" };
+
+    expect(parseCreateBatchRequest({ ...valid, metadata: { run: "1" } }).metadata).toEqual({ run: "1" });
+    expect(() => parseCreateBatchRequest({ ...valid, endpoint: "/v1/embeddings" })).toThrow(InvalidRequestError);
+    expect(() => parseCreateBatchRequest({ ...valid, completion_window: "1h" })).toThrow(InvalidRequestError);
+    expect(() => parseCreateBatchRequest({ ...valid, metadata: { run: 1 } })).toThrow(InvalidRequestError);
+  });
+});
+
+describe("parseBatchInput", () => {
+  it("should report requests for another endpoint or that stream", () => {
+    const { errors } = parseBatchInput(
+      [
+        line("a", "one", { url: "/v1/moderations" }),
+        line("b", "two", { body: { model: "echo", messages: [], stream: true } }),
+      ].join("\n"),
+      "/v1/chat/completions",
+    );
+
+    expect(errors.map((error) => error.code)).toEqual(["mismatched_endpoint", "invalid_body"]);
+  });
+
+  it("should reject an empty file", () => {
+    expect(parseBatchInput("\n", "/v1/chat/completions").errors[0]!.code).toBe("empty_file");
+  });
+});
+
+describe("Batch API", () => {
+  const app = createApp({ auth: { apiKey: owner }, batches: { stepMs: 0 } });
+  const headers = { Authorization: `Bearer ${owner}` };
+
+  it("should run an uploaded file through the server's own endpoints", async () => {
+    const form = new FormData();
+    form.append("purpose", "batch");
+    form.append("file", new File([`${line("greeting", "Hello batch")}\n`], "input.jsonl"));
+    const upload = await app.request("/v1/files", { method: "POST", headers, body: form });
+    const file = await upload.json();
+    expect(file).toMatchObject({ object: "file", purpose: "batch", filename: "input.jsonl" });
+
+    const created = await app.request("/v1/batches", {
+      method: "POST",
+      headers: { ...headers, "Content-Type": "application/json" },
+      body: JSON.stringify({ input_file_id: file.id, endpoint: "/v1/chat/completions", completion_window: "24h" }),
+    });
+    const batch = await (await app.request(`/v1/batches/${(await created.json()).id}`, { headers })).json();
+    expect(batch).toMatchObject({ status: "completed", request_counts: { total: 1, completed: 1, failed: 0 } });
+
+    const output = await app.request(`/v1/files/${batch.output_file_id}/content`, { headers });
+    const result = JSON.parse((await output.text()).trim());
+    expect(result.custom_id).toBe("greeting");
+    expect(result.response.status_code).toBe(200);
+    expect(result.response.request_id).toEqual(expect.any(String));
+    expect(result.response.body.choices[0].message.content).toBe("Hello batch");
+  });
+
+  it("should answer unknown batches with 404", async () => {
+    const res = await app.request("/v1/batches/batch_missing", { headers });
+
+    expect(res.status).toBe(404);
+  });
+});
@@ -0,0 +1,457 @@
+// Emulation of the OpenAI Batch API
+//
+// A batch runs the requests in an uploaded JSONL file against this server.
+// Progress is simulated and advances only when the batch is looked at: each
+// step of stepMs moves it on by one stage or one request, so a client polling
+// every second sees validating, in_progress with growing request_counts,
+// finalizing and completed much as it would against OpenAI. With stepMs set
+// to 0 a batch is done by the first poll.
+
+import { InvalidRequestError, NotFoundError } from './errors.js';
+import type { FileStore } from './files.js';
+import { generateRandomString } from './types.js';
+
+// Endpoints a batch's requests may target
+export const BATCH_ENDPOINTS = ['/v1/chat/completions', '/v1/moderations'] as const;
+export type BatchEndpoint = typeof BATCH_ENDPOINTS[number];
+
+export const BATCH_COMPLETION_WINDOW = '24h';
+export const DEFAULT_BATCH_STEP_MS = 100;
+
+const COMPLETION_WINDOW_SECONDS = 24 * 60 * 60;
+const DEFAULT_LIST_LIMIT = 20;
+const MAX_LIST_LIMIT = 100;
+
+export type BatchStatus =
+  | 'validating'
+  | 'failed'
+  | 'in_progress'
+  | 'finalizing'
+  | 'completed'
+  | 'expired'
+  | 'cancelling'
+  | 'cancelled';
+
+const FINISHED: readonly BatchStatus[] = ['failed', 'completed', 'expired', 'cancelled'];
+
+export interface BatchError {
+  code: string;
+  message: string;
+  param: string | null;
+  line: number | null;
+}
+
+export interface Batch {
+  id: string;
+  object: 'batch';
+  endpoint: BatchEndpoint;
+  errors: { object: 'list'; data: BatchError[] } | null;
+  input_file_id: string;
+  completion_window: string;
+  status: BatchStatus;
+  output_file_id: string | null;
+  error_file_id: string | null;
+  created_at: number;
+  in_progress_at: number | null;
+  expires_at: number;
+  finalizing_at: number | null;
+  completed_at: number | null;
+  failed_at: number | null;
+  expired_at: number | null;
+  cancelling_at: number | null;
+  cancelled_at: number | null;
+  request_counts: { total: number; completed: number; failed: number };
+  metadata: Record<string, string> | null;
+}
+
+export interface BatchList {
+  object: 'list';
+  data: Batch[];
+  first_id: string | null;
+  last_id: string | null;
+  has_more: boolean;
+}
+
+// One line of the input file
+interface BatchRequestLine {
+  custom_id: string;
+  method: 'POST';
+  url: BatchEndpoint;
+  body: Record<string, unknown>;
+}
+
+interface StoredBatch {
+  owner: string;
+  batch: Batch;
+  requests: BatchRequestLine[];
+  // Index of the next request to run
+  next: number;
+  output: string[];
+  errors: string[];
+  // When the batch may take its next step, in milliseconds
+  nextStepAt: number;
+  // Steps run one after another, however many polls arrive at once
+  work: Promise<void>;
+}
+
+// A stored batch as JSON can hold it, without the steps it is running
+export type BatchSnapshot = Omit<StoredBatch, 'work'>;
+
+// Sends one request of a batch to the server, as the batch's owner
+export type BatchDispatch = (owner: string, url: BatchEndpoint, body: Record<string, unknown>) => Promise<Response>;
+
+export interface CreateBatchRequest {
+  input_file_id: string;
+  endpoint: BatchEndpoint;
+  completion_window: string;
+  metadata?: Record<string, string>;
+}
+
+function isObject(value: unknown): value is Record<string, any> {
+  return typeof value === 'object' && value !== null && !Array.isArray(value);
+}
+
+export function parseCreateBatchRequest(body: unknown): CreateBatchRequest {
+  if (!isObject(body)) {
+    throw new InvalidRequestError('Request body must be a JSON object');
+  }
+  const { input_file_id, endpoint, completion_window, metadata } = body;
+  if (typeof input_file_id !== 'string' || input_file_id === '') {
+    throw new InvalidRequestError('Missing required parameter: input_file_id', 'input_file_id');
+  }
+  if (!(BATCH_ENDPOINTS as readonly unknown[]).includes(endpoint)) {
+    throw new InvalidRequestError(
+      `Invalid value for 'endpoint': expected one of ${BATCH_ENDPOINTS.join(', ')}`,
+      'endpoint'
+    );
+  }
+  if (completion_window !== BATCH_COMPLETION_WINDOW) {
+    throw new InvalidRequestError(
+      `Invalid value for 'completion_window': only '${BATCH_COMPLETION_WINDOW}' is supported`,
+      'completion_window'
+    );
+  }
+  if (
+    metadata !== undefined &&
+    metadata !== null &&
+    (!isObject(metadata) || Object.values(metadata).some(value => typeof value !== 'string'))
+  ) {
+    throw new InvalidRequestError("Invalid type for 'metadata': expected an object of strings", 'metadata');
+  }
+
+  const request: CreateBatchRequest = { input_file_id, endpoint, completion_window };
+  if (isObject(metadata)) {
+    request.metadata = metadata;
+  }
+  return request;
+}
+
+// Checks every line of an input file, collecting what's wrong with each
+export function parseBatchInput(
+  content: string,
+  endpoint: BatchEndpoint
+): { requests: BatchRequestLine[]; errors: BatchError[] } {
+  const requests: BatchRequestLine[] = [];
+  const errors: BatchError[] = [];
+  const seen = new Set<string>();
+  const fail = (line: number, code: string, message: string, param: string | null = null) =>
+    errors.push({ code, message, param, line });
+
+  content.split('\n').forEach((text, index) => {
+    const line = index + 1;
+    if (text.trim() === '') {
+      return;
+    }
+    let parsed: unknown;
+    try {
+      parsed = JSON.parse(text);
+    } catch {
+      fail(line, 'invalid_json_line', 'This line is not valid JSON.');
+      return;
+    }
+    if (!isObject(parsed)) {
+      fail(line, 'invalid_json_line', 'Each line must be a JSON object.');
+      return;
+    }
+    const { custom_id, method, url, body } = parsed;
+    if (typeof custom_id !== 'string' || custom_id === '') {
+      fail(line, 'missing_required_parameter', 'Missing required parameter: custom_id', 'custom_id');
+    } else if (seen.has(custom_id)) {
+      fail(line, 'duplicate_custom_id', `The custom_id '${custom_id}' is used by more than one request.`, 'custom_id');
+    } else if (method !== 'POST') {
+      fail(line, 'invalid_method', "Only the 'POST' method is supported.", 'method');
+    } else if (url !== endpoint) {
+      fail(line, 'mismatched_endpoint', `The url '${url}' does not match the batch's endpoint '${endpoint}'.`, 'url');
+    } else if (!isObject(body)) {
+      fail(line, 'invalid_body', 'The body must be a JSON object.', 'body');
+    } else if (body.stream === true) {
+      fail(line, 'invalid_body', 'Streaming is not supported in batches.', 'body.stream');
+    } else {
+      requests.push({ custom_id, method, url, body });
+    }
+    if (typeof custom_id === 'string') {
+      seen.add(custom_id);
+    }
+  });
+
+  if (requests.length === 0 && errors.length === 0) {
+    errors.push({ code: 'empty_file', message: 'The input file contains no requests.', param: null, line: null });
+  }
+  return { requests, errors };
+}
+
+export class BatchStore {
+  private batches = new Map<string, StoredBatch>();
+  private changes = 0;
+
+  constructor(
+    private files: FileStore,
+    private dispatch: BatchDispatch,
+    private stepMs: number = DEFAULT_BATCH_STEP_MS,
+    private now: () => number = Date.now
+  ) {}
+
+  create(owner: string, request: CreateBatchRequest): Batch {
+    const input = this.files.get(owner, request.input_file_id);
+    if (input.purpose !== 'batch') {
+      throw new InvalidRequestError(
+        `The input file must be uploaded with purpose 'batch', not '${input.purpose}'`,
+        'input_file_id'
+      );
+    }
+
+    const now = this.now();
+    const created_at = Math.floor(now / 1000);
+    const batch: Batch = {
+      id: `batch_${generateRandomString(24)}`,
+      object: 'batch',
+      endpoint: request.endpoint,
+      errors: null,
+      input_file_id: input.id,
+      completion_window: request.completion_window,
+      status: 'validating',
+      output_file_id: null,
+      error_file_id: null,
+      created_at,
+      in_progress_at: null,
+      expires_at: created_at + COMPLETION_WINDOW_SECONDS,
+      finalizing_at: null,
+      completed_at: null,
+      failed_at: null,
+      expired_at: null,
+      cancelling_at: null,
+      cancelled_at: null,
+      request_counts: { total: 0, completed: 0, failed: 0 },
+      metadata: request.metadata ?? null,
+    };
+    this.batches.set(batch.id, {
+      owner,
+      batch,
+      requests: [],
+      next: 0,
+      output: [],
+      errors: [],
+      nextStepAt: now + this.stepMs,
+      work: Promise.resolve(),
+    });
+    this.changes++;
+    return { ...batch };
+  }
+
+  // The batch as it stands now, after any steps that were due
+  async get(owner: string, id: string): Promise<Batch> {
+    const stored = this.stored(owner, id);
+    await this.catchUp(stored);
+    return { ...stored.batch };
+  }
+
+  // Newest first, a page at a time
+  async list(owner: string, after?: string, limit = DEFAULT_LIST_LIMIT): Promise<BatchList> {
+    if (!Number.isInteger(limit) || limit < 1 || limit > MAX_LIST_LIMIT) {
+      throw new InvalidRequestError(`Invalid 'limit': expected an integer from 1 to ${MAX_LIST_LIMIT}`, 'limit');
+    }
+    const owned = [...this.batches.values()].filter(stored => stored.owner === owner).reverse();
+    let start = 0;
+    if (after !== undefined) {
+      start = owned.findIndex(stored => stored.batch.id === after) + 1;
+      if (start === 0) {
+        throw new InvalidRequestError(`No batch found with id '${after}'.`, 'after');
+      }
+    }
+    const page = owned.slice(start, start + limit);
+    await Promise.all(page.map(stored => this.catchUp(stored)));
+
+    const data = page.map(stored => ({ ...stored.batch }));
+    return {
+      object: 'list',
+      data,
+      first_id: data[0]?.id ?? null,
+      last_id: data.at(-1)?.id ?? null,
+      has_more: start + limit < owned.length,
+    };
+  }
+
+  // Stops a batch that hasn't finished; requests already run keep their results
+  async cancel(owner: string, id: string): Promise<Batch> {
+    const stored = this.stored(owner, id);
+    await this.catchUp(stored);
+    const { batch } = stored;
+    if (batch.status !== 'validating' && batch.status !== 'in_progress') {
+      throw new InvalidRequestError(`Cannot cancel a batch with status '${batch.status}'.`);
+    }
+    batch.status = 'cancelling';
+    batch.cancelling_at = Math.floor(this.now() / 1000);
+    stored.nextStepAt = this.now() + this.stepMs;
+    this.changes++;
+    return { ...batch };
+  }
+
+  // Every batch, oldest first, to be restored after a restart. A restored
+  // batch takes the steps that fell due while the server was down when it is
+  // next polled.
+  snapshot(): BatchSnapshot[] {
+    return [...this.batches.values()].map(({ work: _work, ...stored }) => structuredClone(stored));
+  }
+
+  restore(batches: BatchSnapshot[]): void {
+    this.batches = new Map(
+      batches.map(stored => [stored.batch.id, { ...structuredClone(stored), work: Promise.resolve() }])
+    );
+  }
+
+  // Goes up with every batch created, cancelled or stepped, so the steps a
+  // poll takes count too
+  get version(): number {
+    return this.changes;
+  }
+
+  private stored(owner: string, id: string): StoredBatch {
+    const stored = this.batches.get(id);
+    if (!stored || stored.owner !== owner) {
+      throw new NotFoundError(`No batch found with id '${id}'.`);
+    }
+    return stored;
+  }
+
+  private catchUp(stored: StoredBatch): Promise<void> {
+    stored.work = stored.work.then(async () => {
+      while (!FINISHED.includes(stored.batch.status) && this.now() >= stored.nextStepAt) {
+        await this.step(stored);
+        stored.nextStepAt += this.stepMs;
+        this.changes++;
+      }
+    });
+    return stored.work;
+  }
+
+  private async step(stored: StoredBatch): Promise<void> {
+    const { batch } = stored;
+    const seconds = Math.floor(this.now() / 1000);
+
+    switch (batch.status) {
+      case 'validating': {
+        // The input file may have been deleted since the batch was created
+        let content: string | undefined;
+        try {
+          content = new TextDecoder().decode(this.files.content(stored.owner, batch.input_file_id));
+        } catch {
+          // Reported as the batch's error below
+        }
+        const { requests, errors } =
+          content === undefined
+            ? {
+                requests: [],
+                errors: [{ code: 'invalid_file', message: 'The input file could not be read.', param: null, line: null }],
+              }
+            : parseBatchInput(content, batch.endpoint);
+        if (errors.length > 0) {
+          batch.status = 'failed';
+          batch.failed_at = seconds;
+          batch.errors = { object: 'list', data: errors };
+          return;
+        }
+        stored.requests = requests;
+        batch.request_counts.total = requests.length;
+        batch.status = 'in_progress';
+        batch.in_progress_at = seconds;
+        return;
+      }
+      case 'in_progress': {
+        const request = stored.requests[stored.next++];
+        if (request) {
+          await this.run(stored, request);
+        }
+        // Unless it was cancelled while the request ran
+        if (batch.status === 'in_progress' && stored.next >= stored.requests.length) {
+          batch.status = 'finalizing';
+          batch.finalizing_at = seconds;
+        }
+        return;
+      }
+      case 'finalizing':
+        this.writeResults(stored);
+        batch.status = 'completed';
+        batch.completed_at = seconds;
+        return;
+      case 'cancelling':
+        this.writeResults(stored);
+        batch.status = 'cancelled';
+        batch.cancelled_at = seconds;
+        return;
+    }
+  }
+
+  private async run(stored: StoredBatch, request: BatchRequestLine): Promise<void> {
+    const id = `batch_req_${generateRandomString(24)}`;
+    const counts = stored.batch.request_counts;
+    try {
+      const response = await this.dispatch(stored.owner, request.url, request.body);
+      const text = await response.text();
+      let body: unknown = text;
+      try {
+        body = JSON.parse(text);
+      } catch {
+        // Kept as text
+      }
+      const line = JSON.stringify({
+        id,
+        custom_id: request.custom_id,
+        response: { status_code: response.status, request_id: response.headers.get('X-Request-ID'), body },
+        error: null,
+      });
+      if (response.ok) {
+        stored.output.push(line);
+        counts.completed++;
+      } else {
+        stored.errors.push(line);
+        counts.failed++;
+      }
+    } catch (error) {
+      stored.errors.push(
+        JSON.stringify({
+          id,
+          custom_id: request.custom_id,
+          response: null,
+          error: { code: 'server_error', message: error instanceof Error ? error.message : String(error) },
+        })
+      );
+      counts.failed++;
+    }
+  }
+
+  // Results are only readable once the batch is done, as with OpenAI
+  private writeResults(stored: StoredBatch): void {
+    const { batch } = stored;
+    const write = (lines: string[], name: string) =>
+      lines.length === 0
+        ? null
+        : this.files.create(
+            stored.owner,
+            `${batch.id}_${name}.jsonl`,
+            'batch_output',
+            new TextEncoder().encode(lines.map(line => `${line}\n`).join(''))
+          ).id;
+    batch.output_file_id = write(stored.output, 'output');
+    batch.error_file_id = write(stored.errors, 'error');
+  }
+}
@@ -0,0 +1,65 @@
+import { describe, it, expect } from "vitest";
+import { parseChunking, rechunk, splitBytes } from "./chunking.js";
+import type { Chunking } from "./chunking.js";
+import { WhitespaceTokenizer } from "../tokenizer/tokenizer.js";
+
+async function collect(pieces: string[], chunking: Chunking): Promise<string[]> {
+  async function* source() {
+    yield* pieces;
+  }
+  const chunks: string[] = [];
+  for await (const chunk of rechunk(source(), chunking, new WhitespaceTokenizer())) {
+    chunks.push(chunk);
+  }
+  return chunks;
+}
+
+// Model output cut mid-word and mid-character pair
+const OUTPUT = ["Hél", "lo wo", "rld, \u{1F389} n", "aïve!"];
+
+describe("Stream chunking", () => {
+  it("should parse each strategy", () => {
+    expect(parseChunking("token")).toEqual({ type: "token" });
+    expect(parseChunking(" message ")).toEqual({ type: "message" });
+    expect(parseChunking("bytes:16")).toEqual({ type: "bytes", bytes: 16 });
+
+    for (const value of ["", "bytes", "bytes:0", "bytes:1.5", "word:2", "sentence"]) {
+      expect(() => parseChunking(value)).toThrow(/Invalid chunking/);
+    }
+  });
+
+  it("should pass model chunks through unchanged", async () => {
+    expect(await collect(OUTPUT, { type: "model" })).toEqual(OUTPUT);
+  });
+
+  it("should send one token per chunk", async () => {
+    expect(await collect(OUTPUT, { type: "token" })).toEqual(["Héllo", " world", ",", " \u{1F389}", " naïve", "!"]);
+  });
+
+  it("should send one word per chunk, whatever the model's chunks", async () => {
+    expect(await collect(OUTPUT, { type: "word" })).toEqual(["Héllo", " world,", " \u{1F389}", " naïve!"]);
+  });
+
+  it("should send the whole message as one chunk", async () => {
+    expect(await collect(OUTPUT, { type: "message" })).toEqual(["Héllo world, \u{1F389} naïve!"]);
+  });
+
+  it("should fill fixed-size chunks without splitting characters", async () => {
+    const chunks = await collect(OUTPUT, { type: "bytes", bytes: 3 });
+
+    expect(chunks.join("")).toBe(OUTPUT.join(""));
+    for (const chunk of chunks) {
+      // With the u flag, only a lone half of a surrogate pair matches
+      expect(chunk).not.toMatch(/[\uD800-\uDFFF]/u);
+      expect(new TextEncoder().encode(chunk).length <= 3 || [...chunk].length === 1).toBe(true);
+    }
+    // The emoji is 4 bytes, so it gets a chunk to itself
+    expect(chunks).toContain("\u{1F389}");
+  });
+
+  it("should give a character longer than the size a piece of its own", () => {
+    expect(splitBytes("aé\u{1F389}b", 1)).toEqual(["a", "é", "\u{1F389}", "b"]);
+    expect(splitBytes("日本語", 7)).toEqual(["日本", "語"]);
+    expect(splitBytes("", 4)).toEqual([]);
+  });
+});
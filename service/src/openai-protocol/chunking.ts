@@ -0,0 +1,112 @@
+// How streamed completions are cut into chunks
+//
+// By default each chunk is whatever the model yielded, usually a word. Clients
+// that reassemble streams can be tested against other cuttings: one token per
+// chunk, one word, a fixed number of bytes, or the whole message at once. No
+// strategy splits a character, however many bytes it takes in UTF-8.
+
+import { InvalidRequestError } from './errors.js';
+import type { Tokenizer } from '../tokenizer/tokenizer.js';
+
+// Chooses the chunking for one request, overriding the configured one
+export const CHUNKING_HEADER = 'x-teenytiny-chunking';
+
+export type Chunking =
+  | { type: 'model' }
+  | { type: 'token' }
+  | { type: 'word' }
+  // At most this many bytes, fewer where a character would be cut
+  | { type: 'bytes'; bytes: number }
+  | { type: 'message' };
+
+// Largest fixed chunk size, well past any message worth chunking
+export const MAX_CHUNK_BYTES = 1024 * 1024;
+
+/**
+ * Parses the text form of a chunking: "model", "token", "word", "bytes:16" or
+ * "message"
+ */
+export function parseChunking(value: string, param: string = CHUNKING_HEADER): Chunking {
+  const [type, size] = value.trim().split(':');
+  if (size === undefined && (type === 'model' || type === 'token' || type === 'word' || type === 'message')) {
+    return { type };
+  }
+  if (type === 'bytes' && size !== undefined && /^\d+$/.test(size)) {
+    const bytes = Number(size);
+    if (bytes >= 1 && bytes <= MAX_CHUNK_BYTES) {
+      return { type, bytes };
+    }
+  }
+  throw new InvalidRequestError(
+    `Invalid chunking '${value}': expected model, token, word, message or bytes:N with N from 1 to ${MAX_CHUNK_BYTES}`,
+    param
+  );
+}
+
+/**
+ * Re-cuts streamed text into chunks. Text that might still grow into a longer
+ * token or word is held back until the next chunk or the end of the stream.
+ */
+export async function* rechunk(
+  source: AsyncIterable<string>,
+  chunking: Chunking,
+  tokenizer: Tokenizer
+): AsyncGenerator<string> {
+  if (chunking.type === 'model') {
+    yield* source;
+    return;
+  }
+
+  let pending = '';
+  for await (const text of source) {
+    pending += text;
+    if (chunking.type === 'message') continue;
+
+    const pieces = cut(pending, chunking, tokenizer);
+    // The last piece is unfinished until more text arrives
+    pending = pieces.pop() ?? '';
+    yield* pieces;
+  }
+  if (pending) {
+    yield* chunking.type === 'message' ? [pending] : cut(pending, chunking, tokenizer);
+  }
+}
+
+function cut(text: string, chunking: Chunking, tokenizer: Tokenizer): string[] {
+  switch (chunking.type) {
+    case 'token':
+      return tokenizer.split(text);
+    case 'word':
+      // Each word with the whitespace before it, like the models write
+      return text.match(/\s*\S+\s*$|\s*\S+|\s+/g) ?? [];
+    case 'bytes':
+      return splitBytes(text, chunking.bytes);
+    default:
+      return [text];
+  }
+}
+
+// Pieces of at most `size` bytes of UTF-8, never cutting a character; a
+// character longer than `size` gets a piece to itself
+export function splitBytes(text: string, size: number): string[] {
+  const pieces: string[] = [];
+  let piece = '';
+  let pieceBytes = 0;
+  for (const char of text) {
+    const charBytes = utf8Length(char);
+    if (piece && pieceBytes + charBytes > size) {
+      pieces.push(piece);
+      piece = '';
+      pieceBytes = 0;
+    }
+    piece += char;
+    pieceBytes += charBytes;
+  }
+  if (piece) pieces.push(piece);
+  return pieces;
+}
+
+function utf8Length(char: string): number {
+  const code = char.codePointAt(0)!;
+  return code < 0x80 ? 1 : code < 0x800 ? 2 : code < 0x10000 ? 3 : 4;
+}
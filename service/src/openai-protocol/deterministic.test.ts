@@ -0,0 +1,23 @@
+import { describe, it, expect } from "vitest";
+import { stampFor } from "./deterministic.js";
+
+describe("Deterministic stamps", () => {
+  it("should give the same stamp for the same seed", () => {
+    const stamp = stampFor("golden-1");
+    expect(stampFor(" golden-1 ")).toEqual(stamp);
+    expect(stamp.id).toMatch(/^chatcmpl-[A-Za-z0-9]{29}$/);
+    expect(stamp.system_fingerprint).toMatch(/^fp_[0-9a-f]{10}$/);
+    expect(stamp.created).toBeGreaterThanOrEqual(1_700_000_000);
+    expect(stamp.created).toBeLessThan(1_800_000_000);
+  });
+
+  it("should give different stamps for different seeds", () => {
+    const stamps = ["a", "b", "golden-1", "golden-2", "🎉"].map(stampFor);
+    expect(new Set(stamps.map((stamp) => stamp.id)).size).toBe(5);
+    expect(new Set(stamps.map((stamp) => stamp.created)).size).toBe(5);
+  });
+
+  it("should reject an empty seed", () => {
+    expect(() => stampFor("  ")).toThrow(/Invalid x-teenytiny-deterministic/);
+  });
+});
@@ -0,0 +1,63 @@
+// Completion ids and timestamps derived from a seed
+//
+// A chat completion sent with `x-teenytiny-deterministic: <seed>` gets an id,
+// created timestamp and system_fingerprint that depend only on the seed, in
+// place of a random id and the current time. With models that answer the
+// same way each time, such as echo, the same request and seed then give
+// byte-identical responses, so golden tests and diffs of recordings needn't
+// scrub them.
+
+import { InvalidRequestError } from './errors.js';
+
+export const DETERMINISTIC_HEADER = 'x-teenytiny-deterministic';
+
+// What a response carries in place of its random id and current time
+export interface Stamp {
+  id: string;
+  created: number;
+  system_fingerprint: string;
+}
+
+// Seeded timestamps fall in the three years after November 2023, so they
+// look like real ones to clients that check
+const EARLIEST = 1_700_000_000;
+const SPREAD = 100_000_000;
+
+const ID_CHARSET = 'abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789';
+
+/**
+ * The stamp for a seed: any text, with surrounding whitespace ignored
+ */
+export function stampFor(seed: string): Stamp {
+  const trimmed = seed.trim();
+  if (trimmed === '') {
+    throw new InvalidRequestError(`Invalid ${DETERMINISTIC_HEADER}: expected a seed`, DETERMINISTIC_HEADER);
+  }
+
+  const random = seeded(trimmed);
+  let id = 'chatcmpl-';
+  for (let i = 0; i < 29; i++) {
+    id += ID_CHARSET.charAt(Math.floor(random() * ID_CHARSET.length));
+  }
+  const created = EARLIEST + Math.floor(random() * SPREAD);
+  let fingerprint = 'fp_';
+  for (let i = 0; i < 10; i++) {
+    fingerprint += Math.floor(random() * 16).toString(16);
+  }
+  return { id, created, system_fingerprint: fingerprint };
+}
+
+// A mulberry32 generator of floats in [0, 1), started from an FNV-1a hash of the seed
+function seeded(seed: string): () => number {
+  let state = 0x811c9dc5;
+  for (const byte of new TextEncoder().encode(seed)) {
+    state = Math.imul(state ^ byte, 0x01000193) >>> 0;
+  }
+  return () => {
+    state = (state + 0x6d2b79f5) >>> 0;
+    let t = state;
+    t = Math.imul(t ^ (t >>> 15), t | 1);
+    t ^= t + Math.imul(t ^ (t >>> 7), t | 61);
+    return ((t ^ (t >>> 14)) >>> 0) / 4294967296;
+  };
+}
@@ -0,0 +1,43 @@
+import { describe, it, expect } from "vitest";
+import { directiveError, parseDirectives, splitIntoChunks } from "./directives.js";
+
+describe("Echo directives", () => {
+  it("should strip directives and collect their values", () => {
+    const { text, directives } = parseDirectives("!delay:250 Hello !finish:length world !tokens:42");
+
+    expect(text).toBe("Hello world");
+    expect(directives).toEqual({ delayMs: 250, finishReason: "length", completionTokens: 42 });
+  });
+
+  it("should keep the message's own whitespace", () => {
+    expect(parseDirectives("  Line 1\n\tLine 2  \n").text).toBe("  Line 1\n\tLine 2  \n");
+    expect(parseDirectives("Line 1\n!delay:5\nLine 2").text).toBe("Line 1\nLine 2");
+  });
+
+  it("should leave ordinary exclamation marks alone", () => {
+    const { text, directives } = parseDirectives("Wow! !important:yes");
+
+    expect(text).toBe("Wow! !important:yes");
+    expect(directives).toEqual({});
+  });
+
+  it("should reject malformed directive values", () => {
+    expect(() => parseDirectives("!chunks:many")).toThrow(/Invalid directive !chunks:many/);
+    expect(() => parseDirectives("!finish:tool_calls")).toThrow(/expected one of/);
+    expect(() => parseDirectives("!error:200")).toThrow(/400 to 599/);
+  });
+
+  it("should map error statuses to OpenAI error types", () => {
+    expect(directiveError(429).type).toBe("rate_limit_error");
+    expect(directiveError(503).type).toBe("overloaded_error");
+    expect(directiveError(502).type).toBe("api_error");
+    expect(directiveError(500).statusCode).toBe(500);
+  });
+
+  it("should split text into exactly the requested number of chunks", () => {
+    const chunks = splitIntoChunks("Hello world", 3);
+
+    expect(chunks).toHaveLength(3);
+    expect(chunks.join("")).toBe("Hello world");
+  });
+});
@@ -0,0 +1,115 @@
+// Directive language for scripted responses
+//
+// A user message can embed directives such as "!delay:500" or "!finish:length"
+// to shape the response, letting clients simulate edge conditions from the
+// request side. Directives are stripped before the model sees the message.
+
+import { APIError, ErrorTypes, InvalidRequestError } from './errors.js';
+import type { ErrorType } from './errors.js';
+import { FAULT_KINDS, isFaultKind } from './faults.js';
+import type { FaultKind } from './faults.js';
+
+export type FinishReason = 'stop' | 'length' | 'content_filter';
+
+export interface Directives {
+  // Wait before the first content chunk
+  delayMs?: number;
+  // Deliver the content in exactly this many chunks
+  chunks?: number;
+  finishReason?: FinishReason;
+  // Fail the request with this HTTP status instead of responding
+  errorStatus?: number;
+  // Report this many completion tokens in usage
+  completionTokens?: number;
+  // Force a fault (see faults.ts), or 'none' to suppress random ones
+  fault?: FaultKind | 'none';
+}
+
+const FINISH_REASONS: readonly FinishReason[] = ['stop', 'length', 'content_filter'];
+
+// "!name:value" at the start of the message or after whitespace
+const DIRECTIVE_PATTERN = /(^|\s)!(delay|chunks|finish|error|tokens|fault):(\S*)/g;
+
+function parseInteger(name: string, value: string, min: number, max: number): number {
+  const parsed = Number(value);
+  if (!/^\d+$/.test(value) || parsed < min || parsed > max) {
+    throw new InvalidRequestError(
+      `Invalid directive !${name}:${value}: expected an integer from ${min} to ${max}`,
+      'messages'
+    );
+  }
+  return parsed;
+}
+
+export function parseDirectives(text: string): { text: string; directives: Directives } {
+  const directives: Directives = {};
+
+  let found = false;
+  const stripped = text.replace(DIRECTIVE_PATTERN, (_match, _leading: string, name: string, value: string) => {
+    found = true;
+    switch (name) {
+      case 'delay':
+        directives.delayMs = parseInteger(name, value, 0, 60000);
+        break;
+      case 'chunks':
+        directives.chunks = parseInteger(name, value, 1, 1000);
+        break;
+      case 'finish':
+        if (!(FINISH_REASONS as readonly string[]).includes(value)) {
+          throw new InvalidRequestError(
+            `Invalid directive !finish:${value}: expected one of ${FINISH_REASONS.join(', ')}`,
+            'messages'
+          );
+        }
+        directives.finishReason = value as FinishReason;
+        break;
+      case 'error':
+        directives.errorStatus = parseInteger(name, value, 400, 599);
+        break;
+      case 'tokens':
+        directives.completionTokens = parseInteger(name, value, 0, 1000000);
+        break;
+      case 'fault':
+        if (value !== 'none' && !isFaultKind(value)) {
+          throw new InvalidRequestError(
+            `Invalid directive !fault:${value}: expected one of none, ${FAULT_KINDS.join(', ')}`,
+            'messages'
+          );
+        }
+        directives.fault = value;
+        break;
+    }
+    // Each directive takes the whitespace before it along
+    return '';
+  });
+
+  // Text without directives is echoed exactly, whitespace included
+  return { text: found ? stripped.trim() : text, directives };
+}
+
+// The error OpenAI would send for a given status
+export function directiveError(status: number): APIError {
+  const types: Record<number, ErrorType> = {
+    400: ErrorTypes.INVALID_REQUEST,
+    401: ErrorTypes.AUTHENTICATION,
+    403: ErrorTypes.PERMISSION,
+    404: ErrorTypes.NOT_FOUND,
+    429: ErrorTypes.RATE_LIMIT,
+    503: ErrorTypes.OVERLOADED,
+  };
+  const type = types[status] ?? (status >= 500 ? ErrorTypes.API_ERROR : ErrorTypes.INVALID_REQUEST);
+
+  return new APIError(`Simulated error requested by !error:${status}`, type, status);
+}
+
+// Splits text into exactly `count` contiguous pieces, as evenly as possible
+export function splitIntoChunks(text: string, count: number): string[] {
+  const chars = Array.from(text);
+  const chunks: string[] = [];
+  for (let i = 0; i < count; i++) {
+    const start = Math.floor((i * chars.length) / count);
+    const end = Math.floor(((i + 1) * chars.length) / count);
+    chunks.push(chars.slice(start, end).join(''));
+  }
+  return chunks;
+}
@@ -7,6 +7,7 @@ export const ErrorTypes = {
   PERMISSION: 'permission_error',
   NOT_FOUND: 'not_found_error',
   RATE_LIMIT: 'rate_limit_error',
+  INSUFFICIENT_QUOTA: 'insufficient_quota',
   API_ERROR: 'api_error',
   OVERLOADED: 'overloaded_error',
 } as const;
@@ -35,20 +36,15 @@ export class APIError extends Error {
   }
 
   toErrorResponse(): ErrorResponse {
-    const error: any = {
-      message: this.message,
-      type: this.type,
+    // OpenAI always sends param and code, using null when they don't apply
+    return {
+      error: {
+        message: this.message,
+        type: this.type,
+        param: this.param ?? null,
+        code: this.code ?? null,
+      },
     };
-    
-    if (this.param !== undefined) {
-      error.param = this.param;
-    }
-    
-    if (this.code !== undefined) {
-      error.code = this.code;
-    }
-    
-    return { error };
   }
 }
 
@@ -61,7 +57,28 @@ export class InvalidRequestError extends APIError {
 
 export class AuthenticationError extends APIError {
   constructor(message: string = 'Invalid API key') {
-    super(message, ErrorTypes.AUTHENTICATION, 401);
+    super(message, ErrorTypes.AUTHENTICATION, 401, undefined, 'invalid_api_key');
+  }
+}
+
+// What OpenAI answers when the OpenAI-Organization or OpenAI-Project header
+// names one the API key doesn't belong to
+export class AccountMismatchError extends APIError {
+  constructor(account: 'organization' | 'project') {
+    const header = account === 'organization' ? 'OpenAI-Organization' : 'OpenAI-Project';
+    super(
+      `${header} header should match ${account} for API key`,
+      ErrorTypes.INVALID_REQUEST,
+      401,
+      undefined,
+      `mismatched_${account}`
+    );
+  }
+}
+
+export class PermissionDeniedError extends APIError {
+  constructor(message: string, code?: string) {
+    super(message, ErrorTypes.PERMISSION, 403, undefined, code);
   }
 }
 
@@ -71,6 +88,81 @@ export class NotFoundError extends APIError {
   }
 }
 
+export class ModelNotFoundError extends APIError {
+  constructor(model: string) {
+    super(
+      `The model \`${model}\` does not exist or you do not have access to it.`,
+      ErrorTypes.INVALID_REQUEST,
+      404,
+      'model',
+      'model_not_found'
+    );
+  }
+}
+
+export class RequestTooLargeError extends APIError {
+  constructor(maxBytes: number) {
+    super(
+      `Request body too large: the maximum size is ${maxBytes} bytes`,
+      ErrorTypes.INVALID_REQUEST,
+      413,
+      undefined,
+      'request_too_large'
+    );
+  }
+}
+
+export class FileTooLargeError extends APIError {
+  constructor(maxBytes: number) {
+    super(
+      `File too large: the maximum size is ${maxBytes} bytes`,
+      ErrorTypes.INVALID_REQUEST,
+      413,
+      'file',
+      'file_too_large'
+    );
+  }
+}
+
+export class UnsupportedContentEncodingError extends APIError {
+  constructor(encoding: string, supported: readonly string[]) {
+    super(
+      `Unsupported Content-Encoding: ${encoding}. Supported encodings: ${supported.join(', ')}`,
+      ErrorTypes.INVALID_REQUEST,
+      415,
+      undefined,
+      'unsupported_content_encoding'
+    );
+  }
+}
+
+export class RateLimitError extends APIError {
+  constructor(message: string) {
+    super(message, ErrorTypes.RATE_LIMIT, 429, undefined, 'rate_limit_exceeded');
+  }
+}
+
+// What OpenAI answers once an account's credit runs out. Unlike a rate limit,
+// retrying won't help until the quota is topped up.
+export class InsufficientQuotaError extends APIError {
+  constructor() {
+    super(
+      'You exceeded your current quota, please check your plan and billing details. For more information on this error, read the docs: https://platform.openai.com/docs/guides/error-codes/api-errors.',
+      ErrorTypes.INSUFFICIENT_QUOTA,
+      429,
+      undefined,
+      'insufficient_quota'
+    );
+  }
+}
+
+// What a server at capacity answers; clients should retry after Retry-After
+export class ServerOverloadedError extends APIError {
+  constructor(message: string) {
+    super(message, ErrorTypes.OVERLOADED, 503);
+  }
+}
+
 export class InternalServerError extends APIError {
   constructor(message: string = 'Internal server error') {
     super(message, ErrorTypes.API_ERROR, 500);
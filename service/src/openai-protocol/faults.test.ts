@@ -0,0 +1,75 @@
+import { describe, it, expect } from "vitest";
+import { chooseFault, faultyJsonResponse, faultyStreamResponse, raiseFault } from "./faults.js";
+import type { ChatCompletionResponse, ChatCompletionStreamResponse } from "./types.js";
+
+async function* chunks(count: number): AsyncIterable<ChatCompletionStreamResponse> {
+  for (let i = 0; i < count; i++) {
+    yield {
+      id: "chatcmpl-test",
+      object: "chat.completion.chunk",
+      created: 0,
+      model: "flaky",
+      choices: [{ index: 0, delta: { content: `chunk ${i}` } }],
+    };
+  }
+}
+
+const completion: ChatCompletionResponse = {
+  id: "chatcmpl-test",
+  object: "chat.completion",
+  created: 0,
+  model: "flaky",
+  choices: [{ index: 0, message: { role: "assistant", content: "Hello" }, finish_reason: "stop" }],
+  usage: { prompt_tokens: 1, completion_tokens: 1, total_tokens: 2 },
+};
+
+describe("Fault injection", () => {
+  it("should only fail at the configured rate", () => {
+    expect(chooseFault({ failureRate: 0.3 }, () => 0.5)).toBeUndefined();
+    expect(chooseFault({ failureRate: 0.3, kinds: ["502"] }, () => 0.1)).toBe("502");
+  });
+
+  it("should never fail with a zero rate", () => {
+    expect(chooseFault({ failureRate: 0 }, () => 0)).toBeUndefined();
+  });
+
+  it("should throw HTTP status faults and return stream faults", () => {
+    expect(() => raiseFault("503")).toThrow(expect.objectContaining({ statusCode: 503 }));
+    expect(raiseFault("reset")).toBe("reset");
+  });
+
+  it("should insert a malformed frame and still finish the stream", async () => {
+    const text = await faultyStreamResponse(chunks(4), "malformed").text();
+    const frames = text.split("\n\n").filter(Boolean);
+
+    expect(frames).toHaveLength(6);
+    expect(() => JSON.parse(frames[2]!.slice("data: ".length))).toThrow();
+    expect(frames[5]).toBe("data: [DONE]");
+  });
+
+  it("should end the stream with an error event instead of [DONE]", async () => {
+    const text = await faultyStreamResponse(chunks(4), "error_event").text();
+    const frames = text.split("\n\n").filter(Boolean);
+
+    expect(frames).toHaveLength(3);
+    expect(JSON.parse(frames[2]!.slice("data: ".length))).toEqual({
+      error: {
+        message: "Simulated error partway through the response",
+        type: "api_error",
+        param: null,
+        code: "stream_error",
+      },
+    });
+  });
+
+  it("should answer a blocking request with an error event as a 500", async () => {
+    const response = faultyJsonResponse(completion, "error_event");
+
+    expect(response.status).toBe(500);
+    expect((await response.json()).error.code).toBe("stream_error");
+  });
+
+  it("should error the body when resetting", async () => {
+    await expect(faultyStreamResponse(chunks(4), "reset").text()).rejects.toThrow(/reset/);
+  });
+});
@@ -0,0 +1,156 @@
+// Fault injection for testing client retry and resilience layers
+//
+// A fault is either an HTTP error status, a connection reset partway through
+// the response, a malformed SSE frame (or truncated JSON when not streaming),
+// or an error event partway through a stream, as OpenAI sends when generation
+// fails after the response has started. The flaky model injects them at random; any model that honors
+// directives can be forced into one with "!fault:<kind>".
+
+import { APIError, ErrorTypes, InvalidRequestError } from './errors.js';
+import type { ChatCompletionResponse, ChatCompletionStreamResponse } from './types.js';
+
+export const FAULT_KINDS = ['500', '502', '503', 'reset', 'malformed', 'error_event'] as const;
+export type FaultKind = typeof FAULT_KINDS[number];
+export type StreamFault = 'reset' | 'malformed' | 'error_event';
+
+export interface FaultConfig {
+  // Fraction of requests that fail, from 0 to 1
+  failureRate: number;
+  // Faults to choose between, defaults to all of FAULT_KINDS
+  kinds?: FaultKind[];
+}
+
+export const DEFAULT_FAULT_CONFIG: FaultConfig = { failureRate: 0.5 };
+
+// Chunks sent before a stream fault, so clients see a response start normally
+const CHUNKS_BEFORE_FAULT = 2;
+
+export function isFaultKind(value: string): value is FaultKind {
+  return (FAULT_KINDS as readonly string[]).includes(value);
+}
+
+// Changes to a FaultConfig, as PUT /admin/faults and config files give them.
+// Kinds of null go back to every kind.
+export interface FaultSettings {
+  failureRate?: number;
+  kinds?: FaultKind[] | null;
+}
+
+/**
+ * Validates {"failure_rate": 0.2, "kinds": ["503", "reset"]}, either of which
+ * may be left out
+ */
+export function parseFaultSettings(body: unknown): FaultSettings {
+  if (!body || typeof body !== 'object' || Array.isArray(body)) {
+    throw new InvalidRequestError('Invalid fault settings: expected an object with failure_rate and/or kinds');
+  }
+  const { failure_rate: rate, kinds } = body as Record<string, unknown>;
+  if (rate !== undefined && (typeof rate !== 'number' || rate < 0 || rate > 1)) {
+    throw new InvalidRequestError("Invalid 'failure_rate': expected a number from 0 to 1", 'failure_rate');
+  }
+  if (
+    kinds !== undefined &&
+    kinds !== null &&
+    (!Array.isArray(kinds) || kinds.length === 0 || !kinds.every(kind => typeof kind === 'string' && isFaultKind(kind)))
+  ) {
+    throw new InvalidRequestError(`Invalid 'kinds': expected a non-empty array of ${FAULT_KINDS.join(', ')}`, 'kinds');
+  }
+
+  const settings: FaultSettings = {};
+  if (rate !== undefined) settings.failureRate = rate;
+  if (kinds !== undefined) settings.kinds = kinds as FaultKind[] | null;
+  return settings;
+}
+
+export function chooseFault(config: FaultConfig, random: () => number = Math.random): FaultKind | undefined {
+  if (random() >= config.failureRate) {
+    return undefined;
+  }
+  const kinds = config.kinds ?? FAULT_KINDS;
+  return kinds[Math.floor(random() * kinds.length)];
+}
+
+// Throws for faults that are an HTTP status, returning the ones that happen mid-response
+export function raiseFault(kind: FaultKind): StreamFault {
+  switch (kind) {
+    case '500':
+      throw new APIError('Simulated internal server error', ErrorTypes.API_ERROR, 500);
+    case '502':
+      throw new APIError('Simulated bad gateway', ErrorTypes.API_ERROR, 502);
+    case '503':
+      throw new APIError('Simulated overload, please retry', ErrorTypes.OVERLOADED, 503);
+    default:
+      return kind;
+  }
+}
+
+const encoder = new TextEncoder();
+
+// What OpenAI sends in place of the next chunk when a stream fails partway
+function midStreamError(): APIError {
+  return new APIError('Simulated error partway through the response', ErrorTypes.API_ERROR, 500, undefined, 'stream_error');
+}
+
+// Streams the first few chunks normally, then resets the connection, sends a
+// malformed frame, or sends an error event and ends the stream without [DONE]
+export function faultyStreamResponse(
+  chunks: AsyncIterable<ChatCompletionStreamResponse>,
+  fault: StreamFault
+): Response {
+  const body = new ReadableStream<Uint8Array>({
+    async start(controller) {
+      let sent = 0;
+      for await (const chunk of chunks) {
+        if (sent === CHUNKS_BEFORE_FAULT) {
+          if (fault === 'reset') {
+            controller.error(new Error('Simulated connection reset'));
+            return;
+          }
+          if (fault === 'error_event') {
+            controller.enqueue(encoder.encode(`data: ${JSON.stringify(midStreamError().toErrorResponse())}\n\n`));
+            controller.close();
+            return;
+          }
+          controller.enqueue(encoder.encode(`data: {"id":"${chunk.id}","choices":[{"delta":{"content":\n\n`));
+        }
+        controller.enqueue(encoder.encode(`data: ${JSON.stringify(chunk)}\n\n`));
+        sent++;
+      }
+      controller.enqueue(encoder.encode('data: [DONE]\n\n'));
+      controller.close();
+    },
+  });
+
+  return new Response(body, {
+    headers: {
+      'Content-Type': 'text/event-stream',
+      'Cache-Control': 'no-cache',
+    },
+  });
+}
+
+// Sends half of the JSON body, then either resets the connection or ends it
+// there. Without a stream to break, an error event is an ordinary 500.
+export function faultyJsonResponse(response: ChatCompletionResponse, fault: StreamFault): Response {
+  if (fault === 'error_event') {
+    const error = midStreamError();
+    return Response.json(error.toErrorResponse(), { status: error.statusCode });
+  }
+  const json = JSON.stringify(response, null, 2);
+  const partial = json.slice(0, Math.floor(json.length / 2));
+
+  const body = new ReadableStream<Uint8Array>({
+    start(controller) {
+      controller.enqueue(encoder.encode(partial));
+      if (fault === 'reset') {
+        controller.error(new Error('Simulated connection reset'));
+      } else {
+        controller.close();
+      }
+    },
+  });
+
+  return new Response(body, {
+    headers: { 'Content-Type': 'application/json' },
+  });
+}
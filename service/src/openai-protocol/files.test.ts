@@ -0,0 +1,86 @@
+import { describe, it, expect } from "vitest";
+import { FileStore, parsePurpose, validateUpload } from "./files.js";
+import { FileTooLargeError, InvalidRequestError, NotFoundError } from "./errors.js";
+
+const text = (value: string) => new TextEncoder().encode(value);
+
+describe("FileStore", () => {
+  it("should describe and return uploaded files", () => {
+    const store = new FileStore();
+
+    const file = store.create("key-a", "input.jsonl", "batch", text("{}\n"));
+
+    expect(file).toMatchObject({ object: "file", bytes: 3, filename: "input.jsonl", purpose: "batch", status: "processed" });
+    expect(file.id).toMatch(/^file-/);
+    expect(store.get("key-a", file.id)).toEqual(file);
+    expect(new TextDecoder().decode(store.content("key-a", file.id))).toBe("{}\n");
+  });
+
+  it("should keep each key's files to itself", () => {
+    const store = new FileStore();
+    const file = store.create("key-a", "input.jsonl", "batch", text(""));
+
+    expect(() => store.get("key-b", file.id)).toThrow(NotFoundError);
+    expect(store.list("key-b")).toEqual([]);
+  });
+
+  it("should list newest first, optionally by purpose", () => {
+    const store = new FileStore();
+    const first = store.create("key-a", "a.jsonl", "batch", text(""));
+    const second = store.create("key-a", "b.jsonl", "batch_output", text(""));
+
+    expect(store.list("key-a").map(file => file.id)).toEqual([second.id, first.id]);
+    expect(store.list("key-a", "batch").map(file => file.id)).toEqual([first.id]);
+  });
+
+  it("should forget deleted files", () => {
+    const store = new FileStore();
+    const file = store.create("key-a", "input.jsonl", "batch", text(""));
+
+    expect(store.delete("key-a", file.id)).toEqual({ id: file.id, object: "file", deleted: true });
+    expect(() => store.content("key-a", file.id)).toThrow(NotFoundError);
+  });
+
+  it("should restore files from a snapshot, content and all", () => {
+    const store = new FileStore();
+    const file = store.create("key-a", "input.jsonl", "batch", new Uint8Array([0, 255, 10]));
+
+    const restored = new FileStore();
+    restored.restore(JSON.parse(JSON.stringify(store.snapshot())));
+
+    expect(restored.get("key-a", file.id)).toEqual(file);
+    expect(restored.content("key-a", file.id)).toEqual(new Uint8Array([0, 255, 10]));
+    expect(() => restored.get("key-b", file.id)).toThrow(NotFoundError);
+  });
+});
+
+describe("parsePurpose", () => {
+  it("should accept purposes clients may upload with", () => {
+    expect(parsePurpose("batch")).toBe("batch");
+    expect(parsePurpose("fine-tune")).toBe("fine-tune");
+  });
+
+  it("should reject missing and output-only purposes", () => {
+    expect(() => parsePurpose(undefined)).toThrow(InvalidRequestError);
+    expect(() => parsePurpose("batch_output")).toThrow(InvalidRequestError);
+  });
+});
+
+describe("validateUpload", () => {
+  it("should accept files up to the limit", () => {
+    expect(() => validateUpload("input.jsonl", "batch", text("{}"), 2)).not.toThrow();
+    expect(() => validateUpload("notes.txt", "assistants", text("hi"), 2)).not.toThrow();
+  });
+
+  it("should reject empty and oversized files", () => {
+    expect(() => validateUpload("input.jsonl", "batch", text(""), 2)).toThrow(InvalidRequestError);
+
+    const tooLarge = () => validateUpload("input.jsonl", "batch", text("{}\n"), 2);
+    expect(tooLarge).toThrow(FileTooLargeError);
+    expect(tooLarge).toThrow(expect.objectContaining({ statusCode: 413, code: "file_too_large" }));
+  });
+
+  it("should only take JSONL as batch input", () => {
+    expect(() => validateUpload("input.csv", "batch", text("a,b"), 10)).toThrow(/\.jsonl/);
+  });
+});
@@ -0,0 +1,137 @@
+// In-memory stand-in for the OpenAI Files API
+//
+// Files belong to the API key that uploaded them, so parallel test runs with
+// their own keys never see each other's uploads. Batch results are stored
+// here too, with the purpose batch_output.
+
+import { FileTooLargeError, InvalidRequestError, NotFoundError } from './errors.js';
+import { generateRandomString, getCurrentTimestamp } from './types.js';
+import { fromBase64, toBase64 } from '../utils/base64.js';
+
+// Purposes a client may upload with; batch_output files are only written by batches
+export const UPLOAD_PURPOSES = ['assistants', 'batch', 'fine-tune', 'vision'] as const;
+export type FilePurpose = typeof UPLOAD_PURPOSES[number] | 'batch_output';
+
+export interface FileObject {
+  id: string;
+  object: 'file';
+  bytes: number;
+  created_at: number;
+  filename: string;
+  purpose: FilePurpose;
+  status: 'processed';
+  status_details: null;
+}
+
+interface StoredFile {
+  owner: string;
+  file: FileObject;
+  content: Uint8Array;
+}
+
+// A stored file as JSON can hold it, with the content in base64
+export interface FileSnapshot {
+  owner: string;
+  file: FileObject;
+  content: string;
+}
+
+export function parsePurpose(value: unknown): FilePurpose {
+  if (typeof value !== 'string' || value === '') {
+    throw new InvalidRequestError('Missing required parameter: purpose', 'purpose');
+  }
+  if (!(UPLOAD_PURPOSES as readonly string[]).includes(value)) {
+    throw new InvalidRequestError(
+      `Invalid value for 'purpose': expected one of ${UPLOAD_PURPOSES.join(', ')}`,
+      'purpose'
+    );
+  }
+  return value as FilePurpose;
+}
+
+// Checks an upload before it's stored. Batch input must be JSONL, as OpenAI
+// insists on; files the server writes itself aren't checked.
+export function validateUpload(filename: string, purpose: FilePurpose, content: Uint8Array, maxBytes: number): void {
+  if (content.length === 0) {
+    throw new InvalidRequestError('File is empty', 'file');
+  }
+  if (content.length > maxBytes) {
+    throw new FileTooLargeError(maxBytes);
+  }
+  if (purpose === 'batch' && !filename.endsWith('.jsonl')) {
+    throw new InvalidRequestError('Invalid file format for Batch API: must be .jsonl', 'file');
+  }
+}
+
+export class FileStore {
+  private files = new Map<string, StoredFile>();
+  private changes = 0;
+
+  create(owner: string, filename: string, purpose: FilePurpose, content: Uint8Array): FileObject {
+    const file: FileObject = {
+      id: `file-${generateRandomString(24)}`,
+      object: 'file',
+      bytes: content.length,
+      created_at: getCurrentTimestamp(),
+      filename,
+      purpose,
+      status: 'processed',
+      status_details: null,
+    };
+    this.files.set(file.id, { owner, file, content });
+    this.changes++;
+    return file;
+  }
+
+  // Another key's file is reported missing, as OpenAI does across organizations
+  get(owner: string, id: string): FileObject {
+    return this.stored(owner, id).file;
+  }
+
+  content(owner: string, id: string): Uint8Array {
+    return this.stored(owner, id).content;
+  }
+
+  // Newest first, optionally only those with one purpose
+  list(owner: string, purpose?: string): FileObject[] {
+    return [...this.files.values()]
+      .filter(stored => stored.owner === owner && (purpose === undefined || stored.file.purpose === purpose))
+      .map(stored => stored.file)
+      .reverse();
+  }
+
+  delete(owner: string, id: string): { id: string; object: 'file'; deleted: true } {
+    this.stored(owner, id);
+    this.files.delete(id);
+    this.changes++;
+    return { id, object: 'file', deleted: true };
+  }
+
+  // Every file, oldest first, to be restored after a restart
+  snapshot(): FileSnapshot[] {
+    return [...this.files.values()].map(({ owner, file, content }) => ({
+      owner,
+      file: { ...file },
+      content: toBase64(content),
+    }));
+  }
+
+  restore(files: FileSnapshot[]): void {
+    this.files = new Map(
+      files.map(({ owner, file, content }) => [file.id, { owner, file: { ...file }, content: fromBase64(content) }])
+    );
+  }
+
+  // Goes up with every file created or deleted
+  get version(): number {
+    return this.changes;
+  }
+
+  private stored(owner: string, id: string): StoredFile {
+    const stored = this.files.get(id);
+    if (!stored || stored.owner !== owner) {
+      throw new NotFoundError(`No such File object: ${id}`);
+    }
+    return stored;
+  }
+}
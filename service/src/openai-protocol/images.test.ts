@@ -0,0 +1,31 @@
+import { describe, it, expect } from "vitest";
+import { inflateSync } from "node:zlib";
+import { parseImageSize, renderPlaceholderPng } from "./images.js";
+
+describe("Image stubs", () => {
+  it("should encode a PNG with the requested dimensions", async () => {
+    const png = await renderPlaceholderPng(256, 128);
+    const view = new DataView(png.buffer, png.byteOffset);
+
+    expect(Array.from(png.subarray(1, 4))).toEqual([0x50, 0x4e, 0x47]); // "PNG"
+    expect(view.getUint32(16)).toBe(256);
+    expect(view.getUint32(20)).toBe(128);
+  });
+
+  it("should store one filter byte plus RGB pixels per row", async () => {
+    const png = await renderPlaceholderPng(4, 2);
+    const view = new DataView(png.buffer, png.byteOffset);
+    const idatLength = view.getUint32(33);
+    const pixels = inflateSync(png.subarray(41, 41 + idatLength));
+
+    expect(pixels.length).toBe(2 * (1 + 4 * 3));
+  });
+
+  it("should default to 1024x1024", () => {
+    expect(parseImageSize(undefined)).toBe("1024x1024");
+  });
+
+  it("should reject unsupported sizes", () => {
+    expect(() => parseImageSize("300x300")).toThrow(/Invalid size/);
+  });
+});
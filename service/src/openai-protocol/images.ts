@@ -0,0 +1,100 @@
+// Stub implementation of the OpenAI image generation endpoint
+//
+// Every generated image is a solid placeholder PNG of the requested size, so
+// clients can exercise both response formats and decode real image bytes.
+
+import { InvalidRequestError } from './errors.js';
+
+export const IMAGE_SIZES = ['256x256', '512x512', '1024x1024', '1792x1024', '1024x1792'] as const;
+export type ImageSize = typeof IMAGE_SIZES[number];
+
+export function parseImageSize(value: unknown): ImageSize {
+  if (value === undefined) {
+    return '1024x1024';
+  }
+  if (typeof value !== 'string' || !(IMAGE_SIZES as readonly string[]).includes(value)) {
+    throw new InvalidRequestError(
+      `Invalid size: ${String(value)}. Supported sizes: ${IMAGE_SIZES.join(', ')}`,
+      'size'
+    );
+  }
+  return value as ImageSize;
+}
+
+export function imageDimensions(size: ImageSize): { width: number; height: number } {
+  const [width, height] = size.split('x').map(Number);
+  return { width: width!, height: height! };
+}
+
+// Encodes a solid teal RGB image as PNG
+export async function renderPlaceholderPng(width: number, height: number): Promise<Uint8Array> {
+  // Each scanline is a filter byte (0 = none) followed by RGB pixels
+  const rowLength = 1 + width * 3;
+  const pixels = new Uint8Array(rowLength * height);
+  for (let y = 0; y < height; y++) {
+    for (let x = 0; x < width; x++) {
+      pixels.set([0x2a, 0x9d, 0x8f], y * rowLength + 1 + x * 3);
+    }
+  }
+
+  const header = new Uint8Array(13);
+  const view = new DataView(header.buffer);
+  view.setUint32(0, width);
+  view.setUint32(4, height);
+  header.set([8, 2, 0, 0, 0], 8); // 8-bit depth, truecolor, default compression/filter/interlace
+
+  return concat([
+    new Uint8Array([0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a]),
+    chunk('IHDR', header),
+    chunk('IDAT', await deflate(pixels)),
+    chunk('IEND', new Uint8Array(0)),
+  ]);
+}
+
+async function deflate(data: Uint8Array): Promise<Uint8Array> {
+  const stream = new Blob([data]).stream().pipeThrough(new CompressionStream('deflate'));
+  return new Uint8Array(await new Response(stream).arrayBuffer());
+}
+
+function chunk(type: string, data: Uint8Array): Uint8Array {
+  const typeAndData = new Uint8Array(4 + data.length);
+  for (let i = 0; i < 4; i++) typeAndData[i] = type.charCodeAt(i);
+  typeAndData.set(data, 4);
+
+  const result = new Uint8Array(12 + data.length);
+  const view = new DataView(result.buffer);
+  view.setUint32(0, data.length);
+  result.set(typeAndData, 4);
+  view.setUint32(8 + data.length, crc32(typeAndData));
+  return result;
+}
+
+function concat(parts: Uint8Array[]): Uint8Array {
+  const result = new Uint8Array(parts.reduce((total, part) => total + part.length, 0));
+  let offset = 0;
+  for (const part of parts) {
+    result.set(part, offset);
+    offset += part.length;
+  }
+  return result;
+}
+
+const CRC_TABLE = (() => {
+  const table = new Uint32Array(256);
+  for (let n = 0; n < 256; n++) {
+    let c = n;
+    for (let k = 0; k < 8; k++) {
+      c = c & 1 ? 0xedb88320 ^ (c >>> 1) : c >>> 1;
+    }
+    table[n] = c >>> 0;
+  }
+  return table;
+})();
+
+function crc32(data: Uint8Array): number {
+  let crc = 0xffffffff;
+  for (const byte of data) {
+    crc = CRC_TABLE[(crc ^ byte) & 0xff]! ^ (crc >>> 8);
+  }
+  return (crc ^ 0xffffffff) >>> 0;
+}
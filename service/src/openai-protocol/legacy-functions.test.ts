@@ -0,0 +1,98 @@
+import { describe, it, expect } from "vitest";
+import { fromLegacyFunctions, toLegacyResponse, toLegacyStream } from "./legacy-functions.js";
+import { OpenAIAdapter } from "./adapter.js";
+import { EchoModel } from "../models/echo-model.js";
+import type { ChatCompletionRequest, ChatCompletionStreamResponse } from "./types.js";
+
+const weather = {
+  name: "get_weather",
+  parameters: {
+    type: "object",
+    properties: { city: { type: "string" } },
+    required: ["city"],
+  },
+};
+
+function request(overrides: Partial<ChatCompletionRequest> = {}): ChatCompletionRequest {
+  return {
+    model: "tooluse",
+    messages: [{ role: "user", content: "What is the weather?" }],
+    functions: [weather, { name: "get_time" }],
+    ...overrides,
+  };
+}
+
+describe("Legacy functions", () => {
+  const adapter = new OpenAIAdapter(new EchoModel(), "tooluse", { toolCalls: true });
+
+  it("should rewrite functions as tools making one call", () => {
+    const req = request({ function_call: { name: "get_time" } });
+
+    expect(fromLegacyFunctions(req)).toBe(true);
+    expect(req.tools).toEqual([
+      { type: "function", function: weather },
+      { type: "function", function: { name: "get_time" } },
+    ]);
+    expect(req.tool_choice).toEqual({ type: "function", function: { name: "get_time" } });
+    expect(req.parallel_tool_calls).toBe(false);
+    expect(req.functions).toBeUndefined();
+    expect(req.function_call).toBeUndefined();
+  });
+
+  it("should leave requests without functions alone", () => {
+    const req = request({ functions: undefined });
+
+    expect(fromLegacyFunctions(req)).toBe(false);
+    expect(req.tools).toBeUndefined();
+  });
+
+  it("should rewrite function calls and results as tool messages", () => {
+    const req = request({
+      messages: [
+        { role: "user", content: "What is the weather?" },
+        { role: "assistant", content: null, function_call: { name: "get_weather", arguments: "{}" } },
+        { role: "function", name: "get_weather", content: "Sunny" },
+      ] as unknown as ChatCompletionRequest["messages"],
+    });
+
+    fromLegacyFunctions(req);
+
+    expect(req.messages[1]?.tool_calls).toEqual([
+      { id: "call_legacy_1", type: "function", function: { name: "get_weather", arguments: "{}" } },
+    ]);
+    expect(req.messages[2]).toEqual({ role: "tool", tool_call_id: "call_legacy_1", content: "Sunny" });
+  });
+
+  it("should reject function messages without a name", () => {
+    const req = request({
+      messages: [{ role: "function", content: "Sunny" }] as unknown as ChatCompletionRequest["messages"],
+    });
+
+    expect(() => fromLegacyFunctions(req)).toThrow(/name/);
+  });
+
+  it("should answer with a function_call", async () => {
+    const req = request();
+    fromLegacyFunctions(req);
+    const response = toLegacyResponse(await adapter.complete(req));
+
+    expect(response.choices[0]?.finish_reason).toBe("function_call");
+    expect(response.choices[0]?.message.tool_calls).toBeUndefined();
+    expect(response.choices[0]?.message.function_call?.name).toBe("get_weather");
+  });
+
+  it("should stream function_call deltas", async () => {
+    const req = request();
+    fromLegacyFunctions(req);
+    const chunks: ChatCompletionStreamResponse[] = [];
+    for await (const chunk of toLegacyStream(adapter.completeStream(req))) {
+      chunks.push(chunk);
+    }
+
+    expect(chunks.some(chunk => chunk.choices[0]?.delta.tool_calls)).toBe(false);
+    expect(chunks[1]?.choices[0]?.delta.function_call).toEqual({ name: "get_weather", arguments: "" });
+    const args = chunks.map(chunk => chunk.choices[0]?.delta.function_call?.arguments ?? "").join("");
+    expect(JSON.parse(args)).toHaveProperty("city");
+    expect(chunks[chunks.length - 1]?.choices[0]?.finish_reason).toBe("function_call");
+  });
+});
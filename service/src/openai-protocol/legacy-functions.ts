@@ -0,0 +1,102 @@
+// The deprecated functions / function_call parameters
+//
+// Before tools, chat completions took 'functions' and 'function_call', and a
+// model answered with a single message.function_call. Older SDKs and LangChain
+// releases still send them, so a legacy request is rewritten as the equivalent
+// tools request, and the completion is rewritten back into the old shape.
+
+import { InvalidRequestError } from './errors.js';
+import type {
+  ChatCompletionRequest,
+  ChatCompletionResponse,
+  ChatCompletionStreamResponse,
+  ChatCompletionTool,
+  ChatCompletionToolChoice,
+} from './types.js';
+
+// Legacy calls have no ids, so each is named after the message that made it
+function legacyCallId(index: number): string {
+  return `call_legacy_${index}`;
+}
+
+/**
+ * Rewrites the legacy parts of a validated request as tools in place:
+ * assistant function_call messages become tool_calls, and function messages
+ * become tool results. Returns whether the answer should use the legacy shape.
+ */
+export function fromLegacyFunctions(request: ChatCompletionRequest): boolean {
+  let lastCallId: string | undefined;
+  request.messages.forEach((message: any, i) => {
+    if (!message || typeof message !== 'object') {
+      return;
+    }
+    if (message.role === 'assistant' && message.function_call !== undefined && message.function_call !== null) {
+      const call = message.function_call;
+      if (typeof call?.name !== 'string' || typeof call.arguments !== 'string') {
+        throw new InvalidRequestError(
+          `Invalid message at index ${i}: 'function_call' needs a name and string arguments`,
+          'messages'
+        );
+      }
+      lastCallId = legacyCallId(i);
+      message.tool_calls = [{ id: lastCallId, type: 'function', function: { name: call.name, arguments: call.arguments } }];
+      delete message.function_call;
+    } else if (message.role === 'function') {
+      if (typeof message.name !== 'string') {
+        throw new InvalidRequestError(`Invalid message at index ${i}: function messages require 'name'`, 'messages');
+      }
+      message.role = 'tool';
+      message.tool_call_id = lastCallId ?? legacyCallId(i);
+      delete message.name;
+    }
+  });
+
+  const { functions, function_call: choice } = request;
+  delete request.functions;
+  delete request.function_call;
+  if (functions === undefined || functions === null) {
+    return false;
+  }
+
+  request.tools = functions.map((fn): ChatCompletionTool => ({ type: 'function', function: fn }));
+  if (choice !== undefined && choice !== null) {
+    const toolChoice: ChatCompletionToolChoice =
+      typeof choice === 'string' ? choice : { type: 'function', function: { name: choice.name } };
+    request.tool_choice = toolChoice;
+  }
+  // A legacy answer holds one call at most
+  request.parallel_tool_calls = false;
+  return true;
+}
+
+// Turns the tool calls of a completion into a legacy function_call
+export function toLegacyResponse(response: ChatCompletionResponse): ChatCompletionResponse {
+  for (const choice of response.choices) {
+    const { tool_calls: calls, ...message } = choice.message;
+    const call = calls?.[0];
+    if (call) {
+      choice.message = { ...message, function_call: call.function };
+      choice.finish_reason = 'function_call';
+    }
+  }
+  return response;
+}
+
+// Turns streamed tool call deltas into legacy function_call deltas
+export async function* toLegacyStream(
+  chunks: AsyncIterable<ChatCompletionStreamResponse>
+): AsyncIterable<ChatCompletionStreamResponse> {
+  for await (const chunk of chunks) {
+    for (const choice of chunk.choices) {
+      const { tool_calls: deltas, ...delta } = choice.delta;
+      const call = deltas?.[0];
+      if (call) {
+        choice.delta = { ...delta, function_call: call.function };
+      }
+      if (choice.finish_reason === 'tool_calls') {
+        choice.finish_reason = 'function_call';
+      }
+    }
+    yield chunk;
+  }
+}
@@ -0,0 +1,75 @@
+import { describe, it, expect } from "vitest";
+import { applyModelDefaults, defaultsFor, parseModelDefaults } from "./model-defaults.js";
+import type { ChatCompletionRequest } from "./types.js";
+
+function request(overrides: Partial<ChatCompletionRequest> = {}): ChatCompletionRequest {
+  return { model: "echo", messages: [{ role: "user", content: "Hello" }], ...overrides };
+}
+
+describe("Model defaults", () => {
+  it("should parse each setting", () => {
+    const config = { echo: { max_tokens: 50, temperature: 0, system_prompt: "Be brief" } };
+
+    expect(parseModelDefaults(config)).toEqual(config);
+    expect(parseModelDefaults({})).toEqual({});
+  });
+
+  it("should reject invalid settings", () => {
+    for (const config of [
+      null,
+      [],
+      { echo: 5 },
+      { echo: { max_tokens: 0 } },
+      { echo: { max_tokens: 1.5 } },
+      { echo: { temperature: 3 } },
+      { echo: { system_prompt: 42 } },
+      { echo: { top_p: 1 } },
+    ]) {
+      expect(() => parseModelDefaults(config)).toThrow(/model defaults|Invalid|Unknown/);
+    }
+  });
+
+  it("should merge the settings for every model under the model's own", () => {
+    const config = { "*": { max_tokens: 100, temperature: 1 }, echo: { max_tokens: 10 } };
+
+    expect(defaultsFor(config, "echo")).toEqual({ max_tokens: 10, temperature: 1 });
+    expect(defaultsFor(config, "eliza")).toEqual({ max_tokens: 100, temperature: 1 });
+    expect(defaultsFor({}, "echo")).toEqual({});
+  });
+
+  it("should clamp max_tokens and set it when missing", () => {
+    const high = request({ max_tokens: 500 });
+    expect(applyModelDefaults(high, { max_tokens: 50 })).toEqual(["max_tokens"]);
+    expect(high.max_tokens).toBe(50);
+
+    const missing = request();
+    expect(applyModelDefaults(missing, { max_tokens: 50 })).toEqual(["max_tokens"]);
+    expect(missing.max_tokens).toBe(50);
+
+    const low = request({ max_tokens: 5 });
+    expect(applyModelDefaults(low, { max_tokens: 50 })).toEqual([]);
+    expect(low.max_tokens).toBe(5);
+
+    const newer = request({ max_completion_tokens: 500 });
+    expect(applyModelDefaults(newer, { max_tokens: 50 })).toEqual(["max_completion_tokens"]);
+    expect(newer.max_completion_tokens).toBe(50);
+  });
+
+  it("should force the temperature", () => {
+    const req = request({ temperature: 1.5 });
+
+    expect(applyModelDefaults(req, { temperature: 0 })).toEqual(["temperature"]);
+    expect(req.temperature).toBe(0);
+    expect(applyModelDefaults(req, { temperature: 0 })).toEqual([]);
+  });
+
+  it("should add the system prompt unless the request has one", () => {
+    const req = request();
+    expect(applyModelDefaults(req, { system_prompt: "Be brief" })).toEqual(["system_prompt"]);
+    expect(req.messages[0]).toEqual({ role: "system", content: "Be brief" });
+
+    const own = request({ messages: [{ role: "system", content: "Be verbose" }, { role: "user", content: "Hi" }] });
+    expect(applyModelDefaults(own, { system_prompt: "Be brief" })).toEqual([]);
+    expect(own.messages).toHaveLength(2);
+  });
+});
@@ -0,0 +1,109 @@
+// Operator-set defaults and clamps per model
+//
+// Lets a server stand in for a provider whose models cap max_tokens, fix the
+// temperature or carry a system prompt, without every client sending them.
+// Settings under "*" apply to every model, and a model's own entry wins.
+
+import { InvalidRequestError } from './errors.js';
+import type { ChatCompletionRequest } from './types.js';
+
+// Names the request parameters a response's defaults changed, e.g. "max_tokens, temperature"
+export const MODEL_DEFAULTS_HEADER = 'x-teenytiny-model-defaults';
+
+export interface ModelDefaults {
+  // Largest max_tokens a request gets, and what it gets when it sets none
+  max_tokens?: number;
+  // Replaces whatever temperature the request asked for
+  temperature?: number;
+  // Sent first when the request has no system message of its own
+  system_prompt?: string;
+}
+
+// Defaults by model name as requested, or "*" for every model
+export type ModelDefaultsConfig = Record<string, ModelDefaults>;
+
+/**
+ * Validates a model defaults config, as given to --model-defaults or
+ * PUT /admin/model-defaults
+ */
+export function parseModelDefaults(body: unknown): ModelDefaultsConfig {
+  if (!body || typeof body !== 'object' || Array.isArray(body)) {
+    throw new InvalidRequestError('Invalid model defaults: expected an object of model names to settings');
+  }
+
+  const config: ModelDefaultsConfig = {};
+  for (const [model, settings] of Object.entries(body)) {
+    if (!settings || typeof settings !== 'object' || Array.isArray(settings)) {
+      throw new InvalidRequestError(
+        `Invalid settings for '${model}': expected an object with max_tokens, temperature and/or system_prompt`,
+        model
+      );
+    }
+    const parsed: ModelDefaults = {};
+    for (const [name, value] of Object.entries(settings)) {
+      const param = `${model}.${name}`;
+      switch (name) {
+        case 'max_tokens':
+          if (!Number.isInteger(value) || value < 1) {
+            throw new InvalidRequestError(`Invalid '${param}': expected a positive integer`, param);
+          }
+          parsed.max_tokens = value;
+          break;
+        case 'temperature':
+          if (typeof value !== 'number' || value < 0 || value > 2) {
+            throw new InvalidRequestError(`Invalid '${param}': expected a number from 0 to 2`, param);
+          }
+          parsed.temperature = value;
+          break;
+        case 'system_prompt':
+          if (typeof value !== 'string') {
+            throw new InvalidRequestError(`Invalid '${param}': expected a string`, param);
+          }
+          parsed.system_prompt = value;
+          break;
+        default:
+          throw new InvalidRequestError(
+            `Unknown setting '${param}': expected max_tokens, temperature or system_prompt`,
+            param
+          );
+      }
+    }
+    config[model] = parsed;
+  }
+  return config;
+}
+
+// The settings in effect for a model
+export function defaultsFor(config: ModelDefaultsConfig, model: string): ModelDefaults {
+  return { ...config['*'], ...config[model] };
+}
+
+/**
+ * Applies a model's defaults to a validated request in place, returning the
+ * names of the parameters that changed
+ */
+export function applyModelDefaults(request: ChatCompletionRequest, defaults: ModelDefaults): string[] {
+  const applied: string[] = [];
+
+  if (defaults.max_tokens !== undefined) {
+    // Clamp whichever limit the request uses
+    const field = typeof request.max_completion_tokens === 'number' ? 'max_completion_tokens' : 'max_tokens';
+    const requested = request[field];
+    if (typeof requested !== 'number' || requested > defaults.max_tokens) {
+      request[field] = defaults.max_tokens;
+      applied.push(field);
+    }
+  }
+
+  if (defaults.temperature !== undefined && request.temperature !== defaults.temperature) {
+    request.temperature = defaults.temperature;
+    applied.push('temperature');
+  }
+
+  if (defaults.system_prompt !== undefined && !request.messages.some(message => message.role === 'system')) {
+    request.messages.unshift({ role: 'system', content: defaults.system_prompt });
+    applied.push('system_prompt');
+  }
+
+  return applied;
+}
@@ -0,0 +1,52 @@
+import { describe, it, expect } from "vitest";
+import { KeywordModerator, MODERATION_CATEGORIES } from "./moderations.js";
+
+describe("KeywordModerator", () => {
+  it("should flag inputs containing configured keywords", () => {
+    const moderator = new KeywordModerator({ violence: ["punch"] });
+
+    const response = moderator.moderate("I will PUNCH the wall");
+
+    expect(response.results[0]!.flagged).toBe(true);
+    expect(response.results[0]!.categories.violence).toBe(true);
+    expect(response.results[0]!.categories.hate).toBe(false);
+  });
+
+  it("should not flag clean inputs", () => {
+    const moderator = new KeywordModerator();
+
+    const response = moderator.moderate("What a lovely day");
+
+    expect(response.results[0]!.flagged).toBe(false);
+  });
+
+  it("should return one result per array entry", () => {
+    const moderator = new KeywordModerator();
+
+    const response = moderator.moderate(["hello", "you idiot", "bye"]);
+
+    expect(response.results.map(result => result.flagged)).toEqual([false, true, false]);
+  });
+
+  it("should flag every category with flag-everything", () => {
+    const moderator = new KeywordModerator();
+
+    const response = moderator.moderate("harmless", "flag-everything");
+
+    expect(Object.values(response.results[0]!.categories)).toEqual(
+      MODERATION_CATEGORIES.map(() => true),
+    );
+  });
+
+  it("should reject unknown models", () => {
+    const moderator = new KeywordModerator();
+
+    expect(() => moderator.moderate("hi", "gpt-4")).toThrow(/Model not found/);
+  });
+
+  it("should reject malformed input", () => {
+    const moderator = new KeywordModerator();
+
+    expect(() => moderator.moderate(42)).toThrow(/Invalid input/);
+  });
+});
@@ -0,0 +1,132 @@
+// Stub implementation of the OpenAI moderation endpoint
+//
+// Inputs are flagged by case-insensitive keyword matches, which makes it easy
+// to drive both the flagged and unflagged code paths of client applications.
+
+import { InvalidRequestError } from './errors.js';
+import { generateRandomString } from './types.js';
+
+export const MODERATION_CATEGORIES = [
+  'harassment',
+  'harassment/threatening',
+  'hate',
+  'hate/threatening',
+  'illicit',
+  'illicit/violent',
+  'self-harm',
+  'self-harm/intent',
+  'self-harm/instructions',
+  'sexual',
+  'sexual/minors',
+  'violence',
+  'violence/graphic',
+] as const;
+export type ModerationCategory = typeof MODERATION_CATEGORIES[number];
+
+export type ModerationKeywords = Partial<Record<ModerationCategory, string[]>>;
+
+export const DEFAULT_MODERATION_KEYWORDS: ModerationKeywords = {
+  'harassment': ['idiot', 'loser'],
+  'harassment/threatening': ['watch your back'],
+  'hate': ['hateful'],
+  'illicit': ['shoplift'],
+  'self-harm': ['self-harm'],
+  'violence': ['kill', 'attack'],
+  'violence/graphic': ['gore'],
+};
+
+// Categories OpenAI also evaluates on images
+const IMAGE_CATEGORIES: readonly ModerationCategory[] = [
+  'self-harm',
+  'self-harm/intent',
+  'self-harm/instructions',
+  'sexual',
+  'violence',
+  'violence/graphic',
+];
+
+// flag-everything flags every category for every input, regardless of keywords
+export const MODERATION_MODELS = [
+  'omni-moderation-latest',
+  'text-moderation-latest',
+  'text-moderation-stable',
+  'flag-everything',
+] as const;
+
+interface ModerationInput {
+  text: string;
+  hasImage: boolean;
+}
+
+export interface ModerationResult {
+  flagged: boolean;
+  categories: Record<ModerationCategory, boolean>;
+  category_scores: Record<ModerationCategory, number>;
+  category_applied_input_types: Record<ModerationCategory, string[]>;
+}
+
+export class KeywordModerator {
+  constructor(private keywords: ModerationKeywords = DEFAULT_MODERATION_KEYWORDS) {}
+
+  moderate(input: unknown, model: string = 'omni-moderation-latest') {
+    if (!(MODERATION_MODELS as readonly string[]).includes(model)) {
+      throw new InvalidRequestError(`Model not found: ${model}`, 'model');
+    }
+
+    return {
+      id: `modr-${generateRandomString(24)}`,
+      model,
+      results: parseInputs(input).map(entry => this.classify(entry, model === 'flag-everything')),
+    };
+  }
+
+  private classify(input: ModerationInput, flagEverything: boolean): ModerationResult {
+    const text = input.text.toLowerCase();
+    const categories = {} as Record<ModerationCategory, boolean>;
+    const scores = {} as Record<ModerationCategory, number>;
+    const appliedTypes = {} as Record<ModerationCategory, string[]>;
+
+    for (const category of MODERATION_CATEGORIES) {
+      const matched = flagEverything ||
+        (this.keywords[category] ?? []).some(keyword => text.includes(keyword.toLowerCase()));
+
+      categories[category] = matched;
+      scores[category] = matched ? 0.99 : 0.0001;
+      appliedTypes[category] = input.hasImage && IMAGE_CATEGORIES.includes(category)
+        ? ['text', 'image']
+        : ['text'];
+    }
+
+    return {
+      flagged: Object.values(categories).some(Boolean),
+      categories,
+      category_scores: scores,
+      category_applied_input_types: appliedTypes,
+    };
+  }
+}
+
+// Accepts a string, an array of strings, or an array of multimodal parts
+function parseInputs(input: unknown): ModerationInput[] {
+  if (typeof input === 'string') {
+    return [{ text: input, hasImage: false }];
+  }
+
+  if (Array.isArray(input) && input.length > 0) {
+    if (input.every(item => typeof item === 'string')) {
+      return input.map(text => ({ text, hasImage: false }));
+    }
+
+    if (input.every(item => item && (item.type === 'text' || item.type === 'image_url'))) {
+      return [{
+        text: input.filter(part => part.type === 'text').map(part => String(part.text)).join('\n'),
+        hasImage: input.some(part => part.type === 'image_url'),
+      }];
+    }
+  }
+
+  throw new InvalidRequestError(
+    "Invalid input: must be a string, an array of strings, or an array of content parts",
+    'input'
+  );
+}
@@ -2,32 +2,116 @@ import type { Model as OpenAIModel, ModelsResponse } from './types.js';
 import { ModelRegistry } from '../models/model-registry.js';
 import { Model } from '../models/model.js';
 import { OpenAIAdapter } from './adapter.js';
+import type { AdapterOptions } from './adapter.js';
+import { WhitespaceTokenizer } from '../tokenizer/tokenizer.js';
+import type { Tokenizer } from '../tokenizer/tokenizer.js';
 
 // OpenAI-specific model registry that wraps the core registry
 export class OpenAIModelRegistry {
   private adapters = new Map<string, OpenAIAdapter>();
+  private options = new Map<string, AdapterOptions>();
+  private variants = new Map<string, (suffix: string) => Model | undefined>();
+  private aliases = new Map<string, string>();
+  // Names clients may use, or undefined for every registered model
+  private enabled: Set<string> | undefined;
 
-  constructor(private coreRegistry: ModelRegistry) {}
+  // Every model's usage is counted with the same tokenizer
+  constructor(
+    private coreRegistry: ModelRegistry,
+    private tokenizer: Tokenizer = new WhitespaceTokenizer()
+  ) {}
 
-  register(id: string, model: Model): void {
+  register(id: string, model: Model, options: AdapterOptions = {}): void {
     // Register in core registry
     this.coreRegistry.register(id, model);
     
     // Create OpenAI adapter
-    const adapter = new OpenAIAdapter(model, id);
+    const adapter = new OpenAIAdapter(model, id, options, this.tokenizer);
     this.adapters.set(id, adapter);
+    this.options.set(id, options);
+  }
+
+  // Make an existing model reachable under another name. Responses report the
+  // alias, as OpenAI does when a client asks for a model by alias.
+  alias(alias: string, targetId: string): void {
+    const model = this.coreRegistry.get(targetId);
+    if (!model) {
+      throw new Error(`Cannot alias ${alias} to unknown model ${targetId}`);
+    }
+    this.adapters.set(alias, new OpenAIAdapter(model, alias, this.options.get(targetId), this.tokenizer));
+    this.aliases.set(alias, targetId);
+  }
+
+  // Every name a client can use: registered ids, aliases with their targets,
+  // and the prefixes that accept "id:suffix" variants
+  describe(): { models: string[]; aliases: Record<string, string>; variants: string[] } {
+    return {
+      models: this.coreRegistry.getIds(),
+      aliases: Object.fromEntries(this.aliases),
+      variants: [...this.variants.keys()],
+    };
+  }
+
+  // Makes "id:suffix" names resolve to a model built from the suffix, e.g.
+  // "slow:200". The factory returns undefined for suffixes it doesn't accept.
+  registerVariants(id: string, factory: (suffix: string) => Model | undefined): void {
+    this.variants.set(id, factory);
+  }
+
+  // Limits clients to the given models, or lifts the limit when undefined. An
+  // alias follows its target and "id:suffix" variants follow their prefix,
+  // unless they are named themselves.
+  enable(ids: string[] | undefined): void {
+    this.enabled = ids && new Set(ids);
+  }
+
+  isEnabled(id: string): boolean {
+    if (!this.enabled || this.enabled.has(id)) {
+      return true;
+    }
+    const target = this.aliases.get(id);
+    if (target !== undefined) {
+      return this.enabled.has(target);
+    }
+    const separator = id.indexOf(':');
+    return separator >= 0 && this.variants.has(id.slice(0, separator)) && this.enabled.has(id.slice(0, separator));
   }
 
   get(id: string): OpenAIAdapter | undefined {
-    return this.adapters.get(id);
+    if (!this.isEnabled(id)) {
+      return undefined;
+    }
+    const adapter = this.adapters.get(id);
+    if (adapter) {
+      return adapter;
+    }
+
+    const separator = id.indexOf(':');
+    if (separator < 0) {
+      return undefined;
+    }
+    const factory = this.variants.get(id.slice(0, separator));
+    const model = factory?.(id.slice(separator + 1));
+    return model && new OpenAIAdapter(model, id, {}, this.tokenizer);
+  }
+
+  // The model behind an id, alias or variant, whether or not clients may use
+  // it, for models that hand requests to others
+  model(id: string): Model | undefined {
+    const model = this.coreRegistry.get(this.aliases.get(id) ?? id);
+    if (model) {
+      return model;
+    }
+    const separator = id.indexOf(':');
+    return separator < 0 ? undefined : this.variants.get(id.slice(0, separator))?.(id.slice(separator + 1));
   }
 
   has(id: string): boolean {
-    return this.coreRegistry.has(id);
+    return this.coreRegistry.has(id) && this.isEnabled(id);
   }
 
   list(): OpenAIModel[] {
-    return this.coreRegistry.getIds().map(id => {
+    return this.coreRegistry.getIds().filter(id => this.isEnabled(id)).map(id => {
       const meta = this.coreRegistry.getMetadata(id)!;
       return {
         id,
@@ -0,0 +1,62 @@
+import { describe, it, expect } from "vitest";
+import { OutputLimiter } from "./output-limits.js";
+
+function run(limiter: OutputLimiter, chunks: string[]): string {
+  let out = "";
+  for (const chunk of chunks) {
+    out += limiter.push(chunk);
+    if (limiter.done) return out;
+  }
+  return out + limiter.flush();
+}
+
+describe("OutputLimiter", () => {
+  it("should pass output through when unlimited", () => {
+    const limiter = new OutputLimiter(undefined);
+
+    expect(run(limiter, ["Hello", " world"])).toBe("Hello world");
+    expect(limiter.finishReason).toBeUndefined();
+  });
+
+  it("should cut output at the character budget", () => {
+    const limiter = new OutputLimiter(8);
+
+    expect(run(limiter, ["Hello", " world"])).toBe("Hello wo");
+    expect(limiter.finishReason).toBe("length");
+  });
+
+  it("should stop before a stop sequence split across chunks", () => {
+    const limiter = new OutputLimiter(undefined, ["END"]);
+
+    expect(run(limiter, ["one two E", "ND three"])).toBe("one two ");
+    expect(limiter.finishReason).toBe("stop");
+  });
+
+  it("should use the earliest of several stop sequences", () => {
+    const limiter = new OutputLimiter(undefined, ["three", "two"]);
+
+    expect(run(limiter, ["one two three"])).toBe("one ");
+  });
+
+  it("should prefer length when the budget runs out before the stop sequence", () => {
+    const limiter = new OutputLimiter(3, ["two"]);
+
+    expect(run(limiter, ["one two"])).toBe("one");
+    expect(limiter.finishReason).toBe("length");
+  });
+
+  it("should measure the budget with the given tokenizer", () => {
+    const words = (text: string) => text.split(" ").filter(Boolean).length;
+    const limiter = new OutputLimiter(2, [], words);
+
+    expect(run(limiter, ["one ", "two three", " four"])).toBe("one two ");
+    expect(limiter.finishReason).toBe("length");
+  });
+
+  it("should release held back text that was not a stop sequence", () => {
+    const limiter = new OutputLimiter(undefined, ["END"]);
+
+    expect(run(limiter, ["ends with EN"])).toBe("ends with EN");
+    expect(limiter.finishReason).toBeUndefined();
+  });
+});
@@ -0,0 +1,106 @@
+// Applies max_tokens and stop sequences to model output as it streams
+//
+// The budget is measured with the same tokenizer as usage accounting, so
+// completion_tokens never exceeds max_tokens. Text that could be the start of
+// a stop sequence is held back until the next chunk shows whether it is.
+
+export class OutputLimiter {
+  private pending = '';
+  private emitted = '';
+  // Tokens emitted so far, counted chunk by chunk and recounted exactly once
+  // that says the budget is used up
+  private spent = 0;
+  private holdback: number;
+  finishReason: 'stop' | 'length' | undefined;
+
+  constructor(
+    private budget: number | undefined,
+    private stops: string[] = [],
+    private measure: (text: string) => number = text => text.length
+  ) {
+    this.holdback = Math.max(0, ...stops.map(stop => stop.length - 1));
+  }
+
+  get done(): boolean {
+    return this.finishReason !== undefined;
+  }
+
+  // Returns the part of the chunk that can be sent now
+  push(chunk: string): string {
+    if (this.done) {
+      return '';
+    }
+    this.pending += chunk;
+
+    const stopIndex = this.findStop();
+    if (stopIndex >= 0) {
+      this.finishReason = 'stop';
+      return this.release(this.pending.length - stopIndex);
+    }
+    return this.release(this.holdback);
+  }
+
+  // Returns whatever was held back once the model has finished
+  flush(): string {
+    if (this.done) {
+      return '';
+    }
+    return this.release(0);
+  }
+
+  private findStop(): number {
+    let earliest = -1;
+    for (const stop of this.stops) {
+      const index = this.pending.indexOf(stop);
+      if (index >= 0 && (earliest < 0 || index < earliest)) {
+        earliest = index;
+      }
+    }
+    return earliest;
+  }
+
+  // Sends all but the last `keep` pending characters (dropping them if finished), within the budget
+  private release(keep: number): string {
+    let out = this.pending.slice(0, Math.max(0, this.pending.length - keep));
+    this.pending = this.done ? '' : this.pending.slice(out.length);
+
+    if (this.budget !== undefined) {
+      this.spent += this.measure(out);
+      if (this.spent > this.budget) {
+        this.spent = this.measure(this.emitted + out);
+      }
+      if (this.spent > this.budget) {
+        out = out.slice(0, this.fit(out));
+        this.pending = '';
+        this.finishReason = 'length';
+      }
+    }
+
+    this.emitted += out;
+    return out;
+  }
+
+  // The longest prefix of text that still fits in the budget, without
+  // splitting a surrogate pair
+  private fit(text: string): number {
+    let low = 0;
+    let high = text.length;
+    while (low < high) {
+      const mid = Math.ceil((low + high) / 2);
+      if (this.measure(this.emitted + text.slice(0, mid)) <= this.budget!) {
+        low = mid;
+      } else {
+        high = mid - 1;
+      }
+    }
+    const code = text.charCodeAt(low - 1);
+    return code >= 0xd800 && code <= 0xdbff ? low - 1 : low;
+  }
+}
+
+export function normalizeStop(stop: string | string[] | null | undefined): string[] {
+  if (!stop) {
+    return [];
+  }
+  return (Array.isArray(stop) ? stop : [stop]).filter(s => s.length > 0);
+}
@@ -0,0 +1,73 @@
+import { describe, it, expect } from "vitest";
+import { PromptCache, withCachedTokens } from "./prompt-cache.js";
+import { WhitespaceTokenizer } from "../tokenizer/tokenizer.js";
+import type { ChatCompletionRequestMessage } from "./types.js";
+
+// A user message of this many distinct words
+function words(count: number, from = 0): string {
+  return Array.from({ length: count }, (_, i) => `w${from + i}`).join(" ");
+}
+
+function prompt(...contents: string[]): ChatCompletionRequestMessage[] {
+  return contents.map(content => ({ role: "user", content }));
+}
+
+describe("PromptCache", () => {
+  const tokenizer = new WhitespaceTokenizer();
+
+  it("should not cache short prompts", () => {
+    const cache = new PromptCache(tokenizer);
+    const messages = prompt(words(100));
+
+    expect(cache.use("key", messages, 100)).toBeUndefined();
+    expect(cache.use("key", messages, 100)).toBeUndefined();
+  });
+
+  it("should report a repeated prompt as cached in 128-token steps", () => {
+    const cache = new PromptCache(tokenizer);
+    const messages = prompt(words(1300));
+
+    expect(cache.use("key", messages, 1310)).toBe(0);
+    const cached = cache.use("key", messages, 1310);
+    expect(cached).toBe(1280);
+  });
+
+  it("should only count the shared prefix", () => {
+    const cache = new PromptCache(tokenizer);
+    const system = words(1100);
+
+    cache.use("key", prompt(system, "first question"), 1120);
+    expect(cache.use("key", prompt(system, words(500, 5000)), 1620)).toBe(1024);
+    // The longer conversation is now cached too
+    expect(cache.use("key", prompt(system, words(500, 5000), "follow up"), 1630)).toBe(1536);
+  });
+
+  it("should keep caches apart per API key", () => {
+    const cache = new PromptCache(tokenizer);
+    const messages = prompt(words(1100));
+
+    cache.use("a", messages, 1110);
+    expect(cache.use("b", messages, 1110)).toBe(0);
+  });
+
+  it("should forget prefixes unused for the TTL", () => {
+    let now = 0;
+    const cache = new PromptCache(tokenizer, 1000, () => now);
+    const messages = prompt(words(1100));
+
+    cache.use("key", messages, 1110);
+    now = 999;
+    expect(cache.use("key", messages, 1110)).toBe(1024);
+    now = 1998;
+    expect(cache.use("key", messages, 1110)).toBe(1024);
+    now = 3000;
+    expect(cache.use("key", messages, 1110)).toBe(0);
+  });
+
+  it("should add prompt_tokens_details only for cacheable prompts", () => {
+    const usage = { prompt_tokens: 1200, completion_tokens: 5, total_tokens: 1205 };
+
+    expect(withCachedTokens(usage, undefined)).toEqual(usage);
+    expect(withCachedTokens(usage, 1024)).toEqual({ ...usage, prompt_tokens_details: { cached_tokens: 1024 } });
+  });
+});
@@ -0,0 +1,109 @@
+// Simulated prompt caching
+//
+// OpenAI caches prompts of 1024 tokens or more in steps of 128 tokens: when a
+// request's prompt starts with a prefix seen in the last few minutes, the
+// shared prefix is reported as usage.prompt_tokens_details.cached_tokens.
+// Nothing is actually cached here, only remembered, so cost dashboards can
+// exercise the field with predictable numbers.
+
+import type { ChatCompletionRequestMessage, ChatCompletionUsage } from './types.js';
+import type { Tokenizer } from '../tokenizer/tokenizer.js';
+
+// Shorter prompts are never cached, and report no prompt_tokens_details
+export const MIN_CACHED_PROMPT_TOKENS = 1024;
+// Cached prefixes grow in steps of this many tokens
+export const PROMPT_CACHE_INCREMENT = 128;
+// How long an unused prefix stays cached, as OpenAI's 5-10 minutes of inactivity
+export const DEFAULT_PROMPT_CACHE_TTL_MS = 5 * 60 * 1000;
+
+// Oldest prefixes are forgotten first past this many
+const MAX_ENTRIES = 100_000;
+
+const FNV_OFFSET = 0x811c9dc5;
+const FNV_PRIME = 0x01000193;
+
+/**
+ * PromptCache - Remembers prompt prefixes per API key
+ *
+ * Each prompt is cut into tokens, and every prefix at a 128-token step from
+ * 1024 tokens on is remembered by a hash. A later prompt sharing a remembered
+ * prefix reports its length as cached. Seeing a prefix again keeps it for
+ * another TTL.
+ */
+export class PromptCache {
+  private prefixes = new Map<string, number>();
+
+  constructor(
+    private tokenizer: Tokenizer,
+    private ttlMs: number = DEFAULT_PROMPT_CACHE_TTL_MS,
+    private now: () => number = Date.now
+  ) {}
+
+  /**
+   * Returns how many of a prompt's tokens were cached, then caches the
+   * prompt. Undefined when the prompt is too short to cache.
+   */
+  use(apiKey: string, messages: ChatCompletionRequestMessage[], promptTokens: number): number | undefined {
+    if (promptTokens < MIN_CACHED_PROMPT_TOKENS) {
+      return undefined;
+    }
+
+    const now = this.now();
+    this.prune(now);
+
+    let cached = 0;
+    const keys: string[] = [];
+    let hash = FNV_OFFSET;
+    this.tokenizer.split(serialize(messages)).forEach((token, i) => {
+      hash = fnv1a(hash, token);
+      const length = i + 1;
+      if (length < MIN_CACHED_PROMPT_TOKENS || length % PROMPT_CACHE_INCREMENT !== 0) {
+        return;
+      }
+      const key = `${apiKey}:${length}:${hash}`;
+      if ((this.prefixes.get(key) ?? 0) > now) {
+        cached = length;
+      }
+      keys.push(key);
+    });
+
+    for (const key of keys) {
+      // Re-inserted so the Map keeps the most recently used prefixes last
+      this.prefixes.delete(key);
+      this.prefixes.set(key, now + this.ttlMs);
+    }
+    return Math.min(cached, promptTokens);
+  }
+
+  private prune(now: number): void {
+    for (const [key, expires] of this.prefixes) {
+      if (expires > now && this.prefixes.size <= MAX_ENTRIES) {
+        break;
+      }
+      this.prefixes.delete(key);
+    }
+  }
+}
+
+// Adds the cached share of a prompt to a completion's usage
+export function withCachedTokens(usage: ChatCompletionUsage, cachedTokens: number | undefined): ChatCompletionUsage {
+  return cachedTokens === undefined ? usage : { ...usage, prompt_tokens_details: { cached_tokens: cachedTokens } };
+}
+
+// The prompt as the model sees it, so only an identical prefix is shared
+function serialize(messages: ChatCompletionRequestMessage[]): string {
+  return messages
+    .map(message => {
+      const content = typeof message.content === 'string' ? message.content : JSON.stringify(message.content);
+      return `${message.role}\n${content}`;
+    })
+    .join('\n');
+}
+
+function fnv1a(hash: number, text: string): number {
+  for (let i = 0; i < text.length; i++) {
+    hash ^= text.charCodeAt(i);
+    hash = Math.imul(hash, FNV_PRIME) >>> 0;
+  }
+  return hash;
+}
@@ -0,0 +1,58 @@
+import { describe, it, expect, vi, afterEach } from "vitest";
+import { forwardChatCompletion, parseUpstream } from "./proxy.js";
+
+describe("Upstream proxy", () => {
+  afterEach(() => {
+    vi.unstubAllGlobals();
+  });
+
+  it("should only configure an upstream when a URL is set", () => {
+    expect(parseUpstream(undefined, "sk-test")).toBeUndefined();
+    expect(parseUpstream("https://api.example.com/v1/", undefined)).toEqual({
+      baseUrl: "https://api.example.com/v1",
+    });
+  });
+
+  it("should forward the request with the upstream model name and key", async () => {
+    const fetch = vi.fn(async () => new Response('{"id":"chatcmpl-1"}', {
+      status: 200,
+      headers: { "Content-Type": "application/json", "Set-Cookie": "session=1" },
+    }));
+    vi.stubGlobal("fetch", fetch);
+
+    const response = await forwardChatCompletion(
+      { baseUrl: "https://api.example.com/v1", apiKey: "sk-test" },
+      { model: "proxy:gpt-4o-mini", messages: [] },
+      "gpt-4o-mini",
+    );
+
+    expect(fetch).toHaveBeenCalledWith(
+      "https://api.example.com/v1/chat/completions",
+      expect.objectContaining({
+        headers: expect.objectContaining({ Authorization: "Bearer sk-test" }),
+        body: JSON.stringify({ model: "gpt-4o-mini", messages: [] }),
+      }),
+    );
+    expect(await response.text()).toBe('{"id":"chatcmpl-1"}');
+    expect(response.headers.get("content-type")).toBe("application/json");
+    expect(response.headers.get("set-cookie")).toBeNull();
+  });
+
+  it("should relay upstream errors with their status", async () => {
+    vi.stubGlobal("fetch", async () => new Response('{"error":{"message":"nope"}}', { status: 429 }));
+
+    const response = await forwardChatCompletion({ baseUrl: "https://api.example.com/v1" }, {}, "gpt-4o-mini");
+
+    expect(response.status).toBe(429);
+  });
+
+  it("should report an unreachable upstream as a 502", async () => {
+    vi.stubGlobal("fetch", async () => {
+      throw new TypeError("fetch failed");
+    });
+
+    await expect(
+      forwardChatCompletion({ baseUrl: "https://api.example.com/v1" }, {}, "gpt-4o-mini"),
+    ).rejects.toMatchObject({ statusCode: 502, code: "upstream_unreachable" });
+  });
+});
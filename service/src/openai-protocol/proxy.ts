@@ -0,0 +1,69 @@
+// Pass-through to a real OpenAI-compatible upstream
+//
+// "proxy:<model>" requests are forwarded to the upstream as <model> and the
+// upstream's response is relayed unchanged, streamed or not, so the same
+// client test suite can run against teenytiny and a real provider.
+
+import { APIError, ErrorTypes } from './errors.js';
+
+export interface UpstreamConfig {
+  // Base URL including the version prefix, e.g. https://api.openai.com/v1
+  baseUrl: string;
+  apiKey?: string;
+}
+
+export const PROXY_PREFIX = 'proxy:';
+
+// Builds the upstream config from environment values, if a URL is set
+export function parseUpstream(
+  url: string | undefined,
+  apiKey: string | undefined
+): UpstreamConfig | undefined {
+  if (!url) {
+    return undefined;
+  }
+  return {
+    baseUrl: url.replace(/\/+$/, ''),
+    ...(apiKey ? { apiKey } : {}),
+  };
+}
+
+// Headers worth passing back to the client; hop-by-hop and encoding headers are left to the runtime
+const RELAYED_HEADERS = ['content-type', 'cache-control', 'x-request-id', 'openai-processing-ms'];
+
+export async function forwardChatCompletion(
+  upstream: UpstreamConfig,
+  body: Record<string, unknown>,
+  model: string,
+  signal?: AbortSignal
+): Promise<Response> {
+  let response: Response;
+  try {
+    response = await fetch(`${upstream.baseUrl}/chat/completions`, {
+      method: 'POST',
+      headers: {
+        'Content-Type': 'application/json',
+        ...(upstream.apiKey ? { Authorization: `Bearer ${upstream.apiKey}` } : {}),
+      },
+      body: JSON.stringify({ ...body, model }),
+      ...(signal ? { signal } : {}),
+    });
+  } catch (error) {
+    throw new APIError(
+      `Upstream request failed: ${error instanceof Error ? error.message : String(error)}`,
+      ErrorTypes.API_ERROR,
+      502,
+      undefined,
+      'upstream_unreachable'
+    );
+  }
+
+  const headers = new Headers();
+  for (const name of RELAYED_HEADERS) {
+    const value = response.headers.get(name);
+    if (value !== null) {
+      headers.set(name, value);
+    }
+  }
+  return new Response(response.body, { status: response.status, headers });
+}
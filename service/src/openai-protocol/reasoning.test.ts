@@ -0,0 +1,91 @@
+import { describe, it, expect } from "vitest";
+import { checkReasoningParameters, reasoningFor } from "./reasoning.js";
+import { OpenAIAdapter } from "./adapter.js";
+import { EchoModel } from "../models/echo-model.js";
+import { WhitespaceTokenizer } from "../tokenizer/tokenizer.js";
+import type { ChatCompletionRequest, ChatCompletionStreamResponse } from "./types.js";
+
+function request(overrides: Record<string, unknown> = {}): ChatCompletionRequest {
+  return {
+    model: "reasoning",
+    messages: [{ role: "user", content: "one two three" }],
+    ...overrides,
+  } as ChatCompletionRequest;
+}
+
+describe("Reasoning parameters", () => {
+  it("should accept parameters left at their defaults", () => {
+    expect(() =>
+      checkReasoningParameters(
+        request({ temperature: 1, top_p: 1, presence_penalty: 0, logprobs: false, logit_bias: {} }),
+      ),
+    ).not.toThrow();
+    expect(() => checkReasoningParameters(request({ max_completion_tokens: 10 }))).not.toThrow();
+  });
+
+  it("should reject other values with unsupported_value", () => {
+    expect(() => checkReasoningParameters(request({ temperature: 0.5 }))).toThrow(
+      expect.objectContaining({ param: "temperature", code: "unsupported_value", statusCode: 400 }),
+    );
+    expect(() => checkReasoningParameters(request({ frequency_penalty: 1 }))).toThrow(/Only the default \(0\)/);
+  });
+
+  it("should reject unsupported parameters with unsupported_parameter", () => {
+    expect(() => checkReasoningParameters(request({ max_tokens: 10 }))).toThrow(
+      expect.objectContaining({ param: "max_tokens", code: "unsupported_parameter" }),
+    );
+    expect(() => checkReasoningParameters(request({ max_tokens: 10 }))).toThrow(/max_completion_tokens/);
+    expect(() => checkReasoningParameters(request({ logit_bias: { "42": 1 } }))).toThrow(
+      expect.objectContaining({ param: "logit_bias" }),
+    );
+  });
+
+  it("should validate reasoning_effort", () => {
+    expect(() => checkReasoningParameters(request({ reasoning_effort: "high" }))).not.toThrow();
+    expect(() => checkReasoningParameters(request({ reasoning_effort: "extreme" }))).toThrow(
+      expect.objectContaining({ param: "reasoning_effort" }),
+    );
+  });
+
+  it("should reason longer with more effort", () => {
+    expect(reasoningFor("one two three", "low")).toBe("The user wrote 3 words.");
+    expect(reasoningFor("one", "high").split(". ")).toHaveLength(3);
+  });
+});
+
+describe("Reasoning adapter", () => {
+  const adapter = new OpenAIAdapter(new EchoModel(), "reasoning", { reasoning: true });
+
+  it("should answer with reasoning and count its tokens", async () => {
+    const response = await adapter.complete(request());
+    const reasoning = response.choices[0]?.message.reasoning_content;
+
+    expect(response.choices[0]?.message.content).toBe("one two three");
+    expect(reasoning).toBe(reasoningFor("one two three"));
+    const reasoningTokens = new WhitespaceTokenizer().count(reasoning!);
+    expect(response.usage.completion_tokens_details).toEqual({ reasoning_tokens: reasoningTokens });
+    expect(response.usage.completion_tokens).toBe(3 + reasoningTokens);
+  });
+
+  it("should stream the reasoning before the answer", async () => {
+    const chunks: ChatCompletionStreamResponse[] = [];
+    for await (const chunk of adapter.completeStream(request())) {
+      chunks.push(chunk);
+    }
+    const deltas = chunks.map(chunk => chunk.choices[0]?.delta);
+    const firstContent = deltas.findIndex(delta => delta?.content !== undefined);
+
+    expect(deltas.slice(1, firstContent).map(delta => delta?.reasoning_content).join("")).toBe(
+      reasoningFor("one two three"),
+    );
+    expect(chunks[chunks.length - 1]?.usage?.completion_tokens_details?.reasoning_tokens).toBeGreaterThan(0);
+  });
+
+  it("should leave other models' responses alone", async () => {
+    const echo = new OpenAIAdapter(new EchoModel(), "echo");
+    const response = await echo.complete(request({ temperature: 0.5 }));
+
+    expect(response.choices[0]?.message.reasoning_content).toBeUndefined();
+    expect(response.usage.completion_tokens_details).toBeUndefined();
+  });
+});
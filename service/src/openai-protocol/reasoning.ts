@@ -0,0 +1,96 @@
+// Emulation of reasoning models such as o1
+//
+// A reasoning model thinks before it answers. The thinking is billed as
+// reasoning tokens within completion_tokens, reported under
+// usage.completion_tokens_details, and returned as reasoning_content the way
+// DeepSeek-style providers do. Reasoning models also refuse the sampling
+// parameters they don't support, with the errors OpenAI sends for o1.
+
+import { APIError, ErrorTypes, InvalidRequestError } from './errors.js';
+import type { ChatCompletionRequest } from './types.js';
+
+const EFFORTS = ['low', 'medium', 'high'] as const;
+export type ReasoningEffort = typeof EFFORTS[number];
+
+// How many sentences of reasoning each effort produces
+const STEPS: Record<ReasoningEffort, number> = { low: 1, medium: 2, high: 3 };
+
+// Parameters o1 only accepts at their defaults
+const FIXED_PARAMETERS: Record<string, number> = {
+  temperature: 1,
+  top_p: 1,
+  presence_penalty: 0,
+  frequency_penalty: 0,
+};
+
+// Parameters o1 rejects outright, with what to use instead if anything
+const UNSUPPORTED_PARAMETERS: Record<string, string | undefined> = {
+  max_tokens: 'max_completion_tokens',
+  logprobs: undefined,
+  top_logprobs: undefined,
+  logit_bias: undefined,
+};
+
+function unsupportedParameter(param: string, replacement: string | undefined): APIError {
+  const hint = replacement ? ` Use '${replacement}' instead.` : '';
+  return new APIError(
+    `Unsupported parameter: '${param}' is not supported with this model.${hint}`,
+    ErrorTypes.INVALID_REQUEST,
+    400,
+    param,
+    'unsupported_parameter'
+  );
+}
+
+function unsupportedValue(param: string, value: unknown, only: number): APIError {
+  return new APIError(
+    `Unsupported value: '${param}' does not support ${JSON.stringify(value)} with this model. Only the default (${only}) value is supported.`,
+    ErrorTypes.INVALID_REQUEST,
+    400,
+    param,
+    'unsupported_value'
+  );
+}
+
+/**
+ * Rejects a request that sets parameters a reasoning model doesn't support.
+ * Those left at their defaults are accepted and ignored, as OpenAI does.
+ */
+export function checkReasoningParameters(request: ChatCompletionRequest): void {
+  const params = request as unknown as Record<string, unknown>;
+
+  for (const [param, replacement] of Object.entries(UNSUPPORTED_PARAMETERS)) {
+    const value = params[param];
+    const empty = value === null || value === false || (typeof value === 'object' && Object.keys(value).length === 0);
+    if (value !== undefined && !empty) {
+      throw unsupportedParameter(param, replacement);
+    }
+  }
+
+  for (const [param, only] of Object.entries(FIXED_PARAMETERS)) {
+    const value = params[param];
+    if (value !== undefined && value !== null && value !== only) {
+      throw unsupportedValue(param, value, only);
+    }
+  }
+
+  const effort = request.reasoning_effort;
+  if (effort !== undefined && effort !== null && !(EFFORTS as readonly unknown[]).includes(effort)) {
+    throw new InvalidRequestError(
+      `Invalid value for 'reasoning_effort': expected 'low', 'medium' or 'high'`,
+      'reasoning_effort'
+    );
+  }
+}
+
+// The reasoning behind echoing a message, longer for higher efforts
+export function reasoningFor(input: string, effort: ReasoningEffort = 'medium'): string {
+  const words = input.split(/\s+/).filter(Boolean).length;
+  return [
+    `The user wrote ${words} ${words === 1 ? 'word' : 'words'}.`,
+    'They want the message repeated back.',
+    'Nothing needs changing, so I will echo it exactly.',
+  ]
+    .slice(0, STEPS[effort])
+    .join(' ');
+}
@@ -0,0 +1,263 @@
+import { describe, it, expect } from "vitest";
+import { OpenAIAdapter } from "./adapter.js";
+import { EchoModel } from "../models/echo-model.js";
+import {
+  ResponseStore,
+  completeResponse,
+  parseCreateResponseRequest,
+  responseEvents,
+} from "./responses.js";
+import type { ParsedResponseRequest, ResponseEvent } from "./responses.js";
+import { APIError, InvalidRequestError, NotFoundError } from "./errors.js";
+
+const owner = "tt-responses-key";
+const echo = new OpenAIAdapter(new EchoModel(), "echo");
+const tooluse = new OpenAIAdapter(new EchoModel(), "tooluse", { toolCalls: true });
+
+const weather = {
+  type: "function",
+  name: "get_weather",
+  parameters: { type: "object", properties: { city: { type: "string" } }, required: ["city"] },
+};
+
+const noHistory = () => undefined;
+
+async function respond(store: ResponseStore, body: Record<string, unknown>, adapter = echo): Promise<ParsedResponseRequest> {
+  const parsed = parseCreateResponseRequest(body, (id) => store.history(owner, id));
+  completeResponse(parsed.response, await adapter.complete(parsed.request));
+  store.save(owner, parsed);
+  return parsed;
+}
+
+async function collect(events: AsyncIterable<ResponseEvent>): Promise<ResponseEvent[]> {
+  const all: ResponseEvent[] = [];
+  for await (const event of events) {
+    all.push(event);
+  }
+  return all;
+}
+
+describe("parseCreateResponseRequest", () => {
+  it("should turn string input and instructions into chat messages", () => {
+    const { request, input, response } = parseCreateResponseRequest(
+      { model: "echo", input: "Hello", instructions: "Be brief", max_output_tokens: 50 },
+      noHistory
+    );
+
+    expect(request.messages).toEqual([
+      { role: "system", content: "Be brief" },
+      { role: "user", content: "Hello" },
+    ]);
+    expect(request.max_tokens).toBe(50);
+    expect(input).toEqual([
+      expect.objectContaining({ type: "message", role: "user", content: [{ type: "input_text", text: "Hello" }] }),
+    ]);
+    expect(response).toMatchObject({ object: "response", status: "in_progress", instructions: "Be brief", output: [] });
+    expect(response.id).toMatch(/^resp_/);
+  });
+
+  it("should translate message items, function calls and their outputs", () => {
+    const { request } = parseCreateResponseRequest(
+      {
+        model: "echo",
+        input: [
+          { role: "developer", content: "Use tools" },
+          { type: "message", role: "user", content: [{ type: "input_text", text: "Weather?" }] },
+          { type: "function_call", call_id: "call_1", name: "get_weather", arguments: "{}" },
+          { type: "function_call_output", call_id: "call_1", output: "Sunny" },
+        ],
+      },
+      noHistory
+    );
+
+    expect(request.messages).toEqual([
+      { role: "system", content: "Use tools" },
+      { role: "user", content: "Weather?" },
+      {
+        role: "assistant",
+        content: null,
+        tool_calls: [{ id: "call_1", type: "function", function: { name: "get_weather", arguments: "{}" } }],
+      },
+      { role: "tool", content: "Sunny", tool_call_id: "call_1" },
+    ]);
+  });
+
+  it("should translate flat function tools and text formats", () => {
+    const { request, response } = parseCreateResponseRequest(
+      {
+        model: "echo",
+        input: "Hi",
+        tools: [weather],
+        tool_choice: { type: "function", name: "get_weather" },
+        text: { format: { type: "json_schema", name: "reply", schema: { type: "object" } } },
+      },
+      noHistory
+    );
+
+    expect(request.tools).toEqual([
+      { type: "function", function: { name: "get_weather", parameters: weather.parameters, strict: true } },
+    ]);
+    expect(request.tool_choice).toEqual({ type: "function", function: { name: "get_weather" } });
+    expect(request.response_format).toEqual({ type: "json_schema", json_schema: { name: "reply", schema: { type: "object" } } });
+    expect(response.tools[0]).toMatchObject({ name: "get_weather", strict: true });
+  });
+
+  it("should reject what it can't answer", () => {
+    const parse = (body: unknown) => () => parseCreateResponseRequest(body, noHistory);
+
+    expect(parse({ input: "Hi" })).toThrow(InvalidRequestError);
+    expect(parse({ model: "echo" })).toThrow(/input/);
+    expect(parse({ model: "echo", input: "Hi", frobnicate: true })).toThrow(/frobnicate/);
+    expect(parse({ model: "echo", input: "Hi", tools: [{ type: "web_search_preview" }] })).toThrow(/function tools/);
+    expect(parse({ model: "echo", input: [{ type: "function_call_output", call_id: "nope", output: "x" }] })).toThrow(
+      /call_id nope/
+    );
+    expect(parse({ model: "echo", input: "Hi", previous_response_id: "resp_missing" })).toThrow(
+      expect.objectContaining({ statusCode: 400, code: "previous_response_not_found" })
+    );
+  });
+});
+
+describe("completeResponse", () => {
+  it("should answer with a message of output_text and usage", async () => {
+    const { response } = await respond(new ResponseStore(), { model: "echo", input: "Hello there" });
+
+    expect(response.status).toBe("completed");
+    expect(response.output).toEqual([
+      expect.objectContaining({
+        type: "message",
+        role: "assistant",
+        status: "completed",
+        content: [{ type: "output_text", text: "Hello there", annotations: [] }],
+      }),
+    ]);
+    expect(response.usage).toMatchObject({ input_tokens_details: { cached_tokens: 0 } });
+    expect(response.usage!.total_tokens).toBe(response.usage!.input_tokens + response.usage!.output_tokens);
+  });
+
+  it("should answer with function calls when the model calls tools", async () => {
+    const { response } = await respond(new ResponseStore(), { model: "tooluse", input: "Weather?", tools: [weather] }, tooluse);
+
+    expect(response.output).toHaveLength(1);
+    expect(response.output[0]).toMatchObject({ type: "function_call", name: "get_weather", status: "completed" });
+  });
+
+  it("should be incomplete when cut short by max_output_tokens", async () => {
+    const { response } = await respond(new ResponseStore(), {
+      model: "echo",
+      input: "one two three four five six seven eight nine ten",
+      max_output_tokens: 3,
+    });
+
+    expect(response.status).toBe("incomplete");
+    expect(response.incomplete_details).toEqual({ reason: "max_output_tokens" });
+  });
+});
+
+describe("responseEvents", () => {
+  it("should stream output_text deltas between created and completed", async () => {
+    const { request, response } = parseCreateResponseRequest({ model: "echo", input: "Hello streaming world" }, noHistory);
+
+    const events = await collect(responseEvents(response, echo.completeStream(request)));
+    const types = events.map(event => event.type);
+
+    expect(types.slice(0, 4)).toEqual([
+      "response.created",
+      "response.in_progress",
+      "response.output_item.added",
+      "response.content_part.added",
+    ]);
+    expect(types.slice(-4)).toEqual([
+      "response.output_text.done",
+      "response.content_part.done",
+      "response.output_item.done",
+      "response.completed",
+    ]);
+    expect(events.map(event => event.sequence_number)).toEqual(events.map((_, i) => i));
+
+    const deltas = events.filter(event => event.type === "response.output_text.delta").map(event => event.delta);
+    expect(deltas.join("")).toBe("Hello streaming world");
+    expect(events[0]!.response).toMatchObject({ status: "in_progress", output: [] });
+    expect(events.at(-1)!.response).toMatchObject({ status: "completed", output: [{ content: [{ text: "Hello streaming world" }] }] });
+  });
+
+  it("should stream function call arguments", async () => {
+    const { request, response } = parseCreateResponseRequest(
+      { model: "tooluse", input: "Weather?", tools: [weather] },
+      noHistory
+    );
+
+    const events = await collect(responseEvents(response, tooluse.completeStream(request)));
+    const done = events.find(event => event.type === "response.function_call_arguments.done")!;
+    const deltas = events.filter(event => event.type === "response.function_call_arguments.delta").map(event => event.delta);
+
+    expect(deltas.join("")).toBe(done.arguments);
+    expect(response.output[0]).toMatchObject({ type: "function_call", name: "get_weather", arguments: done.arguments });
+    expect(events.at(-1)!.type).toBe("response.completed");
+  });
+});
+
+describe("ResponseStore", () => {
+  it("should continue a conversation from previous_response_id", async () => {
+    const store = new ResponseStore();
+    const first = await respond(store, { model: "tooluse", input: "Weather in Paris?", tools: [weather] }, tooluse);
+    const call = first.response.output[0]!;
+    expect(call.type).toBe("function_call");
+
+    const second = await respond(
+      store,
+      {
+        model: "tooluse",
+        previous_response_id: first.response.id,
+        input: [{ type: "function_call_output", call_id: call.type === "function_call" ? call.call_id : "", output: "Sunny, 21C" }],
+        tools: [weather],
+      },
+      tooluse
+    );
+
+    expect(second.request.messages.map(message => message.role)).toEqual(["user", "assistant", "tool"]);
+    expect(second.response.previous_response_id).toBe(first.response.id);
+    expect(second.response.output[0]).toMatchObject({ type: "message", content: [{ text: "Sunny, 21C" }] });
+  });
+
+  it("should not carry instructions over", async () => {
+    const store = new ResponseStore();
+    const first = await respond(store, { model: "echo", input: "One", instructions: "Be brief" });
+
+    const second = parseCreateResponseRequest(
+      { model: "echo", input: "Two", previous_response_id: first.response.id },
+      (id) => store.history(owner, id)
+    );
+
+    expect(second.request.messages).toEqual([
+      { role: "user", content: "One" },
+      { role: "assistant", content: "One" },
+      { role: "user", content: "Two" },
+    ]);
+  });
+
+  it("should list input items, newest first by default", async () => {
+    const store = new ResponseStore();
+    const { response } = await respond(store, {
+      model: "echo",
+      input: [
+        { role: "user", content: "First" },
+        { role: "user", content: "Second" },
+      ],
+    });
+
+    const page = store.inputItems(owner, response.id, {});
+    expect(page.data.map(item => (item.type === "message" ? item.content[0]!.text : ""))).toEqual(["Second", "First"]);
+    expect(store.inputItems(owner, response.id, { order: "asc", limit: "1" }).has_more).toBe(true);
+  });
+
+  it("should keep each key's responses to itself and forget deleted ones", async () => {
+    const store = new ResponseStore();
+    const { response } = await respond(store, { model: "echo", input: "Private" });
+
+    expect(() => store.get("another-key", response.id)).toThrow(NotFoundError);
+    expect(store.history("another-key", response.id)).toBeUndefined();
+    expect(store.delete(owner, response.id)).toEqual({ id: response.id, object: "response", deleted: true });
+    expect(() => store.get(owner, response.id)).toThrow(APIError);
+  });
+});
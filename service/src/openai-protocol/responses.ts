@@ -0,0 +1,673 @@
+// OpenAI's Responses API, the surface newer SDKs are moving to
+//
+// A request's input items, after the conversation so far when it names a
+// previous_response_id, become a chat completion request answered by the same
+// adapters as /v1/chat/completions. Replies go back as output items: a
+// message of output_text, or function_call items. Streams are typed
+// server-sent events, from response.created to response.completed.
+// Responses are kept in memory, separately for each API key, unless the
+// request sets store to false.
+
+import { APIError, ErrorTypes, InvalidRequestError, NotFoundError } from './errors.js';
+import { paginate } from './assistants.js';
+import type { ListPage } from './assistants.js';
+import type {
+  ChatCompletionContentPart,
+  ChatCompletionFinishReason,
+  ChatCompletionMessageToolCall,
+  ChatCompletionRequest,
+  ChatCompletionRequestMessage,
+  ChatCompletionResponse,
+  ChatCompletionResponseFormat,
+  ChatCompletionStreamResponse,
+  ChatCompletionTool,
+  ChatCompletionToolChoice,
+  ChatCompletionUsage,
+} from './types.js';
+import { generateRandomString, getCurrentTimestamp } from './types.js';
+import { validateResponseFormat, validateSamplingParameters, validateTools } from './validation.js';
+
+// Every top-level parameter accepted; anything else is rejected, as for chat completions
+const KNOWN_PARAMETERS = new Set([
+  'model',
+  'input',
+  'instructions',
+  'previous_response_id',
+  'stream',
+  'stream_options',
+  'store',
+  'metadata',
+  'max_output_tokens',
+  'temperature',
+  'top_p',
+  'tools',
+  'tool_choice',
+  'parallel_tool_calls',
+  'text',
+  'user',
+  'truncation',
+  'reasoning',
+  'include',
+  'service_tier',
+]);
+
+const MESSAGE_ROLES = ['user', 'assistant', 'system', 'developer'];
+
+export type ResponseStatus = 'in_progress' | 'completed' | 'incomplete' | 'failed';
+
+export interface OutputText {
+  type: 'output_text';
+  text: string;
+  annotations: unknown[];
+}
+
+export interface OutputMessage {
+  type: 'message';
+  id: string;
+  status: 'in_progress' | 'completed' | 'incomplete';
+  role: 'assistant';
+  content: OutputText[];
+}
+
+export interface FunctionCallItem {
+  type: 'function_call';
+  id: string;
+  call_id: string;
+  name: string;
+  arguments: string;
+  status: 'in_progress' | 'completed';
+}
+
+export type OutputItem = OutputMessage | FunctionCallItem;
+
+// Input items as listed back by /v1/responses/{id}/input_items
+export type InputItem =
+  | {
+      type: 'message';
+      id: string;
+      status: 'completed';
+      role: 'user' | 'assistant' | 'system' | 'developer';
+      content: Record<string, unknown>[];
+    }
+  | { type: 'function_call'; id: string; call_id: string; name: string; arguments: string; status: 'completed' }
+  | { type: 'function_call_output'; id: string; call_id: string; output: string; status: 'completed' };
+
+export interface ResponseTool {
+  type: 'function';
+  name: string;
+  description: string | null;
+  parameters: Record<string, unknown> | null;
+  strict: boolean;
+}
+
+export interface ResponseUsage {
+  input_tokens: number;
+  input_tokens_details: { cached_tokens: number };
+  output_tokens: number;
+  output_tokens_details: { reasoning_tokens: number };
+  total_tokens: number;
+}
+
+export interface ResponseObject {
+  id: string;
+  object: 'response';
+  created_at: number;
+  status: ResponseStatus;
+  error: { code: string; message: string } | null;
+  incomplete_details: { reason: 'max_output_tokens' | 'content_filter' } | null;
+  instructions: string | null;
+  max_output_tokens: number | null;
+  model: string;
+  output: OutputItem[];
+  parallel_tool_calls: boolean;
+  previous_response_id: string | null;
+  reasoning: { effort: null; summary: null };
+  service_tier: 'default';
+  store: boolean;
+  temperature: number;
+  text: { format: Record<string, unknown> };
+  tool_choice: unknown;
+  tools: ResponseTool[];
+  top_p: number;
+  truncation: 'auto' | 'disabled';
+  usage: ResponseUsage | null;
+  user: string | null;
+  metadata: Record<string, string>;
+}
+
+export interface ResponseEvent {
+  type: string;
+  sequence_number: number;
+  [field: string]: unknown;
+}
+
+// A validated request: what to ask the model, and what to remember of it
+export interface ParsedResponseRequest {
+  request: ChatCompletionRequest;
+  // This request's input, and the whole conversation it continues, without instructions
+  input: InputItem[];
+  conversation: ChatCompletionRequestMessage[];
+  stream: boolean;
+  store: boolean;
+  // An in_progress response with everything the request echoes back
+  response: ResponseObject;
+}
+
+function isObject(value: unknown): value is Record<string, any> {
+  return typeof value === 'object' && value !== null && !Array.isArray(value);
+}
+
+function newId(prefix: string): string {
+  return `${prefix}_${generateRandomString(24)}`;
+}
+
+function usageOf(usage: ChatCompletionUsage): ResponseUsage {
+  return {
+    input_tokens: usage.prompt_tokens,
+    input_tokens_details: { cached_tokens: 0 },
+    output_tokens: usage.completion_tokens,
+    output_tokens_details: { reasoning_tokens: 0 },
+    total_tokens: usage.total_tokens,
+  };
+}
+
+function previousResponseNotFound(id: string): APIError {
+  return new APIError(
+    `Previous response with id '${id}' not found.`,
+    ErrorTypes.INVALID_REQUEST,
+    400,
+    'previous_response_id',
+    'previous_response_not_found'
+  );
+}
+
+// Content parts of an input message, as chat completion content
+function messageContent(content: unknown, path: string): string | ChatCompletionContentPart[] {
+  if (typeof content === 'string') {
+    return content;
+  }
+  if (!Array.isArray(content)) {
+    throw new InvalidRequestError(`Invalid ${path}.content: expected a string or an array of content parts`, 'input');
+  }
+  const parts = content.map((part, i): ChatCompletionContentPart => {
+    const where = `${path}.content[${i}]`;
+    if (!isObject(part)) {
+      throw new InvalidRequestError(`Invalid ${where}: must be an object`, 'input');
+    }
+    switch (part.type) {
+      case 'input_text':
+      case 'output_text':
+        if (typeof part.text !== 'string') {
+          throw new InvalidRequestError(`Invalid ${where}.text: expected a string`, 'input');
+        }
+        return { type: 'text', text: part.text };
+      case 'refusal':
+        return { type: 'text', text: String(part.refusal ?? '') };
+      case 'input_image':
+        if (typeof part.image_url !== 'string') {
+          throw new InvalidRequestError(`Invalid ${where}: only images given by image_url are supported`, 'input');
+        }
+        return {
+          type: 'image_url',
+          image_url: { url: part.image_url, ...(part.detail ? { detail: part.detail } : {}) },
+        };
+      default:
+        throw new InvalidRequestError(`Invalid ${where}.type: '${part.type}' is not supported`, 'input');
+    }
+  });
+  return parts.every(part => part.type === 'text') ? parts.map(part => (part as { text: string }).text).join('') : parts;
+}
+
+// The input as listed back: a string becomes one user message of input_text
+function inputItems(input: unknown): Record<string, any>[] {
+  if (typeof input === 'string') {
+    return [{ type: 'message', role: 'user', content: [{ type: 'input_text', text: input }] }];
+  }
+  if (!Array.isArray(input) || input.length === 0) {
+    throw new InvalidRequestError('Missing required parameter: input', 'input');
+  }
+  return input.map((item, i) => {
+    if (!isObject(item)) {
+      throw new InvalidRequestError(`Invalid input[${i}]: must be an object`, 'input');
+    }
+    return item;
+  });
+}
+
+// Turns input items into chat messages, adding them to the conversation.
+// Consecutive function calls become one assistant message, as parallel calls.
+function addInput(items: Record<string, any>[], messages: ChatCompletionRequestMessage[]): InputItem[] {
+  return items.map((item, i): InputItem => {
+    const path = `input[${i}]`;
+    const type = item.type ?? 'message';
+
+    if (type === 'message') {
+      if (!MESSAGE_ROLES.includes(item.role)) {
+        throw new InvalidRequestError(
+          `Invalid ${path}.role: must be one of 'user', 'assistant', 'system' or 'developer'`,
+          'input'
+        );
+      }
+      // Chat completions only know system messages
+      const role = item.role === 'developer' ? 'system' : item.role;
+      messages.push({ role, content: messageContent(item.content, path) });
+      const content =
+        typeof item.content === 'string'
+          ? [{ type: item.role === 'assistant' ? 'output_text' : 'input_text', text: item.content }]
+          : item.content;
+      return { type: 'message', id: item.id ?? newId('msg'), status: 'completed', role: item.role, content };
+    }
+
+    if (type === 'function_call') {
+      if (typeof item.call_id !== 'string' || typeof item.name !== 'string') {
+        throw new InvalidRequestError(`Invalid ${path}: function calls need call_id and name`, 'input');
+      }
+      const call: ChatCompletionMessageToolCall = {
+        id: item.call_id,
+        type: 'function',
+        function: { name: item.name, arguments: typeof item.arguments === 'string' ? item.arguments : '{}' },
+      };
+      const last = messages[messages.length - 1];
+      if (last?.role === 'assistant' && last.tool_calls && last.content === null) {
+        last.tool_calls.push(call);
+      } else {
+        messages.push({ role: 'assistant', content: null, tool_calls: [call] });
+      }
+      return { type: 'function_call', id: item.id ?? newId('fc'), status: 'completed', ...call.function, call_id: call.id };
+    }
+
+    if (type === 'function_call_output') {
+      if (typeof item.call_id !== 'string') {
+        throw new InvalidRequestError(`Invalid ${path}: missing call_id`, 'input');
+      }
+      const output = typeof item.output === 'string' ? item.output : JSON.stringify(item.output ?? '');
+      const answered = messages.some(message => message.tool_calls?.some(call => call.id === item.call_id));
+      if (!answered) {
+        throw new InvalidRequestError(`No tool call found for function call output with call_id ${item.call_id}.`, 'input');
+      }
+      messages.push({ role: 'tool', content: output, tool_call_id: item.call_id });
+      return { type: 'function_call_output', id: item.id ?? newId('fco'), status: 'completed', call_id: item.call_id, output };
+    }
+
+    throw new InvalidRequestError(`Invalid ${path}.type: '${type}' is not supported`, 'input');
+  });
+}
+
+// Responses declare function tools flat; chat completions nest them under function
+function parseTools(tools: unknown): ResponseTool[] {
+  if (!Array.isArray(tools)) {
+    throw new InvalidRequestError("Invalid 'tools': expected an array", 'tools');
+  }
+  return tools.map((tool, i): ResponseTool => {
+    if (!isObject(tool) || tool.type !== 'function') {
+      throw new InvalidRequestError(`Invalid tools[${i}]: only function tools are supported`, 'tools');
+    }
+    return {
+      type: 'function',
+      name: tool.name,
+      description: tool.description ?? null,
+      parameters: tool.parameters ?? null,
+      strict: tool.strict ?? true,
+    };
+  });
+}
+
+function chatTool(tool: ResponseTool): ChatCompletionTool {
+  return {
+    type: 'function',
+    function: {
+      name: tool.name,
+      ...(tool.description !== null ? { description: tool.description } : {}),
+      ...(tool.parameters !== null ? { parameters: tool.parameters } : {}),
+      strict: tool.strict,
+    },
+  };
+}
+
+function chatToolChoice(choice: unknown): ChatCompletionToolChoice {
+  if (choice === 'none' || choice === 'auto' || choice === 'required') {
+    return choice;
+  }
+  if (isObject(choice) && choice.type === 'function' && typeof choice.name === 'string') {
+    return { type: 'function', function: { name: choice.name } };
+  }
+  throw new InvalidRequestError(
+    "Invalid 'tool_choice': expected 'none', 'auto', 'required' or a function",
+    'tool_choice'
+  );
+}
+
+// text.format spells json_schema's name and schema at the top level
+function chatResponseFormat(text: unknown): ChatCompletionResponseFormat {
+  if (!isObject(text) || !isObject(text.format)) {
+    throw new InvalidRequestError("Invalid 'text': expected an object with a format", 'text');
+  }
+  const { type, name, description, schema, strict } = text.format;
+  if (type === 'text' || type === 'json_object') {
+    return { type };
+  }
+  if (type !== 'json_schema') {
+    throw new InvalidRequestError(
+      "Invalid 'text.format.type': expected 'text', 'json_object' or 'json_schema'",
+      'text'
+    );
+  }
+  return {
+    type: 'json_schema',
+    json_schema: {
+      name,
+      ...(description !== undefined ? { description } : {}),
+      ...(schema !== undefined ? { schema } : {}),
+      ...(strict !== undefined ? { strict } : {}),
+    },
+  };
+}
+
+/**
+ * Validates a create request and translates it. previous_response_id is
+ * looked up with history, which returns the conversation that response ended
+ * or undefined when there's none. Instructions aren't carried over.
+ */
+export function parseCreateResponseRequest(
+  body: unknown,
+  history: (id: string) => ChatCompletionRequestMessage[] | undefined
+): ParsedResponseRequest {
+  if (!isObject(body)) {
+    throw new InvalidRequestError('Request body must be a JSON object');
+  }
+  for (const name of Object.keys(body)) {
+    if (!KNOWN_PARAMETERS.has(name)) {
+      throw new InvalidRequestError(`Unknown parameter: '${name}'.`, name);
+    }
+  }
+  if (typeof body.model !== 'string' || body.model === '') {
+    throw new InvalidRequestError('Missing required parameter: model', 'model');
+  }
+  if (body.instructions != null && typeof body.instructions !== 'string') {
+    throw new InvalidRequestError("Invalid type for 'instructions': expected a string", 'instructions');
+  }
+
+  const previousId: string | null = body.previous_response_id ?? null;
+  let conversation: ChatCompletionRequestMessage[] = [];
+  if (previousId !== null) {
+    const previous = typeof previousId === 'string' ? history(previousId) : undefined;
+    if (!previous) {
+      throw previousResponseNotFound(String(previousId));
+    }
+    conversation = previous.map(message => ({ ...message }));
+  }
+  const input = addInput(inputItems(body.input), conversation);
+
+  const tools = body.tools != null ? parseTools(body.tools) : [];
+  const request: ChatCompletionRequest = {
+    model: body.model,
+    messages: [
+      ...(body.instructions ? [{ role: 'system' as const, content: body.instructions as string }] : []),
+      ...conversation,
+    ],
+  };
+  if (body.temperature != null) request.temperature = body.temperature;
+  if (body.top_p != null) request.top_p = body.top_p;
+  if (body.max_output_tokens != null) request.max_tokens = body.max_output_tokens;
+  if (body.user != null) request.user = body.user;
+  if (tools.length > 0) request.tools = tools.map(chatTool);
+  if (body.tool_choice != null) request.tool_choice = chatToolChoice(body.tool_choice);
+  if (body.parallel_tool_calls != null) request.parallel_tool_calls = body.parallel_tool_calls;
+  if (body.text != null) request.response_format = chatResponseFormat(body.text);
+
+  const parameters = request as unknown as Record<string, unknown>;
+  validateSamplingParameters(parameters);
+  validateTools(parameters);
+  validateResponseFormat(parameters);
+
+  const store = body.store !== false;
+  return {
+    request,
+    input,
+    conversation,
+    stream: body.stream === true,
+    store,
+    response: {
+      id: newId('resp'),
+      object: 'response',
+      created_at: getCurrentTimestamp(),
+      status: 'in_progress',
+      error: null,
+      incomplete_details: null,
+      instructions: body.instructions ?? null,
+      max_output_tokens: body.max_output_tokens ?? null,
+      model: body.model,
+      output: [],
+      parallel_tool_calls: body.parallel_tool_calls ?? true,
+      previous_response_id: previousId,
+      reasoning: { effort: null, summary: null },
+      service_tier: 'default',
+      store,
+      temperature: body.temperature ?? 1,
+      text: { format: isObject(body.text) && isObject(body.text.format) ? body.text.format : { type: 'text' } },
+      tool_choice: body.tool_choice ?? 'auto',
+      tools,
+      top_p: body.top_p ?? 1,
+      truncation: body.truncation === 'auto' ? 'auto' : 'disabled',
+      usage: null,
+      user: body.user ?? null,
+      metadata: isObject(body.metadata) ? body.metadata : {},
+    },
+  };
+}
+
+function functionCallItem(call: ChatCompletionMessageToolCall, status: FunctionCallItem['status']): FunctionCallItem {
+  return {
+    type: 'function_call',
+    id: newId('fc'),
+    call_id: call.id,
+    name: call.function.name,
+    arguments: call.function.arguments,
+    status,
+  };
+}
+
+// Sets the status a finished response ends with
+function settle(response: ResponseObject, finish: ChatCompletionFinishReason | null | undefined): void {
+  if (finish === 'length' || finish === 'content_filter') {
+    response.status = 'incomplete';
+    response.incomplete_details = { reason: finish === 'length' ? 'max_output_tokens' : 'content_filter' };
+  } else {
+    response.status = 'completed';
+  }
+}
+
+// Fills in a response from a whole chat completion
+export function completeResponse(response: ResponseObject, completion: ChatCompletionResponse): ResponseObject {
+  const choice = completion.choices[0];
+  const finish = choice?.finish_reason;
+  const text = choice?.message.content;
+  if (text || !choice?.message.tool_calls) {
+    response.output.push({
+      type: 'message',
+      id: newId('msg'),
+      status: finish === 'length' || finish === 'content_filter' ? 'incomplete' : 'completed',
+      role: 'assistant',
+      content: [{ type: 'output_text', text: text ?? '', annotations: [] }],
+    });
+  }
+  for (const call of choice?.message.tool_calls ?? []) {
+    response.output.push(functionCallItem(call, 'completed'));
+  }
+  response.usage = usageOf(completion.usage);
+  settle(response, finish);
+  return response;
+}
+
+/**
+ * Rewrites a chat completion stream as Responses API events, filling in the
+ * response as it goes. Text goes out as output_text deltas of one message;
+ * each tool call becomes a function_call item whose arguments stream as
+ * deltas. The last event is response.completed, or response.incomplete.
+ */
+export async function* responseEvents(
+  response: ResponseObject,
+  chunks: AsyncIterable<ChatCompletionStreamResponse>
+): AsyncGenerator<ResponseEvent> {
+  let sequence = 0;
+  const event = (type: string, fields: Record<string, unknown>): ResponseEvent => ({
+    type,
+    sequence_number: sequence++,
+    ...fields,
+  });
+  const snapshot = () => structuredClone(response);
+
+  yield event('response.created', { response: snapshot() });
+  yield event('response.in_progress', { response: snapshot() });
+
+  let message: OutputMessage | undefined;
+  const calls: FunctionCallItem[] = [];
+  let finish: ChatCompletionFinishReason | null | undefined;
+  let usage: ChatCompletionUsage | undefined;
+
+  for await (const chunk of chunks) {
+    const choice = chunk.choices[0];
+    usage = chunk.usage ?? usage;
+    finish = choice?.finish_reason ?? finish;
+
+    const text = choice?.delta.content;
+    if (text) {
+      if (!message) {
+        message = { type: 'message', id: newId('msg'), status: 'in_progress', role: 'assistant', content: [] };
+        response.output.push(message);
+        yield event('response.output_item.added', { output_index: response.output.length - 1, item: structuredClone(message) });
+        message.content.push({ type: 'output_text', text: '', annotations: [] });
+        yield event('response.content_part.added', {
+          item_id: message.id,
+          output_index: response.output.length - 1,
+          content_index: 0,
+          part: { type: 'output_text', text: '', annotations: [] },
+        });
+      }
+      message.content[0]!.text += text;
+      yield event('response.output_text.delta', {
+        item_id: message.id,
+        output_index: response.output.indexOf(message),
+        content_index: 0,
+        delta: text,
+      });
+    }
+
+    for (const fragment of choice?.delta.tool_calls ?? []) {
+      let call = calls[fragment.index];
+      if (!call) {
+        call = {
+          type: 'function_call',
+          id: newId('fc'),
+          call_id: fragment.id ?? '',
+          name: fragment.function.name ?? '',
+          arguments: '',
+          status: 'in_progress',
+        };
+        calls[fragment.index] = call;
+        response.output.push(call);
+        yield event('response.output_item.added', { output_index: response.output.length - 1, item: { ...call } });
+      }
+      const delta = fragment.function.arguments;
+      if (delta) {
+        call.arguments += delta;
+        yield event('response.function_call_arguments.delta', {
+          item_id: call.id,
+          output_index: response.output.indexOf(call),
+          delta,
+        });
+      }
+    }
+  }
+
+  // Every item is done before the response is
+  for (const [output_index, item] of response.output.entries()) {
+    if (item.type === 'message') {
+      const part = item.content[0]!;
+      yield event('response.output_text.done', { item_id: item.id, output_index, content_index: 0, text: part.text });
+      yield event('response.content_part.done', { item_id: item.id, output_index, content_index: 0, part: { ...part } });
+      item.status = finish === 'length' || finish === 'content_filter' ? 'incomplete' : 'completed';
+    } else {
+      yield event('response.function_call_arguments.done', { item_id: item.id, output_index, arguments: item.arguments });
+      item.status = 'completed';
+    }
+    yield event('response.output_item.done', { output_index, item: structuredClone(item) });
+  }
+
+  response.usage = usage ? usageOf(usage) : null;
+  settle(response, finish);
+  yield event(response.status === 'incomplete' ? 'response.incomplete' : 'response.completed', { response: snapshot() });
+}
+
+// Marks a response failed, for a stream that broke partway
+export function failedEvent(response: ResponseObject, sequence_number: number): ResponseEvent {
+  response.status = 'failed';
+  response.error = { code: 'server_error', message: 'Streaming failed' };
+  return { type: 'response.failed', sequence_number, response: structuredClone(response) };
+}
+
+// The assistant message a response adds to its conversation
+function outputMessage(output: OutputItem[]): ChatCompletionRequestMessage {
+  const text = output
+    .flatMap(item => (item.type === 'message' ? item.content.map(part => part.text) : []))
+    .join('');
+  const calls = output.flatMap((item): ChatCompletionMessageToolCall[] =>
+    item.type === 'function_call'
+      ? [{ id: item.call_id, type: 'function', function: { name: item.name, arguments: item.arguments } }]
+      : []
+  );
+  return calls.length > 0
+    ? { role: 'assistant', content: text || null, tool_calls: calls }
+    : { role: 'assistant', content: text };
+}
+
+interface StoredResponse {
+  owner: string;
+  response: ResponseObject;
+  input: InputItem[];
+  // The conversation up to and including this response's output
+  conversation: ChatCompletionRequestMessage[];
+}
+
+export class ResponseStore {
+  private responses = new Map<string, StoredResponse>();
+
+  save(owner: string, parsed: ParsedResponseRequest): void {
+    const { response } = parsed;
+    this.responses.set(response.id, {
+      owner,
+      response,
+      input: parsed.input,
+      conversation: [...parsed.conversation, outputMessage(response.output)],
+    });
+  }
+
+  // Another key's response is reported missing, as across organizations
+  get(owner: string, id: string): ResponseObject {
+    return this.stored(owner, id).response;
+  }
+
+  // The conversation to continue from, for previous_response_id
+  history(owner: string, id: string): ChatCompletionRequestMessage[] | undefined {
+    const stored = this.responses.get(id);
+    return stored?.owner === owner ? stored.conversation : undefined;
+  }
+
+  inputItems(owner: string, id: string, query: Record<string, string>): ListPage<InputItem> {
+    return paginate([...this.stored(owner, id).input], query);
+  }
+
+  delete(owner: string, id: string): { id: string; object: 'response'; deleted: true } {
+    this.stored(owner, id);
+    this.responses.delete(id);
+    return { id, object: 'response', deleted: true };
+  }
+
+  private stored(owner: string, id: string): StoredResponse {
+    const stored = this.responses.get(id);
+    if (!stored || stored.owner !== owner) {
+      throw new NotFoundError(`Response with id '${id}' not found.`);
+    }
+    return stored;
+  }
+}
@@ -0,0 +1,65 @@
+import { describe, it, expect } from "vitest";
+import { parseScenarios, playStep, Scenarios } from "./scenarios.js";
+
+describe("Failure scenarios", () => {
+  it("should play a key's steps in order, then let requests through", () => {
+    const scenarios = new Scenarios();
+    scenarios.replace(parseScenarios({ backoff: { key: "tt-retry", steps: ["429", "429", "500", "ok"] } }));
+
+    const played = Array.from({ length: 5 }, () => scenarios.next("tt-retry")?.outcome);
+    expect(played).toEqual(["429", "429", "500", "ok", undefined]);
+    expect(scenarios.next("tt-other")).toBeUndefined();
+  });
+
+  it("should prefer a session's scenario to its key's", () => {
+    const scenarios = new Scenarios();
+    scenarios.replace(
+      parseScenarios({
+        by_key: { key: "tt-retry", steps: ["500"] },
+        by_session: { session: "checkout", steps: [{ outcome: "429", retry_after: 2 }] },
+      }),
+    );
+
+    expect(scenarios.next("tt-retry", "checkout")).toEqual({ outcome: "429", retryAfter: 2 });
+    expect(scenarios.next("tt-retry", "checkout")).toEqual({ outcome: "500" });
+    expect(scenarios.next("tt-other", "checkout")).toBeUndefined();
+  });
+
+  it("should show scenarios as they are given, with how far they've got", () => {
+    const body = {
+      checkout: { session: "checkout", steps: [{ outcome: "503", retry_after: 1 }, "reset", "ok"], played: 1 },
+    };
+    const scenarios = new Scenarios();
+    scenarios.replace(parseScenarios(body));
+
+    expect(scenarios.snapshot()).toEqual(body);
+    expect(scenarios.next("tt-any", "checkout")).toEqual({ outcome: "reset" });
+    expect(scenarios.snapshot()).toEqual({ checkout: { ...body.checkout, played: 2 } });
+  });
+
+  it("should throw status steps and return stream faults", () => {
+    expect(() => playStep({ outcome: "429" })).toThrow(
+      expect.objectContaining({ statusCode: 429, code: "rate_limit_exceeded" }),
+    );
+    expect(() => playStep({ outcome: "502" })).toThrow(expect.objectContaining({ statusCode: 502 }));
+    expect(playStep({ outcome: "error_event" })).toBe("error_event");
+    expect(playStep({ outcome: "ok" })).toBeUndefined();
+  });
+
+  it("should reject invalid scenarios", () => {
+    for (const body of [
+      [],
+      { a: { key: "k", steps: [] } },
+      { a: { steps: ["ok"] } },
+      { a: { key: "k", session: "s", steps: ["ok"] } },
+      { a: { key: "k", steps: ["418"] } },
+      { a: { key: "k", steps: [{ outcome: "429", retry_after: -1 }] } },
+      { a: { key: "k", steps: ["ok"], played: 2 } },
+      { a: { key: "k", steps: ["ok"], repeat: true } },
+    ]) {
+      expect(() => parseScenarios(body)).toThrow(
+        expect.objectContaining({ statusCode: 400, type: "invalid_request_error" }),
+      );
+    }
+  });
+});
@@ -0,0 +1,159 @@
+// Failure scenarios: scripted sequences of outcomes for testing retries
+//
+// A scenario names a key or a session (the x-teenytiny-session header) and
+// the outcome each of its chat completion requests gets in turn, so a client's
+// backoff can be tested against an exact sequence rather than random faults:
+//
+//   {
+//     "backoff": { "key": "tt-retry", "steps": ["429", "429", "500", "ok"] },
+//     "checkout": { "session": "checkout", "steps": [{ "outcome": "429", "retry_after": 2 }, "reset"] }
+//   }
+//
+// Each step is "ok", "429" or one of FAULT_KINDS, optionally with the
+// Retry-After seconds sent with it. Once its steps are played, a scenario
+// lets requests through as usual.
+
+import { InvalidRequestError, RateLimitError } from './errors.js';
+import { FAULT_KINDS, isFaultKind, raiseFault } from './faults.js';
+import type { FaultKind, StreamFault } from './faults.js';
+
+export const SCENARIO_SESSION_HEADER = 'x-teenytiny-session';
+
+export const SCENARIO_OUTCOMES = ['ok', '429', ...FAULT_KINDS] as const;
+export type ScenarioOutcome = 'ok' | '429' | FaultKind;
+
+export interface ScenarioStep {
+  outcome: ScenarioOutcome;
+  retryAfter?: number;
+}
+
+export interface Scenario {
+  // Exactly one of these
+  key?: string;
+  session?: string;
+  steps: ScenarioStep[];
+  // Steps already played, so a scenario can be saved and resumed
+  played: number;
+}
+
+function isOutcome(value: unknown): value is ScenarioOutcome {
+  return typeof value === 'string' && (value === 'ok' || value === '429' || isFaultKind(value));
+}
+
+/**
+ * Validates scenarios by name, as PUT /admin/scenarios and --scenarios files
+ * give them. Played counts are optional and start at 0.
+ */
+export function parseScenarios(body: unknown): Record<string, Scenario> {
+  if (!isObject(body)) {
+    throw new InvalidRequestError('Invalid scenarios: expected an object of scenario names to scenarios');
+  }
+  return Object.fromEntries(Object.entries(body).map(([name, scenario]) => [name, parseScenario(name, scenario)]));
+}
+
+function parseScenario(name: string, value: unknown): Scenario {
+  if (!isObject(value) || !Array.isArray(value.steps) || value.steps.length === 0) {
+    throw new InvalidRequestError(`Invalid scenario '${name}': expected an object with a non-empty array of steps`, name);
+  }
+  const unknown = Object.keys(value).find(setting => !['key', 'session', 'steps', 'played'].includes(setting));
+  if (unknown !== undefined) {
+    throw new InvalidRequestError(
+      `Unknown setting '${name}.${unknown}': expected key, session, steps or played`,
+      `${name}.${unknown}`
+    );
+  }
+
+  const { key, session, played = 0 } = value;
+  const target = key ?? session;
+  if ((key === undefined) === (session === undefined) || typeof target !== 'string' || target === '') {
+    throw new InvalidRequestError(`Invalid scenario '${name}': expected either a key or a session`, name);
+  }
+  if (!Number.isInteger(played) || (played as number) < 0 || (played as number) > value.steps.length) {
+    throw new InvalidRequestError(
+      `Invalid '${name}.played': expected an integer from 0 to the number of steps`,
+      `${name}.played`
+    );
+  }
+
+  const steps = value.steps.map((step, i) => parseStep(step, `${name}.steps[${i}]`));
+  return key !== undefined
+    ? { key: key as string, steps, played: played as number }
+    : { session: session as string, steps, played: played as number };
+}
+
+function parseStep(value: unknown, param: string): ScenarioStep {
+  if (isOutcome(value)) {
+    return { outcome: value };
+  }
+  const expected = `expected one of ${SCENARIO_OUTCOMES.join(', ')}, or { "outcome", "retry_after" }`;
+  if (!isObject(value) || !isOutcome(value.outcome)) {
+    throw new InvalidRequestError(`Invalid '${param}': ${expected}`, param);
+  }
+  const unknown = Object.keys(value).find(setting => setting !== 'outcome' && setting !== 'retry_after');
+  if (unknown !== undefined) {
+    throw new InvalidRequestError(`Unknown setting '${param}.${unknown}': expected outcome or retry_after`, `${param}.${unknown}`);
+  }
+  const retryAfter = value.retry_after;
+  if (retryAfter === undefined) {
+    return { outcome: value.outcome };
+  }
+  if (!Number.isInteger(retryAfter) || (retryAfter as number) < 0) {
+    throw new InvalidRequestError(`Invalid '${param}.retry_after': expected a non-negative integer of seconds`, `${param}.retry_after`);
+  }
+  return { outcome: value.outcome, retryAfter: retryAfter as number };
+}
+
+export class Scenarios {
+  private scenarios: Record<string, Scenario> = {};
+
+  replace(scenarios: Record<string, Scenario>): void {
+    this.scenarios = scenarios;
+  }
+
+  /**
+   * Plays the next step of the scenario for a request's session, or failing
+   * that its key, returning undefined when neither has steps left. The first
+   * scenario in order wins when more than one matches.
+   */
+  next(apiKey: string, session?: string): ScenarioStep | undefined {
+    const scenarios = Object.values(this.scenarios).filter(scenario => scenario.played < scenario.steps.length);
+    const scenario =
+      (session !== undefined ? scenarios.find(scenario => scenario.session === session) : undefined) ??
+      scenarios.find(scenario => scenario.key === apiKey);
+    if (!scenario) {
+      return undefined;
+    }
+    return scenario.steps[scenario.played++];
+  }
+
+  // As GET /admin/scenarios shows them, which PUT accepts back
+  snapshot(): Record<string, unknown> {
+    return Object.fromEntries(Object.entries(this.scenarios).map(([name, { key, session, steps, played }]) => [
+      name,
+      {
+        ...(key !== undefined ? { key } : { session }),
+        steps: steps.map(step =>
+          step.retryAfter === undefined ? step.outcome : { outcome: step.outcome, retry_after: step.retryAfter }
+        ),
+        played,
+      },
+    ]));
+  }
+}
+
+// Throws for steps that are an HTTP status, returning a fault that happens
+// mid-response, or undefined for "ok"
+export function playStep(step: ScenarioStep): StreamFault | undefined {
+  switch (step.outcome) {
+    case 'ok':
+      return undefined;
+    case '429':
+      throw new RateLimitError('Simulated rate limit, please retry');
+    default:
+      return raiseFault(step.outcome);
+  }
+}
+
+function isObject(value: unknown): value is Record<string, unknown> {
+  return typeof value === 'object' && value !== null && !Array.isArray(value);
+}
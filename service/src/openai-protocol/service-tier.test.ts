@@ -0,0 +1,34 @@
+import { describe, it, expect } from "vitest";
+import { parseServiceTierDelays, servedTier, validateServiceTier } from "./service-tier.js";
+
+describe("Service tiers", () => {
+  it("should accept every tier OpenAI documents", () => {
+    for (const tier of ["auto", "default", "flex", "priority", null, undefined]) {
+      expect(() => validateServiceTier({ service_tier: tier })).not.toThrow();
+    }
+  });
+
+  it("should reject other tiers with invalid_value", () => {
+    expect(() => validateServiceTier({ service_tier: "turbo" })).toThrow(
+      expect.objectContaining({ param: "service_tier", code: "invalid_value", statusCode: 400 }),
+    );
+    expect(() => validateServiceTier({ service_tier: 1 })).toThrow(/Supported values are/);
+  });
+
+  it("should serve auto on the default tier", () => {
+    expect(servedTier("auto")).toBe("default");
+    expect(servedTier("flex")).toBe("flex");
+    expect(servedTier(undefined)).toBeUndefined();
+  });
+
+  it("should parse delays per tier", () => {
+    expect(parseServiceTierDelays("flex=2000, default=200~50", "TIERS")).toEqual({
+      flex: { type: "fixed", ms: 2000 },
+      default: { type: "jitter", ms: 200, jitter_ms: 50 },
+    });
+    expect(() => parseServiceTierDelays("auto=100", "TIERS")).toThrow(/auto=100/);
+    expect(() => parseServiceTierDelays("flex=soon", "TIERS")).toThrow(
+      expect.objectContaining({ param: "TIERS" }),
+    );
+  });
+});
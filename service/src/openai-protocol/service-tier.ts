@@ -0,0 +1,69 @@
+// Service tiers
+//
+// OpenAI serves a request on the tier it names in service_tier and reports the
+// tier that served it in the response: 'auto' is served on 'default', 'flex'
+// trades latency for price and 'priority' the other way round. Here every tier
+// is served the same, but a delay can be configured per tier so clients can
+// see the latency difference.
+
+import { APIError, ErrorTypes, InvalidRequestError } from './errors.js';
+import { parseDelayHeader } from '../middleware/latency.js';
+import type { Delay } from '../middleware/latency.js';
+
+export const SERVICE_TIERS = ['auto', 'default', 'flex', 'priority'] as const;
+export type ServiceTier = typeof SERVICE_TIERS[number];
+
+// The tiers a response can report as having served it
+export type ServedTier = Exclude<ServiceTier, 'auto'>;
+
+// Extra delay before answering, per tier served. None by default.
+export type ServiceTierDelays = Partial<Record<ServedTier, Delay>>;
+
+/**
+ * Rejects a service_tier that isn't one of SERVICE_TIERS
+ */
+export function validateServiceTier(request: Record<string, unknown>): void {
+  const tier = request.service_tier;
+  if (tier === undefined || tier === null || (SERVICE_TIERS as readonly unknown[]).includes(tier)) {
+    return;
+  }
+  throw new APIError(
+    `Invalid value: ${JSON.stringify(tier)}. Supported values are: ${SERVICE_TIERS.map(t => `'${t}'`).join(', ')}.`,
+    ErrorTypes.INVALID_REQUEST,
+    400,
+    'service_tier',
+    'invalid_value'
+  );
+}
+
+// The tier that serves a request, or undefined when it didn't ask for one
+export function servedTier(requested: ServiceTier | null | undefined): ServedTier | undefined {
+  if (requested === undefined || requested === null) {
+    return undefined;
+  }
+  return requested === 'auto' ? 'default' : requested;
+}
+
+/**
+ * Parses delays per tier in the form "flex=2000,default=normal:200:50", each
+ * delay written as for the x-teenytiny-delay-ms header
+ */
+export function parseServiceTierDelays(value: string, param: string): ServiceTierDelays {
+  const delays: ServiceTierDelays = {};
+  for (const entry of value.split(',').map(entry => entry.trim()).filter(Boolean)) {
+    const [tier, delay] = entry.split('=');
+    try {
+      if (tier === 'default' || tier === 'flex' || tier === 'priority') {
+        delays[tier] = parseDelayHeader(delay ?? '', param);
+        continue;
+      }
+    } catch {
+      // Reported below, with the whole entry
+    }
+    throw new InvalidRequestError(
+      `Invalid ${param} entry '${entry}': expected default, flex or priority=<delay>, such as flex=2000`,
+      param
+    );
+  }
+  return delays;
+}
@@ -0,0 +1,151 @@
+import { describe, it, expect } from "vitest";
+import { planToolCalls, toolCallDeltas } from "./tool-calls.js";
+import { OpenAIAdapter } from "./adapter.js";
+import { EchoModel } from "../models/echo-model.js";
+import type { ChatCompletionRequest, ChatCompletionTool } from "./types.js";
+
+const weather: ChatCompletionTool = {
+  type: "function",
+  function: {
+    name: "get_weather",
+    parameters: {
+      type: "object",
+      properties: {
+        city: { type: "string" },
+        unit: { type: "string", enum: ["celsius", "fahrenheit"] },
+      },
+      required: ["city", "unit"],
+    },
+  },
+};
+
+const time: ChatCompletionTool = {
+  type: "function",
+  function: { name: "get_time" },
+};
+
+function request(overrides: Partial<ChatCompletionRequest> = {}): ChatCompletionRequest {
+  return {
+    model: "tooluse",
+    messages: [{ role: "user", content: "What is the weather?" }],
+    tools: [weather, time],
+    ...overrides,
+  };
+}
+
+describe("Tool call planning", () => {
+  it("should call every tool with schema-derived arguments", () => {
+    expect(planToolCalls(request())).toEqual([
+      {
+        id: "call_0_0",
+        type: "function",
+        function: { name: "get_weather", arguments: '{"city":"string","unit":"celsius"}' },
+      },
+      { id: "call_0_1", type: "function", function: { name: "get_time", arguments: "{}" } },
+    ]);
+  });
+
+  it("should honor tool_choice and parallel_tool_calls", () => {
+    expect(planToolCalls(request({ tool_choice: "none" }))).toEqual([]);
+    expect(planToolCalls(request({ parallel_tool_calls: false })).map(c => c.function.name)).toEqual([
+      "get_weather",
+    ]);
+    expect(
+      planToolCalls(request({ tool_choice: { type: "function", function: { name: "get_time" } } })).map(
+        c => c.function.name,
+      ),
+    ).toEqual(["get_time"]);
+  });
+
+  it("should answer once a tool result arrives", () => {
+    const calls = planToolCalls(request());
+    const followUp = request({
+      messages: [
+        { role: "user", content: "What is the weather?" },
+        { role: "assistant", content: null, tool_calls: calls },
+        { role: "tool", tool_call_id: "call_0_0", content: "Sunny" },
+      ],
+    });
+
+    expect(planToolCalls(followUp)).toEqual([]);
+    expect(planToolCalls({ ...followUp, tool_choice: "required" })[0]?.id).toBe("call_1_0");
+  });
+
+  it("should split arguments into fragments after a header per call", () => {
+    const calls = planToolCalls(request());
+    const deltas = toolCallDeltas(calls);
+
+    expect(deltas[0]).toEqual({
+      index: 0,
+      id: "call_0_0",
+      type: "function",
+      function: { name: "get_weather", arguments: "" },
+    });
+    const reassembled = deltas
+      .filter(d => d.index === 0)
+      .map(d => d.function.arguments)
+      .join("");
+    expect(reassembled).toBe(calls[0]!.function.arguments);
+    expect(deltas.filter(d => d.id !== undefined)).toHaveLength(2);
+  });
+
+  it("should interleave the deltas of parallel calls", () => {
+    const calls = planToolCalls(request());
+    const deltas = toolCallDeltas(calls);
+
+    expect(deltas.slice(0, 4).map(d => d.index)).toEqual([0, 1, 0, 1]);
+    expect(deltas[1]?.id).toBe("call_0_1");
+    for (const [index, call] of calls.entries()) {
+      const reassembled = deltas
+        .filter(d => d.index === index)
+        .map(d => d.function.arguments)
+        .join("");
+      expect(reassembled).toBe(call.function.arguments);
+    }
+  });
+
+  it("should make a single call when parallel_tool_calls is false", () => {
+    const deltas = toolCallDeltas(planToolCalls(request({ parallel_tool_calls: false })));
+
+    expect(new Set(deltas.map(d => d.index))).toEqual(new Set([0]));
+  });
+});
+
+describe("Tool calling adapter", () => {
+  const adapter = new OpenAIAdapter(new EchoModel(), "tooluse", { toolCalls: true });
+
+  it("should respond with tool calls and no content", async () => {
+    const response = await adapter.complete(request());
+
+    expect(response.choices[0]?.finish_reason).toBe("tool_calls");
+    expect(response.choices[0]?.message.content).toBeNull();
+    expect(response.choices[0]?.message.tool_calls).toHaveLength(2);
+  });
+
+  it("should echo tool results back as the answer", async () => {
+    const response = await adapter.complete(
+      request({
+        messages: [
+          { role: "user", content: "What is the weather?" },
+          { role: "assistant", content: null, tool_calls: planToolCalls(request()) },
+          { role: "tool", tool_call_id: "call_0_0", content: "Sunny" },
+          { role: "tool", tool_call_id: "call_0_1", content: "Noon" },
+        ],
+      }),
+    );
+
+    expect(response.choices[0]?.finish_reason).toBe("stop");
+    expect(response.choices[0]?.message.content).toBe("Sunny\nNoon");
+  });
+
+  it("should end the stream with a tool_calls finish reason", async () => {
+    const chunks = [];
+    for await (const chunk of adapter.completeStream(request())) {
+      chunks.push(chunk);
+    }
+
+    expect(chunks[0]?.choices[0]?.delta.role).toBe("assistant");
+    expect(chunks[chunks.length - 1]?.choices[0]?.finish_reason).toBe("tool_calls");
+    expect(chunks[chunks.length - 1]?.usage).toBeDefined();
+  });
+});
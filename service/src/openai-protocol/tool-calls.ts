@@ -0,0 +1,78 @@
+// Deterministic tool calling for the tooluse model
+//
+// Whenever tools are offered the model calls them, with arguments built from
+// each tool's parameter schema. Call ids depend only on the conversation, so
+// the same request always produces the same calls.
+
+import type {
+  ChatCompletionMessageToolCall,
+  ChatCompletionRequest,
+  ChatCompletionStreamToolCall,
+} from './types.js';
+import { synthesize } from '../utils/json-schema.js';
+
+// Arguments are streamed in fragments of this many characters, so clients
+// have to reassemble them the way they would for a real model
+const ARGUMENT_FRAGMENT_LENGTH = 8;
+
+// Picks the calls to make for a request, empty when the model should answer in text.
+// With tool_choice 'auto' the model answers once the last message is a tool
+// result; 'required' and named choices always call, as OpenAI does.
+export function planToolCalls(request: ChatCompletionRequest): ChatCompletionMessageToolCall[] {
+  const tools = request.tools ?? [];
+  const choice = request.tool_choice ?? 'auto';
+  if (tools.length === 0 || choice === 'none') {
+    return [];
+  }
+  if (choice === 'auto' && request.messages[request.messages.length - 1]?.role === 'tool') {
+    return [];
+  }
+
+  let selected = tools;
+  if (typeof choice === 'object') {
+    selected = tools.filter(tool => tool.function.name === choice.function.name);
+  } else if (request.parallel_tool_calls === false) {
+    selected = tools.slice(0, 1);
+  }
+
+  // Earlier rounds of calls keep their ids distinct within one conversation
+  const turn = request.messages.filter(
+    message => message.role === 'assistant' && (message.tool_calls?.length ?? 0) > 0
+  ).length;
+
+  return selected.map((tool, i): ChatCompletionMessageToolCall => ({
+    id: `call_${turn}_${i}`,
+    type: 'function',
+    function: {
+      name: tool.function.name,
+      arguments: JSON.stringify(synthesize(tool.function.parameters ?? { type: 'object' })),
+    },
+  }));
+}
+
+// Splits calls into the stream deltas OpenAI sends: a header per call with
+// its id and name, then fragments of its arguments. Parallel calls take turns,
+// one delta each, so clients must keep every index's arguments apart.
+export function toolCallDeltas(calls: ChatCompletionMessageToolCall[]): ChatCompletionStreamToolCall[] {
+  const perCall = calls.map((call, index): ChatCompletionStreamToolCall[] => {
+    const deltas: ChatCompletionStreamToolCall[] = [
+      { index, id: call.id, type: 'function', function: { name: call.function.name, arguments: '' } },
+    ];
+    for (let i = 0; i < call.function.arguments.length; i += ARGUMENT_FRAGMENT_LENGTH) {
+      deltas.push({ index, function: { arguments: call.function.arguments.slice(i, i + ARGUMENT_FRAGMENT_LENGTH) } });
+    }
+    return deltas;
+  });
+
+  const deltas: ChatCompletionStreamToolCall[] = [];
+  const rounds = Math.max(0, ...perCall.map(call => call.length));
+  for (let round = 0; round < rounds; round++) {
+    for (const call of perCall) {
+      const delta = call[round];
+      if (delta) {
+        deltas.push(delta);
+      }
+    }
+  }
+  return deltas;
+}
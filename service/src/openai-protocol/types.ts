@@ -2,31 +2,130 @@
 
 export interface ChatCompletionMessage {
   role: 'system' | 'user' | 'assistant';
-  content: string;
+  content: string | null;
+  tool_calls?: ChatCompletionMessageToolCall[];
+  // In place of tool_calls, answering a request that used the deprecated 'functions'
+  function_call?: { name: string; arguments: string };
+  // What a reasoning model thought before answering
+  reasoning_content?: string;
 }
 
+// Tool calling
+export interface ChatCompletionTool {
+  type: 'function';
+  function: {
+    name: string;
+    description?: string;
+    parameters?: Record<string, unknown>;
+    strict?: boolean;
+  };
+}
+
+export type ChatCompletionToolChoice =
+  | 'none'
+  | 'auto'
+  | 'required'
+  | { type: 'function'; function: { name: string } };
+
+export interface ChatCompletionMessageToolCall {
+  id: string;
+  type: 'function';
+  function: {
+    name: string;
+    arguments: string;
+  };
+}
+
+export type ChatCompletionFinishReason = 'stop' | 'length' | 'content_filter' | 'tool_calls' | 'function_call';
+
+// The deprecated predecessors of tools and tool_choice
+export interface ChatCompletionFunction {
+  name: string;
+  description?: string;
+  parameters?: Record<string, unknown>;
+}
+
+export type ChatCompletionFunctionCallOption = 'none' | 'auto' | { name: string };
+
+// Multimodal content parts, accepted in place of a plain string on request messages
+export interface ChatCompletionTextContentPart {
+  type: 'text';
+  text: string;
+}
+
+export interface ChatCompletionImageContentPart {
+  type: 'image_url';
+  image_url: {
+    url: string;
+    detail?: 'auto' | 'low' | 'high';
+  };
+}
+
+export type ChatCompletionContentPart =
+  | ChatCompletionTextContentPart
+  | ChatCompletionImageContentPart;
+
+export interface ChatCompletionRequestMessage {
+  role: 'system' | 'user' | 'assistant' | 'tool';
+  // Only null on assistant messages carrying tool_calls
+  content: string | ChatCompletionContentPart[] | null;
+  // Tells participants with the same role apart
+  name?: string;
+  tool_calls?: ChatCompletionMessageToolCall[];
+  // Set on tool messages, naming the call they answer
+  tool_call_id?: string;
+}
+
+export type ChatCompletionResponseFormat =
+  | { type: 'text' }
+  | { type: 'json_object' }
+  | {
+      type: 'json_schema';
+      json_schema: {
+        name: string;
+        description?: string;
+        schema?: Record<string, unknown>;
+        strict?: boolean;
+      };
+    };
+
 export interface ChatCompletionRequest {
   model: string;
-  messages: ChatCompletionMessage[];
+  messages: ChatCompletionRequestMessage[];
   stream?: boolean;
   user?: string;
   temperature?: number;
   max_tokens?: number;
+  max_completion_tokens?: number;
   top_p?: number;
   n?: number;
   stop?: string | string[];
+  seed?: number;
+  metadata?: Record<string, string>;
+  tools?: ChatCompletionTool[];
+  tool_choice?: ChatCompletionToolChoice;
+  parallel_tool_calls?: boolean;
+  reasoning_effort?: 'low' | 'medium' | 'high';
+  service_tier?: 'auto' | 'default' | 'flex' | 'priority' | null;
+  functions?: ChatCompletionFunction[] | undefined;
+  function_call?: ChatCompletionFunctionCallOption | undefined;
+  response_format?: ChatCompletionResponseFormat;
 }
 
 export interface ChatCompletionUsage {
   prompt_tokens: number;
   completion_tokens: number;
   total_tokens: number;
+  // The share of prompt_tokens served from the prompt cache (see prompt-cache.ts)
+  prompt_tokens_details?: { cached_tokens: number };
+  // Reasoning models report the share of completion_tokens spent thinking
+  completion_tokens_details?: { reasoning_tokens: number };
 }
 
 export interface ChatCompletionChoice {
   index: number;
   message: ChatCompletionMessage;
-  finish_reason: 'stop' | 'length' | 'content_filter' | null;
+  finish_reason: ChatCompletionFinishReason | null;
 }
 
 export interface ChatCompletionResponse {
@@ -36,18 +135,37 @@ export interface ChatCompletionResponse {
   model: string;
   choices: ChatCompletionChoice[];
   usage: ChatCompletionUsage;
+  // The tier that served the request, when it asked for one
+  service_tier?: 'default' | 'flex' | 'priority';
+  // Only sent with x-teenytiny-deterministic (see deterministic.ts)
+  system_fingerprint?: string;
 }
 
 // Streaming types
 export interface ChatCompletionStreamDelta {
   role?: 'assistant' | undefined;
   content?: string | undefined;
+  tool_calls?: ChatCompletionStreamToolCall[] | undefined;
+  function_call?: { name?: string; arguments?: string } | undefined;
+  reasoning_content?: string | undefined;
+}
+
+// Tool calls arrive in fragments keyed by index: the first fragment of each
+// call carries its id, type and name, later ones append to the arguments
+export interface ChatCompletionStreamToolCall {
+  index: number;
+  id?: string;
+  type?: 'function';
+  function: {
+    name?: string;
+    arguments?: string;
+  };
 }
 
 export interface ChatCompletionStreamChoice {
   index: number;
   delta: ChatCompletionStreamDelta;
-  finish_reason?: 'stop' | 'length' | 'content_filter' | null;
+  finish_reason?: ChatCompletionFinishReason | null;
 }
 
 export interface ChatCompletionStreamResponse {
@@ -57,6 +175,8 @@ export interface ChatCompletionStreamResponse {
   model: string;
   choices: ChatCompletionStreamChoice[];
   usage?: ChatCompletionUsage;
+  service_tier?: 'default' | 'flex' | 'priority';
+  system_fingerprint?: string;
 }
 
 // Models API types
@@ -76,8 +196,8 @@ export interface ModelsResponse {
 export interface ErrorDetail {
   message: string;
   type: string;
-  param?: string | undefined;
-  code?: string | undefined;
+  param: string | null;
+  code: string | null;
 }
 
 export interface ErrorResponse {
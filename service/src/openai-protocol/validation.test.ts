@@ -0,0 +1,103 @@
+import { describe, it, expect } from "vitest";
+import {
+  rejectUnknownParameters,
+  validateResponseFormat,
+  validateFunctions,
+  validateSamplingParameters,
+  validateTools,
+} from "./validation.js";
+
+describe("Chat completion validation", () => {
+  it("should accept parameters OpenAI knows about", () => {
+    expect(() =>
+      rejectUnknownParameters({ model: "echo", messages: [], seed: 1, tools: [] }),
+    ).not.toThrow();
+  });
+
+  it("should name the unknown parameter", () => {
+    expect(() => rejectUnknownParameters({ model: "echo", temprature: 1 })).toThrow(
+      expect.objectContaining({ param: "temprature" }),
+    );
+  });
+
+  it("should enforce sampling parameter ranges", () => {
+    expect(() => validateSamplingParameters({ temperature: 2 })).not.toThrow();
+    expect(() => validateSamplingParameters({ temperature: 2.5 })).toThrow(/maximum of 2/);
+    expect(() => validateSamplingParameters({ top_p: -0.1 })).toThrow(/minimum of 0/);
+  });
+
+  it("should require positive integer token limits", () => {
+    expect(() => validateSamplingParameters({ max_tokens: -5 })).toThrow(
+      expect.objectContaining({ param: "max_tokens" }),
+    );
+    expect(() => validateSamplingParameters({ max_tokens: 1.5 })).toThrow(/integer/);
+    expect(() => validateSamplingParameters({ max_tokens: null })).not.toThrow();
+  });
+
+  it("should accept up to 300 logit_bias entries of token ids", () => {
+    const bias = (count: number) => Object.fromEntries(Array.from({ length: count }, (_, i) => [String(i), -100]));
+
+    expect(() => validateSamplingParameters({ logit_bias: { "50256": 100, "15496": -5.5 } })).not.toThrow();
+    expect(() => validateSamplingParameters({ logit_bias: bias(300) })).not.toThrow();
+    expect(() => validateSamplingParameters({ logit_bias: bias(301) })).toThrow(/at most 300/);
+  });
+
+  it("should reject invalid logit_bias keys and values", () => {
+    for (const logit_bias of [[1, 2], { hello: 1 }, { "-1": 1 }, { "1.5": 1 }, { "42": 101 }, { "42": "high" }]) {
+      expect(() => validateSamplingParameters({ logit_bias })).toThrow(
+        expect.objectContaining({ param: "logit_bias" }),
+      );
+    }
+  });
+
+  it("should require tool_choice to name a provided tool", () => {
+    const tools = [{ type: "function", function: { name: "get_weather", parameters: { type: "object" } } }];
+
+    expect(() =>
+      validateTools({ tools, tool_choice: { type: "function", function: { name: "get_weather" } } }),
+    ).not.toThrow();
+    expect(() =>
+      validateTools({ tools, tool_choice: { type: "function", function: { name: "get_time" } } }),
+    ).toThrow(expect.objectContaining({ param: "tool_choice" }));
+    expect(() => validateTools({ tools: [{ type: "function", function: { name: "bad name" } }] })).toThrow(
+      expect.objectContaining({ param: "tools[0].function.name" }),
+    );
+  });
+
+  it("should require function_call to name a provided function", () => {
+    const functions = [{ name: "get_weather", parameters: { type: "object" } }];
+
+    expect(() => validateFunctions({ functions, function_call: { name: "get_weather" } })).not.toThrow();
+    expect(() => validateFunctions({ functions, function_call: "auto" })).not.toThrow();
+    expect(() => validateFunctions({ functions, function_call: { name: "get_time" } })).toThrow(
+      expect.objectContaining({ param: "function_call" }),
+    );
+    expect(() => validateFunctions({ functions: [{ name: "bad name" }] })).toThrow(
+      expect.objectContaining({ param: "functions[0].name" }),
+    );
+  });
+
+  it("should not mix functions with tools", () => {
+    const tools = [{ type: "function", function: { name: "get_weather" } }];
+
+    expect(() => validateFunctions({ tools, functions: [{ name: "get_time" }] })).toThrow(
+      expect.objectContaining({ param: "functions" }),
+    );
+    expect(() => validateFunctions({ tools, tool_choice: "auto", function_call: "auto" })).toThrow(
+      expect.objectContaining({ param: "function_call" }),
+    );
+  });
+
+  it("should require a named schema for json_schema response formats", () => {
+    expect(() => validateResponseFormat({ response_format: { type: "json_object" } })).not.toThrow();
+    expect(() =>
+      validateResponseFormat({ response_format: { type: "json_schema", json_schema: { name: "article", schema: {} } } }),
+    ).not.toThrow();
+    expect(() => validateResponseFormat({ response_format: { type: "xml" } })).toThrow(
+      expect.objectContaining({ param: "response_format" }),
+    );
+    expect(() => validateResponseFormat({ response_format: { type: "json_schema" } })).toThrow(
+      expect.objectContaining({ param: "response_format.json_schema" }),
+    );
+  });
+});
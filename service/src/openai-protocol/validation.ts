@@ -0,0 +1,320 @@
+// Validation of chat completion request parameters
+//
+// Models ignore sampling parameters, but out of range values are still
+// rejected the way OpenAI rejects them, so client bugs surface here first.
+
+import { InvalidRequestError } from './errors.js';
+
+// Every top-level parameter OpenAI accepts for chat completions. Anything else
+// is rejected as OpenAI does, rather than silently ignored.
+const KNOWN_CHAT_COMPLETION_PARAMETERS = new Set([
+  'model',
+  'messages',
+  'stream',
+  'stream_options',
+  'user',
+  'temperature',
+  'top_p',
+  'n',
+  'stop',
+  'max_tokens',
+  'max_completion_tokens',
+  'presence_penalty',
+  'frequency_penalty',
+  'logit_bias',
+  'logprobs',
+  'top_logprobs',
+  'seed',
+  'response_format',
+  'tools',
+  'tool_choice',
+  'parallel_tool_calls',
+  'functions',
+  'function_call',
+  'store',
+  'metadata',
+  'modalities',
+  'audio',
+  'prediction',
+  'reasoning_effort',
+  'service_tier',
+  'web_search_options',
+]);
+
+export function rejectUnknownParameters(request: object): void {
+  for (const name of Object.keys(request)) {
+    if (!KNOWN_CHAT_COMPLETION_PARAMETERS.has(name)) {
+      throw new InvalidRequestError(`Unrecognized request argument supplied: ${name}`, name);
+    }
+  }
+}
+
+function checkNumber(
+  request: Record<string, unknown>,
+  param: string,
+  min: number,
+  max: number,
+  integer: boolean = false
+): void {
+  const value = request[param];
+  if (value === undefined || value === null) {
+    return;
+  }
+  if (typeof value !== 'number' || Number.isNaN(value) || (integer && !Number.isInteger(value))) {
+    throw new InvalidRequestError(
+      `Invalid type for '${param}': expected ${integer ? 'an integer' : 'a number'}`,
+      param
+    );
+  }
+  if (value < min) {
+    throw new InvalidRequestError(
+      `Invalid '${param}': ${value} is less than the minimum of ${min}`,
+      param
+    );
+  }
+  if (value > max) {
+    throw new InvalidRequestError(
+      `Invalid '${param}': ${value} is greater than the maximum of ${max}`,
+      param
+    );
+  }
+}
+
+export function validateSamplingParameters(request: Record<string, unknown>): void {
+  checkNumber(request, 'temperature', 0, 2);
+  checkNumber(request, 'top_p', 0, 1);
+  checkNumber(request, 'presence_penalty', -2, 2);
+  checkNumber(request, 'frequency_penalty', -2, 2);
+  checkNumber(request, 'n', 1, 128, true);
+  checkNumber(request, 'max_tokens', 1, Number.MAX_SAFE_INTEGER, true);
+  checkNumber(request, 'max_completion_tokens', 1, Number.MAX_SAFE_INTEGER, true);
+
+  checkNumber(request, 'seed', Number.MIN_SAFE_INTEGER, Number.MAX_SAFE_INTEGER, true);
+
+  const stop = request['stop'];
+  if (stop !== undefined && stop !== null) {
+    const stops = Array.isArray(stop) ? stop : [stop];
+    if (stops.some(s => typeof s !== 'string')) {
+      throw new InvalidRequestError(`Invalid type for 'stop': expected a string or an array of strings`, 'stop');
+    }
+    if (stops.length > 4) {
+      throw new InvalidRequestError(`Invalid 'stop': at most 4 stop sequences are allowed`, 'stop');
+    }
+  }
+
+  validateLogitBias(request['logit_bias']);
+
+  const metadata = request['metadata'];
+  if (metadata !== undefined && metadata !== null) {
+    if (typeof metadata !== 'object' || Array.isArray(metadata)
+        || Object.values(metadata).some(value => typeof value !== 'string')) {
+      throw new InvalidRequestError(`Invalid type for 'metadata': expected an object of string values`, 'metadata');
+    }
+  }
+
+  if (request['stream'] !== undefined && request['stream'] !== null && typeof request['stream'] !== 'boolean') {
+    throw new InvalidRequestError(`Invalid type for 'stream': expected a boolean`, 'stream');
+  }
+}
+
+// OpenAI accepts at most this many logit_bias entries
+const MAX_LOGIT_BIAS_ENTRIES = 300;
+
+// Token ids as strings, each mapped to a bias from -100 to 100
+function validateLogitBias(bias: unknown): void {
+  if (bias === undefined || bias === null) {
+    return;
+  }
+  if (typeof bias !== 'object' || Array.isArray(bias)) {
+    throw new InvalidRequestError(
+      `Invalid type for 'logit_bias': expected an object of token ids to biases`,
+      'logit_bias'
+    );
+  }
+  const entries = Object.entries(bias);
+  if (entries.length > MAX_LOGIT_BIAS_ENTRIES) {
+    throw new InvalidRequestError(
+      `Invalid 'logit_bias': at most ${MAX_LOGIT_BIAS_ENTRIES} entries are allowed, got ${entries.length}`,
+      'logit_bias'
+    );
+  }
+  for (const [token, value] of entries) {
+    if (!/^\d+$/.test(token)) {
+      throw new InvalidRequestError(
+        `Invalid key in 'logit_bias': ${token}. You should only be submitting non-negative integers.`,
+        'logit_bias'
+      );
+    }
+    if (typeof value !== 'number' || Number.isNaN(value) || value < -100 || value > 100) {
+      throw new InvalidRequestError(
+        `Invalid value for 'logit_bias' token ${token}: expected a number from -100 to 100`,
+        'logit_bias'
+      );
+    }
+  }
+}
+
+// Tool and json_schema names follow OpenAI's rules for function names
+const TOOL_NAME_PATTERN = /^[a-zA-Z0-9_-]{1,64}$/;
+
+export function validateTools(request: Record<string, unknown>): void {
+  const tools = request['tools'];
+  const names = new Set<string>();
+
+  if (tools !== undefined && tools !== null) {
+    if (!Array.isArray(tools)) {
+      throw new InvalidRequestError(`Invalid type for 'tools': expected an array of tools`, 'tools');
+    }
+    tools.forEach((tool: any, i) => {
+      if (tool?.type !== 'function' || typeof tool.function !== 'object' || tool.function === null) {
+        throw new InvalidRequestError(`Invalid 'tools[${i}]': expected a function tool`, `tools[${i}]`);
+      }
+      const name = tool.function.name;
+      if (typeof name !== 'string' || !TOOL_NAME_PATTERN.test(name)) {
+        throw new InvalidRequestError(
+          `Invalid 'tools[${i}].function.name': must be 1-64 letters, digits, underscores or dashes`,
+          `tools[${i}].function.name`
+        );
+      }
+      const parameters = tool.function.parameters;
+      if (parameters !== undefined && (typeof parameters !== 'object' || parameters === null || Array.isArray(parameters))) {
+        throw new InvalidRequestError(
+          `Invalid type for 'tools[${i}].function.parameters': expected a JSON schema object`,
+          `tools[${i}].function.parameters`
+        );
+      }
+      names.add(name);
+    });
+  }
+
+  const parallel = request['parallel_tool_calls'];
+  if (parallel !== undefined && parallel !== null && typeof parallel !== 'boolean') {
+    throw new InvalidRequestError(`Invalid type for 'parallel_tool_calls': expected a boolean`, 'parallel_tool_calls');
+  }
+
+  const choice = request['tool_choice'] as any;
+  if (choice === undefined || choice === null) {
+    return;
+  }
+  if (typeof choice === 'string') {
+    if (!['none', 'auto', 'required'].includes(choice)) {
+      throw new InvalidRequestError(
+        `Invalid 'tool_choice': expected 'none', 'auto', 'required' or a named function`,
+        'tool_choice'
+      );
+    }
+    if (choice === 'required' && names.size === 0) {
+      throw new InvalidRequestError(`Invalid 'tool_choice': 'required' needs at least one tool`, 'tool_choice');
+    }
+    return;
+  }
+  const name = choice?.function?.name;
+  if (choice?.type !== 'function' || typeof name !== 'string') {
+    throw new InvalidRequestError(
+      `Invalid 'tool_choice': expected 'none', 'auto', 'required' or a named function`,
+      'tool_choice'
+    );
+  }
+  if (!names.has(name)) {
+    throw new InvalidRequestError(
+      `Invalid 'tool_choice': function '${name}' is not in 'tools'`,
+      'tool_choice'
+    );
+  }
+}
+
+// The deprecated 'functions' and 'function_call', which older SDKs still send
+// in place of 'tools' and 'tool_choice'
+export function validateFunctions(request: Record<string, unknown>): void {
+  const functions = request['functions'];
+  const names = new Set<string>();
+
+  if (functions !== undefined && functions !== null) {
+    if (request['tools'] !== undefined && request['tools'] !== null) {
+      throw new InvalidRequestError(`Invalid 'functions': use either 'tools' or 'functions', not both`, 'functions');
+    }
+    if (!Array.isArray(functions)) {
+      throw new InvalidRequestError(`Invalid type for 'functions': expected an array of functions`, 'functions');
+    }
+    functions.forEach((fn: any, i) => {
+      const name = fn?.name;
+      if (typeof name !== 'string' || !TOOL_NAME_PATTERN.test(name)) {
+        throw new InvalidRequestError(
+          `Invalid 'functions[${i}].name': must be 1-64 letters, digits, underscores or dashes`,
+          `functions[${i}].name`
+        );
+      }
+      const parameters = fn.parameters;
+      if (parameters !== undefined && (typeof parameters !== 'object' || parameters === null || Array.isArray(parameters))) {
+        throw new InvalidRequestError(
+          `Invalid type for 'functions[${i}].parameters': expected a JSON schema object`,
+          `functions[${i}].parameters`
+        );
+      }
+      names.add(name);
+    });
+  }
+
+  const choice = request['function_call'] as any;
+  if (choice === undefined || choice === null) {
+    return;
+  }
+  if (request['tool_choice'] !== undefined && request['tool_choice'] !== null) {
+    throw new InvalidRequestError(
+      `Invalid 'function_call': use either 'tool_choice' or 'function_call', not both`,
+      'function_call'
+    );
+  }
+  if (choice === 'none' || choice === 'auto') {
+    return;
+  }
+  if (typeof choice?.name !== 'string') {
+    throw new InvalidRequestError(
+      `Invalid 'function_call': expected 'none', 'auto' or an object naming a function`,
+      'function_call'
+    );
+  }
+  if (!names.has(choice.name)) {
+    throw new InvalidRequestError(
+      `Invalid 'function_call': function '${choice.name}' is not in 'functions'`,
+      'function_call'
+    );
+  }
+}
+
+export function validateResponseFormat(request: Record<string, unknown>): void {
+  const format = request['response_format'] as any;
+  if (format === undefined || format === null) {
+    return;
+  }
+  if (typeof format !== 'object' || !['text', 'json_object', 'json_schema'].includes(format.type)) {
+    throw new InvalidRequestError(
+      `Invalid 'response_format': type must be one of 'text', 'json_object' or 'json_schema'`,
+      'response_format'
+    );
+  }
+  if (format.type !== 'json_schema') {
+    return;
+  }
+
+  const jsonSchema = format.json_schema;
+  if (typeof jsonSchema !== 'object' || jsonSchema === null) {
+    throw new InvalidRequestError(
+      `Missing required parameter: 'response_format.json_schema'`,
+      'response_format.json_schema'
+    );
+  }
+  if (typeof jsonSchema.name !== 'string' || !TOOL_NAME_PATTERN.test(jsonSchema.name)) {
+    throw new InvalidRequestError(
+      `Invalid 'response_format.json_schema.name': must be 1-64 letters, digits, underscores or dashes`,
+      'response_format.json_schema.name'
+    );
+  }
+  const schema = jsonSchema.schema;
+  if (schema !== undefined && (typeof schema !== 'object' || schema === null || Array.isArray(schema))) {
+    throw new InvalidRequestError(
+      `Invalid type for 'response_format.json_schema.schema': expected a JSON schema object`,
+      'response_format.json_schema.schema'
+    );
+  }
+}
@@ -0,0 +1,36 @@
+// Node.js only: keeps server state in a SQLite database (node:sqlite)
+import { DatabaseSync } from 'node:sqlite';
+import type { StateStore } from './state-store.js';
+
+/**
+ * Each part of the state is a row of JSON, which any SQLite client can read.
+ * The request log can share the database file (see SqliteRequestLog).
+ */
+export class SqliteStateStore implements StateStore {
+  private db: DatabaseSync;
+
+  constructor(file: string) {
+    this.db = new DatabaseSync(file);
+    this.db.exec(`
+      CREATE TABLE IF NOT EXISTS state (
+        name TEXT PRIMARY KEY,
+        value TEXT NOT NULL,
+        updated_ms INTEGER NOT NULL
+      )
+    `);
+  }
+
+  load(name: string): string | undefined {
+    const row = this.db.prepare('SELECT value FROM state WHERE name = ?').get(name) as { value: string } | undefined;
+    return row?.value;
+  }
+
+  save(name: string, json: string): void {
+    this.db
+      .prepare(
+        'INSERT INTO state (name, value, updated_ms) VALUES (?, ?, ?) ' +
+          'ON CONFLICT (name) DO UPDATE SET value = excluded.value, updated_ms = excluded.updated_ms'
+      )
+      .run(name, json, Date.now());
+  }
+}
@@ -0,0 +1,26 @@
+// Server state kept between restarts, so a shared test environment keeps its
+// keys, usage, files and batches when the server is redeployed
+//
+// Each part of the state is saved whole, as JSON under its name, once a
+// request that changed it is done, and read back when the server starts.
+
+export interface StateStore {
+  load(name: string): string | undefined;
+  save(name: string, json: string): void;
+}
+
+/**
+ * Keeps state for as long as the store itself lives, so an app created again
+ * with the same store starts where the last one left off
+ */
+export class MemoryStateStore implements StateStore {
+  private state = new Map<string, string>();
+
+  load(name: string): string | undefined {
+    return this.state.get(name);
+  }
+
+  save(name: string, json: string): void {
+    this.state.set(name, json);
+  }
+}
@@ -0,0 +1,163 @@
+import { describe, it, expect } from "vitest";
+import { OpenAIAdapter } from "../openai-protocol/adapter.js";
+import { EchoModel } from "../models/echo-model.js";
+import { SlowModel } from "../models/slow-model.js";
+import { RealtimeSession } from "./realtime.js";
+import type { RealtimeEvent } from "./realtime.js";
+import { Quotas } from "../utils/quotas.js";
+
+function open(adapter = new OpenAIAdapter(new EchoModel(), "echo")) {
+  const events: RealtimeEvent[] = [];
+  const session = new RealtimeSession(adapter, "echo", (event) => events.push(event));
+  const send = (event: Record<string, unknown>) => session.handle(JSON.stringify(event));
+  const types = () => events.map((event) => event.type);
+  return { session, events, send, types };
+}
+
+function say(text: string) {
+  return {
+    type: "conversation.item.create",
+    item: { type: "message", role: "user", content: [{ type: "input_text", text }] },
+  };
+}
+
+describe("RealtimeSession", () => {
+  it("should announce the session when it starts", () => {
+    const { session, events } = open();
+
+    session.start();
+
+    expect(events).toHaveLength(1);
+    expect(events[0]).toMatchObject({
+      type: "session.created",
+      session: { object: "realtime.session", id: session.id, model: "echo", output_modalities: ["text"] },
+    });
+    expect(typeof events[0]!.event_id).toBe("string");
+  });
+
+  it("should apply session.update and answer with the whole session", async () => {
+    const { events, send } = open();
+
+    await send({ type: "session.update", session: { instructions: "Be brief", max_output_tokens: 10 } });
+
+    expect(events[0]).toMatchObject({
+      type: "session.updated",
+      session: { instructions: "Be brief", max_output_tokens: 10 },
+    });
+  });
+
+  it("should stream a response to the conversation", async () => {
+    const { events, send, types } = open();
+
+    await send(say("Hello realtime"));
+    await send({ type: "response.create" });
+
+    expect(types()).toEqual([
+      "conversation.item.added",
+      "conversation.item.done",
+      "response.created",
+      "response.output_item.added",
+      "response.content_part.added",
+      "response.output_text.delta",
+      "response.output_text.done",
+      "response.content_part.done",
+      "response.output_item.done",
+      "response.done",
+    ]);
+    const deltas = events.filter((event) => event.type === "response.output_text.delta");
+    expect(deltas.map((event) => event.delta).join("")).toBe("Hello realtime");
+
+    const done = events.at(-1)!.response as any;
+    expect(done).toMatchObject({ object: "realtime.response", status: "completed", status_details: null });
+    expect(done.output[0]).toMatchObject({ role: "assistant", content: [{ type: "output_text", text: "Hello realtime" }] });
+    expect(done.usage.total_tokens).toBe(done.usage.input_tokens + done.usage.output_tokens);
+    expect(done.usage.output_tokens).toBeGreaterThan(0);
+  });
+
+  it("should keep the conversation across responses", async () => {
+    const { events, send } = open();
+
+    await send(say("First"));
+    await send({ type: "response.create" });
+    await send(say("Second"));
+    await send({ type: "response.create" });
+
+    const done = events.filter((event) => event.type === "response.done");
+    expect(done.map((event) => (event.response as any).output[0].content[0].text)).toEqual(["First", "Second"]);
+  });
+
+  it("should mark responses cut short by max_output_tokens as incomplete", async () => {
+    const { events, send } = open();
+
+    await send(say("one two three four five"));
+    await send({ type: "response.create", response: { max_output_tokens: 2 } });
+
+    expect(events.at(-1)!.response).toMatchObject({
+      status: "incomplete",
+      status_details: { type: "incomplete", reason: "max_output_tokens" },
+    });
+  });
+
+  it("should cancel a response in progress", async () => {
+    const { events, send } = open(new OpenAIAdapter(new SlowModel(50), "slow"));
+
+    await send(say("one two three four five six"));
+    const responding = send({ type: "response.create" });
+    await new Promise((resolve) => setTimeout(resolve, 80));
+    await send({ type: "response.cancel" });
+    await responding;
+
+    expect(events.at(-1)!.response).toMatchObject({
+      status: "cancelled",
+      status_details: { reason: "client_cancelled" },
+    });
+  });
+
+  it("should allow one response at a time", async () => {
+    const { events, send } = open(new OpenAIAdapter(new SlowModel(20), "slow"));
+
+    await send(say("one two"));
+    const responding = send({ type: "response.create" });
+    await send({ type: "response.create", event_id: "evt_second" });
+    await responding;
+
+    expect(events.find((event) => event.type === "error")).toMatchObject({
+      error: { code: "conversation_already_has_active_response", event_id: "evt_second" },
+    });
+    expect(events.filter((event) => event.type === "response.done")).toHaveLength(1);
+  });
+
+  it("should answer bad events with error events", async () => {
+    const { events, session, send } = open();
+
+    await session.handle("not json");
+    await send({ type: "input_audio_buffer.append" });
+    await send({ type: "response.create" });
+    await send({ type: "session.update", session: { output_modalities: ["audio"] } });
+
+    expect(events.map((event) => (event.error as any).code)).toEqual([
+      "invalid_json",
+      "invalid_value",
+      "invalid_value",
+      "invalid_value",
+    ]);
+    expect(events.every((event) => event.type === "error")).toBe(true);
+  });
+
+  it("should meter each response and refuse one once the quota is spent", async () => {
+    const quotas = new Quotas({ key: 1 });
+    const events: RealtimeEvent[] = [];
+    const session = new RealtimeSession(new OpenAIAdapter(new EchoModel(), "echo"), "echo", (event) => events.push(event), {
+      check: () => quotas.check("key"),
+      record: (usage) => quotas.spend("key", usage.total_tokens),
+    });
+
+    await session.handle(JSON.stringify(say("Hello")));
+    await session.handle(JSON.stringify({ type: "response.create" }));
+    await session.handle(JSON.stringify({ type: "response.create" }));
+
+    expect(quotas.get("key")!.tokens_used).toBeGreaterThan(1);
+    expect(events.filter((event) => event.type === "response.done")).toHaveLength(1);
+    expect(events.at(-1)).toMatchObject({ type: "error", error: { type: "insufficient_quota", code: "insufficient_quota" } });
+  });
+});
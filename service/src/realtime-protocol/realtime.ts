@@ -0,0 +1,381 @@
+// A text-only subset of OpenAI's Realtime API, spoken over a WebSocket
+//
+// The client sends JSON events (session.update, conversation.item.create,
+// response.create, response.cancel) and the server answers with events of its
+// own, streaming each reply as response.output_text.delta events and ending
+// it with response.done. Replies come from the same adapters as /v1, so a
+// session on echo echoes the latest user message.
+
+import { APIError } from '../openai-protocol/errors.js';
+import type { OpenAIAdapter } from '../openai-protocol/adapter.js';
+import type {
+  ChatCompletionFinishReason,
+  ChatCompletionRequest,
+  ChatCompletionRequestMessage,
+  ChatCompletionUsage,
+} from '../openai-protocol/types.js';
+import { generateRandomString } from '../openai-protocol/types.js';
+
+export const REALTIME_PATH = '/v1/realtime';
+
+// The model a connection talks to when the URL doesn't name one
+export const DEFAULT_REALTIME_MODEL = 'echo';
+
+export type RealtimeEvent = { type: string } & Record<string, unknown>;
+
+export interface RealtimeSessionSettings {
+  instructions: string;
+  // Most tokens per response, or "inf" for no limit
+  max_output_tokens: number | 'inf';
+}
+
+interface ContentPart {
+  type: 'input_text' | 'output_text';
+  text: string;
+}
+
+interface ConversationItem {
+  id: string;
+  object: 'realtime.item';
+  type: 'message';
+  status: 'completed' | 'in_progress' | 'incomplete';
+  role: 'system' | 'user' | 'assistant';
+  content: ContentPart[];
+}
+
+interface ActiveResponse {
+  id: string;
+  cancellation: AbortController;
+}
+
+const ROLES = ['system', 'user', 'assistant'];
+
+// Why a response ended other than completed, for response.done's status_details
+const STATUS_REASONS: Record<string, string> = {
+  cancelled: 'client_cancelled',
+  incomplete: 'max_output_tokens',
+  failed: 'server_error',
+};
+
+function isObject(value: unknown): value is Record<string, any> {
+  return typeof value === 'object' && value !== null && !Array.isArray(value);
+}
+
+function newId(prefix: string): string {
+  return `${prefix}_${generateRandomString(22)}`;
+}
+
+// Raised for a client event the session can't act on, sent back as an error event
+class RealtimeError extends Error {
+  constructor(
+    message: string,
+    readonly code: string,
+    readonly param: string | null = null
+  ) {
+    super(message);
+  }
+}
+
+function maxOutputTokens(value: unknown, param: string): number | 'inf' {
+  if (value === 'inf' || (typeof value === 'number' && Number.isInteger(value) && value >= 1)) {
+    return value;
+  }
+  throw new RealtimeError(`Invalid '${param}': expected a positive integer or "inf"`, 'invalid_value', param);
+}
+
+function textOf(item: ConversationItem): string {
+  return item.content.map(part => part.text).join('');
+}
+
+/**
+ * One WebSocket connection's conversation. handle() takes each message the
+ * client sends; events for the client go to send(). Only one response runs
+ * at a time, as in OpenAI's API.
+ */
+// Checks the caller may still generate before each response, and is told
+// what each response used
+export interface RealtimeMeter {
+  check(): void;
+  record(usage: ChatCompletionUsage): void;
+}
+
+export class RealtimeSession {
+  readonly id = newId('sess');
+  private settings: RealtimeSessionSettings = { instructions: '', max_output_tokens: 'inf' };
+  private items: ConversationItem[] = [];
+  private active?: ActiveResponse;
+
+  constructor(
+    private adapter: OpenAIAdapter,
+    private model: string,
+    private send: (event: RealtimeEvent) => void,
+    private meter?: RealtimeMeter
+  ) {}
+
+  // Sent as soon as the connection opens
+  start(): void {
+    this.emit({ type: 'session.created', session: this.describe() });
+  }
+
+  async handle(data: string): Promise<void> {
+    let event: unknown;
+    try {
+      event = JSON.parse(data);
+    } catch {
+      this.error(new RealtimeError('The message is not valid JSON', 'invalid_json'));
+      return;
+    }
+    if (!isObject(event) || typeof event.type !== 'string') {
+      this.error(new RealtimeError("Missing required parameter: 'type'", 'missing_required_parameter', 'type'));
+      return;
+    }
+
+    try {
+      switch (event.type) {
+        case 'session.update':
+          this.updateSession(event.session);
+          break;
+        case 'conversation.item.create':
+          this.createItem(event.item, event.previous_item_id);
+          break;
+        case 'response.create':
+          await this.createResponse(event.response);
+          break;
+        case 'response.cancel':
+          this.cancelResponse();
+          break;
+        default:
+          throw new RealtimeError(`Unknown event type '${event.type}'`, 'invalid_value', 'type');
+      }
+    } catch (error) {
+      this.error(error, event.event_id);
+    }
+  }
+
+  // Stops any response in progress, as when the client goes away
+  close(): void {
+    this.active?.cancellation.abort();
+  }
+
+  private describe(): Record<string, unknown> {
+    return {
+      type: 'realtime',
+      object: 'realtime.session',
+      id: this.id,
+      model: this.model,
+      output_modalities: ['text'],
+      ...this.settings,
+    };
+  }
+
+  private updateSession(session: unknown): void {
+    if (!isObject(session)) {
+      throw new RealtimeError("Missing required parameter: 'session'", 'missing_required_parameter', 'session');
+    }
+    if (session.instructions !== undefined) {
+      if (typeof session.instructions !== 'string') {
+        throw new RealtimeError("Invalid 'session.instructions': expected a string", 'invalid_type', 'session.instructions');
+      }
+      this.settings.instructions = session.instructions;
+    }
+    if (session.max_output_tokens !== undefined) {
+      this.settings.max_output_tokens = maxOutputTokens(session.max_output_tokens, 'session.max_output_tokens');
+    }
+    const modalities = session.output_modalities;
+    if (modalities !== undefined && (!Array.isArray(modalities) || modalities.some(modality => modality !== 'text'))) {
+      throw new RealtimeError('Only text output is supported', 'invalid_value', 'session.output_modalities');
+    }
+    this.emit({ type: 'session.updated', session: this.describe() });
+  }
+
+  private createItem(item: unknown, previousItemId: unknown): void {
+    if (!isObject(item) || item.type !== 'message') {
+      throw new RealtimeError("Invalid 'item': only message items are supported", 'invalid_value', 'item.type');
+    }
+    if (!ROLES.includes(item.role)) {
+      throw new RealtimeError(
+        "Invalid 'item.role': must be one of 'system', 'user' or 'assistant'",
+        'invalid_value',
+        'item.role'
+      );
+    }
+    if (!Array.isArray(item.content) || !item.content.every(part => isObject(part) && typeof part.text === 'string')) {
+      throw new RealtimeError("Invalid 'item.content': expected text parts", 'invalid_value', 'item.content');
+    }
+
+    const created: ConversationItem = {
+      id: typeof item.id === 'string' ? item.id : newId('item'),
+      object: 'realtime.item',
+      type: 'message',
+      status: 'completed',
+      role: item.role,
+      content: item.content.map((part: { text: string }) => ({
+        type: item.role === 'assistant' ? 'output_text' : 'input_text',
+        text: part.text,
+      })),
+    };
+
+    // Inserted after the named item, or at the end
+    let index = this.items.length;
+    if (typeof previousItemId === 'string') {
+      const previous = this.items.findIndex(existing => existing.id === previousItemId);
+      if (previous === -1) {
+        throw new RealtimeError(`No item with id '${previousItemId}'`, 'item_not_found', 'previous_item_id');
+      }
+      index = previous + 1;
+    }
+    this.items.splice(index, 0, created);
+
+    const previous_item_id = this.items[index - 1]?.id ?? null;
+    this.emit({ type: 'conversation.item.added', previous_item_id, item: created });
+    this.emit({ type: 'conversation.item.done', previous_item_id, item: created });
+  }
+
+  private request(options: Record<string, any>): ChatCompletionRequest {
+    const instructions = typeof options.instructions === 'string' ? options.instructions : this.settings.instructions;
+    const limit =
+      options.max_output_tokens !== undefined
+        ? maxOutputTokens(options.max_output_tokens, 'response.max_output_tokens')
+        : this.settings.max_output_tokens;
+
+    const messages: ChatCompletionRequestMessage[] = [];
+    if (instructions) {
+      messages.push({ role: 'system', content: instructions });
+    }
+    for (const item of this.items) {
+      messages.push({ role: item.role, content: textOf(item) });
+    }
+    if (!messages.some(message => message.role === 'user')) {
+      throw new RealtimeError('The conversation has no user message to respond to', 'invalid_value', 'item');
+    }
+
+    return {
+      model: this.model,
+      messages,
+      stream: true,
+      ...(limit !== 'inf' ? { max_tokens: limit } : {}),
+    };
+  }
+
+  private async createResponse(options: unknown): Promise<void> {
+    if (this.active) {
+      throw new RealtimeError(
+        `Conversation already has an active response in progress: ${this.active.id}`,
+        'conversation_already_has_active_response'
+      );
+    }
+    if (options !== undefined && !isObject(options)) {
+      throw new RealtimeError("Invalid 'response': expected an object", 'invalid_type', 'response');
+    }
+    const request = this.request(options ?? {});
+    this.meter?.check();
+    this.adapter.preflight(request);
+
+    const active: ActiveResponse = { id: newId('resp'), cancellation: new AbortController() };
+    this.active = active;
+    const response_id = active.id;
+    const item: ConversationItem = {
+      id: newId('item'),
+      object: 'realtime.item',
+      type: 'message',
+      status: 'in_progress',
+      role: 'assistant',
+      content: [],
+    };
+    const at = { response_id, item_id: item.id, output_index: 0, content_index: 0 };
+
+    this.emit({
+      type: 'response.created',
+      response: { object: 'realtime.response', id: response_id, status: 'in_progress', output: [], usage: null },
+    });
+    this.emit({ type: 'response.output_item.added', response_id, output_index: 0, item });
+    this.emit({ type: 'response.content_part.added', ...at, part: { type: 'output_text', text: '' } });
+
+    let text = '';
+    let finishReason: ChatCompletionFinishReason | null | undefined;
+    let usage: ChatCompletionUsage | undefined;
+    let failed = false;
+    try {
+      for await (const chunk of this.adapter.completeStream(request, active.cancellation.signal)) {
+        const choice = chunk.choices[0];
+        usage = chunk.usage ?? usage;
+        finishReason = choice?.finish_reason ?? finishReason;
+        const delta = choice?.delta.content;
+        if (delta) {
+          text += delta;
+          this.emit({ type: 'response.output_text.delta', ...at, delta });
+        }
+      }
+    } catch (error) {
+      // Reported, then the response is closed off as failed below
+      failed = true;
+      this.error(error);
+    } finally {
+      this.active = undefined;
+    }
+    if (usage) {
+      this.meter?.record(usage);
+    }
+
+    const cancelled = active.cancellation.signal.aborted;
+    const status = failed
+      ? 'failed'
+      : cancelled
+        ? 'cancelled'
+        : finishReason === 'length'
+          ? 'incomplete'
+          : 'completed';
+    const part: ContentPart = { type: 'output_text', text };
+    const done: ConversationItem = { ...item, status: status === 'completed' ? 'completed' : 'incomplete', content: [part] };
+    this.items.push(done);
+
+    this.emit({ type: 'response.output_text.done', ...at, text });
+    this.emit({ type: 'response.content_part.done', ...at, part });
+    this.emit({ type: 'response.output_item.done', response_id, output_index: 0, item: done });
+    this.emit({
+      type: 'response.done',
+      response: {
+        object: 'realtime.response',
+        id: response_id,
+        status,
+        status_details: status === 'completed' ? null : { type: status, reason: STATUS_REASONS[status] },
+        output: [done],
+        usage: {
+          input_tokens: usage?.prompt_tokens ?? 0,
+          output_tokens: usage?.completion_tokens ?? 0,
+          total_tokens: usage?.total_tokens ?? 0,
+        },
+      },
+    });
+  }
+
+  private cancelResponse(): void {
+    if (!this.active) {
+      throw new RealtimeError('There is no active response to cancel', 'response_cancel_not_active');
+    }
+    this.active.cancellation.abort();
+  }
+
+  private emit(event: RealtimeEvent): void {
+    this.send({ ...event, event_id: newId('event') });
+  }
+
+  // Errors go back as events; the connection stays open
+  private error(error: unknown, clientEventId?: unknown): void {
+    const event_id = typeof clientEventId === 'string' ? clientEventId : null;
+    if (error instanceof RealtimeError) {
+      this.emit({
+        type: 'error',
+        error: { type: 'invalid_request_error', code: error.code, message: error.message, param: error.param, event_id },
+      });
+    } else if (error instanceof APIError) {
+      const { type, code, message, param } = error.toErrorResponse().error;
+      this.emit({ type: 'error', error: { type, code, message, param, event_id } });
+    } else {
+      this.emit({
+        type: 'error',
+        error: { type: 'server_error', code: null, message: 'The response failed', param: null, event_id },
+      });
+    }
+  }
+}
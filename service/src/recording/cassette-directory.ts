@@ -0,0 +1,33 @@
+// Node.js only: keeps cassettes as JSON files in a directory
+import fs from 'fs';
+import path from 'path';
+import type { Cassette, CassetteStore } from './cassette.js';
+
+/**
+ * Each cassette is <name>.json in the directory, which is created if needed.
+ * Files can be edited by hand or checked into a repository between runs.
+ */
+export class CassetteDirectory implements CassetteStore {
+  constructor(private dir: string) {
+    fs.mkdirSync(dir, { recursive: true });
+  }
+
+  list(): string[] {
+    return fs.readdirSync(this.dir)
+      .filter(file => file.endsWith('.json'))
+      .map(file => path.basename(file, '.json'))
+      .sort();
+  }
+
+  load(name: string): Cassette | undefined {
+    const file = path.join(this.dir, `${name}.json`);
+    if (!fs.existsSync(file)) {
+      return undefined;
+    }
+    return JSON.parse(fs.readFileSync(file, 'utf8')) as Cassette;
+  }
+
+  save(cassette: Cassette): void {
+    fs.writeFileSync(path.join(this.dir, `${cassette.name}.json`), JSON.stringify(cassette, null, 2));
+  }
+}
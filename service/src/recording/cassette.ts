@@ -0,0 +1,92 @@
+// Cassettes: recorded request/response pairs that can be replayed later
+
+export interface RecordedChunk {
+  // Milliseconds after the response started
+  offset_ms: number;
+  data: string;
+}
+
+export interface Interaction {
+  request: {
+    method: string;
+    path: string;
+    body: string;
+  };
+  response: {
+    status: number;
+    headers: Record<string, string>;
+    chunks: RecordedChunk[];
+  };
+}
+
+// Who recorded a cassette: a tenant, or a key without one
+export interface CassetteOwner {
+  tenant?: string;
+  key?: string;
+}
+
+export interface Cassette {
+  name: string;
+  // Absent for cassettes the server's key recorded or that were put in the
+  // directory by hand, which every key may replay
+  owner?: CassetteOwner;
+  interactions: Interaction[];
+}
+
+// Where cassettes are kept between recording and replay
+export interface CassetteStore {
+  list(): string[];
+  load(name: string): Cassette | undefined;
+  save(cassette: Cassette): void;
+}
+
+// Cassette names double as file names, so they are kept to a safe alphabet
+export const CASSETTE_NAME_PATTERN = /^[a-zA-Z0-9_-]{1,64}$/;
+
+/**
+ * In-memory storage, used when no cassette directory is configured. On
+ * Cloudflare Workers cassettes only live as long as the isolate.
+ */
+export class MemoryCassetteStore implements CassetteStore {
+  private cassettes = new Map<string, Cassette>();
+
+  list(): string[] {
+    return [...this.cassettes.keys()].sort();
+  }
+
+  load(name: string): Cassette | undefined {
+    return this.cassettes.get(name);
+  }
+
+  save(cassette: Cassette): void {
+    this.cassettes.set(cassette.name, cassette);
+  }
+}
+
+// Identifies a request for replay. JSON bodies are compared by content, so
+// key order and whitespace don't matter.
+export function requestKey(method: string, path: string, body: string): string {
+  return `${method} ${path} ${canonicalize(body)}`;
+}
+
+function canonicalize(body: string): string {
+  try {
+    return JSON.stringify(sortKeys(JSON.parse(body)));
+  } catch {
+    return body;
+  }
+}
+
+function sortKeys(value: unknown): unknown {
+  if (Array.isArray(value)) {
+    return value.map(sortKeys);
+  }
+  if (value && typeof value === 'object') {
+    return Object.fromEntries(
+      Object.keys(value)
+        .sort()
+        .map(key => [key, sortKeys((value as Record<string, unknown>)[key])])
+    );
+  }
+  return value;
+}
@@ -0,0 +1,149 @@
+import { describe, it, expect } from "vitest";
+import { Hono } from "hono";
+import { Recorder } from "./recorder.js";
+import { MemoryCassetteStore, requestKey } from "./cassette.js";
+import { createErrorHandler } from "../middleware/errors.js";
+
+// A tiny app whose responses change on every request, so replayed ones stand out
+function createTestApp(recorder: Recorder) {
+  let counter = 0;
+  const app = new Hono<{ Variables: { apiKey: string } }>();
+  app.onError(createErrorHandler());
+  app.use("*", async (c, next) => {
+    c.set("apiKey", c.req.header("x-key") ?? "key-a");
+    await next();
+  });
+  app.use("*", recorder.middleware());
+  app.post("/v1/count", (c) => c.text(`count ${counter++}`));
+  app.post("/v1/stream", () => {
+    const encoder = new TextEncoder();
+    const n = counter++;
+    return new Response(
+      new ReadableStream({
+        start(controller) {
+          controller.enqueue(encoder.encode(`data: ${n}\n\n`));
+          controller.enqueue(encoder.encode("data: [DONE]\n\n"));
+          controller.close();
+        },
+      }),
+      { headers: { "Content-Type": "text/event-stream" } },
+    );
+  });
+  return app;
+}
+
+const post = (app: ReturnType<typeof createTestApp>, path: string, body: unknown, key = "key-a") =>
+  app.request(path, {
+    method: "POST",
+    headers: { "x-key": key, "Content-Type": "application/json" },
+    body: JSON.stringify(body),
+  });
+
+describe("Recorder", () => {
+  it("should replay recorded responses byte-for-byte", async () => {
+    const store = new MemoryCassetteStore();
+    const recorder = new Recorder(store);
+    const app = createTestApp(recorder);
+
+    recorder.startRecording("key-a", "demo");
+    const live = await (await post(app, "/v1/stream", { n: 1 })).text();
+    recorder.stop("key-a");
+
+    expect(store.load("demo")?.interactions[0]?.response.chunks.map(c => c.data)).toEqual([
+      "data: 0\n\n",
+      "data: [DONE]\n\n",
+    ]);
+
+    recorder.startReplay("key-a", "demo", false);
+    const replayed = await post(app, "/v1/stream", { n: 1 });
+
+    expect(replayed.headers.get("content-type")).toBe("text/event-stream");
+    expect(await replayed.text()).toBe(live);
+  });
+
+  it("should serve identical requests in recorded order, repeating the last", async () => {
+    const recorder = new Recorder(new MemoryCassetteStore());
+    const app = createTestApp(recorder);
+
+    recorder.startRecording("key-a", "twice");
+    await post(app, "/v1/count", {});
+    await post(app, "/v1/count", {});
+    recorder.stop("key-a");
+
+    recorder.startReplay("key-a", "twice", false);
+    const replies = [];
+    for (let i = 0; i < 3; i++) {
+      replies.push(await (await post(app, "/v1/count", {})).text());
+    }
+
+    expect(replies).toEqual(["count 0", "count 1", "count 1"]);
+  });
+
+  it("should reject requests missing from the cassette", async () => {
+    const recorder = new Recorder(new MemoryCassetteStore());
+    const app = createTestApp(recorder);
+    recorder.startRecording("key-a", "empty");
+    recorder.stop("key-a");
+
+    recorder.startReplay("key-a", "empty");
+    const res = await post(app, "/v1/count", {});
+
+    expect(res.status).toBe(404);
+    expect((await res.json()).error.code).toBe("cassette_miss");
+  });
+
+  it("should leave other API keys unaffected", async () => {
+    const recorder = new Recorder(new MemoryCassetteStore());
+    const app = createTestApp(recorder);
+    recorder.startRecording("key-a", "mine");
+    recorder.stop("key-a");
+    recorder.startReplay("key-a", "mine");
+
+    const res = await post(app, "/v1/count", {}, "key-b");
+
+    expect(res.status).toBe(200);
+    expect(recorder.status("key-b").mode).toBe("idle");
+  });
+
+  it("should refuse to start while busy", () => {
+    const recorder = new Recorder(new MemoryCassetteStore());
+    recorder.startRecording("key-a", "first");
+
+    expect(() => recorder.startRecording("key-a", "second")).toThrow(
+      expect.objectContaining({ statusCode: 409, code: "recorder_busy" }),
+    );
+  });
+
+  it("should keep cassettes to the owner that recorded them", () => {
+    const recorder = new Recorder(new MemoryCassetteStore());
+    for (const [key, owner, name] of [
+      ["key-a", { tenant: "team-a" }, "team-a-run"],
+      ["key-b", { key: "key-b" }, "key-b-run"],
+      ["server", undefined, "shared"],
+    ] as const) {
+      recorder.startRecording(key, name, owner);
+      recorder.stop(key);
+    }
+
+    expect(recorder.list({ tenant: "team-a" })).toEqual(["shared", "team-a-run"]);
+    expect(recorder.list({ key: "key-b" })).toEqual(["key-b-run", "shared"]);
+    expect(recorder.list()).toEqual(["key-b-run", "shared", "team-a-run"]);
+    expect(() => recorder.load("team-a-run", { key: "key-b" })).toThrow(
+      expect.objectContaining({ statusCode: 404 }),
+    );
+    expect(() => recorder.startReplay("key-b", "team-a-run", false, { key: "key-b" })).toThrow(
+      expect.objectContaining({ statusCode: 404 }),
+    );
+    expect(() => recorder.startRecording("key-b", "shared", { key: "key-b" })).toThrow(
+      expect.objectContaining({ statusCode: 409, code: "cassette_name_taken" }),
+    );
+    recorder.startRecording("key-c", "team-a-run", { tenant: "team-a" });
+    expect(recorder.status("key-c").mode).toBe("recording");
+  });
+
+  it("should match JSON bodies regardless of key order", () => {
+    expect(requestKey("POST", "/v1/x", '{"a":1,"b":[{"d":2,"c":3}]}')).toBe(
+      requestKey("POST", "/v1/x", '{ "b": [{"c":3,"d":2}], "a": 1 }'),
+    );
+  });
+});
@@ -0,0 +1,233 @@
+import { Context, Next } from 'hono';
+import { APIError, ErrorTypes, InvalidRequestError, NotFoundError } from '../openai-protocol/errors.js';
+import { sleep } from '../utils/sleep.js';
+import { CASSETTE_NAME_PATTERN, requestKey } from './cassette.js';
+import type { Cassette, CassetteOwner, CassetteStore, Interaction, RecordedChunk } from './cassette.js';
+
+// Only headers that shape how clients read the body are recorded
+const RECORDED_HEADERS = ['content-type', 'cache-control'];
+
+type RecorderState =
+  | { mode: 'idle' }
+  | { mode: 'recording'; cassette: Cassette }
+  | { mode: 'replaying'; cassette: Cassette; realtime: boolean; served: Map<string, number> };
+
+export interface RecorderStatus {
+  mode: 'idle' | 'recording' | 'replaying';
+  cassette: string | null;
+  interactions: number;
+}
+
+/**
+ * Recorder - VCR-style record and replay of API traffic
+ *
+ * While recording, every request and response passing through the recorder
+ * middleware is kept, including each streamed chunk and when it was sent.
+ * Stopping saves the cassette. While replaying, requests are answered from the
+ * cassette byte-for-byte, with the original chunk timing unless realtime is
+ * off. Identical requests are served in recorded order, repeating the last.
+ *
+ * Each API key records and replays independently, so one client's replay
+ * doesn't affect anyone else's traffic. Cassettes belong to the tenant or key
+ * that recorded them: a caller passes its owner, and only sees and records
+ * over its own cassettes and unowned ones. Without an owner it sees them all.
+ * State lives as long as the app instance, so on Cloudflare Workers it only
+ * survives within one isolate.
+ */
+export class Recorder {
+  private states = new Map<string, RecorderState>();
+
+  constructor(
+    private store: CassetteStore,
+    private now: () => number = Date.now
+  ) {}
+
+  status(apiKey: string): RecorderStatus {
+    const state = this.state(apiKey);
+    if (state.mode === 'idle') {
+      return { mode: 'idle', cassette: null, interactions: 0 };
+    }
+    return {
+      mode: state.mode,
+      cassette: state.cassette.name,
+      interactions: state.cassette.interactions.length,
+    };
+  }
+
+  list(owner?: CassetteOwner): string[] {
+    return this.store.list().filter(name => visible(this.store.load(name), owner));
+  }
+
+  // Another owner's cassette is answered as if it didn't exist
+  load(name: string, owner?: CassetteOwner): Cassette {
+    const cassette = this.store.load(checkName(name));
+    if (!cassette || !visible(cassette, owner)) {
+      throw new NotFoundError(`No cassette named ${name}`);
+    }
+    return cassette;
+  }
+
+  startRecording(apiKey: string, name: string, owner?: CassetteOwner): void {
+    this.checkIdle(apiKey);
+    const existing = this.store.load(checkName(name));
+    if (existing && owner && !sameOwner(existing.owner, owner)) {
+      throw new APIError(
+        `Cassette name ${name} is taken; pick another`,
+        ErrorTypes.INVALID_REQUEST,
+        409,
+        'name',
+        'cassette_name_taken'
+      );
+    }
+    const cassette: Cassette = { name, ...(owner ? { owner } : {}), interactions: [] };
+    this.states.set(apiKey, { mode: 'recording', cassette });
+  }
+
+  startReplay(apiKey: string, name: string, realtime: boolean = true, owner?: CassetteOwner): void {
+    this.checkIdle(apiKey);
+    this.states.set(apiKey, { mode: 'replaying', cassette: this.load(name, owner), realtime, served: new Map() });
+  }
+
+  // Stops recording or replaying, saving a recorded cassette
+  stop(apiKey: string): RecorderStatus {
+    const status = this.status(apiKey);
+    const state = this.state(apiKey);
+    if (state.mode === 'recording') {
+      this.store.save(state.cassette);
+    }
+    this.states.delete(apiKey);
+    return status;
+  }
+
+  // Must run after auth, which identifies the caller's API key
+  middleware() {
+    return async (c: Context, next: Next) => {
+      const state = this.state(c.get('apiKey') ?? '');
+      if (state.mode === 'idle') {
+        await next();
+        return;
+      }
+
+      const request = { method: c.req.method, path: c.req.path, body: await c.req.text() };
+      if (state.mode === 'replaying') {
+        return this.replay(state, request);
+      }
+
+      await next();
+      c.res = this.record(state.cassette, request, c.res);
+    };
+  }
+
+  private replay(
+    state: Extract<RecorderState, { mode: 'replaying' }>,
+    request: Interaction['request']
+  ): Response {
+    const key = requestKey(request.method, request.path, request.body);
+    const matches = state.cassette.interactions.filter(
+      interaction => requestKey(interaction.request.method, interaction.request.path, interaction.request.body) === key
+    );
+    if (matches.length === 0) {
+      throw new APIError(
+        `No interaction in cassette ${state.cassette.name} matches ${request.method} ${request.path}`,
+        ErrorTypes.NOT_FOUND,
+        404,
+        undefined,
+        'cassette_miss'
+      );
+    }
+
+    const served = state.served.get(key) ?? 0;
+    state.served.set(key, served + 1);
+    const { response } = matches[Math.min(served, matches.length - 1)]!;
+
+    const encoder = new TextEncoder();
+    const realtime = state.realtime;
+    const body = new ReadableStream<Uint8Array>({
+      async start(controller) {
+        let elapsed = 0;
+        for (const chunk of response.chunks) {
+          if (realtime && chunk.offset_ms > elapsed) {
+            await sleep(chunk.offset_ms - elapsed);
+            elapsed = chunk.offset_ms;
+          }
+          controller.enqueue(encoder.encode(chunk.data));
+        }
+        controller.close();
+      },
+    });
+    return new Response(body, { status: response.status, headers: response.headers });
+  }
+
+  // Passes the response through unchanged, keeping a copy of each chunk. The
+  // interaction is added once the body has been fully sent.
+  private record(cassette: Cassette, request: Interaction['request'], response: Response): Response {
+    const headers: Record<string, string> = {};
+    for (const name of RECORDED_HEADERS) {
+      const value = response.headers.get(name);
+      if (value !== null) {
+        headers[name] = value;
+      }
+    }
+
+    const started = this.now();
+    const chunks: RecordedChunk[] = [];
+    const decoder = new TextDecoder();
+    const now = this.now;
+    const save = () => {
+      const rest = decoder.decode();
+      if (rest) chunks.push({ offset_ms: now() - started, data: rest });
+      cassette.interactions.push({ request, response: { status: response.status, headers, chunks } });
+    };
+
+    if (!response.body) {
+      save();
+      return response;
+    }
+
+    const tee = new TransformStream<Uint8Array, Uint8Array>({
+      transform(chunk, controller) {
+        chunks.push({ offset_ms: now() - started, data: decoder.decode(chunk, { stream: true }) });
+        controller.enqueue(chunk);
+      },
+      flush() {
+        save();
+      },
+    });
+    return new Response(response.body.pipeThrough(tee), response);
+  }
+
+  private state(apiKey: string): RecorderState {
+    return this.states.get(apiKey) ?? { mode: 'idle' };
+  }
+
+  private checkIdle(apiKey: string): void {
+    const state = this.state(apiKey);
+    if (state.mode !== 'idle') {
+      throw new APIError(
+        `Already ${state.mode} cassette ${state.cassette.name}; stop it first`,
+        ErrorTypes.INVALID_REQUEST,
+        409,
+        undefined,
+        'recorder_busy'
+      );
+    }
+  }
+}
+
+function visible(cassette: Cassette | undefined, owner: CassetteOwner | undefined): boolean {
+  return owner === undefined || cassette?.owner === undefined || sameOwner(cassette.owner, owner);
+}
+
+function sameOwner(a: CassetteOwner | undefined, b: CassetteOwner): boolean {
+  return a !== undefined && a.tenant === b.tenant && a.key === b.key;
+}
+
+function checkName(name: string): string {
+  if (!CASSETTE_NAME_PATTERN.test(name)) {
+    throw new InvalidRequestError(
+      'Invalid cassette name: must be 1-64 letters, digits, underscores or dashes',
+      'name'
+    );
+  }
+  return name;
+}
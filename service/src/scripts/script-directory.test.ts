@@ -0,0 +1,47 @@
+import { describe, it, expect, afterEach } from "vitest";
+import fs from "fs";
+import os from "os";
+import path from "path";
+import { fileURLToPath } from "url";
+import { loadScripts } from "./script-directory.js";
+
+describe("loadScripts", () => {
+  const dirs: string[] = [];
+  const tempDir = () => {
+    const dir = fs.mkdtempSync(path.join(os.tmpdir(), "scripts-"));
+    dirs.push(dir);
+    return dir;
+  };
+
+  afterEach(() => {
+    for (const dir of dirs.splice(0)) {
+      fs.rmSync(dir, { recursive: true, force: true });
+    }
+  });
+
+  it("should name scripts after their files", async () => {
+    const dir = tempDir();
+    fs.writeFileSync(path.join(dir, "greeter.mjs"), "export default ({ input }) => `Hi ${input}`;");
+    fs.writeFileSync(path.join(dir, "notes.txt"), "ignored");
+
+    const scripts = await loadScripts(dir);
+
+    expect(Object.keys(scripts)).toEqual(["greeter"]);
+    expect(await scripts["greeter"]!({ messages: [], input: "Ada", metadata: {} })).toBe("Hi Ada");
+  });
+
+  it("should load the example script", async () => {
+    const scripts = await loadScripts(fileURLToPath(new URL("../../scripts", import.meta.url)));
+
+    expect(await scripts["example"]!({ messages: [], input: "count to 3", metadata: {} })).toEqual({
+      chunks: ["1", " 2", " 3"],
+    });
+  });
+
+  it("should reject modules without a default export function", async () => {
+    const dir = tempDir();
+    fs.writeFileSync(path.join(dir, "broken.mjs"), "export const respond = () => 'hi';");
+
+    await expect(loadScripts(dir)).rejects.toThrow(/broken.mjs: expected a default export function/);
+  });
+});
@@ -0,0 +1,33 @@
+// Node.js only: loads scripted models from the modules in a directory
+import fs from 'fs';
+import path from 'path';
+import { pathToFileURL } from 'url';
+import type { Script } from '../models/script-model.js';
+
+const SCRIPT_EXTENSIONS = ['.js', '.mjs'];
+
+/**
+ * Every *.js or *.mjs file in the directory is an ES module whose default
+ * export is a script, named after the file: greeter.mjs becomes the
+ * script:greeter model. Scripts are loaded once, at startup.
+ */
+export async function loadScripts(dir: string): Promise<Record<string, Script>> {
+  const scripts: Record<string, Script> = {};
+
+  const files = fs.readdirSync(dir)
+    .filter(file => SCRIPT_EXTENSIONS.includes(path.extname(file)))
+    .sort();
+  for (const file of files) {
+    const module = await import(pathToFileURL(path.resolve(dir, file)).href);
+    if (typeof module.default !== 'function') {
+      throw new Error(`${file}: expected a default export function`);
+    }
+    const name = path.basename(file, path.extname(file));
+    if (name in scripts) {
+      throw new Error(`${file}: another script is already named ${name}`);
+    }
+    scripts[name] = module.default;
+  }
+
+  return scripts;
+}
@@ -1,8 +1,30 @@
 #!/usr/bin/env node
 
-import { serve } from '@hono/node-server';
 import { serveStatic } from '@hono/node-server/serve-static';
 import { createApp } from './app.js';
+import { parseKeyList, parseNameList } from './auth/auth-config.js';
+import { FixtureDirectory } from './fixtures/fixture-directory.js';
+import { loadScripts } from './scripts/script-directory.js';
+import { MarkovChain } from './models/markov-model.js';
+import { parseUpstream } from './openai-protocol/proxy.js';
+import { parseOrigins } from './middleware/cors.js';
+import { parseDeployments } from './azure-protocol/azure.js';
+import { parseChunking, type Chunking } from './openai-protocol/chunking.js';
+import { parseServiceTierDelays, type ServiceTierDelays } from './openai-protocol/service-tier.js';
+import { parseModelDefaults, type ModelDefaultsConfig } from './openai-protocol/model-defaults.js';
+import { parseScenarios, type Scenario } from './openai-protocol/scenarios.js';
+import { NODE_COMPRESSORS } from './middleware/node-compressors.js';
+import { CassetteDirectory } from './recording/cassette-directory.js';
+import { buildInfo, type BuildInfo } from './build-info.js';
+import { parseConfigFile, type ConfigFile } from './config-file.js';
+import { InvalidRequestError } from './openai-protocol/errors.js';
+import { isLogLevel, LOG_LEVELS, Logger, type LogLevel } from './utils/logger.js';
+import { BPE_PATTERNS, BpeTokenizer, isBpeEncoding } from './tokenizer/bpe.js';
+import { createNodeServer } from './node-server.js';
+import { createNodeWebSocket } from './node-websocket.js';
+import { sleep } from './utils/sleep.js';
+import { execFileSync } from 'child_process';
+import { appendFileSync, readFileSync, statSync } from 'fs';
 import path from 'path';
 import { fileURLToPath } from 'url';
 
@@ -15,8 +37,23 @@ const DEFAULT_API_KEY = 'testkey';
 function parseArgs() {
   const args = process.argv.slice(2);
   const config = {
-    port: DEFAULT_PORT,
-    apiKey: DEFAULT_API_KEY,
+    // Left undefined so a --config file can set them, falling back to the defaults
+    port: undefined as number | undefined,
+    apiKey: undefined as string | undefined,
+    configFile: undefined as string | undefined,
+    fixtures: undefined as string | undefined,
+    scripts: undefined as string | undefined,
+    corpus: undefined as string | undefined,
+    cassettes: undefined as string | undefined,
+    requestLog: undefined as string | undefined,
+    state: undefined as string | undefined,
+    tokenizer: undefined as string | undefined,
+    modelDefaults: undefined as string | undefined,
+    scenarios: undefined as string | undefined,
+    logFile: undefined as string | undefined,
+    tlsCert: undefined as string | undefined,
+    tlsKey: undefined as string | undefined,
+    exitWithStdin: false,
     help: false,
   };
 
@@ -46,6 +83,140 @@ function parseArgs() {
         }
         break;
       
+      case '--config':
+        if (nextArg) {
+          config.configFile = nextArg;
+          i++; // Skip next argument
+        } else {
+          console.error('Error: --config requires a TOML or JSON file');
+          process.exit(1);
+        }
+        break;
+      
+      case '--fixtures':
+        if (nextArg) {
+          config.fixtures = nextArg;
+          i++; // Skip next argument
+        } else {
+          console.error('Error: --fixtures requires a directory');
+          process.exit(1);
+        }
+        break;
+      
+      case '--scripts':
+        if (nextArg) {
+          config.scripts = nextArg;
+          i++; // Skip next argument
+        } else {
+          console.error('Error: --scripts requires a directory');
+          process.exit(1);
+        }
+        break;
+      
+      case '--corpus':
+        if (nextArg) {
+          config.corpus = nextArg;
+          i++; // Skip next argument
+        } else {
+          console.error('Error: --corpus requires a text file');
+          process.exit(1);
+        }
+        break;
+      
+      case '--cassettes':
+        if (nextArg) {
+          config.cassettes = nextArg;
+          i++; // Skip next argument
+        } else {
+          console.error('Error: --cassettes requires a directory');
+          process.exit(1);
+        }
+        break;
+      
+      case '--request-log':
+        if (nextArg) {
+          config.requestLog = nextArg;
+          i++; // Skip next argument
+        } else {
+          console.error('Error: --request-log requires a file');
+          process.exit(1);
+        }
+        break;
+      
+      case '--state':
+        if (nextArg) {
+          config.state = nextArg;
+          i++; // Skip next argument
+        } else {
+          console.error('Error: --state requires a file');
+          process.exit(1);
+        }
+        break;
+      
+      case '--tokenizer':
+        if (nextArg) {
+          config.tokenizer = nextArg;
+          i++; // Skip next argument
+        } else {
+          console.error('Error: --tokenizer requires a .tiktoken file');
+          process.exit(1);
+        }
+        break;
+      
+      case '--model-defaults':
+        if (nextArg) {
+          config.modelDefaults = nextArg;
+          i++; // Skip next argument
+        } else {
+          console.error('Error: --model-defaults requires a JSON file');
+          process.exit(1);
+        }
+        break;
+      
+      case '--scenarios':
+        if (nextArg) {
+          config.scenarios = nextArg;
+          i++; // Skip next argument
+        } else {
+          console.error('Error: --scenarios requires a JSON file');
+          process.exit(1);
+        }
+        break;
+      
+      case '--log-file':
+        if (nextArg) {
+          config.logFile = nextArg;
+          i++; // Skip next argument
+        } else {
+          console.error('Error: --log-file requires a file');
+          process.exit(1);
+        }
+        break;
+      
+      case '--tls-cert':
+        if (nextArg) {
+          config.tlsCert = nextArg;
+          i++; // Skip next argument
+        } else {
+          console.error('Error: --tls-cert requires a PEM file');
+          process.exit(1);
+        }
+        break;
+      
+      case '--tls-key':
+        if (nextArg) {
+          config.tlsKey = nextArg;
+          i++; // Skip next argument
+        } else {
+          console.error('Error: --tls-key requires a PEM file');
+          process.exit(1);
+        }
+        break;
+      
+      case '--exit-with-stdin':
+        config.exitWithStdin = true;
+        break;
+      
       case '--help':
       case '-h':
         config.help = true;
@@ -69,11 +240,55 @@ function showHelp() {
   console.log('Options:');
   console.log('  --port, -p <port>     Port to run the server on (default: 8080)');
   console.log('  --api-key <key>       API key for authentication (default: testkey)');
+  console.log('  --config <file>       Read settings from a TOML (or .json) file, reloaded on SIGHUP or POST /admin/reload');
+  console.log('  --fixtures <dir>      Serve the fixture model from JSON files in dir, reloading on change');
+  console.log('  --scripts <dir>       Serve each JavaScript module in dir as a script:<name> model');
+  console.log('  --corpus <file>       Serve the markov model, trained on the text in file');
+  console.log('  --cassettes <dir>     Save recorded cassettes as JSON files in dir (default: in memory)');
+  console.log('  --request-log <file>  Keep the request log in a SQLite database, Node.js 22.5+ (default: in memory)');
+  console.log('  --state <file>        Keep keys, usage, files and batches in a SQLite database across restarts,');
+  console.log('                        and the request log too unless --request-log is given (Node.js 22.5+)');
+  console.log('  --tokenizer <file>    Count tokens with a tiktoken rank file, o200k_base.tiktoken or cl100k_base.tiktoken');
+  console.log('                        (default: one token per word or symbol)');
+  console.log('  --model-defaults <file> Per-model max_tokens caps, forced temperature and system prompts, from JSON');
+  console.log('  --scenarios <file>    Script outcomes such as 429, 429, 500, ok for a key or session, from JSON');
+  console.log('  --log-file <file>     Append JSON log lines to file (default: stdout)');
+  console.log('  --tls-cert <file>     Serve HTTPS with this PEM certificate, offering HTTP/2 over ALPN');
+  console.log('  --tls-key <file>      Private key for --tls-cert');
+  console.log('  --exit-with-stdin     Exit once stdin closes, so a test harness that spawned the server takes it down');
   console.log('  --help, -h            Show this help message');
   console.log('');
+  console.log('Environment:');
+  console.log('  TEENYTINY_API_KEYS     Extra keys, e.g. "key1,key2:echo|eliza" limits key2 to echo and eliza');
+  console.log('  TEENYTINY_REVOKED_KEYS Comma-separated keys to reject with 401');
+  console.log('  TEENYTINY_ORGANIZATIONS Comma-separated OpenAI-Organization values to accept, 401 for others (default: any)');
+  console.log('  TEENYTINY_PROJECTS     Comma-separated OpenAI-Project values to accept, 401 for others (default: any)');
+  console.log('  TEENYTINY_FLAKY_RATE   Fraction of flaky model requests that fail (default: 0.5)');
+  console.log('  TEENYTINY_BATCH_STEP_MS Milliseconds per simulated step of a batch (default: 100)');
+  console.log('  TEENYTINY_MAX_BODY_BYTES Largest request body accepted (default: 8388608)');
+  console.log('  TEENYTINY_MAX_FILE_BYTES Largest file /v1/files accepts (default: 4194304)');
+  console.log('  TEENYTINY_DRAIN_GRACE_MS How long requests in flight get to finish after SIGTERM (default: 30000)');
+  console.log('  TEENYTINY_MAX_STREAMS Streamed responses open at once, 503 with Retry-After beyond (default: unlimited)');
+  console.log('  TEENYTINY_SESSION_TTL_MS Idle time after which a /session conversation is forgotten (default: 1800000)');
+  console.log('  TEENYTINY_MEMORY_TTL_MS Idle time after which the memory model forgets a conversation (default: 1800000)');
+  console.log('  TEENYTINY_IDEMPOTENCY_TTL_MS How long a response is replayed for a repeated Idempotency-Key (default: 86400000)');
+  console.log('  TEENYTINY_PROMPT_CACHE_TTL_MS How long an unused prompt prefix is reported as cached (default: 300000)');
+  console.log('  TEENYTINY_SERVICE_TIER_DELAYS Extra delay per service tier, such as flex=2000,default=200~50 (default: none)');
+  console.log('  TEENYTINY_LOG_LEVEL    Least severe log lines written: debug, info, warn or error (default: info)');
+  console.log('  TEENYTINY_CHUNKING     How chat streams are cut: model, token, word, bytes:N or message (default: model)');
+  console.log('  TEENYTINY_CORS_ORIGINS Comma-separated origins browsers may call from (default: any)');
+  console.log('  TEENYTINY_UPSTREAM     OpenAI-compatible base URL that proxy:<model> requests are forwarded to');
+  console.log('  TEENYTINY_UPSTREAM_KEY API key sent to the upstream');
+  console.log('  TEENYTINY_AZURE_DEPLOYMENTS Azure deployments and their models, e.g. "gpt-4o=eliza,gpt-35-turbo=echo"');
+  console.log('  TEENYTINY_GIT_SHA      Git sha reported by /version (default: the checkout\'s HEAD)');
+  console.log('  TEENYTINY_BUILD_TIME   Build time reported by /version (default: when the server was compiled)');
+  console.log('');
   console.log('Examples:');
   console.log('  npm run dev                    # Run on default port 8080');
   console.log('  npm run dev -- --port 3000     # Run on port 3000');
+  console.log('  npm run dev -- --fixtures fixtures  # Serve the example fixtures');
+  console.log('  npm run dev -- --scripts scripts    # Serve the example scripts');
+  console.log('  npm run dev -- --corpus corpus/example.txt  # Serve markov, trained on the example corpus');
   console.log('');
   console.log('API Usage:');
   console.log(`  curl -X POST http://localhost:${DEFAULT_PORT}/v1/chat/completions \\`);
@@ -84,6 +299,163 @@ function showHelp() {
   console.log('  llm -m echo "Hello!" # Using llm tool (configure with: llm keys set teenytiny)');
 }
 
+// Version from package.json, the git sha from TEENYTINY_GIT_SHA or the
+// checkout, and the build time from TEENYTINY_BUILD_TIME or when this file
+// was compiled
+function readBuildInfo(): BuildInfo {
+  const attempt = <T>(read: () => T): T | undefined => {
+    try {
+      return read();
+    } catch {
+      return undefined;
+    }
+  };
+  return buildInfo({
+    version: attempt(
+      () => JSON.parse(readFileSync(path.resolve(__dirname, '../package.json'), 'utf8')).version,
+    ),
+    gitSha:
+      process.env.TEENYTINY_GIT_SHA ||
+      attempt(() =>
+        execFileSync('git', ['rev-parse', '--short', 'HEAD'], {
+          cwd: __dirname,
+          stdio: ['ignore', 'pipe', 'ignore'],
+        })
+          .toString()
+          .trim(),
+      ),
+    buildTime:
+      process.env.TEENYTINY_BUILD_TIME || attempt(() => statSync(__filename).mtime.toISOString()),
+  });
+}
+
+// The encoding is named by the file, as in OpenAI's published rank files
+function loadTokenizer(file: string): BpeTokenizer {
+  const encoding = path.basename(file, '.tiktoken');
+  if (!isBpeEncoding(encoding)) {
+    console.error(`Error: --tokenizer expects ${Object.keys(BPE_PATTERNS).map(name => `${name}.tiktoken`).join(' or ')}`);
+    process.exit(1);
+  }
+  try {
+    return new BpeTokenizer(encoding, readFileSync(file, 'utf8'));
+  } catch (error) {
+    console.error(`Error: can't load --tokenizer ${file}: ${(error as Error).message}`);
+    process.exit(1);
+  }
+}
+
+function loadChunking(value: string): Chunking {
+  try {
+    return parseChunking(value, 'TEENYTINY_CHUNKING');
+  } catch (error) {
+    console.error(`Error: ${(error as Error).message}`);
+    process.exit(1);
+  }
+}
+
+function loadServiceTierDelays(value: string): ServiceTierDelays {
+  try {
+    return parseServiceTierDelays(value, 'TEENYTINY_SERVICE_TIER_DELAYS');
+  } catch (error) {
+    console.error(`Error: ${(error as Error).message}`);
+    process.exit(1);
+  }
+}
+
+// A numeric setting from the environment, undefined when it isn't set
+function loadNumber(name: string, expected: string, valid: (value: number) => boolean): number | undefined {
+  const text = process.env[name];
+  if (!text) {
+    return undefined;
+  }
+  const value = Number(text);
+  if (text.trim() === '' || !Number.isFinite(value) || !valid(value)) {
+    console.error(`Error: ${name} expects ${expected}, got '${text}'`);
+    process.exit(1);
+  }
+  return value;
+}
+
+const isCount = (value: number) => Number.isInteger(value) && value >= 0;
+const isPositiveCount = (value: number) => Number.isInteger(value) && value > 0;
+
+// Read whole at startup, and checked to be long enough to learn from
+function loadCorpus(file: string): string {
+  try {
+    const text = readFileSync(file, 'utf8');
+    new MarkovChain(text);
+    return text;
+  } catch (error) {
+    console.error(`Error: can't load --corpus ${file}: ${(error as Error).message}`);
+    process.exit(1);
+  }
+}
+
+function loadScenarios(file: string): Record<string, Scenario> {
+  try {
+    return parseScenarios(JSON.parse(readFileSync(file, 'utf8')));
+  } catch (error) {
+    console.error(`Error: can't load --scenarios ${file}: ${(error as Error).message}`);
+    process.exit(1);
+  }
+}
+
+function loadModelDefaults(file: string): ModelDefaultsConfig {
+  try {
+    return parseModelDefaults(JSON.parse(readFileSync(file, 'utf8')));
+  } catch (error) {
+    console.error(`Error: can't load --model-defaults ${file}: ${(error as Error).message}`);
+    process.exit(1);
+  }
+}
+
+function readConfigFile(file: string): ConfigFile {
+  let text: string;
+  try {
+    text = readFileSync(file, 'utf8');
+  } catch (error) {
+    throw new InvalidRequestError(`Can't read config file ${file}: ${(error as Error).message}`);
+  }
+  return parseConfigFile(text, file);
+}
+
+function loadConfigFile(file: string): ConfigFile {
+  try {
+    return readConfigFile(file);
+  } catch (error) {
+    console.error(`Error: ${(error as Error).message}`);
+    process.exit(1);
+  }
+}
+
+function loadLogger(value: string | undefined, file: string | undefined): Logger {
+  if (value !== undefined && !isLogLevel(value)) {
+    console.error(`Error: TEENYTINY_LOG_LEVEL must be one of ${LOG_LEVELS.join(', ')}`);
+    process.exit(1);
+  }
+  const level: LogLevel | undefined = value;
+  if (!file) {
+    return new Logger(level);
+  }
+  try {
+    appendFileSync(file, '');
+  } catch (error) {
+    console.error(`Error: can't write --log-file ${file}: ${(error as Error).message}`);
+    process.exit(1);
+  }
+  // Written synchronously, so lines keep their order and none are lost on exit
+  return new Logger(level, line => appendFileSync(file, `${line}\n`));
+}
+
+function loadTls(certFile: string, keyFile: string): { cert: Buffer; key: Buffer } {
+  try {
+    return { cert: readFileSync(certFile), key: readFileSync(keyFile) };
+  } catch (error) {
+    console.error(`Error: can't read the TLS certificate or key: ${(error as Error).message}`);
+    process.exit(1);
+  }
+}
+
 function maskAPIKey(key: string): string {
   if (key.length <= 6) {
     return '***';
@@ -98,56 +470,189 @@ async function main() {
     showHelp();
     process.exit(0);
   }
+  if (!config.tlsCert !== !config.tlsKey) {
+    console.error('Error: --tls-cert and --tls-key must be given together');
+    process.exit(1);
+  }
+
+  const logger = loadLogger(process.env.TEENYTINY_LOG_LEVEL || undefined, config.logFile);
+  const configFile = config.configFile ? loadConfigFile(config.configFile) : undefined;
+  const port = config.port ?? configFile?.port ?? DEFAULT_PORT;
+  const apiKey = config.apiKey ?? configFile?.apiKey ?? DEFAULT_API_KEY;
+  const fixtures = config.fixtures ? new FixtureDirectory(config.fixtures) : undefined;
+  fixtures?.watch();
+  const scripts = config.scripts ? await loadScripts(config.scripts) : undefined;
+  const corpus = config.corpus ? loadCorpus(config.corpus) : undefined;
+  const upstream = parseUpstream(process.env.TEENYTINY_UPSTREAM, process.env.TEENYTINY_UPSTREAM_KEY);
+  const corsOrigins = parseOrigins(process.env.TEENYTINY_CORS_ORIGINS);
+  const deployments = parseDeployments(process.env.TEENYTINY_AZURE_DEPLOYMENTS);
+  const tokenizer = config.tokenizer ? loadTokenizer(config.tokenizer) : undefined;
+  const chunking = process.env.TEENYTINY_CHUNKING ? loadChunking(process.env.TEENYTINY_CHUNKING) : undefined;
+  const serviceTiers = process.env.TEENYTINY_SERVICE_TIER_DELAYS
+    ? loadServiceTierDelays(process.env.TEENYTINY_SERVICE_TIER_DELAYS)
+    : undefined;
+  const modelDefaults = config.modelDefaults ? loadModelDefaults(config.modelDefaults) : undefined;
+  const flakyRate = loadNumber('TEENYTINY_FLAKY_RATE', 'a fraction from 0 to 1', value => value >= 0 && value <= 1);
+  const batchStepMs = loadNumber('TEENYTINY_BATCH_STEP_MS', 'a whole number of milliseconds', isCount);
+  const maxBodyBytes = loadNumber('TEENYTINY_MAX_BODY_BYTES', 'a positive whole number of bytes', isPositiveCount);
+  const maxFileBytes = loadNumber('TEENYTINY_MAX_FILE_BYTES', 'a positive whole number of bytes', isPositiveCount);
+  const maxStreams = loadNumber('TEENYTINY_MAX_STREAMS', 'a positive whole number of streams', isPositiveCount);
+  const sessionTtlMs = loadNumber('TEENYTINY_SESSION_TTL_MS', 'a whole number of milliseconds', isCount);
+  const memoryTtlMs = loadNumber('TEENYTINY_MEMORY_TTL_MS', 'a whole number of milliseconds', isCount);
+  const idempotencyTtlMs = loadNumber('TEENYTINY_IDEMPOTENCY_TTL_MS', 'a whole number of milliseconds', isCount);
+  const promptCacheTtlMs = loadNumber('TEENYTINY_PROMPT_CACHE_TTL_MS', 'a whole number of milliseconds', isCount);
+  const drainGraceMs = loadNumber('TEENYTINY_DRAIN_GRACE_MS', 'a whole number of milliseconds', isCount);
+  const scenarios = config.scenarios ? loadScenarios(config.scenarios) : undefined;
+  // node:sqlite is only loaded when asked for, so older Node.js versions still run
+  const stateFile = config.state ?? configFile?.state;
+  const state = stateFile
+    ? new (await import('./persistence/sqlite-state-store.js')).SqliteStateStore(stateFile)
+    : undefined;
+  const requestLogFile = config.requestLog ?? stateFile;
+  const requestLog = requestLogFile
+    ? new (await import('./capture/sqlite-request-log.js')).SqliteRequestLog(requestLogFile)
+    : undefined;
+
+  // Counted as the server accepts and closes sockets, for /metrics
+  let openConnections = 0;
+
+  // WebSocket upgrades go through the app, then the handshake is done here
+  const websocket = createNodeWebSocket();
 
   // Create the app
   const app = createApp({
+    build: readBuildInfo(),
+    logger,
+    compression: NODE_COMPRESSORS,
+    upgradeWebSocket: websocket.upgradeWebSocket,
+    processStats: () => ({
+      rss_bytes: process.memoryUsage.rss(),
+      open_connections: openConnections,
+    }),
     auth: {
-      apiKey: config.apiKey,
+      apiKey,
+      keys: parseKeyList(process.env.TEENYTINY_API_KEYS),
+      revokedKeys: parseKeyList(process.env.TEENYTINY_REVOKED_KEYS).map(({ key }) => key),
+      organizations: parseNameList(process.env.TEENYTINY_ORGANIZATIONS),
+      projects: parseNameList(process.env.TEENYTINY_PROJECTS),
     },
+    ...(flakyRate !== undefined ? { faults: { failureRate: flakyRate } } : {}),
+    ...(batchStepMs !== undefined ? { batches: { stepMs: batchStepMs } } : {}),
+    ...(maxBodyBytes !== undefined ? { limits: { maxBodyBytes } } : {}),
+    ...(maxFileBytes !== undefined ? { files: { maxFileBytes } } : {}),
+    ...(maxStreams !== undefined ? { streams: { maxConcurrent: maxStreams } } : {}),
+    ...(sessionTtlMs !== undefined ? { sessions: { ttlMs: sessionTtlMs } } : {}),
+    ...(memoryTtlMs !== undefined ? { memory: { ttlMs: memoryTtlMs } } : {}),
+    ...(idempotencyTtlMs !== undefined ? { idempotency: { ttlMs: idempotencyTtlMs } } : {}),
+    ...(promptCacheTtlMs !== undefined ? { promptCache: { ttlMs: promptCacheTtlMs } } : {}),
+    ...(corsOrigins ? { cors: { allowOrigins: corsOrigins } } : {}),
+    ...(fixtures ? { fixtures } : {}),
+    ...(scripts ? { scripts } : {}),
+    ...(corpus ? { corpus } : {}),
+    ...(upstream ? { upstream } : {}),
+    ...(deployments ? { azure: { deployments } } : {}),
+    ...(config.cassettes ? { cassettes: new CassetteDirectory(config.cassettes) } : {}),
+    ...(requestLog ? { requestLog } : {}),
+    ...(state ? { state } : {}),
+    ...(tokenizer ? { tokenizer } : {}),
+    ...(chunking ? { chunking } : {}),
+    ...(serviceTiers ? { serviceTiers } : {}),
+    ...(modelDefaults ? { modelDefaults } : {}),
+    ...(scenarios ? { scenarios } : {}),
+    ...(configFile?.middleware ? { middleware: configFile.middleware } : {}),
+    ...(configFile
+      ? { settings: configFile.settings, reload: () => readConfigFile(config.configFile!).settings }
+      : {}),
   });
 
   // Add static file serving for development (Node.js only)
   const websiteRoot = path.resolve(__dirname, '../../website');
   app.use('/*', serveStatic({ root: websiteRoot }));
 
-  console.log(JSON.stringify({
-    level: 'info',
-    message: 'Starting TeenyTiny AI server',
-    port: config.port,
-    api_key: maskAPIKey(config.apiKey),
-  }));
-
-  // Start the server
-  serve({
-    fetch: app.fetch,
-    port: config.port,
+  logger.info('Starting TeenyTiny AI server', {
+    port,
+    api_key: maskAPIKey(apiKey),
   });
 
-  console.log(JSON.stringify({
-    level: 'info',
-    message: 'Server started successfully',
-    address: `http://localhost:${config.port}`,
-    health_check: `http://localhost:${config.port}/health`,
-    models_endpoint: `http://localhost:${config.port}/v1/models`,
-    chat_endpoint: `http://localhost:${config.port}/v1/chat/completions`,
-  }));
+  // Start the server, speaking HTTP/1.1 and HTTP/2 on the one port
+  const tls = config.tlsCert && config.tlsKey ? loadTls(config.tlsCert, config.tlsKey) : undefined;
+  const server = createNodeServer(app.fetch, {
+    ...(tls ? { tls } : {}),
+    upgrade: websocket.upgradeListener(app.fetch),
+  });
+  server.on('connection', socket => {
+    openConnections++;
+    socket.once('close', () => openConnections--);
+  });
+  server.listen(port);
+
+  const address = `${tls ? 'https' : 'http'}://localhost:${port}`;
+  logger.info('Server started successfully', {
+    address,
+    health_check: `${address}/health`,
+    models_endpoint: `${address}/v1/models`,
+    chat_endpoint: `${address}/v1/chat/completions`,
+    realtime_endpoint: `${address.replace('http', 'ws')}/v1/realtime`,
+  });
+
+  // Reload the config file through the admin API, so a bad file is reported
+  // the same way either way
+  process.on('SIGHUP', async () => {
+    const response = await app.request('/admin/reload', {
+      method: 'POST',
+      headers: { Authorization: `Bearer ${apiKey}` },
+    });
+    const body = await response.json();
+    if (response.ok) {
+      logger.info('Reloaded config file', { file: config.configFile });
+    } else {
+      logger.error('Failed to reload config file', { error: body.error?.message });
+    }
+  });
+
+  if (config.exitWithStdin) {
+    process.stdin.on('end', () => process.exit(0));
+    process.stdin.resume();
+  }
 
   // Graceful shutdown
   process.on('SIGINT', () => {
-    console.log(JSON.stringify({
-      level: 'info',
-      message: 'Server shutting down gracefully...',
-    }));
+    logger.info('Server shutting down gracefully...');
     
     process.exit(0);
   });
 
-  process.on('SIGTERM', () => {
-    console.log(JSON.stringify({
-      level: 'info',
-      message: 'Server shutting down gracefully...',
-    }));
-    
+  // Drain through the admin API, then exit once the requests in flight are
+  // done or the grace period runs out. A second SIGTERM exits at once.
+  let draining = false;
+  process.on('SIGTERM', async () => {
+    if (draining) {
+      logger.info('Server shutting down without waiting for the drain');
+      process.exit(0);
+    }
+    draining = true;
+
+    const headers = { Authorization: `Bearer ${apiKey}` };
+    const response = await app.request('/admin/drain', {
+      method: 'POST',
+      headers,
+      body: JSON.stringify(drainGraceMs !== undefined ? { grace_ms: drainGraceMs } : {}),
+    });
+    let status = await response.json();
+    if (!response.ok) {
+      logger.error('Failed to drain before shutting down', { error: status.error?.message });
+      process.exit(1);
+    }
+    logger.info('Server draining before shutting down...', status);
+
+    while (status.in_flight > 0 && status.grace_remaining_ms > 0) {
+      await sleep(100);
+      status = await (await app.request('/admin/drain', { headers })).json();
+    }
+    if (status.in_flight > 0) {
+      logger.warn('Grace period ran out with requests in flight', { in_flight: status.in_flight });
+    }
+    logger.info('Server shutting down gracefully...');
     process.exit(0);
   });
 }
@@ -0,0 +1,59 @@
+import { describe, it, expect } from "vitest";
+import { SessionStore } from "./session-store.js";
+
+describe("SessionStore", () => {
+  it("should accumulate history per session", () => {
+    const store = new SessionStore();
+
+    store.append("a", { role: "user", content: "hello" });
+    store.append("a", { role: "assistant", content: "hi" });
+    store.append("b", { role: "user", content: "other" });
+
+    expect(store.history("a")).toEqual([
+      { role: "user", content: "hello" },
+      { role: "assistant", content: "hi" },
+    ]);
+    expect(store.history("b")).toHaveLength(1);
+  });
+
+  it("should forget sessions idle for longer than the TTL", () => {
+    let now = 0;
+    const store = new SessionStore(1000, () => now);
+
+    store.append("a", { role: "user", content: "hello" });
+    now = 999;
+    expect(store.history("a")).toHaveLength(1);
+
+    store.append("a", { role: "user", content: "again" });
+    now = 1998;
+    expect(store.history("a")).toHaveLength(2);
+
+    now = 3000;
+    expect(store.history("a")).toEqual([]);
+  });
+
+  it("should list live sessions and forget deleted ones", () => {
+    let now = 0;
+    const store = new SessionStore(1000, () => now);
+    store.append("a", { role: "user", content: "hello" });
+    now = 600;
+    store.append("b", { role: "user", content: "other" });
+
+    now = 1200;
+    expect(store.list()).toEqual([
+      { id: "b", messages: [{ role: "user", content: "other" }], lastUsed: 600, expiresAt: 1600 },
+    ]);
+    expect(store.delete("b")).toBe(true);
+    expect(store.delete("b")).toBe(false);
+    expect(store.list()).toEqual([]);
+  });
+
+  it("should return a copy that callers cannot mutate", () => {
+    const store = new SessionStore();
+    store.append("a", { role: "user", content: "hello" });
+
+    store.history("a").push({ role: "user", content: "sneaky" });
+
+    expect(store.history("a")).toHaveLength(1);
+  });
+});
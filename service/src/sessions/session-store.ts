@@ -0,0 +1,70 @@
+import type { ChatCompletionRequestMessage } from '../openai-protocol/types.js';
+
+interface Session {
+  messages: ChatCompletionRequestMessage[];
+  lastUsed: number;
+}
+
+/**
+ * SessionStore - In-memory conversation history keyed by session id
+ *
+ * Sessions expire after ttlMs without activity. Storage lives as long as the
+ * app instance, so on Cloudflare Workers history only survives within one isolate.
+ */
+export class SessionStore {
+  private sessions = new Map<string, Session>();
+
+  constructor(
+    private ttlMs: number = 30 * 60 * 1000,
+    private now: () => number = Date.now
+  ) {}
+
+  /**
+   * Returns the history for a session, empty if it never existed or has expired
+   */
+  history(id: string): ChatCompletionRequestMessage[] {
+    this.evictExpired();
+    return [...(this.sessions.get(id)?.messages ?? [])];
+  }
+
+  /**
+   * Appends messages to a session, creating it if needed
+   */
+  append(id: string, ...messages: ChatCompletionRequestMessage[]): void {
+    this.evictExpired();
+    const session = this.sessions.get(id) ?? { messages: [], lastUsed: 0 };
+    session.messages.push(...messages);
+    session.lastUsed = this.now();
+    this.sessions.set(id, session);
+  }
+
+  /**
+   * Every live session, with when it was last used and when it will expire
+   */
+  list(): { id: string; messages: ChatCompletionRequestMessage[]; lastUsed: number; expiresAt: number }[] {
+    this.evictExpired();
+    return [...this.sessions].map(([id, session]) => ({
+      id,
+      messages: [...session.messages],
+      lastUsed: session.lastUsed,
+      expiresAt: session.lastUsed + this.ttlMs,
+    }));
+  }
+
+  /**
+   * Forgets a session, returning whether there was one
+   */
+  delete(id: string): boolean {
+    this.evictExpired();
+    return this.sessions.delete(id);
+  }
+
+  private evictExpired(): void {
+    const cutoff = this.now() - this.ttlMs;
+    for (const [id, session] of this.sessions) {
+      if (session.lastUsed < cutoff) {
+        this.sessions.delete(id);
+      }
+    }
+  }
+}
@@ -0,0 +1,54 @@
+import { describe, it, expect } from "vitest";
+import { BpeTokenizer } from "./bpe.js";
+
+// A tiny rank file: every byte used below, then merges in rank order
+function ranks(tokens: string[]): string {
+  return tokens.map((token, rank) => `${btoa(token)} ${rank}`).join("\n");
+}
+
+const bytes = [..." abcdehlorw!,'s"];
+
+describe("BpeTokenizer", () => {
+  it("should count unmerged bytes when no merges apply", () => {
+    const tokenizer = new BpeTokenizer("cl100k_base", ranks(bytes));
+
+    expect(tokenizer.count("abc")).toBe(3);
+  });
+
+  it("should merge the lowest ranked pair first", () => {
+    // "bc" outranks "ab" and "cd", so "abcd" becomes a + bc + d, not ab + cd
+    const tokenizer = new BpeTokenizer("cl100k_base", ranks([...bytes, "bc", "ab", "cd"]));
+
+    expect(tokenizer.count("abcd")).toBe(3);
+  });
+
+  it("should count whole pieces that are known tokens as one", () => {
+    const tokenizer = new BpeTokenizer("cl100k_base", ranks([...bytes, " world"]));
+
+    // "hello" is split into bytes, " world" is one token, "!" is one more
+    expect(tokenizer.count("hello world!")).toBe(5 + 1 + 1);
+  });
+
+  it("should split text the way tiktoken does before merging", () => {
+    const tokenizer = new BpeTokenizer("o200k_base", ranks([...bytes, "he's", "He's"]));
+
+    // Contractions stay with their word, in either case
+    expect(tokenizer.count("he's")).toBe(1);
+    expect(tokenizer.count("HE'S")).toBe(4);
+  });
+
+  it("should count bytes of characters outside the ranks", () => {
+    const tokenizer = new BpeTokenizer("cl100k_base", ranks(bytes));
+
+    // é is two bytes in UTF-8, neither of them a known token
+    expect(tokenizer.count("é")).toBe(2);
+  });
+
+  it("should split into tokens without cutting characters", () => {
+    const tokenizer = new BpeTokenizer("cl100k_base", ranks([...bytes, " world"]));
+
+    expect(tokenizer.split("hello world!")).toEqual(["h", "e", "l", "l", "o", " world", "!"]);
+    // é's two bytes are two tokens, but only whole characters are sent
+    expect(tokenizer.split("é")).toEqual(["é"]);
+  });
+});
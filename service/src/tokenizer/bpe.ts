@@ -0,0 +1,110 @@
+import type { Tokenizer } from './tokenizer.js';
+
+// tiktoken's pre-tokenization patterns. JavaScript has no inline (?i:...), so
+// contractions spell out both cases.
+const CONTRACTION = "'(?:[sS]|[tT]|[rR][eE]|[vV][eE]|[mM]|[lL][lL]|[dD])";
+
+export const BPE_PATTERNS = {
+  cl100k_base: [
+    CONTRACTION,
+    '[^\\r\\n\\p{L}\\p{N}]?\\p{L}+',
+    '\\p{N}{1,3}',
+    ' ?[^\\s\\p{L}\\p{N}]+[\\r\\n]*',
+    '\\s*[\\r\\n]+',
+    '\\s+(?!\\S)',
+    '\\s+',
+  ].join('|'),
+  o200k_base: [
+    `[^\\r\\n\\p{L}\\p{N}]?[\\p{Lu}\\p{Lt}\\p{Lm}\\p{Lo}\\p{M}]*[\\p{Ll}\\p{Lm}\\p{Lo}\\p{M}]+(?:${CONTRACTION})?`,
+    `[^\\r\\n\\p{L}\\p{N}]?[\\p{Lu}\\p{Lt}\\p{Lm}\\p{Lo}\\p{M}]+[\\p{Ll}\\p{Lm}\\p{Lo}\\p{M}]*(?:${CONTRACTION})?`,
+    '\\p{N}{1,3}',
+    ' ?[^\\s\\p{L}\\p{N}]+[\\r\\n/]*',
+    '\\s*[\\r\\n]+',
+    '\\s+(?!\\S)',
+    '\\s+',
+  ].join('|'),
+};
+
+export type BpeEncoding = keyof typeof BPE_PATTERNS;
+
+export function isBpeEncoding(name: string): name is BpeEncoding {
+  return Object.hasOwn(BPE_PATTERNS, name);
+}
+
+/**
+ * BpeTokenizer - byte pair encoding as done by OpenAI's tiktoken
+ *
+ * Ranks come from a .tiktoken file (one base64 token and its rank per line),
+ * such as o200k_base.tiktoken for the GPT-4o family or cl100k_base.tiktoken for
+ * GPT-4 and GPT-3.5. Special tokens like <|endoftext|> are counted as text.
+ */
+export class BpeTokenizer implements Tokenizer {
+  // Tokens as binary strings, one character per byte
+  private ranks = new Map<string, number>();
+  private pattern: RegExp;
+  private encoder = new TextEncoder();
+  private decoder = new TextDecoder();
+
+  constructor(readonly name: BpeEncoding, ranks: string) {
+    for (const line of ranks.split('\n')) {
+      const [token, rank] = line.split(' ');
+      if (token && rank) {
+        this.ranks.set(atob(token), Number(rank));
+      }
+    }
+    this.pattern = new RegExp(BPE_PATTERNS[name], 'gu');
+  }
+
+  count(text: string): number {
+    let tokens = 0;
+    for (const [piece] of text.matchAll(this.pattern)) {
+      tokens += this.bounds(this.encoder.encode(piece)).length - 1;
+    }
+    return tokens;
+  }
+
+  // Tokens that would be counted, except that a token ending partway through a
+  // character is joined with the next so every piece is valid text
+  split(text: string): string[] {
+    const pieces: string[] = [];
+    for (const [piece] of text.matchAll(this.pattern)) {
+      const encoded = this.encoder.encode(piece);
+      // Continuation bytes look like 10xxxxxx
+      const bounds = this.bounds(encoded).filter(i => i === encoded.length || (encoded[i]! & 0xc0) !== 0x80);
+      for (let i = 0; i + 1 < bounds.length; i++) {
+        pieces.push(this.decoder.decode(encoded.subarray(bounds[i]!, bounds[i + 1]!)));
+      }
+    }
+    return pieces;
+  }
+
+  // Byte offsets between the tokens of one pre-tokenized piece
+  private bounds(encoded: Uint8Array): number[] {
+    let bytes = '';
+    for (const byte of encoded) {
+      bytes += String.fromCharCode(byte);
+    }
+    return this.ranks.has(bytes) ? [0, bytes.length] : this.merge(bytes);
+  }
+
+  // Repeatedly merges the adjacent pair with the lowest rank, returning the
+  // bounds of the parts left once no pair is a known token
+  private merge(bytes: string): number[] {
+    const bounds = Array.from({ length: bytes.length + 1 }, (_, i) => i);
+    for (;;) {
+      let best = -1;
+      let bestRank = Infinity;
+      for (let i = 0; i + 2 < bounds.length; i++) {
+        const rank = this.ranks.get(bytes.slice(bounds[i]!, bounds[i + 2]!));
+        if (rank !== undefined && rank < bestRank) {
+          best = i;
+          bestRank = rank;
+        }
+      }
+      if (best < 0) {
+        return bounds;
+      }
+      bounds.splice(best + 1, 1);
+    }
+  }
+}
@@ -0,0 +1,54 @@
+import { describe, it, expect } from "vitest";
+import { WhitespaceTokenizer, countChatTokens } from "./tokenizer.js";
+import type { Tokenizer } from "./tokenizer.js";
+
+describe("WhitespaceTokenizer", () => {
+  const tokenizer = new WhitespaceTokenizer();
+
+  it("should count words and symbols", () => {
+    expect(tokenizer.count("Hello, world!")).toBe(4);
+    expect(tokenizer.count("  spaced\n\tout  ")).toBe(2);
+    expect(tokenizer.count("")).toBe(0);
+  });
+
+  it("should keep accented words and emoji apart", () => {
+    expect(tokenizer.count("Zoë à Paris")).toBe(3);
+    expect(tokenizer.count("Party 🎉🥳")).toBe(3);
+  });
+
+  it("should split text into the tokens it counts", () => {
+    expect(tokenizer.split("Hello, world!")).toEqual(["Hello", ",", " world", "!"]);
+    expect(tokenizer.split("  spaced\n\tout  ")).toEqual(["  spaced", "\n\tout  "]);
+    expect(tokenizer.split("Party 🎉🥳").join("")).toBe("Party 🎉🥳");
+  });
+});
+
+describe("countChatTokens", () => {
+  // One token per character makes the overhead easy to see
+  const chars: Tokenizer = { name: "chars", count: (text) => [...text].length, split: (text) => [...text] };
+
+  it("should add the chat formatting overhead", () => {
+    const tokens = countChatTokens(chars, [
+      { role: "system", content: "Be brief" },
+      { role: "user", content: "Hi", name: "ann" },
+    ]);
+
+    // 3 for the reply, 3 per message, roles, contents, and 1 plus the name
+    expect(tokens).toBe(3 + (3 + 6 + 8) + (3 + 4 + 2) + (3 + 1));
+  });
+
+  it("should count the text parts of multimodal messages", () => {
+    const tokens = countChatTokens(chars, [
+      {
+        role: "user",
+        content: [
+          { type: "text", text: "What is" },
+          { type: "image_url", image_url: { url: "https://example.com/cat.png" } },
+          { type: "text", text: " this?" },
+        ],
+      },
+    ]);
+
+    expect(tokens).toBe(3 + 3 + 4 + "What is this?".length);
+  });
+});
@@ -0,0 +1,63 @@
+// Token counting for usage reporting and max_tokens budgets
+import type { ChatCompletionRequestMessage } from '../openai-protocol/types.js';
+
+export interface Tokenizer {
+  // Reported by /version, e.g. "o200k_base"
+  readonly name: string;
+  count(text: string): number;
+  // The text cut into tokens, which join back into the text
+  split(text: string): string[];
+}
+
+/**
+ * WhitespaceTokenizer - the fallback when no BPE ranks are loaded
+ *
+ * Counts each run of letters and digits, and each other non-space character,
+ * as one token. Close to BPE counts for plain English, low for languages
+ * written without spaces.
+ */
+export class WhitespaceTokenizer implements Tokenizer {
+  readonly name = 'whitespace';
+
+  count(text: string): number {
+    return text.match(/[\p{L}\p{M}\p{N}]+|[^\s\p{L}\p{M}\p{N}]/gu)?.length ?? 0;
+  }
+
+  // Whitespace goes with the token after it, as in BPE, or the last token
+  split(text: string): string[] {
+    return text.match(/\s*(?:[\p{L}\p{M}\p{N}]+|[^\s\p{L}\p{M}\p{N}])(?:\s+$)?|\s+/gu) ?? [];
+  }
+}
+
+// Chat formatting overhead, as in OpenAI's guide to counting tokens: each
+// message is wrapped in 3 tokens, a name costs 1 more, and the reply is primed
+// with 3
+const TOKENS_PER_MESSAGE = 3;
+const TOKENS_PER_NAME = 1;
+const REPLY_PRIMING_TOKENS = 3;
+
+/**
+ * Counts the prompt tokens of a conversation the way clients estimate them
+ */
+export function countChatTokens(tokenizer: Tokenizer, messages: ChatCompletionRequestMessage[]): number {
+  let tokens = REPLY_PRIMING_TOKENS;
+  for (const message of messages) {
+    tokens += TOKENS_PER_MESSAGE + tokenizer.count(message.role) + tokenizer.count(messageText(message));
+    if (message.name) {
+      tokens += tokenizer.count(message.name) + TOKENS_PER_NAME;
+    }
+    if (message.tool_calls) {
+      tokens += tokenizer.count(JSON.stringify(message.tool_calls));
+    }
+  }
+  return tokens;
+}
+
+function messageText(message: ChatCompletionRequestMessage): string {
+  if (typeof message.content === 'string') {
+    return message.content;
+  }
+  return (message.content ?? [])
+    .map(part => (part.type === 'text' ? part.text : ''))
+    .join('');
+}
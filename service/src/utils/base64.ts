@@ -0,0 +1,17 @@
+// Base64 that works the same on Node.js and Cloudflare Workers
+
+// Bytes are turned into a binary string a chunk at a time, as spreading a
+// whole file into String.fromCharCode would overflow the call stack
+const CHUNK_BYTES = 0x8000;
+
+export function toBase64(bytes: Uint8Array): string {
+  let binary = '';
+  for (let i = 0; i < bytes.length; i += CHUNK_BYTES) {
+    binary += String.fromCharCode(...bytes.subarray(i, i + CHUNK_BYTES));
+  }
+  return btoa(binary);
+}
+
+export function fromBase64(text: string): Uint8Array {
+  return Uint8Array.from(atob(text), char => char.charCodeAt(0));
+}
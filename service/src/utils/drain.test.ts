@@ -0,0 +1,32 @@
+import { describe, it, expect } from "vitest";
+import { Drain } from "./drain.js";
+
+describe("Drain", () => {
+  it("should finish once the last request in flight is done", () => {
+    const drain = new Drain();
+    const first = drain.track();
+    const second = drain.track();
+    expect(drain.finished(0)).toBe(false);
+
+    drain.start(1000, 0);
+    first();
+    first();
+    expect(drain.finished(0)).toBe(false);
+    expect(drain.snapshot(250)).toEqual({ draining: true, in_flight: 1, grace_remaining_ms: 750 });
+
+    second();
+    expect(drain.finished(0)).toBe(true);
+  });
+
+  it("should give up on requests still in flight after the grace period", () => {
+    const drain = new Drain();
+    drain.track();
+    drain.start(1000, 0);
+    // Draining again doesn't buy more time
+    drain.start(5000, 500);
+
+    expect(drain.finished(999)).toBe(false);
+    expect(drain.finished(1000)).toBe(true);
+    expect(drain.snapshot(2000).grace_remaining_ms).toBe(0);
+  });
+});
@@ -0,0 +1,56 @@
+// Draining, for a graceful shutdown: the server stops taking new requests but
+// lets those it already took finish, as a server taken out of a load balancer
+// before it stops would
+
+// How long requests in flight get to finish when no grace period is given
+export const DEFAULT_DRAIN_GRACE_MS = 30_000;
+
+// How long a client turned away while draining is told to wait
+export const DRAIN_RETRY_AFTER_SECONDS = 1;
+
+/**
+ * Drain - Counts requests in flight and stops new ones once draining
+ *
+ * A streamed response is in flight until its last chunk is sent, not only
+ * until its headers are, so streams are counted for as long as they are open.
+ */
+export class Drain {
+  private inFlight = 0;
+  private deadline?: number;
+
+  get draining(): boolean {
+    return this.deadline !== undefined;
+  }
+
+  // Counts a request in flight, returning the function that marks it done
+  track(): () => void {
+    this.inFlight++;
+    let done = false;
+    return () => {
+      if (!done) {
+        done = true;
+        this.inFlight--;
+      }
+    };
+  }
+
+  // Starts draining. Draining again doesn't move the deadline, so a second
+  // request can't keep the server up for longer.
+  start(graceMs: number = DEFAULT_DRAIN_GRACE_MS, now: number = Date.now()): void {
+    this.deadline ??= now + graceMs;
+  }
+
+  // Whether a draining server can stop: nothing is in flight, or the grace
+  // period ran out first
+  finished(now: number = Date.now()): boolean {
+    return this.deadline !== undefined && (this.inFlight === 0 || now >= this.deadline);
+  }
+
+  snapshot(now: number = Date.now()) {
+    return {
+      draining: this.draining,
+      in_flight: this.inFlight,
+      grace_remaining_ms: this.deadline === undefined ? null : Math.max(0, this.deadline - now),
+    };
+  }
+}
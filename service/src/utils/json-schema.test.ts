@@ -0,0 +1,73 @@
+import { describe, it, expect } from "vitest";
+import { MAX_SYNTHESIZED_ITEMS, MAX_SYNTHESIZED_LENGTH, synthesize } from "./json-schema.js";
+
+describe("JSON schema synthesis", () => {
+  it("should fill in required properties only", () => {
+    const schema = {
+      type: "object",
+      properties: {
+        city: { type: "string" },
+        unit: { type: "string", enum: ["celsius", "fahrenheit"] },
+        days: { type: "integer" },
+      },
+      required: ["city", "unit"],
+    };
+
+    expect(synthesize(schema)).toEqual({ city: "string", unit: "celsius" });
+  });
+
+  it("should respect numeric bounds", () => {
+    expect(synthesize({ type: "integer", minimum: 5, maximum: 10 })).toBe(5);
+    expect(synthesize({ type: "number", maximum: -3 })).toBe(-3);
+    expect(synthesize({ type: "integer", exclusiveMinimum: 0 })).toBe(1);
+    expect(synthesize({ type: "integer", minimum: 7, multipleOf: 5 })).toBe(10);
+  });
+
+  it("should respect string lengths and formats", () => {
+    expect(synthesize({ type: "string", minLength: 10 })).toHaveLength(10);
+    expect(synthesize({ type: "string", maxLength: 3 })).toBe("str");
+    expect(synthesize({ type: "string", format: "date" })).toBe("2024-01-01");
+  });
+
+  it("should cap oversized lower bounds", () => {
+    expect(synthesize({ type: "string", minLength: 1e12 })).toHaveLength(MAX_SYNTHESIZED_LENGTH);
+    expect(synthesize({ type: "array", items: { type: "integer" }, minItems: 1e9 })).toHaveLength(MAX_SYNTHESIZED_ITEMS);
+  });
+
+  it("should build arrays with the minimum number of items", () => {
+    expect(synthesize({ type: "array", items: { type: "boolean" }, minItems: 2 })).toEqual([true, true]);
+    expect(synthesize({ type: "array", items: { type: "string" } })).toEqual([]);
+  });
+
+  it("should resolve local refs and nested objects", () => {
+    const schema = {
+      type: "object",
+      properties: { owner: { $ref: "#/$defs/person" } },
+      required: ["owner"],
+      $defs: {
+        person: {
+          type: "object",
+          properties: { name: { type: "string" }, age: { type: ["integer", "null"] } },
+          required: ["name", "age"],
+        },
+      },
+    };
+
+    expect(synthesize(schema)).toEqual({ owner: { name: "string", age: 0 } });
+  });
+
+  it("should terminate on recursive schemas", () => {
+    const schema = {
+      type: "object",
+      properties: { child: { $ref: "#" } },
+      required: ["child"],
+    };
+
+    expect(() => JSON.stringify(synthesize(schema))).not.toThrow();
+  });
+
+  it("should use consts and the first anyOf option", () => {
+    expect(synthesize({ const: "fixed" })).toBe("fixed");
+    expect(synthesize({ anyOf: [{ type: "integer" }, { type: "string" }] })).toBe(0);
+  });
+});
@@ -0,0 +1,156 @@
+// Synthesizes a minimal value satisfying a JSON schema
+//
+// Used to fill in tool call arguments and structured outputs. Covers the
+// subset of JSON Schema that OpenAI accepts for function parameters and
+// response formats: types, required properties, enums and consts, numeric
+// and length bounds, arrays, anyOf, and local $refs.
+
+export type JsonSchema = Record<string, unknown>;
+
+const STRING_FORMATS: Record<string, string> = {
+  'date-time': '2024-01-01T00:00:00Z',
+  date: '2024-01-01',
+  time: '00:00:00',
+  email: 'user@example.com',
+  uri: 'https://example.com',
+  uuid: '00000000-0000-4000-8000-000000000000',
+  hostname: 'example.com',
+  ipv4: '127.0.0.1',
+  ipv6: '::1',
+};
+
+// Nested $refs deeper than this are cut off, so recursive schemas terminate
+const MAX_DEPTH = 16;
+
+// Lower bounds are capped at these, so a schema can't make the server build a
+// huge value; a larger minItems or minLength goes unsatisfied
+export const MAX_SYNTHESIZED_ITEMS = 1000;
+export const MAX_SYNTHESIZED_LENGTH = 10_000;
+
+export function synthesize(schema: unknown, root: unknown = schema, depth: number = 0): unknown {
+  if (!isSchema(schema) || depth > MAX_DEPTH) {
+    return null;
+  }
+
+  if (typeof schema['$ref'] === 'string') {
+    return synthesize(resolveRef(root, schema['$ref']), root, depth + 1);
+  }
+  if ('const' in schema) {
+    return schema['const'];
+  }
+  if (Array.isArray(schema['enum']) && schema['enum'].length > 0) {
+    return schema['enum'][0];
+  }
+  for (const keyword of ['anyOf', 'oneOf'] as const) {
+    const options = schema[keyword];
+    if (Array.isArray(options) && options.length > 0) {
+      return synthesize(options[0], root, depth + 1);
+    }
+  }
+  if (Array.isArray(schema['allOf'])) {
+    return Object.assign({}, ...schema['allOf'].map(part => synthesize(part, root, depth + 1)));
+  }
+
+  const type = Array.isArray(schema['type'])
+    ? schema['type'].find(t => t !== 'null') ?? 'null'
+    : schema['type'] ?? (isSchema(schema['properties']) ? 'object' : undefined);
+
+  switch (type) {
+    case 'object':
+      return synthesizeObject(schema, root, depth);
+    case 'array':
+      return synthesizeArray(schema, root, depth);
+    case 'string':
+      return synthesizeString(schema);
+    case 'integer':
+      return synthesizeNumber(schema, true);
+    case 'number':
+      return synthesizeNumber(schema, false);
+    case 'boolean':
+      return true;
+    default:
+      return null;
+  }
+}
+
+function isSchema(value: unknown): value is JsonSchema {
+  return typeof value === 'object' && value !== null && !Array.isArray(value);
+}
+
+// Resolves "#/$defs/name" style pointers within the root schema
+function resolveRef(root: unknown, ref: string): unknown {
+  if (!ref.startsWith('#')) {
+    return undefined;
+  }
+  return ref
+    .slice(1)
+    .split('/')
+    .filter(part => part.length > 0)
+    .map(part => part.replace(/~1/g, '/').replace(/~0/g, '~'))
+    .reduce<unknown>((node, part) => (isSchema(node) ? node[part] : undefined), root);
+}
+
+function synthesizeObject(schema: JsonSchema, root: unknown, depth: number): Record<string, unknown> {
+  const properties = isSchema(schema['properties']) ? schema['properties'] : {};
+  const required = Array.isArray(schema['required']) ? schema['required'] : [];
+
+  const value: Record<string, unknown> = {};
+  for (const name of required) {
+    if (typeof name === 'string') {
+      value[name] = synthesize(properties[name] ?? {}, root, depth + 1);
+    }
+  }
+  return value;
+}
+
+function synthesizeArray(schema: JsonSchema, root: unknown, depth: number): unknown[] {
+  const minItems = typeof schema['minItems'] === 'number' ? Math.min(schema['minItems'], MAX_SYNTHESIZED_ITEMS) : 0;
+  const prefixItems = Array.isArray(schema['prefixItems']) ? schema['prefixItems'] : [];
+
+  const items: unknown[] = prefixItems.map(item => synthesize(item, root, depth + 1));
+  while (items.length < minItems) {
+    items.push(synthesize(schema['items'] ?? {}, root, depth + 1));
+  }
+  return items;
+}
+
+function synthesizeString(schema: JsonSchema): string {
+  const format = typeof schema['format'] === 'string' ? STRING_FORMATS[schema['format']] : undefined;
+  let value = format ?? 'string';
+
+  const minLength = typeof schema['minLength'] === 'number' ? Math.min(schema['minLength'], MAX_SYNTHESIZED_LENGTH) : 0;
+  const maxLength = typeof schema['maxLength'] === 'number' ? schema['maxLength'] : Infinity;
+  if (value.length < minLength) {
+    value = value.padEnd(minLength, 'x');
+  }
+  if (value.length > maxLength) {
+    value = value.slice(0, maxLength);
+  }
+  return value;
+}
+
+function synthesizeNumber(schema: JsonSchema, integer: boolean): number {
+  const num = (key: string) => (typeof schema[key] === 'number' ? (schema[key] as number) : undefined);
+  const step = num('multipleOf') ?? (integer ? 1 : undefined);
+
+  let lower = num('minimum') ?? -Infinity;
+  const exclusiveMinimum = num('exclusiveMinimum');
+  if (exclusiveMinimum !== undefined) {
+    lower = Math.max(lower, exclusiveMinimum + (step ?? 0.5));
+  }
+  let upper = num('maximum') ?? Infinity;
+  const exclusiveMaximum = num('exclusiveMaximum');
+  if (exclusiveMaximum !== undefined) {
+    upper = Math.min(upper, exclusiveMaximum - (step ?? 0.5));
+  }
+
+  // Prefer zero, otherwise the bound nearest to it
+  let value = Math.min(Math.max(0, lower), upper);
+  if (step !== undefined) {
+    value = Math.ceil(value / step) * step;
+    if (value > upper) {
+      value -= step;
+    }
+  }
+  return value;
+}
@@ -0,0 +1,39 @@
+import { describe, it, expect } from "vitest";
+import { Logger, isLogLevel } from "./logger.js";
+
+describe("Logger", () => {
+  it("should write one JSON object per line", () => {
+    const lines: string[] = [];
+    const logger = new Logger("info", (line) => lines.push(line), () => 0);
+
+    logger.info("Request completed", { status: 200, model: null });
+
+    expect(lines).toHaveLength(1);
+    expect(lines[0]).not.toContain("\n");
+    expect(JSON.parse(lines[0]!)).toEqual({
+      timestamp: "1970-01-01T00:00:00.000Z",
+      level: "info",
+      message: "Request completed",
+      status: 200,
+      model: null,
+    });
+  });
+
+  it("should only write lines at or above its level", () => {
+    const lines: string[] = [];
+    const logger = new Logger("warn", (line) => lines.push(line));
+
+    logger.debug("debug");
+    logger.info("info");
+    logger.warn("warn");
+    logger.error("error");
+
+    expect(lines.map((line) => JSON.parse(line).message)).toEqual(["warn", "error"]);
+    expect(logger.enabled("info")).toBe(false);
+  });
+
+  it("should recognise the level names", () => {
+    expect(["debug", "info", "warn", "error"].every(isLogLevel)).toBe(true);
+    expect(isLogLevel("verbose")).toBe(false);
+  });
+});
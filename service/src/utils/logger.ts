@@ -0,0 +1,84 @@
+// JSON lines logging, one object per line with its time, level and message
+
+export const LOG_LEVELS = ['debug', 'info', 'warn', 'error'] as const;
+export type LogLevel = (typeof LOG_LEVELS)[number];
+
+// Where finished lines go, e.g. stdout or a file; lines have no newline
+export type LogSink = (line: string) => void;
+
+export function isLogLevel(value: string): value is LogLevel {
+  return (LOG_LEVELS as readonly string[]).includes(value);
+}
+
+/**
+ * Logger - writes lines at or above its level to its sink
+ */
+export class Logger {
+  constructor(
+    private level: LogLevel = 'info',
+    private sink: LogSink = line => console.log(line),
+    private now: () => number = Date.now
+  ) {}
+
+  enabled(level: LogLevel): boolean {
+    return LOG_LEVELS.indexOf(level) >= LOG_LEVELS.indexOf(this.level);
+  }
+
+  log(level: LogLevel, message: string, fields: Record<string, unknown> = {}): void {
+    if (this.enabled(level)) {
+      this.sink(JSON.stringify({ timestamp: new Date(this.now()).toISOString(), level, message, ...fields }));
+    }
+  }
+
+  debug(message: string, fields?: Record<string, unknown>): void {
+    this.log('debug', message, fields);
+  }
+
+  info(message: string, fields?: Record<string, unknown>): void {
+    this.log('info', message, fields);
+  }
+
+  warn(message: string, fields?: Record<string, unknown>): void {
+    this.log('warn', message, fields);
+  }
+
+  error(message: string, fields?: Record<string, unknown>): void {
+    this.log('error', message, fields);
+  }
+}
+
+// Passes a response body through, calling done once it has been read to the
+// end (false) or cancelled by the client disconnecting (true)
+export function loggedWhenDone(
+  body: ReadableStream<Uint8Array>,
+  done: (cancelled: boolean) => void
+): ReadableStream<Uint8Array> {
+  const reader = body.getReader();
+  let finished = false;
+  const finish = (cancelled: boolean) => {
+    if (!finished) {
+      finished = true;
+      done(cancelled);
+    }
+  };
+  return new ReadableStream<Uint8Array>({
+    async pull(controller) {
+      try {
+        const { done: end, value } = await reader.read();
+        if (end) {
+          finish(false);
+          controller.close();
+        } else {
+          controller.enqueue(value);
+        }
+      } catch (error) {
+        finish(true);
+        controller.error(error);
+      }
+    },
+    async cancel(reason) {
+      finish(true);
+      await reader.cancel(reason);
+    },
+  });
+}
@@ -0,0 +1,27 @@
+import { describe, it, expect } from "vitest";
+import { Metrics } from "./metrics.js";
+
+describe("Metrics", () => {
+  it("should track active streams by request id", () => {
+    const metrics = new Metrics();
+    metrics.streamStarted("a");
+    metrics.streamStarted("b");
+    metrics.streamEnded("a");
+    metrics.cancelledGenerations++;
+
+    expect(metrics.snapshot()).toEqual({
+      active_streams: 1,
+      active_stream_ids: ["b"],
+      cancelled_generations: 1,
+    });
+
+    metrics.reset();
+    expect(metrics.snapshot()).toMatchObject({ active_streams: 1, cancelled_generations: 0 });
+  });
+
+  it("should include process stats when the runtime reports them", () => {
+    const metrics = new Metrics(() => ({ rss_bytes: 64_000_000, open_connections: 3 }));
+
+    expect(metrics.snapshot()).toMatchObject({ rss_bytes: 64_000_000, open_connections: 3 });
+  });
+});
@@ -0,0 +1,48 @@
+// Resource usage of the server process, where the runtime can report it
+export interface ProcessStats {
+  rss_bytes: number;
+  open_connections: number;
+}
+
+/**
+ * In-process counters exposed on GET /metrics
+ *
+ * Counters live as long as the app instance, so on Node.js they cover the
+ * server's lifetime while on Cloudflare Workers they only reflect one isolate.
+ * Process stats are only reported on Node.js, where soak tests watch them for
+ * leaks.
+ */
+export class Metrics {
+  // Request ids of streams still generating, so a client can check that the
+  // server stopped work on a stream it abandoned
+  private streams = new Set<string>();
+  cancelledGenerations = 0;
+
+  constructor(private processStats?: () => ProcessStats) {}
+
+  get activeStreams(): number {
+    return this.streams.size;
+  }
+
+  streamStarted(requestId: string): void {
+    this.streams.add(requestId);
+  }
+
+  streamEnded(requestId: string): void {
+    this.streams.delete(requestId);
+  }
+
+  // Active streams are still running, so only the cumulative counters reset
+  reset(): void {
+    this.cancelledGenerations = 0;
+  }
+
+  snapshot() {
+    return {
+      active_streams: this.activeStreams,
+      active_stream_ids: [...this.streams],
+      cancelled_generations: this.cancelledGenerations,
+      ...this.processStats?.(),
+    };
+  }
+}
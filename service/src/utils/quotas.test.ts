@@ -0,0 +1,54 @@
+import { describe, it, expect } from "vitest";
+import { Quotas } from "./quotas.js";
+
+describe("Quotas", () => {
+  it("should refuse a key once it has used its budget", () => {
+    const quotas = new Quotas({ a: 100 });
+
+    quotas.check("a");
+    quotas.spend("a", 60);
+    quotas.check("a");
+    // The request that crosses the budget was allowed; the next one isn't
+    quotas.spend("a", 60);
+    expect(() => quotas.check("a")).toThrow(
+      expect.objectContaining({ statusCode: 429, type: "insufficient_quota", code: "insufficient_quota" }),
+    );
+    expect(quotas.get("a")).toEqual({ key: "a", token_budget: 100, tokens_used: 120 });
+  });
+
+  it("should never refuse keys without a budget", () => {
+    const quotas = new Quotas();
+    quotas.spend("a", 1_000_000);
+
+    expect(() => quotas.check("a")).not.toThrow();
+    expect(quotas.list()).toEqual([]);
+  });
+
+  it("should allow a key again once reset or given more budget", () => {
+    const quotas = new Quotas();
+    quotas.set("a", 10);
+    quotas.spend("a", 10);
+    expect(() => quotas.check("a")).toThrow();
+
+    quotas.set("a", 20);
+    expect(() => quotas.check("a")).not.toThrow();
+    quotas.set("a", 5);
+    quotas.reset("a");
+    expect(() => quotas.check("a")).not.toThrow();
+    quotas.set("a", null);
+    expect(quotas.get("a")).toBeUndefined();
+  });
+
+  it("should pick up a snapshot's budgets and spending, keeping budgets it already has", () => {
+    const before = new Quotas({ a: 100 });
+    before.spend("a", 40);
+    const restored = new Quotas({ b: 50 });
+
+    restored.restore(JSON.parse(JSON.stringify(before.snapshot())));
+
+    expect(restored.list()).toEqual([
+      { key: "b", token_budget: 50, tokens_used: 0 },
+      { key: "a", token_budget: 100, tokens_used: 40 },
+    ]);
+  });
+});
@@ -0,0 +1,102 @@
+// Token budgets per API key, for testing how clients handle running out of
+// credit
+
+import { InsufficientQuotaError } from '../openai-protocol/errors.js';
+
+export interface Quota {
+  key: string;
+  token_budget: number;
+  tokens_used: number;
+}
+
+// Budgets and tokens spent by key, as saved between restarts
+export interface QuotaSnapshot {
+  budgets: Record<string, number>;
+  used: Record<string, number>;
+}
+
+/**
+ * Quotas - Tokens each budgeted key may spend
+ *
+ * A request is refused once its key has used its whole budget, so the request
+ * that crosses the budget still succeeds, as OpenAI only cuts an account off
+ * after the fact. Keys without a budget are never refused.
+ */
+export class Quotas {
+  private budgets = new Map<string, number>();
+  private used = new Map<string, number>();
+  private changes = 0;
+
+  constructor(budgets: Record<string, number> = {}) {
+    for (const [key, tokens] of Object.entries(budgets)) {
+      this.budgets.set(key, tokens);
+    }
+  }
+
+  // Sets a key's budget, or removes it when null. Tokens spent under an
+  // earlier budget still count until the key is reset.
+  set(apiKey: string, tokens: number | null): void {
+    if (tokens === null) {
+      this.budgets.delete(apiKey);
+    } else {
+      this.budgets.set(apiKey, tokens);
+    }
+    this.changes++;
+  }
+
+  get(apiKey: string): Quota | undefined {
+    const budget = this.budgets.get(apiKey);
+    return budget === undefined
+      ? undefined
+      : { key: apiKey, token_budget: budget, tokens_used: this.used.get(apiKey) ?? 0 };
+  }
+
+  list(): Quota[] {
+    return [...this.budgets.keys()].map(key => this.get(key)!);
+  }
+
+  /**
+   * Throws InsufficientQuotaError when the key has used up its budget
+   */
+  check(apiKey: string): void {
+    const quota = this.get(apiKey);
+    if (quota && quota.tokens_used >= quota.token_budget) {
+      throw new InsufficientQuotaError();
+    }
+  }
+
+  spend(apiKey: string, tokens: number): void {
+    if (this.budgets.has(apiKey)) {
+      this.used.set(apiKey, (this.used.get(apiKey) ?? 0) + tokens);
+      this.changes++;
+    }
+  }
+
+  // Tops one key's budget back up, or every key's when no key is given
+  reset(apiKey?: string): void {
+    if (apiKey === undefined) {
+      this.used.clear();
+    } else {
+      this.used.delete(apiKey);
+    }
+    this.changes++;
+  }
+
+  // Every budget and what each key has spent, to be restored after a restart
+  snapshot(): QuotaSnapshot {
+    return { budgets: Object.fromEntries(this.budgets), used: Object.fromEntries(this.used) };
+  }
+
+  // Adds the saved budgets to those already set, and takes the spent tokens as saved
+  restore({ budgets, used }: QuotaSnapshot): void {
+    for (const [key, tokens] of Object.entries(budgets)) {
+      this.budgets.set(key, tokens);
+    }
+    this.used = new Map(Object.entries(used));
+  }
+
+  // Goes up whenever a budget is set, spent from or reset
+  get version(): number {
+    return this.changes;
+  }
+}
@@ -0,0 +1,31 @@
+import { describe, it, expect } from "vitest";
+import { StreamLimiter } from "./stream-limiter.js";
+
+describe("StreamLimiter", () => {
+  it("should turn streams away at the limit until one ends", () => {
+    const limiter = new StreamLimiter(2);
+    const first = limiter.acquire("key")!;
+    expect(limiter.acquire("key")).toBeDefined();
+    expect(limiter.acquire("other")).toBeUndefined();
+
+    first();
+    first();
+    expect(limiter.acquire("other")).toBeDefined();
+    expect(limiter.acquire("key")).toBeUndefined();
+    expect(limiter.snapshot()).toEqual({ max_streams: 2, rejected_streams: 2 });
+
+    limiter.reset();
+    expect(limiter.snapshot().rejected_streams).toBe(0);
+  });
+
+  it("should count a request's own limit among its key's streams", () => {
+    const limiter = new StreamLimiter();
+    expect(limiter.acquire("a", 1)).toBeDefined();
+    expect(limiter.acquire("a", 1)).toBeUndefined();
+    // Other keys, other limits and requests without one aren't held back
+    expect(limiter.acquire("b", 1)).toBeDefined();
+    expect(limiter.acquire("a", 2)).toBeDefined();
+    expect(limiter.acquire("a")).toBeDefined();
+    expect(limiter.snapshot()).toEqual({ max_streams: null, rejected_streams: 1 });
+  });
+});
@@ -0,0 +1,69 @@
+// Lets a client opt into a lower limit, counted among the streams its key opens
+// with the same header, e.g. to test 503 handling without using up the
+// streams of every other client
+export const MAX_STREAMS_HEADER = 'x-teenytiny-max-streams';
+
+// How long a client turned away at the limit is told to wait
+export const STREAM_RETRY_AFTER_SECONDS = 1;
+
+/**
+ * StreamLimiter - Caps how many streamed responses are open at once
+ *
+ * A stream asked for at the limit is turned away at once, for a 503 with
+ * Retry-After as a provider at capacity answers, rather than left waiting for
+ * a place until the client times out.
+ */
+export class StreamLimiter {
+  // Open streams per bucket: '*' for all of them, and key:limit for those
+  // that asked for a lower limit
+  private open = new Map<string, number>();
+  private rejected = 0;
+
+  constructor(private max?: number) {}
+
+  /**
+   * Takes a place for a stream, returning the function that gives it back,
+   * or undefined when the server's limit or the request's own is reached
+   */
+  acquire(apiKey: string, override?: number): (() => void) | undefined {
+    const buckets: [string, number | undefined][] = [['*', this.max]];
+    if (override !== undefined) {
+      buckets.push([`${apiKey}:${override}`, override]);
+    }
+    if (buckets.some(([bucket, limit]) => limit !== undefined && (this.open.get(bucket) ?? 0) >= limit)) {
+      this.rejected++;
+      return undefined;
+    }
+
+    for (const [bucket] of buckets) {
+      this.open.set(bucket, (this.open.get(bucket) ?? 0) + 1);
+    }
+    let released = false;
+    return () => {
+      if (released) {
+        return;
+      }
+      released = true;
+      for (const [bucket] of buckets) {
+        const count = (this.open.get(bucket) ?? 1) - 1;
+        if (count > 0) {
+          this.open.set(bucket, count);
+        } else {
+          this.open.delete(bucket);
+        }
+      }
+    };
+  }
+
+  // Open streams still hold their places, so only the count turned away resets
+  reset(): void {
+    this.rejected = 0;
+  }
+
+  snapshot() {
+    return {
+      max_streams: this.max ?? null,
+      rejected_streams: this.rejected,
+    };
+  }
+}
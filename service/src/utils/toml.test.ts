@@ -0,0 +1,66 @@
+import { describe, it, expect } from "vitest";
+import { parseToml, TomlError } from "./toml.js";
+
+describe("parseToml", () => {
+  it("should parse keys, values and tables", () => {
+    const text = `
+# A comment
+port = 8080
+name = "teeny \\"tiny\\"" # trailing comment
+path = 'C:\\no\\escapes'
+rate = 0.25
+big = 1_000_000
+on = true
+models = ["echo", "eliza",
+  "slow", # one per line
+]
+
+[rate_limit]
+requests_per_minute = 60
+
+[latency."/v1/*".ttfb]
+type = "fixed"
+ms = 200
+
+[model_defaults]
+echo = { max_tokens = 10, system_prompt = "Be brief" }
+"*".temperature = 0
+`;
+
+    expect(parseToml(text)).toEqual({
+      port: 8080,
+      name: 'teeny "tiny"',
+      path: "C:\\no\\escapes",
+      rate: 0.25,
+      big: 1000000,
+      on: true,
+      models: ["echo", "eliza", "slow"],
+      rate_limit: { requests_per_minute: 60 },
+      latency: { "/v1/*": { ttfb: { type: "fixed", ms: 200 } } },
+      model_defaults: { echo: { max_tokens: 10, system_prompt: "Be brief" }, "*": { temperature: 0 } },
+    });
+  });
+
+  it("should collect arrays of tables", () => {
+    const text = "[[keys]]\nkey = \"a\"\n\n[[keys]]\nkey = \"b\"\nmodels = [\"echo\"]\n";
+
+    expect(parseToml(text)).toEqual({ keys: [{ key: "a" }, { key: "b", models: ["echo"] }] });
+  });
+
+  it("should report the line of the first problem", () => {
+    for (const [text, line] of [
+      ["a = 1\nb = \n", 2],
+      ["a = 1\na = 2", 2],
+      ["[t]\n[t]", 2],
+      ["a = \"unterminated", 1],
+      ["a = \"\"\"multi\nline\"\"\"", 1],
+      ["a = 1979-05-27", 1],
+      ["a = 1 b = 2", 1],
+      ["a = [1 2]", 1],
+      ["__proto__ = 1", 1],
+    ] as const) {
+      expect(() => parseToml(text)).toThrow(TomlError);
+      expect(() => parseToml(text)).toThrow(expect.objectContaining({ line }));
+    }
+  });
+});
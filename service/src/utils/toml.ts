@@ -0,0 +1,317 @@
+// Parses the subset of TOML that server config files need
+//
+// Covers tables and arrays of tables, bare, quoted and dotted keys, basic and
+// literal strings, integers, floats, booleans, arrays and inline tables.
+// Multi-line strings and dates are rejected rather than misread.
+
+type Table = Record<string, unknown>;
+
+export class TomlError extends Error {
+  constructor(message: string, public readonly line: number) {
+    super(`${message} on line ${line}`);
+    this.name = 'TomlError';
+  }
+}
+
+export function parseToml(text: string): Table {
+  return new Parser(text).parse();
+}
+
+const BARE_KEY = /^[A-Za-z0-9_-]+/;
+const VALUE_TOKEN = /^[^\s,\]}#]+/;
+const NUMBER = /^[+-]?(0|[1-9](_?\d)*)(\.\d(_?\d)*)?([eE][+-]?\d(_?\d)*)?$/;
+const ESCAPES: Record<string, string> = { b: '\b', t: '\t', n: '\n', f: '\f', r: '\r', '"': '"', '\\': '\\' };
+
+class Parser {
+  private pos = 0;
+  private root: Table = {};
+  // Tables given a [header], which can't be given another
+  private headed = new Set<Table>();
+
+  constructor(private text: string) {}
+
+  parse(): Table {
+    let current = this.root;
+    for (;;) {
+      this.skipBlankLines();
+      if (this.pos >= this.text.length) {
+        return this.root;
+      }
+      if (this.peek() === '[') {
+        current = this.header();
+      } else {
+        this.keyValue(current);
+      }
+      this.endOfLine();
+    }
+  }
+
+  // [table] or [[array.of.tables]], returning the table that follows it
+  private header(): Table {
+    const array = this.text.startsWith('[[', this.pos);
+    this.pos += array ? 2 : 1;
+    const path = this.key();
+    this.expect(array ? ']]' : ']');
+    const parent = this.descend(this.root, path.slice(0, -1));
+    const last = path[path.length - 1]!;
+    const existing = parent[last];
+
+    if (array) {
+      if (existing !== undefined && !Array.isArray(existing)) {
+        throw this.error(`'${path.join('.')}' is not an array of tables`);
+      }
+      const table: Table = {};
+      parent[last] = [...((existing as Table[] | undefined) ?? []), table];
+      return table;
+    }
+    if (existing === undefined) {
+      const table: Table = {};
+      parent[last] = table;
+      this.headed.add(table);
+      return table;
+    }
+    if (isTable(existing) && !this.headed.has(existing)) {
+      this.headed.add(existing);
+      return existing;
+    }
+    throw this.error(`'${path.join('.')}' is defined twice`);
+  }
+
+  // The table at a path, created as needed; an array of tables stands for
+  // its last table
+  private descend(table: Table, path: string[]): Table {
+    for (const key of path) {
+      let next = table[key];
+      if (next === undefined) {
+        next = {};
+        table[key] = next;
+      }
+      if (Array.isArray(next)) {
+        next = next[next.length - 1];
+      }
+      if (!isTable(next)) {
+        throw this.error(`'${key}' is not a table`);
+      }
+      table = next;
+    }
+    return table;
+  }
+
+  private keyValue(table: Table): void {
+    const path = this.key();
+    this.expect('=');
+    this.skipSpaces();
+    const value = this.value();
+    const parent = this.descend(table, path.slice(0, -1));
+    const last = path[path.length - 1]!;
+    if (Object.hasOwn(parent, last)) {
+      throw this.error(`'${path.join('.')}' is defined twice`);
+    }
+    parent[last] = value;
+  }
+
+  // A dotted key such as a."b.c".d, as its parts
+  private key(): string[] {
+    const path: string[] = [];
+    for (;;) {
+      this.skipSpaces();
+      const c = this.peek();
+      if (c === '"') {
+        path.push(this.basicString());
+      } else if (c === "'") {
+        path.push(this.literalString());
+      } else {
+        const match = BARE_KEY.exec(this.text.slice(this.pos));
+        if (!match) {
+          throw this.error('Expected a key');
+        }
+        path.push(match[0]);
+        this.pos += match[0].length;
+      }
+      if (path[path.length - 1] === '__proto__') {
+        throw this.error("'__proto__' can't be used as a key");
+      }
+      this.skipSpaces();
+      if (this.peek() !== '.') {
+        return path;
+      }
+      this.pos++;
+    }
+  }
+
+  private value(): unknown {
+    switch (this.peek()) {
+      case '"':
+        if (this.text.startsWith('"""', this.pos)) {
+          throw this.error('Multi-line strings are not supported');
+        }
+        return this.basicString();
+      case "'":
+        if (this.text.startsWith("'''", this.pos)) {
+          throw this.error('Multi-line strings are not supported');
+        }
+        return this.literalString();
+      case '[':
+        return this.array();
+      case '{':
+        return this.inlineTable();
+    }
+
+    const match = VALUE_TOKEN.exec(this.text.slice(this.pos));
+    if (!match) {
+      throw this.error('Expected a value');
+    }
+    const token = match[0];
+    this.pos += token.length;
+    if (token === 'true' || token === 'false') {
+      return token === 'true';
+    }
+    if (NUMBER.test(token)) {
+      return Number(token.replace(/_/g, ''));
+    }
+    throw this.error(`Unsupported value '${token}'`);
+  }
+
+  private array(): unknown[] {
+    this.pos++;
+    const items: unknown[] = [];
+    for (;;) {
+      this.skipBlankLines();
+      if (this.peek() === ']') {
+        this.pos++;
+        return items;
+      }
+      items.push(this.value());
+      this.skipBlankLines();
+      const c = this.text[this.pos++];
+      if (c === ']') {
+        return items;
+      }
+      if (c !== ',') {
+        throw this.error("Expected ',' or ']' in an array");
+      }
+    }
+  }
+
+  private inlineTable(): Table {
+    this.pos++;
+    const table: Table = {};
+    this.skipSpaces();
+    if (this.peek() === '}') {
+      this.pos++;
+      return table;
+    }
+    for (;;) {
+      this.keyValue(table);
+      this.skipSpaces();
+      const c = this.text[this.pos++];
+      if (c === '}') {
+        return table;
+      }
+      if (c !== ',') {
+        throw this.error("Expected ',' or '}' in an inline table");
+      }
+    }
+  }
+
+  private basicString(): string {
+    this.pos++;
+    let value = '';
+    for (;;) {
+      const c = this.text[this.pos++];
+      if (c === undefined || c === '\n') {
+        throw this.error('Unterminated string');
+      }
+      if (c === '"') {
+        return value;
+      }
+      if (c !== '\\') {
+        value += c;
+        continue;
+      }
+      const escape = this.text[this.pos++] ?? '';
+      if (escape === 'u' || escape === 'U') {
+        const hex = this.text.slice(this.pos, this.pos + (escape === 'u' ? 4 : 8));
+        const code = /^[0-9A-Fa-f]+$/.test(hex) ? parseInt(hex, 16) : NaN;
+        if (hex.length !== (escape === 'u' ? 4 : 8) || Number.isNaN(code) || code > 0x10ffff) {
+          throw this.error(`Invalid escape '\\${escape}${hex}'`);
+        }
+        value += String.fromCodePoint(code);
+        this.pos += hex.length;
+      } else if (Object.hasOwn(ESCAPES, escape)) {
+        value += ESCAPES[escape]!;
+      } else {
+        throw this.error(`Invalid escape '\\${escape}'`);
+      }
+    }
+  }
+
+  private literalString(): string {
+    const end = this.text.indexOf("'", this.pos + 1);
+    const newline = this.text.indexOf('\n', this.pos + 1);
+    if (end < 0 || (newline >= 0 && newline < end)) {
+      throw this.error('Unterminated string');
+    }
+    const value = this.text.slice(this.pos + 1, end);
+    this.pos = end + 1;
+    return value;
+  }
+
+  private expect(token: string): void {
+    this.skipSpaces();
+    if (!this.text.startsWith(token, this.pos)) {
+      throw this.error(`Expected '${token}'`);
+    }
+    this.pos += token.length;
+  }
+
+  private skipSpaces(): void {
+    while (this.peek() === ' ' || this.peek() === '\t') {
+      this.pos++;
+    }
+  }
+
+  private skipComment(): void {
+    if (this.peek() === '#') {
+      while (this.pos < this.text.length && this.peek() !== '\n') {
+        this.pos++;
+      }
+    }
+  }
+
+  // Whitespace, comments and newlines, as allowed between lines and in arrays
+  private skipBlankLines(): void {
+    for (;;) {
+      this.skipSpaces();
+      this.skipComment();
+      if (this.peek() !== '\n' && this.peek() !== '\r') {
+        return;
+      }
+      this.pos++;
+    }
+  }
+
+  private endOfLine(): void {
+    this.skipSpaces();
+    this.skipComment();
+    if (this.peek() === '\r') {
+      this.pos++;
+    }
+    if (this.pos < this.text.length && this.peek() !== '\n') {
+      throw this.error('Expected the end of the line');
+    }
+    this.pos++;
+  }
+
+  private peek(): string | undefined {
+    return this.text[this.pos];
+  }
+
+  private error(message: string): TomlError {
+    return new TomlError(message, this.text.slice(0, this.pos).split('\n').length);
+  }
+}
+
+function isTable(value: unknown): value is Table {
+  return typeof value === 'object' && value !== null && !Array.isArray(value);
+}
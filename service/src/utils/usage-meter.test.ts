@@ -0,0 +1,99 @@
+import { describe, it, expect } from "vitest";
+import { UsageMeter, parseUsageFilter } from "./usage-meter.js";
+
+const HOUR = 3_600_000;
+
+function usage(prompt: number, completion: number, cached?: number) {
+  return {
+    prompt_tokens: prompt,
+    completion_tokens: completion,
+    total_tokens: prompt + completion,
+    ...(cached === undefined ? {} : { prompt_tokens_details: { cached_tokens: cached } }),
+  };
+}
+
+describe("UsageMeter", () => {
+  it("should count requests and tokens per key and model", () => {
+    const meter = new UsageMeter(undefined, () => 0);
+    meter.record("a", "echo", usage(10, 5));
+    meter.record("a", "echo", usage(20, 5, 8));
+    meter.record("a", "eliza", usage(1, 1));
+    meter.record("b", "echo", usage(3, 3));
+
+    const { data, totals } = meter.query({ bucket: "day", apiKey: "a" });
+    expect(meter.query({ bucket: "day", apiKeys: ["b", "c"] }).totals.requests).toBe(1);
+
+    expect(data).toEqual([
+      {
+        start_time: "1970-01-01T00:00:00.000Z",
+        end_time: "1970-01-02T00:00:00.000Z",
+        api_key: "a",
+        model: "echo",
+        requests: 2,
+        prompt_tokens: 30,
+        cached_tokens: 8,
+        completion_tokens: 10,
+        total_tokens: 40,
+      },
+      expect.objectContaining({ api_key: "a", model: "eliza", requests: 1, total_tokens: 2 }),
+    ]);
+    expect(totals).toEqual({ requests: 3, prompt_tokens: 31, cached_tokens: 8, completion_tokens: 11, total_tokens: 42 });
+  });
+
+  it("should add minutes up into wider buckets", () => {
+    let now = 0;
+    const meter = new UsageMeter(undefined, () => now);
+    for (const time of [0, 30 * 60_000, HOUR + 60_000]) {
+      now = time;
+      meter.record("a", "echo", usage(1, 1));
+    }
+
+    expect(meter.query({ bucket: "minute" }).data).toHaveLength(3);
+    expect(meter.query({ bucket: "hour" }).data.map(bucket => bucket.requests)).toEqual([2, 1]);
+    expect(meter.query({ bucket: "day" }).data.map(bucket => bucket.requests)).toEqual([3]);
+    expect(meter.query({ bucket: "hour", since: HOUR }).totals.requests).toBe(1);
+    expect(meter.query({ bucket: "hour", until: HOUR - 1 }).totals.requests).toBe(2);
+  });
+
+  it("should forget usage past its retention and on reset", () => {
+    let now = 0;
+    const meter = new UsageMeter(HOUR, () => now);
+    meter.record("a", "echo", usage(1, 1));
+    now = 2 * HOUR;
+    meter.record("b", "echo", usage(1, 1));
+
+    expect(meter.query({ bucket: "day" }).data.map(bucket => bucket.api_key)).toEqual(["b"]);
+    meter.reset("b");
+    expect(meter.query({ bucket: "day" }).totals.requests).toBe(0);
+  });
+
+  it("should go on counting from a snapshot", () => {
+    const meter = new UsageMeter(undefined, () => 0);
+    meter.record("a", "echo", usage(10, 5));
+
+    const restored = new UsageMeter(undefined, () => 0);
+    restored.restore(JSON.parse(JSON.stringify(meter.snapshot())));
+    restored.record("a", "echo", usage(1, 1));
+
+    expect(restored.query({ bucket: "minute" }).data).toEqual([
+      expect.objectContaining({ api_key: "a", model: "echo", requests: 2, total_tokens: 17 }),
+    ]);
+  });
+});
+
+describe("parseUsageFilter", () => {
+  it("should default to daily buckets", () => {
+    expect(parseUsageFilter({})).toEqual({ bucket: "day" });
+    expect(parseUsageFilter({ bucket: "hour", since: "1000", until: "1970-01-01T00:00:02Z", model: "echo" })).toEqual({
+      bucket: "hour",
+      since: 1000,
+      until: 2000,
+      model: "echo",
+    });
+  });
+
+  it("should reject unknown buckets and times", () => {
+    expect(() => parseUsageFilter({ bucket: "week" })).toThrow(expect.objectContaining({ param: "bucket" }));
+    expect(() => parseUsageFilter({ since: "yesterday" })).toThrow(expect.objectContaining({ param: "since" }));
+  });
+});
@@ -0,0 +1,196 @@
+// Usage metering: tokens and requests per API key and model, for testing
+// clients that track spend or enforce quotas
+
+import { InvalidRequestError } from '../openai-protocol/errors.js';
+import type { ChatCompletionUsage } from '../openai-protocol/types.js';
+
+export const BUCKET_WIDTHS = { minute: 60_000, hour: 3_600_000, day: 86_400_000 } as const;
+export type BucketWidth = keyof typeof BUCKET_WIDTHS;
+
+// Usage older than this is forgotten
+export const DEFAULT_USAGE_RETENTION_MS = 7 * BUCKET_WIDTHS.day;
+
+export interface UsageCounts {
+  requests: number;
+  prompt_tokens: number;
+  // The share of prompt_tokens served from the prompt cache
+  cached_tokens: number;
+  completion_tokens: number;
+  total_tokens: number;
+}
+
+export interface UsageBucket extends UsageCounts {
+  // ISO 8601 times, start inclusive and end exclusive
+  start_time: string;
+  end_time: string;
+  api_key: string;
+  model: string;
+}
+
+export interface UsageFilter {
+  bucket: BucketWidth;
+  apiKey?: string;
+  // Any of these keys, as for a tenant
+  apiKeys?: string[];
+  model?: string;
+  // Epoch milliseconds, inclusive
+  since?: number;
+  until?: number;
+}
+
+export interface MinuteUsage extends UsageCounts {
+  minute: number;
+  apiKey: string;
+  model: string;
+}
+
+/**
+ * UsageMeter - Counts usage per API key and model a minute at a time
+ *
+ * Queries add the minutes up into buckets of a minute, an hour or a day (UTC),
+ * so totals are exact whatever the bucket width. Like the other in-memory
+ * stores, on Cloudflare Workers it only covers requests one isolate handled.
+ */
+export class UsageMeter {
+  private minutes = new Map<string, MinuteUsage>();
+  private changes = 0;
+
+  constructor(
+    private retentionMs: number = DEFAULT_USAGE_RETENTION_MS,
+    private now: () => number = Date.now
+  ) {}
+
+  record(apiKey: string, model: string, usage: ChatCompletionUsage): void {
+    const now = this.now();
+    this.prune(now);
+
+    const minute = Math.floor(now / BUCKET_WIDTHS.minute) * BUCKET_WIDTHS.minute;
+    const key = `${minute}:${apiKey}:${model}`;
+    let counts = this.minutes.get(key);
+    if (!counts) {
+      counts = { minute, apiKey, model, ...emptyCounts() };
+      this.minutes.set(key, counts);
+    }
+    counts.requests++;
+    counts.prompt_tokens += usage.prompt_tokens;
+    counts.cached_tokens += usage.prompt_tokens_details?.cached_tokens ?? 0;
+    counts.completion_tokens += usage.completion_tokens;
+    counts.total_tokens += usage.total_tokens;
+    this.changes++;
+  }
+
+  /**
+   * Usage per bucket, API key and model, oldest bucket first, with the totals
+   * of everything matched. Buckets without usage are left out.
+   */
+  query(filter: UsageFilter): { data: UsageBucket[]; totals: UsageCounts } {
+    const width = BUCKET_WIDTHS[filter.bucket];
+    const buckets = new Map<string, UsageBucket>();
+    const totals = emptyCounts();
+    for (const usage of this.minutes.values()) {
+      if (
+        (filter.apiKey !== undefined && usage.apiKey !== filter.apiKey) ||
+        (filter.apiKeys !== undefined && !filter.apiKeys.includes(usage.apiKey)) ||
+        (filter.model !== undefined && usage.model !== filter.model) ||
+        (filter.since !== undefined && usage.minute + BUCKET_WIDTHS.minute <= filter.since) ||
+        (filter.until !== undefined && usage.minute > filter.until)
+      ) {
+        continue;
+      }
+      const start = Math.floor(usage.minute / width) * width;
+      const key = `${start}:${usage.apiKey}:${usage.model}`;
+      let bucket = buckets.get(key);
+      if (!bucket) {
+        bucket = {
+          start_time: new Date(start).toISOString(),
+          end_time: new Date(start + width).toISOString(),
+          api_key: usage.apiKey,
+          model: usage.model,
+          ...emptyCounts(),
+        };
+        buckets.set(key, bucket);
+      }
+      add(bucket, usage);
+      add(totals, usage);
+    }
+
+    const data = [...buckets.values()].sort(
+      (a, b) =>
+        a.start_time.localeCompare(b.start_time) || a.api_key.localeCompare(b.api_key) || a.model.localeCompare(b.model)
+    );
+    return { data, totals };
+  }
+
+  // Every minute counted, to be restored after a restart
+  snapshot(): MinuteUsage[] {
+    return [...this.minutes.values()].map(usage => ({ ...usage }));
+  }
+
+  restore(minutes: MinuteUsage[]): void {
+    this.minutes = new Map(minutes.map(usage => [`${usage.minute}:${usage.apiKey}:${usage.model}`, { ...usage }]));
+  }
+
+  // Goes up whenever usage is recorded or reset
+  get version(): number {
+    return this.changes;
+  }
+
+  // Forgets one key's usage, or everyone's when no key is given
+  reset(apiKey?: string): void {
+    for (const [key, usage] of this.minutes) {
+      if (apiKey === undefined || usage.apiKey === apiKey) {
+        this.minutes.delete(key);
+      }
+    }
+    this.changes++;
+  }
+
+  private prune(now: number): void {
+    // Minutes are added in time order, so the oldest come first
+    for (const [key, usage] of this.minutes) {
+      if (usage.minute + this.retentionMs > now) {
+        break;
+      }
+      this.minutes.delete(key);
+    }
+  }
+}
+
+/**
+ * Parses the query of GET /admin/usage: bucket (minute, hour or day), since
+ * and until (ISO 8601 or epoch milliseconds) and model
+ */
+export function parseUsageFilter(query: Record<string, string>): UsageFilter {
+  const bucket = query.bucket ?? 'day';
+  if (!Object.hasOwn(BUCKET_WIDTHS, bucket)) {
+    throw new InvalidRequestError("Invalid 'bucket': expected minute, hour or day", 'bucket');
+  }
+  const filter: UsageFilter = { bucket: bucket as BucketWidth };
+
+  if (query.model !== undefined) {
+    filter.model = query.model;
+  }
+  for (const name of ['since', 'until'] as const) {
+    const value = query[name];
+    if (value !== undefined) {
+      const time = /^\d+$/.test(value) ? Number(value) : Date.parse(value);
+      if (Number.isNaN(time)) {
+        throw new InvalidRequestError(`Invalid '${name}': expected an ISO 8601 time or epoch milliseconds`, name);
+      }
+      filter[name] = time;
+    }
+  }
+  return filter;
+}
+
+function emptyCounts(): UsageCounts {
+  return { requests: 0, prompt_tokens: 0, cached_tokens: 0, completion_tokens: 0, total_tokens: 0 };
+}
+
+function add(counts: UsageCounts, usage: UsageCounts): void {
+  counts.requests += usage.requests;
+  counts.prompt_tokens += usage.prompt_tokens;
+  counts.cached_tokens += usage.cached_tokens;
+  counts.completion_tokens += usage.completion_tokens;
+  counts.total_tokens += usage.total_tokens;
+}
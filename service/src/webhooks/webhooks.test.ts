@@ -0,0 +1,148 @@
+import { describe, it, expect, vi } from "vitest";
+import { Hono } from "hono";
+import { Webhooks, parseWebhooks, signature, type Send, type WebhookPayload } from "./webhooks.js";
+import { createErrorHandler } from "../middleware/errors.js";
+import { InvalidRequestError } from "../openai-protocol/errors.js";
+import { Logger } from "../utils/logger.js";
+
+interface Delivery {
+  url: string;
+  headers: Record<string, string>;
+  body: string;
+  payload: WebhookPayload;
+}
+
+// Collects deliveries instead of sending them
+function receiver() {
+  const deliveries: Delivery[] = [];
+  const send: Send = async (url, init) => {
+    const body = init.body as string;
+    deliveries.push({ url, headers: init.headers as Record<string, string>, body, payload: JSON.parse(body) });
+    return new Response(null);
+  };
+  return { deliveries, send };
+}
+
+function createTestApp(webhooks: Webhooks) {
+  const app = new Hono<{ Variables: { apiKey: string; requestId: string; model: string } }>();
+  app.onError(createErrorHandler());
+  app.use("*", async (c, next) => {
+    c.set("apiKey", "key-a");
+    c.set("requestId", "req-1");
+    await next();
+  });
+  app.use("*", webhooks.middleware());
+  app.post("/v1/echo", (c) => c.json({ ok: true }));
+  app.post("/v1/fail", (c) => {
+    c.set("model", "echo");
+    throw new InvalidRequestError("Nope", "messages");
+  });
+  app.post("/v1/stream", (c) => {
+    c.set("model", "echo");
+    return new Response("data: [DONE]\n\n", { headers: { "Content-Type": "text/event-stream" } });
+  });
+  return app;
+}
+
+const hook = { url: "http://localhost:9000/hooks", secret: "whsec" };
+
+describe("Webhooks", () => {
+  it("should send a request's lifecycle events, signed with the secret", async () => {
+    const { deliveries, send } = receiver();
+    const webhooks = new Webhooks(new Logger("error"), send, () => 1_700_000_000_000);
+    webhooks.replace(parseWebhooks([hook]));
+    const app = createTestApp(webhooks);
+
+    await (await app.request("/v1/stream", { method: "POST" })).text();
+    await vi.waitFor(() => expect(deliveries).toHaveLength(2));
+
+    const received = deliveries.find((delivery) => delivery.payload.type === "request.received");
+    const completed = deliveries.find((delivery) => delivery.payload.type === "stream.completed");
+    expect(received?.payload).toMatchObject({
+      type: "request.received",
+      created: 1_700_000_000,
+      data: { request_id: "req-1", method: "POST", path: "/v1/stream", key_id: expect.stringMatching(/^[0-9a-f]{16}$/) },
+    });
+    expect(completed?.payload).toMatchObject({
+      type: "stream.completed",
+      data: { request_id: "req-1", model: "echo", status: 200, cancelled: false },
+    });
+    for (const delivery of deliveries) {
+      expect(delivery.url).toBe(hook.url);
+      expect(delivery.payload.id).toMatch(/^evt_/);
+      expect(delivery.headers["x-teenytiny-event"]).toBe(delivery.payload.type);
+      expect(delivery.headers["x-teenytiny-delivery"]).toBe(delivery.payload.id);
+      expect(delivery.headers["x-teenytiny-signature"]).toBe(
+        await signature("whsec", delivery.payload.created, delivery.body),
+      );
+    }
+  });
+
+  it("should send only the events a webhook subscribes to", async () => {
+    const { deliveries, send } = receiver();
+    const webhooks = new Webhooks(new Logger("error"), send);
+    webhooks.replace(parseWebhooks([{ ...hook, events: ["error.emitted"] }]));
+    const app = createTestApp(webhooks);
+
+    await app.request("/v1/echo", { method: "POST" });
+    await app.request("/v1/fail", { method: "POST" });
+    await vi.waitFor(() => expect(deliveries).toHaveLength(1));
+
+    expect(deliveries[0]?.payload).toMatchObject({
+      type: "error.emitted",
+      data: { path: "/v1/fail", model: "echo", status: 400, error: { message: "Nope", param: "messages" } },
+    });
+  });
+
+  it("should sign the timestamp and body with HMAC-SHA256", async () => {
+    expect(await signature("whsec", 1_700_000_000, '{"id":"evt_1"}')).toBe(
+      "t=1700000000,v1=e7e846cdb96220c3674ade89e534304fc91f6f15064facee1e7a7096f5f57f62",
+    );
+    // A replay with a fresh timestamp needs a new signature
+    expect(await signature("whsec", 1_700_000_600, '{"id":"evt_1"}')).not.toMatch(/v1=e7e846cd/);
+  });
+
+  it("should log deliveries that fail without failing the request", async () => {
+    const lines: string[] = [];
+    const webhooks = new Webhooks(new Logger("warn", (line) => lines.push(line)), async () => {
+      throw new Error("connection refused");
+    });
+    webhooks.replace(parseWebhooks([hook]));
+
+    const res = await createTestApp(webhooks).request("/v1/echo", { method: "POST" });
+    expect(res.status).toBe(200);
+    await vi.waitFor(() => expect(lines).toHaveLength(1));
+    expect(JSON.parse(lines[0]!)).toMatchObject({
+      level: "warn",
+      message: "Webhook delivery failed",
+      event: "request.received",
+      error: "connection refused",
+    });
+  });
+
+  it("should show webhooks as they are given, with every event by default", () => {
+    const webhooks = new Webhooks(new Logger("error"));
+    webhooks.replace(parseWebhooks([hook]));
+    expect(webhooks.list()).toEqual([
+      { ...hook, events: ["request.received", "stream.completed", "error.emitted"] },
+    ]);
+    expect(parseWebhooks(webhooks.list())).toEqual(webhooks.list());
+  });
+
+  it("should reject invalid webhooks", () => {
+    for (const body of [
+      {},
+      [{ secret: "whsec" }],
+      [{ url: "not a url", secret: "whsec" }],
+      [{ url: "ftp://localhost/hooks", secret: "whsec" }],
+      [{ url: hook.url }],
+      [{ ...hook, events: [] }],
+      [{ ...hook, events: ["request.sent"] }],
+      [{ ...hook, retries: 3 }],
+    ]) {
+      expect(() => parseWebhooks(body)).toThrow(
+        expect.objectContaining({ statusCode: 400, type: "invalid_request_error" }),
+      );
+    }
+  });
+});
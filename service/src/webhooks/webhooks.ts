@@ -0,0 +1,242 @@
+// Webhooks: request lifecycle events POSTed to a URL of the user's
+//
+// Each webhook is sent the events it subscribes to, every event when it names
+// none, as JSON signed with its secret so the receiver can check the event
+// came from this server:
+//
+//   POST https://example.com/hooks
+//   x-teenytiny-event: stream.completed
+//   x-teenytiny-delivery: evt_...
+//   x-teenytiny-signature: t=1700000000,v1=<hex HMAC-SHA256 of "1700000000.<body>">
+//
+//   {"id": "evt_...", "type": "stream.completed", "created": 1700000000, "data": {...}}
+//
+// The timestamp is signed along with the body, so a receiver that refuses
+// old timestamps can't be fooled by a captured delivery sent again later.
+//
+// Deliveries don't hold up the response they are about. One that fails is
+// logged and dropped, not retried.
+
+import { Context, Next } from 'hono';
+import { InvalidRequestError } from '../openai-protocol/errors.js';
+import { keyId } from '../middleware/logging.js';
+import { loggedWhenDone, type Logger } from '../utils/logger.js';
+
+export const WEBHOOK_EVENTS = ['request.received', 'stream.completed', 'error.emitted'] as const;
+export type WebhookEvent = (typeof WEBHOOK_EVENTS)[number];
+
+export const WEBHOOK_EVENT_HEADER = 'x-teenytiny-event';
+export const WEBHOOK_DELIVERY_HEADER = 'x-teenytiny-delivery';
+export const WEBHOOK_SIGNATURE_HEADER = 'x-teenytiny-signature';
+
+// A receiver that doesn't answer by then is given up on
+export const WEBHOOK_TIMEOUT_MS = 10_000;
+
+export interface Webhook {
+  url: string;
+  secret: string;
+  events: WebhookEvent[];
+}
+
+export interface WebhookPayload {
+  id: string;
+  type: WebhookEvent;
+  // Unix seconds, the same as the signature's timestamp
+  created: number;
+  data: Record<string, unknown>;
+}
+
+export type Send = (url: string, init: RequestInit) => Promise<Response>;
+
+/**
+ * Webhooks - delivers events about the requests passing through its middleware
+ *
+ * request.received is sent once a request is authenticated, before it is
+ * handled; error.emitted for each error response; stream.completed once a
+ * streamed response ends, with whether the client went away first.
+ */
+export class Webhooks {
+  private webhooks: Webhook[] = [];
+
+  constructor(
+    private logger: Logger,
+    private send: Send = (url, init) => fetch(url, init),
+    private now: () => number = Date.now
+  ) {}
+
+  replace(webhooks: Webhook[]): void {
+    this.webhooks = webhooks;
+  }
+
+  // As GET /admin/webhooks shows them, which PUT accepts back
+  list(): Webhook[] {
+    return this.webhooks.map(webhook => ({ ...webhook, events: [...webhook.events] }));
+  }
+
+  /**
+   * Sends an event to every webhook subscribed to it, resolving once each
+   * delivery has been answered or has failed
+   */
+  async emit(type: WebhookEvent, data: Record<string, unknown>): Promise<void> {
+    const webhooks = this.webhooks.filter(webhook => webhook.events.includes(type));
+    if (webhooks.length === 0) {
+      return;
+    }
+    const payload: WebhookPayload = {
+      id: `evt_${globalThis.crypto.randomUUID()}`,
+      type,
+      created: Math.floor(this.now() / 1000),
+      data,
+    };
+    const body = JSON.stringify(payload);
+    await Promise.all(webhooks.map(webhook => this.deliver(webhook, payload, body)));
+  }
+
+  private async deliver(webhook: Webhook, payload: WebhookPayload, body: string): Promise<void> {
+    const fields = { event: payload.type, delivery: payload.id, url: webhook.url };
+    try {
+      const response = await this.send(webhook.url, {
+        method: 'POST',
+        headers: {
+          'Content-Type': 'application/json',
+          [WEBHOOK_EVENT_HEADER]: payload.type,
+          [WEBHOOK_DELIVERY_HEADER]: payload.id,
+          [WEBHOOK_SIGNATURE_HEADER]: await signature(webhook.secret, payload.created, body),
+        },
+        body,
+        signal: AbortSignal.timeout(WEBHOOK_TIMEOUT_MS),
+      });
+      if (!response.ok) {
+        this.logger.warn('Webhook delivery rejected', { ...fields, status: response.status });
+      }
+      await response.body?.cancel();
+    } catch (error) {
+      this.logger.warn('Webhook delivery failed', {
+        ...fields,
+        error: error instanceof Error ? error.message : String(error),
+      });
+    }
+  }
+
+  // Must run after auth, which identifies the caller's API key
+  middleware() {
+    return async (c: Context, next: Next) => {
+      if (this.webhooks.length === 0) {
+        return next();
+      }
+      const started = this.now();
+      const apiKey: string | undefined = c.get('apiKey');
+      const request = {
+        request_id: c.get('requestId') ?? null,
+        method: c.req.method,
+        path: c.req.path,
+        key_id: apiKey ? await keyId(apiKey) : null,
+      };
+      background(c, this.emit('request.received', request));
+
+      await next();
+
+      const model = () => c.get('model') ?? null;
+      if (c.res.status >= 400) {
+        const body = await c.res.clone().json().catch(() => null);
+        background(
+          c,
+          this.emit('error.emitted', { ...request, model: model(), status: c.res.status, error: body?.error ?? null })
+        );
+      }
+
+      if ((c.res.headers.get('content-type') ?? '').startsWith('text/event-stream') && c.res.body) {
+        const status = c.res.status;
+        const body = loggedWhenDone(c.res.body, cancelled => {
+          background(
+            c,
+            this.emit('stream.completed', {
+              ...request,
+              model: model(),
+              status,
+              duration_ms: this.now() - started,
+              usage: c.get('usage') ?? null,
+              cancelled,
+            })
+          );
+        });
+        c.res = new Response(body, c.res);
+      }
+    };
+  }
+}
+
+// The signature header's value: the timestamp, and the HMAC-SHA256 under the
+// secret of the timestamp and body joined by a dot
+export async function signature(secret: string, timestamp: number, body: string): Promise<string> {
+  const encoder = new TextEncoder();
+  const key = await globalThis.crypto.subtle.importKey(
+    'raw',
+    encoder.encode(secret),
+    { name: 'HMAC', hash: 'SHA-256' },
+    false,
+    ['sign']
+  );
+  const mac = await globalThis.crypto.subtle.sign('HMAC', key, encoder.encode(`${timestamp}.${body}`));
+  return `t=${timestamp},v1=${[...new Uint8Array(mac)].map(byte => byte.toString(16).padStart(2, '0')).join('')}`;
+}
+
+// Keeps a Worker alive until a delivery is done. Node.js has no execution
+// context, and finishes the delivery anyway.
+function background(c: Context, delivery: Promise<void>): void {
+  try {
+    c.executionCtx.waitUntil(delivery);
+  } catch {
+    // Not on Workers
+  }
+}
+
+/**
+ * Validates [{"url": "https://...", "secret": "...", "events": ["error.emitted"]}],
+ * as PUT /admin/webhooks and config files give them. Events default to all.
+ */
+export function parseWebhooks(body: unknown): Webhook[] {
+  if (!Array.isArray(body)) {
+    throw new InvalidRequestError('Invalid webhooks: expected an array of webhooks');
+  }
+  return body.map((webhook, i) => parseWebhook(webhook, `webhooks[${i}]`));
+}
+
+function parseWebhook(value: unknown, param: string): Webhook {
+  if (!value || typeof value !== 'object' || Array.isArray(value)) {
+    throw new InvalidRequestError(`Invalid '${param}': expected an object with a url and secret`, param);
+  }
+  const webhook = value as Record<string, unknown>;
+  const unknown = Object.keys(webhook).find(setting => !['url', 'secret', 'events'].includes(setting));
+  if (unknown !== undefined) {
+    throw new InvalidRequestError(`Unknown setting '${param}.${unknown}': expected url, secret or events`, `${param}.${unknown}`);
+  }
+
+  const { url, secret, events = [...WEBHOOK_EVENTS] } = webhook;
+  if (typeof url !== 'string' || !isHttpUrl(url)) {
+    throw new InvalidRequestError(`Invalid '${param}.url': expected an http or https URL`, `${param}.url`);
+  }
+  if (typeof secret !== 'string' || secret === '') {
+    throw new InvalidRequestError(`Invalid '${param}.secret': expected a non-empty string`, `${param}.secret`);
+  }
+  if (
+    !Array.isArray(events) ||
+    events.length === 0 ||
+    !events.every(event => (WEBHOOK_EVENTS as readonly unknown[]).includes(event))
+  ) {
+    throw new InvalidRequestError(
+      `Invalid '${param}.events': expected a non-empty array of ${WEBHOOK_EVENTS.join(', ')}`,
+      `${param}.events`
+    );
+  }
+  return { url, secret, events: [...new Set(events as WebhookEvent[])] };
+}
+
+function isHttpUrl(value: string): boolean {
+  try {
+    const { protocol } = new URL(value);
+    return protocol === 'http:' || protocol === 'https:';
+  } catch {
+    return false;
+  }
+}
@@ -0,0 +1,196 @@
+import { describe, it, expect, beforeAll, afterAll, vi } from 'vitest';
+import { mkdirSync, readdirSync, readFileSync, writeFileSync } from 'node:fs';
+import { fileURLToPath } from 'node:url';
+import path from 'node:path';
+import { createApp } from '../../src/app.js';
+import { createRandom, randomCase } from './generators.js';
+import type { FuzzCase } from './generators.js';
+
+// Minimized inputs that once broke the parser, replayed on every run. A failing
+// fuzz run saves its input here, so fixing it and committing the file turns it
+// into a regression test.
+const CRASHERS = path.join(path.dirname(fileURLToPath(import.meta.url)), 'crashers');
+
+// FUZZ_ITERATIONS and FUZZ_SEED lengthen or repeat a run
+const ITERATIONS = Number(process.env.FUZZ_ITERATIONS ?? 300);
+const SEED = Number(process.env.FUZZ_SEED ?? Math.floor(Math.random() * 2 ** 32));
+
+// Nothing the fuzzer sends should take longer than this to answer
+const TIMEOUT_MS = 2000;
+
+const testAPIKey = 'tt-fuzz-key';
+
+type App = ReturnType<typeof createApp>;
+
+async function send(app: App, input: FuzzCase): Promise<Response> {
+  const hasBody = input.method !== 'GET' && input.method !== 'HEAD';
+  return app.request('/v1/chat/completions', {
+    method: input.method,
+    headers: { ...input.headers, Authorization: `Bearer ${testAPIKey}` },
+    ...(hasBody ? { body: input.body } : {}),
+  });
+}
+
+function checkErrorEnvelope(body: string): string | undefined {
+  let parsed: any;
+  try {
+    parsed = JSON.parse(body);
+  } catch {
+    return `error response is not JSON: ${body.slice(0, 200)}`;
+  }
+  const error = parsed?.error;
+  if (
+    typeof error?.message !== 'string' ||
+    typeof error.type !== 'string' ||
+    !('param' in error) ||
+    !('code' in error)
+  ) {
+    return `malformed error envelope: ${body.slice(0, 200)}`;
+  }
+  return undefined;
+}
+
+function checkStream(body: string): string | undefined {
+  for (const line of body.split('\n')) {
+    if (!line.startsWith('data: ') || line === 'data: [DONE]') continue;
+    try {
+      JSON.parse(line.slice(6));
+    } catch {
+      return `stream event is not JSON: ${line.slice(0, 200)}`;
+    }
+  }
+  return undefined;
+}
+
+// What's wrong with the server's answer to a case, or undefined if nothing is
+async function problemWith(app: App, input: FuzzCase): Promise<string | undefined> {
+  const answer = (async () => {
+    const res = await send(app, input);
+    const body = await res.text();
+    if (res.status >= 500) {
+      return `status ${res.status}: ${body.slice(0, 200)}`;
+    }
+    if (res.status >= 400) {
+      return checkErrorEnvelope(body);
+    }
+    if ((res.headers.get('content-type') ?? '').startsWith('text/event-stream')) {
+      return checkStream(body);
+    }
+    return undefined;
+  })();
+  const timeout = new Promise<string>(resolve =>
+    setTimeout(() => resolve(`no answer within ${TIMEOUT_MS}ms`), TIMEOUT_MS).unref()
+  );
+  try {
+    return await Promise.race([answer, timeout]);
+  } catch (error) {
+    return `threw ${error instanceof Error ? error.stack : String(error)}`;
+  }
+}
+
+type JsonPath = (string | number)[];
+
+// Paths to every value below the root, parents before children
+function pathsIn(value: unknown, prefix: JsonPath = []): JsonPath[] {
+  if (!value || typeof value !== 'object') return [];
+  return Object.entries(value).flatMap(([key, child]) => {
+    const path = [...prefix, Array.isArray(value) ? Number(key) : key];
+    return [path, ...pathsIn(child, path)];
+  });
+}
+
+// A copy of root with the value at path replaced, or removed if undefined
+function edited(root: unknown, path: JsonPath, replacement: unknown): unknown {
+  const copy = structuredClone(root) as any;
+  const parent = path.slice(0, -1).reduce((node, key) => node[key], copy);
+  const key = path[path.length - 1]!;
+  if (replacement !== undefined) {
+    parent[key] = replacement;
+  } else if (Array.isArray(parent)) {
+    parent.splice(Number(key), 1);
+  } else {
+    delete parent[key];
+  }
+  return copy;
+}
+
+// Greedily drops keys and array elements and halves strings while the case
+// still fails, so saved crashers are small enough to read
+async function minimize(app: App, input: FuzzCase): Promise<FuzzCase> {
+  let root: unknown;
+  try {
+    root = JSON.parse(input.body);
+  } catch {
+    return input;
+  }
+  const fails = async (candidate: unknown) =>
+    (await problemWith(app, { ...input, body: JSON.stringify(candidate) })) !== undefined;
+
+  // Each attempt is a request, and a hang costs the full timeout, so the
+  // search is bounded in time as well as attempts
+  const deadline = Date.now() + 30_000;
+  let attempts = 0;
+  let improved = true;
+  while (improved && attempts < 500 && Date.now() < deadline) {
+    improved = false;
+    for (const path of pathsIn(root)) {
+      const value = path.reduce<any>((node, key) => node[key], root);
+      const replacements: unknown[] = [undefined];
+      if (typeof value === 'string' && value.length > 1) {
+        replacements.push(value.slice(0, value.length / 2));
+      }
+      for (const replacement of replacements) {
+        attempts++;
+        const candidate = edited(root, path, replacement);
+        if (await fails(candidate)) {
+          root = candidate;
+          improved = true;
+          break;
+        }
+      }
+      if (improved || attempts >= 500 || Date.now() >= deadline) break;
+    }
+  }
+  return { ...input, body: JSON.stringify(root) };
+}
+
+describe('Chat completions fuzzing', () => {
+  let app: App;
+
+  beforeAll(() => {
+    app = createApp({
+      auth: { apiKey: testAPIKey },
+      rateLimit: { requestsPerMinute: Number.MAX_SAFE_INTEGER },
+    });
+    // Hundreds of requests would otherwise bury the test output in request logs
+    vi.spyOn(console, 'log').mockImplementation(() => {});
+    vi.spyOn(console, 'error').mockImplementation(() => {});
+  });
+
+  afterAll(() => {
+    vi.restoreAllMocks();
+  });
+
+  const crashers = readdirSync(CRASHERS).filter(file => file.endsWith('.json'));
+
+  it.each(crashers)('should handle crasher %s', async file => {
+    const input = JSON.parse(readFileSync(path.join(CRASHERS, file), 'utf8')) as FuzzCase;
+
+    expect(await problemWith(app, input)).toBeUndefined();
+  });
+
+  it('should answer generated requests with a response or an error envelope', async () => {
+    const random = createRandom(SEED);
+    for (let i = 0; i < ITERATIONS; i++) {
+      const input = randomCase(random);
+      const problem = await problemWith(app, input);
+      if (problem !== undefined) {
+        const crasher = await minimize(app, input);
+        mkdirSync(CRASHERS, { recursive: true });
+        const file = path.join(CRASHERS, `seed-${SEED}-${i}.json`);
+        writeFileSync(file, `${JSON.stringify(crasher, null, 2)}\n`);
+        expect.fail(`Case ${i} of seed ${SEED}: ${problem}\nSaved to ${file}`);
+      }
+    }
+  }, 120_000);
+});
@@ -0,0 +1,199 @@
+// Input generation for the chat completions fuzzer
+//
+// Cases start from valid requests and are mutated structurally (values
+// replaced, keys dropped, elements duplicated), then sometimes corrupted as
+// text, with headers and methods varied to cover malformed streaming requests.
+
+export interface FuzzCase {
+  method: string;
+  headers: Record<string, string>;
+  body: string;
+}
+
+export type Random = () => number;
+
+// mulberry32: small, fast and seedable, so any run can be repeated
+export function createRandom(seed: number): Random {
+  let state = seed >>> 0;
+  return () => {
+    state = (state + 0x6d2b79f5) >>> 0;
+    let t = state;
+    t = Math.imul(t ^ (t >>> 15), t | 1);
+    t ^= t + Math.imul(t ^ (t >>> 7), t | 61);
+    return ((t ^ (t >>> 14)) >>> 0) / 4294967296;
+  };
+}
+
+const pick = <T>(random: Random, items: readonly T[]): T => items[Math.floor(random() * items.length)]!;
+
+// Models that answer quickly and never fail on purpose
+const MODELS = ['echo', 'eliza', 'parry', 'racter', 'json', 'tooluse', 'gpt-4o-mini', 'nope', 'slow:abc', ''];
+
+const SEEDS: Record<string, unknown>[] = [
+  { model: 'echo', messages: [{ role: 'user', content: 'Hello' }] },
+  { model: 'echo', stream: true, stream_options: { include_usage: true }, messages: [{ role: 'user', content: 'Hi' }] },
+  {
+    model: 'eliza',
+    temperature: 0.5,
+    max_tokens: 20,
+    stop: ['.'],
+    messages: [
+      { role: 'system', content: 'Be brief' },
+      { role: 'user', content: 'I feel fine', name: 'sam' },
+    ],
+  },
+  {
+    model: 'echo',
+    messages: [
+      {
+        role: 'user',
+        content: [
+          { type: 'text', text: 'What is this?' },
+          { type: 'image_url', image_url: { url: 'https://example.com/a.png' } },
+        ],
+      },
+    ],
+  },
+  {
+    model: 'tooluse',
+    tools: [
+      {
+        type: 'function',
+        function: {
+          name: 'lookup',
+          parameters: { type: 'object', properties: { q: { type: 'string' } }, required: ['q'] },
+        },
+      },
+    ],
+    tool_choice: 'auto',
+    messages: [
+      { role: 'user', content: 'Look it up' },
+      {
+        role: 'assistant',
+        content: null,
+        tool_calls: [{ id: 'call_1', type: 'function', function: { name: 'lookup', arguments: '{"q":"x"}' } }],
+      },
+      { role: 'tool', tool_call_id: 'call_1', content: 'found' },
+    ],
+  },
+  {
+    model: 'json',
+    response_format: {
+      type: 'json_schema',
+      json_schema: {
+        name: 'answer',
+        schema: {
+          type: 'object',
+          properties: { items: { type: 'array', items: { $ref: '#/$defs/item' } } },
+          required: ['items'],
+          $defs: { item: { type: 'string', minLength: 2 } },
+        },
+      },
+    },
+    messages: [{ role: 'user', content: 'List' }],
+  },
+];
+
+// Keys and strings that mean something to the parser, so mutations reach
+// deeper than the first type check
+const KEYS = [
+  'model', 'messages', 'role', 'content', 'name', 'stream', 'stream_options', 'include_usage', 'n', 'stop',
+  'max_tokens', 'max_completion_tokens', 'temperature', 'top_p', 'seed', 'tools', 'tool_choice', 'tool_calls',
+  'tool_call_id', 'function', 'arguments', 'parameters', 'type', 'text', 'image_url', 'url', 'response_format',
+  'json_schema', 'schema', 'properties', 'required', 'items', 'prefixItems', 'minItems', 'minLength', 'maxLength',
+  'enum', 'const', 'anyOf', 'allOf', '$ref', 'minimum', 'maximum', 'multipleOf', 'metadata', 'interval_ms',
+  '__proto__', 'constructor',
+];
+const STRINGS = [
+  '', 'user', 'assistant', 'system', 'tool', 'developer', 'function', 'text', 'image_url', 'json_object',
+  'json_schema', 'object', 'array', 'string', 'integer', 'number', 'auto', 'required', 'none', '#', '#/$defs/item',
+  'data:image/png;base64,', 'https://', 'echo', '\u0000', '🌟', 'a'.repeat(300),
+];
+const NUMBERS = [0, -0, 1, -1, 0.5, 2, 3, 128, 129, 4096, 1e9, 1e12, 2 ** 53, -(2 ** 53), 1e308, -1e308, 5e-324];
+
+export function randomValue(random: Random, depth = 0): unknown {
+  const roll = random();
+  if (depth > 3 || roll < 0.15) return pick(random, [null, true, false]);
+  if (roll < 0.4) return pick(random, NUMBERS);
+  if (roll < 0.7) return pick(random, STRINGS);
+  if (roll < 0.85) {
+    return Array.from({ length: Math.floor(random() * 4) }, () => randomValue(random, depth + 1));
+  }
+  const object: Record<string, unknown> = {};
+  for (let i = Math.floor(random() * 4); i > 0; i--) {
+    object[pick(random, KEYS)] = randomValue(random, depth + 1);
+  }
+  return object;
+}
+
+// Every object and array in a JSON tree, for picking where to mutate
+function containers(value: unknown, found: (Record<string, unknown> | unknown[])[] = []) {
+  if (value && typeof value === 'object') {
+    found.push(value as Record<string, unknown> | unknown[]);
+    for (const child of Object.values(value)) containers(child, found);
+  }
+  return found;
+}
+
+function mutate(random: Random, root: Record<string, unknown>): void {
+  const target = pick(random, containers(root));
+  const keys = Object.keys(target);
+  const roll = random();
+  if (Array.isArray(target)) {
+    if (roll < 0.4 && target.length > 0) {
+      target[Math.floor(random() * target.length)] = randomValue(random);
+    } else if (roll < 0.6 && target.length > 0) {
+      target.splice(Math.floor(random() * target.length), 1);
+    } else if (roll < 0.8 && target.length > 0) {
+      target.push(structuredClone(pick(random, target)));
+    } else {
+      target.push(randomValue(random));
+    }
+  } else if (roll < 0.5 && keys.length > 0) {
+    target[pick(random, keys)] = randomValue(random);
+  } else if (roll < 0.7 && keys.length > 0) {
+    delete target[pick(random, keys)];
+  } else {
+    // Defined rather than assigned, so "__proto__" becomes an own key as JSON.parse would make it
+    Object.defineProperty(target, pick(random, KEYS), {
+      value: randomValue(random), enumerable: true, writable: true, configurable: true,
+    });
+  }
+}
+
+// Damages serialized JSON the way a broken client or proxy might
+function corrupt(random: Random, text: string): string {
+  const at = Math.floor(random() * (text.length + 1));
+  switch (Math.floor(random() * 4)) {
+    case 0: return text.slice(0, at);
+    case 1: return text.slice(0, at) + pick(random, ['{', '}', '[', ']', '"', ',', ':', '\\', '\u0000', 'NaN']) + text.slice(at);
+    case 2: return text.slice(0, at) + text.slice(at + 1);
+    default: return text.slice(0, at) + text.slice(at, at + 20).repeat(3) + text.slice(at + 20);
+  }
+}
+
+export function randomCase(random: Random): FuzzCase {
+  const request = structuredClone(pick(random, SEEDS));
+  if (random() < 0.3) request['model'] = pick(random, MODELS);
+  for (let i = 1 + Math.floor(random() * 4); i > 0; i--) {
+    mutate(random, request);
+  }
+
+  let body = JSON.stringify(request);
+  if (random() < 0.15) body = corrupt(random, body);
+
+  const headers: Record<string, string> = {};
+  const contentType = pick(random, [
+    'application/json', 'application/json', 'application/json; charset=utf-8', 'text/plain', 'multipart/form-data', '',
+  ]);
+  if (contentType) headers['Content-Type'] = contentType;
+  // What an EventSource or a confused SSE client sends
+  if (random() < 0.3) {
+    headers['Accept'] = pick(random, ['text/event-stream', 'text/event-stream, application/json', '*/*', 'nonsense']);
+    if (random() < 0.5) headers['Cache-Control'] = 'no-cache';
+    if (random() < 0.3) headers['Last-Event-ID'] = pick(random, ['0', '', 'chatcmpl-123']);
+  }
+  const method = random() < 0.9 ? 'POST' : pick(random, ['GET', 'PUT', 'PATCH', 'DELETE', 'OPTIONS']);
+
+  return { method, headers, body };
+}
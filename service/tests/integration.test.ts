@@ -1,8 +1,15 @@
-import { describe, it, expect, beforeAll, afterAll } from 'vitest';
+import { describe, it, expect, vi, beforeAll, afterAll } from 'vitest';
 import { createApp } from '../src/app.js';
+import { parseConfigFile } from '../src/config-file.js';
+import { DEFAULT_MIDDLEWARE, type MiddlewareConfig } from '../src/middleware/middleware-config.js';
+import { Logger, type LogLevel } from '../src/utils/logger.js';
+import { MemoryStateStore, type StateStore } from '../src/persistence/state-store.js';
+import { signature } from '../src/webhooks/webhooks.js';
 import type { ChatCompletionRequest } from '../src/types/openai.js';
 
 const testAPIKey = 'tt-test-key-123';
+// node:sqlite needs Node.js 22.5+
+const hasSqlite = await import('node:sqlite').then(() => true, () => false);
 
 describe('TeenyTiny API Integration Tests', () => {
   let app: ReturnType<typeof createApp>;
@@ -27,6 +34,74 @@ describe('TeenyTiny API Integration Tests', () => {
       });
       expect(data.timestamp).toBeDefined();
     });
+
+    it('should answer liveness and readiness probes', async () => {
+      const live = await app.request('/healthz');
+      expect(live.status).toBe(200);
+      expect(await live.json()).toEqual({ status: 'ok' });
+
+      const ready = await app.request('/readyz');
+      expect(ready.status).toBe(200);
+      const data = await ready.json();
+      expect(data.status).toBe('ready');
+      expect(data.checks.models).toBeGreaterThan(0);
+    });
+
+    it('should report build metadata', async () => {
+      const built = createApp({
+        auth: { apiKey: testAPIKey },
+        build: { version: '1.2.3', gitSha: 'abc1234', buildTime: '2024-01-01T00:00:00Z' },
+      });
+      const res = await built.request('/version');
+      expect(res.status).toBe(200);
+
+      const data = await res.json();
+      expect(data).toMatchObject({
+        service: 'teenytiny-api',
+        version: '1.2.3',
+        git_sha: 'abc1234',
+        build_time: '2024-01-01T00:00:00Z',
+      });
+      expect(data.api_surface).toContain('chat.completions');
+      expect(data.api_surface).not.toContain('proxy');
+    });
+
+    it('should report unknown build metadata when none is configured', async () => {
+      const data = await (await app.request('/version')).json();
+      expect(data).toMatchObject({
+        version: 'unknown',
+        git_sha: 'unknown',
+        build_time: 'unknown',
+        tokenizer: 'whitespace',
+      });
+    });
+
+    it('should report the size limits in effect', async () => {
+      const defaults = await (await app.request('/version')).json();
+      expect(defaults.limits).toEqual({ max_body_bytes: 8 * 1024 * 1024, max_file_bytes: 4 * 1024 * 1024 });
+
+      const limited = createApp({
+        auth: { apiKey: testAPIKey },
+        limits: { maxBodyBytes: 1024 },
+        files: { maxFileBytes: 512 },
+      });
+      const data = await (await limited.request('/version')).json();
+      expect(data.limits).toEqual({ max_body_bytes: 1024, max_file_bytes: 512 });
+    });
+  });
+
+  describe('Metrics', () => {
+    it('should report stream counters without authentication', async () => {
+      const res = await app.request('/metrics');
+      expect(res.status).toBe(200);
+
+      const data = await res.json();
+      expect(data).toMatchObject({
+        active_streams: 0,
+        active_stream_ids: [],
+        cancelled_generations: 0,
+      });
+    });
   });
 
   describe('Models Endpoint', () => {
@@ -71,6 +146,26 @@ describe('TeenyTiny API Integration Tests', () => {
       const data = await res.json();
       expect(data.error.type).toBe('authentication_error');
     });
+
+    it('should match the bearer scheme case-insensitively', async () => {
+      const res = await app.request('/v1/models', {
+        headers: {
+          'Authorization': `bearer ${testAPIKey}`,
+        },
+      });
+
+      expect(res.status).toBe(200);
+    });
+
+    it('should reject a token without a scheme', async () => {
+      const res = await app.request('/v1/models', {
+        headers: {
+          'Authorization': testAPIKey,
+        },
+      });
+
+      expect(res.status).toBe(401);
+    });
   });
 
   describe('Chat Completions', () => {
@@ -219,10 +314,32 @@ describe('TeenyTiny API Integration Tests', () => {
         body: JSON.stringify(invalidRequest),
       });
 
-      expect(res.status).toBe(400);
+      expect(res.status).toBe(404);
       const data = await res.json();
       expect(data.error.type).toBe('invalid_request_error');
       expect(data.error.param).toBe('model');
+      expect(data.error.code).toBe('model_not_found');
+    });
+
+    it('should resolve model aliases and report the alias', async () => {
+      const request: ChatCompletionRequest = {
+        model: 'gpt-3.5-turbo',
+        messages: [{ role: 'user', content: 'Hello!' }],
+      };
+
+      const res = await app.request('/v1/chat/completions', {
+        method: 'POST',
+        headers: {
+          'Authorization': `Bearer ${testAPIKey}`,
+          'Content-Type': 'application/json',
+        },
+        body: JSON.stringify(request),
+      });
+
+      expect(res.status).toBe(200);
+      const data = await res.json();
+      expect(data.model).toBe('gpt-3.5-turbo');
+      expect(data.choices[0].message.content).toBe('Hello!');
     });
 
     it('should handle empty messages array', async () => {
@@ -247,6 +364,234 @@ describe('TeenyTiny API Integration Tests', () => {
     });
   });
 
+  describe('Multimodal Messages', () => {
+    const post = (messages: unknown) => app.request('/v1/chat/completions', {
+      method: 'POST',
+      headers: {
+        'Authorization': `Bearer ${testAPIKey}`,
+        'Content-Type': 'application/json',
+      },
+      body: JSON.stringify({ model: 'echo', messages }),
+    });
+
+    it('should echo the text parts of a content array', async () => {
+      const res = await post([
+        {
+          role: 'user',
+          content: [
+            { type: 'text', text: 'What is in this image?' },
+            { type: 'image_url', image_url: { url: 'https://example.com/cat.png' } },
+            { type: 'text', text: 'Be brief.' },
+          ],
+        },
+      ]);
+
+      expect(res.status).toBe(200);
+      const data = await res.json();
+      expect(data.choices[0].message.content).toBe('What is in this image?\nBe brief.');
+    });
+
+    it('should name the path of a malformed content part', async () => {
+      const res = await post([
+        {
+          role: 'user',
+          content: [
+            { type: 'text', text: 'Hello' },
+            { type: 'image_url', image_url: {} },
+          ],
+        },
+      ]);
+
+      expect(res.status).toBe(400);
+      const data = await res.json();
+      expect(data.error.type).toBe('invalid_request_error');
+      expect(data.error.message).toContain('messages[0].content[1].image_url.url');
+    });
+  });
+
+  describe('Provisioned Keys', () => {
+    const scopedApp = createApp({
+      auth: {
+        apiKey: testAPIKey,
+        keys: [{ key: 'tenant-a' }, { key: 'tenant-b', models: ['echo'] }],
+        revokedKeys: [testAPIKey],
+      },
+    });
+
+    const complete = (key: string, model: string) =>
+      scopedApp.request('/v1/chat/completions', {
+        method: 'POST',
+        headers: {
+          'Authorization': `Bearer ${key}`,
+          'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({ model, messages: [{ role: 'user', content: 'Hello!' }] }),
+      });
+
+    it('should accept each provisioned key', async () => {
+      expect((await complete('tenant-a', 'eliza')).status).toBe(200);
+      expect((await complete('tenant-b', 'echo')).status).toBe(200);
+    });
+
+    it('should reject revoked keys', async () => {
+      const res = await complete(testAPIKey, 'echo');
+
+      expect(res.status).toBe(401);
+    });
+
+    it('should enforce model allowlists with 403', async () => {
+      const res = await complete('tenant-b', 'eliza');
+
+      expect(res.status).toBe(403);
+      const data = await res.json();
+      expect(data.error.type).toBe('permission_error');
+      expect(data.error.code).toBe('model_not_allowed');
+    });
+  });
+
+  describe('Session Conversations', () => {
+    const say = (target: ReturnType<typeof createApp>, sessionId: string, message: string) =>
+      target.request(`/session/${sessionId}/say`, {
+        method: 'POST',
+        headers: {
+          'Authorization': `Bearer ${testAPIKey}`,
+          'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({ message }),
+      });
+
+    it('should accumulate history across turns', async () => {
+      await say(app, 'history-test', 'First');
+      const res = await say(app, 'history-test', 'Second');
+
+      expect(res.status).toBe(200);
+      const data = await res.json();
+      expect(data.reply).toBe('Second');
+      expect(data.messages.map((m: any) => m.content)).toEqual([
+        'First', 'First', 'Second', 'Second',
+      ]);
+    });
+
+    it('should keep sessions independent', async () => {
+      await say(app, 'independent-a', 'Only in A');
+      const res = await say(app, 'independent-b', 'Only in B');
+
+      const data = await res.json();
+      expect(data.messages).toHaveLength(2);
+    });
+
+    it('should start over once a session expires', async () => {
+      const shortLived = createApp({
+        auth: { apiKey: testAPIKey },
+        sessions: { ttlMs: 10 },
+      });
+
+      await say(shortLived, 'expiring', 'Hello');
+      await new Promise(resolve => setTimeout(resolve, 30));
+      const res = await say(shortLived, 'expiring', 'Hello again');
+
+      const data = await res.json();
+      expect(data.messages).toHaveLength(2);
+    });
+
+    it("should keep one key's session from another's of the same name", async () => {
+      const shared = createApp({ auth: { apiKey: testAPIKey, keys: [{ key: 'session-a' }, { key: 'session-b' }] } });
+      const sayAs = (key: string, message: string) =>
+        shared.request('/session/same-name/say', {
+          method: 'POST',
+          headers: { 'Authorization': `Bearer ${key}`, 'Content-Type': 'application/json' },
+          body: JSON.stringify({ message }),
+        });
+
+      await sayAs('session-a', 'Only for A');
+      const data = await (await sayAs('session-b', 'Only for B')).json();
+      expect(data.messages.map((m: any) => m.content)).toEqual(['Only for B', 'Only for B']);
+    });
+
+    it('should reject a body that is not an object with 400', async () => {
+      for (const body of ['null', '[]', '"Hi"']) {
+        const res = await app.request('/session/not-an-object/say', {
+          method: 'POST',
+          headers: { 'Authorization': `Bearer ${testAPIKey}`, 'Content-Type': 'application/json' },
+          body,
+        });
+        expect(res.status, body).toBe(400);
+        expect((await res.json()).error.type).toBe('invalid_request_error');
+      }
+    });
+
+    it('should rate limit sessions like the rest of the API', async () => {
+      const limited = createApp({ auth: { apiKey: testAPIKey }, rateLimit: { requestsPerMinute: 2 } });
+
+      const statuses = [];
+      for (let i = 0; i < 3; i++) {
+        statuses.push((await say(limited, `limited-${i}`, 'Hi')).status);
+      }
+
+      expect(statuses).toEqual([200, 200, 429]);
+    });
+
+    it('should require authentication', async () => {
+      const res = await app.request('/session/anything/say', {
+        method: 'POST',
+        body: JSON.stringify({ message: 'Hi' }),
+      });
+
+      expect(res.status).toBe(401);
+    });
+  });
+
+  describe('Memory Model', () => {
+    const chat = (target: ReturnType<typeof createApp>, content: string, headers: Record<string, string> = {}, user?: string) =>
+      target.request('/v1/chat/completions', {
+        method: 'POST',
+        headers: {
+          'Authorization': `Bearer ${testAPIKey}`,
+          'Content-Type': 'application/json',
+          ...headers,
+        },
+        body: JSON.stringify({ model: 'memory', user, messages: [{ role: 'user', content }] }),
+      });
+    const reply = async (res: Response) => (await res.json()).choices[0].message.content;
+
+    it('should continue the conversation named by user or the conversation header', async () => {
+      await chat(app, 'my name is Ada', {}, 'memory-user');
+      expect(await reply(await chat(app, 'who am I?', {}, 'memory-user'))).toBe(
+        'Turn 2. You said "who am I?". Before that you said "my name is Ada".',
+      );
+
+      // The header takes precedence over user
+      await chat(app, 'first', { 'x-teenytiny-conversation': 'memory-header' }, 'memory-user');
+      expect(await reply(await chat(app, 'second', { 'x-teenytiny-conversation': 'memory-header' }))).toContain('Turn 2.');
+
+      // Without either, only the request's own messages count
+      expect(await reply(await chat(app, 'alone'))).toContain('Turn 1.');
+    });
+
+    it('should list and forget conversations through the admin API', async () => {
+      const remembering = createApp({ auth: { apiKey: testAPIKey } });
+      await chat(remembering, 'hello', {}, 'inspected');
+      const admin = (method: string, path: string) =>
+        remembering.request(path, { method, headers: { 'Authorization': `Bearer ${testAPIKey}` } });
+
+      const listed = await (await admin('GET', '/admin/conversations')).json();
+      expect(listed.data).toEqual([
+        expect.objectContaining({ id: 'inspected', key: testAPIKey, turns: 1 }),
+      ]);
+      expect(listed.data[0].messages.map((m: any) => m.role)).toEqual(['user', 'assistant']);
+
+      expect(await (await admin('DELETE', '/admin/conversations/inspected')).json()).toMatchObject({ deleted: true });
+      expect(await reply(await chat(remembering, 'again', {}, 'inspected'))).toContain('Turn 1.');
+    });
+
+    it('should forget conversations left idle past the TTL', async () => {
+      const forgetful = createApp({ auth: { apiKey: testAPIKey }, memory: { ttlMs: 10 } });
+      await chat(forgetful, 'hello', {}, 'expiring');
+      await new Promise(resolve => setTimeout(resolve, 30));
+      expect(await reply(await chat(forgetful, 'hello again', {}, 'expiring'))).toContain('Turn 1.');
+    });
+  });
+
   describe('Echo Model Behavior', () => {
     it('should echo the last user message', async () => {
       const request: ChatCompletionRequest = {
@@ -273,6 +618,28 @@ describe('TeenyTiny API Integration Tests', () => {
       expect(data.choices[0].message.content).toBe('Second message');
     });
 
+    it('should accept developer messages and message names', async () => {
+      const res = await app.request('/v1/chat/completions', {
+        method: 'POST',
+        headers: {
+          'Authorization': `Bearer ${testAPIKey}`,
+          'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({
+          model: 'echo',
+          messages: [
+            { role: 'developer', content: 'You are a helpful assistant.' },
+            { role: 'system', name: 'style_guide', content: 'Be brief.' },
+            { role: 'user', name: 'alice', content: 'Hello from Alice' },
+          ],
+        }),
+      });
+
+      expect(res.status).toBe(200);
+      const data = await res.json();
+      expect(data.choices[0].message.content).toBe('Hello from Alice');
+    });
+
     it('should provide default response when no user messages', async () => {
       const request: ChatCompletionRequest = {
         model: 'echo',
@@ -294,47 +661,1663 @@ describe('TeenyTiny API Integration Tests', () => {
       const data = await res.json();
       expect(data.choices[0].message.content).toContain('Echo model');
     });
-  });
+  
 
-  describe('CORS', () => {
-    it('should handle OPTIONS requests', async () => {
+    it('should apply directives embedded in the message', async () => {
       const res = await app.request('/v1/chat/completions', {
-        method: 'OPTIONS',
+        method: 'POST',
+        headers: {
+          'Authorization': `Bearer ${testAPIKey}`,
+          'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({
+          model: 'echo',
+          messages: [{ role: 'user', content: 'Hello !finish:length !tokens:99' }],
+        }),
       });
 
       expect(res.status).toBe(200);
-      expect(res.headers.get('access-control-allow-origin')).toBe('*');
-      expect(res.headers.get('access-control-allow-methods')).toContain('POST');
-      expect(res.headers.get('access-control-allow-headers')).toContain('Authorization');
+      const data = await res.json();
+      expect(data.choices[0].message.content).toBe('Hello');
+      expect(data.choices[0].finish_reason).toBe('length');
+      expect(data.usage.completion_tokens).toBe(99);
     });
-  });
 
-  describe('Error Handling', () => {
-    it('should handle 404 for unknown routes', async () => {
-      const res = await app.request('/unknown-route', {
+    it('should fail with the status requested by an error directive', async () => {
+      const res = await app.request('/v1/chat/completions', {
+        method: 'POST',
         headers: {
           'Authorization': `Bearer ${testAPIKey}`,
+          'Content-Type': 'application/json',
         },
+        body: JSON.stringify({
+          model: 'echo',
+          stream: true,
+          messages: [{ role: 'user', content: '!error:503' }],
+        }),
       });
 
-      expect(res.status).toBe(404);
+      expect(res.status).toBe(503);
       const data = await res.json();
-      expect(data.error.type).toBe('not_found_error');
+      expect(data.error.type).toBe('overloaded_error');
     });
 
-    it('should handle malformed JSON', async () => {
+    it('should cut the filtered model short with content_filter', async () => {
       const res = await app.request('/v1/chat/completions', {
         method: 'POST',
         headers: {
           'Authorization': `Bearer ${testAPIKey}`,
           'Content-Type': 'application/json',
         },
-        body: 'invalid json',
+        body: JSON.stringify({
+          model: 'filtered',
+          messages: [{ role: 'user', content: 'one two three four five' }],
+        }),
+      });
+
+      expect(res.status).toBe(200);
+      const data = await res.json();
+      expect(data.choices[0].message.content).toBe('one two three');
+      expect(data.choices[0].finish_reason).toBe('content_filter');
+    });
+
+    it('should reason before answering and reject unsupported parameters', async () => {
+      const chat = (body: Record<string, unknown>) =>
+        app.request('/v1/chat/completions', {
+          method: 'POST',
+          headers: {
+            'Authorization': `Bearer ${testAPIKey}`,
+            'Content-Type': 'application/json',
+          },
+          body: JSON.stringify({
+            model: 'reasoning',
+            messages: [{ role: 'user', content: 'Think it over' }],
+            ...body,
+          }),
+        });
+
+      const res = await chat({ temperature: 1 });
+      expect(res.status).toBe(200);
+      const data = await res.json();
+      expect(data.choices[0].message.content).toBe('Think it over');
+      expect(data.choices[0].message.reasoning_content).toContain('3 words');
+      expect(data.usage.completion_tokens_details.reasoning_tokens).toBeGreaterThan(0);
+
+      const rejected = await chat({ temperature: 0.2 });
+      expect(rejected.status).toBe(400);
+      const error = await rejected.json();
+      expect(error.error.param).toBe('temperature');
+      expect(error.error.code).toBe('unsupported_value');
+    });
+
+    it('should cut streams as the chunking header asks', async () => {
+      const streamedDeltas = async (target: typeof app, chunking?: string) => {
+        const res = await target.request('/v1/chat/completions', {
+          method: 'POST',
+          headers: {
+            'Authorization': `Bearer ${testAPIKey}`,
+            'Content-Type': 'application/json',
+            ...(chunking ? { 'x-teenytiny-chunking': chunking } : {}),
+          },
+          body: JSON.stringify({
+            model: 'echo',
+            stream: true,
+            messages: [{ role: 'user', content: 'Héllo wörld 🎉' }],
+          }),
+        });
+        expect(res.status).toBe(200);
+        return (await res.text())
+          .split('\n')
+          .filter(line => line.startsWith('data: {'))
+          .map(line => JSON.parse(line.slice(6)).choices[0].delta.content)
+          .filter(content => typeof content === 'string');
+      };
+
+      expect(await streamedDeltas(app, 'word')).toEqual(['Héllo', ' wörld', ' 🎉']);
+      expect(await streamedDeltas(app, 'bytes:3')).toEqual(['Hé', 'llo', ' w', 'ör', 'ld ', '🎉']);
+      expect(await streamedDeltas(app, 'message')).toEqual(['Héllo wörld 🎉']);
+
+      // Configured chunking applies until a request asks for another
+      const byWord = createApp({ auth: { apiKey: testAPIKey }, chunking: { type: 'word' } });
+      expect(await streamedDeltas(byWord)).toHaveLength(3);
+      expect(await streamedDeltas(byWord, 'model')).toEqual(['Héllo wörld 🎉']);
+    });
+
+    it('should reject an invalid chunking header', async () => {
+      const res = await app.request('/v1/chat/completions', {
+        method: 'POST',
+        headers: {
+          'Authorization': `Bearer ${testAPIKey}`,
+          'Content-Type': 'application/json',
+          'x-teenytiny-chunking': 'bytes:0',
+        },
+        body: JSON.stringify({
+          model: 'echo',
+          stream: true,
+          messages: [{ role: 'user', content: 'Hello' }],
+        }),
       });
 
       expect(res.status).toBe(400);
       const data = await res.json();
-      expect(data.error.type).toBe('invalid_request_error');
+      expect(data.error.param).toBe('x-teenytiny-chunking');
+    });
+  });
+
+  describe('Tool Calling', () => {
+    const tools = [
+      {
+        type: 'function',
+        function: {
+          name: 'get_weather',
+          parameters: {
+            type: 'object',
+            properties: { city: { type: 'string' } },
+            required: ['city'],
+          },
+        },
+      },
+    ];
+
+    it('should call the offered tools and then answer from the results', async () => {
+      const first = await app.request('/v1/chat/completions', {
+        method: 'POST',
+        headers: {
+          'Authorization': `Bearer ${testAPIKey}`,
+          'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({
+          model: 'tooluse',
+          tools,
+          messages: [{ role: 'user', content: 'Weather in Paris?' }],
+        }),
+      });
+
+      expect(first.status).toBe(200);
+      const call = await first.json();
+      expect(call.choices[0].finish_reason).toBe('tool_calls');
+      expect(call.choices[0].message.tool_calls[0].function).toEqual({
+        name: 'get_weather',
+        arguments: '{"city":"string"}',
+      });
+
+      const second = await app.request('/v1/chat/completions', {
+        method: 'POST',
+        headers: {
+          'Authorization': `Bearer ${testAPIKey}`,
+          'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({
+          model: 'tooluse',
+          tools,
+          messages: [
+            { role: 'user', content: 'Weather in Paris?' },
+            call.choices[0].message,
+            { role: 'tool', tool_call_id: call.choices[0].message.tool_calls[0].id, content: 'Sunny, 21C' },
+          ],
+        }),
+      });
+
+      expect(second.status).toBe(200);
+      const answer = await second.json();
+      expect(answer.choices[0].finish_reason).toBe('stop');
+      expect(answer.choices[0].message.content).toBe('Sunny, 21C');
+    });
+
+    it('should reject a tool_choice naming an unknown tool', async () => {
+      const res = await app.request('/v1/chat/completions', {
+        method: 'POST',
+        headers: {
+          'Authorization': `Bearer ${testAPIKey}`,
+          'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({
+          model: 'tooluse',
+          tools,
+          tool_choice: { type: 'function', function: { name: 'get_time' } },
+          messages: [{ role: 'user', content: 'What time is it?' }],
+        }),
+      });
+
+      expect(res.status).toBe(400);
+      const data = await res.json();
+      expect(data.error.param).toBe('tool_choice');
+    });
+
+    it('should require tool_call_id on tool messages', async () => {
+      const res = await app.request('/v1/chat/completions', {
+        method: 'POST',
+        headers: {
+          'Authorization': `Bearer ${testAPIKey}`,
+          'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({
+          model: 'tooluse',
+          messages: [{ role: 'tool', content: 'Sunny' }],
+        }),
+      });
+
+      expect(res.status).toBe(400);
+    });
+  });
+
+  describe('Structured Output', () => {
+    it('should answer with a value satisfying the json_schema', async () => {
+      const res = await app.request('/v1/chat/completions', {
+        method: 'POST',
+        headers: {
+          'Authorization': `Bearer ${testAPIKey}`,
+          'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({
+          model: 'json',
+          messages: [{ role: 'user', content: 'Describe a book' }],
+          response_format: {
+            type: 'json_schema',
+            json_schema: {
+              name: 'book',
+              schema: {
+                type: 'object',
+                properties: {
+                  title: { type: 'string' },
+                  pages: { type: 'integer', minimum: 10 },
+                  genres: { type: 'array', items: { enum: ['fiction', 'poetry'] }, minItems: 1 },
+                },
+                required: ['title', 'pages', 'genres'],
+              },
+            },
+          },
+        }),
+      });
+
+      expect(res.status).toBe(200);
+      const data = await res.json();
+      expect(JSON.parse(data.choices[0].message.content)).toEqual({
+        title: 'string',
+        pages: 10,
+        genres: ['fiction'],
+      });
+    });
+  });
+
+  describe('Scripted Models', () => {
+    it('should serve scripts as script:<name> models', async () => {
+      const scripted = createApp({
+        auth: { apiKey: testAPIKey },
+        scripts: {
+          shout: ({ input }) => ({ content: input.toUpperCase(), finish_reason: 'length' }),
+        },
+      });
+
+      const res = await scripted.request('/v1/chat/completions', {
+        method: 'POST',
+        headers: {
+          'Authorization': `Bearer ${testAPIKey}`,
+          'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({
+          model: 'script:shout',
+          messages: [{ role: 'user', content: 'hello' }],
+        }),
+      });
+
+      expect(res.status).toBe(200);
+      const data = await res.json();
+      expect(data.model).toBe('script:shout');
+      expect(data.choices[0].message.content).toBe('HELLO');
+      expect(data.choices[0].finish_reason).toBe('length');
+    });
+  });
+
+  describe('Markov Model', () => {
+    const trained = createApp({
+      auth: { apiKey: testAPIKey },
+      corpus: 'The cat sat on the mat. The dog sat on the rug. A bird sang on the roof all day.',
+    });
+    const chat = async (client: ReturnType<typeof createApp>, body: Record<string, unknown>) =>
+      client.request('/v1/chat/completions', {
+        method: 'POST',
+        headers: { 'Authorization': `Bearer ${testAPIKey}`, 'Content-Type': 'application/json' },
+        body: JSON.stringify({ model: 'markov', messages: [{ role: 'user', content: 'Hello' }], ...body }),
+      });
+
+    it('should write the same text for the same seed', async () => {
+      const replies = await Promise.all(
+        [1, 1].map(async (seed) => (await (await chat(trained, { seed })).json()).choices[0].message.content),
+      );
+
+      expect(replies[0]).toBe(replies[1]);
+      expect(replies[0]).toMatch(/^[A-Z].*\.$/);
+    });
+
+    it('should write up to max_tokens', async () => {
+      const data = await (await chat(trained, { seed: 3, max_tokens: 25 })).json();
+
+      expect(data.usage.completion_tokens).toBe(25);
+      expect(data.choices[0].finish_reason).toBe('length');
+    });
+
+    it('should only be served with a corpus', async () => {
+      expect((await chat(app, {})).status).toBe(404);
+    });
+  });
+
+  describe('Record and Replay', () => {
+    const admin = (path: string, body?: unknown) =>
+      app.request(path, {
+        method: 'POST',
+        headers: {
+          'Authorization': `Bearer ${testAPIKey}`,
+          'Content-Type': 'application/json',
+        },
+        ...(body === undefined ? {} : { body: JSON.stringify(body) }),
+      });
+
+    const complete = () =>
+      app.request('/v1/chat/completions', {
+        method: 'POST',
+        headers: {
+          'Authorization': `Bearer ${testAPIKey}`,
+          'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({
+          model: 'echo',
+          stream: true,
+          messages: [{ role: 'user', content: 'Record me' }],
+        }),
+      });
+
+    it('should replay a recorded stream byte-for-byte', async () => {
+      expect((await admin('/admin/cassettes/integration/record')).status).toBe(200);
+      const recorded = await (await complete()).text();
+      const stopped = await (await admin('/admin/cassettes/stop')).json();
+      expect(stopped).toMatchObject({ mode: 'recording', cassette: 'integration', interactions: 1 });
+
+      await admin('/admin/cassettes/integration/replay', { realtime: false });
+      const replayed = await (await complete()).text();
+      await admin('/admin/cassettes/stop');
+
+      // Live responses get a fresh id each time, so equality proves the replay
+      expect(replayed).toBe(recorded);
+      expect(await (await complete()).text()).not.toBe(recorded);
+    });
+
+    it('should list saved cassettes', async () => {
+      const res = await app.request('/admin/cassettes', {
+        headers: { 'Authorization': `Bearer ${testAPIKey}` },
+      });
+
+      expect(res.status).toBe(200);
+      const data = await res.json();
+      expect(data.data).toContain('integration');
+      expect(data.recorder.mode).toBe('idle');
+    });
+
+    it('should require authentication', async () => {
+      const res = await app.request('/admin/cassettes');
+      expect(res.status).toBe(401);
+    });
+  });
+
+  describe('Admin API', () => {
+    const adminRequest = (method: string, path: string, body?: unknown, key = testAPIKey) =>
+      app.request(path, {
+        method,
+        headers: {
+          'Authorization': `Bearer ${key}`,
+          'Content-Type': 'application/json',
+        },
+        ...(body === undefined ? {} : { body: JSON.stringify(body) }),
+      });
+
+    const chat = (key: string, model: string) =>
+      app.request('/v1/chat/completions', {
+        method: 'POST',
+        headers: {
+          'Authorization': `Bearer ${key}`,
+          'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({ model, messages: [{ role: 'user', content: 'Hi' }] }),
+      });
+
+    it('should create scoped keys and revoke them', async () => {
+      const created = await (await adminRequest('POST', '/admin/keys', { models: ['echo'] })).json();
+
+      expect((await chat(created.key, 'echo')).status).toBe(200);
+      expect((await chat(created.key, 'eliza')).status).toBe(403);
+
+      expect((await adminRequest('DELETE', `/admin/keys/${created.key}`)).status).toBe(200);
+      expect((await chat(created.key, 'echo')).status).toBe(401);
+    });
+
+    it('should change the rate limit at runtime', async () => {
+      const res = await adminRequest('PUT', '/admin/rate-limit', { requests_per_minute: 1234 });
+      expect(res.status).toBe(200);
+
+      const chatRes = await chat(testAPIKey, 'echo');
+      expect(chatRes.headers.get('x-ratelimit-limit-requests')).toBe('1234');
+
+      expect((await adminRequest('PUT', '/admin/rate-limit', { requests_per_minute: 0 })).status).toBe(400);
+    });
+
+    it('should apply model defaults set at runtime', async () => {
+      const defaults = { 'slow:0': { max_tokens: 2, system_prompt: 'Be brief' } };
+      expect((await adminRequest('PUT', '/admin/model-defaults', defaults)).status).toBe(200);
+
+      const effective = await (await adminRequest('GET', '/admin/model-defaults/slow:0')).json();
+      expect(effective).toEqual({ model: 'slow:0', max_tokens: 2, temperature: null, system_prompt: 'Be brief' });
+
+      const res = await app.request('/v1/chat/completions', {
+        method: 'POST',
+        headers: {
+          'Authorization': `Bearer ${testAPIKey}`,
+          'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({ model: 'slow:0', max_tokens: 10, messages: [{ role: 'user', content: 'one two three four' }] }),
+      });
+      expect(res.headers.get('x-teenytiny-model-defaults')).toBe('max_tokens, system_prompt');
+      const data = await res.json();
+      expect(data.choices[0]).toMatchObject({ message: { content: 'one two' }, finish_reason: 'length' });
+
+      expect((await adminRequest('PUT', '/admin/model-defaults', { 'slow:0': { top_p: 1 } })).status).toBe(400);
+      expect((await adminRequest('PUT', '/admin/model-defaults', {})).status).toBe(200);
+    });
+
+    it('should switch fault injection off and on', async () => {
+      await adminRequest('PUT', '/admin/faults', { failure_rate: 0 });
+      for (let i = 0; i < 5; i++) {
+        expect((await chat(testAPIKey, 'flaky')).status).toBe(200);
+      }
+
+      const res = await adminRequest('PUT', '/admin/faults', { failure_rate: 1, kinds: ['503'] });
+      expect(await res.json()).toEqual({ failure_rate: 1, kinds: ['503'] });
+      expect((await chat(testAPIKey, 'flaky')).status).toBe(503);
+    });
+
+    it('should inject latency per endpoint', async () => {
+      const profiles = { '/v1/models': { ttfb: { type: 'fixed', ms: 60 } } };
+      const res = await adminRequest('PUT', '/admin/latency', profiles);
+      expect(await res.json()).toEqual(profiles);
+
+      const started = Date.now();
+      await app.request('/v1/models', { headers: { 'Authorization': `Bearer ${testAPIKey}` } });
+      expect(Date.now() - started).toBeGreaterThanOrEqual(55);
+
+      expect((await adminRequest('PUT', '/admin/latency', { '/v1/models': { ttfb: { type: 'fixed' } } })).status).toBe(400);
+      await adminRequest('PUT', '/admin/latency', {});
+      expect(await (await adminRequest('GET', '/admin/latency')).json()).toEqual({});
+    });
+
+    it('should list models, aliases and variants', async () => {
+      const data = await (await adminRequest('GET', '/admin/models')).json();
+
+      expect(data.models).toContain('echo');
+      expect(data.aliases['gpt-4o-mini']).toBe('echo');
+      expect(data.variants).toContain('slow');
+    });
+
+    it('should play a scenario of outcomes for a key, then let it through', async () => {
+      const created = await (await adminRequest('POST', '/admin/keys')).json();
+      const res = await adminRequest('PUT', '/admin/scenarios', {
+        backoff: { key: created.key, steps: [{ outcome: '429', retry_after: 3 }, '429', '500', 'ok'] },
+      });
+      expect(res.status).toBe(200);
+
+      const responses = [];
+      for (let i = 0; i < 5; i++) {
+        responses.push(await chat(created.key, 'echo'));
+      }
+      expect(responses.map((response) => response.status)).toEqual([429, 429, 500, 200, 200]);
+      expect(responses[0]!.headers.get('retry-after')).toBe('3');
+      expect(responses[1]!.headers.get('retry-after')).toBeNull();
+      expect((await responses[0]!.json()).error.code).toBe('rate_limit_exceeded');
+
+      const scenarios = await (await adminRequest('GET', '/admin/scenarios')).json();
+      expect(scenarios.backoff.played).toBe(4);
+      expect((await chat(testAPIKey, 'echo')).status).toBe(200);
+
+      expect((await adminRequest('PUT', '/admin/scenarios', { bad: { key: 'k', steps: ['418'] } })).status).toBe(400);
+      await adminRequest('PUT', '/admin/scenarios', {});
+    });
+
+    it('should send signed lifecycle events to webhooks', async () => {
+      const deliveries: { headers: Record<string, string>; body: string }[] = [];
+      vi.stubGlobal('fetch', async (_url: string, init: RequestInit) => {
+        deliveries.push({ headers: init.headers as Record<string, string>, body: init.body as string });
+        return new Response(null);
+      });
+      try {
+        const webhooks = [{ url: 'http://localhost:9000/hooks', secret: 'whsec', events: ['error.emitted'] }];
+        const res = await adminRequest('PUT', '/admin/webhooks', webhooks);
+        expect(await res.json()).toEqual(webhooks);
+
+        expect((await chat(testAPIKey, 'no-such-model')).status).toBe(404);
+        await vi.waitFor(() => expect(deliveries).toHaveLength(1));
+        const event = JSON.parse(deliveries[0]!.body);
+        expect(event).toMatchObject({
+          type: 'error.emitted',
+          data: { path: '/v1/chat/completions', status: 404, error: { code: 'model_not_found' } },
+        });
+        expect(deliveries[0]!.headers['x-teenytiny-signature']).toBe(
+          await signature('whsec', event.created, deliveries[0]!.body),
+        );
+
+        expect((await adminRequest('PUT', '/admin/webhooks', [{ url: 'not a url', secret: 'whsec' }])).status).toBe(400);
+      } finally {
+        await adminRequest('PUT', '/admin/webhooks', []);
+        vi.unstubAllGlobals();
+      }
+    });
+
+    it('should refuse keys other than the server key', async () => {
+      const created = await (await adminRequest('POST', '/admin/keys')).json();
+      const res = await adminRequest('GET', '/admin/models', undefined, created.key);
+
+      expect(res.status).toBe(403);
+      expect((await res.json()).error.code).toBe('admin_required');
+    });
+
+    it('should reject a body that is not an object with 400', async () => {
+      for (const [method, path] of [
+        ['POST', '/admin/keys'],
+        ['PUT', '/admin/rate-limit'],
+        ['PUT', `/admin/quotas/${testAPIKey}`],
+        ['POST', '/admin/usage/reset'],
+        ['POST', '/admin/cassettes/anything/replay'],
+      ] as const) {
+        const res = await adminRequest(method, path, null);
+        expect(res.status, path).toBe(400);
+        expect((await res.json()).error.message).toBe('Request body must be a JSON object');
+      }
+    });
+  });
+
+  describe('Request Log', () => {
+    const get = (path: string, key = testAPIKey) =>
+      app.request(path, { headers: { 'Authorization': `Bearer ${key}` } });
+
+    const chat = (key: string, body: Record<string, unknown>) =>
+      app.request('/v1/chat/completions', {
+        method: 'POST',
+        headers: {
+          'Authorization': `Bearer ${key}`,
+          'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({ model: 'echo', messages: [{ role: 'user', content: 'Hi' }], ...body }),
+      });
+
+    it('should show each key the requests it made', async () => {
+      const { key } = await (await app.request('/site/new-key', { method: 'POST' })).json();
+      await chat(key, { temperature: 0.25 });
+      await chat(testAPIKey, { temperature: 0.75 });
+
+      const own = await (await get('/admin/requests?limit=1', key)).json();
+      expect(own.data).toHaveLength(1);
+      expect(own.data[0]).toMatchObject({
+        api_key: key,
+        path: '/v1/chat/completions',
+        model: 'echo',
+        status: 200,
+        request_body: { temperature: 0.25 },
+      });
+
+      const all = await (await get('/admin/requests?model=echo')).json();
+      expect(all.data[0].request_body.temperature).toBe(0.75);
+      const filtered = await (await get(`/admin/requests?key=${key}`)).json();
+      expect(filtered.data.map((entry: { api_key: string }) => entry.api_key)).toEqual([key]);
+    });
+
+    it('should keep bodies only for chosen keys, redacted', async () => {
+      const { key: chosen } = await (await app.request('/site/new-key', { method: 'POST' })).json();
+      const { key: other } = await (await app.request('/site/new-key', { method: 'POST' })).json();
+      const configure = (settings: unknown) =>
+        app.request('/admin/capture', {
+          method: 'PUT',
+          headers: { 'Authorization': `Bearer ${testAPIKey}`, 'Content-Type': 'application/json' },
+          body: JSON.stringify(settings),
+        });
+
+      const res = await configure({ keys: [chosen], redact: ['\\d{4}-\\d{4}-\\d{4}-\\d{4}'] });
+      expect(await res.json()).toEqual({ keys: [chosen], redact: ['\\d{4}-\\d{4}-\\d{4}-\\d{4}'] });
+      const card = { messages: [{ role: 'user', content: 'Charge 4111-1111-1111-1111 please' }] };
+      await chat(chosen, card);
+      await chat(other, card);
+      await configure({ keys: null, redact: [] });
+
+      const [kept] = (await (await get(`/admin/requests?key=${chosen}`)).json()).data;
+      expect(kept.request_body.messages[0].content).toBe('Charge [REDACTED] please');
+      expect(kept.response_body.choices[0].message.content).toBe('Charge [REDACTED] please');
+      const [withheld] = (await (await get(`/admin/requests?key=${other}`)).json()).data;
+      expect(withheld).toMatchObject({ model: 'echo', status: 200, request_body: null, response_body: null });
+
+      expect((await configure({ redact: ['('] })).status).toBe(400);
+      expect((await get('/admin/capture', chosen)).status).toBe(403);
+    });
+
+    it('should reject invalid filters', async () => {
+      const res = await get('/admin/requests?status=teapot');
+      expect(res.status).toBe(400);
+      expect((await res.json()).error.param).toBe('status');
+    });
+  });
+
+  describe('Usage Metering', () => {
+    const get = (path: string, key = testAPIKey) =>
+      app.request(path, { headers: { 'Authorization': `Bearer ${key}` } });
+
+    const chat = (key: string, body: Record<string, unknown>) =>
+      app.request('/v1/chat/completions', {
+        method: 'POST',
+        headers: {
+          'Authorization': `Bearer ${key}`,
+          'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({ model: 'echo', messages: [{ role: 'user', content: 'Hello, world!' }], ...body }),
+      });
+
+    it('should meter every request a key makes', async () => {
+      const { key } = await (await app.request('/site/new-key', { method: 'POST' })).json();
+      for (let i = 0; i < 3; i++) {
+        await (await chat(key, {})).json();
+      }
+      await (await chat(key, { stream: true })).text();
+      await (await chat(key, { model: 'eliza' })).json();
+
+      const usage = await (await get('/admin/usage', key)).json();
+      expect(usage.bucket).toBe('day');
+      const echo = usage.data.find((bucket: { model: string }) => bucket.model === 'echo');
+      expect(echo).toMatchObject({ api_key: key, requests: 4, prompt_tokens: 44, completion_tokens: 16, total_tokens: 60 });
+      expect(usage.totals.requests).toBe(5);
+
+      const filtered = await (await get(`/admin/usage?key=${key}&model=eliza&bucket=minute`)).json();
+      expect(filtered.data).toHaveLength(1);
+      expect(filtered.totals.requests).toBe(1);
+    });
+
+    it('should meter requests outside OpenAI\'s endpoints too', async () => {
+      const { key } = await (await app.request('/site/new-key', { method: 'POST' })).json();
+      const post = (path: string, body: unknown) =>
+        app.request(path, {
+          method: 'POST',
+          headers: { 'Authorization': `Bearer ${key}`, 'Content-Type': 'application/json' },
+          body: JSON.stringify(body),
+        });
+
+      await (await post('/api/chat', { model: 'echo', stream: false, messages: [{ role: 'user', content: 'Hello' }] })).json();
+      await (await post('/api/generate', { model: 'echo', prompt: 'Hello', stream: false })).json();
+      await (await post('/v1beta/models/echo:generateContent', { contents: [{ role: 'user', parts: [{ text: 'Hello' }] }] })).json();
+      await (await post(`/session/usage-${key}/say`, { message: 'Hello' })).json();
+
+      const usage = await (await get('/admin/usage', key)).json();
+      expect(usage.data).toHaveLength(1);
+      expect(usage.data[0]).toMatchObject({ api_key: key, model: 'echo', requests: 4 });
+      expect(usage.totals.total_tokens).toBeGreaterThan(0);
+    });
+
+    it('should forget a key\'s usage when it is reset', async () => {
+      const { key } = await (await app.request('/site/new-key', { method: 'POST' })).json();
+      await (await chat(key, {})).json();
+
+      await app.request('/admin/usage/reset', {
+        method: 'POST',
+        headers: { 'Authorization': `Bearer ${testAPIKey}`, 'Content-Type': 'application/json' },
+        body: JSON.stringify({ key }),
+      });
+
+      expect((await (await get('/admin/usage', key)).json()).totals.requests).toBe(0);
+    });
+
+    it('should reject an unknown bucket', async () => {
+      const res = await get('/admin/usage?bucket=week');
+      expect(res.status).toBe(400);
+      expect((await res.json()).error.param).toBe('bucket');
+    });
+  });
+
+  describe('State Persistence', () => {
+    const request = (server: ReturnType<typeof createApp>, method: string, path: string, body?: unknown, key = testAPIKey) =>
+      server.request(path, {
+        method,
+        headers: { 'Authorization': `Bearer ${key}`, 'Content-Type': 'application/json' },
+        ...(body === undefined ? {} : { body: JSON.stringify(body) }),
+      });
+
+    // Leaves keys, usage, a file and a batch behind, as a shared test
+    // environment would have them before a redeploy
+    async function populate(state: StateStore) {
+      const before = createApp({ auth: { apiKey: testAPIKey }, batches: { stepMs: 0 }, state });
+      const { key } = await (await request(before, 'POST', '/admin/keys', { models: ['echo'] })).json();
+      const { key: revoked } = await (await request(before, 'POST', '/admin/keys')).json();
+      await request(before, 'DELETE', `/admin/keys/${revoked}`);
+      await request(before, 'PUT', `/admin/quotas/${key}`, { token_budget: 1000000 });
+
+      const chat = await request(before, 'POST', '/v1/chat/completions', {
+        model: 'echo',
+        messages: [{ role: 'user', content: 'Remember me' }],
+      }, key);
+      expect(chat.status).toBe(200);
+      await (await request(before, 'POST', '/v1/chat/completions', {
+        model: 'echo',
+        stream: true,
+        messages: [{ role: 'user', content: 'Remember me too' }],
+      }, key)).text();
+
+      const form = new FormData();
+      form.append('purpose', 'batch');
+      const line = { custom_id: 'kept', method: 'POST', url: '/v1/chat/completions', body: { model: 'echo', messages: [{ role: 'user', content: 'Batched' }] } };
+      form.append('file', new File([`${JSON.stringify(line)}\n`], 'input.jsonl'));
+      const file = await (await before.request('/v1/files', { method: 'POST', headers: { 'Authorization': `Bearer ${key}` }, body: form })).json();
+      const batch = await (await request(before, 'POST', '/v1/batches', {
+        input_file_id: file.id,
+        endpoint: '/v1/chat/completions',
+        completion_window: '24h',
+      }, key)).json();
+      return { key, revoked, file, batch };
+    }
+
+    async function expectRestored(state: StateStore, { key, revoked, file, batch }: Awaited<ReturnType<typeof populate>>) {
+      const after = createApp({ auth: { apiKey: testAPIKey }, batches: { stepMs: 0 }, state });
+
+      const keys = await (await request(after, 'GET', '/admin/keys')).json();
+      expect(keys.keys).toContainEqual({ key, models: ['echo'] });
+      expect(keys.revoked).toContain(revoked);
+      expect((await request(after, 'GET', '/v1/models', undefined, revoked)).status).toBe(401);
+
+      const usage = await (await request(after, 'GET', '/admin/usage', undefined, key)).json();
+      expect(usage.totals.requests).toBe(2);
+      const quotas = await (await request(after, 'GET', '/admin/quotas')).json();
+      expect(quotas.data).toContainEqual({ key, token_budget: 1000000, tokens_used: usage.totals.total_tokens });
+      const requests = await (await request(after, 'GET', '/admin/requests', undefined, key)).json();
+      expect(requests.data.map((entry: { path: string }) => entry.path)).toContain('/v1/chat/completions');
+
+      const content = await request(after, 'GET', `/v1/files/${file.id}/content`, undefined, key);
+      expect(await content.text()).toContain('"custom_id":"kept"');
+      const done = await (await request(after, 'GET', `/v1/batches/${batch.id}`, undefined, key)).json();
+      expect(done).toMatchObject({ status: 'completed', request_counts: { total: 1, completed: 1 } });
+      const output = await request(after, 'GET', `/v1/files/${done.output_file_id}/content`, undefined, key);
+      expect(JSON.parse(await output.text()).response.body.choices[0].message.content).toBe('Batched');
+    }
+
+    it('should keep keys, usage, quotas, captured requests, files and batches across a restart', async () => {
+      const state = new MemoryStateStore();
+      await expectRestored(state, await populate(state));
+    });
+
+    it("should keep only the keys the admin API created", async () => {
+      const state = new MemoryStateStore();
+      const configured = { apiKey: testAPIKey, keys: [{ key: 'from-env' }] };
+      const before = createApp({ auth: configured, state, settings: { keys: [{ key: 'from-file' }] } });
+      const { key } = await (await request(before, 'POST', '/admin/keys')).json();
+
+      const after = createApp({ auth: { apiKey: testAPIKey }, state });
+      const keys = await (await request(after, 'GET', '/admin/keys')).json();
+      expect(keys.keys).toEqual([{ key }]);
+      for (const removed of ['from-env', 'from-file']) {
+        expect((await request(after, 'GET', '/v1/models', undefined, removed)).status).toBe(401);
+      }
+    });
+
+    it('should save only the parts a request changed', async () => {
+      const saved: string[] = [];
+      const state = new MemoryStateStore();
+      const server = createApp({ auth: { apiKey: testAPIKey }, state: {
+        load: (name) => state.load(name),
+        save: (name, json) => {
+          saved.push(name);
+          state.save(name, json);
+        },
+      } });
+
+      await server.request('/healthz');
+      await request(server, 'GET', '/admin/keys');
+      expect(saved).toEqual([]);
+      await request(server, 'POST', '/admin/keys');
+      expect(saved).toEqual(['keys']);
+    });
+
+    it.runIf(hasSqlite)('should keep state in a SQLite database', async () => {
+      const { mkdtempSync, rmSync } = await import('node:fs');
+      const { tmpdir } = await import('node:os');
+      const { join } = await import('node:path');
+      const { SqliteStateStore } = await import('../src/persistence/sqlite-state-store.js');
+      const dir = mkdtempSync(join(tmpdir(), 'teenytiny-state-'));
+      try {
+        const populated = await populate(new SqliteStateStore(join(dir, 'state.db')));
+        await expectRestored(new SqliteStateStore(join(dir, 'state.db')), populated);
+      } finally {
+        rmSync(dir, { recursive: true, force: true });
+      }
+    });
+  });
+
+  describe('Tenants', () => {
+    const tenants = createApp({
+      auth: {
+        apiKey: testAPIKey,
+        keys: [
+          { key: 'team-a-ci', tenant: 'team-a' },
+          { key: 'team-a-dev', tenant: 'team-a' },
+          { key: 'team-b-ci', tenant: 'team-b' },
+          { key: 'loner' },
+        ],
+      },
+    });
+
+    const request = (key: string, method: string, path: string, body?: unknown, headers: Record<string, string> = {}) =>
+      tenants.request(path, {
+        method,
+        headers: { 'Authorization': `Bearer ${key}`, 'Content-Type': 'application/json', ...headers },
+        ...(body === undefined ? {} : { body: JSON.stringify(body) }),
+      });
+
+    const chat = (key: string) =>
+      request(key, 'POST', '/v1/chat/completions', { model: 'echo', messages: [{ role: 'user', content: 'Hi' }] });
+
+    beforeAll(async () => {
+      for (const key of ['team-a-ci', 'team-a-dev', 'team-b-ci', 'loner']) {
+        expect((await chat(key)).status).toBe(200);
+      }
+    });
+
+    const keysIn = (entries: { api_key: string }[]) => [...new Set(entries.map((entry) => entry.api_key))].sort();
+
+    it("should show a tenant's keys only their tenant's requests and usage", async () => {
+      const requests = await (await request('team-a-ci', 'GET', '/admin/requests')).json();
+      expect(keysIn(requests.data)).toEqual(['team-a-ci', 'team-a-dev']);
+      const usage = await (await request('team-a-dev', 'GET', '/admin/usage')).json();
+      expect(keysIn(usage.data)).toEqual(['team-a-ci', 'team-a-dev']);
+      const narrowed = await (await request('team-a-ci', 'GET', '/admin/usage?key=team-a-dev')).json();
+      expect(keysIn(narrowed.data)).toEqual(['team-a-dev']);
+
+      // Keys without a tenant still see only their own
+      const own = await (await request('loner', 'GET', '/admin/requests')).json();
+      expect(keysIn(own.data)).toEqual(['loner']);
+    });
+
+    it("should answer another tenant's keys with 404", async () => {
+      for (const path of ['/admin/requests?key=team-b-ci', '/admin/usage?key=team-b-ci']) {
+        const res = await request('team-a-ci', 'GET', path);
+        expect(res.status, path).toBe(404);
+        expect((await res.json()).error.type).toBe('not_found_error');
+      }
+      const asTeamA = { 'X-TeenyTiny-Tenant': 'team-a' };
+      const revoked = await request(testAPIKey, 'DELETE', '/admin/keys/team-b-ci', undefined, asTeamA);
+      expect(revoked.status).toBe(404);
+      expect((await chat('team-b-ci')).status).toBe(200);
+    });
+
+    it("should keep conversations and cassettes to their tenant", async () => {
+      const remember = (key: string) =>
+        request(key, 'POST', '/v1/chat/completions', {
+          model: 'memory', user: `${key}-thread`, messages: [{ role: 'user', content: 'Hi' }],
+        });
+      for (const key of ['team-a-ci', 'team-b-ci']) {
+        expect((await remember(key)).status).toBe(200);
+      }
+      const conversations = await (await request('team-a-dev', 'GET', '/admin/conversations')).json();
+      expect(conversations.data.map((entry: { key: string }) => entry.key)).toEqual(['team-a-ci']);
+      const elsewhere = await request('team-a-dev', 'DELETE', '/admin/conversations/team-b-ci-thread?key=team-b-ci');
+      expect(elsewhere.status).toBe(404);
+
+      expect((await request('team-a-ci', 'POST', '/admin/cassettes/team-a-tape/record')).status).toBe(200);
+      expect((await request('team-a-ci', 'POST', '/admin/cassettes/stop')).status).toBe(200);
+      const listed = async (key: string) => (await (await request(key, 'GET', '/admin/cassettes')).json()).data;
+      expect(await listed('team-a-dev')).toContain('team-a-tape');
+      expect(await listed('team-b-ci')).not.toContain('team-a-tape');
+      expect(await listed('loner')).not.toContain('team-a-tape');
+      expect((await request('team-b-ci', 'GET', '/admin/cassettes/team-a-tape')).status).toBe(404);
+      expect((await request('team-b-ci', 'POST', '/admin/cassettes/team-a-tape/replay')).status).toBe(404);
+      expect((await request('team-b-ci', 'POST', '/admin/cassettes/team-a-tape/record')).status).toBe(409);
+      expect((await request('team-a-dev', 'POST', '/admin/cassettes/team-a-tape/replay')).status).toBe(200);
+      expect((await request('team-a-dev', 'POST', '/admin/cassettes/stop')).status).toBe(200);
+    });
+
+    it("should keep tenants out of each other's tenant and the server's settings", async () => {
+      const res = await request('team-a-ci', 'GET', '/admin/requests', undefined, { 'X-TeenyTiny-Tenant': 'team-b' });
+      expect(res.status).toBe(403);
+      expect((await res.json()).error.code).toBe('tenant_override_not_allowed');
+
+      const created = await request('team-a-ci', 'POST', '/admin/keys', { tenant: 'team-b' });
+      expect(created.status).toBe(403);
+      for (const path of ['/admin/faults', '/admin/capture', '/admin/webhooks']) {
+        const settings = await request('team-a-ci', 'GET', path);
+        expect(settings.status, path).toBe(403);
+        expect((await settings.json()).error.code).toBe('admin_required');
+      }
+      expect((await request('loner', 'GET', '/admin/keys')).status).toBe(403);
+    });
+
+    it("should leave changing keys, quotas and usage to the server's key", async () => {
+      const attempts: [string, string, unknown?][] = [
+        ['POST', '/admin/keys', {}],
+        ['DELETE', '/admin/keys/team-b-ci'],
+        ['PUT', '/admin/quotas/team-b-ci', { token_budget: 1000000 }],
+        ['POST', '/admin/usage/reset', { key: 'team-b-ci' }],
+        ['POST', '/admin/usage/reset', {}],
+      ];
+      for (const [method, path, body] of attempts) {
+        const res = await request('team-b-ci', method, path, body);
+        expect(res.status, `${method} ${path}`).toBe(403);
+        expect((await res.json()).error.code).toBe('admin_required');
+      }
+    });
+
+    it("should show a tenant the keys the server's key manages for it", async () => {
+      const asTeamB = { 'X-TeenyTiny-Tenant': 'team-b' };
+      const created = await (await request(testAPIKey, 'POST', '/admin/keys', { models: ['echo'] }, asTeamB)).json();
+      expect(created).toMatchObject({ models: ['echo'], tenant: 'team-b' });
+      expect((await chat(created.key)).status).toBe(200);
+
+      const keys = await (await request('team-b-ci', 'GET', '/admin/keys')).json();
+      expect(keys.keys.map((entry: { key: string }) => entry.key).sort()).toEqual([created.key, 'team-b-ci'].sort());
+
+      expect((await request(testAPIKey, 'DELETE', `/admin/keys/${created.key}`, undefined, asTeamB)).status).toBe(200);
+      expect((await chat(created.key)).status).toBe(401);
+      const after = await (await request('team-b-ci', 'GET', '/admin/keys')).json();
+      expect(after).toEqual({ keys: [{ key: 'team-b-ci', tenant: 'team-b' }], revoked: [created.key] });
+      // A revoked key's requests are still its tenant's to look at
+      const requests = await (await request('team-b-ci', 'GET', `/admin/requests?key=${created.key}`)).json();
+      expect(requests.data).toHaveLength(1);
+    });
+
+    it('should let the server\'s key act for any tenant with the tenant header', async () => {
+      const all = await (await request(testAPIKey, 'GET', '/admin/requests')).json();
+      expect(keysIn(all.data)).toEqual(expect.arrayContaining(['loner', 'team-a-ci', 'team-b-ci']));
+
+      const asTeamA = { 'X-TeenyTiny-Tenant': 'team-a' };
+      const requests = await (await request(testAPIKey, 'GET', '/admin/requests', undefined, asTeamA)).json();
+      expect(keysIn(requests.data)).toEqual(['team-a-ci', 'team-a-dev']);
+      const missing = await request(testAPIKey, 'GET', '/admin/usage?key=loner', undefined, asTeamA);
+      expect(missing.status).toBe(404);
+
+      const created = await (await request(testAPIKey, 'POST', '/admin/keys', {}, asTeamA)).json();
+      expect(created.tenant).toBe('team-a');
+      const keys = await (await request('team-a-dev', 'GET', '/admin/keys')).json();
+      expect(keys.keys.map((entry: { key: string }) => entry.key)).toContain(created.key);
+    });
+  });
+
+  describe('Quotas', () => {
+    const chat = (key: string) =>
+      app.request('/v1/chat/completions', {
+        method: 'POST',
+        headers: {
+          'Authorization': `Bearer ${key}`,
+          'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({ model: 'echo', messages: [{ role: 'user', content: 'Hello, world!' }] }),
+      });
+
+    it('should answer insufficient_quota once the budget is spent', async () => {
+      const budgeted = createApp({ auth: { apiKey: testAPIKey }, quotas: { [testAPIKey]: 40 } });
+      const statuses: number[] = [];
+      for (let i = 0; i < 4; i++) {
+        const res = await budgeted.request('/v1/chat/completions', {
+          method: 'POST',
+          headers: {
+            'Authorization': `Bearer ${testAPIKey}`,
+            'Content-Type': 'application/json',
+          },
+          body: JSON.stringify({ model: 'echo', messages: [{ role: 'user', content: 'Hello, world!' }] }),
+        });
+        statuses.push(res.status);
+        if (res.status === 429) {
+          expect(await res.json()).toEqual({
+            error: expect.objectContaining({ type: 'insufficient_quota', param: null, code: 'insufficient_quota' }),
+          });
+        }
+      }
+
+      // 15 tokens a request: the third crosses 40, so the fourth is refused
+      expect(statuses).toEqual([200, 200, 200, 429]);
+    });
+
+    it('should set budgets and reset them through the admin API', async () => {
+      const { key } = await (await app.request('/site/new-key', { method: 'POST' })).json();
+      const admin = (method: string, path: string, body: unknown) =>
+        app.request(path, {
+          method,
+          headers: { 'Authorization': `Bearer ${testAPIKey}`, 'Content-Type': 'application/json' },
+          body: JSON.stringify(body),
+        });
+
+      expect((await admin('PUT', `/admin/quotas/${key}`, { token_budget: 1 })).status).toBe(200);
+      expect((await chat(key)).status).toBe(200);
+      expect((await chat(key)).status).toBe(429);
+
+      const listed = await (await app.request('/admin/quotas', { headers: { 'Authorization': `Bearer ${testAPIKey}` } })).json();
+      expect(listed.data).toContainEqual({ key, token_budget: 1, tokens_used: 15 });
+
+      await admin('POST', '/admin/usage/reset', { key });
+      expect((await chat(key)).status).toBe(200);
+
+      expect((await admin('PUT', `/admin/quotas/${key}`, { token_budget: -1 })).status).toBe(400);
+      expect((await admin('PUT', `/admin/quotas/${key}`, { token_budget: null })).status).toBe(200);
+      expect((await chat(key)).status).toBe(200);
+    });
+
+    it('should refuse a spent key on every route that generates', async () => {
+      const budgeted = createApp({ auth: { apiKey: testAPIKey }, quotas: { [testAPIKey]: 1 } });
+      const post = (path: string, body: unknown) =>
+        budgeted.request(path, {
+          method: 'POST',
+          headers: { 'Authorization': `Bearer ${testAPIKey}`, 'Content-Type': 'application/json' },
+          body: JSON.stringify(body),
+        });
+      const ollama = { model: 'echo', stream: false, messages: [{ role: 'user', content: 'Hello' }] };
+      const gemini = { contents: [{ role: 'user', parts: [{ text: 'Hello' }] }] };
+
+      // Spent on Ollama's endpoint, so refused there and everywhere else
+      expect((await post('/api/chat', ollama)).status).toBe(200);
+      for (const [path, body] of [
+        ['/api/chat', ollama],
+        ['/api/generate', { model: 'echo', prompt: 'Hello', stream: false }],
+        ['/v1beta/models/echo:generateContent', gemini],
+        ['/session/quota/say', { message: 'Hello' }],
+        ['/v1/chat/completions', { model: 'echo', messages: [{ role: 'user', content: 'Hello' }] }],
+      ] as const) {
+        const res = await post(path, body);
+        expect(res.status, path).toBe(429);
+      }
+      const error = await (await post('/v1beta/models/echo:generateContent', gemini)).json();
+      expect(error.error).toMatchObject({ code: 429, status: 'RESOURCE_EXHAUSTED' });
+    });
+  });
+
+  describe('Request Logging', () => {
+    const logged = (level: LogLevel = 'info') => {
+      const lines: string[] = [];
+      const logApp = createApp({ auth: { apiKey: testAPIKey }, logger: new Logger(level, line => lines.push(line)) });
+      const chat = (body: object) =>
+        logApp.request('/v1/chat/completions', {
+          method: 'POST',
+          headers: { 'Authorization': `Bearer ${testAPIKey}`, 'Content-Type': 'application/json' },
+          body: JSON.stringify({ messages: [{ role: 'user', content: 'Hello, world!' }], ...body }),
+        });
+      const completed = () =>
+        lines.map(line => JSON.parse(line)).filter(entry => entry.message === 'Request completed');
+      return { lines, chat, completed };
+    };
+
+    it('should log one parseable line per request with its usage', async () => {
+      const { lines, chat, completed } = logged();
+
+      const res = await chat({ model: 'echo' });
+      const { usage } = await res.json();
+
+      expect(lines).toHaveLength(1);
+      expect(completed()).toEqual([{
+        timestamp: expect.any(String),
+        level: 'info',
+        message: 'Request completed',
+        request_id: res.headers.get('X-Request-ID'),
+        method: 'POST',
+        path: '/v1/chat/completions',
+        model: 'echo',
+        status: 200,
+        duration_ms: expect.any(Number),
+        prompt_tokens: usage.prompt_tokens,
+        completion_tokens: usage.completion_tokens,
+        total_tokens: usage.total_tokens,
+        key_id: expect.stringMatching(/^[0-9a-f]{16}$/),
+      }]);
+      expect(lines[0]).not.toContain(testAPIKey);
+    });
+
+    it('should log a stream once it ends, with the usage of its last chunk', async () => {
+      const { chat, completed } = logged();
+
+      const res = await chat({ model: 'echo', stream: true });
+      expect(completed()).toEqual([]);
+      const text = await res.text();
+
+      const usage = text
+        .split('\n')
+        .filter(line => line.startsWith('data: {'))
+        .map(line => JSON.parse(line.slice('data: '.length)))
+        .find(chunk => chunk.usage)?.usage;
+      expect(completed()).toEqual([
+        expect.objectContaining({ status: 200, model: 'echo', total_tokens: usage.total_tokens }),
+      ]);
+    });
+
+    it('should log failed requests without usage', async () => {
+      const { chat, completed } = logged();
+
+      await chat({ model: 'no-such-model' });
+
+      expect(completed()).toEqual([
+        expect.objectContaining({ status: 404, model: 'no-such-model', total_tokens: null }),
+      ]);
+    });
+
+    it('should write what each endpoint did at debug level only', async () => {
+      const info = logged('info');
+      const debug = logged('debug');
+
+      await info.chat({ model: 'echo' });
+      await debug.chat({ model: 'echo' });
+
+      expect(info.lines.map(line => JSON.parse(line).message)).toEqual(['Request completed']);
+      expect(debug.lines.map(line => JSON.parse(line).message)).toEqual([
+        'Request started',
+        'Chat completion request',
+        'Chat completion completed',
+        'Request completed',
+      ]);
+      expect(logged('warn').lines).toEqual([]);
+    });
+  });
+
+  describe('Config Reload', () => {
+    it('should apply a changed config file without a restart', async () => {
+      let file = 'models = ["echo"]\n\n[rate_limit]\nrequests_per_minute = 3\n';
+      const configured = createApp({
+        auth: { apiKey: testAPIKey },
+        settings: parseConfigFile(file, 'teenytiny.toml').settings,
+        reload: () => parseConfigFile(file, 'teenytiny.toml').settings,
+      });
+      const request = (path: string, init: RequestInit = {}) =>
+        configured.request(path, {
+          ...init,
+          headers: { 'Authorization': `Bearer ${testAPIKey}`, 'Content-Type': 'application/json' },
+        });
+      const chat = (model: string) =>
+        request('/v1/chat/completions', {
+          method: 'POST',
+          body: JSON.stringify({ model, messages: [{ role: 'user', content: 'Hello' }] }),
+        });
+      const models = async () => (await (await request('/v1/models')).json()).data.map((model: any) => model.id);
+
+      expect(await models()).toEqual(['echo']);
+      expect((await chat('eliza')).status).toBe(404);
+      expect((await chat('gpt-4o-mini')).status).toBe(200);
+      // The fourth request this minute
+      expect((await chat('echo')).status).toBe(429);
+
+      file = 'models = ["echo", "eliza"]\n\n[rate_limit]\nrequests_per_minute = 100\n';
+      const reloaded = await request('/admin/reload', { method: 'POST' });
+      expect(reloaded.status).toBe(200);
+      expect(await reloaded.json()).toEqual({ reloaded: true });
+
+      expect(await models()).toEqual(['echo', 'eliza']);
+      expect((await chat('eliza')).status).toBe(200);
+      expect(await (await request('/admin/rate-limit')).json()).toEqual({ requests_per_minute: 100 });
+    });
+
+    it('should keep the old settings when the new file is invalid', async () => {
+      let file = '[faults]\nfailure_rate = 0.5\n';
+      const configured = createApp({
+        auth: { apiKey: testAPIKey },
+        settings: parseConfigFile(file, 'teenytiny.toml').settings,
+        reload: () => parseConfigFile(file, 'teenytiny.toml').settings,
+      });
+      const reload = () =>
+        configured.request('/admin/reload', { method: 'POST', headers: { 'Authorization': `Bearer ${testAPIKey}` } });
+
+      file = '[faults]\nfailure_rate = 5\n';
+      const res = await reload();
+      expect(res.status).toBe(400);
+      expect((await res.json()).error.param).toBe('failure_rate');
+
+      file = 'models = ["no-such-model"]\n';
+      expect((await reload()).status).toBe(400);
+
+      const faults = await configured.request('/admin/faults', { headers: { 'Authorization': `Bearer ${testAPIKey}` } });
+      expect((await faults.json()).failure_rate).toBe(0.5);
+    });
+
+    it('should only let the server key reload, and only with a config file', async () => {
+      const { key } = await (await app.request('/site/new-key', { method: 'POST' })).json();
+
+      const forbidden = await app.request('/admin/reload', { method: 'POST', headers: { 'Authorization': `Bearer ${key}` } });
+      expect(forbidden.status).toBe(403);
+
+      const res = await app.request('/admin/reload', { method: 'POST', headers: { 'Authorization': `Bearer ${testAPIKey}` } });
+      expect(res.status).toBe(400);
+      expect((await res.json()).error.message).toContain('without a config file');
+    });
+  });
+
+  describe('Router Models', () => {
+    const file = `
+models = ["router", "echo"]
+
+[[routers.agent.routes]]
+match = "weather"
+flags = "i"
+reply = "It's sunny."
+
+[[routers.agent.routes]]
+match = "^explain"
+model = "template"
+`;
+    const configured = createApp({
+      auth: { apiKey: testAPIKey },
+      settings: parseConfigFile(file, 'teenytiny.toml').settings,
+    });
+    const chat = (model: string, content: string) =>
+      configured.request('/v1/chat/completions', {
+        method: 'POST',
+        headers: { 'Authorization': `Bearer ${testAPIKey}`, 'Content-Type': 'application/json' },
+        body: JSON.stringify({ model, messages: [{ role: 'user', content }] }),
+      });
+    const reply = async (content: string) => {
+      const res = await chat('router:agent', content);
+      expect(res.status).toBe(200);
+      const data = await res.json();
+      expect(data.model).toBe('router:agent');
+      return data.choices[0].message.content;
+    };
+
+    it('should route by the first matching regex and fall through to echo', async () => {
+      expect(await reply('What is the WEATHER like?')).toBe("It's sunny.");
+      expect(await reply('explain the weather')).toBe("It's sunny.");
+      expect(await reply('explain yourself')).toBe(
+        'You said "explain yourself" in a conversation of 1 messages with router:agent.',
+      );
+      expect(await reply('Hello')).toBe('Hello');
+    });
+
+    it('should not serve routers the config file does not name', async () => {
+      expect((await chat('router:other', 'Hello')).status).toBe(404);
+    });
+
+    it('should reject routes to unknown models', () => {
+      const settings = parseConfigFile(
+        '[[routers.agent.routes]]\nmatch = "."\nmodel = "no-such-model"\n',
+        'teenytiny.toml',
+      ).settings;
+      expect(() => createApp({ auth: { apiKey: testAPIKey }, settings })).toThrow(/no-such-model/);
+    });
+  });
+
+  describe('Draining', () => {
+    it('should let streams in flight finish while turning new requests away', async () => {
+      const draining = createApp({ auth: { apiKey: testAPIKey } });
+      const request = (path: string, init: RequestInit = {}) =>
+        draining.request(path, {
+          ...init,
+          headers: { 'Authorization': `Bearer ${testAPIKey}`, 'Content-Type': 'application/json' },
+        });
+      const chat = (stream: boolean) =>
+        request('/v1/chat/completions', {
+          method: 'POST',
+          body: JSON.stringify({ model: 'slow:20', stream, messages: [{ role: 'user', content: 'one two three' }] }),
+        });
+
+      const streamed = await chat(true);
+      expect(streamed.status).toBe(200);
+      expect((await request('/admin/drain', { method: 'POST', body: JSON.stringify({ grace_ms: 5000 }) })).status).toBe(200);
+
+      const refused = await chat(false);
+      expect(refused.status).toBe(503);
+      expect(refused.headers.get('Retry-After')).toBe('1');
+      expect((await refused.json()).error.type).toBe('overloaded_error');
+      expect((await request('/healthz')).status).toBe(200);
+      const ready = await request('/readyz');
+      expect(ready.status).toBe(503);
+      expect((await ready.json()).status).toBe('draining');
+      expect((await (await request('/admin/drain')).json()).in_flight).toBe(1);
+
+      expect(await streamed.text()).toContain('data: [DONE]');
+      expect((await (await request('/admin/drain')).json()).in_flight).toBe(0);
+    });
+
+    it('should reject a bad grace period', async () => {
+      const res = await app.request('/admin/drain', {
+        method: 'POST',
+        headers: { 'Authorization': `Bearer ${testAPIKey}`, 'Content-Type': 'application/json' },
+        body: JSON.stringify({ grace_ms: -1 }),
+      });
+      expect(res.status).toBe(400);
+    });
+  });
+
+  describe('Token Usage', () => {
+    it('should count prompt tokens with chat overhead', async () => {
+      const res = await app.request('/v1/chat/completions', {
+        method: 'POST',
+        headers: {
+          'Authorization': `Bearer ${testAPIKey}`,
+          'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({ model: 'echo', messages: [{ role: 'user', content: 'Hello, world!' }] }),
+      });
+
+      // 3 to prime the reply, 3 around the message, 1 for the role, 4 for the content
+      expect((await res.json()).usage).toEqual({
+        prompt_tokens: 11,
+        completion_tokens: 4,
+        total_tokens: 15,
+      });
+    });
+
+    it('should return n choices and count each one', async () => {
+      const res = await app.request('/v1/chat/completions', {
+        method: 'POST',
+        headers: {
+          'Authorization': `Bearer ${testAPIKey}`,
+          'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({ model: 'echo', n: 3, messages: [{ role: 'user', content: 'Hello, world!' }] }),
+      });
+
+      const data = await res.json();
+      expect(data.choices.map((choice: any) => choice.index)).toEqual([0, 1, 2]);
+      expect(data.choices.every((choice: any) => choice.message.content === 'Hello, world!')).toBe(true);
+      expect(data.usage).toEqual({
+        prompt_tokens: 11,
+        completion_tokens: 12,
+        total_tokens: 23,
+      });
+    });
+
+    it('should stream n choices with usage for all of them', async () => {
+      const res = await app.request('/v1/chat/completions', {
+        method: 'POST',
+        headers: {
+          'Authorization': `Bearer ${testAPIKey}`,
+          'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({ model: 'echo', n: 2, stream: true, messages: [{ role: 'user', content: 'Hello, world!' }] }),
+      });
+
+      const chunks = (await res.text()).split('\n')
+        .filter(line => line.startsWith('data: ') && line !== 'data: [DONE]')
+        .map(line => JSON.parse(line.replace('data: ', '')));
+      const finished = chunks.filter(chunk => chunk.choices[0]?.finish_reason);
+      expect(finished.map(chunk => chunk.choices[0].index)).toEqual([0, 1]);
+      expect(chunks.filter(chunk => chunk.usage)).toHaveLength(1);
+      expect(chunks[chunks.length - 1].usage).toEqual({
+        prompt_tokens: 11,
+        completion_tokens: 8,
+        total_tokens: 19,
+      });
+    });
+  });
+
+  describe('Service Tiers', () => {
+    it('should delay each tier as configured', async () => {
+      const tiered = createApp({
+        auth: { apiKey: testAPIKey },
+        serviceTiers: { flex: { type: 'fixed', ms: 150 } },
+      });
+      const timed = async (tier: string) => {
+        const started = Date.now();
+        const res = await tiered.request('/v1/chat/completions', {
+          method: 'POST',
+          headers: {
+            'Authorization': `Bearer ${testAPIKey}`,
+            'Content-Type': 'application/json',
+          },
+          body: JSON.stringify({ model: 'echo', service_tier: tier, messages: [{ role: 'user', content: 'Hello' }] }),
+        });
+        expect((await res.json()).service_tier).toBe(tier);
+        return Date.now() - started;
+      };
+
+      expect(await timed('flex')).toBeGreaterThanOrEqual(150);
+      expect(await timed('default')).toBeLessThan(150);
+    });
+  });
+
+  describe('Organization and Project Headers', () => {
+    const chat = (target: ReturnType<typeof createApp>, headers: Record<string, string>) =>
+      target.request('/v1/chat/completions', {
+        method: 'POST',
+        headers: {
+          'Authorization': `Bearer ${testAPIKey}`,
+          'Content-Type': 'application/json',
+          ...headers,
+        },
+        body: JSON.stringify({ model: 'echo', messages: [{ role: 'user', content: 'Hi' }] }),
+      });
+
+    it('should accept any organization and project by default, and log them', async () => {
+      const res = await chat(app, { 'OpenAI-Organization': 'org-anything', 'OpenAI-Project': 'proj_anything' });
+      expect(res.status).toBe(200);
+
+      const log = await (await app.request('/admin/requests?limit=1', {
+        headers: { 'Authorization': `Bearer ${testAPIKey}` },
+      })).json();
+      expect(log.data[0].request_headers).toMatchObject({
+        'openai-organization': 'org-anything',
+        'openai-project': 'proj_anything',
+      });
+    });
+
+    it('should reject unknown organizations and projects when configured', async () => {
+      const strict = createApp({
+        auth: { apiKey: testAPIKey, organizations: ['org-teenytiny'], projects: ['proj_teenytiny'] },
+      });
+
+      expect((await chat(strict, { 'OpenAI-Organization': 'org-teenytiny', 'OpenAI-Project': 'proj_teenytiny' })).status).toBe(200);
+      expect((await chat(strict, {})).status).toBe(200);
+
+      const org = await chat(strict, { 'OpenAI-Organization': 'org-other' });
+      expect(org.status).toBe(401);
+      expect(await org.json()).toEqual({
+        error: {
+          message: 'OpenAI-Organization header should match organization for API key',
+          type: 'invalid_request_error',
+          param: null,
+          code: 'mismatched_organization',
+        },
+      });
+
+      const project = await chat(strict, { 'OpenAI-Project': 'proj_other' });
+      expect(project.status).toBe(401);
+      expect((await project.json()).error.code).toBe('mismatched_project');
+    });
+  });
+
+  describe('Request IDs and Idempotency', () => {
+    const chat = (headers: Record<string, string>, content = 'Hello') =>
+      app.request('/v1/chat/completions', {
+        method: 'POST',
+        headers: {
+          'Authorization': `Bearer ${testAPIKey}`,
+          'Content-Type': 'application/json',
+          ...headers,
+        },
+        body: JSON.stringify({ model: 'eliza', messages: [{ role: 'user', content }] }),
+      });
+
+    it('should pass through the caller\'s request ID', async () => {
+      const res = await chat({ 'X-Request-ID': 'client-req-584' });
+      expect(res.headers.get('X-Request-ID')).toBe('client-req-584');
+
+      const generated = await chat({ 'X-Request-ID': 'not a valid id' });
+      expect(generated.headers.get('X-Request-ID')).not.toBe('not a valid id');
+    });
+
+    it('should replay the response to a repeated idempotency key', async () => {
+      const first = await chat({ 'Idempotency-Key': 'integration-1' });
+      const body = await first.json();
+      const repeat = await chat({ 'Idempotency-Key': 'integration-1' });
+
+      expect(await repeat.json()).toEqual(body);
+      expect(repeat.headers.get('X-Request-ID')).toBe(first.headers.get('X-Request-ID'));
+      expect(repeat.headers.get('Idempotent-Replayed')).toBe('true');
+
+      const fresh = await chat({ 'Idempotency-Key': 'integration-2' });
+      expect((await fresh.json()).id).not.toBe(body.id);
+    });
+  });
+
+  describe('CORS', () => {
+    it('should handle OPTIONS requests', async () => {
+      const res = await app.request('/v1/chat/completions', {
+        method: 'OPTIONS',
+      });
+
+      expect(res.status).toBe(200);
+      expect(res.headers.get('access-control-allow-origin')).toBe('*');
+      expect(res.headers.get('access-control-allow-methods')).toContain('POST');
+      expect(res.headers.get('access-control-allow-headers')).toContain('Authorization');
+    });
+  });
+
+  describe('Error Handling', () => {
+    it('should handle 404 for unknown routes', async () => {
+      const res = await app.request('/unknown-route', {
+        headers: {
+          'Authorization': `Bearer ${testAPIKey}`,
+        },
+      });
+
+      expect(res.status).toBe(404);
+      const data = await res.json();
+      expect(data.error.type).toBe('not_found_error');
+    });
+
+    it('should always include param and code in error envelopes', async () => {
+      const res = await app.request('/v1/models', {
+        headers: {
+          'Authorization': 'Bearer invalid-key',
+        },
+      });
+
+      const data = await res.json();
+      expect(data.error).toEqual({
+        message: 'Invalid API key',
+        type: 'authentication_error',
+        param: null,
+        code: 'invalid_api_key',
+      });
+    });
+
+    it('should reject bodies over the configured limit with 413', async () => {
+      const tinyLimit = createApp({
+        auth: { apiKey: testAPIKey },
+        limits: { maxBodyBytes: 64 },
+      });
+
+      const res = await tinyLimit.request('/v1/chat/completions', {
+        method: 'POST',
+        headers: {
+          'Authorization': `Bearer ${testAPIKey}`,
+          'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({
+          model: 'echo',
+          messages: [{ role: 'user', content: 'x'.repeat(100) }],
+        }),
+      });
+
+      expect(res.status).toBe(413);
+      const data = await res.json();
+      expect(data.error.type).toBe('invalid_request_error');
+      expect(data.error.code).toBe('request_too_large');
+    });
+
+    it('should return 429 with rate limit headers once the budget is spent', async () => {
+      const limited = createApp({
+        auth: { apiKey: testAPIKey },
+        rateLimit: { requestsPerMinute: 2 },
+      });
+      const request = () => limited.request('/v1/models', {
+        headers: { 'Authorization': `Bearer ${testAPIKey}` },
+      });
+
+      const first = await request();
+      expect(first.headers.get('x-ratelimit-limit-requests')).toBe('2');
+      expect(first.headers.get('x-ratelimit-remaining-requests')).toBe('1');
+      await request();
+
+      const res = await request();
+      expect(res.status).toBe(429);
+      expect(res.headers.get('retry-after')).toMatch(/^\d+$/);
+      const data = await res.json();
+      expect(data.error.type).toBe('rate_limit_error');
+      expect(data.error.code).toBe('rate_limit_exceeded');
+    });
+
+    it('should handle malformed JSON', async () => {
+      const res = await app.request('/v1/chat/completions', {
+        method: 'POST',
+        headers: {
+          'Authorization': `Bearer ${testAPIKey}`,
+          'Content-Type': 'application/json',
+        },
+        body: 'invalid json',
+      });
+
+      expect(res.status).toBe(400);
+      const data = await res.json();
+      expect(data.error.type).toBe('invalid_request_error');
+    });
+  });
+
+  describe('Middleware Ordering', () => {
+    // The default stack with only the given groups changed, and nothing for every path unless given
+    const ordering = (groups: MiddlewareConfig): MiddlewareConfig => ({ ...DEFAULT_MIDDLEWARE, '*': [], ...groups });
+
+    it('should reject unknown middleware names at startup', () => {
+      expect(() => createApp({
+        auth: { apiKey: testAPIKey },
+        middleware: { '*': ['cors', 'cache' as any] },
+      })).toThrow(/Unknown middleware "cache"/);
+    });
+
+    it('should reject duplicate middleware in a route group', () => {
+      expect(() => createApp({
+        auth: { apiKey: testAPIKey },
+        middleware: { '*': ['logging', 'logging'] },
+      })).toThrow(/more than once/);
+    });
+
+    it('should reject API route groups without auth', () => {
+      for (const middleware of [ordering({ '/v1/*': ['logging'] }), { '*': ['cors'], '/v1/*': ['auth'] }]) {
+        expect(() => createApp({ auth: { apiKey: testAPIKey }, middleware })).toThrow(
+          expect.objectContaining({ statusCode: 400, type: 'invalid_request_error' }),
+        );
+      }
+    });
+
+    it('should tag auth failures with a request ID when logging runs first', async () => {
+      const res = await app.request('/v1/models');
+
+      expect(res.status).toBe(401);
+      expect(res.headers.get('x-request-id')).toBeTruthy();
+    });
+
+    it('should not tag auth failures when auth runs before logging', async () => {
+      const authFirst = createApp({
+        auth: { apiKey: testAPIKey },
+        middleware: ordering({ '*': ['cors'], '/v1/*': ['auth', 'logging'] }),
+      });
+
+      const res = await authFirst.request('/v1/models');
+
+      expect(res.status).toBe(401);
+      expect(res.headers.get('x-request-id')).toBeNull();
+    });
+
+    it('should fail CORS preflights when auth runs before cors', async () => {
+      const authFirst = createApp({
+        auth: { apiKey: testAPIKey },
+        middleware: ordering({ '/v1/*': ['auth', 'cors'] }),
+      });
+
+      const res = await authFirst.request('/v1/chat/completions', {
+        method: 'OPTIONS',
+      });
+
+      expect(res.status).toBe(401);
+    });
+
+    it('should allow disabling a layer entirely', async () => {
+      const noCors = createApp({
+        auth: { apiKey: testAPIKey },
+        middleware: ordering({ '*': ['logging'], '/v1/*': ['auth'] }),
+      });
+
+      const res = await noCors.request('/health');
+
+      expect(res.status).toBe(200);
+      expect(res.headers.get('access-control-allow-origin')).toBeNull();
     });
   });
-});
\ No newline at end of file
+});